@@ -0,0 +1,57 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_ack_round_trip() -> Result<()> {
+    let raw_ack = vec![
+        0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01,
+    ];
+    let parsed_ack = Ack {
+        record_numbers: vec![
+            RecordNumber {
+                epoch: 2,
+                sequence_number: 0,
+            },
+            RecordNumber {
+                epoch: 2,
+                sequence_number: 1,
+            },
+        ],
+    };
+
+    let mut reader = BufReader::new(raw_ack.as_slice());
+    let a = Ack::unmarshal(&mut reader)?;
+    assert_eq!(
+        a, parsed_ack,
+        "ack unmarshal: got {:?}, want {:?}",
+        a, parsed_ack
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        a.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_ack,
+        "ack marshal: got {:?}, want {:?}",
+        raw, raw_ack
+    );
+    assert_eq!(a.size(), raw_ack.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_ack_invalid_length() -> Result<()> {
+    let raw_ack = vec![0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02];
+
+    let mut reader = BufReader::new(raw_ack.as_slice());
+    let result = Ack::unmarshal(&mut reader);
+    assert_eq!(result, Err(Error::ErrLengthMismatch));
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use super::*;
+use crate::crypto::crypto_chacha20_poly1305::*;
+use crate::prf::*;
+
+#[derive(Clone)]
+pub struct CipherSuiteChacha20Poly1305Sha256 {
+    chacha: Option<CryptoChacha20Poly1305>,
+    rsa: bool,
+}
+
+impl CipherSuiteChacha20Poly1305Sha256 {
+    const PRF_MAC_LEN: usize = 0;
+    const PRF_KEY_LEN: usize = 32;
+    const PRF_IV_LEN: usize = 12;
+
+    pub fn new(rsa: bool) -> Self {
+        CipherSuiteChacha20Poly1305Sha256 { chacha: None, rsa }
+    }
+}
+
+impl CipherSuite for CipherSuiteChacha20Poly1305Sha256 {
+    fn to_string(&self) -> String {
+        if self.rsa {
+            "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256".to_owned()
+        } else {
+            "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256".to_owned()
+        }
+    }
+
+    fn id(&self) -> CipherSuiteId {
+        if self.rsa {
+            CipherSuiteId::Tls_Ecdhe_Rsa_With_Chacha20_Poly1305_Sha256
+        } else {
+            CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256
+        }
+    }
+
+    fn certificate_type(&self) -> ClientCertificateType {
+        if self.rsa {
+            ClientCertificateType::RsaSign
+        } else {
+            ClientCertificateType::EcdsaSign
+        }
+    }
+
+    fn hash_func(&self) -> CipherSuiteHash {
+        CipherSuiteHash::Sha256
+    }
+
+    fn is_psk(&self) -> bool {
+        false
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.chacha.is_some()
+    }
+
+    fn init(
+        &mut self,
+        master_secret: &[u8],
+        client_random: &[u8],
+        server_random: &[u8],
+        is_client: bool,
+    ) -> Result<()> {
+        let keys = prf_encryption_keys(
+            master_secret,
+            client_random,
+            server_random,
+            CipherSuiteChacha20Poly1305Sha256::PRF_MAC_LEN,
+            CipherSuiteChacha20Poly1305Sha256::PRF_KEY_LEN,
+            CipherSuiteChacha20Poly1305Sha256::PRF_IV_LEN,
+            self.hash_func(),
+        )?;
+
+        if is_client {
+            self.chacha = Some(CryptoChacha20Poly1305::new(
+                &keys.client_write_key,
+                &keys.client_write_iv,
+                &keys.server_write_key,
+                &keys.server_write_iv,
+            ));
+        } else {
+            self.chacha = Some(CryptoChacha20Poly1305::new(
+                &keys.server_write_key,
+                &keys.server_write_iv,
+                &keys.client_write_key,
+                &keys.client_write_iv,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn encrypt(&self, pkt_rlh: &RecordLayerHeader, raw: &[u8]) -> Result<Vec<u8>> {
+        if let Some(cc) = &self.chacha {
+            cc.encrypt(pkt_rlh, raw)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to encrypt".to_owned(),
+            ))
+        }
+    }
+
+    fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>> {
+        if let Some(cc) = &self.chacha {
+            cc.decrypt(input)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to decrypt".to_owned(),
+            ))
+        }
+    }
+
+    fn supports_connection_id(&self) -> bool {
+        true
+    }
+
+    fn encrypt_cid(
+        &self,
+        pkt_rlh: &RecordLayerHeader,
+        connection_id: &[u8],
+        raw: &[u8],
+    ) -> Result<Vec<u8>> {
+        if let Some(cc) = &self.chacha {
+            cc.encrypt_cid(pkt_rlh, connection_id, raw)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to encrypt".to_owned(),
+            ))
+        }
+    }
+
+    fn decrypt_cid(&self, input: &[u8], connection_id_len: usize) -> Result<Vec<u8>> {
+        if let Some(cc) = &self.chacha {
+            cc.decrypt_cid(input, connection_id_len)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to decrypt".to_owned(),
+            ))
+        }
+    }
+}
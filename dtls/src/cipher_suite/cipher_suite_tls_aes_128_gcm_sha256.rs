@@ -0,0 +1,207 @@
+use super::*;
+use crate::crypto::crypto_gcm_dtls13::*;
+use crate::prf::*;
+use crate::record_layer::record_layer_unified_header::*;
+
+use std::collections::HashMap;
+use std::io::BufWriter;
+use std::sync::Mutex;
+
+struct EpochKeys {
+    traffic_secret: Vec<u8>,
+    gcm: CryptoGcmDtls13,
+}
+
+// TLS_AES_128_GCM_SHA256 is the DTLS 1.3 cipher suite, every epoch is
+// protected with keys derived from its own traffic secret.
+// https://www.rfc-editor.org/rfc/rfc8446#appendix-B.4
+#[derive(Default)]
+pub struct CipherSuiteTlsAes128GcmSha256 {
+    local_keys: HashMap<u16, EpochKeys>,
+    remote_keys: HashMap<u16, EpochKeys>,
+    // Highest sequence number received per epoch, the unified header only
+    // carries its low bits
+    remote_sequence_numbers: Mutex<HashMap<u16, u64>>,
+}
+
+impl CipherSuiteTlsAes128GcmSha256 {
+    const PRF_KEY_LEN: usize = 16;
+    const PRF_IV_LEN: usize = 12;
+
+    fn keys(&self, is_local: bool) -> &HashMap<u16, EpochKeys> {
+        if is_local {
+            &self.local_keys
+        } else {
+            &self.remote_keys
+        }
+    }
+}
+
+// reconstruct_sequence_number picks the sequence number closest to the next
+// expected one that ends with the received low bits.
+// https://www.rfc-editor.org/rfc/rfc9147#section-4.2.2
+pub(crate) fn reconstruct_sequence_number(expected: u64, bits: u64, bits_len: usize) -> u64 {
+    let window = 1u64 << (8 * bits_len);
+    let candidate = (expected & !(window - 1)) | bits;
+    if candidate + window / 2 < expected {
+        candidate + window
+    } else if candidate >= window && candidate > expected + window / 2 {
+        candidate - window
+    } else {
+        candidate
+    }
+}
+
+impl CipherSuite for CipherSuiteTlsAes128GcmSha256 {
+    fn to_string(&self) -> String {
+        "TLS_AES_128_GCM_SHA256".to_owned()
+    }
+
+    fn id(&self) -> CipherSuiteId {
+        CipherSuiteId::Tls_Aes_128_Gcm_Sha256
+    }
+
+    fn certificate_type(&self) -> ClientCertificateType {
+        ClientCertificateType::EcdsaSign
+    }
+
+    fn hash_func(&self) -> CipherSuiteHash {
+        CipherSuiteHash::Sha256
+    }
+
+    fn is_psk(&self) -> bool {
+        false
+    }
+
+    fn is_initialized(&self) -> bool {
+        !self.local_keys.is_empty() && !self.remote_keys.is_empty()
+    }
+
+    // The keys of DTLS 1.3 are installed with set_traffic_secret
+    fn init(
+        &mut self,
+        _master_secret: &[u8],
+        _client_random: &[u8],
+        _server_random: &[u8],
+        _is_client: bool,
+    ) -> Result<()> {
+        Err(Error::ErrNotImplemented)
+    }
+
+    fn set_traffic_secret(
+        &mut self,
+        epoch: u16,
+        traffic_secret: &[u8],
+        is_local: bool,
+    ) -> Result<()> {
+        let keys = prf_traffic_keys(
+            traffic_secret,
+            CipherSuiteTlsAes128GcmSha256::PRF_KEY_LEN,
+            CipherSuiteTlsAes128GcmSha256::PRF_IV_LEN,
+            self.hash_func(),
+        )?;
+
+        let epoch_keys = EpochKeys {
+            traffic_secret: traffic_secret.to_vec(),
+            gcm: CryptoGcmDtls13::new(&keys.write_key, &keys.write_iv, &keys.sn_key),
+        };
+        if is_local {
+            self.local_keys.insert(epoch, epoch_keys);
+        } else {
+            self.remote_keys.insert(epoch, epoch_keys);
+        }
+
+        Ok(())
+    }
+
+    fn update_traffic_secret(&mut self, is_local: bool) -> Result<u16> {
+        let (epoch, next_traffic_secret) = match self.keys(is_local).iter().max_by_key(|(e, _)| **e)
+        {
+            Some((epoch, keys)) => (
+                *epoch + 1,
+                prf_next_traffic_secret(&keys.traffic_secret, self.hash_func())?,
+            ),
+            None => return Err(Error::ErrEpochKeysUnavailable),
+        };
+
+        self.set_traffic_secret(epoch, &next_traffic_secret, is_local)?;
+        Ok(epoch)
+    }
+
+    fn encrypt(&self, pkt_rlh: &RecordLayerHeader, raw: &[u8]) -> Result<Vec<u8>> {
+        match self.local_keys.get(&pkt_rlh.epoch) {
+            Some(keys) => keys.gcm.encrypt(
+                pkt_rlh.epoch,
+                pkt_rlh.sequence_number,
+                pkt_rlh.content_type,
+                &raw[RECORD_LAYER_HEADER_SIZE..],
+            ),
+            None => Err(Error::ErrEpochKeysUnavailable),
+        }
+    }
+
+    fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>> {
+        if input.is_empty() || !is_unified_header(input[0]) {
+            return Err(Error::ErrInvalidUnifiedHeader);
+        }
+
+        // Only the low bits of the epoch are sent, try the newest epoch first
+        let epoch_bits = (input[0] & UNIFIED_HEADER_EPOCH_MASK) as u16;
+        let mut epochs: Vec<u16> = self
+            .remote_keys
+            .keys()
+            .filter(|e| **e & UNIFIED_HEADER_EPOCH_MASK as u16 == epoch_bits)
+            .copied()
+            .collect();
+        epochs.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut result = Err(Error::ErrEpochKeysUnavailable);
+        for epoch in epochs {
+            let keys = &self.remote_keys[&epoch];
+            let h = keys.gcm.sequence_number_bits(input)?;
+
+            let expected = match self.remote_sequence_numbers.lock() {
+                Ok(remote_sequence_numbers) => {
+                    remote_sequence_numbers.get(&epoch).map_or(0, |s| s + 1)
+                }
+                Err(err) => return Err(Error::Other(err.to_string())),
+            };
+            let sequence_number =
+                reconstruct_sequence_number(expected, h.sequence_number, h.sequence_number_len);
+
+            let (content_type, content) = match keys.gcm.decrypt(&h, sequence_number, input) {
+                Ok(r) => r,
+                Err(err) => {
+                    result = Err(err);
+                    continue;
+                }
+            };
+
+            if let Ok(mut remote_sequence_numbers) = self.remote_sequence_numbers.lock() {
+                let highest = remote_sequence_numbers.entry(epoch).or_insert(0);
+                if *highest < sequence_number {
+                    *highest = sequence_number;
+                }
+            }
+
+            // Hand the record on in the DTLS 1.2 layout the reader expects
+            let record_layer_header = RecordLayerHeader {
+                content_type,
+                protocol_version: PROTOCOL_VERSION1_2,
+                epoch,
+                sequence_number,
+                content_len: content.len() as u16,
+            };
+            let mut d = Vec::with_capacity(RECORD_LAYER_HEADER_SIZE + content.len());
+            {
+                let mut writer = BufWriter::<&mut Vec<u8>>::new(d.as_mut());
+                record_layer_header.marshal(&mut writer)?;
+            }
+            d.extend_from_slice(&content);
+
+            return Ok(d);
+        }
+
+        result
+    }
+}
@@ -93,4 +93,33 @@ impl CipherSuite for CipherSuiteTlsPskWithAes128GcmSha256 {
             ))
         }
     }
+
+    fn supports_connection_id(&self) -> bool {
+        true
+    }
+
+    fn encrypt_cid(
+        &self,
+        pkt_rlh: &RecordLayerHeader,
+        connection_id: &[u8],
+        raw: &[u8],
+    ) -> Result<Vec<u8>> {
+        if let Some(cg) = &self.gcm {
+            cg.encrypt_cid(pkt_rlh, connection_id, raw)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to encrypt".to_owned(),
+            ))
+        }
+    }
+
+    fn decrypt_cid(&self, input: &[u8], connection_id_len: usize) -> Result<Vec<u8>> {
+        if let Some(cg) = &self.gcm {
+            cg.decrypt_cid(input, connection_id_len)
+        } else {
+            Err(Error::Other(
+                "CipherSuite has not been initialized, unable to decrypt".to_owned(),
+            ))
+        }
+    }
 }
@@ -1,6 +1,9 @@
 pub mod cipher_suite_aes_128_ccm;
 pub mod cipher_suite_aes_128_gcm_sha256;
 pub mod cipher_suite_aes_256_cbc_sha;
+pub mod cipher_suite_aes_256_gcm_sha384;
+pub mod cipher_suite_chacha20_poly1305_sha256;
+pub mod cipher_suite_tls_aes_128_gcm_sha256;
 pub mod cipher_suite_tls_ecdhe_ecdsa_with_aes_128_ccm;
 pub mod cipher_suite_tls_ecdhe_ecdsa_with_aes_128_ccm8;
 pub mod cipher_suite_tls_psk_with_aes_128_ccm;
@@ -16,6 +19,9 @@ use super::record_layer::record_layer_header::*;
 
 use cipher_suite_aes_128_gcm_sha256::*;
 use cipher_suite_aes_256_cbc_sha::*;
+use cipher_suite_aes_256_gcm_sha384::*;
+use cipher_suite_chacha20_poly1305_sha256::*;
+use cipher_suite_tls_aes_128_gcm_sha256::*;
 use cipher_suite_tls_ecdhe_ecdsa_with_aes_128_ccm::*;
 use cipher_suite_tls_ecdhe_ecdsa_with_aes_128_ccm8::*;
 use cipher_suite_tls_psk_with_aes_128_ccm::*;
@@ -39,10 +45,21 @@ pub enum CipherSuiteId {
     Tls_Ecdhe_Ecdsa_With_Aes_256_Cbc_Sha = 0xc00a,
     Tls_Ecdhe_Rsa_With_Aes_256_Cbc_Sha = 0xc014,
 
+    // AES-256-GCM-SHA384
+    Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384 = 0xc02c,
+    Tls_Ecdhe_Rsa_With_Aes_256_Gcm_Sha384 = 0xc030,
+
+    // CHACHA20-POLY1305-SHA256
+    Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256 = 0xcca9,
+    Tls_Ecdhe_Rsa_With_Chacha20_Poly1305_Sha256 = 0xcca8,
+
     Tls_Psk_With_Aes_128_Ccm = 0xc0a4,
     Tls_Psk_With_Aes_128_Ccm_8 = 0xc0a8,
     Tls_Psk_With_Aes_128_Gcm_Sha256 = 0x00a8,
 
+    // DTLS 1.3
+    Tls_Aes_128_Gcm_Sha256 = 0x1301,
+
     Unsupported,
 }
 
@@ -67,11 +84,24 @@ impl fmt::Display for CipherSuiteId {
             CipherSuiteId::Tls_Ecdhe_Rsa_With_Aes_256_Cbc_Sha => {
                 write!(f, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA")
             }
+            CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384 => {
+                write!(f, "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384")
+            }
+            CipherSuiteId::Tls_Ecdhe_Rsa_With_Aes_256_Gcm_Sha384 => {
+                write!(f, "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384")
+            }
+            CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256 => {
+                write!(f, "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256")
+            }
+            CipherSuiteId::Tls_Ecdhe_Rsa_With_Chacha20_Poly1305_Sha256 => {
+                write!(f, "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256")
+            }
             CipherSuiteId::Tls_Psk_With_Aes_128_Ccm => write!(f, "TLS_PSK_WITH_AES_128_CCM"),
             CipherSuiteId::Tls_Psk_With_Aes_128_Ccm_8 => write!(f, "TLS_PSK_WITH_AES_128_CCM_8"),
             CipherSuiteId::Tls_Psk_With_Aes_128_Gcm_Sha256 => {
                 write!(f, "TLS_PSK_WITH_AES_128_GCM_SHA256")
             }
+            CipherSuiteId::Tls_Aes_128_Gcm_Sha256 => write!(f, "TLS_AES_128_GCM_SHA256"),
             _ => write!(f, "Unsupported CipherSuiteID"),
         }
     }
@@ -92,24 +122,45 @@ impl From<u16> for CipherSuiteId {
             0xc00a => CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Cbc_Sha,
             0xc014 => CipherSuiteId::Tls_Ecdhe_Rsa_With_Aes_256_Cbc_Sha,
 
+            // AES-256-GCM-SHA384
+            0xc02c => CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384,
+            0xc030 => CipherSuiteId::Tls_Ecdhe_Rsa_With_Aes_256_Gcm_Sha384,
+
+            // CHACHA20-POLY1305-SHA256
+            0xcca9 => CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256,
+            0xcca8 => CipherSuiteId::Tls_Ecdhe_Rsa_With_Chacha20_Poly1305_Sha256,
+
             0xc0a4 => CipherSuiteId::Tls_Psk_With_Aes_128_Ccm,
             0xc0a8 => CipherSuiteId::Tls_Psk_With_Aes_128_Ccm_8,
             0x00a8 => CipherSuiteId::Tls_Psk_With_Aes_128_Gcm_Sha256,
 
+            // DTLS 1.3
+            0x1301 => CipherSuiteId::Tls_Aes_128_Gcm_Sha256,
+
             _ => CipherSuiteId::Unsupported,
         }
     }
 }
 
+impl CipherSuiteId {
+    // DTLS 1.3 cipher suites only name the AEAD and hash, they can not be
+    // negotiated for DTLS 1.2
+    pub fn is_dtls13(&self) -> bool {
+        *self == CipherSuiteId::Tls_Aes_128_Gcm_Sha256
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum CipherSuiteHash {
     Sha256,
+    Sha384,
 }
 
 impl CipherSuiteHash {
     pub(crate) fn size(&self) -> usize {
         match *self {
             CipherSuiteHash::Sha256 => 32,
+            CipherSuiteHash::Sha384 => 48,
         }
     }
 }
@@ -131,8 +182,43 @@ pub trait CipherSuite {
         is_client: bool,
     ) -> Result<()>;
 
+    // Install the keys of an epoch derived from a DTLS 1.3 traffic secret
+    fn set_traffic_secret(
+        &mut self,
+        _epoch: u16,
+        _traffic_secret: &[u8],
+        _is_local: bool,
+    ) -> Result<()> {
+        Err(Error::ErrNotImplemented)
+    }
+
+    // Derive the keys of the next epoch after a DTLS 1.3 KeyUpdate, returns
+    // the new epoch
+    fn update_traffic_secret(&mut self, _is_local: bool) -> Result<u16> {
+        Err(Error::ErrNotImplemented)
+    }
+
     fn encrypt(&self, pkt_rlh: &RecordLayerHeader, raw: &[u8]) -> Result<Vec<u8>>;
     fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>>;
+
+    // Only the DTLS 1.2 AEAD cipher suites protect records with a connection ID
+    // https://www.rfc-editor.org/rfc/rfc9146#section-5
+    fn supports_connection_id(&self) -> bool {
+        false
+    }
+
+    fn encrypt_cid(
+        &self,
+        _pkt_rlh: &RecordLayerHeader,
+        _connection_id: &[u8],
+        _raw: &[u8],
+    ) -> Result<Vec<u8>> {
+        Err(Error::ErrNotImplemented)
+    }
+
+    fn decrypt_cid(&self, _input: &[u8], _connection_id_len: usize) -> Result<Vec<u8>> {
+        Err(Error::ErrNotImplemented)
+    }
 }
 
 // Taken from https://www.iana.org/assignments/tls-parameters/tls-parameters.xml
@@ -158,6 +244,18 @@ pub fn cipher_suite_for_id(id: CipherSuiteId) -> Result<Box<dyn CipherSuite + Se
         CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Cbc_Sha => {
             Ok(Box::new(CipherSuiteAes256CbcSha::new(false)))
         }
+        CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384 => {
+            Ok(Box::new(CipherSuiteAes256GcmSha384::new(false)))
+        }
+        CipherSuiteId::Tls_Ecdhe_Rsa_With_Aes_256_Gcm_Sha384 => {
+            Ok(Box::new(CipherSuiteAes256GcmSha384::new(true)))
+        }
+        CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256 => {
+            Ok(Box::new(CipherSuiteChacha20Poly1305Sha256::new(false)))
+        }
+        CipherSuiteId::Tls_Ecdhe_Rsa_With_Chacha20_Poly1305_Sha256 => {
+            Ok(Box::new(CipherSuiteChacha20Poly1305Sha256::new(true)))
+        }
         CipherSuiteId::Tls_Psk_With_Aes_128_Ccm => {
             Ok(Box::new(new_cipher_suite_tls_psk_with_aes_128_ccm()))
         }
@@ -167,6 +265,9 @@ pub fn cipher_suite_for_id(id: CipherSuiteId) -> Result<Box<dyn CipherSuite + Se
         CipherSuiteId::Tls_Psk_With_Aes_128_Gcm_Sha256 => {
             Ok(Box::new(CipherSuiteTlsPskWithAes128GcmSha256::default()))
         }
+        CipherSuiteId::Tls_Aes_128_Gcm_Sha256 => {
+            Ok(Box::new(CipherSuiteTlsAes128GcmSha256::default()))
+        }
         _ => Err(Error::ErrInvalidCipherSuite),
     }
 }
@@ -178,6 +279,10 @@ pub(crate) fn default_cipher_suites() -> Vec<Box<dyn CipherSuite + Send + Sync>>
         Box::new(CipherSuiteAes256CbcSha::new(false)),
         Box::new(CipherSuiteAes128GcmSha256::new(true)),
         Box::new(CipherSuiteAes256CbcSha::new(true)),
+        Box::new(CipherSuiteAes256GcmSha384::new(false)),
+        Box::new(CipherSuiteChacha20Poly1305Sha256::new(false)),
+        Box::new(CipherSuiteAes256GcmSha384::new(true)),
+        Box::new(CipherSuiteChacha20Poly1305Sha256::new(true)),
     ]
 }
 
@@ -189,6 +294,10 @@ fn all_cipher_suites() -> Vec<Box<dyn CipherSuite + Send + Sync>> {
         Box::new(CipherSuiteAes128GcmSha256::new(true)),
         Box::new(CipherSuiteAes256CbcSha::new(false)),
         Box::new(CipherSuiteAes256CbcSha::new(true)),
+        Box::new(CipherSuiteAes256GcmSha384::new(false)),
+        Box::new(CipherSuiteAes256GcmSha384::new(true)),
+        Box::new(CipherSuiteChacha20Poly1305Sha256::new(false)),
+        Box::new(CipherSuiteChacha20Poly1305Sha256::new(true)),
         Box::new(new_cipher_suite_tls_psk_with_aes_128_ccm()),
         Box::new(new_cipher_suite_tls_psk_with_aes_128_ccm8()),
         Box::new(CipherSuiteTlsPskWithAes128GcmSha256::default()),
@@ -217,6 +326,7 @@ pub(crate) fn parse_cipher_suites(
     let filtered_cipher_suites: Vec<Box<dyn CipherSuite + Send + Sync>> = cipher_suites
         .into_iter()
         .filter(|c| !((exclude_psk && c.is_psk()) || (exclude_non_psk && !c.is_psk())))
+        .filter(|c| !c.id().is_dtls13())
         .collect();
 
     if filtered_cipher_suites.is_empty() {
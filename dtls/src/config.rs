@@ -1,8 +1,10 @@
 use crate::cipher_suite::*;
+use crate::connection_id::ConnectionIdGenerator;
 use crate::crypto::*;
 use crate::error::*;
 use crate::extension::extension_use_srtp::SrtpProtectionProfile;
 use crate::handshaker::VerifyPeerCertificateFn;
+use crate::session::*;
 use crate::signature_hash_algorithm::SignatureScheme;
 
 use std::sync::Arc;
@@ -96,6 +98,27 @@ pub struct Config {
     /// Packet with sequence number older than this value compared to the latest
     /// accepted packet will be discarded. (default is 64)
     pub replay_protection_window: usize,
+
+    /// session_store caches the sessions resumed with an abbreviated handshake.
+    /// Clients look up the session of the server they dial, servers look up
+    /// the session ID offered by the client. If nil sessions are not resumed.
+    pub session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+
+    /// session_ticket_key enables RFC 5077 session tickets on a server, the
+    /// 32 bytes key encrypts the session state handed to clients.
+    /// Clients offer tickets whenever session_store is set.
+    pub session_ticket_key: Option<Vec<u8>>,
+
+    /// enable_dtls13 offers DTLS 1.3 (RFC 9147) in addition to DTLS 1.2.
+    /// The handshake falls back to DTLS 1.2 when the peer does not support
+    /// DTLS 1.3. It is ignored when a PSK is configured.
+    pub enable_dtls13: bool,
+
+    /// connection_id_generator enables the RFC 9146 connection ID extension
+    /// of DTLS 1.2. The generated connection ID is what the peer must send in
+    /// its records, which lets a listener find the connection after the
+    /// address of the peer changed. If nil connection IDs are not negotiated.
+    pub connection_id_generator: Option<ConnectionIdGenerator>,
 }
 
 impl Default for Config {
@@ -118,6 +141,10 @@ impl Default for Config {
             server_name: String::default(),
             mtu: 0,
             replay_protection_window: 0,
+            session_store: None,
+            session_ticket_key: None,
+            enable_dtls13: false,
+            connection_id_generator: None,
         }
     }
 }
@@ -185,6 +212,12 @@ pub(crate) fn validate_config(is_client: bool, config: &Config) -> Result<()> {
         }
     }
 
+    if let Some(key) = &config.session_ticket_key {
+        if key.len() != SESSION_TICKET_KEY_LENGTH {
+            return Err(Error::ErrInvalidSessionTicketKey);
+        }
+    }
+
     parse_cipher_suites(
         &config.cipher_suites,
         config.psk.is_none(),
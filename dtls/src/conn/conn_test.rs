@@ -2,6 +2,7 @@ use super::*;
 use crate::cipher_suite::cipher_suite_aes_128_gcm_sha256::*;
 use crate::cipher_suite::*;
 use crate::compression_methods::*;
+use crate::connection_id::*;
 use crate::crypto::*;
 use crate::curve::*;
 use crate::error::*;
@@ -16,12 +17,16 @@ use crate::handshake::handshake_message_server_hello::*;
 use crate::handshake::handshake_message_server_hello_done::*;
 use crate::handshake::handshake_message_server_key_exchange::*;
 use crate::handshake::handshake_random::*;
+use crate::session::*;
 use crate::signature_hash_algorithm::*;
 
 use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
 use rand::Rng;
+use std::sync::atomic::AtomicUsize;
 use std::time::SystemTime;
+use tokio::net::UdpSocket;
 use util::conn::conn_pipe::*;
+use util::conn::Listener;
 use util::KeyingMaterialExporter;
 
 const ERR_TEST_PSK_INVALID_IDENTITY: &str = "TestPSK: Server got invalid identity";
@@ -261,6 +266,7 @@ async fn test_sequence_number_overflow_on_handshake() -> Result<()> {
                 0,
                 Content::Handshake(Handshake::new(HandshakeMessage::ClientHello(
                     HandshakeMessageClientHello {
+                        session_id: vec![],
                         version: PROTOCOL_VERSION1_2,
                         random: HandshakeRandom::default(),
                         cookie: vec![0; 64],
@@ -436,6 +442,7 @@ async fn test_export_keying_material() -> Result<()> {
         cache: HandshakeCache::new(),
         decrypted_rx: Mutex::new(decrypted_rx),
         handshake_completed_successfully: Arc::new(AtomicBool::new(false)),
+        key_update: Arc::new(KeyUpdateState::default()),
         connection_closed_by_user: false,
         closed: AtomicBool::new(false),
         current_flight: Box::new(Flight0 {}) as Box<dyn Flight + Send + Sync>,
@@ -792,6 +799,20 @@ async fn test_srtp_configuration() -> Result<()> {
             None,
             None,
         ),
+        (
+            "AES-256 Suites",
+            vec![
+                SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm,
+                SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            ],
+            vec![
+                SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_32,
+                SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            ],
+            SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            None,
+            None,
+        ),
     ];
 
     for (name, client_srtp, server_srtp, expected_profile, want_client_err, want_server_err) in
@@ -1591,6 +1612,22 @@ async fn test_cipher_suite_configuration() -> Result<()> {
             None,
             Some(CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Ccm_8),
         ),
+        (
+            "Valid CipherSuites AES-256-GCM specified",
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384],
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384],
+            None,
+            None,
+            Some(CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Gcm_Sha384),
+        ),
+        (
+            "Valid CipherSuites CHACHA20-POLY1305 specified",
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256],
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256],
+            None,
+            None,
+            Some(CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256),
+        ),
         (
             "Server supports subset of client suites",
             vec![
@@ -1915,6 +1952,7 @@ async fn test_server_timeout() -> Result<()> {
         0,
         Content::Handshake(Handshake::new(HandshakeMessage::ClientHello(
             HandshakeMessageClientHello {
+                session_id: vec![],
                 version: PROTOCOL_VERSION1_2,
                 cookie,
                 random,
@@ -2046,6 +2084,7 @@ async fn test_protocol_version_validation() -> Result<()> {
                     0,
                     Content::Handshake(Handshake::new(HandshakeMessage::ClientHello(
                         HandshakeMessageClientHello {
+                            session_id: vec![],
                             version: ProtocolVersion {
                                 major: 0xfe,
                                 minor: 0xff,
@@ -2069,6 +2108,7 @@ async fn test_protocol_version_validation() -> Result<()> {
                         0,
                         Content::Handshake(Handshake::new(HandshakeMessage::ClientHello(
                             HandshakeMessageClientHello {
+                                session_id: vec![],
                                 version: PROTOCOL_VERSION1_2,
                                 cookie: cookie.clone(),
                                 random: random.clone(),
@@ -2083,6 +2123,7 @@ async fn test_protocol_version_validation() -> Result<()> {
                     {
                         let mut handshake = Handshake::new(HandshakeMessage::ClientHello(
                             HandshakeMessageClientHello {
+                                session_id: vec![],
                                 version: ProtocolVersion {
                                     major: 0xfe,
                                     minor: 0xff,
@@ -2186,6 +2227,7 @@ async fn test_protocol_version_validation() -> Result<()> {
                 {
                     let mut handshake = Handshake::new(HandshakeMessage::ServerHello(
                         HandshakeMessageServerHello {
+                            session_id: vec![],
                             version: ProtocolVersion {
                                 major: 0xfe,
                                 minor: 0xff,
@@ -2419,6 +2461,7 @@ async fn send_client_hello(
     }
 
     let mut h = Handshake::new(HandshakeMessage::ClientHello(HandshakeMessageClientHello {
+        session_id: vec![],
         version: PROTOCOL_VERSION1_2,
         random: HandshakeRandom::default(),
         cookie,
@@ -2538,3 +2581,921 @@ async fn test_renegotation_info() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_session_resumption() -> Result<()> {
+    let session_ticket_key = vec![0x42; SESSION_TICKET_KEY_LENGTH];
+    let tests = vec![
+        ("SessionID", true, None, true),
+        (
+            "SessionTicket",
+            false,
+            Some(session_ticket_key.clone()),
+            true,
+        ),
+        (
+            "SessionIDAndTicket",
+            true,
+            Some(session_ticket_key.clone()),
+            true,
+        ),
+        ("NoServerSupport", false, None, false),
+    ];
+
+    for (name, server_store, server_ticket_key, want_resume) in tests {
+        let client_store: Arc<dyn SessionStore + Send + Sync> = Arc::new(MemorySessionStore::new());
+        let server_store: Option<Arc<dyn SessionStore + Send + Sync>> = if server_store {
+            Some(Arc::new(MemorySessionStore::new()))
+        } else {
+            None
+        };
+
+        // The certificates of the full handshake, a resumed session keeps them
+        let mut full_handshake_certificates = None;
+        for i in 0..2 {
+            let (ca, cb) = pipe();
+
+            let (c_tx, mut c_rx) = mpsc::channel(1);
+            let client_cfg = Config {
+                session_store: Some(Arc::clone(&client_store)),
+                ..Default::default()
+            };
+            tokio::spawn(async move {
+                let client = create_test_client(Arc::new(ca), client_cfg, true).await;
+                let _ = c_tx.send(client).await;
+            });
+
+            let server = create_test_server(
+                Arc::new(cb),
+                Config {
+                    session_store: server_store.clone(),
+                    session_ticket_key: server_ticket_key.clone(),
+                    client_auth: ClientAuthType::RequireAnyClientCert,
+                    ..Default::default()
+                },
+                true,
+            )
+            .await?;
+            let client = c_rx.recv().await.unwrap()?;
+
+            let certificates = (
+                client.connection_state().await.peer_certificates,
+                server.connection_state().await.peer_certificates,
+            );
+            assert!(
+                !certificates.0.is_empty(),
+                "{} #{}: client has no peer certificates",
+                name,
+                i
+            );
+            assert!(
+                !certificates.1.is_empty(),
+                "{} #{}: server has no peer certificates",
+                name,
+                i
+            );
+            if i == 0 {
+                full_handshake_certificates = Some(certificates);
+            } else if want_resume {
+                assert_eq!(
+                    Some(certificates),
+                    full_handshake_certificates,
+                    "{} #{}: peer certificates of the resumed session",
+                    name,
+                    i
+                );
+            }
+
+            let want = i == 1 && want_resume;
+            assert_eq!(
+                client.did_resume(),
+                want,
+                "{} #{}: client did_resume",
+                name,
+                i
+            );
+            assert_eq!(
+                server.did_resume(),
+                want,
+                "{} #{}: server did_resume",
+                name,
+                i
+            );
+
+            let msg = b"resumed";
+            client.write(msg, None).await?;
+            let mut buf = vec![0u8; 64];
+            let n = server.read(&mut buf, None).await?;
+            assert_eq!(&buf[..n], msg, "{} #{}: application data", name, i);
+
+            client.close().await?;
+            server.close().await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_resumption_mismatch() -> Result<()> {
+    // A session the server no longer knows falls back to a full handshake
+    let client_store = Arc::new(MemorySessionStore::new());
+    client_store.set(
+        b"localhost",
+        Session {
+            id: vec![0x01; SESSION_ID_LENGTH],
+            secret: vec![0x02; 48],
+            cipher_suite: CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Gcm_Sha256,
+            extended_master_secret: true,
+            ticket: vec![0x03; 64],
+            peer_certificates: vec![],
+            issued_at: SystemTime::now(),
+        },
+    )?;
+
+    let (ca, cb) = pipe();
+    let (c_tx, mut c_rx) = mpsc::channel(1);
+    let client_cfg = Config {
+        session_store: Some(Arc::clone(&client_store) as Arc<dyn SessionStore + Send + Sync>),
+        ..Default::default()
+    };
+    tokio::spawn(async move {
+        let client = create_test_client(Arc::new(ca), client_cfg, false).await;
+        let _ = c_tx.send(client).await;
+    });
+
+    let server = create_test_server(
+        Arc::new(cb),
+        Config {
+            session_store: Some(Arc::new(MemorySessionStore::new())),
+            session_ticket_key: Some(vec![0x42; SESSION_TICKET_KEY_LENGTH]),
+            ..Default::default()
+        },
+        true,
+    )
+    .await?;
+    let client = c_rx.recv().await.unwrap()?;
+
+    assert!(!client.did_resume(), "client resumed an unknown session");
+    assert!(!server.did_resume(), "server resumed an unknown session");
+
+    let session = client_store.get(b"localhost")?.unwrap();
+    assert_ne!(session.secret, vec![0x02; 48], "stale session kept");
+    assert!(!session.ticket.is_empty(), "no ticket stored");
+
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_resumption_expired() -> Result<()> {
+    // Sessions older than their lifetime are not resumed by session ID
+    let client_store = Arc::new(MemorySessionStore::new());
+    let server_store = Arc::new(MemorySessionStore::new());
+
+    for i in 0..2 {
+        let (ca, cb) = pipe();
+        let (c_tx, mut c_rx) = mpsc::channel(1);
+        let client_cfg = Config {
+            session_store: Some(Arc::clone(&client_store) as Arc<dyn SessionStore + Send + Sync>),
+            ..Default::default()
+        };
+        tokio::spawn(async move {
+            let client = create_test_client(Arc::new(ca), client_cfg, false).await;
+            let _ = c_tx.send(client).await;
+        });
+
+        let server = create_test_server(
+            Arc::new(cb),
+            Config {
+                session_store: Some(
+                    Arc::clone(&server_store) as Arc<dyn SessionStore + Send + Sync>
+                ),
+                ..Default::default()
+            },
+            true,
+        )
+        .await?;
+        let client = c_rx.recv().await.unwrap()?;
+
+        assert!(!client.did_resume(), "#{}: client resumed", i);
+        assert!(!server.did_resume(), "#{}: server resumed", i);
+
+        client.close().await?;
+        server.close().await?;
+
+        // Age the session of the server past its lifetime
+        let id = client_store.get(b"localhost")?.unwrap().id;
+        let mut session = server_store.get(&id)?.unwrap();
+        session.issued_at = SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+        server_store.set(&id, session)?;
+    }
+
+    Ok(())
+}
+
+async fn dtls13_pipe(
+    client_cfg: Config,
+    server_cfg: Config,
+) -> (Result<DTLSConn>, Result<DTLSConn>) {
+    let (ca, cb) = pipe();
+
+    let (c_tx, mut c_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let client = DTLSConn::new(Arc::new(ca), client_cfg, true, None).await;
+        let _ = c_tx.send(client).await;
+    });
+
+    let server = DTLSConn::new(Arc::new(cb), server_cfg, false, None).await;
+    let client = c_rx.recv().await.unwrap();
+
+    (client, server)
+}
+
+async fn check_data_exchange(client: &DTLSConn, server: &DTLSConn, name: &str) -> Result<()> {
+    let mut buf = vec![0u8; 64];
+
+    client.write(b"ping", None).await?;
+    let n = server.read(&mut buf, None).await?;
+    assert_eq!(&buf[..n], b"ping", "{}: client to server", name);
+
+    server.write(b"pong", None).await?;
+    let n = client.read(&mut buf, None).await?;
+    assert_eq!(&buf[..n], b"pong", "{}: server to client", name);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dtls13_handshake() -> Result<()> {
+    let tests = vec![
+        ("DTLS13", true, true, PROTOCOL_VERSION1_3),
+        ("ClientFallback", true, false, PROTOCOL_VERSION1_2),
+        ("ServerFallback", false, true, PROTOCOL_VERSION1_2),
+    ];
+
+    for (name, client_dtls13, server_dtls13, want_version) in tests {
+        let (client, server) = dtls13_pipe(
+            Config {
+                certificates: vec![Certificate::generate_self_signed(vec![
+                    "localhost".to_owned()
+                ])?],
+                insecure_skip_verify: true,
+                srtp_protection_profiles: vec![SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80],
+                enable_dtls13: client_dtls13,
+                ..Default::default()
+            },
+            Config {
+                certificates: vec![Certificate::generate_self_signed(vec![
+                    "localhost".to_owned()
+                ])?],
+                srtp_protection_profiles: vec![SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80],
+                enable_dtls13: server_dtls13,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (client, server) = (client?, server?);
+
+        assert_eq!(client.protocol_version(), want_version, "{}: client", name);
+        assert_eq!(server.protocol_version(), want_version, "{}: server", name);
+        assert_eq!(
+            client.selected_srtpprotection_profile(),
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80,
+            "{}: client SRTP profile",
+            name
+        );
+        assert_eq!(
+            server.selected_srtpprotection_profile(),
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80,
+            "{}: server SRTP profile",
+            name
+        );
+
+        let client_keys = client
+            .connection_state()
+            .await
+            .export_keying_material("EXTRACTOR-dtls_srtp", &[], 60)
+            .await?;
+        let server_keys = server
+            .connection_state()
+            .await
+            .export_keying_material("EXTRACTOR-dtls_srtp", &[], 60)
+            .await?;
+        assert_eq!(client_keys, server_keys, "{}: exported keys", name);
+
+        check_data_exchange(&client, &server, name).await?;
+
+        client.close().await?;
+        server.close().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dtls13_client_certificate() -> Result<()> {
+    let srv_cert = Certificate::generate_self_signed(vec!["localhost".to_owned()])?;
+    let mut srv_ca_pool = rustls::RootCertStore::empty();
+    srv_ca_pool
+        .add(&srv_cert.certificate[0])
+        .map_err(|_err| Error::Other("add srv_cert error".to_owned()))?;
+
+    let cert = Certificate::generate_self_signed(vec!["localhost".to_owned()])?;
+    let mut ca_pool = rustls::RootCertStore::empty();
+    ca_pool
+        .add(&cert.certificate[0])
+        .map_err(|_err| Error::Other("add cert error".to_owned()))?;
+
+    let tests = vec![
+        (
+            "RequireAndVerifyClientCert",
+            vec![cert.clone()],
+            ClientAuthType::RequireAndVerifyClientCert,
+            None,
+        ),
+        (
+            "RequestClientCert_no_cert",
+            vec![],
+            ClientAuthType::RequestClientCert,
+            None,
+        ),
+        (
+            "RequireAnyClientCert_no_cert",
+            vec![],
+            ClientAuthType::RequireAnyClientCert,
+            Some(Error::ErrClientCertificateRequired),
+        ),
+    ];
+
+    for (name, client_certificates, client_auth, want_err) in tests {
+        let (client, server) = dtls13_pipe(
+            Config {
+                certificates: client_certificates.clone(),
+                roots_cas: srv_ca_pool.clone(),
+                server_name: "localhost".to_owned(),
+                enable_dtls13: true,
+                ..Default::default()
+            },
+            Config {
+                certificates: vec![srv_cert.clone()],
+                client_auth,
+                client_cas: ca_pool.clone(),
+                enable_dtls13: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        if let Some(want_err) = want_err {
+            match server {
+                Err(err) => assert_eq!(err, want_err, "{}: server error", name),
+                Ok(_) => panic!("{}: expected error {}", name, want_err),
+            }
+            if let Ok(client) = client {
+                client.close().await?;
+            }
+            continue;
+        }
+
+        let (client, server) = (client?, server?);
+        assert_eq!(
+            server.connection_state().await.peer_certificates,
+            client_certificates
+                .iter()
+                .map(|c| c.certificate[0].0.clone())
+                .collect::<Vec<Vec<u8>>>(),
+            "{}: client certificate",
+            name
+        );
+        assert_eq!(
+            client.connection_state().await.peer_certificates[0],
+            srv_cert.certificate[0].0,
+            "{}: server certificate",
+            name
+        );
+
+        check_data_exchange(&client, &server, name).await?;
+
+        client.close().await?;
+        server.close().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dtls13_key_update() -> Result<()> {
+    let (client, server) = dtls13_pipe(
+        Config {
+            insecure_skip_verify: true,
+            enable_dtls13: true,
+            ..Default::default()
+        },
+        Config {
+            certificates: vec![Certificate::generate_self_signed(vec![
+                "localhost".to_owned()
+            ])?],
+            enable_dtls13: true,
+            ..Default::default()
+        },
+    )
+    .await;
+    let (client, server) = (client?, server?);
+
+    let epoch = client.get_local_epoch();
+    client.key_update(true).await?;
+    assert_eq!(client.get_local_epoch(), epoch + 1, "client epoch");
+    check_data_exchange(&client, &server, "update requested").await?;
+    assert_eq!(server.get_local_epoch(), epoch + 1, "server epoch");
+
+    server.key_update(false).await?;
+    assert_eq!(server.get_local_epoch(), epoch + 2, "server epoch");
+    check_data_exchange(&client, &server, "update not requested").await?;
+    assert_eq!(client.get_local_epoch(), epoch + 1, "client epoch");
+
+    client.close().await?;
+    server.close().await?;
+
+    // DTLS 1.2 has no KeyUpdate
+    let (client, server) = build_pipe().await?;
+    assert_eq!(
+        client.key_update(false).await,
+        Err(Error::ErrKeyUpdateRequiresDtls13)
+    );
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+// StripSupportedVersionsConn removes the supported_versions extension from
+// the ClientHellos it sends, like an attacker downgrading the handshake
+struct StripSupportedVersionsConn {
+    next_conn: Arc<dyn util::Conn + Send + Sync>,
+}
+
+#[async_trait]
+impl util::Conn for StripSupportedVersionsConn {
+    async fn connect(&self, addr: SocketAddr) -> util::Result<()> {
+        self.next_conn.connect(addr).await
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> util::Result<usize> {
+        self.next_conn.recv(buf).await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> util::Result<(usize, SocketAddr)> {
+        self.next_conn.recv_from(buf).await
+    }
+
+    async fn send(&self, buf: &[u8]) -> util::Result<usize> {
+        let mut packet = vec![];
+        for data in unpack_datagram(buf).map_err(|err| util::Error::Other(err.to_string()))? {
+            let record = match RecordLayer::unmarshal(&mut BufReader::new(&data[..])) {
+                Ok(RecordLayer {
+                    record_layer_header,
+                    content: Content::Handshake(h),
+                }) => match h.handshake_message {
+                    HandshakeMessage::ClientHello(mut client_hello) => {
+                        client_hello
+                            .extensions
+                            .retain(|e| !matches!(e, Extension::SupportedVersions(_)));
+                        let mut stripped =
+                            Handshake::new(HandshakeMessage::ClientHello(client_hello));
+                        stripped.handshake_header.message_sequence =
+                            h.handshake_header.message_sequence;
+                        let mut record = RecordLayer::new(
+                            record_layer_header.protocol_version,
+                            record_layer_header.epoch,
+                            Content::Handshake(stripped),
+                        );
+                        record.record_layer_header.sequence_number =
+                            record_layer_header.sequence_number;
+                        Some(record)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match record {
+                Some(record) => record
+                    .marshal(&mut packet)
+                    .map_err(|err| util::Error::Other(err.to_string()))?,
+                None => packet.extend_from_slice(&data),
+            }
+        }
+
+        self.next_conn.send(&packet).await?;
+        Ok(buf.len())
+    }
+
+    async fn send_to(&self, buf: &[u8], target: SocketAddr) -> util::Result<usize> {
+        self.next_conn.send_to(buf, target).await
+    }
+
+    async fn local_addr(&self) -> util::Result<SocketAddr> {
+        self.next_conn.local_addr().await
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        self.next_conn.remote_addr().await
+    }
+
+    async fn close(&self) -> util::Result<()> {
+        self.next_conn.close().await
+    }
+}
+
+#[tokio::test]
+async fn test_dtls13_downgrade_protection() -> Result<()> {
+    let (ca, cb) = pipe();
+    let ca = StripSupportedVersionsConn {
+        next_conn: Arc::new(ca),
+    };
+
+    let (c_tx, mut c_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let client = DTLSConn::new(
+            Arc::new(ca),
+            Config {
+                insecure_skip_verify: true,
+                enable_dtls13: true,
+                ..Default::default()
+            },
+            true,
+            None,
+        )
+        .await;
+        let _ = c_tx.send(client).await;
+    });
+
+    let server = create_test_server(
+        Arc::new(cb),
+        Config {
+            enable_dtls13: true,
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    let client = c_rx.recv().await.unwrap();
+
+    match client {
+        Err(err) => assert_eq!(err, Error::ErrDowngradeDetected, "client error"),
+        Ok(_) => panic!("client accepted a downgraded handshake"),
+    }
+    assert!(server.is_err(), "server completed a downgraded handshake");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connection_id() -> Result<()> {
+    let tests = vec![
+        (
+            "Both",
+            Some(random_cid_generator(8)),
+            Some(random_cid_generator(4)),
+            vec![],
+            false,
+            Some((8, 4)),
+        ),
+        (
+            "ClientOnlySend",
+            Some(only_send_cid_generator()),
+            Some(random_cid_generator(4)),
+            vec![],
+            false,
+            Some((0, 4)),
+        ),
+        (
+            "ServerDisabled",
+            Some(random_cid_generator(8)),
+            None,
+            vec![],
+            false,
+            None,
+        ),
+        (
+            "ChaCha20Poly1305",
+            Some(random_cid_generator(8)),
+            Some(random_cid_generator(4)),
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Chacha20_Poly1305_Sha256],
+            false,
+            Some((8, 4)),
+        ),
+        (
+            "CBC",
+            Some(random_cid_generator(8)),
+            Some(random_cid_generator(4)),
+            vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_256_Cbc_Sha],
+            false,
+            None,
+        ),
+        (
+            "DTLS13",
+            Some(random_cid_generator(8)),
+            Some(random_cid_generator(4)),
+            vec![],
+            true,
+            None,
+        ),
+    ];
+
+    for (name, client_generator, server_generator, cipher_suites, dtls13, want) in tests {
+        let (client, server) = dtls13_pipe(
+            Config {
+                certificates: vec![Certificate::generate_self_signed(vec![
+                    "localhost".to_owned()
+                ])?],
+                insecure_skip_verify: true,
+                cipher_suites: cipher_suites.clone(),
+                enable_dtls13: dtls13,
+                connection_id_generator: client_generator,
+                ..Default::default()
+            },
+            Config {
+                certificates: vec![Certificate::generate_self_signed(vec![
+                    "localhost".to_owned()
+                ])?],
+                cipher_suites,
+                enable_dtls13: dtls13,
+                connection_id_generator: server_generator,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (client, server) = (client?, server?);
+
+        let client_local = client.state.local_connection_id.lock().await.clone();
+        let client_remote = client.state.remote_connection_id.lock().await.clone();
+        let server_local = server.state.local_connection_id.lock().await.clone();
+        let server_remote = server.state.remote_connection_id.lock().await.clone();
+
+        match want {
+            Some((client_len, server_len)) => {
+                assert_eq!(
+                    client_local.as_ref().map(|cid| cid.len()),
+                    Some(client_len),
+                    "{}: client connection ID",
+                    name
+                );
+                assert_eq!(
+                    server_local.as_ref().map(|cid| cid.len()),
+                    Some(server_len),
+                    "{}: server connection ID",
+                    name
+                );
+                assert_eq!(client_local, server_remote, "{}: client CID", name);
+                assert_eq!(server_local, client_remote, "{}: server CID", name);
+            }
+            None => {
+                assert_eq!(client_remote, None, "{}: client remote CID", name);
+                assert_eq!(server_local, None, "{}: server local CID", name);
+                assert_eq!(server_remote, None, "{}: server remote CID", name);
+            }
+        }
+
+        check_data_exchange(&client, &server, name).await?;
+
+        client.close().await?;
+        server.close().await?;
+    }
+
+    Ok(())
+}
+
+// RebindingConn sends from one of two sockets and receives on both, like a
+// client behind a NAT which changed its address mapping
+struct RebindingConn {
+    sockets: Vec<Arc<UdpSocket>>,
+    current: AtomicUsize,
+    raddr: SocketAddr,
+    rx: Mutex<mpsc::Receiver<Vec<u8>>>,
+}
+
+impl RebindingConn {
+    async fn new(raddr: SocketAddr) -> Result<Self> {
+        let (tx, rx) = mpsc::channel(16);
+        let mut sockets = vec![];
+        for _ in 0..2 {
+            let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+            let (socket2, tx2) = (Arc::clone(&socket), tx.clone());
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 2048];
+                while let Ok(n) = socket2.recv(&mut buf).await {
+                    if tx2.send(buf[..n].to_vec()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+            socket.connect(raddr).await?;
+            sockets.push(socket);
+        }
+
+        Ok(RebindingConn {
+            sockets,
+            current: AtomicUsize::new(0),
+            raddr,
+            rx: Mutex::new(rx),
+        })
+    }
+
+    fn rebind(&self) {
+        self.current.store(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl util::Conn for RebindingConn {
+    async fn connect(&self, _addr: SocketAddr) -> util::Result<()> {
+        Err(util::Error::Other("Not applicable".to_owned()))
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> util::Result<usize> {
+        let mut rx = self.rx.lock().await;
+        match rx.recv().await {
+            Some(pkt) => {
+                buf[..pkt.len()].copy_from_slice(&pkt);
+                Ok(pkt.len())
+            }
+            None => Err(util::Error::ErrBufferClosed),
+        }
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> util::Result<(usize, SocketAddr)> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.raddr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> util::Result<usize> {
+        let socket = &self.sockets[self.current.load(Ordering::SeqCst)];
+        Ok(socket.send(buf).await?)
+    }
+
+    async fn send_to(&self, _buf: &[u8], _target: SocketAddr) -> util::Result<usize> {
+        Err(util::Error::Other("Not applicable".to_owned()))
+    }
+
+    async fn local_addr(&self) -> util::Result<SocketAddr> {
+        Ok(self.sockets[self.current.load(Ordering::SeqCst)].local_addr()?)
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.raddr)
+    }
+
+    async fn close(&self) -> util::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_connection_id_listener_rebinding() -> Result<()> {
+    let listener = crate::listener::listen(
+        "127.0.0.1:0",
+        Config {
+            certificates: vec![Certificate::generate_self_signed(vec![
+                "localhost".to_owned()
+            ])?],
+            connection_id_generator: Some(random_cid_generator(8)),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let client_conn = Arc::new(RebindingConn::new(listener.addr().await?).await?);
+    let client_conn2 = Arc::clone(&client_conn);
+    let (c_tx, mut c_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let client = DTLSConn::new(
+            client_conn2,
+            Config {
+                insecure_skip_verify: true,
+                connection_id_generator: Some(only_send_cid_generator()),
+                ..Default::default()
+            },
+            true,
+            None,
+        )
+        .await;
+        let _ = c_tx.send(client).await;
+    });
+
+    let (server, _) = listener.accept().await?;
+    let client = c_rx.recv().await.unwrap()?;
+
+    let mut buf = vec![0u8; 64];
+    client.write(b"ping", None).await?;
+    let n = server.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"ping", "before rebinding");
+
+    // The server answers to the new address of the client
+    client_conn.rebind();
+    client.write(b"ping2", None).await?;
+    let n = server.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"ping2", "after rebinding");
+    assert_eq!(
+        server.remote_addr().await,
+        Some(client_conn.local_addr().await?),
+        "server should follow the client address"
+    );
+
+    server.send(b"pong").await?;
+    let n = client.read(&mut buf, None).await?;
+    assert_eq!(&buf[..n], b"pong", "server to rebound client");
+
+    client.close().await?;
+    server.close().await?;
+    listener.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connection_id_listener_forged_record() -> Result<()> {
+    let listener = crate::listener::listen(
+        "127.0.0.1:0",
+        Config {
+            certificates: vec![Certificate::generate_self_signed(vec![
+                "localhost".to_owned()
+            ])?],
+            connection_id_generator: Some(random_cid_generator(8)),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let client_conn = Arc::new(RebindingConn::new(listener.addr().await?).await?);
+    let client_conn2 = Arc::clone(&client_conn);
+    let (c_tx, mut c_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let client = DTLSConn::new(
+            client_conn2,
+            Config {
+                insecure_skip_verify: true,
+                connection_id_generator: Some(only_send_cid_generator()),
+                ..Default::default()
+            },
+            true,
+            None,
+        )
+        .await;
+        let _ = c_tx.send(client).await;
+    });
+
+    let (server, _) = listener.accept().await?;
+    let client = c_rx.recv().await.unwrap()?;
+    let client_addr = client_conn.local_addr().await?;
+
+    let mut buf = vec![0u8; 64];
+    client.write(b"ping", None).await?;
+    let n = server.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"ping", "before the forged record");
+
+    // An attacker who saw the connection ID sends a record with a higher
+    // sequence number from another address, it can't be authenticated
+    let connection_id = client
+        .state
+        .remote_connection_id
+        .lock()
+        .await
+        .clone()
+        .unwrap_or_default();
+    let mut forged = vec![];
+    crate::record_layer::record_layer_cid_header::RecordLayerCidHeader {
+        protocol_version: PROTOCOL_VERSION1_2,
+        epoch: 1,
+        sequence_number: 1000,
+        connection_id,
+        content_len: 32,
+    }
+    .marshal(&mut forged)?;
+    forged.extend_from_slice(&[0xAB; 32]);
+
+    let attacker = UdpSocket::bind("127.0.0.1:0").await?;
+    attacker.send_to(&forged, listener.addr().await?).await?;
+
+    client.write(b"ping2", None).await?;
+    let n = server.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"ping2", "after the forged record");
+    assert_eq!(
+        server.remote_addr().await,
+        Some(client_addr),
+        "forged record should not move the server"
+    );
+
+    server.send(b"pong").await?;
+    let n = client.read(&mut buf, None).await?;
+    assert_eq!(&buf[..n], b"pong", "server to client");
+
+    client.close().await?;
+    server.close().await?;
+    listener.close().await?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod conn_test;
 
+use crate::ack::*;
 use crate::alert::*;
 use crate::application_data::*;
 use crate::cipher_suite::*;
@@ -17,9 +18,11 @@ use crate::flight::*;
 use crate::fragment_buffer::*;
 use crate::handshake::handshake_cache::*;
 use crate::handshake::handshake_header::HandshakeHeader;
+use crate::handshake::handshake_message_key_update::*;
 use crate::handshake::*;
 use crate::handshaker::*;
 use crate::record_layer::record_layer_header::*;
+use crate::record_layer::record_layer_unified_header::*;
 use crate::record_layer::*;
 use crate::signature_hash_algorithm::parse_signature_schemes;
 use crate::state::*;
@@ -33,7 +36,7 @@ use std::marker::{Send, Sync};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, Mutex, Notify};
 use tokio::time::Duration;
 
 pub(crate) const INITIAL_TICKER_INTERVAL: Duration = Duration::from_secs(1);
@@ -52,6 +55,16 @@ pub static INVALID_KEYING_LABELS: &[&str] = &[
 
 type PacketSendRequest = (Vec<Packet>, Option<mpsc::Sender<Result<()>>>);
 
+// KeyUpdateState is shared by key_update and the reader, which answers the
+// KeyUpdates of the peer and switches to the next keys once our KeyUpdate
+// is acknowledged.
+#[derive(Default)]
+struct KeyUpdateState {
+    pending: AtomicBool,
+    message_sequence: AtomicU16,
+    done: Notify,
+}
+
 struct ConnReaderContext {
     is_client: bool,
     replay_protection_window: usize,
@@ -65,6 +78,15 @@ struct ConnReaderContext {
     handshake_tx: mpsc::Sender<mpsc::Sender<()>>,
     handshake_done_rx: mpsc::Receiver<()>,
     packet_tx: Arc<mpsc::Sender<PacketSendRequest>>,
+    dtls13: Arc<AtomicBool>,
+    key_update: Arc<KeyUpdateState>,
+    local_connection_id: Arc<Mutex<Option<Vec<u8>>>>,
+    next_conn: Arc<dyn util::Conn + Send + Sync>,
+    // Source of the datagram being handled and the newest record number
+    // authenticated with the connection ID, the peer address only follows
+    // newer authenticated records (RFC 9146 section 6)
+    datagram_source: Option<SocketAddr>,
+    newest_cid_record: Option<(u16, u64)>,
 }
 
 // Conn represents a DTLS connection
@@ -75,6 +97,7 @@ pub struct DTLSConn {
     pub(crate) state: State,                              // Internal state
 
     handshake_completed_successfully: Arc<AtomicBool>,
+    key_update: Arc<KeyUpdateState>,
     connection_closed_by_user: bool,
     // closeLock              sync.Mutex
     closed: AtomicBool, //  *closer.Closer
@@ -199,6 +222,19 @@ impl DTLSConn {
             }
         }
 
+        let session_key = if is_client {
+            if let Some(remote_addr) = conn.remote_addr().await {
+                format!("{}_{}", remote_addr, server_name).into_bytes()
+            } else {
+                server_name.clone().into_bytes()
+            }
+        } else {
+            vec![]
+        };
+
+        // The DTLS 1.3 handshake only authenticates with certificates
+        let enable_dtls13 = config.enable_dtls13 && config.psk.is_none();
+
         let cfg = HandshakeConfig {
             local_psk_callback: config.psk.take(),
             local_psk_identity_hint: config.psk_identity_hint.take(),
@@ -222,6 +258,11 @@ impl DTLSConn {
             retransmit_interval,
             //log: logger,
             initial_epoch: 0,
+            session_store: config.session_store.take(),
+            session_ticket_key: config.session_ticket_key.take(),
+            session_key,
+            enable_dtls13,
+            connection_id_generator: config.connection_id_generator.take(),
             ..Default::default()
         };
 
@@ -273,6 +314,7 @@ impl DTLSConn {
             decrypted_rx: Mutex::new(decrypted_rx),
             state,
             handshake_completed_successfully,
+            key_update: Arc::new(KeyUpdateState::default()),
             connection_closed_by_user: false,
             closed: AtomicBool::new(false),
 
@@ -289,6 +331,7 @@ impl DTLSConn {
 
         let cipher_suite1 = Arc::clone(&c.state.cipher_suite);
         let sequence_number = Arc::clone(&c.state.local_sequence_number);
+        let remote_connection_id = Arc::clone(&c.state.remote_connection_id);
 
         tokio::spawn(async move {
             loop {
@@ -303,6 +346,7 @@ impl DTLSConn {
                         is_client,
                         &sequence_number,
                         &cipher_suite1,
+                        &remote_connection_id,
                         maximum_transmission_unit,
                     )
                     .await;
@@ -320,6 +364,9 @@ impl DTLSConn {
         let local_epoch = Arc::clone(&c.state.local_epoch);
         let remote_epoch = Arc::clone(&c.state.remote_epoch);
         let cipher_suite2 = Arc::clone(&c.state.cipher_suite);
+        let dtls13 = Arc::clone(&c.state.dtls13);
+        let key_update = Arc::clone(&c.key_update);
+        let local_connection_id = Arc::clone(&c.state.local_connection_id);
 
         tokio::spawn(async move {
             let mut buf = vec![0u8; INBOUND_BUFFER_SIZE];
@@ -336,6 +383,12 @@ impl DTLSConn {
                 handshake_tx,
                 handshake_done_rx,
                 packet_tx: packet_tx2,
+                dtls13,
+                key_update,
+                local_connection_id,
+                next_conn: next_conn_rx,
+                datagram_source: None,
+                newest_cid_record: None,
             };
 
             //trace!("before enter read_and_buffer: {}] ", srv_cli_str(is_client));
@@ -350,7 +403,6 @@ impl DTLSConn {
                     }
                     result = DTLSConn::read_and_buffer(
                                             &mut ctx,
-                                            &next_conn_rx,
                                             &mut handle_queue_rx,
                                             &mut buf,
                                             &local_epoch,
@@ -380,6 +432,11 @@ impl DTLSConn {
         // Do handshake
         c.handshake(initial_fsm_state).await?;
 
+        // KeyUpdates continue the message sequence of the handshake
+        c.key_update
+            .message_sequence
+            .store(c.state.handshake_send_sequence as u16, Ordering::SeqCst);
+
         trace!("Handshake Completed");
 
         Ok(c)
@@ -491,6 +548,71 @@ impl DTLSConn {
         self.state.srtp_protection_profile
     }
 
+    /// did_resume tells whether the handshake resumed a previous session
+    /// instead of running a full handshake.
+    pub fn did_resume(&self) -> bool {
+        self.state.did_resume
+    }
+
+    /// protocol_version returns the DTLS version negotiated by the handshake.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        if self.state.dtls13.load(Ordering::SeqCst) {
+            PROTOCOL_VERSION1_3
+        } else {
+            PROTOCOL_VERSION1_2
+        }
+    }
+
+    /// key_update switches a DTLS 1.3 connection to the next keys for sending,
+    /// with update_requested the peer updates its keys too. It returns once
+    /// the peer acknowledged the KeyUpdate.
+    /// https://www.rfc-editor.org/rfc/rfc9147#section-8
+    pub async fn key_update(&self, update_requested: bool) -> Result<()> {
+        if !self.state.dtls13.load(Ordering::SeqCst) {
+            return Err(Error::ErrKeyUpdateRequiresDtls13);
+        }
+        if !self.is_handshake_completed_successfully() {
+            return Err(Error::ErrHandshakeInProgress);
+        }
+        if self.key_update.pending.swap(true, Ordering::SeqCst) {
+            return Err(Error::ErrKeyUpdateInProgress);
+        }
+
+        let mut handshake =
+            Handshake::new(HandshakeMessage::KeyUpdate(HandshakeMessageKeyUpdate {
+                update_requested,
+            }));
+        handshake.handshake_header.message_sequence = self
+            .key_update
+            .message_sequence
+            .fetch_add(1, Ordering::SeqCst);
+        let pkts = vec![Packet {
+            record: RecordLayer::new(
+                PROTOCOL_VERSION1_2,
+                self.get_local_epoch(),
+                Content::Handshake(handshake),
+            ),
+            should_encrypt: true,
+            reset_local_sequence_number: false,
+        }];
+
+        // Retransmit the KeyUpdate until the reader sees the ACK of the peer
+        while self.key_update.pending.load(Ordering::SeqCst) {
+            if self.is_connection_closed() {
+                return Err(Error::ErrConnClosed);
+            }
+
+            let done = self.key_update.done.notified();
+            self.write_packets(pkts.clone()).await?;
+            tokio::select! {
+                _ = done => {}
+                _ = tokio::time::sleep(self.cfg.retransmit_interval) => {}
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn notify(&self, level: AlertLevel, desc: AlertDescription) -> Result<()> {
         self.write_packets(vec![Packet {
             record: RecordLayer::new(
@@ -519,6 +641,7 @@ impl DTLSConn {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_outgoing_packets(
         next_conn: &Arc<dyn util::Conn + Send + Sync>,
         mut pkts: Vec<Packet>,
@@ -526,6 +649,7 @@ impl DTLSConn {
         is_client: bool,
         local_sequence_number: &Arc<Mutex<Vec<u64>>>,
         cipher_suite: &Arc<Mutex<Option<Box<dyn CipherSuite + Send + Sync>>>>,
+        remote_connection_id: &Arc<Mutex<Option<Vec<u8>>>>,
         maximum_transmission_unit: usize,
     ) -> Result<()> {
         let mut raw_packets = vec![];
@@ -556,6 +680,7 @@ impl DTLSConn {
                 let raw_handshake_packets = DTLSConn::process_handshake_packet(
                     local_sequence_number,
                     cipher_suite,
+                    remote_connection_id,
                     maximum_transmission_unit,
                     p,
                     h,
@@ -569,8 +694,13 @@ impl DTLSConn {
                     }
                 }*/
 
-                let raw_packet =
-                    DTLSConn::process_packet(local_sequence_number, cipher_suite, p).await?;
+                let raw_packet = DTLSConn::process_packet(
+                    local_sequence_number,
+                    cipher_suite,
+                    remote_connection_id,
+                    p,
+                )
+                .await?;
                 raw_packets.push(raw_packet);
             }
         }
@@ -590,6 +720,7 @@ impl DTLSConn {
     async fn process_packet(
         local_sequence_number: &Arc<Mutex<Vec<u64>>>,
         cipher_suite: &Arc<Mutex<Option<Box<dyn CipherSuite + Send + Sync>>>>,
+        remote_connection_id: &Arc<Mutex<Option<Vec<u8>>>>,
         p: &mut Packet,
     ) -> Result<Vec<u8>> {
         let epoch = p.record.record_layer_header.epoch as usize;
@@ -619,18 +750,44 @@ impl DTLSConn {
         }
 
         if p.should_encrypt {
-            let cipher_suite = cipher_suite.lock().await;
-            if let Some(cipher_suite) = &*cipher_suite {
-                raw_packet = cipher_suite.encrypt(&p.record.record_layer_header, &raw_packet)?;
-            }
+            raw_packet = DTLSConn::encrypt_packet(
+                cipher_suite,
+                remote_connection_id,
+                &p.record.record_layer_header,
+                raw_packet,
+            )
+            .await?;
         }
 
         Ok(raw_packet)
     }
 
+    // encrypt_packet protects an outgoing record, encrypted records carry the
+    // connection ID of the peer if it asked for one
+    async fn encrypt_packet(
+        cipher_suite: &Arc<Mutex<Option<Box<dyn CipherSuite + Send + Sync>>>>,
+        remote_connection_id: &Arc<Mutex<Option<Vec<u8>>>>,
+        h: &RecordLayerHeader,
+        raw_packet: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let cipher_suite = cipher_suite.lock().await;
+        if let Some(cipher_suite) = &*cipher_suite {
+            let remote_connection_id = remote_connection_id.lock().await;
+            match &*remote_connection_id {
+                Some(cid) if !cid.is_empty() && h.epoch != 0 => {
+                    cipher_suite.encrypt_cid(h, cid, &raw_packet)
+                }
+                _ => cipher_suite.encrypt(h, &raw_packet),
+            }
+        } else {
+            Ok(raw_packet)
+        }
+    }
+
     async fn process_handshake_packet(
         local_sequence_number: &Arc<Mutex<Vec<u64>>>,
         cipher_suite: &Arc<Mutex<Option<Box<dyn CipherSuite + Send + Sync>>>>,
+        remote_connection_id: &Arc<Mutex<Option<Vec<u8>>>>,
         maximum_transmission_unit: usize,
         p: &Packet,
         h: &Handshake,
@@ -676,10 +833,13 @@ impl DTLSConn {
             raw_packet.extend_from_slice(&record_layer_header_bytes);
             raw_packet.extend_from_slice(handshake_fragment);
             if p.should_encrypt {
-                let cipher_suite = cipher_suite.lock().await;
-                if let Some(cipher_suite) = &*cipher_suite {
-                    raw_packet = cipher_suite.encrypt(&record_layer_header, &raw_packet)?;
-                }
+                raw_packet = DTLSConn::encrypt_packet(
+                    cipher_suite,
+                    remote_connection_id,
+                    &record_layer_header,
+                    raw_packet,
+                )
+                .await?;
             }
 
             raw_packets.push(raw_packet);
@@ -744,17 +904,30 @@ impl DTLSConn {
 
     async fn read_and_buffer(
         ctx: &mut ConnReaderContext,
-        next_conn: &Arc<dyn util::Conn + Send + Sync>,
         handle_queue_rx: &mut mpsc::Receiver<mpsc::Sender<()>>,
         buf: &mut [u8],
         local_epoch: &Arc<AtomicU16>,
         handshake_completed_successfully: &Arc<AtomicBool>,
     ) -> Result<()> {
-        let n = next_conn.recv(buf).await?;
-        let pkts = unpack_datagram(&buf[..n])?;
+        let connection_id_len = ctx
+            .local_connection_id
+            .lock()
+            .await
+            .as_ref()
+            .map_or(0, |cid| cid.len());
+        // The source is only needed to follow a peer which uses a connection ID
+        let n = if connection_id_len != 0 {
+            let (n, source) = ctx.next_conn.recv_from(buf).await?;
+            ctx.datagram_source = Some(source);
+            n
+        } else {
+            ctx.next_conn.recv(buf).await?
+        };
+        let pkts = unpack_datagram_with_cid(&buf[..n], connection_id_len)?;
         let mut has_handshake = false;
         for pkt in pkts {
-            let (hs, alert, mut err) = DTLSConn::handle_incoming_packet(ctx, pkt, true).await;
+            let (hs, alert, mut err) =
+                DTLSConn::handle_incoming_packet(ctx, pkt, local_epoch, true).await;
             if let Some(alert) = alert {
                 let alert_err = ctx
                     .packet_tx
@@ -828,14 +1001,39 @@ impl DTLSConn {
         Ok(())
     }
 
+    // follow_peer_address moves the conn to the source of an authenticated
+    // connection ID record if it is newer than the records seen so far
+    async fn follow_peer_address(ctx: &mut ConnReaderContext, epoch: u16, sequence_number: u64) {
+        let record = (epoch, sequence_number);
+        if matches!(ctx.newest_cid_record, Some(newest) if record <= newest) {
+            return;
+        }
+        ctx.newest_cid_record = Some(record);
+
+        if let Some(source) = ctx.datagram_source {
+            if ctx.next_conn.remote_addr().await != Some(source) {
+                if let Err(err) = ctx.next_conn.set_remote_addr(source).await {
+                    debug!(
+                        "{}: failed to follow the peer address: {}",
+                        srv_cli_str(ctx.is_client),
+                        err
+                    );
+                }
+            }
+        }
+    }
+
     async fn handle_queued_packets(
         ctx: &mut ConnReaderContext,
         local_epoch: &Arc<AtomicU16>,
         handshake_completed_successfully: &Arc<AtomicBool>,
         pkts: Vec<Vec<u8>>,
     ) -> Result<()> {
+        // The source of queued packets is unknown
+        ctx.datagram_source = None;
         for p in pkts {
-            let (_, alert, mut err) = DTLSConn::handle_incoming_packet(ctx, p, false).await; // don't re-enqueue
+            let (_, alert, mut err) =
+                DTLSConn::handle_incoming_packet(ctx, p, local_epoch, false).await; // don't re-enqueue
             if let Some(alert) = alert {
                 let alert_err = ctx
                     .packet_tx
@@ -879,8 +1077,39 @@ impl DTLSConn {
     async fn handle_incoming_packet(
         ctx: &mut ConnReaderContext,
         mut pkt: Vec<u8>,
+        local_epoch: &Arc<AtomicU16>,
         enqueue: bool,
     ) -> (bool, Option<Alert>, Option<Error>) {
+        // DTLS 1.3 records carry the unified header, once decrypted they are
+        // handled like DTLS 1.2 records
+        let unified = !pkt.is_empty() && is_unified_header(pkt[0]);
+        if unified {
+            let decrypted = {
+                let cipher_suite = ctx.cipher_suite.lock().await;
+                match &*cipher_suite {
+                    Some(cipher_suite) => cipher_suite.decrypt(&pkt),
+                    None => Err(Error::ErrEpochKeysUnavailable),
+                }
+            };
+            pkt = match decrypted {
+                Ok(pkt) => pkt,
+                Err(Error::ErrEpochKeysUnavailable) => {
+                    if enqueue {
+                        debug!(
+                            "{}: keys of the epoch not installed yet, queuing packet",
+                            srv_cli_str(ctx.is_client)
+                        );
+                        ctx.encrypted_packets.push(pkt);
+                    }
+                    return (false, None, None);
+                }
+                Err(err) => {
+                    debug!("{}: decrypt failed: {}", srv_cli_str(ctx.is_client), err);
+                    return (false, None, None);
+                }
+            };
+        }
+
         let mut reader = BufReader::new(pkt.as_slice());
         let h = match RecordLayerHeader::unmarshal(&mut reader) {
             Ok(h) => h,
@@ -896,9 +1125,26 @@ impl DTLSConn {
             }
         };
 
+        // Records with a connection ID are only expected once it was
+        // negotiated, and always encrypted
+        let connection_id_len = if h.content_type == ContentType::Tls12Cid {
+            match &*ctx.local_connection_id.lock().await {
+                Some(cid) if !cid.is_empty() && h.epoch != 0 => Some(cid.len()),
+                _ => {
+                    debug!(
+                        "{}: discarded unexpected connection ID record",
+                        srv_cli_str(ctx.is_client),
+                    );
+                    return (false, None, None);
+                }
+            }
+        } else {
+            None
+        };
+
         // Validate epoch
         let epoch = ctx.remote_epoch.load(Ordering::SeqCst);
-        if h.epoch > epoch {
+        if h.epoch > epoch && !unified {
             if h.epoch > epoch + 1 {
                 debug!(
                     "{}: discarded future packet (epoch: {}, seq: {})",
@@ -939,7 +1185,7 @@ impl DTLSConn {
         }
 
         // Decrypt
-        if h.epoch != 0 {
+        if h.epoch != 0 && !unified {
             let invalid_cipher_suite = {
                 let cipher_suite = ctx.cipher_suite.lock().await;
                 if cipher_suite.is_none() {
@@ -963,7 +1209,11 @@ impl DTLSConn {
 
             let cipher_suite = ctx.cipher_suite.lock().await;
             if let Some(cipher_suite) = &*cipher_suite {
-                pkt = match cipher_suite.decrypt(&pkt) {
+                let decrypted = match connection_id_len {
+                    Some(connection_id_len) => cipher_suite.decrypt_cid(&pkt, connection_id_len),
+                    None => cipher_suite.decrypt(&pkt),
+                };
+                pkt = match decrypted {
                     Ok(pkt) => pkt,
                     Err(err) => {
                         debug!("{}: decrypt failed: {}", srv_cli_str(ctx.is_client), err);
@@ -971,6 +1221,11 @@ impl DTLSConn {
                     }
                 };
             }
+            drop(cipher_suite);
+
+            if connection_id_len.is_some() {
+                DTLSConn::follow_peer_address(ctx, h.epoch, h.sequence_number).await;
+            }
         }
 
         let is_handshake = match ctx.fragment_buffer.push(&pkt) {
@@ -984,10 +1239,52 @@ impl DTLSConn {
         };
         if is_handshake {
             ctx.replay_detector[h.epoch as usize].accept();
+
+            // DTLS 1.3 acknowledges the last flight of the client and the
+            // messages after the handshake, other flights are acknowledged
+            // implicitly by the next flight.
+            // https://www.rfc-editor.org/rfc/rfc9147#section-7.1
+            let dtls13 = ctx.dtls13.load(Ordering::SeqCst);
+            if dtls13
+                && (h.epoch >= DTLS13_APPLICATION_EPOCH
+                    || (!ctx.is_client && h.epoch == DTLS13_HANDSHAKE_EPOCH))
+            {
+                if let Err(err) = ctx
+                    .packet_tx
+                    .send((
+                        vec![Packet {
+                            record: RecordLayer::new(
+                                PROTOCOL_VERSION1_2,
+                                local_epoch.load(Ordering::SeqCst),
+                                Content::Ack(Ack {
+                                    record_numbers: vec![RecordNumber {
+                                        epoch: h.epoch as u64,
+                                        sequence_number: h.sequence_number,
+                                    }],
+                                }),
+                            ),
+                            should_encrypt: true,
+                            reset_local_sequence_number: false,
+                        }],
+                        None,
+                    ))
+                    .await
+                {
+                    return (false, None, Some(Error::Other(err.to_string())));
+                }
+            }
+            if dtls13 && h.epoch >= DTLS13_APPLICATION_EPOCH {
+                return DTLSConn::handle_post_handshake_messages(ctx, local_epoch).await;
+            }
+
             while let Ok((out, epoch)) = ctx.fragment_buffer.pop() {
                 //log::debug!("Extension Debug: out.len()={}", out.len());
                 let mut reader = BufReader::new(out.as_slice());
-                let raw_handshake = match Handshake::unmarshal(&mut reader) {
+                let raw_handshake = match if dtls13 {
+                    Handshake::unmarshal_dtls13(&mut reader)
+                } else {
+                    Handshake::unmarshal(&mut reader)
+                } {
                     Ok(rh) => {
                         trace!(
                             "Recv [handshake:{}] -> {} (epoch: {}, seq: {})",
@@ -1110,6 +1407,48 @@ impl DTLSConn {
                     case < -c.closed.Done():
                 }*/
             }
+            Content::Ack(a) if ctx.dtls13.load(Ordering::SeqCst) => {
+                ctx.replay_detector[h.epoch as usize].accept();
+
+                // Our KeyUpdate is the only record acknowledged in the
+                // current epoch, the next epoch starts once the peer has it
+                let current_epoch = local_epoch.load(Ordering::SeqCst);
+                if ctx.key_update.pending.load(Ordering::SeqCst)
+                    && a.record_numbers
+                        .iter()
+                        .any(|r| r.epoch == current_epoch as u64)
+                {
+                    let next_epoch = {
+                        let mut cipher_suite = ctx.cipher_suite.lock().await;
+                        match &mut *cipher_suite {
+                            Some(cipher_suite) => cipher_suite.update_traffic_secret(true),
+                            None => Err(Error::ErrCipherSuiteUnset),
+                        }
+                    };
+                    let next_epoch = match next_epoch {
+                        Ok(next_epoch) => next_epoch,
+                        Err(err) => {
+                            return (
+                                false,
+                                Some(Alert {
+                                    alert_level: AlertLevel::Fatal,
+                                    alert_description: AlertDescription::InternalError,
+                                }),
+                                Some(err),
+                            )
+                        }
+                    };
+                    local_epoch.store(next_epoch, Ordering::SeqCst);
+                    trace!(
+                        "{}: KeyUpdate acknowledged (epoch: {})",
+                        srv_cli_str(ctx.is_client),
+                        next_epoch
+                    );
+
+                    ctx.key_update.pending.store(false, Ordering::SeqCst);
+                    ctx.key_update.done.notify_one();
+                }
+            }
             _ => {
                 return (
                     false,
@@ -1125,6 +1464,95 @@ impl DTLSConn {
         (false, None, None)
     }
 
+    // handle_post_handshake_messages processes the handshake messages a DTLS
+    // 1.3 peer sends after the handshake. A KeyUpdate switches to the next
+    // keys of the peer and is answered if the peer requests it.
+    async fn handle_post_handshake_messages(
+        ctx: &mut ConnReaderContext,
+        local_epoch: &Arc<AtomicU16>,
+    ) -> (bool, Option<Alert>, Option<Error>) {
+        while let Ok((out, _)) = ctx.fragment_buffer.pop() {
+            let mut reader = BufReader::new(out.as_slice());
+            let key_update = match Handshake::unmarshal_dtls13(&mut reader) {
+                Ok(Handshake {
+                    handshake_message: HandshakeMessage::KeyUpdate(key_update),
+                    ..
+                }) => key_update,
+                Ok(rh) => {
+                    debug!(
+                        "{}: discarded post-handshake message {}",
+                        srv_cli_str(ctx.is_client),
+                        rh.handshake_header.handshake_type
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    debug!(
+                        "{}: handshake parse failed: {}",
+                        srv_cli_str(ctx.is_client),
+                        err
+                    );
+                    continue;
+                }
+            };
+            trace!(
+                "Recv [handshake:{}] -> KeyUpdate (update_requested: {})",
+                srv_cli_str(ctx.is_client),
+                key_update.update_requested
+            );
+
+            let result = {
+                let mut cipher_suite = ctx.cipher_suite.lock().await;
+                match &mut *cipher_suite {
+                    Some(cipher_suite) => cipher_suite.update_traffic_secret(false),
+                    None => Err(Error::ErrCipherSuiteUnset),
+                }
+            };
+            if let Err(err) = result {
+                return (
+                    false,
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    Some(err),
+                );
+            }
+
+            // Skip the answer while a KeyUpdate of our own is on its way
+            if key_update.update_requested && !ctx.key_update.pending.swap(true, Ordering::SeqCst) {
+                let mut handshake =
+                    Handshake::new(HandshakeMessage::KeyUpdate(HandshakeMessageKeyUpdate {
+                        update_requested: false,
+                    }));
+                handshake.handshake_header.message_sequence = ctx
+                    .key_update
+                    .message_sequence
+                    .fetch_add(1, Ordering::SeqCst);
+                if let Err(err) = ctx
+                    .packet_tx
+                    .send((
+                        vec![Packet {
+                            record: RecordLayer::new(
+                                PROTOCOL_VERSION1_2,
+                                local_epoch.load(Ordering::SeqCst),
+                                Content::Handshake(handshake),
+                            ),
+                            should_encrypt: true,
+                            reset_local_sequence_number: false,
+                        }],
+                        None,
+                    ))
+                    .await
+                {
+                    return (false, None, Some(Error::Other(err.to_string())));
+                }
+            }
+        }
+
+        (false, None, None)
+    }
+
     fn is_connection_closed(&self) -> bool {
         self.closed.load(Ordering::SeqCst)
     }
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod connection_id_test;
+
+use crate::content::*;
+use crate::extension::*;
+use crate::handshake::*;
+use crate::record_layer::record_layer_header::*;
+use crate::record_layer::*;
+
+use rand::Rng;
+use std::io::BufReader;
+use std::sync::Arc;
+use util::conn::conn_udp_listener::{ConnIdentifierFn, DatagramRouterFn};
+
+/// ConnectionIdGenerator returns the connection ID the peer is asked to send
+/// in its records (RFC 9146). An empty connection ID supports the extension
+/// without receiving connection IDs. Servers behind a DTLS listener must
+/// always return connection IDs of the same length.
+pub type ConnectionIdGenerator = Arc<dyn (Fn() -> Vec<u8>) + Send + Sync>;
+
+/// random_cid_generator generates random connection IDs of size bytes.
+pub fn random_cid_generator(size: usize) -> ConnectionIdGenerator {
+    Arc::new(move || {
+        let mut cid = vec![0u8; size];
+        rand::thread_rng().fill(cid.as_mut_slice());
+        cid
+    })
+}
+
+/// only_send_cid_generator sends the connection ID of the peer without asking
+/// for one, typical for clients which are not behind a listener.
+pub fn only_send_cid_generator() -> ConnectionIdGenerator {
+    Arc::new(Vec::new)
+}
+
+fn cid_key(cid: &[u8]) -> String {
+    cid.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// cid_datagram_router routes datagrams whose first record carries a
+// connection ID of size bytes to the conn which asked for it.
+pub(crate) fn cid_datagram_router(size: usize) -> DatagramRouterFn {
+    Box::new(move |packet: &[u8]| {
+        if size == 0
+            || packet.len() < RECORD_LAYER_HEADER_SIZE + size
+            || packet[0] != ContentType::Tls12Cid as u8
+        {
+            return None;
+        }
+
+        // The connection ID follows the epoch and the sequence number
+        let offset = RECORD_LAYER_HEADER_SIZE - 2;
+        Some(cid_key(&packet[offset..offset + size]))
+    })
+}
+
+// cid_conn_identifier finds the connection ID a server conn asked for in the
+// ServerHello it sends.
+pub(crate) fn cid_conn_identifier() -> ConnIdentifierFn {
+    Arc::new(|packet: &[u8]| {
+        let pkts = unpack_datagram(packet).ok()?;
+        for pkt in pkts {
+            if pkt.first() != Some(&(ContentType::Handshake as u8)) {
+                continue;
+            }
+
+            let mut reader = BufReader::new(pkt.as_slice());
+            if let Ok(RecordLayer {
+                content: Content::Handshake(h),
+                ..
+            }) = RecordLayer::unmarshal(&mut reader)
+            {
+                if let HandshakeMessage::ServerHello(server_hello) = h.handshake_message {
+                    return server_hello.extensions.iter().find_map(|e| match e {
+                        Extension::ConnectionId(e) if !e.cid.is_empty() => Some(cid_key(&e.cid)),
+                        _ => None,
+                    });
+                }
+            }
+        }
+
+        None
+    })
+}
@@ -0,0 +1,78 @@
+use super::*;
+use crate::cipher_suite::CipherSuiteId;
+use crate::compression_methods::CompressionMethodId;
+use crate::extension::extension_connection_id::ExtensionConnectionId;
+use crate::handshake::handshake_message_server_hello::HandshakeMessageServerHello;
+use crate::handshake::handshake_random::HandshakeRandom;
+
+use std::io::BufWriter;
+
+#[test]
+fn test_random_cid_generator() {
+    let generator = random_cid_generator(8);
+    let (a, b) = (generator(), generator());
+    assert_eq!(a.len(), 8);
+    assert_ne!(a, b, "connection IDs should be random");
+
+    assert!(only_send_cid_generator()().is_empty());
+}
+
+#[test]
+fn test_cid_datagram_router() {
+    let router = cid_datagram_router(4);
+
+    let cid_record = vec![
+        0x19, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xde, 0xad, 0xbe, 0xef,
+        0x00, 0x01, 0xff,
+    ];
+    assert_eq!(router(&cid_record), Some("deadbeef".to_owned()));
+
+    // Other records are routed by address
+    let mut application_data = cid_record.clone();
+    application_data[0] = ContentType::ApplicationData as u8;
+    assert_eq!(router(&application_data), None);
+    assert_eq!(router(&cid_record[..12]), None);
+}
+
+#[test]
+fn test_cid_conn_identifier() -> crate::error::Result<()> {
+    let identifier = cid_conn_identifier();
+
+    let server_hello = |extensions: Vec<Extension>| -> crate::error::Result<Vec<u8>> {
+        let record = RecordLayer::new(
+            PROTOCOL_VERSION1_2,
+            0,
+            Content::Handshake(Handshake::new(HandshakeMessage::ServerHello(
+                HandshakeMessageServerHello {
+                    version: PROTOCOL_VERSION1_2,
+                    random: HandshakeRandom::default(),
+                    session_id: vec![],
+                    cipher_suite: CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Gcm_Sha256,
+                    compression_method: CompressionMethodId::Null,
+                    extensions,
+                },
+            ))),
+        );
+        let mut raw = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+            record.marshal(&mut writer)?;
+        }
+        Ok(raw)
+    };
+
+    let raw = server_hello(vec![Extension::ConnectionId(ExtensionConnectionId {
+        cid: vec![0x01, 0x02],
+    })])?;
+    assert_eq!(identifier(&raw), Some("0102".to_owned()));
+
+    // A server which did not ask for a connection ID is found by its address
+    let raw = server_hello(vec![Extension::ConnectionId(ExtensionConnectionId {
+        cid: vec![],
+    })])?;
+    assert_eq!(identifier(&raw), None);
+    let raw = server_hello(vec![])?;
+    assert_eq!(identifier(&raw), None);
+
+    Ok(())
+}
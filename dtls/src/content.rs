@@ -1,3 +1,4 @@
+use super::ack::*;
 use super::alert::*;
 use super::application_data::*;
 use super::change_cipher_spec::*;
@@ -13,6 +14,9 @@ pub enum ContentType {
     Alert = 21,
     Handshake = 22,
     ApplicationData = 23,
+    // Outer type of records carrying a connection ID, RFC 9146
+    Tls12Cid = 25,
+    Ack = 26,
     Invalid,
 }
 
@@ -23,6 +27,8 @@ impl From<u8> for ContentType {
             21 => ContentType::Alert,
             22 => ContentType::Handshake,
             23 => ContentType::ApplicationData,
+            25 => ContentType::Tls12Cid,
+            26 => ContentType::Ack,
             _ => ContentType::Invalid,
         }
     }
@@ -40,6 +46,7 @@ pub enum Content {
     Alert(Alert),
     Handshake(Handshake),
     ApplicationData(ApplicationData),
+    Ack(Ack),
 }
 
 impl Content {
@@ -49,6 +56,7 @@ impl Content {
             Content::Alert(c) => c.content_type(),
             Content::Handshake(c) => c.content_type(),
             Content::ApplicationData(c) => c.content_type(),
+            Content::Ack(c) => c.content_type(),
         }
     }
 
@@ -58,6 +66,7 @@ impl Content {
             Content::Alert(c) => c.size(),
             Content::Handshake(c) => c.size(),
             Content::ApplicationData(c) => c.size(),
+            Content::Ack(c) => c.size(),
         }
     }
 
@@ -67,6 +76,7 @@ impl Content {
             Content::Alert(c) => c.marshal(writer),
             Content::Handshake(c) => c.marshal(writer),
             Content::ApplicationData(c) => c.marshal(writer),
+            Content::Ack(c) => c.marshal(writer),
         }
     }
 
@@ -80,6 +90,7 @@ impl Content {
             ContentType::ApplicationData => Ok(Content::ApplicationData(
                 ApplicationData::unmarshal(reader)?,
             )),
+            ContentType::Ack => Ok(Content::Ack(Ack::unmarshal(reader)?)),
             _ => Err(Error::ErrInvalidContentType),
         }
     }
@@ -15,6 +15,7 @@ use std::io::Cursor;
 use super::*;
 use crate::content::*;
 use crate::error::*;
+use crate::record_layer::record_layer_cid_header::*;
 use crate::record_layer::record_layer_header::*;
 
 use aes::Aes128;
@@ -106,39 +107,69 @@ impl CryptoCcm {
 
     pub fn encrypt(&self, pkt_rlh: &RecordLayerHeader, raw: &[u8]) -> Result<Vec<u8>> {
         let payload = &raw[RECORD_LAYER_HEADER_SIZE..];
-        let raw = &raw[..RECORD_LAYER_HEADER_SIZE];
+        let additional_data = generate_aead_additional_data(pkt_rlh, payload.len());
+
+        self.seal(&raw[..RECORD_LAYER_HEADER_SIZE], &additional_data, payload)
+    }
+
+    // encrypt_cid protects a record for a peer which asked for the connection
+    // ID, the content type moves into the encrypted DTLSInnerPlaintext
+    pub fn encrypt_cid(
+        &self,
+        pkt_rlh: &RecordLayerHeader,
+        connection_id: &[u8],
+        raw: &[u8],
+    ) -> Result<Vec<u8>> {
+        let inner = RecordLayerCidHeader::inner_plaintext(
+            pkt_rlh.content_type,
+            &raw[RECORD_LAYER_HEADER_SIZE..],
+        );
+        let h = RecordLayerCidHeader {
+            protocol_version: pkt_rlh.protocol_version,
+            epoch: pkt_rlh.epoch,
+            sequence_number: pkt_rlh.sequence_number,
+            connection_id: connection_id.to_vec(),
+            content_len: 0,
+        };
+        let additional_data = generate_aead_additional_data_cid(&h, inner.len());
+
+        let mut header = vec![];
+        h.marshal(&mut header)?;
+
+        self.seal(&header, &additional_data, &inner)
+    }
 
+    // seal encrypts the payload and returns it after the header and the
+    // explicit nonce, the length field at the end of the header is updated
+    fn seal(&self, header: &[u8], additional_data: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
         let mut nonce = vec![0u8; CRYPTO_CCM_NONCE_LENGTH];
         nonce[..4].copy_from_slice(&self.local_write_iv[..4]);
         rand::thread_rng().fill(&mut nonce[4..]);
         let nonce = GenericArray::from_slice(&nonce);
 
-        let additional_data = generate_aead_additional_data(pkt_rlh, payload.len());
-
         let mut buffer: Vec<u8> = Vec::new();
         buffer.extend_from_slice(payload);
 
         match &self.local_ccm {
             CryptoCcmType::CryptoCcm(ccm) => {
-                ccm.encrypt_in_place(nonce, &additional_data, &mut buffer)
+                ccm.encrypt_in_place(nonce, additional_data, &mut buffer)
                     .map_err(|e| Error::Other(e.to_string()))?;
             }
             CryptoCcmType::CryptoCcm8(ccm8) => {
-                ccm8.encrypt_in_place(nonce, &additional_data, &mut buffer)
+                ccm8.encrypt_in_place(nonce, additional_data, &mut buffer)
                     .map_err(|e| Error::Other(e.to_string()))?;
             }
         }
 
-        let mut r = Vec::with_capacity(raw.len() + nonce.len() + buffer.len());
+        let mut r = Vec::with_capacity(header.len() + nonce.len() + buffer.len());
 
-        r.extend_from_slice(raw);
+        r.extend_from_slice(header);
         r.extend_from_slice(&nonce[4..]);
         r.extend_from_slice(&buffer);
 
         // Update recordLayer size to include explicit nonce
-        let r_len = (r.len() - RECORD_LAYER_HEADER_SIZE) as u16;
-        r[RECORD_LAYER_HEADER_SIZE - 2..RECORD_LAYER_HEADER_SIZE]
-            .copy_from_slice(&r_len.to_be_bytes());
+        let r_len = (r.len() - header.len()) as u16;
+        r[header.len() - 2..header.len()].copy_from_slice(&r_len.to_be_bytes());
 
         Ok(r)
     }
@@ -151,39 +182,61 @@ impl CryptoCcm {
             return Ok(r.to_vec());
         }
 
-        if r.len() <= (RECORD_LAYER_HEADER_SIZE + 8) {
+        let buffer = self.open(r, RECORD_LAYER_HEADER_SIZE, |payload_len| {
+            generate_aead_additional_data(&h, payload_len)
+        })?;
+
+        let mut d = Vec::with_capacity(RECORD_LAYER_HEADER_SIZE + buffer.len());
+        d.extend_from_slice(&r[..RECORD_LAYER_HEADER_SIZE]);
+        d.extend_from_slice(&buffer);
+
+        Ok(d)
+    }
+
+    // decrypt_cid opens a tls12_cid record and returns it with the real
+    // content type, as decrypt does for other records
+    pub fn decrypt_cid(&self, r: &[u8], connection_id_len: usize) -> Result<Vec<u8>> {
+        let mut reader = Cursor::new(r);
+        let h = RecordLayerCidHeader::unmarshal(&mut reader, connection_id_len)?;
+
+        let inner = self.open(r, h.size(), |payload_len| {
+            generate_aead_additional_data_cid(&h, payload_len)
+        })?;
+
+        h.record_from_inner_plaintext(inner)
+    }
+
+    fn open<F>(&self, r: &[u8], header_len: usize, additional_data: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce(usize) -> Vec<u8>,
+    {
+        if r.len() <= (header_len + 8) {
             return Err(Error::ErrNotEnoughRoomForNonce);
         }
 
         let mut nonce = vec![];
         nonce.extend_from_slice(&self.remote_write_iv[..4]);
-        nonce.extend_from_slice(&r[RECORD_LAYER_HEADER_SIZE..RECORD_LAYER_HEADER_SIZE + 8]);
+        nonce.extend_from_slice(&r[header_len..header_len + 8]);
         let nonce = GenericArray::from_slice(&nonce);
 
-        let out = &r[RECORD_LAYER_HEADER_SIZE + 8..];
+        let out = &r[header_len + 8..];
 
         let mut buffer: Vec<u8> = Vec::new();
         buffer.extend_from_slice(out);
 
         match &self.remote_ccm {
             CryptoCcmType::CryptoCcm(ccm) => {
-                let additional_data =
-                    generate_aead_additional_data(&h, out.len() - CRYPTO_CCM_TAG_LENGTH);
+                let additional_data = additional_data(out.len() - CRYPTO_CCM_TAG_LENGTH);
                 ccm.decrypt_in_place(nonce, &additional_data, &mut buffer)
                     .map_err(|e| Error::Other(e.to_string()))?;
             }
             CryptoCcmType::CryptoCcm8(ccm8) => {
-                let additional_data =
-                    generate_aead_additional_data(&h, out.len() - CRYPTO_CCM_8_TAG_LENGTH);
+                let additional_data = additional_data(out.len() - CRYPTO_CCM_8_TAG_LENGTH);
                 ccm8.decrypt_in_place(nonce, &additional_data, &mut buffer)
                     .map_err(|e| Error::Other(e.to_string()))?;
             }
         }
 
-        let mut d = Vec::with_capacity(RECORD_LAYER_HEADER_SIZE + buffer.len());
-        d.extend_from_slice(&r[..RECORD_LAYER_HEADER_SIZE]);
-        d.extend_from_slice(&buffer);
-
-        Ok(d)
+        Ok(buffer)
     }
 }
@@ -0,0 +1,200 @@
+// ChaCha20-Poly1305
+// Stream cipher AEAD which is fast in software, for devices without AES
+// instructions. The nonce is not sent, it is derived from the write IV and
+// the epoch and sequence number of the record.
+// RFC 7905 year 2016 https://tools.ietf.org/html/rfc7905
+
+// https://github.com/RustCrypto/AEADs
+// https://docs.rs/chacha20poly1305/0.7.1/chacha20poly1305/
+
+use std::io::Cursor;
+
+use super::*;
+use crate::content::*;
+use crate::error::*;
+use crate::record_layer::record_layer_cid_header::*;
+use crate::record_layer::record_layer_header::*;
+
+use chacha20poly1305::aead::{generic_array::GenericArray, AeadInPlace, NewAead};
+use chacha20poly1305::ChaCha20Poly1305;
+
+const CRYPTO_CHACHA20_POLY1305_TAG_LENGTH: usize = 16;
+const CRYPTO_CHACHA20_POLY1305_NONCE_LENGTH: usize = 12;
+
+// State needed to handle encrypted input/output
+#[derive(Clone)]
+pub struct CryptoChacha20Poly1305 {
+    local_chacha: ChaCha20Poly1305,
+    remote_chacha: ChaCha20Poly1305,
+    local_write_iv: Vec<u8>,
+    remote_write_iv: Vec<u8>,
+}
+
+impl CryptoChacha20Poly1305 {
+    pub fn new(
+        local_key: &[u8],
+        local_write_iv: &[u8],
+        remote_key: &[u8],
+        remote_write_iv: &[u8],
+    ) -> Self {
+        let key = GenericArray::from_slice(local_key);
+        let local_chacha = ChaCha20Poly1305::new(key);
+
+        let key = GenericArray::from_slice(remote_key);
+        let remote_chacha = ChaCha20Poly1305::new(key);
+
+        CryptoChacha20Poly1305 {
+            local_chacha,
+            local_write_iv: local_write_iv.to_vec(),
+            remote_chacha,
+            remote_write_iv: remote_write_iv.to_vec(),
+        }
+    }
+
+    pub fn encrypt(&self, pkt_rlh: &RecordLayerHeader, raw: &[u8]) -> Result<Vec<u8>> {
+        let payload = &raw[RECORD_LAYER_HEADER_SIZE..];
+        let additional_data = generate_aead_additional_data(pkt_rlh, payload.len());
+        let nonce = Self::nonce(&self.local_write_iv, pkt_rlh.epoch, pkt_rlh.sequence_number);
+
+        self.seal(
+            &raw[..RECORD_LAYER_HEADER_SIZE],
+            &nonce,
+            &additional_data,
+            payload,
+        )
+    }
+
+    // encrypt_cid protects a record for a peer which asked for the connection
+    // ID, the content type moves into the encrypted DTLSInnerPlaintext
+    pub fn encrypt_cid(
+        &self,
+        pkt_rlh: &RecordLayerHeader,
+        connection_id: &[u8],
+        raw: &[u8],
+    ) -> Result<Vec<u8>> {
+        let inner = RecordLayerCidHeader::inner_plaintext(
+            pkt_rlh.content_type,
+            &raw[RECORD_LAYER_HEADER_SIZE..],
+        );
+        let h = RecordLayerCidHeader {
+            protocol_version: pkt_rlh.protocol_version,
+            epoch: pkt_rlh.epoch,
+            sequence_number: pkt_rlh.sequence_number,
+            connection_id: connection_id.to_vec(),
+            content_len: 0,
+        };
+        let additional_data = generate_aead_additional_data_cid(&h, inner.len());
+        let nonce = Self::nonce(&self.local_write_iv, h.epoch, h.sequence_number);
+
+        let mut header = vec![];
+        h.marshal(&mut header)?;
+
+        self.seal(&header, &nonce, &additional_data, &inner)
+    }
+
+    // The per-record nonce is the write IV XORed with the 64-bit epoch and
+    // sequence number, left padded with zeros
+    // https://tools.ietf.org/html/rfc7905#section-2
+    fn nonce(write_iv: &[u8], epoch: u16, sequence_number: u64) -> Vec<u8> {
+        let mut nonce = write_iv[..CRYPTO_CHACHA20_POLY1305_NONCE_LENGTH].to_vec();
+        let mut seq = sequence_number.to_be_bytes();
+        seq[..2].copy_from_slice(&epoch.to_be_bytes());
+        for (n, s) in nonce[4..].iter_mut().zip(seq.iter()) {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    // seal encrypts the payload and returns it after the header, the length
+    // field at the end of the header is updated
+    fn seal(
+        &self,
+        header: &[u8],
+        nonce: &[u8],
+        additional_data: &[u8],
+        payload: &[u8],
+    ) -> Result<Vec<u8>> {
+        let nonce = GenericArray::from_slice(nonce);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        buffer.extend_from_slice(payload);
+
+        self.local_chacha
+            .encrypt_in_place(nonce, additional_data, &mut buffer)
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        let mut r = Vec::with_capacity(header.len() + buffer.len());
+        r.extend_from_slice(header);
+        r.extend_from_slice(&buffer);
+
+        // Update recordLayer size to include the tag
+        let r_len = (r.len() - header.len()) as u16;
+        r[header.len() - 2..header.len()].copy_from_slice(&r_len.to_be_bytes());
+
+        Ok(r)
+    }
+
+    pub fn decrypt(&self, r: &[u8]) -> Result<Vec<u8>> {
+        let mut reader = Cursor::new(r);
+        let h = RecordLayerHeader::unmarshal(&mut reader)?;
+        if h.content_type == ContentType::ChangeCipherSpec {
+            // Nothing to encrypt with ChangeCipherSpec
+            return Ok(r.to_vec());
+        }
+
+        let nonce = Self::nonce(&self.remote_write_iv, h.epoch, h.sequence_number);
+        let buffer = self.open(r, RECORD_LAYER_HEADER_SIZE, &nonce, |payload_len| {
+            generate_aead_additional_data(&h, payload_len)
+        })?;
+
+        let mut d = Vec::with_capacity(RECORD_LAYER_HEADER_SIZE + buffer.len());
+        d.extend_from_slice(&r[..RECORD_LAYER_HEADER_SIZE]);
+        d.extend_from_slice(&buffer);
+
+        Ok(d)
+    }
+
+    // decrypt_cid opens a tls12_cid record and returns it with the real
+    // content type, as decrypt does for other records
+    pub fn decrypt_cid(&self, r: &[u8], connection_id_len: usize) -> Result<Vec<u8>> {
+        let mut reader = Cursor::new(r);
+        let h = RecordLayerCidHeader::unmarshal(&mut reader, connection_id_len)?;
+
+        let nonce = Self::nonce(&self.remote_write_iv, h.epoch, h.sequence_number);
+        let inner = self.open(r, h.size(), &nonce, |payload_len| {
+            generate_aead_additional_data_cid(&h, payload_len)
+        })?;
+
+        h.record_from_inner_plaintext(inner)
+    }
+
+    fn open<F>(
+        &self,
+        r: &[u8],
+        header_len: usize,
+        nonce: &[u8],
+        additional_data: F,
+    ) -> Result<Vec<u8>>
+    where
+        F: FnOnce(usize) -> Vec<u8>,
+    {
+        if r.len() < (header_len + CRYPTO_CHACHA20_POLY1305_TAG_LENGTH) {
+            return Err(Error::ErrInvalidPacketLength);
+        }
+
+        let nonce = GenericArray::from_slice(nonce);
+
+        let out = &r[header_len..];
+
+        let additional_data = additional_data(out.len() - CRYPTO_CHACHA20_POLY1305_TAG_LENGTH);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        buffer.extend_from_slice(out);
+
+        self.remote_chacha
+            .decrypt_in_place(nonce, &additional_data, &mut buffer)
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        Ok(buffer)
+    }
+}
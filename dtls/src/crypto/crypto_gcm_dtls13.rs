@@ -0,0 +1,161 @@
+// AES-GCM record protection of DTLS 1.3, records carry a unified header with
+// an encrypted sequence number and the content type moves into the
+// encrypted DTLSInnerPlaintext.
+// https://www.rfc-editor.org/rfc/rfc9147#section-4
+
+use std::io::BufWriter;
+
+use crate::content::*;
+use crate::error::*;
+use crate::record_layer::record_layer_unified_header::*;
+
+use aes::{Aes128, BlockCipher, NewBlockCipher};
+use aes_gcm::aead::{generic_array::GenericArray, AeadInPlace, NewAead};
+use aes_gcm::Aes128Gcm;
+
+const CRYPTO_GCM_DTLS13_TAG_LENGTH: usize = 16;
+const CRYPTO_GCM_DTLS13_SN_MASK_LENGTH: usize = 16;
+
+// Keys of one direction and epoch
+#[derive(Clone)]
+pub struct CryptoGcmDtls13 {
+    gcm: Aes128Gcm,
+    write_iv: Vec<u8>,
+    sn_cipher: Aes128,
+}
+
+impl CryptoGcmDtls13 {
+    pub fn new(write_key: &[u8], write_iv: &[u8], sn_key: &[u8]) -> Self {
+        CryptoGcmDtls13 {
+            gcm: Aes128Gcm::new(GenericArray::from_slice(write_key)),
+            write_iv: write_iv.to_vec(),
+            sn_cipher: Aes128::new(GenericArray::from_slice(sn_key)),
+        }
+    }
+
+    // The per-record nonce is the write IV xored with the 64 bit sequence number
+    fn nonce(&self, sequence_number: u64) -> Vec<u8> {
+        let mut nonce = self.write_iv.clone();
+        let offset = nonce.len() - 8;
+        for (n, s) in nonce[offset..]
+            .iter_mut()
+            .zip(sequence_number.to_be_bytes().iter())
+        {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc9147#section-4.2.3
+    fn sequence_number_mask(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < CRYPTO_GCM_DTLS13_SN_MASK_LENGTH {
+            return Err(Error::ErrInvalidPacketLength);
+        }
+        let mut block =
+            GenericArray::clone_from_slice(&ciphertext[..CRYPTO_GCM_DTLS13_SN_MASK_LENGTH]);
+        self.sn_cipher.encrypt_block(&mut block);
+        Ok(block.to_vec())
+    }
+
+    pub fn encrypt(
+        &self,
+        epoch: u16,
+        sequence_number: u64,
+        content_type: ContentType,
+        payload: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(payload.len() + 1 + CRYPTO_GCM_DTLS13_TAG_LENGTH);
+        buffer.extend_from_slice(payload);
+        buffer.push(content_type as u8);
+
+        let header = RecordLayerUnifiedHeader {
+            epoch,
+            sequence_number,
+            sequence_number_len: 2,
+            content_len: Some((buffer.len() + CRYPTO_GCM_DTLS13_TAG_LENGTH) as u16),
+        };
+        let mut r = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(r.as_mut());
+            header.marshal(&mut writer)?;
+        }
+
+        let nonce = self.nonce(sequence_number);
+        self.gcm
+            .encrypt_in_place(GenericArray::from_slice(&nonce), &r, &mut buffer)
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        let mask = self.sequence_number_mask(&buffer)?;
+        for (b, m) in r[1..1 + header.sequence_number_len].iter_mut().zip(mask) {
+            *b ^= m;
+        }
+
+        r.extend_from_slice(&buffer);
+        Ok(r)
+    }
+
+    // sequence_number_bits returns the low bits of the sequence number that
+    // were sent in the unified header at the start of r.
+    pub fn sequence_number_bits(&self, r: &[u8]) -> Result<RecordLayerUnifiedHeader> {
+        let mut reader = r;
+        let mut h = RecordLayerUnifiedHeader::unmarshal(&mut reader)?;
+        let ciphertext = &r[h.size()..];
+        let mask = self.sequence_number_mask(ciphertext)?;
+        if h.sequence_number_len == 2 {
+            h.sequence_number ^= u16::from_be_bytes([mask[0], mask[1]]) as u64;
+        } else {
+            h.sequence_number ^= mask[0] as u64;
+        }
+        Ok(h)
+    }
+
+    // decrypt opens the record with its full sequence number and returns the
+    // content and its type
+    pub fn decrypt(
+        &self,
+        h: &RecordLayerUnifiedHeader,
+        sequence_number: u64,
+        r: &[u8],
+    ) -> Result<(ContentType, Vec<u8>)> {
+        let header_len = h.size();
+        let ciphertext = match h.content_len {
+            Some(content_len) => {
+                if r.len() < header_len + content_len as usize {
+                    return Err(Error::ErrInvalidPacketLength);
+                }
+                &r[header_len..header_len + content_len as usize]
+            }
+            None => &r[header_len..],
+        };
+
+        let mut additional_data = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(additional_data.as_mut());
+            h.marshal(&mut writer)?;
+        }
+
+        let nonce = self.nonce(sequence_number);
+        let mut buffer = ciphertext.to_vec();
+        self.gcm
+            .decrypt_in_place(
+                GenericArray::from_slice(&nonce),
+                &additional_data,
+                &mut buffer,
+            )
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        // Strip the zero padding, the last non-zero byte is the content type
+        while let Some(&last) = buffer.last() {
+            if last != 0 {
+                break;
+            }
+            buffer.pop();
+        }
+        let content_type = match buffer.pop() {
+            Some(t) => ContentType::from(t),
+            None => return Err(Error::ErrInvalidContentType),
+        };
+
+        Ok((content_type, buffer))
+    }
+}
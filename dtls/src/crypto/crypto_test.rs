@@ -1,8 +1,12 @@
 use super::crypto_ccm::*;
+use super::crypto_chacha20_poly1305::*;
+use super::crypto_gcm::*;
 use super::*;
 
 use crate::content::ContentType;
-use crate::record_layer::record_layer_header::{ProtocolVersion, RECORD_LAYER_HEADER_SIZE};
+use crate::record_layer::record_layer_header::{
+    ProtocolVersion, PROTOCOL_VERSION1_2, RECORD_LAYER_HEADER_SIZE,
+};
 
 use std::io::Cursor;
 
@@ -154,6 +158,175 @@ fn test_ccm_encryption_and_decryption() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_aes_256_gcm_encryption_and_decryption() -> Result<()> {
+    let key: Vec<u8> = (0x00..0x20).collect();
+    let iv = vec![0x0e, 0xb2, 0x09, 0x06];
+
+    let gcm = CryptoGcm::new(&key, &iv, &key, &iv);
+
+    let rlh = RecordLayerHeader {
+        content_type: ContentType::ApplicationData,
+        protocol_version: PROTOCOL_VERSION1_2,
+        epoch: 1,
+        sequence_number: 18,
+        content_len: 3,
+    };
+
+    let raw = vec![
+        0x17, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x03, 0xff, 0xaa,
+        0xbb,
+    ];
+
+    let cipher_text = gcm.encrypt(&rlh, &raw)?;
+
+    // Explicit nonce, content and tag
+    assert_eq!(
+        [0, 27],
+        &cipher_text[RECORD_LAYER_HEADER_SIZE - 2..RECORD_LAYER_HEADER_SIZE],
+        "RecordLayer size updating failed"
+    );
+
+    let plain_text = gcm.decrypt(&cipher_text)?;
+    assert_eq!(
+        raw[RECORD_LAYER_HEADER_SIZE..],
+        plain_text[RECORD_LAYER_HEADER_SIZE..],
+        "Decryption failed"
+    );
+
+    // A 128-bit key does not open records sealed with the 256-bit one
+    let gcm128 = CryptoGcm::new(&key[..16], &iv, &key[..16], &iv);
+    assert!(gcm128.decrypt(&cipher_text).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_chacha20_poly1305_encryption_and_decryption() -> Result<()> {
+    let key: Vec<u8> = (0x00..0x20).collect();
+    let iv: Vec<u8> = (0x40..0x4c).collect();
+    let connection_id = vec![0xaa, 0xbb, 0xcc, 0xdd];
+
+    let chacha = CryptoChacha20Poly1305::new(&key, &iv, &key, &iv);
+
+    let rlh = RecordLayerHeader {
+        content_type: ContentType::ApplicationData,
+        protocol_version: PROTOCOL_VERSION1_2,
+        epoch: 1,
+        sequence_number: 18,
+        content_len: 3,
+    };
+
+    let raw = vec![
+        0x17, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x03, 0xff, 0xaa,
+        0xbb,
+    ];
+
+    let cipher_text = chacha.encrypt(&rlh, &raw)?;
+
+    // No explicit nonce is sent, only content and tag
+    assert_eq!(
+        [0, 19],
+        &cipher_text[RECORD_LAYER_HEADER_SIZE - 2..RECORD_LAYER_HEADER_SIZE],
+        "RecordLayer size updating failed"
+    );
+
+    let plain_text = chacha.decrypt(&cipher_text)?;
+    assert_eq!(
+        raw[RECORD_LAYER_HEADER_SIZE..],
+        plain_text[RECORD_LAYER_HEADER_SIZE..],
+        "Decryption failed"
+    );
+
+    // The nonce depends on the sequence number of the record
+    let mut replayed = cipher_text.clone();
+    replayed[RECORD_LAYER_HEADER_SIZE - 3] ^= 0x01;
+    assert!(chacha.decrypt(&replayed).is_err());
+
+    let cipher_text = chacha.encrypt_cid(&rlh, &connection_id, &raw)?;
+    let plain_text = chacha.decrypt_cid(&cipher_text, connection_id.len())?;
+    assert_eq!(raw, plain_text, "Decryption with connection ID failed");
+
+    Ok(())
+}
+
+#[test]
+fn test_connection_id_encryption_and_decryption() -> Result<()> {
+    let key = vec![
+        0x18, 0x78, 0xac, 0xc2, 0x2a, 0xd8, 0xbd, 0xd8, 0xc6, 0x01, 0xa6, 0x17, 0x12, 0x6f, 0x63,
+        0x54,
+    ];
+    let iv = vec![0x0e, 0xb2, 0x09, 0x06];
+    let connection_id = vec![0xaa, 0xbb, 0xcc, 0xdd];
+
+    let rlh = RecordLayerHeader {
+        content_type: ContentType::ApplicationData,
+        protocol_version: PROTOCOL_VERSION1_2,
+        epoch: 1,
+        sequence_number: 18,
+        content_len: 3,
+    };
+
+    let raw = vec![
+        0x17, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x03, 0xff, 0xaa,
+        0xbb,
+    ];
+
+    let gcm = CryptoGcm::new(&key, &iv, &key, &iv);
+    let ccm = CryptoCcm::new(&CryptoCcmTagLen::CryptoCcm8TagLength, &key, &iv, &key, &iv);
+    let cipher_texts = vec![
+        ("gcm", gcm.encrypt_cid(&rlh, &connection_id, &raw)?, 16),
+        ("ccm8", ccm.encrypt_cid(&rlh, &connection_id, &raw)?, 8),
+    ];
+
+    for (name, cipher_text, tag_len) in cipher_texts {
+        let header_len = RECORD_LAYER_HEADER_SIZE + connection_id.len();
+        assert_eq!(
+            ContentType::Tls12Cid as u8,
+            cipher_text[0],
+            "{} outer content type",
+            name
+        );
+        assert_eq!(
+            connection_id,
+            &cipher_text[RECORD_LAYER_HEADER_SIZE - 2..header_len - 2],
+            "{} connection ID",
+            name
+        );
+        // Explicit nonce, content, real content type and tag
+        let content_len = (8 + 3 + 1 + tag_len) as u16;
+        assert_eq!(
+            content_len.to_be_bytes(),
+            &cipher_text[header_len - 2..header_len],
+            "{} record length",
+            name
+        );
+
+        let decrypted = if name == "gcm" {
+            gcm.decrypt_cid(&cipher_text, connection_id.len())?
+        } else {
+            ccm.decrypt_cid(&cipher_text, connection_id.len())?
+        };
+        assert_eq!(raw, decrypted, "{} decryption failed", name);
+
+        // The connection ID is authenticated
+        let mut tampered = cipher_text.clone();
+        tampered[RECORD_LAYER_HEADER_SIZE - 2] ^= 0x01;
+        let result = if name == "gcm" {
+            gcm.decrypt_cid(&tampered, connection_id.len())
+        } else {
+            ccm.decrypt_cid(&tampered, connection_id.len())
+        };
+        assert!(
+            result.is_err(),
+            "{} accepted a modified connection ID",
+            name
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_certificate_verify() -> Result<()> {
     let plain_text: Vec<u8> = vec![
@@ -3,11 +3,15 @@ mod crypto_test;
 
 pub mod crypto_cbc;
 pub mod crypto_ccm;
+pub mod crypto_chacha20_poly1305;
 pub mod crypto_gcm;
+pub mod crypto_gcm_dtls13;
 pub mod padding;
 
+use crate::content::ContentType;
 use crate::curve::named_curve::*;
 use crate::error::*;
+use crate::record_layer::record_layer_cid_header::*;
 use crate::record_layer::record_layer_header::*;
 use crate::signature_hash_algorithm::{HashAlgorithm, SignatureAlgorithm, SignatureHashAlgorithm};
 
@@ -397,6 +401,22 @@ pub(crate) fn verify_certificate_verify(
     )
 }
 
+// DTLS 1.3 signs the transcript hash prefixed with a context string that
+// binds the signature to the role of the signer
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.4.3
+pub(crate) fn certificate_verify_content(transcript_hash: &[u8], is_client: bool) -> Vec<u8> {
+    let mut content = vec![0x20u8; 64];
+    if is_client {
+        content.extend_from_slice(b"TLS 1.3, client CertificateVerify");
+    } else {
+        content.extend_from_slice(b"TLS 1.3, server CertificateVerify");
+    }
+    content.push(0);
+    content.extend_from_slice(transcript_hash);
+
+    content
+}
+
 pub(crate) fn load_certs(raw_certificates: &[Vec<u8>]) -> Result<Vec<rustls::Certificate>> {
     if raw_certificates.is_empty() {
         return Err(Error::ErrLengthMismatch);
@@ -458,3 +478,25 @@ pub(crate) fn generate_aead_additional_data(h: &RecordLayerHeader, payload_len:
 
     additional_data
 }
+
+// Records with a connection ID authenticate the connection ID and the length
+// of the DTLSInnerPlaintext, the leading placeholder keeps the additional data
+// distinct from the one of records without connection ID.
+// https://www.rfc-editor.org/rfc/rfc9146#section-5
+pub(crate) fn generate_aead_additional_data_cid(
+    h: &RecordLayerCidHeader,
+    payload_len: usize,
+) -> Vec<u8> {
+    let mut additional_data = vec![0xffu8; 8];
+    additional_data.push(ContentType::Tls12Cid as u8);
+    additional_data.push(h.connection_id.len() as u8);
+    additional_data.push(ContentType::Tls12Cid as u8);
+    additional_data.push(h.protocol_version.major);
+    additional_data.push(h.protocol_version.minor);
+    additional_data.extend_from_slice(&h.epoch.to_be_bytes());
+    additional_data.extend_from_slice(&h.sequence_number.to_be_bytes()[2..]);
+    additional_data.extend_from_slice(&h.connection_id);
+    additional_data.extend_from_slice(&(payload_len as u16).to_be_bytes());
+
+    additional_data
+}
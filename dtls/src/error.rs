@@ -27,6 +27,8 @@ pub enum Error {
     ErrInvalidContentType,
     #[error("invalid mac")]
     ErrInvalidMac,
+    #[error("invalid DTLS 1.3 unified header")]
+    ErrInvalidUnifiedHeader,
     #[error("packet length and declared length do not match")]
     ErrInvalidPacketLength,
     #[error("export_keying_material can not be used with a reserved label")]
@@ -69,6 +71,10 @@ pub enum Error {
     ErrInvalidEllipticCurveType,
     #[error("invalid extension type")]
     ErrInvalidExtensionType,
+    #[error("connection ID must be at most 255 bytes")]
+    ErrInvalidConnectionIdLength,
+    #[error("server sent a connection ID the client did not ask for")]
+    ErrUnexpectedConnectionId,
     #[error("invalid hash algorithm")]
     ErrInvalidHashAlgorithm,
     #[error("invalid named curve")]
@@ -143,6 +149,28 @@ pub enum Error {
     ErrEmptyFragment,
     #[error("Alert is Fatal or Close Notify")]
     ErrAlertFatalOrClose,
+    #[error("session ID is longer than 32 bytes")]
+    ErrSessionIdTooLong,
+    #[error("session ticket key must be 32 bytes")]
+    ErrInvalidSessionTicketKey,
+    #[error("invalid or expired session ticket")]
+    ErrInvalidSessionTicket,
+    #[error("server resumed the session with other parameters")]
+    ErrResumedSessionMismatch,
+    #[error("no keys have been installed for the record epoch")]
+    ErrEpochKeysUnavailable,
+    #[error("server sent more than one HelloRetryRequest")]
+    ErrHelloRetryRequestRepeated,
+    #[error("client did not offer a key share for the selected group")]
+    ErrNoKeyShare,
+    #[error("DTLS 1.3 server negotiated DTLS 1.2, the handshake may have been downgraded")]
+    ErrDowngradeDetected,
+    #[error("key update is only supported by DTLS 1.3 connections")]
+    ErrKeyUpdateRequiresDtls13,
+    #[error("a key update is already in progress")]
+    ErrKeyUpdateInProgress,
+    #[error("invalid KeyUpdate request")]
+    ErrInvalidKeyUpdate,
 
     #[error(
         "Fragment buffer overflow. New size {new_size} is greater than specified max {max_size}"
@@ -0,0 +1,40 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_extension_connection_id() -> Result<()> {
+    let raw_connection_id = vec![0x00, 0x04, 0x03, 0x01, 0x02, 0x03];
+    let parsed_connection_id = ExtensionConnectionId {
+        cid: vec![0x01, 0x02, 0x03],
+    };
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        parsed_connection_id.marshal(&mut writer)?;
+    }
+
+    assert_eq!(
+        raw, raw_connection_id,
+        "extensionConnectionId marshal: got {:?}, want {:?}",
+        raw, raw_connection_id
+    );
+
+    let mut reader = BufReader::new(raw.as_slice());
+    let new_connection_id = ExtensionConnectionId::unmarshal(&mut reader)?;
+
+    assert_eq!(
+        new_connection_id, parsed_connection_id,
+        "extensionConnectionId unmarshal: got {:?}, want {:?}",
+        new_connection_id, parsed_connection_id
+    );
+
+    // An empty connection ID only announces support for the extension
+    let raw_empty = vec![0x00, 0x01, 0x00];
+    let mut reader = BufReader::new(raw_empty.as_slice());
+    let empty = ExtensionConnectionId::unmarshal(&mut reader)?;
+    assert!(empty.cid.is_empty(), "expected an empty connection ID");
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod extension_cookie_test;
+
+use super::*;
+
+// A DTLS 1.3 server sends a cookie in the HelloRetryRequest which the client
+// must echo in its second ClientHello, it replaces the HelloVerifyRequest
+// cookie exchange of DTLS 1.2.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.2.2
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionCookie {
+    pub(crate) cookie: Vec<u8>,
+}
+
+impl ExtensionCookie {
+    pub fn extension_value(&self) -> ExtensionValue {
+        ExtensionValue::Cookie
+    }
+
+    pub fn size(&self) -> usize {
+        2 + 2 + self.cookie.len()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<BigEndian>(2 + self.cookie.len() as u16)?;
+        writer.write_u16::<BigEndian>(self.cookie.len() as u16)?;
+        writer.write_all(&self.cookie)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let _ = reader.read_u16::<BigEndian>()?;
+
+        let cookie_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut cookie = vec![0u8; cookie_len];
+        reader.read_exact(&mut cookie)?;
+
+        Ok(ExtensionCookie { cookie })
+    }
+}
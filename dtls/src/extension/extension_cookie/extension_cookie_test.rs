@@ -0,0 +1,34 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_extension_cookie() -> Result<()> {
+    let raw_cookie = vec![0x00, 0x05, 0x00, 0x03, 0x01, 0x02, 0x03];
+    let parsed_cookie = ExtensionCookie {
+        cookie: vec![0x01, 0x02, 0x03],
+    };
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        parsed_cookie.marshal(&mut writer)?;
+    }
+
+    assert_eq!(
+        raw, raw_cookie,
+        "extensionCookie marshal: got {:?}, want {:?}",
+        raw, raw_cookie
+    );
+
+    let mut reader = BufReader::new(raw.as_slice());
+    let new_cookie = ExtensionCookie::unmarshal(&mut reader)?;
+
+    assert_eq!(
+        new_cookie, parsed_cookie,
+        "extensionCookie unmarshal: got {:?}, want {:?}",
+        new_cookie, parsed_cookie
+    );
+
+    Ok(())
+}
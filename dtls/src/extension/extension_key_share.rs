@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod extension_key_share_test;
+
+use super::*;
+use crate::curve::named_curve::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyShareEntry {
+    pub(crate) group: NamedCurve,
+    pub(crate) key_exchange: Vec<u8>,
+}
+
+impl KeyShareEntry {
+    fn size(&self) -> usize {
+        2 + 2 + self.key_exchange.len()
+    }
+
+    fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<BigEndian>(self.group as u16)?;
+        writer.write_u16::<BigEndian>(self.key_exchange.len() as u16)?;
+        writer.write_all(&self.key_exchange)?;
+        Ok(())
+    }
+
+    fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let group = reader.read_u16::<BigEndian>()?.into();
+        let key_exchange_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut key_exchange = vec![0u8; key_exchange_len];
+        reader.read_exact(&mut key_exchange)?;
+
+        Ok(KeyShareEntry {
+            group,
+            key_exchange,
+        })
+    }
+}
+
+// key_share carries the (EC)DHE shares of DTLS 1.3. A ClientHello offers a
+// share per group, the ServerHello answers with one share of the same group
+// and a HelloRetryRequest names the group the client should offer instead.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.2.8
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ExtensionKeyShare {
+    pub(crate) client_shares: Vec<KeyShareEntry>, // ClientHello
+    pub(crate) server_share: Option<KeyShareEntry>, // ServerHello
+    pub(crate) selected_group: Option<NamedCurve>, // HelloRetryRequest
+}
+
+impl ExtensionKeyShare {
+    pub fn extension_value(&self) -> ExtensionValue {
+        ExtensionValue::KeyShare
+    }
+
+    pub fn size(&self) -> usize {
+        if let Some(server_share) = &self.server_share {
+            2 + server_share.size()
+        } else if self.selected_group.is_some() {
+            2 + 2
+        } else {
+            2 + 2 + self.client_shares.iter().map(|e| e.size()).sum::<usize>()
+        }
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<BigEndian>(self.size() as u16 - 2)?;
+        if let Some(server_share) = &self.server_share {
+            server_share.marshal(writer)?;
+        } else if let Some(selected_group) = self.selected_group {
+            writer.write_u16::<BigEndian>(selected_group as u16)?;
+        } else {
+            writer.write_u16::<BigEndian>(self.size() as u16 - 4)?;
+            for e in &self.client_shares {
+                e.marshal(writer)?;
+            }
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let extension_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut data = vec![0u8; extension_len];
+        reader.read_exact(&mut data)?;
+
+        let mut ext = ExtensionKeyShare::default();
+        if extension_len == 2 {
+            ext.selected_group = Some(u16::from_be_bytes([data[0], data[1]]).into());
+            return Ok(ext);
+        }
+        if extension_len < 4 {
+            return Err(Error::ErrLengthMismatch);
+        }
+
+        // The three forms are told apart by their length, the client shares
+        // are prefixed with the length of the whole list.
+        let mut reader = &data[..];
+        if u16::from_be_bytes([data[0], data[1]]) as usize + 2 == extension_len {
+            let _ = reader.read_u16::<BigEndian>()?;
+            while !reader.is_empty() {
+                ext.client_shares
+                    .push(KeyShareEntry::unmarshal(&mut reader)?);
+            }
+        } else {
+            let server_share = KeyShareEntry::unmarshal(&mut reader)?;
+            if !reader.is_empty() {
+                return Err(Error::ErrLengthMismatch);
+            }
+            ext.server_share = Some(server_share);
+        }
+
+        Ok(ext)
+    }
+}
@@ -0,0 +1,73 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_extension_key_share() -> Result<()> {
+    let tests = vec![
+        (
+            "ClientHello",
+            vec![
+                0x00, 0x0c, 0x00, 0x0a, 0x00, 0x1d, 0x00, 0x02, 0x01, 0x02, 0x00, 0x17, 0x00, 0x00,
+            ],
+            ExtensionKeyShare {
+                client_shares: vec![
+                    KeyShareEntry {
+                        group: NamedCurve::X25519,
+                        key_exchange: vec![0x01, 0x02],
+                    },
+                    KeyShareEntry {
+                        group: NamedCurve::P256,
+                        key_exchange: vec![],
+                    },
+                ],
+                ..Default::default()
+            },
+        ),
+        (
+            "ServerHello",
+            vec![0x00, 0x07, 0x00, 0x1d, 0x00, 0x03, 0x01, 0x02, 0x03],
+            ExtensionKeyShare {
+                server_share: Some(KeyShareEntry {
+                    group: NamedCurve::X25519,
+                    key_exchange: vec![0x01, 0x02, 0x03],
+                }),
+                ..Default::default()
+            },
+        ),
+        (
+            "HelloRetryRequest",
+            vec![0x00, 0x02, 0x00, 0x17],
+            ExtensionKeyShare {
+                selected_group: Some(NamedCurve::P256),
+                ..Default::default()
+            },
+        ),
+    ];
+
+    for (name, raw_key_share, parsed_key_share) in tests {
+        let mut raw = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+            parsed_key_share.marshal(&mut writer)?;
+        }
+
+        assert_eq!(
+            raw, raw_key_share,
+            "{} extensionKeyShare marshal: got {:?}, want {:?}",
+            name, raw, raw_key_share
+        );
+        assert_eq!(raw.len(), parsed_key_share.size());
+
+        let mut reader = BufReader::new(raw.as_slice());
+        let new_key_share = ExtensionKeyShare::unmarshal(&mut reader)?;
+
+        assert_eq!(
+            new_key_share, parsed_key_share,
+            "{} extensionKeyShare unmarshal: got {:?}, want {:?}",
+            name, new_key_share, parsed_key_share
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod extension_session_ticket_test;
+
+use super::*;
+
+// SessionTicket carries the ticket of the session a client resumes, or is
+// empty to request one. A server includes it empty when it will send a
+// NewSessionTicket.
+// https://tools.ietf.org/html/rfc5077#section-3.2
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionSessionTicket {
+    pub(crate) ticket: Vec<u8>,
+}
+
+impl ExtensionSessionTicket {
+    pub fn extension_value(&self) -> ExtensionValue {
+        ExtensionValue::SessionTicket
+    }
+
+    pub fn size(&self) -> usize {
+        2 + self.ticket.len()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<BigEndian>(self.ticket.len() as u16)?;
+        writer.write_all(&self.ticket)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let ticket_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut ticket = vec![0u8; ticket_len];
+        reader.read_exact(&mut ticket)?;
+
+        Ok(ExtensionSessionTicket { ticket })
+    }
+}
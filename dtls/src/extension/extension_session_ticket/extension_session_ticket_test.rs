@@ -0,0 +1,40 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_extension_session_ticket() -> Result<()> {
+    let tests = vec![
+        (vec![0x00, 0x00], ExtensionSessionTicket { ticket: vec![] }),
+        (
+            vec![0x00, 0x03, 0x01, 0x02, 0x03],
+            ExtensionSessionTicket {
+                ticket: vec![0x01, 0x02, 0x03],
+            },
+        ),
+    ];
+
+    for (raw_extension, parsed_extension) in tests {
+        let mut raw = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+            parsed_extension.marshal(&mut writer)?;
+        }
+        assert_eq!(
+            raw, raw_extension,
+            "extension_session_ticket marshal: got {:?}, want {:?}",
+            raw, raw_extension
+        );
+        assert_eq!(parsed_extension.size(), raw_extension.len());
+
+        let mut reader = BufReader::new(raw.as_slice());
+        let new_extension = ExtensionSessionTicket::unmarshal(&mut reader)?;
+        assert_eq!(
+            new_extension, parsed_extension,
+            "extension_session_ticket unmarshal: got {:?}, want {:?}",
+            new_extension, parsed_extension
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod extension_supported_versions_test;
+
+use super::*;
+use crate::record_layer::record_layer_header::*;
+
+// supported_versions lists the versions a client supports in preference
+// order, the server puts the version it selected in the ServerHello or
+// HelloRetryRequest. DTLS 1.2 servers ignore the extension, which makes the
+// handshake fall back to DTLS 1.2.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.2.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionSupportedVersions {
+    pub(crate) supported_versions: Vec<ProtocolVersion>, // ClientHello
+    pub(crate) selected_version: Option<ProtocolVersion>, // ServerHello
+}
+
+impl ExtensionSupportedVersions {
+    pub fn extension_value(&self) -> ExtensionValue {
+        ExtensionValue::SupportedVersions
+    }
+
+    pub fn size(&self) -> usize {
+        if self.selected_version.is_some() {
+            2 + 2
+        } else {
+            2 + 1 + 2 * self.supported_versions.len()
+        }
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if let Some(selected_version) = &self.selected_version {
+            writer.write_u16::<BigEndian>(2)?;
+            writer.write_u8(selected_version.major)?;
+            writer.write_u8(selected_version.minor)?;
+        } else {
+            writer.write_u16::<BigEndian>(1 + 2 * self.supported_versions.len() as u16)?;
+            writer.write_u8(2 * self.supported_versions.len() as u8)?;
+            for v in &self.supported_versions {
+                writer.write_u8(v.major)?;
+                writer.write_u8(v.minor)?;
+            }
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let extension_len = reader.read_u16::<BigEndian>()? as usize;
+
+        // A ServerHello carries the single selected version, a ClientHello a
+        // length prefixed list which is always odd-sized.
+        if extension_len == 2 {
+            let major = reader.read_u8()?;
+            let minor = reader.read_u8()?;
+            return Ok(ExtensionSupportedVersions {
+                supported_versions: vec![],
+                selected_version: Some(ProtocolVersion { major, minor }),
+            });
+        }
+
+        let versions_len = reader.read_u8()? as usize;
+        if versions_len + 1 != extension_len || !versions_len.is_multiple_of(2) {
+            return Err(Error::ErrLengthMismatch);
+        }
+
+        let mut supported_versions = vec![];
+        for _ in 0..versions_len / 2 {
+            let major = reader.read_u8()?;
+            let minor = reader.read_u8()?;
+            supported_versions.push(ProtocolVersion { major, minor });
+        }
+
+        Ok(ExtensionSupportedVersions {
+            supported_versions,
+            selected_version: None,
+        })
+    }
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_extension_supported_versions() -> Result<()> {
+    let tests = vec![
+        (
+            "ClientHello",
+            vec![0x00, 0x05, 0x04, 0xfe, 0xfc, 0xfe, 0xfd],
+            ExtensionSupportedVersions {
+                supported_versions: vec![PROTOCOL_VERSION1_3, PROTOCOL_VERSION1_2],
+                selected_version: None,
+            },
+        ),
+        (
+            "ServerHello",
+            vec![0x00, 0x02, 0xfe, 0xfc],
+            ExtensionSupportedVersions {
+                supported_versions: vec![],
+                selected_version: Some(PROTOCOL_VERSION1_3),
+            },
+        ),
+    ];
+
+    for (name, raw_supported_versions, parsed_supported_versions) in tests {
+        let mut raw = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+            parsed_supported_versions.marshal(&mut writer)?;
+        }
+
+        assert_eq!(
+            raw, raw_supported_versions,
+            "{} extensionSupportedVersions marshal: got {:?}, want {:?}",
+            name, raw, raw_supported_versions
+        );
+        assert_eq!(raw.len(), parsed_supported_versions.size());
+
+        let mut reader = BufReader::new(raw.as_slice());
+        let new_supported_versions = ExtensionSupportedVersions::unmarshal(&mut reader)?;
+
+        assert_eq!(
+            new_supported_versions, parsed_supported_versions,
+            "{} extensionSupportedVersions unmarshal: got {:?}, want {:?}",
+            name, new_supported_versions, parsed_supported_versions
+        );
+    }
+
+    Ok(())
+}
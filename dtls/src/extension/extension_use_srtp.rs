@@ -10,6 +10,10 @@ use super::*;
 pub enum SrtpProtectionProfile {
     Srtp_Aes128_Cm_Hmac_Sha1_80 = 0x0001,
     Srtp_Aes128_Cm_Hmac_Sha1_32 = 0x0002,
+    // AES-256 counter mode profiles of draft-ietf-avt-srtp-big-aes, not
+    // registered by IANA but offered by some implementations
+    Srtp_Aes256_Cm_Hmac_Sha1_80 = 0x0003,
+    Srtp_Aes256_Cm_Hmac_Sha1_32 = 0x0004,
     Srtp_Aead_Aes_128_Gcm = 0x0007,
     Srtp_Aead_Aes_256_Gcm = 0x0008,
     Unsupported,
@@ -20,6 +24,8 @@ impl From<u16> for SrtpProtectionProfile {
         match val {
             0x0001 => SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80,
             0x0002 => SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32,
+            0x0003 => SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            0x0004 => SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_32,
             0x0007 => SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm,
             0x0008 => SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm,
             _ => SrtpProtectionProfile::Unsupported,
@@ -32,3 +32,34 @@ fn test_extension_use_srtp() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_extension_use_srtp_profiles() -> Result<()> {
+    let raw_use_srtp = vec![
+        0x00, 0x0f, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x07, 0x00,
+        0x08, 0x00,
+    ];
+    let parsed_use_srtp = ExtensionUseSrtp {
+        protection_profiles: vec![
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80,
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32,
+            SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_32,
+            SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm,
+            SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm,
+        ],
+    };
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        parsed_use_srtp.marshal(&mut writer)?;
+    }
+    assert_eq!(raw, raw_use_srtp, "extensionUseSRTP marshal");
+
+    let mut reader = BufReader::new(raw.as_slice());
+    let new_use_srtp = ExtensionUseSrtp::unmarshal(&mut reader)?;
+    assert_eq!(new_use_srtp, parsed_use_srtp, "extensionUseSRTP unmarshal");
+
+    Ok(())
+}
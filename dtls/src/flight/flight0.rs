@@ -1,11 +1,15 @@
 use super::flight2::*;
 use super::*;
+use crate::cipher_suite::cipher_suite_tls_aes_128_gcm_sha256::*;
 use crate::config::*;
 use crate::conn::*;
+use crate::curve::named_curve::*;
 use crate::error::Error;
 use crate::extension::*;
+use crate::handshake::handshake_message_client_hello::*;
 use crate::handshake::*;
 use crate::record_layer::record_layer_header::*;
+use crate::session::*;
 use crate::*;
 
 use async_trait::async_trait;
@@ -76,7 +80,25 @@ impl Flight for Flight0 {
 
             state.remote_random = client_hello.random.clone();
 
-            if let Ok(id) =
+            let mut client_connection_id = None;
+
+            // A client offering DTLS 1.3 gets a HelloRetryRequest instead of a
+            // HelloVerifyRequest, other clients fall back to DTLS 1.2
+            let dtls13_named_curve = find_dtls13_named_curve(cfg, client_hello);
+            state
+                .dtls13
+                .store(dtls13_named_curve.is_some(), Ordering::SeqCst);
+
+            // A server which could have negotiated DTLS 1.3 tells the client
+            // it falls back, so that a stripped supported_versions is noticed
+            if cfg.enable_dtls13 && dtls13_named_curve.is_none() {
+                state.local_random.set_downgrade_sentinel();
+            }
+
+            if dtls13_named_curve.is_some() {
+                let mut cs = state.cipher_suite.lock().await;
+                *cs = Some(Box::new(CipherSuiteTlsAes128GcmSha256::default()));
+            } else if let Ok(id) =
                 find_matching_cipher_suite(&client_hello.cipher_suites, &cfg.local_cipher_suites)
             {
                 if let Ok(cipher_suite) = cipher_suite_for_id(id) {
@@ -136,11 +158,19 @@ impl Flight for Flight0 {
                     Extension::ServerName(e) => {
                         state.server_name = e.server_name.clone(); // remote server name
                     }
+                    Extension::SessionTicket(_) if cfg.session_ticket_key.is_some() => {
+                        state.session_ticket_negotiated = true;
+                    }
+                    Extension::ConnectionId(e) => {
+                        client_connection_id = Some(e.cid.clone());
+                    }
                     _ => {}
                 }
             }
 
-            if cfg.extended_master_secret == ExtendedMasterSecretType::Require
+            // The DTLS 1.3 key schedule always covers the whole transcript
+            if dtls13_named_curve.is_none()
+                && cfg.extended_master_secret == ExtendedMasterSecretType::Require
                 && !state.extended_master_secret
             {
                 return Err((
@@ -152,6 +182,65 @@ impl Flight for Flight0 {
                 ));
             }
 
+            if let Some(session) = find_session(state, cfg, client_hello) {
+                // Resume the session with an abbreviated handshake
+                let cipher_suite = match cipher_suite_for_id(session.cipher_suite) {
+                    Ok(cipher_suite) => cipher_suite,
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::InternalError,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                };
+                {
+                    let mut cs = state.cipher_suite.lock().await;
+                    *cs = Some(cipher_suite);
+                }
+                state.master_secret = session.secret;
+                state.peer_certificates = session.peer_certificates;
+                state.session_id = client_hello.session_id.clone();
+                state.did_resume = true;
+            } else {
+                state.did_resume = false;
+                state.session_id = vec![];
+                if cfg.session_store.is_some() {
+                    state.session_id = vec![0; SESSION_ID_LENGTH];
+                    rand::thread_rng().fill(state.session_id.as_mut_slice());
+                }
+            }
+
+            // A DTLS 1.3 server echoes the legacy session ID of the client
+            if let Some(named_curve) = dtls13_named_curve {
+                state.named_curve = named_curve;
+                state.session_id = client_hello.session_id.clone();
+            }
+
+            // Connection IDs are only negotiated for the DTLS 1.2 AEAD cipher
+            // suites, and only if the client asks for them
+            // https://www.rfc-editor.org/rfc/rfc9146#section-3
+            let supports_connection_id = {
+                let cipher_suite = state.cipher_suite.lock().await;
+                match &*cipher_suite {
+                    Some(cipher_suite) => cipher_suite.supports_connection_id(),
+                    None => false,
+                }
+            };
+            let (local_connection_id, remote_connection_id) =
+                match (&cfg.connection_id_generator, client_connection_id) {
+                    (Some(generator), Some(cid))
+                        if supports_connection_id && dtls13_named_curve.is_none() =>
+                    {
+                        (Some(generator()), Some(cid))
+                    }
+                    _ => (None, None),
+                };
+            *state.local_connection_id.lock().await = local_connection_id;
+            *state.remote_connection_id.lock().await = remote_connection_id;
+
             if state.local_keypair.is_none() {
                 state.local_keypair = match state.named_curve.generate_keypair() {
                     Ok(local_keypar) => Some(local_keypar),
@@ -200,3 +289,99 @@ impl Flight for Flight0 {
         Ok(vec![])
     }
 }
+
+// find_session returns the session the client asks to resume with a ticket or
+// a session ID, if it can be resumed with the parameters of the ClientHello.
+fn find_session(
+    state: &State,
+    cfg: &HandshakeConfig,
+    client_hello: &HandshakeMessageClientHello,
+) -> Option<Session> {
+    // The client recognizes the resumption by the session ID echoed back,
+    // DTLS 1.3 does not resume sessions
+    if client_hello.session_id.is_empty() || state.dtls13.load(Ordering::SeqCst) {
+        return None;
+    }
+
+    let mut session = None;
+    if let Some(session_ticket_key) = &cfg.session_ticket_key {
+        for extension in &client_hello.extensions {
+            if let Extension::SessionTicket(e) = extension {
+                if !e.ticket.is_empty() {
+                    session = decrypt_session_ticket(session_ticket_key, &e.ticket).ok();
+                }
+            }
+        }
+    }
+    if session.is_none() {
+        if let Some(session_store) = &cfg.session_store {
+            session = session_store.get(&client_hello.session_id).ok().flatten();
+            if matches!(&session, Some(s) if s.is_expired()) {
+                let _ = session_store.del(&client_hello.session_id);
+                session = None;
+            }
+        }
+    }
+
+    match session {
+        Some(session)
+            if session.extended_master_secret == state.extended_master_secret
+                && client_hello.cipher_suites.contains(&session.cipher_suite)
+                && cfg.local_cipher_suites.contains(&session.cipher_suite) =>
+        {
+            Some(session)
+        }
+        _ => None,
+    }
+}
+
+// find_dtls13_named_curve returns the group of the DTLS 1.3 key exchange if
+// the client offers DTLS 1.3 and we accept it. The group of a key share sent
+// by the client is preferred.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.2.8
+fn find_dtls13_named_curve(
+    cfg: &HandshakeConfig,
+    client_hello: &HandshakeMessageClientHello,
+) -> Option<NamedCurve> {
+    let supported_named_curves = [NamedCurve::X25519, NamedCurve::P256, NamedCurve::P384];
+
+    if !cfg.enable_dtls13
+        || !client_hello
+            .cipher_suites
+            .contains(&CipherSuiteId::Tls_Aes_128_Gcm_Sha256)
+    {
+        return None;
+    }
+
+    let mut offers_dtls13 = false;
+    let mut key_share_named_curve = None;
+    let mut named_curve = None;
+    for extension in &client_hello.extensions {
+        match extension {
+            Extension::SupportedVersions(e) => {
+                offers_dtls13 = e.supported_versions.contains(&PROTOCOL_VERSION1_3);
+            }
+            Extension::KeyShare(e) => {
+                key_share_named_curve = e
+                    .client_shares
+                    .iter()
+                    .map(|s| s.group)
+                    .find(|g| supported_named_curves.contains(g));
+            }
+            Extension::SupportedEllipticCurves(e) => {
+                named_curve = e
+                    .elliptic_curves
+                    .iter()
+                    .copied()
+                    .find(|g| supported_named_curves.contains(g));
+            }
+            _ => {}
+        }
+    }
+
+    if offers_dtls13 {
+        key_share_named_curve.or(named_curve)
+    } else {
+        None
+    }
+}
@@ -1,15 +1,21 @@
 use super::flight3::*;
 use super::*;
+use crate::cipher_suite::*;
 use crate::compression_methods::*;
 use crate::config::*;
 use crate::conn::*;
 use crate::content::*;
 use crate::curve::named_curve::*;
 use crate::error::Error;
+use crate::extension::extension_connection_id::*;
+use crate::extension::extension_cookie::*;
+use crate::extension::extension_key_share::*;
 use crate::extension::extension_server_name::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_elliptic_curves::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_supported_signature_algorithms::*;
+use crate::extension::extension_supported_versions::*;
 use crate::extension::extension_use_extended_master_secret::*;
 use crate::extension::extension_use_srtp::*;
 use crate::extension::*;
@@ -17,9 +23,11 @@ use crate::handshake::handshake_message_client_hello::*;
 use crate::handshake::*;
 use crate::record_layer::record_layer_header::*;
 use crate::record_layer::*;
+use crate::session::*;
 
 use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
 use async_trait::async_trait;
+use rand::Rng;
 use std::fmt;
 use std::sync::atomic::Ordering;
 
@@ -129,6 +137,52 @@ impl Flight for Flight1 {
         state.cookie = vec![];
         state.local_random.populate();
 
+        state.dtls13.store(false, Ordering::SeqCst);
+        state.hello_retry_transcript = vec![];
+        if cfg.enable_dtls13 {
+            state.local_keypair = match state.named_curve.generate_keypair() {
+                Ok(local_keypair) => Some(local_keypair),
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+        }
+
+        // Offer the session cached for this server, a random session ID lets
+        // the client recognize a ticket the server accepted.
+        state.offered_session = None;
+        state.session_id = vec![];
+        if let Some(session_store) = &cfg.session_store {
+            if let Ok(Some(session)) = session_store.get(&cfg.session_key) {
+                if session.is_expired() {
+                    let _ = session_store.del(&cfg.session_key);
+                } else if cfg.local_cipher_suites.contains(&session.cipher_suite) {
+                    state.session_id = if session.id.is_empty() {
+                        let mut session_id = vec![0u8; SESSION_ID_LENGTH];
+                        rand::thread_rng().fill(session_id.as_mut_slice());
+                        session_id
+                    } else {
+                        session.id.clone()
+                    };
+                    state.offered_session = Some(session);
+                }
+            }
+        }
+
+        // Ask the server for a new connection ID, the ClientHello after a
+        // HelloVerifyRequest repeats it
+        *state.local_connection_id.lock().await = cfg
+            .connection_id_generator
+            .as_ref()
+            .map(|generator| generator());
+        *state.remote_connection_id.lock().await = None;
+
         let mut extensions = vec![
             Extension::SupportedSignatureAlgorithms(ExtensionSupportedSignatureAlgorithms {
                 signature_hash_algorithms: cfg.local_signature_schemes.clone(),
@@ -169,6 +223,24 @@ impl Flight for Flight1 {
             }));
         }
 
+        if cfg.session_store.is_some() {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: match &state.offered_session {
+                    Some(session) => session.ticket.clone(),
+                    None => vec![],
+                },
+            }));
+        }
+
+        if let Some(cid) = &*state.local_connection_id.lock().await {
+            extensions.push(Extension::ConnectionId(ExtensionConnectionId {
+                cid: cid.clone(),
+            }));
+        }
+
+        let mut cipher_suites = cfg.local_cipher_suites.clone();
+        offer_dtls13(state, cfg, &mut cipher_suites, &mut extensions);
+
         Ok(vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -177,9 +249,10 @@ impl Flight for Flight1 {
                     HandshakeMessageClientHello {
                         version: PROTOCOL_VERSION1_2,
                         random: state.local_random.clone(),
+                        session_id: state.session_id.clone(),
                         cookie: state.cookie.clone(),
 
-                        cipher_suites: cfg.local_cipher_suites.clone(),
+                        cipher_suites,
                         compression_methods: default_compression_methods(),
                         extensions,
                     },
@@ -190,3 +263,39 @@ impl Flight for Flight1 {
         }])
     }
 }
+
+// offer_dtls13 adds the DTLS 1.3 cipher suite and extensions to a
+// ClientHello. Servers without DTLS 1.3 support ignore them and continue
+// with DTLS 1.2.
+// https://www.rfc-editor.org/rfc/rfc9147#section-5.3
+pub(crate) fn offer_dtls13(
+    state: &State,
+    cfg: &HandshakeConfig,
+    cipher_suites: &mut Vec<CipherSuiteId>,
+    extensions: &mut Vec<Extension>,
+) {
+    let local_keypair = match &state.local_keypair {
+        Some(local_keypair) if cfg.enable_dtls13 => local_keypair,
+        _ => return,
+    };
+
+    cipher_suites.insert(0, CipherSuiteId::Tls_Aes_128_Gcm_Sha256);
+    extensions.push(Extension::SupportedVersions(ExtensionSupportedVersions {
+        supported_versions: vec![PROTOCOL_VERSION1_3, PROTOCOL_VERSION1_2],
+        selected_version: None,
+    }));
+    extensions.push(Extension::KeyShare(ExtensionKeyShare {
+        client_shares: vec![KeyShareEntry {
+            group: local_keypair.curve,
+            key_exchange: local_keypair.public_key.clone(),
+        }],
+        ..Default::default()
+    }));
+
+    // The cookie of a HelloRetryRequest is echoed in an extension
+    if !state.hello_retry_transcript.is_empty() && !state.cookie.is_empty() {
+        extensions.push(Extension::Cookie(ExtensionCookie {
+            cookie: state.cookie.clone(),
+        }));
+    }
+}
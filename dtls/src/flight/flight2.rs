@@ -1,14 +1,27 @@
 use super::flight0::*;
 use super::flight4::*;
+use super::flight4b::*;
+use super::flight4c::*;
 use super::*;
+use crate::cipher_suite::*;
+use crate::compression_methods::*;
 use crate::content::*;
 use crate::error::Error;
+use crate::extension::extension_cookie::*;
+use crate::extension::extension_key_share::*;
+use crate::extension::extension_supported_versions::*;
+use crate::extension::*;
+use crate::handshake::handshake_message_client_hello::*;
 use crate::handshake::handshake_message_hello_verify_request::*;
+use crate::handshake::handshake_message_server_hello::*;
+use crate::handshake::handshake_random::*;
 use crate::handshake::*;
+use crate::prf::*;
 use crate::record_layer::record_layer_header::*;
 
 use async_trait::async_trait;
 use std::fmt;
+use std::sync::atomic::Ordering;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight2;
@@ -79,6 +92,10 @@ impl Flight for Flight2 {
                 ));
             }
 
+            if state.dtls13.load(Ordering::SeqCst) {
+                return parse_dtls13_client_hello(state, client_hello);
+            }
+
             if client_hello.cookie.is_empty() {
                 return Err((None, None));
             }
@@ -93,7 +110,11 @@ impl Flight for Flight2 {
                 ));
             }
 
-            Ok(Box::new(Flight4 {}))
+            if state.did_resume {
+                Ok(Box::new(Flight4b {}))
+            } else {
+                Ok(Box::new(Flight4 {}))
+            }
         } else {
             Err((
                 Some(Alert {
@@ -108,10 +129,15 @@ impl Flight for Flight2 {
     async fn generate(
         &self,
         state: &mut State,
-        _cache: &HandshakeCache,
-        _cfg: &HandshakeConfig,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
     ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
         state.handshake_send_sequence = 0;
+
+        if state.dtls13.load(Ordering::SeqCst) {
+            return generate_hello_retry_request(state, cache, cfg).await;
+        }
+
         Ok(vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -128,3 +154,146 @@ impl Flight for Flight2 {
         }])
     }
 }
+
+// generate_hello_retry_request asks a client offering DTLS 1.3 to return a
+// cookie, the DTLS 1.3 counterpart of the HelloVerifyRequest. It also names
+// the group the client has to send a key share for.
+// https://www.rfc-editor.org/rfc/rfc9147#section-5.1
+async fn generate_hello_retry_request(
+    state: &mut State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
+    let hello_retry_request = HandshakeMessage::ServerHello(HandshakeMessageServerHello {
+        version: PROTOCOL_VERSION1_2,
+        random: HandshakeRandom::hello_retry_request(),
+        session_id: state.session_id.clone(),
+        cipher_suite: CipherSuiteId::Tls_Aes_128_Gcm_Sha256,
+        compression_method: default_compression_methods().ids[0],
+        extensions: vec![
+            Extension::SupportedVersions(ExtensionSupportedVersions {
+                supported_versions: vec![],
+                selected_version: Some(PROTOCOL_VERSION1_3),
+            }),
+            Extension::KeyShare(ExtensionKeyShare {
+                selected_group: Some(state.named_curve),
+                ..Default::default()
+            }),
+            Extension::Cookie(ExtensionCookie {
+                cookie: state.cookie.clone(),
+            }),
+        ],
+    });
+
+    let raw = match hello_retry_request.marshal_transcript() {
+        Ok(raw) => raw,
+        Err(err) => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ))
+        }
+    };
+    let client_hello = cache
+        .pull_and_merge_transcript(&[HandshakeCachePullRule {
+            typ: HandshakeType::ClientHello,
+            epoch: cfg.initial_epoch,
+            is_client: true,
+            optional: false,
+        }])
+        .await;
+    state.hello_retry_transcript = dtls13_hello_retry_transcript(state, &client_hello, &raw).await;
+
+    Ok(vec![Packet {
+        record: RecordLayer::new(
+            PROTOCOL_VERSION1_2,
+            0,
+            Content::Handshake(Handshake::new(hello_retry_request)),
+        ),
+        should_encrypt: false,
+        reset_local_sequence_number: false,
+    }])
+}
+
+// parse_dtls13_client_hello checks the ClientHello answering a
+// HelloRetryRequest and computes the shared secret of the key exchange.
+fn parse_dtls13_client_hello(
+    state: &mut State,
+    client_hello: &HandshakeMessageClientHello,
+) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+    let mut cookie = None;
+    let mut key_share = None;
+    for extension in &client_hello.extensions {
+        match extension {
+            Extension::Cookie(e) => cookie = Some(&e.cookie),
+            Extension::KeyShare(e) => key_share = Some(&e.client_shares),
+            _ => {}
+        }
+    }
+
+    match cookie {
+        Some(cookie) if *cookie == state.cookie => {}
+        Some(_) => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::AccessDenied,
+                }),
+                Some(Error::ErrCookieMismatch),
+            ))
+        }
+        None => return Err((None, None)),
+    }
+
+    let local_keypair = match &state.local_keypair {
+        Some(local_keypair) => local_keypair,
+        None => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                None,
+            ))
+        }
+    };
+
+    let entry = key_share
+        .into_iter()
+        .flatten()
+        .find(|e| e.group == local_keypair.curve);
+    let entry = match entry {
+        Some(entry) => entry,
+        None => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::IllegalParameter,
+                }),
+                Some(Error::ErrNoKeyShare),
+            ))
+        }
+    };
+
+    state.pre_master_secret = match prf_pre_master_secret(
+        &entry.key_exchange,
+        &local_keypair.private_key,
+        local_keypair.curve,
+    ) {
+        Ok(pre_master_secret) => pre_master_secret,
+        Err(err) => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::IllegalParameter,
+                }),
+                Some(err),
+            ))
+        }
+    };
+
+    Ok(Box::new(Flight4c {}))
+}
@@ -1,11 +1,18 @@
+use super::flight1::*;
+use super::flight4c::*;
 use super::flight5::*;
+use super::flight5b::*;
+use super::flight5c::*;
 use super::*;
+use crate::cipher_suite::*;
 use crate::compression_methods::*;
 use crate::config::*;
 use crate::content::*;
 use crate::curve::named_curve::*;
 use crate::error::Error;
+use crate::extension::extension_connection_id::*;
 use crate::extension::extension_server_name::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_elliptic_curves::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_supported_signature_algorithms::*;
@@ -13,19 +20,22 @@ use crate::extension::extension_use_extended_master_secret::*;
 use crate::extension::extension_use_srtp::*;
 use crate::extension::*;
 use crate::handshake::handshake_message_client_hello::*;
+use crate::handshake::handshake_message_server_hello::*;
 use crate::handshake::handshake_message_server_key_exchange::*;
 use crate::handshake::*;
 use crate::record_layer::record_layer_header::*;
 use crate::record_layer::*;
+use crate::session::*;
 
 use crate::cipher_suite::cipher_suite_for_id;
-use crate::prf::{prf_pre_master_secret, prf_psk_pre_master_secret};
+use crate::prf::{prf_pre_master_secret, prf_psk_pre_master_secret, prf_verify_data_server};
 use crate::{find_matching_cipher_suite, find_matching_srtp_profile};
 
 use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
 use async_trait::async_trait;
 use log::*;
 use std::fmt;
+use std::time::SystemTime;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight3;
@@ -40,7 +50,7 @@ impl fmt::Display for Flight3 {
 impl Flight for Flight3 {
     async fn parse(
         &self,
-        _tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        tx: &mut mpsc::Sender<mpsc::Sender<()>>,
         state: &mut State,
         cache: &HandshakeCache,
         cfg: &HandshakeConfig,
@@ -94,6 +104,59 @@ impl Flight for Flight3 {
             }
         }
 
+        // A DTLS 1.3 server starts with a HelloRetryRequest, its ServerHello
+        // selects DTLS 1.3 with the supported_versions extension.
+        if cfg.enable_dtls13 {
+            if let Ok((seq, msgs)) = cache
+                .full_pull_map(
+                    state.handshake_recv_sequence,
+                    &[HandshakeCachePullRule {
+                        typ: HandshakeType::ServerHello,
+                        epoch: cfg.initial_epoch,
+                        is_client: false,
+                        optional: false,
+                    }],
+                )
+                .await
+            {
+                if let Some(HandshakeMessage::ServerHello(h)) =
+                    msgs.get(&HandshakeType::ServerHello)
+                {
+                    if h.random.is_hello_retry_request() {
+                        return handle_hello_retry_request(state, cache, cfg, h, seq).await;
+                    }
+                    if selected_version(h) == Some(PROTOCOL_VERSION1_3) {
+                        return handle_dtls13_server_hello(tx, state, cache, cfg, h).await;
+                    }
+                }
+            }
+        }
+
+        // A server resuming the offered session echoes its session ID and
+        // continues with ChangeCipherSpec and Finished right away.
+        if let Some(session) = state.offered_session.clone() {
+            if let Ok((_, msgs)) = cache
+                .full_pull_map(
+                    state.handshake_recv_sequence,
+                    &[HandshakeCachePullRule {
+                        typ: HandshakeType::ServerHello,
+                        epoch: cfg.initial_epoch,
+                        is_client: false,
+                        optional: false,
+                    }],
+                )
+                .await
+            {
+                if let Some(HandshakeMessage::ServerHello(h)) =
+                    msgs.get(&HandshakeType::ServerHello)
+                {
+                    if !h.session_id.is_empty() && h.session_id == state.session_id {
+                        return handle_resumed_session(tx, state, cache, cfg, h, &session).await;
+                    }
+                }
+            }
+        }
+
         let result = if cfg.local_psk_callback.is_some() {
             cache
                 .full_pull_map(
@@ -181,112 +244,7 @@ impl Flight for Flight3 {
                 }
             };
 
-            if h.version != PROTOCOL_VERSION1_2 {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::ProtocolVersion,
-                    }),
-                    Some(Error::ErrUnsupportedProtocolVersion),
-                ));
-            }
-
-            for extension in &h.extensions {
-                match extension {
-                    Extension::UseSrtp(e) => {
-                        let profile = match find_matching_srtp_profile(
-                            &e.protection_profiles,
-                            &cfg.local_srtp_protection_profiles,
-                        ) {
-                            Ok(profile) => profile,
-                            Err(_) => {
-                                return Err((
-                                    Some(Alert {
-                                        alert_level: AlertLevel::Fatal,
-                                        alert_description: AlertDescription::IllegalParameter,
-                                    }),
-                                    Some(Error::ErrClientNoMatchingSrtpProfile),
-                                ))
-                            }
-                        };
-                        state.srtp_protection_profile = profile;
-                    }
-                    Extension::UseExtendedMasterSecret(_) => {
-                        if cfg.extended_master_secret != ExtendedMasterSecretType::Disable {
-                            state.extended_master_secret = true;
-                        }
-                    }
-                    _ => {}
-                };
-            }
-
-            if cfg.extended_master_secret == ExtendedMasterSecretType::Require
-                && !state.extended_master_secret
-            {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrClientRequiredButNoServerEms),
-                ));
-            }
-            if !cfg.local_srtp_protection_profiles.is_empty()
-                && state.srtp_protection_profile == SrtpProtectionProfile::Unsupported
-            {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrRequestedButNoSrtpExtension),
-                ));
-            }
-            if find_matching_cipher_suite(&[h.cipher_suite], &cfg.local_cipher_suites).is_err() {
-                debug!(
-                    "[handshake:{}] use cipher suite: {}",
-                    srv_cli_str(state.is_client),
-                    h.cipher_suite
-                );
-
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrCipherSuiteNoIntersection),
-                ));
-            }
-
-            let cipher_suite = match cipher_suite_for_id(h.cipher_suite) {
-                Ok(cipher_suite) => cipher_suite,
-                Err(_) => {
-                    debug!(
-                        "[handshake:{}] use cipher suite: {}",
-                        srv_cli_str(state.is_client),
-                        h.cipher_suite
-                    );
-
-                    return Err((
-                        Some(Alert {
-                            alert_level: AlertLevel::Fatal,
-                            alert_description: AlertDescription::InsufficientSecurity,
-                        }),
-                        Some(Error::ErrInvalidCipherSuite),
-                    ));
-                }
-            };
-
-            trace!(
-                "[handshake:{}] use cipher suite: {}",
-                srv_cli_str(state.is_client),
-                cipher_suite.to_string()
-            );
-            {
-                let mut cs = state.cipher_suite.lock().await;
-                *cs = Some(cipher_suite);
-            }
-            state.remote_random = h.random.clone();
+            handle_server_hello(state, cfg, h).await?;
         }
 
         if let Some(message) = msgs.get(&HandshakeType::Certificate) {
@@ -389,6 +347,24 @@ impl Flight for Flight3 {
             }));
         }
 
+        if cfg.session_store.is_some() {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: match &state.offered_session {
+                    Some(session) => session.ticket.clone(),
+                    None => vec![],
+                },
+            }));
+        }
+
+        if let Some(cid) = &*state.local_connection_id.lock().await {
+            extensions.push(Extension::ConnectionId(ExtensionConnectionId {
+                cid: cid.clone(),
+            }));
+        }
+
+        let mut cipher_suites = cfg.local_cipher_suites.clone();
+        offer_dtls13(state, cfg, &mut cipher_suites, &mut extensions);
+
         Ok(vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -397,9 +373,15 @@ impl Flight for Flight3 {
                     HandshakeMessageClientHello {
                         version: PROTOCOL_VERSION1_2,
                         random: state.local_random.clone(),
-                        cookie: state.cookie.clone(),
+                        session_id: state.session_id.clone(),
+                        // The cookie of a HelloRetryRequest is sent in an extension instead
+                        cookie: if state.hello_retry_transcript.is_empty() {
+                            state.cookie.clone()
+                        } else {
+                            vec![]
+                        },
 
-                        cipher_suites: cfg.local_cipher_suites.clone(),
+                        cipher_suites,
                         compression_methods: default_compression_methods(),
                         extensions,
                     },
@@ -411,6 +393,433 @@ impl Flight for Flight3 {
     }
 }
 
+fn selected_version(h: &HandshakeMessageServerHello) -> Option<ProtocolVersion> {
+    h.extensions.iter().find_map(|extension| match extension {
+        Extension::SupportedVersions(e) => e.selected_version,
+        _ => None,
+    })
+}
+
+// handle_hello_retry_request answers the HelloRetryRequest of a DTLS 1.3
+// server, the next ClientHello returns its cookie and a key share of the
+// group it selected.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.1.4
+async fn handle_hello_retry_request(
+    state: &mut State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageServerHello,
+    seq: isize,
+) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+    if !state.hello_retry_transcript.is_empty() {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::UnexpectedMessage,
+            }),
+            Some(Error::ErrHelloRetryRequestRepeated),
+        ));
+    }
+
+    if selected_version(h) != Some(PROTOCOL_VERSION1_3) {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::ProtocolVersion,
+            }),
+            Some(Error::ErrUnsupportedProtocolVersion),
+        ));
+    }
+    if h.cipher_suite != CipherSuiteId::Tls_Aes_128_Gcm_Sha256 {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::IllegalParameter,
+            }),
+            Some(Error::ErrInvalidCipherSuite),
+        ));
+    }
+
+    state.cookie = vec![];
+    for extension in &h.extensions {
+        match extension {
+            Extension::Cookie(e) => state.cookie = e.cookie.clone(),
+            Extension::KeyShare(e) => {
+                let selected_group = match e.selected_group {
+                    Some(selected_group) => selected_group,
+                    None => continue,
+                };
+                if state.local_keypair.as_ref().map(|k| k.curve) == Some(selected_group) {
+                    continue;
+                }
+
+                let local_keypair = match selected_group {
+                    NamedCurve::X25519 | NamedCurve::P256 | NamedCurve::P384 => {
+                        selected_group.generate_keypair()
+                    }
+                    _ => Err(Error::ErrInvalidNamedCurve),
+                };
+                state.local_keypair = match local_keypair {
+                    Ok(local_keypair) => Some(local_keypair),
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::IllegalParameter,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                };
+                state.named_curve = selected_group;
+            }
+            _ => {}
+        }
+    }
+
+    let client_hello = cache
+        .pull_and_merge_transcript(&[HandshakeCachePullRule {
+            typ: HandshakeType::ClientHello,
+            epoch: cfg.initial_epoch,
+            is_client: true,
+            optional: false,
+        }])
+        .await;
+    let hello_retry_request = cache
+        .pull_and_merge_transcript(&[HandshakeCachePullRule {
+            typ: HandshakeType::ServerHello,
+            epoch: cfg.initial_epoch,
+            is_client: false,
+            optional: false,
+        }])
+        .await;
+    state.hello_retry_transcript =
+        dtls13_hello_retry_transcript(state, &client_hello, &hello_retry_request).await;
+
+    state.handshake_recv_sequence = seq;
+    Ok(Box::new(Flight3 {}) as Box<dyn Flight + Send + Sync>)
+}
+
+pub(crate) async fn handle_server_hello(
+    state: &mut State,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageServerHello,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    if h.version != PROTOCOL_VERSION1_2 {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::ProtocolVersion,
+            }),
+            Some(Error::ErrUnsupportedProtocolVersion),
+        ));
+    }
+
+    // We offered DTLS 1.3, a DTLS 1.3 server would have selected it
+    // https://www.rfc-editor.org/rfc/rfc8446#section-4.1.3
+    if cfg.enable_dtls13 && h.random.is_downgrade_sentinel() {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::IllegalParameter,
+            }),
+            Some(Error::ErrDowngradeDetected),
+        ));
+    }
+
+    for extension in &h.extensions {
+        match extension {
+            Extension::UseSrtp(e) => {
+                let profile = match find_matching_srtp_profile(
+                    &e.protection_profiles,
+                    &cfg.local_srtp_protection_profiles,
+                ) {
+                    Ok(profile) => profile,
+                    Err(_) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::IllegalParameter,
+                            }),
+                            Some(Error::ErrClientNoMatchingSrtpProfile),
+                        ))
+                    }
+                };
+                state.srtp_protection_profile = profile;
+            }
+            Extension::UseExtendedMasterSecret(_) => {
+                if cfg.extended_master_secret != ExtendedMasterSecretType::Disable {
+                    state.extended_master_secret = true;
+                }
+            }
+            Extension::SessionTicket(_) if cfg.session_store.is_some() => {
+                state.session_ticket_negotiated = true;
+            }
+            Extension::ConnectionId(e) => {
+                if state.local_connection_id.lock().await.is_none() {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::UnsupportedExtension,
+                        }),
+                        Some(Error::ErrUnexpectedConnectionId),
+                    ));
+                }
+                *state.remote_connection_id.lock().await = Some(e.cid.clone());
+            }
+            _ => {}
+        };
+    }
+
+    if cfg.extended_master_secret == ExtendedMasterSecretType::Require
+        && !state.extended_master_secret
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrClientRequiredButNoServerEms),
+        ));
+    }
+    if !cfg.local_srtp_protection_profiles.is_empty()
+        && state.srtp_protection_profile == SrtpProtectionProfile::Unsupported
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrRequestedButNoSrtpExtension),
+        ));
+    }
+    if find_matching_cipher_suite(&[h.cipher_suite], &cfg.local_cipher_suites).is_err() {
+        debug!(
+            "[handshake:{}] use cipher suite: {}",
+            srv_cli_str(state.is_client),
+            h.cipher_suite
+        );
+
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrCipherSuiteNoIntersection),
+        ));
+    }
+
+    let cipher_suite = match cipher_suite_for_id(h.cipher_suite) {
+        Ok(cipher_suite) => cipher_suite,
+        Err(_) => {
+            debug!(
+                "[handshake:{}] use cipher suite: {}",
+                srv_cli_str(state.is_client),
+                h.cipher_suite
+            );
+
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InsufficientSecurity,
+                }),
+                Some(Error::ErrInvalidCipherSuite),
+            ));
+        }
+    };
+
+    trace!(
+        "[handshake:{}] use cipher suite: {}",
+        srv_cli_str(state.is_client),
+        cipher_suite.to_string()
+    );
+    {
+        let mut cs = state.cipher_suite.lock().await;
+        *cs = Some(cipher_suite);
+    }
+    state.remote_random = h.random.clone();
+    state.session_id = h.session_id.clone();
+
+    Ok(())
+}
+
+async fn handle_resumed_session(
+    tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+    state: &mut State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageServerHello,
+    session: &Session,
+) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+    let initialized = {
+        let cipher_suite = state.cipher_suite.lock().await;
+        match &*cipher_suite {
+            Some(cipher_suite) => cipher_suite.is_initialized(),
+            None => false,
+        }
+    };
+
+    if !initialized {
+        handle_server_hello(state, cfg, h).await?;
+
+        if h.cipher_suite != session.cipher_suite
+            || state.extended_master_secret != session.extended_master_secret
+        {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::IllegalParameter,
+                }),
+                Some(Error::ErrResumedSessionMismatch),
+            ));
+        }
+
+        state.master_secret = session.secret.clone();
+        state.peer_certificates = session.peer_certificates.clone();
+        if let Err(err) = state.init_cipher_suite().await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+    }
+
+    // Now, encrypted packets can be handled
+    let (done_tx, mut done_rx) = mpsc::channel(1);
+    if let Err(err) = tx.send(done_tx).await {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(Error::Other(err.to_string())),
+        ));
+    }
+
+    done_rx.recv().await;
+
+    let (seq, msgs) = match cache
+        .full_pull_map(
+            state.handshake_recv_sequence,
+            &[
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ServerHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::NewSessionTicket,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: true,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: false,
+                    optional: false,
+                },
+            ],
+        )
+        .await
+    {
+        Ok((seq, msgs)) => (seq, msgs),
+        // No valid message received. Keep reading
+        Err(_) => return Err((None, None)),
+    };
+
+    let finished = if let Some(HandshakeMessage::Finished(h)) = msgs.get(&HandshakeType::Finished) {
+        h
+    } else {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            None,
+        ));
+    };
+
+    let plain_text = cache
+        .pull_and_merge(&[
+            HandshakeCachePullRule {
+                typ: HandshakeType::ClientHello,
+                epoch: cfg.initial_epoch,
+                is_client: true,
+                optional: false,
+            },
+            HandshakeCachePullRule {
+                typ: HandshakeType::ServerHello,
+                epoch: cfg.initial_epoch,
+                is_client: false,
+                optional: false,
+            },
+            HandshakeCachePullRule {
+                typ: HandshakeType::NewSessionTicket,
+                epoch: cfg.initial_epoch,
+                is_client: false,
+                optional: true,
+            },
+        ])
+        .await;
+
+    {
+        let cipher_suite = state.cipher_suite.lock().await;
+        if let Some(cipher_suite) = &*cipher_suite {
+            let expected_verify_data = match prf_verify_data_server(
+                &state.master_secret,
+                &plain_text,
+                cipher_suite.hash_func(),
+            ) {
+                Ok(d) => d,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InsufficientSecurity,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+
+            if expected_verify_data != finished.verify_data {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::HandshakeFailure,
+                    }),
+                    Some(Error::ErrVerifyDataMismatch),
+                ));
+            }
+        }
+    }
+
+    state.handshake_recv_sequence = seq;
+    state.did_resume = true;
+
+    // Keep the renewed ticket for the next resumption
+    if let Some(HandshakeMessage::NewSessionTicket(t)) = msgs.get(&HandshakeType::NewSessionTicket)
+    {
+        if let Some(session_store) = &cfg.session_store {
+            let _ = session_store.set(
+                &cfg.session_key,
+                Session {
+                    ticket: t.ticket.clone(),
+                    issued_at: SystemTime::now(),
+                    ..session.clone()
+                },
+            );
+        }
+    }
+
+    Ok(Box::new(Flight5b {}) as Box<dyn Flight + Send + Sync>)
+}
+
 pub(crate) fn handle_server_key_exchange(
     state: &mut State,
     cfg: &HandshakeConfig,
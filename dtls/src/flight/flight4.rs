@@ -9,6 +9,8 @@ use crate::crypto::*;
 use crate::curve::named_curve::*;
 use crate::curve::*;
 use crate::error::Error;
+use crate::extension::extension_connection_id::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_elliptic_curves::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_use_extended_master_secret::*;
@@ -23,6 +25,7 @@ use crate::handshake::*;
 use crate::prf::*;
 use crate::record_layer::record_layer_header::*;
 use crate::record_layer::*;
+use crate::session::*;
 use crate::signature_hash_algorithm::*;
 
 use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
@@ -30,6 +33,7 @@ use async_trait::async_trait;
 use log::*;
 use std::fmt;
 use std::io::BufWriter;
+use std::time::SystemTime;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight4;
@@ -485,9 +489,17 @@ impl Flight for Flight4 {
                     ));
                 }
             }
-            ClientAuthType::NoClientCert | ClientAuthType::RequestClientCert => {
-                return Ok(Box::new(Flight6 {}) as Box<dyn Flight + Send + Sync>);
-            }
+            ClientAuthType::NoClientCert | ClientAuthType::RequestClientCert => {}
+        }
+
+        if let Err(err) = save_server_session(state, cfg).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
         }
 
         Ok(Box::new(Flight6 {}) as Box<dyn Flight + Send + Sync>)
@@ -528,6 +540,18 @@ impl Flight for Flight4 {
             ]);
         }
 
+        if state.session_ticket_negotiated {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: vec![],
+            }));
+        }
+
+        if let Some(cid) = &*state.local_connection_id.lock().await {
+            extensions.push(Extension::ConnectionId(ExtensionConnectionId {
+                cid: cid.clone(),
+            }));
+        }
+
         let mut pkts = vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -536,6 +560,7 @@ impl Flight for Flight4 {
                     HandshakeMessageServerHello {
                         version: PROTOCOL_VERSION1_2,
                         random: state.local_random.clone(),
+                        session_id: state.session_id.clone(),
                         cipher_suite: {
                             let cipher_suite = state.cipher_suite.lock().await;
                             if let Some(cipher_suite) = &*cipher_suite {
@@ -722,6 +747,36 @@ impl Flight for Flight4 {
     }
 }
 
+// save_server_session stores the session under the session ID handed to the
+// client, sessions resumed with tickets need no server state.
+async fn save_server_session(state: &State, cfg: &HandshakeConfig) -> Result<(), Error> {
+    let session_store = match &cfg.session_store {
+        Some(session_store) if !state.session_id.is_empty() => session_store,
+        _ => return Ok(()),
+    };
+
+    let cipher_suite = {
+        let cipher_suite = state.cipher_suite.lock().await;
+        match &*cipher_suite {
+            Some(cipher_suite) => cipher_suite.id(),
+            None => return Err(Error::ErrCipherSuiteUnset),
+        }
+    };
+
+    session_store.set(
+        &state.session_id,
+        Session {
+            id: state.session_id.clone(),
+            secret: state.master_secret.clone(),
+            cipher_suite,
+            extended_master_secret: state.extended_master_secret,
+            ticket: vec![],
+            peer_certificates: state.peer_certificates.clone(),
+            issued_at: SystemTime::now(),
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
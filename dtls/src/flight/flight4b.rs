@@ -0,0 +1,319 @@
+use super::flight6::*;
+use super::*;
+use crate::change_cipher_spec::*;
+use crate::cipher_suite::*;
+use crate::compression_methods::*;
+use crate::config::*;
+use crate::content::*;
+use crate::extension::extension_connection_id::*;
+use crate::extension::extension_session_ticket::*;
+use crate::extension::extension_use_extended_master_secret::*;
+use crate::extension::extension_use_srtp::*;
+use crate::extension::*;
+use crate::handshake::handshake_message_finished::*;
+use crate::handshake::handshake_message_server_hello::*;
+use crate::handshake::*;
+use crate::prf::*;
+use crate::record_layer::record_layer_header::*;
+
+use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
+use async_trait::async_trait;
+use std::fmt;
+
+// Flight4b is the flight of a server resuming a session, the ServerHello
+// echoing the session ID is directly followed by [NewSessionTicket],
+// ChangeCipherSpec and Finished.
+// https://tools.ietf.org/html/rfc5246#section-7.3
+// https://tools.ietf.org/html/rfc5077#section-3.1
+#[derive(Debug, PartialEq)]
+pub(crate) struct Flight4b;
+
+impl fmt::Display for Flight4b {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flight 4b")
+    }
+}
+
+#[async_trait]
+impl Flight for Flight4b {
+    fn is_last_recv_flight(&self) -> bool {
+        true
+    }
+
+    async fn parse(
+        &self,
+        tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+        // Now, encrypted packets can be handled
+        let (done_tx, mut done_rx) = mpsc::channel(1);
+        if let Err(err) = tx.send(done_tx).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(Error::Other(err.to_string())),
+            ));
+        }
+
+        done_rx.recv().await;
+
+        let (_, msgs) = match cache
+            .full_pull_map(
+                state.handshake_recv_sequence,
+                &[HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: true,
+                    optional: false,
+                }],
+            )
+            .await
+        {
+            Ok((seq, msgs)) => (seq, msgs),
+            // No valid message received. Keep reading
+            Err(_) => return Err((None, None)),
+        };
+
+        let finished =
+            if let Some(HandshakeMessage::Finished(h)) = msgs.get(&HandshakeType::Finished) {
+                h
+            } else {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ));
+            };
+
+        let plain_text = cache
+            .pull_and_merge(&[
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ClientHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: true,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ServerHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::NewSessionTicket,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: true,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: false,
+                    optional: false,
+                },
+            ])
+            .await;
+
+        let cipher_suite = state.cipher_suite.lock().await;
+        if let Some(cipher_suite) = &*cipher_suite {
+            let expected_verify_data = match prf_verify_data_client(
+                &state.master_secret,
+                &plain_text,
+                cipher_suite.hash_func(),
+            ) {
+                Ok(d) => d,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InsufficientSecurity,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+
+            if expected_verify_data != finished.verify_data {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::HandshakeFailure,
+                    }),
+                    Some(Error::ErrVerifyDataMismatch),
+                ));
+            }
+        }
+
+        Ok(Box::new(Flight4b {}))
+    }
+
+    async fn generate(
+        &self,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
+        let mut extensions = vec![Extension::RenegotiationInfo(ExtensionRenegotiationInfo {
+            renegotiated_connection: 0,
+        })];
+        if (cfg.extended_master_secret == ExtendedMasterSecretType::Request
+            || cfg.extended_master_secret == ExtendedMasterSecretType::Require)
+            && state.extended_master_secret
+        {
+            extensions.push(Extension::UseExtendedMasterSecret(
+                ExtensionUseExtendedMasterSecret { supported: true },
+            ));
+        }
+
+        if state.srtp_protection_profile != SrtpProtectionProfile::Unsupported {
+            extensions.push(Extension::UseSrtp(ExtensionUseSrtp {
+                protection_profiles: vec![state.srtp_protection_profile],
+            }));
+        }
+
+        if state.session_ticket_negotiated {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: vec![],
+            }));
+        }
+
+        if let Some(cid) = &*state.local_connection_id.lock().await {
+            extensions.push(Extension::ConnectionId(ExtensionConnectionId {
+                cid: cid.clone(),
+            }));
+        }
+
+        if let Err(err) = state.init_cipher_suite().await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+
+        let cipher_suite_id = {
+            let cipher_suite = state.cipher_suite.lock().await;
+            if let Some(cipher_suite) = &*cipher_suite {
+                cipher_suite.id()
+            } else {
+                CipherSuiteId::Unsupported
+            }
+        };
+
+        let mut pkts = vec![Packet {
+            record: RecordLayer::new(
+                PROTOCOL_VERSION1_2,
+                0,
+                Content::Handshake(Handshake::new(HandshakeMessage::ServerHello(
+                    HandshakeMessageServerHello {
+                        version: PROTOCOL_VERSION1_2,
+                        random: state.local_random.clone(),
+                        session_id: state.session_id.clone(),
+                        cipher_suite: cipher_suite_id,
+                        compression_method: default_compression_methods().ids[0],
+                        extensions,
+                    },
+                ))),
+            ),
+            should_encrypt: false,
+            reset_local_sequence_number: false,
+        }];
+
+        if state.session_ticket_negotiated {
+            match new_session_ticket(state, cfg).await {
+                Ok(p) => pkts.push(p),
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ))
+                }
+            }
+        }
+
+        if state.local_verify_data.is_empty() {
+            let mut plain_text = cache
+                .pull_and_merge(&[HandshakeCachePullRule {
+                    typ: HandshakeType::ClientHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: true,
+                    optional: false,
+                }])
+                .await;
+
+            // ServerHello and NewSessionTicket are not sent yet, marshal them
+            // with the sequence numbers they will be sent with.
+            for (seq_pred, p) in (state.handshake_send_sequence..).zip(pkts.iter()) {
+                match marshal_predicted(p, seq_pred) {
+                    Ok(raw) => plain_text.extend_from_slice(&raw),
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::InternalError,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                }
+            }
+
+            let cipher_suite = state.cipher_suite.lock().await;
+            if let Some(cipher_suite) = &*cipher_suite {
+                state.local_verify_data = match prf_verify_data_server(
+                    &state.master_secret,
+                    &plain_text,
+                    cipher_suite.hash_func(),
+                ) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::InternalError,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                };
+            }
+        }
+
+        pkts.push(Packet {
+            record: RecordLayer::new(
+                PROTOCOL_VERSION1_2,
+                0,
+                Content::ChangeCipherSpec(ChangeCipherSpec {}),
+            ),
+            should_encrypt: false,
+            reset_local_sequence_number: false,
+        });
+
+        pkts.push(Packet {
+            record: RecordLayer::new(
+                PROTOCOL_VERSION1_2,
+                1,
+                Content::Handshake(Handshake::new(HandshakeMessage::Finished(
+                    HandshakeMessageFinished {
+                        verify_data: state.local_verify_data.clone(),
+                    },
+                ))),
+            ),
+            should_encrypt: true,
+            reset_local_sequence_number: true,
+        });
+
+        Ok(pkts)
+    }
+}
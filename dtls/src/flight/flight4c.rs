@@ -0,0 +1,763 @@
+use super::*;
+use crate::cipher_suite::*;
+use crate::compression_methods::*;
+use crate::config::*;
+use crate::content::*;
+use crate::crypto::*;
+use crate::error::Error;
+use crate::extension::extension_key_share::*;
+use crate::extension::extension_supported_versions::*;
+use crate::extension::extension_use_srtp::*;
+use crate::extension::*;
+use crate::handshake::handshake_message_certificate13::*;
+use crate::handshake::handshake_message_certificate_request13::*;
+use crate::handshake::handshake_message_certificate_verify::*;
+use crate::handshake::handshake_message_encrypted_extensions::*;
+use crate::handshake::handshake_message_finished::*;
+use crate::handshake::handshake_message_server_hello::*;
+use crate::handshake::*;
+use crate::prf::*;
+use crate::record_layer::record_layer_header::*;
+use crate::signature_hash_algorithm::*;
+
+use async_trait::async_trait;
+use log::*;
+use std::fmt;
+use std::sync::atomic::Ordering;
+
+// The messages of a DTLS 1.3 handshake in transcript order, with the sender
+// and the epoch relative to the initial epoch
+const DTLS13_TRANSCRIPT: [(HandshakeType, bool, u16); 10] = [
+    (HandshakeType::ClientHello, true, 0),
+    (HandshakeType::ServerHello, false, 0),
+    (
+        HandshakeType::EncryptedExtensions,
+        false,
+        DTLS13_HANDSHAKE_EPOCH,
+    ),
+    (
+        HandshakeType::CertificateRequest,
+        false,
+        DTLS13_HANDSHAKE_EPOCH,
+    ),
+    (HandshakeType::Certificate, false, DTLS13_HANDSHAKE_EPOCH),
+    (
+        HandshakeType::CertificateVerify,
+        false,
+        DTLS13_HANDSHAKE_EPOCH,
+    ),
+    (HandshakeType::Finished, false, DTLS13_HANDSHAKE_EPOCH),
+    (HandshakeType::Certificate, true, DTLS13_HANDSHAKE_EPOCH),
+    (
+        HandshakeType::CertificateVerify,
+        true,
+        DTLS13_HANDSHAKE_EPOCH,
+    ),
+    (HandshakeType::Finished, true, DTLS13_HANDSHAKE_EPOCH),
+];
+
+const HANDSHAKE_TYPE_MESSAGE_HASH: u8 = 254;
+
+// Flight4c is the DTLS 1.3 flight of a server. It answers the ClientHello
+// that returned the cookie of the HelloRetryRequest, and authenticates the
+// server right away with the handshake keys.
+// https://www.rfc-editor.org/rfc/rfc9147#section-5.7
+#[derive(Debug, PartialEq)]
+pub(crate) struct Flight4c;
+
+impl fmt::Display for Flight4c {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flight 4c")
+    }
+}
+
+#[async_trait]
+impl Flight for Flight4c {
+    fn is_last_recv_flight(&self) -> bool {
+        true
+    }
+
+    async fn parse(
+        &self,
+        tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+        // Records of the client may have been queued before the handshake
+        // keys were installed
+        let (done_tx, mut done_rx) = mpsc::channel(1);
+        if let Err(err) = tx.send(done_tx).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(Error::Other(err.to_string())),
+            ));
+        }
+
+        done_rx.recv().await;
+
+        let epoch = cfg.initial_epoch + DTLS13_HANDSHAKE_EPOCH;
+        let (seq, msgs) = match cache
+            .full_pull_map_dtls13(
+                state.handshake_recv_sequence,
+                &[
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::Certificate,
+                        epoch,
+                        is_client: true,
+                        optional: true,
+                    },
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::CertificateVerify,
+                        epoch,
+                        is_client: true,
+                        optional: true,
+                    },
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::Finished,
+                        epoch,
+                        is_client: true,
+                        optional: false,
+                    },
+                ],
+            )
+            .await
+        {
+            Ok((seq, msgs)) => (seq, msgs),
+            // No valid message received. Keep reading
+            Err(_) => return Err((None, None)),
+        };
+
+        if let Some(HandshakeMessage::Certificate13(h)) = msgs.get(&HandshakeType::Certificate) {
+            state.peer_certificates = h.certificate.clone();
+            trace!(
+                "[handshake] PeerCertificates4c {}",
+                state.peer_certificates.len()
+            );
+        }
+
+        if !state.peer_certificates.is_empty() {
+            let h = match msgs.get(&HandshakeType::CertificateVerify) {
+                Some(HandshakeMessage::CertificateVerify(h)) => h,
+                _ => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::DecodeError,
+                        }),
+                        Some(Error::ErrClientCertificateNotVerified),
+                    ))
+                }
+            };
+
+            let transcript_hash =
+                dtls13_transcript_hash(state, cache, cfg, (HandshakeType::Certificate, true), &[])
+                    .await;
+            dtls13_verify_certificate_verify(state, cfg, h, &transcript_hash, true)?;
+
+            let mut chains = vec![];
+            let mut verified = false;
+            if cfg.client_auth as u8 >= ClientAuthType::VerifyClientCertIfGiven as u8 {
+                if let Some(client_cert_verifier) = &cfg.client_cert_verifier {
+                    chains =
+                        match verify_client_cert(&state.peer_certificates, client_cert_verifier) {
+                            Ok(chains) => chains,
+                            Err(err) => {
+                                return Err((
+                                    Some(Alert {
+                                        alert_level: AlertLevel::Fatal,
+                                        alert_description: AlertDescription::BadCertificate,
+                                    }),
+                                    Some(err),
+                                ))
+                            }
+                        };
+                } else {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::BadCertificate,
+                        }),
+                        Some(Error::ErrInvalidCertificate),
+                    ));
+                }
+
+                verified = true
+            }
+            if let Some(verify_peer_certificate) = &cfg.verify_peer_certificate {
+                if let Err(err) = verify_peer_certificate(&state.peer_certificates, &chains) {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::BadCertificate,
+                        }),
+                        Some(err),
+                    ));
+                }
+            }
+            state.peer_certificates_verified = verified
+        }
+
+        let finished = match msgs.get(&HandshakeType::Finished) {
+            Some(HandshakeMessage::Finished(h)) => h,
+            _ => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ))
+            }
+        };
+
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::CertificateVerify, true),
+            &[],
+        )
+        .await;
+        dtls13_verify_finished(
+            state,
+            &state.client_handshake_traffic_secret,
+            &transcript_hash,
+            finished,
+        )
+        .await?;
+
+        state.handshake_recv_sequence = seq;
+
+        match cfg.client_auth {
+            ClientAuthType::RequireAnyClientCert => {
+                if state.peer_certificates.is_empty() {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::NoCertificate,
+                        }),
+                        Some(Error::ErrClientCertificateRequired),
+                    ));
+                }
+            }
+            ClientAuthType::VerifyClientCertIfGiven => {
+                if !state.peer_certificates.is_empty() && !state.peer_certificates_verified {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::BadCertificate,
+                        }),
+                        Some(Error::ErrClientCertificateNotVerified),
+                    ));
+                }
+            }
+            ClientAuthType::RequireAndVerifyClientCert => {
+                if state.peer_certificates.is_empty() {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::NoCertificate,
+                        }),
+                        Some(Error::ErrClientCertificateRequired),
+                    ));
+                }
+                if !state.peer_certificates_verified {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::BadCertificate,
+                        }),
+                        Some(Error::ErrClientCertificateNotVerified),
+                    ));
+                }
+            }
+            ClientAuthType::NoClientCert | ClientAuthType::RequestClientCert => {}
+        }
+
+        Ok(Box::new(Flight4c {}) as Box<dyn Flight + Send + Sync>)
+    }
+
+    async fn generate(
+        &self,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
+        let local_keypair = match &state.local_keypair {
+            Some(local_keypair) => local_keypair,
+            None => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ))
+            }
+        };
+
+        let server_hello = HandshakeMessage::ServerHello(HandshakeMessageServerHello {
+            version: PROTOCOL_VERSION1_2,
+            random: state.local_random.clone(),
+            session_id: state.session_id.clone(),
+            cipher_suite: CipherSuiteId::Tls_Aes_128_Gcm_Sha256,
+            compression_method: default_compression_methods().ids[0],
+            extensions: vec![
+                Extension::SupportedVersions(ExtensionSupportedVersions {
+                    supported_versions: vec![],
+                    selected_version: Some(PROTOCOL_VERSION1_3),
+                }),
+                Extension::KeyShare(ExtensionKeyShare {
+                    server_share: Some(KeyShareEntry {
+                        group: local_keypair.curve,
+                        key_exchange: local_keypair.public_key.clone(),
+                    }),
+                    ..Default::default()
+                }),
+            ],
+        });
+
+        // Everything after the ServerHello is protected with the handshake keys
+        let mut transcript = marshal_transcript(&server_hello)?;
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::ClientHello, true),
+            &transcript,
+        )
+        .await;
+        let pre_master_secret = state.pre_master_secret.clone();
+        if let Err(err) =
+            dtls13_install_handshake_keys(state, cfg, &pre_master_secret, &transcript_hash).await
+        {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+
+        let mut extensions = vec![];
+        if state.srtp_protection_profile != SrtpProtectionProfile::Unsupported {
+            extensions.push(Extension::UseSrtp(ExtensionUseSrtp {
+                protection_profiles: vec![state.srtp_protection_profile],
+            }));
+        }
+
+        let mut messages = vec![HandshakeMessage::EncryptedExtensions(
+            HandshakeMessageEncryptedExtensions { extensions },
+        )];
+
+        if cfg.client_auth as u8 > ClientAuthType::NoClientCert as u8 {
+            messages.push(HandshakeMessage::CertificateRequest13(
+                HandshakeMessageCertificateRequest13 {
+                    certificate_request_context: vec![],
+                    signature_hash_algorithms: dtls13_signature_schemes(cfg),
+                },
+            ));
+        }
+
+        let certificate = match cfg.get_certificate(&cfg.server_name) {
+            Ok(cert) => cert,
+            Err(err) => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::HandshakeFailure,
+                    }),
+                    Some(err),
+                ))
+            }
+        };
+
+        messages.push(HandshakeMessage::Certificate13(
+            HandshakeMessageCertificate13 {
+                certificate_request_context: vec![],
+                certificate: certificate
+                    .certificate
+                    .iter()
+                    .map(|x| x.0.clone())
+                    .collect(),
+            },
+        ));
+
+        for message in &messages {
+            transcript.extend_from_slice(&marshal_transcript(message)?);
+        }
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::ClientHello, true),
+            &transcript,
+        )
+        .await;
+        let certificate_verify =
+            dtls13_certificate_verify(cfg, &certificate, &transcript_hash, false)?;
+        transcript.extend_from_slice(&marshal_transcript(&certificate_verify)?);
+        messages.push(certificate_verify);
+
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::ClientHello, true),
+            &transcript,
+        )
+        .await;
+        let finished = dtls13_finished(
+            state,
+            &state.server_handshake_traffic_secret,
+            &transcript_hash,
+        )
+        .await?;
+        transcript.extend_from_slice(&marshal_transcript(&finished)?);
+        messages.push(finished);
+
+        // The application keys only depend on the messages of the server, the
+        // server may send with them right after its Finished
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::ClientHello, true),
+            &transcript,
+        )
+        .await;
+        if let Err(err) = dtls13_install_application_keys(state, cfg, &transcript_hash).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+        state.local_epoch.store(
+            cfg.initial_epoch + DTLS13_APPLICATION_EPOCH,
+            Ordering::SeqCst,
+        );
+
+        let mut pkts = vec![Packet {
+            record: RecordLayer::new(
+                PROTOCOL_VERSION1_2,
+                0,
+                Content::Handshake(Handshake::new(server_hello)),
+            ),
+            should_encrypt: false,
+            reset_local_sequence_number: false,
+        }];
+        for message in messages {
+            pkts.push(Packet {
+                record: RecordLayer::new(
+                    PROTOCOL_VERSION1_2,
+                    DTLS13_HANDSHAKE_EPOCH,
+                    Content::Handshake(Handshake::new(message)),
+                ),
+                should_encrypt: true,
+                reset_local_sequence_number: false,
+            });
+        }
+
+        Ok(pkts)
+    }
+}
+
+fn marshal_transcript(
+    message: &HandshakeMessage,
+) -> Result<Vec<u8>, (Option<Alert>, Option<Error>)> {
+    message.marshal_transcript().map_err(|err| {
+        (
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(err),
+        )
+    })
+}
+
+async fn dtls13_hash_func(state: &State) -> CipherSuiteHash {
+    let cipher_suite = state.cipher_suite.lock().await;
+    match &*cipher_suite {
+        Some(cipher_suite) => cipher_suite.hash_func(),
+        None => CipherSuiteHash::Sha256,
+    }
+}
+
+// dtls13_transcript_hash hashes the transcript up to and including the last
+// cached message of the given type and sender, followed by the messages of
+// the flight being generated.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.4.1
+pub(crate) async fn dtls13_transcript_hash(
+    state: &State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+    last: (HandshakeType, bool),
+    additional: &[u8],
+) -> Vec<u8> {
+    let mut rules = vec![];
+    for (typ, is_client, epoch) in &DTLS13_TRANSCRIPT {
+        rules.push(HandshakeCachePullRule {
+            typ: *typ,
+            epoch: cfg.initial_epoch + epoch,
+            is_client: *is_client,
+            optional: true,
+        });
+        if (*typ, *is_client) == last {
+            break;
+        }
+    }
+
+    let mut transcript = state.hello_retry_transcript.clone();
+    transcript.extend_from_slice(&cache.pull_and_merge_transcript(&rules).await);
+    transcript.extend_from_slice(additional);
+
+    prf_transcript_hash(&transcript, dtls13_hash_func(state).await)
+}
+
+// dtls13_hello_retry_transcript starts the transcript of a handshake with a
+// HelloRetryRequest, the first ClientHello is replaced by its hash.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.4.1
+pub(crate) async fn dtls13_hello_retry_transcript(
+    state: &State,
+    client_hello: &[u8],
+    hello_retry_request: &[u8],
+) -> Vec<u8> {
+    let hash = prf_transcript_hash(client_hello, dtls13_hash_func(state).await);
+
+    let mut transcript = vec![HANDSHAKE_TYPE_MESSAGE_HASH, 0, 0, hash.len() as u8];
+    transcript.extend_from_slice(&hash);
+    transcript.extend_from_slice(hello_retry_request);
+    transcript
+}
+
+// dtls13_install_handshake_keys derives the handshake traffic secrets from
+// the (EC)DHE shared secret and protects epoch 2 with them.
+// https://www.rfc-editor.org/rfc/rfc8446#section-7.1
+pub(crate) async fn dtls13_install_handshake_keys(
+    state: &mut State,
+    cfg: &HandshakeConfig,
+    shared_secret: &[u8],
+    transcript_hash: &[u8],
+) -> Result<(), Error> {
+    let mut cipher_suite = state.cipher_suite.lock().await;
+    let cipher_suite = match &mut *cipher_suite {
+        Some(cipher_suite) => cipher_suite,
+        None => return Err(Error::ErrCipherSuiteUnset),
+    };
+    let h = cipher_suite.hash_func();
+
+    state.handshake_secret = prf_handshake_secret(shared_secret, h)?;
+    state.client_handshake_traffic_secret = prf_derive_secret(
+        &state.handshake_secret,
+        PRF_CLIENT_HANDSHAKE_TRAFFIC_LABEL,
+        transcript_hash,
+        h,
+    )?;
+    state.server_handshake_traffic_secret = prf_derive_secret(
+        &state.handshake_secret,
+        PRF_SERVER_HANDSHAKE_TRAFFIC_LABEL,
+        transcript_hash,
+        h,
+    )?;
+
+    let epoch = cfg.initial_epoch + DTLS13_HANDSHAKE_EPOCH;
+    cipher_suite.set_traffic_secret(
+        epoch,
+        &state.client_handshake_traffic_secret,
+        state.is_client,
+    )?;
+    cipher_suite.set_traffic_secret(
+        epoch,
+        &state.server_handshake_traffic_secret,
+        !state.is_client,
+    )
+}
+
+// dtls13_install_application_keys derives the application traffic secrets
+// and the exporter secret once the server sent its Finished.
+// https://www.rfc-editor.org/rfc/rfc8446#section-7.1
+pub(crate) async fn dtls13_install_application_keys(
+    state: &mut State,
+    cfg: &HandshakeConfig,
+    transcript_hash: &[u8],
+) -> Result<(), Error> {
+    let mut cipher_suite = state.cipher_suite.lock().await;
+    let cipher_suite = match &mut *cipher_suite {
+        Some(cipher_suite) => cipher_suite,
+        None => return Err(Error::ErrCipherSuiteUnset),
+    };
+    let h = cipher_suite.hash_func();
+
+    let main_secret = prf_main_secret(&state.handshake_secret, h)?;
+    let client_traffic_secret = prf_derive_secret(
+        &main_secret,
+        PRF_CLIENT_APPLICATION_TRAFFIC_LABEL,
+        transcript_hash,
+        h,
+    )?;
+    let server_traffic_secret = prf_derive_secret(
+        &main_secret,
+        PRF_SERVER_APPLICATION_TRAFFIC_LABEL,
+        transcript_hash,
+        h,
+    )?;
+
+    let epoch = cfg.initial_epoch + DTLS13_APPLICATION_EPOCH;
+    cipher_suite.set_traffic_secret(epoch, &client_traffic_secret, state.is_client)?;
+    cipher_suite.set_traffic_secret(epoch, &server_traffic_secret, !state.is_client)?;
+
+    state.master_secret =
+        prf_derive_secret(&main_secret, PRF_EXPORTER_MASTER_LABEL, transcript_hash, h)?;
+
+    Ok(())
+}
+
+// dtls13_signature_schemes are the configured signature schemes DTLS 1.3
+// allows for our keys, it has no RSA PKCS#1 v1.5 and binds ECDSA to a curve.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.2.3
+pub(crate) fn dtls13_signature_schemes(cfg: &HandshakeConfig) -> Vec<SignatureHashAlgorithm> {
+    cfg.local_signature_schemes
+        .iter()
+        .filter(|s| {
+            matches!(
+                (s.hash, s.signature),
+                (HashAlgorithm::Sha256, SignatureAlgorithm::Ecdsa)
+                    | (HashAlgorithm::Ed25519, SignatureAlgorithm::Ed25519)
+            )
+        })
+        .copied()
+        .collect()
+}
+
+pub(crate) fn dtls13_certificate_verify(
+    cfg: &HandshakeConfig,
+    certificate: &Certificate,
+    transcript_hash: &[u8],
+    is_client: bool,
+) -> Result<HandshakeMessage, (Option<Alert>, Option<Error>)> {
+    let algorithm =
+        match select_signature_scheme(&dtls13_signature_schemes(cfg), &certificate.private_key) {
+            Ok(s) => s,
+            Err(err) => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InsufficientSecurity,
+                    }),
+                    Some(err),
+                ))
+            }
+        };
+
+    let content = certificate_verify_content(transcript_hash, is_client);
+    let signature = match generate_certificate_verify(&content, &certificate.private_key) {
+        Ok(signature) => signature,
+        Err(err) => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ))
+        }
+    };
+
+    Ok(HandshakeMessage::CertificateVerify(
+        HandshakeMessageCertificateVerify {
+            algorithm,
+            signature,
+        },
+    ))
+}
+
+pub(crate) fn dtls13_verify_certificate_verify(
+    state: &State,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageCertificateVerify,
+    transcript_hash: &[u8],
+    is_client: bool,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    // Verify that the pair of hash algorithm and signature is listed.
+    if !dtls13_signature_schemes(cfg)
+        .iter()
+        .any(|ss| ss.hash == h.algorithm.hash && ss.signature == h.algorithm.signature)
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrNoAvailableSignatureSchemes),
+        ));
+    }
+
+    let content = certificate_verify_content(transcript_hash, is_client);
+    if let Err(err) = verify_certificate_verify(
+        &content,
+        &h.algorithm,
+        &h.signature,
+        &state.peer_certificates,
+    ) {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::BadCertificate,
+            }),
+            Some(err),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn dtls13_finished(
+    state: &State,
+    handshake_traffic_secret: &[u8],
+    transcript_hash: &[u8],
+) -> Result<HandshakeMessage, (Option<Alert>, Option<Error>)> {
+    match prf_finished_verify_data(
+        handshake_traffic_secret,
+        transcript_hash,
+        dtls13_hash_func(state).await,
+    ) {
+        Ok(verify_data) => Ok(HandshakeMessage::Finished(HandshakeMessageFinished {
+            verify_data,
+        })),
+        Err(err) => Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(err),
+        )),
+    }
+}
+
+pub(crate) async fn dtls13_verify_finished(
+    state: &State,
+    handshake_traffic_secret: &[u8],
+    transcript_hash: &[u8],
+    h: &HandshakeMessageFinished,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    let expected = dtls13_finished(state, handshake_traffic_secret, transcript_hash).await?;
+    match expected {
+        HandshakeMessage::Finished(expected) if expected.verify_data == h.verify_data => Ok(()),
+        _ => Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::HandshakeFailure,
+            }),
+            Some(Error::ErrVerifyDataMismatch),
+        )),
+    }
+}
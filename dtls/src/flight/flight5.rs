@@ -15,11 +15,13 @@ use crate::handshake::*;
 use crate::prf::*;
 use crate::record_layer::record_layer_header::*;
 use crate::record_layer::*;
+use crate::session::*;
 use crate::signature_hash_algorithm::*;
 
 use async_trait::async_trait;
 use std::fmt;
 use std::io::{BufReader, BufWriter};
+use std::time::SystemTime;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight5;
@@ -46,12 +48,20 @@ impl Flight for Flight5 {
         let (_seq, msgs) = match cache
             .full_pull_map(
                 state.handshake_recv_sequence,
-                &[HandshakeCachePullRule {
-                    typ: HandshakeType::Finished,
-                    epoch: cfg.initial_epoch + 1,
-                    is_client: false,
-                    optional: false,
-                }],
+                &[
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::NewSessionTicket,
+                        epoch: cfg.initial_epoch,
+                        is_client: false,
+                        optional: true,
+                    },
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::Finished,
+                        epoch: cfg.initial_epoch + 1,
+                        is_client: false,
+                        optional: false,
+                    },
+                ],
             )
             .await
         {
@@ -134,6 +144,12 @@ impl Flight for Flight5 {
                     is_client: true,
                     optional: false,
                 },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::NewSessionTicket,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: true,
+                },
             ])
             .await;
 
@@ -169,6 +185,12 @@ impl Flight for Flight5 {
             }
         }
 
+        let ticket = match msgs.get(&HandshakeType::NewSessionTicket) {
+            Some(HandshakeMessage::NewSessionTicket(h)) => h.ticket.clone(),
+            _ => vec![],
+        };
+        save_client_session(state, cfg, ticket).await;
+
         Ok(Box::new(Flight5 {}))
     }
 
@@ -598,6 +620,42 @@ impl Flight for Flight5 {
         Ok(pkts)
     }
 }
+// save_client_session stores the session just established so the next
+// connection to the server can resume it.
+async fn save_client_session(state: &State, cfg: &HandshakeConfig, ticket: Vec<u8>) {
+    let session_store = match &cfg.session_store {
+        Some(session_store) => session_store,
+        None => return,
+    };
+
+    if state.session_id.is_empty() && ticket.is_empty() {
+        // The server won't resume this session
+        let _ = session_store.del(&cfg.session_key);
+        return;
+    }
+
+    let cipher_suite = {
+        let cipher_suite = state.cipher_suite.lock().await;
+        match &*cipher_suite {
+            Some(cipher_suite) => cipher_suite.id(),
+            None => return,
+        }
+    };
+
+    let _ = session_store.set(
+        &cfg.session_key,
+        Session {
+            id: state.session_id.clone(),
+            secret: state.master_secret.clone(),
+            cipher_suite,
+            extended_master_secret: state.extended_master_secret,
+            ticket,
+            peer_certificates: state.peer_certificates.clone(),
+            issued_at: SystemTime::now(),
+        },
+    );
+}
+
 async fn initalize_cipher_suite(
     state: &mut State,
     cache: &HandshakeCache,
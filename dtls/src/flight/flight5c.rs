@@ -0,0 +1,493 @@
+use super::flight4c::*;
+use super::*;
+use crate::cipher_suite::cipher_suite_tls_aes_128_gcm_sha256::*;
+use crate::cipher_suite::*;
+use crate::content::*;
+use crate::crypto::*;
+use crate::error::Error;
+use crate::extension::extension_use_srtp::*;
+use crate::extension::*;
+use crate::find_matching_srtp_profile;
+use crate::handshake::handshake_message_certificate13::*;
+use crate::handshake::handshake_message_server_hello::*;
+use crate::handshake::*;
+use crate::prf::*;
+use crate::record_layer::record_layer_header::*;
+
+use async_trait::async_trait;
+use log::*;
+use std::fmt;
+use std::sync::atomic::Ordering;
+
+// Flight5c is the last flight of a DTLS 1.3 client, it answers the
+// encrypted flight of the server with its Certificate, CertificateVerify
+// and Finished.
+// https://www.rfc-editor.org/rfc/rfc9147#section-5.7
+#[derive(Debug, PartialEq)]
+pub(crate) struct Flight5c;
+
+impl fmt::Display for Flight5c {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flight 5c")
+    }
+}
+
+#[async_trait]
+impl Flight for Flight5c {
+    fn is_last_send_flight(&self) -> bool {
+        true
+    }
+
+    async fn parse(
+        &self,
+        _tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+        let (_, msgs) = match cache
+            .full_pull_map_dtls13(
+                state.handshake_recv_sequence - 1,
+                &[HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + DTLS13_HANDSHAKE_EPOCH,
+                    is_client: false,
+                    optional: false,
+                }],
+            )
+            .await
+        {
+            Ok((seq, msgs)) => (seq, msgs),
+            // No valid message received. Keep reading
+            Err(_) => return Err((None, None)),
+        };
+
+        match msgs.get(&HandshakeType::Finished) {
+            Some(HandshakeMessage::Finished(_)) => {}
+            _ => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ))
+            }
+        };
+
+        // Other party retransmitted the last flight.
+        Ok(Box::new(Flight5c {}))
+    }
+
+    async fn generate(
+        &self,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
+        let mut messages = vec![];
+        if state.remote_requested_certificate {
+            let certificate = if !cfg.local_certificates.is_empty() {
+                match cfg.get_certificate(&cfg.server_name) {
+                    Ok(cert) => Some(cert),
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::HandshakeFailure,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                }
+            } else {
+                None
+            };
+
+            messages.push(HandshakeMessage::Certificate13(
+                HandshakeMessageCertificate13 {
+                    certificate_request_context: state.certificate_request_context.clone(),
+                    certificate: if let Some(cert) = &certificate {
+                        cert.certificate.iter().map(|x| x.0.clone()).collect()
+                    } else {
+                        vec![]
+                    },
+                },
+            ));
+
+            // A CertificateVerify is only sent along with a certificate
+            if let Some(certificate) = &certificate {
+                let transcript_hash = dtls13_transcript_hash(
+                    state,
+                    cache,
+                    cfg,
+                    (HandshakeType::Finished, false),
+                    &marshal_transcript(&messages)?,
+                )
+                .await;
+                messages.push(dtls13_certificate_verify(
+                    cfg,
+                    certificate,
+                    &transcript_hash,
+                    true,
+                )?);
+            }
+        }
+
+        let transcript_hash = dtls13_transcript_hash(
+            state,
+            cache,
+            cfg,
+            (HandshakeType::Finished, false),
+            &marshal_transcript(&messages)?,
+        )
+        .await;
+        messages.push(
+            dtls13_finished(
+                state,
+                &state.client_handshake_traffic_secret,
+                &transcript_hash,
+            )
+            .await?,
+        );
+
+        state.local_epoch.store(
+            cfg.initial_epoch + DTLS13_APPLICATION_EPOCH,
+            Ordering::SeqCst,
+        );
+
+        Ok(messages
+            .into_iter()
+            .map(|message| Packet {
+                record: RecordLayer::new(
+                    PROTOCOL_VERSION1_2,
+                    DTLS13_HANDSHAKE_EPOCH,
+                    Content::Handshake(Handshake::new(message)),
+                ),
+                should_encrypt: true,
+                reset_local_sequence_number: false,
+            })
+            .collect())
+    }
+}
+
+fn marshal_transcript(
+    messages: &[HandshakeMessage],
+) -> Result<Vec<u8>, (Option<Alert>, Option<Error>)> {
+    let mut transcript = vec![];
+    for message in messages {
+        match message.marshal_transcript() {
+            Ok(raw) => transcript.extend_from_slice(&raw),
+            Err(err) => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    Some(err),
+                ))
+            }
+        }
+    }
+    Ok(transcript)
+}
+
+// handle_dtls13_server_hello processes a ServerHello selecting DTLS 1.3 and
+// the encrypted flight following it.
+pub(crate) async fn handle_dtls13_server_hello(
+    tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+    state: &mut State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageServerHello,
+) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+    if !state.dtls13.load(Ordering::SeqCst) {
+        if h.cipher_suite != CipherSuiteId::Tls_Aes_128_Gcm_Sha256 {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::IllegalParameter,
+                }),
+                Some(Error::ErrCipherSuiteNoIntersection),
+            ));
+        }
+
+        let local_keypair = match &state.local_keypair {
+            Some(local_keypair) => local_keypair,
+            None => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ))
+            }
+        };
+
+        let server_share = h.extensions.iter().find_map(|extension| match extension {
+            Extension::KeyShare(e) => e.server_share.as_ref(),
+            _ => None,
+        });
+        let server_share = match server_share {
+            Some(server_share) if server_share.group == local_keypair.curve => server_share,
+            _ => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::IllegalParameter,
+                    }),
+                    Some(Error::ErrNoKeyShare),
+                ))
+            }
+        };
+
+        let shared_secret = match prf_pre_master_secret(
+            &server_share.key_exchange,
+            &local_keypair.private_key,
+            local_keypair.curve,
+        ) {
+            Ok(shared_secret) => shared_secret,
+            Err(err) => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::IllegalParameter,
+                    }),
+                    Some(err),
+                ))
+            }
+        };
+
+        debug!(
+            "[handshake:{}] use cipher suite: {}",
+            srv_cli_str(state.is_client),
+            h.cipher_suite
+        );
+        {
+            let mut cipher_suite = state.cipher_suite.lock().await;
+            *cipher_suite = Some(Box::new(CipherSuiteTlsAes128GcmSha256::default()));
+        }
+        state.remote_random = h.random.clone();
+        state.session_id = h.session_id.clone();
+
+        let transcript_hash =
+            dtls13_transcript_hash(state, cache, cfg, (HandshakeType::ServerHello, false), &[])
+                .await;
+        if let Err(err) =
+            dtls13_install_handshake_keys(state, cfg, &shared_secret, &transcript_hash).await
+        {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+        state.dtls13.store(true, Ordering::SeqCst);
+    }
+
+    // Records of the server may have been queued before the handshake keys
+    // were installed
+    let (done_tx, mut done_rx) = mpsc::channel(1);
+    if let Err(err) = tx.send(done_tx).await {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(Error::Other(err.to_string())),
+        ));
+    }
+
+    done_rx.recv().await;
+
+    let epoch = cfg.initial_epoch + DTLS13_HANDSHAKE_EPOCH;
+    let (seq, msgs) = match cache
+        .full_pull_map_dtls13(
+            state.handshake_recv_sequence,
+            &[
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ServerHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::EncryptedExtensions,
+                    epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::CertificateRequest,
+                    epoch,
+                    is_client: false,
+                    optional: true,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Certificate,
+                    epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::CertificateVerify,
+                    epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch,
+                    is_client: false,
+                    optional: false,
+                },
+            ],
+        )
+        .await
+    {
+        Ok((seq, msgs)) => (seq, msgs),
+        // No valid message received. Keep reading
+        Err(_) => return Err((None, None)),
+    };
+
+    if let Some(HandshakeMessage::EncryptedExtensions(h)) =
+        msgs.get(&HandshakeType::EncryptedExtensions)
+    {
+        for extension in &h.extensions {
+            if let Extension::UseSrtp(e) = extension {
+                state.srtp_protection_profile = match find_matching_srtp_profile(
+                    &e.protection_profiles,
+                    &cfg.local_srtp_protection_profiles,
+                ) {
+                    Ok(profile) => profile,
+                    Err(_) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::IllegalParameter,
+                            }),
+                            Some(Error::ErrClientNoMatchingSrtpProfile),
+                        ))
+                    }
+                };
+            }
+        }
+    }
+    if !cfg.local_srtp_protection_profiles.is_empty()
+        && state.srtp_protection_profile == SrtpProtectionProfile::Unsupported
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrRequestedButNoSrtpExtension),
+        ));
+    }
+
+    if let Some(HandshakeMessage::CertificateRequest13(h)) =
+        msgs.get(&HandshakeType::CertificateRequest)
+    {
+        state.remote_requested_certificate = true;
+        state.certificate_request_context = h.certificate_request_context.clone();
+    }
+
+    if let Some(HandshakeMessage::Certificate13(h)) = msgs.get(&HandshakeType::Certificate) {
+        state.peer_certificates = h.certificate.clone();
+    }
+
+    let h = match msgs.get(&HandshakeType::CertificateVerify) {
+        Some(HandshakeMessage::CertificateVerify(h)) => h,
+        _ => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                None,
+            ))
+        }
+    };
+    let transcript_hash =
+        dtls13_transcript_hash(state, cache, cfg, (HandshakeType::Certificate, false), &[]).await;
+    dtls13_verify_certificate_verify(state, cfg, h, &transcript_hash, false)?;
+
+    let mut chains = vec![];
+    if !cfg.insecure_skip_verify {
+        chains = match verify_server_cert(
+            &state.peer_certificates,
+            &cfg.server_cert_verifier,
+            &cfg.roots_cas,
+            &cfg.server_name,
+        ) {
+            Ok(chains) => chains,
+            Err(err) => {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::BadCertificate,
+                    }),
+                    Some(err),
+                ))
+            }
+        }
+    }
+    if let Some(verify_peer_certificate) = &cfg.verify_peer_certificate {
+        if let Err(err) = verify_peer_certificate(&state.peer_certificates, &chains) {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::BadCertificate,
+                }),
+                Some(err),
+            ));
+        }
+    }
+
+    let finished = match msgs.get(&HandshakeType::Finished) {
+        Some(HandshakeMessage::Finished(h)) => h,
+        _ => {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                None,
+            ))
+        }
+    };
+    let transcript_hash = dtls13_transcript_hash(
+        state,
+        cache,
+        cfg,
+        (HandshakeType::CertificateVerify, false),
+        &[],
+    )
+    .await;
+    dtls13_verify_finished(
+        state,
+        &state.server_handshake_traffic_secret,
+        &transcript_hash,
+        finished,
+    )
+    .await?;
+
+    let transcript_hash =
+        dtls13_transcript_hash(state, cache, cfg, (HandshakeType::Finished, false), &[]).await;
+    if let Err(err) = dtls13_install_application_keys(state, cfg, &transcript_hash).await {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(err),
+        ));
+    }
+
+    state.handshake_recv_sequence = seq;
+
+    Ok(Box::new(Flight5c {}) as Box<dyn Flight + Send + Sync>)
+}
@@ -2,12 +2,17 @@ use super::*;
 use crate::change_cipher_spec::*;
 use crate::content::*;
 use crate::handshake::handshake_message_finished::*;
+use crate::handshake::handshake_message_new_session_ticket::*;
 use crate::handshake::*;
 use crate::prf::*;
 use crate::record_layer::record_layer_header::*;
+use crate::record_layer::*;
+use crate::session::*;
 
 use async_trait::async_trait;
 use std::fmt;
+use std::io::BufWriter;
+use std::time::SystemTime;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight6;
@@ -73,7 +78,40 @@ impl Flight for Flight6 {
         cache: &HandshakeCache,
         cfg: &HandshakeConfig,
     ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
-        let mut pkts = vec![Packet {
+        let mut pkts = vec![];
+
+        // The NewSessionTicket is part of the handshake hash, marshal it
+        // with the sequence number it will be sent with.
+        let mut session_ticket = vec![];
+        if state.session_ticket_negotiated {
+            let p = match new_session_ticket(state, cfg).await {
+                Ok(p) => p,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+            session_ticket = match marshal_predicted(&p, state.handshake_send_sequence) {
+                Ok(raw) => raw,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+            pkts.push(p);
+        }
+
+        pkts.push(Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
                 0,
@@ -81,10 +119,10 @@ impl Flight for Flight6 {
             ),
             should_encrypt: false,
             reset_local_sequence_number: false,
-        }];
+        });
 
         if state.local_verify_data.is_empty() {
-            let plain_text = cache
+            let mut plain_text = cache
                 .pull_and_merge(&[
                     HandshakeCachePullRule {
                         typ: HandshakeType::ClientHello,
@@ -149,6 +187,8 @@ impl Flight for Flight6 {
                 ])
                 .await;
 
+            plain_text.extend_from_slice(&session_ticket);
+
             let cipher_suite = state.cipher_suite.lock().await;
             if let Some(cipher_suite) = &*cipher_suite {
                 state.local_verify_data = match prf_verify_data_server(
@@ -187,3 +227,71 @@ impl Flight for Flight6 {
         Ok(pkts)
     }
 }
+
+// new_session_ticket builds the NewSessionTicket of the session, the ticket
+// is kept in the state so a retransmitted flight hashes the same message.
+pub(crate) async fn new_session_ticket(
+    state: &mut State,
+    cfg: &HandshakeConfig,
+) -> Result<Packet, Error> {
+    if state.session_ticket.is_empty() {
+        let session_ticket_key = match &cfg.session_ticket_key {
+            Some(session_ticket_key) => session_ticket_key,
+            None => return Err(Error::ErrInvalidSessionTicketKey),
+        };
+
+        let cipher_suite = {
+            let cipher_suite = state.cipher_suite.lock().await;
+            match &*cipher_suite {
+                Some(cipher_suite) => cipher_suite.id(),
+                None => return Err(Error::ErrCipherSuiteUnset),
+            }
+        };
+
+        state.session_ticket = encrypt_session_ticket(
+            session_ticket_key,
+            &Session {
+                id: vec![],
+                secret: state.master_secret.clone(),
+                cipher_suite,
+                extended_master_secret: state.extended_master_secret,
+                ticket: vec![],
+                peer_certificates: state.peer_certificates.clone(),
+                issued_at: SystemTime::now(),
+            },
+        )?;
+    }
+
+    Ok(Packet {
+        record: RecordLayer::new(
+            PROTOCOL_VERSION1_2,
+            0,
+            Content::Handshake(Handshake::new(HandshakeMessage::NewSessionTicket(
+                HandshakeMessageNewSessionTicket {
+                    ticket_lifetime_hint: SESSION_LIFETIME.as_secs() as u32,
+                    ticket: state.session_ticket.clone(),
+                },
+            ))),
+        ),
+        should_encrypt: false,
+        reset_local_sequence_number: false,
+    })
+}
+
+// marshal_predicted marshals the handshake message of p as it will be sent
+// with message sequence seq.
+pub(crate) fn marshal_predicted(p: &Packet, seq: isize) -> Result<Vec<u8>, Error> {
+    let mut h = match &p.record.content {
+        Content::Handshake(h) => h.clone(),
+        _ => return Err(Error::ErrInvalidContentType),
+    };
+    h.handshake_header.message_sequence = seq as u16;
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        h.marshal(&mut writer)?;
+    }
+
+    Ok(raw)
+}
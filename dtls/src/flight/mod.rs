@@ -3,7 +3,11 @@ pub(crate) mod flight1;
 pub(crate) mod flight2;
 pub(crate) mod flight3;
 pub(crate) mod flight4;
+pub(crate) mod flight4b;
+pub(crate) mod flight4c;
 pub(crate) mod flight5;
+pub(crate) mod flight5b;
+pub(crate) mod flight5c;
 pub(crate) mod flight6;
 
 use crate::alert::*;
@@ -48,8 +52,49 @@ use tokio::sync::mpsc;
                                       [ChangeCipherSpec]    \ Flight 6
                           <--------             Finished    /
 
+
+  A client resuming a session with a session ID or a session ticket gets an
+  abbreviated handshake instead of flights 4 to 6.
+  https://tools.ietf.org/html/rfc5077#section-3.1
+
+                                             ServerHello    \
+                                       NewSessionTicket*     \ Flight 4b
+                                      [ChangeCipherSpec]     /
+                          <--------             Finished    /
+
+  [ChangeCipherSpec]                                        \ Flight 5b
+  Finished                -------->                         /
+
+
+  A client and a server that both support DTLS 1.3 answer the cookie
+  exchange with a single flight each. The cookie is sent in a
+  HelloRetryRequest, a ServerHello with a special random, instead of a
+  HelloVerifyRequest.
+  https://www.rfc-editor.org/rfc/rfc9147#section-5.7
+
+                          <-------     HelloRetryRequest      Flight 2
+
+                                             ServerHello    \
+                                    {EncryptedExtensions}    \
+                                    {CertificateRequest*}     \ Flight 4c
+                                           {Certificate}      /
+                                     {CertificateVerify}     /
+                          <--------           {Finished}    /
+
+  {Certificate*}                                            \
+  {CertificateVerify*}                                       Flight 5c
+  {Finished}              -------->                         /
+
+  {} messages are protected with the handshake keys of epoch 2, the
+  application data follows in epoch 3.
+
 */
 
+// Epochs of the DTLS 1.3 handshake and of the first application data
+// https://www.rfc-editor.org/rfc/rfc9147#section-6.1
+pub(crate) const DTLS13_HANDSHAKE_EPOCH: u16 = 2;
+pub(crate) const DTLS13_APPLICATION_EPOCH: u16 = 3;
+
 #[derive(Clone, Debug)]
 pub(crate) struct Packet {
     pub(crate) record: RecordLayer,
@@ -2,6 +2,7 @@
 mod handshake_cache_test;
 
 use crate::cipher_suite::*;
+use crate::handshake::handshake_header::HANDSHAKE_HEADER_LENGTH;
 use crate::handshake::*;
 
 use std::collections::HashMap;
@@ -9,7 +10,7 @@ use std::io::BufReader;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha384};
 
 #[derive(Clone, Debug)]
 pub(crate) struct HandshakeCacheItem {
@@ -101,6 +102,25 @@ impl HandshakeCache {
         &self,
         start_seq: isize,
         rules: &[HandshakeCachePullRule],
+    ) -> Result<(isize, HashMap<HandshakeType, HandshakeMessage>)> {
+        self.full_pull_map_version(start_seq, rules, false).await
+    }
+
+    // full_pull_map_dtls13 is full_pull_map for a DTLS 1.3 handshake, whose
+    // Certificate and CertificateRequest differ from DTLS 1.2.
+    pub(crate) async fn full_pull_map_dtls13(
+        &self,
+        start_seq: isize,
+        rules: &[HandshakeCachePullRule],
+    ) -> Result<(isize, HashMap<HandshakeType, HandshakeMessage>)> {
+        self.full_pull_map_version(start_seq, rules, true).await
+    }
+
+    async fn full_pull_map_version(
+        &self,
+        start_seq: isize,
+        rules: &[HandshakeCachePullRule],
+        dtls13: bool,
     ) -> Result<(isize, HashMap<HandshakeType, HandshakeMessage>)> {
         let cache = self.cache.lock().await;
 
@@ -134,7 +154,11 @@ impl HandshakeCache {
             let t = r.typ;
             if let Some(i) = ci.get(&t) {
                 let mut reader = BufReader::new(i.data.as_slice());
-                let raw_handshake = Handshake::unmarshal(&mut reader)?;
+                let raw_handshake = if dtls13 {
+                    Handshake::unmarshal_dtls13(&mut reader)?
+                } else {
+                    Handshake::unmarshal(&mut reader)?
+                };
                 if seq as u16 != raw_handshake.handshake_header.message_sequence {
                     // There is a gap. Some messages are not arrived.
                     return Err(Error::Other(
@@ -160,6 +184,26 @@ impl HandshakeCache {
         merged
     }
 
+    // pull_and_merge_transcript is pull_and_merge for the DTLS 1.3 transcript,
+    // which hashes the messages without the DTLS specific header fields.
+    // https://www.rfc-editor.org/rfc/rfc9147#section-5.2
+    pub(crate) async fn pull_and_merge_transcript(
+        &self,
+        rules: &[HandshakeCachePullRule],
+    ) -> Vec<u8> {
+        let mut merged = vec![];
+
+        for p in &self.pull(rules).await {
+            if p.data.len() < HANDSHAKE_HEADER_LENGTH {
+                continue;
+            }
+            merged.extend_from_slice(&p.data[..4]);
+            merged.extend_from_slice(&p.data[HANDSHAKE_HEADER_LENGTH..]);
+        }
+
+        merged
+    }
+
     // session_hash returns the session hash for Extended Master Secret support
     // https://tools.ietf.org/html/draft-ietf-tls-session-hash-06#section-4
     pub(crate) async fn session_hash(
@@ -230,12 +274,11 @@ impl HandshakeCache {
 
         merged.extend_from_slice(additional);
 
-        let mut hasher = match hf {
-            CipherSuiteHash::Sha256 => Sha256::new(),
+        let result = match hf {
+            CipherSuiteHash::Sha256 => Sha256::digest(&merged).to_vec(),
+            CipherSuiteHash::Sha384 => Sha384::digest(&merged).to_vec(),
         };
-        hasher.update(&merged);
-        let result = hasher.finalize();
 
-        Ok(result.as_slice().to_vec())
+        Ok(result)
     }
 }
@@ -0,0 +1,37 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_handshake_message_certificate13() -> Result<()> {
+    let raw_certificate = vec![
+        0x00, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03,
+        0x00, 0x00,
+    ];
+    let parsed_certificate = HandshakeMessageCertificate13 {
+        certificate_request_context: vec![],
+        certificate: vec![vec![0x01, 0x02], vec![0x03]],
+    };
+
+    let mut reader = BufReader::new(raw_certificate.as_slice());
+    let c = HandshakeMessageCertificate13::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_certificate,
+        "handshakeMessageCertificate13 unmarshal: got {:?}, want {:?}",
+        c, parsed_certificate
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_certificate,
+        "handshakeMessageCertificate13 marshal: got {:?}, want {:?}",
+        raw, raw_certificate
+    );
+    assert_eq!(raw.len(), c.size());
+
+    Ok(())
+}
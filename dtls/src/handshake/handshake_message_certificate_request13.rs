@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod handshake_message_certificate_request13_test;
+
+use super::*;
+use crate::extension::extension_supported_signature_algorithms::*;
+use crate::extension::*;
+use crate::signature_hash_algorithm::*;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{BufReader, BufWriter};
+
+/*
+The DTLS 1.3 CertificateRequest, the signature algorithms the server accepts
+are sent as a signature_algorithms extension.
+https://www.rfc-editor.org/rfc/rfc8446#section-4.3.2
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeMessageCertificateRequest13 {
+    pub(crate) certificate_request_context: Vec<u8>,
+    pub(crate) signature_hash_algorithms: Vec<SignatureHashAlgorithm>,
+}
+
+impl HandshakeMessageCertificateRequest13 {
+    pub fn handshake_type(&self) -> HandshakeType {
+        HandshakeType::CertificateRequest
+    }
+
+    fn extension(&self) -> Extension {
+        Extension::SupportedSignatureAlgorithms(ExtensionSupportedSignatureAlgorithms {
+            signature_hash_algorithms: self.signature_hash_algorithms.clone(),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        1 + self.certificate_request_context.len() + 2 + self.extension().size()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(self.certificate_request_context.len() as u8)?;
+        writer.write_all(&self.certificate_request_context)?;
+
+        let mut extension_buffer = vec![];
+        {
+            let mut extension_writer = BufWriter::<&mut Vec<u8>>::new(extension_buffer.as_mut());
+            self.extension().marshal(&mut extension_writer)?;
+        }
+        writer.write_u16::<BigEndian>(extension_buffer.len() as u16)?;
+        writer.write_all(&extension_buffer)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let context_len = reader.read_u8()? as usize;
+        let mut certificate_request_context = vec![0u8; context_len];
+        reader.read_exact(&mut certificate_request_context)?;
+
+        let extension_buffer_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut extension_buffer = vec![0u8; extension_buffer_len];
+        reader.read_exact(&mut extension_buffer)?;
+
+        let mut signature_hash_algorithms = vec![];
+        let mut offset = 0;
+        while offset + 4 <= extension_buffer_len {
+            let mut extension_reader = BufReader::new(&extension_buffer[offset..]);
+            if let Ok(Extension::SupportedSignatureAlgorithms(e)) =
+                Extension::unmarshal(&mut extension_reader)
+            {
+                signature_hash_algorithms = e.signature_hash_algorithms;
+            }
+
+            let extension_len =
+                u16::from_be_bytes([extension_buffer[offset + 2], extension_buffer[offset + 3]])
+                    as usize;
+            offset += 4 + extension_len;
+        }
+
+        Ok(HandshakeMessageCertificateRequest13 {
+            certificate_request_context,
+            signature_hash_algorithms,
+        })
+    }
+}
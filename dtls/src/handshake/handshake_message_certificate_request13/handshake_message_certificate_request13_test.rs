@@ -0,0 +1,45 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_handshake_message_certificate_request13() -> Result<()> {
+    let raw_certificate_request = vec![
+        0x00, 0x00, 0x0a, 0x00, 0x0d, 0x00, 0x06, 0x00, 0x04, 0x04, 0x03, 0x08, 0x07,
+    ];
+    let parsed_certificate_request = HandshakeMessageCertificateRequest13 {
+        certificate_request_context: vec![],
+        signature_hash_algorithms: vec![
+            SignatureHashAlgorithm {
+                hash: HashAlgorithm::Sha256,
+                signature: SignatureAlgorithm::Ecdsa,
+            },
+            SignatureHashAlgorithm {
+                hash: HashAlgorithm::Ed25519,
+                signature: SignatureAlgorithm::Ed25519,
+            },
+        ],
+    };
+
+    let mut reader = BufReader::new(raw_certificate_request.as_slice());
+    let c = HandshakeMessageCertificateRequest13::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_certificate_request,
+        "handshakeMessageCertificateRequest13 unmarshal: got {:?}, want {:?}",
+        c, parsed_certificate_request
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_certificate_request,
+        "handshakeMessageCertificateRequest13 marshal: got {:?}, want {:?}",
+        raw, raw_certificate_request
+    );
+    assert_eq!(raw.len(), c.size());
+
+    Ok(())
+}
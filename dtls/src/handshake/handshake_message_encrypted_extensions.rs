@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod handshake_message_encrypted_extensions_test;
+
+use super::*;
+use crate::extension::*;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{BufReader, BufWriter};
+
+/*
+In DTLS 1.3 the server sends the extensions that are not needed to establish
+the handshake keys encrypted, right after the ServerHello.
+https://www.rfc-editor.org/rfc/rfc8446#section-4.3.1
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeMessageEncryptedExtensions {
+    pub(crate) extensions: Vec<Extension>,
+}
+
+impl HandshakeMessageEncryptedExtensions {
+    pub fn handshake_type(&self) -> HandshakeType {
+        HandshakeType::EncryptedExtensions
+    }
+
+    pub fn size(&self) -> usize {
+        2 + self.extensions.iter().map(|e| e.size()).sum::<usize>()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut extension_buffer = vec![];
+        {
+            let mut extension_writer = BufWriter::<&mut Vec<u8>>::new(extension_buffer.as_mut());
+            for extension in &self.extensions {
+                extension.marshal(&mut extension_writer)?;
+            }
+        }
+
+        writer.write_u16::<BigEndian>(extension_buffer.len() as u16)?;
+        writer.write_all(&extension_buffer)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let extension_buffer_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut extension_buffer = vec![0u8; extension_buffer_len];
+        reader.read_exact(&mut extension_buffer)?;
+
+        let mut extensions = vec![];
+        let mut offset = 0;
+        while offset + 4 <= extension_buffer_len {
+            let mut extension_reader = BufReader::new(&extension_buffer[offset..]);
+            if let Ok(extension) = Extension::unmarshal(&mut extension_reader) {
+                extensions.push(extension);
+            } else {
+                log::warn!(
+                    "Unsupported Extension Type {} {}",
+                    extension_buffer[offset],
+                    extension_buffer[offset + 1]
+                );
+            }
+
+            let extension_len =
+                u16::from_be_bytes([extension_buffer[offset + 2], extension_buffer[offset + 3]])
+                    as usize;
+            offset += 4 + extension_len;
+        }
+
+        Ok(HandshakeMessageEncryptedExtensions { extensions })
+    }
+}
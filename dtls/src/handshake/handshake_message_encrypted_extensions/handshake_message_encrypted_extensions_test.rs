@@ -0,0 +1,37 @@
+use super::*;
+use crate::extension::extension_use_srtp::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_handshake_message_encrypted_extensions() -> Result<()> {
+    let raw_encrypted_extensions = vec![
+        0x00, 0x09, 0x00, 0x0e, 0x00, 0x05, 0x00, 0x02, 0x00, 0x01, 0x00,
+    ];
+    let parsed_encrypted_extensions = HandshakeMessageEncryptedExtensions {
+        extensions: vec![Extension::UseSrtp(ExtensionUseSrtp {
+            protection_profiles: vec![SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80],
+        })],
+    };
+
+    let mut reader = BufReader::new(raw_encrypted_extensions.as_slice());
+    let c = HandshakeMessageEncryptedExtensions::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_encrypted_extensions,
+        "handshakeMessageEncryptedExtensions unmarshal: got {:?}, want {:?}",
+        c, parsed_encrypted_extensions
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_encrypted_extensions,
+        "handshakeMessageEncryptedExtensions marshal: got {:?}, want {:?}",
+        raw, raw_encrypted_extensions
+    );
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod handshake_message_key_update_test;
+
+use super::*;
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/*
+A DTLS 1.3 KeyUpdate tells the peer that the sender switches to the next
+generation of its traffic keys once the message is acknowledged. If
+update_requested is set the peer answers with a KeyUpdate of its own.
+https://www.rfc-editor.org/rfc/rfc9147#section-8
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeMessageKeyUpdate {
+    pub(crate) update_requested: bool,
+}
+
+impl HandshakeMessageKeyUpdate {
+    pub fn handshake_type(&self) -> HandshakeType {
+        HandshakeType::KeyUpdate
+    }
+
+    pub fn size(&self) -> usize {
+        1
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(self.update_requested as u8)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let update_requested = match reader.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::ErrInvalidKeyUpdate),
+        };
+
+        Ok(HandshakeMessageKeyUpdate { update_requested })
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_handshake_message_key_update() -> Result<()> {
+    let raw_key_update = vec![0x01];
+    let parsed_key_update = HandshakeMessageKeyUpdate {
+        update_requested: true,
+    };
+
+    let mut reader = BufReader::new(raw_key_update.as_slice());
+    let c = HandshakeMessageKeyUpdate::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_key_update,
+        "handshakeMessageKeyUpdate unmarshal: got {:?}, want {:?}",
+        c, parsed_key_update
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_key_update,
+        "handshakeMessageKeyUpdate marshal: got {:?}, want {:?}",
+        raw, raw_key_update
+    );
+
+    let mut reader = BufReader::new([0x02].as_ref());
+    assert_eq!(
+        HandshakeMessageKeyUpdate::unmarshal(&mut reader),
+        Err(Error::ErrInvalidKeyUpdate)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod handshake_message_new_session_ticket_test;
+
+use super::*;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/*
+   struct {
+     uint32 ticket_lifetime_hint;
+     opaque ticket<0..2^16-1>;
+   } NewSessionTicket;
+
+   The server sends NewSessionTicket before its ChangeCipherSpec to hand
+   the client a ticket the session can be resumed with, the lifetime hint
+   is in seconds.
+
+   https://tools.ietf.org/html/rfc5077#section-3.3
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeMessageNewSessionTicket {
+    pub(crate) ticket_lifetime_hint: u32,
+    pub(crate) ticket: Vec<u8>,
+}
+
+impl HandshakeMessageNewSessionTicket {
+    pub fn handshake_type(&self) -> HandshakeType {
+        HandshakeType::NewSessionTicket
+    }
+
+    pub fn size(&self) -> usize {
+        4 + 2 + self.ticket.len()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<BigEndian>(self.ticket_lifetime_hint)?;
+        writer.write_u16::<BigEndian>(self.ticket.len() as u16)?;
+        writer.write_all(&self.ticket)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let ticket_lifetime_hint = reader.read_u32::<BigEndian>()?;
+        let ticket_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut ticket = vec![0; ticket_len];
+        reader.read_exact(&mut ticket)?;
+
+        Ok(HandshakeMessageNewSessionTicket {
+            ticket_lifetime_hint,
+            ticket,
+        })
+    }
+}
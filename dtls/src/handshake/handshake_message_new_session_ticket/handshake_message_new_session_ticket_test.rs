@@ -0,0 +1,34 @@
+use super::*;
+
+use std::io::{BufReader, BufWriter};
+
+#[test]
+fn test_handshake_message_new_session_ticket() -> Result<()> {
+    let raw_new_session_ticket = vec![0x00, 0x01, 0x51, 0x80, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef];
+    let parsed_new_session_ticket = HandshakeMessageNewSessionTicket {
+        ticket_lifetime_hint: 86400,
+        ticket: vec![0xde, 0xad, 0xbe, 0xef],
+    };
+
+    let mut reader = BufReader::new(raw_new_session_ticket.as_slice());
+    let c = HandshakeMessageNewSessionTicket::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_new_session_ticket,
+        "handshake_message_new_session_ticket unmarshal: got {:?}, want {:?}",
+        c, parsed_new_session_ticket
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_new_session_ticket,
+        "handshake_message_new_session_ticket marshal: got {:?}, want {:?}",
+        raw, raw_new_session_ticket
+    );
+    assert_eq!(c.size(), raw_new_session_ticket.len());
+
+    Ok(())
+}
@@ -19,6 +19,7 @@ fn test_handshake_message_server_hello() -> Result<()> {
         SystemTime::UNIX_EPOCH
     };
     let parsed_server_hello = HandshakeMessageServerHello {
+        session_id: vec![],
         version: ProtocolVersion {
             major: 0xFE,
             minor: 0xFD,
@@ -7,6 +7,17 @@ use std::time::{Duration, SystemTime};
 pub const RANDOM_BYTES_LENGTH: usize = 28;
 pub const HANDSHAKE_RANDOM_LENGTH: usize = RANDOM_BYTES_LENGTH + 4;
 
+// A ServerHello with this random is a DTLS 1.3 HelloRetryRequest
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.1.3
+pub const HELLO_RETRY_REQUEST_RANDOM: [u8; HANDSHAKE_RANDOM_LENGTH] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+// A DTLS 1.3 server negotiating DTLS 1.2 ends its random with this value
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.1.3
+pub const DOWNGRADE_SENTINEL_TLS12: [u8; 8] = *b"DOWNGRD\x01";
+
 // https://tools.ietf.org/html/rfc4346#section-7.4.1.2
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HandshakeRandom {
@@ -58,6 +69,29 @@ impl HandshakeRandom {
         })
     }
 
+    // hello_retry_request returns the random of a HelloRetryRequest
+    pub fn hello_retry_request() -> Self {
+        let mut reader = &HELLO_RETRY_REQUEST_RANDOM[..];
+        // Reading from a slice of the right length can not fail
+        HandshakeRandom::unmarshal(&mut reader).unwrap_or_default()
+    }
+
+    pub fn is_hello_retry_request(&self) -> bool {
+        let mut random = vec![];
+        self.marshal(&mut random).is_ok() && random == HELLO_RETRY_REQUEST_RANDOM
+    }
+
+    // set_downgrade_sentinel replaces the last bytes of the random with
+    // DOWNGRADE_SENTINEL_TLS12
+    pub fn set_downgrade_sentinel(&mut self) {
+        let offset = RANDOM_BYTES_LENGTH - DOWNGRADE_SENTINEL_TLS12.len();
+        self.random_bytes[offset..].copy_from_slice(&DOWNGRADE_SENTINEL_TLS12);
+    }
+
+    pub fn is_downgrade_sentinel(&self) -> bool {
+        self.random_bytes.ends_with(&DOWNGRADE_SENTINEL_TLS12)
+    }
+
     // populate fills the HandshakeRandom with random values
     // may be called multiple times
     pub fn populate(&mut self) {
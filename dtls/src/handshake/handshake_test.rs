@@ -1,7 +1,7 @@
 use super::*;
 use crate::compression_methods::*;
 use crate::handshake::handshake_message_client_hello::*;
-use crate::handshake::handshake_random::HandshakeRandom;
+use crate::handshake::handshake_random::*;
 use crate::record_layer::record_layer_header::ProtocolVersion;
 
 use std::time::{Duration, SystemTime};
@@ -25,6 +25,7 @@ fn test_handshake_message() -> Result<()> {
             fragment_length: 0x29,
         },
         handshake_message: HandshakeMessage::ClientHello(HandshakeMessageClientHello {
+            session_id: vec![],
             version: ProtocolVersion {
                 major: 0xFE,
                 minor: 0xFD,
@@ -71,3 +72,25 @@ fn test_handshake_message() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_hello_retry_request_random() -> Result<()> {
+    let random = HandshakeRandom::hello_retry_request();
+    assert!(
+        random.is_hello_retry_request(),
+        "HelloRetryRequest random not recognized"
+    );
+
+    let mut raw = vec![];
+    random.marshal(&mut raw)?;
+    assert_eq!(raw, HELLO_RETRY_REQUEST_RANDOM.to_vec());
+
+    let mut random = HandshakeRandom::default();
+    random.populate();
+    assert!(
+        !random.is_hello_retry_request(),
+        "ServerHello random taken for a HelloRetryRequest"
+    );
+
+    Ok(())
+}
@@ -1,12 +1,17 @@
 pub mod handshake_cache;
 pub mod handshake_header;
 pub mod handshake_message_certificate;
+pub mod handshake_message_certificate13;
 pub mod handshake_message_certificate_request;
+pub mod handshake_message_certificate_request13;
 pub mod handshake_message_certificate_verify;
 pub mod handshake_message_client_hello;
 pub mod handshake_message_client_key_exchange;
+pub mod handshake_message_encrypted_extensions;
 pub mod handshake_message_finished;
 pub mod handshake_message_hello_verify_request;
+pub mod handshake_message_key_update;
+pub mod handshake_message_new_session_ticket;
 pub mod handshake_message_server_hello;
 pub mod handshake_message_server_hello_done;
 pub mod handshake_message_server_key_exchange;
@@ -23,23 +28,31 @@ use super::error::*;
 
 use handshake_header::*;
 use handshake_message_certificate::*;
+use handshake_message_certificate13::*;
 use handshake_message_certificate_request::*;
+use handshake_message_certificate_request13::*;
 use handshake_message_certificate_verify::*;
 use handshake_message_client_hello::*;
 use handshake_message_client_key_exchange::*;
+use handshake_message_encrypted_extensions::*;
 use handshake_message_finished::*;
 use handshake_message_hello_verify_request::*;
+use handshake_message_key_update::*;
+use handshake_message_new_session_ticket::*;
 use handshake_message_server_hello::*;
 use handshake_message_server_hello_done::*;
 use handshake_message_server_key_exchange::*;
 
 // https://tools.ietf.org/html/rfc5246#section-7.4
+// https://www.rfc-editor.org/rfc/rfc8446#section-4
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HandshakeType {
     HelloRequest = 0,
     ClientHello = 1,
     ServerHello = 2,
     HelloVerifyRequest = 3,
+    NewSessionTicket = 4,
+    EncryptedExtensions = 8,
     Certificate = 11,
     ServerKeyExchange = 12,
     CertificateRequest = 13,
@@ -47,6 +60,7 @@ pub enum HandshakeType {
     CertificateVerify = 15,
     ClientKeyExchange = 16,
     Finished = 20,
+    KeyUpdate = 24,
     Invalid,
 }
 
@@ -57,6 +71,8 @@ impl fmt::Display for HandshakeType {
             HandshakeType::ClientHello => write!(f, "ClientHello"),
             HandshakeType::ServerHello => write!(f, "ServerHello"),
             HandshakeType::HelloVerifyRequest => write!(f, "HelloVerifyRequest"),
+            HandshakeType::NewSessionTicket => write!(f, "NewSessionTicket"),
+            HandshakeType::EncryptedExtensions => write!(f, "EncryptedExtensions"),
             HandshakeType::Certificate => write!(f, "Certificate"),
             HandshakeType::ServerKeyExchange => write!(f, "ServerKeyExchange"),
             HandshakeType::CertificateRequest => write!(f, "CertificateRequest"),
@@ -64,6 +80,7 @@ impl fmt::Display for HandshakeType {
             HandshakeType::CertificateVerify => write!(f, "CertificateVerify"),
             HandshakeType::ClientKeyExchange => write!(f, "ClientKeyExchange"),
             HandshakeType::Finished => write!(f, "Finished"),
+            HandshakeType::KeyUpdate => write!(f, "KeyUpdate"),
             HandshakeType::Invalid => write!(f, "Invalid"),
         }
     }
@@ -76,6 +93,8 @@ impl From<u8> for HandshakeType {
             1 => HandshakeType::ClientHello,
             2 => HandshakeType::ServerHello,
             3 => HandshakeType::HelloVerifyRequest,
+            4 => HandshakeType::NewSessionTicket,
+            8 => HandshakeType::EncryptedExtensions,
             11 => HandshakeType::Certificate,
             12 => HandshakeType::ServerKeyExchange,
             13 => HandshakeType::CertificateRequest,
@@ -83,6 +102,7 @@ impl From<u8> for HandshakeType {
             15 => HandshakeType::CertificateVerify,
             16 => HandshakeType::ClientKeyExchange,
             20 => HandshakeType::Finished,
+            24 => HandshakeType::KeyUpdate,
             _ => HandshakeType::Invalid,
         }
     }
@@ -100,6 +120,7 @@ pub enum HandshakeMessage {
     ClientHello(HandshakeMessageClientHello),
     ServerHello(HandshakeMessageServerHello),
     HelloVerifyRequest(HandshakeMessageHelloVerifyRequest),
+    NewSessionTicket(HandshakeMessageNewSessionTicket),
     Certificate(HandshakeMessageCertificate),
     ServerKeyExchange(HandshakeMessageServerKeyExchange),
     CertificateRequest(HandshakeMessageCertificateRequest),
@@ -107,6 +128,12 @@ pub enum HandshakeMessage {
     CertificateVerify(HandshakeMessageCertificateVerify),
     ClientKeyExchange(HandshakeMessageClientKeyExchange),
     Finished(HandshakeMessageFinished),
+
+    // DTLS 1.3
+    EncryptedExtensions(HandshakeMessageEncryptedExtensions),
+    Certificate13(HandshakeMessageCertificate13),
+    CertificateRequest13(HandshakeMessageCertificateRequest13),
+    KeyUpdate(HandshakeMessageKeyUpdate),
 }
 
 impl HandshakeMessage {
@@ -115,6 +142,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.handshake_type(),
             HandshakeMessage::ServerHello(msg) => msg.handshake_type(),
             HandshakeMessage::HelloVerifyRequest(msg) => msg.handshake_type(),
+            HandshakeMessage::NewSessionTicket(msg) => msg.handshake_type(),
             HandshakeMessage::Certificate(msg) => msg.handshake_type(),
             HandshakeMessage::ServerKeyExchange(msg) => msg.handshake_type(),
             HandshakeMessage::CertificateRequest(msg) => msg.handshake_type(),
@@ -122,6 +150,10 @@ impl HandshakeMessage {
             HandshakeMessage::CertificateVerify(msg) => msg.handshake_type(),
             HandshakeMessage::ClientKeyExchange(msg) => msg.handshake_type(),
             HandshakeMessage::Finished(msg) => msg.handshake_type(),
+            HandshakeMessage::EncryptedExtensions(msg) => msg.handshake_type(),
+            HandshakeMessage::Certificate13(msg) => msg.handshake_type(),
+            HandshakeMessage::CertificateRequest13(msg) => msg.handshake_type(),
+            HandshakeMessage::KeyUpdate(msg) => msg.handshake_type(),
         }
     }
 
@@ -130,6 +162,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.size(),
             HandshakeMessage::ServerHello(msg) => msg.size(),
             HandshakeMessage::HelloVerifyRequest(msg) => msg.size(),
+            HandshakeMessage::NewSessionTicket(msg) => msg.size(),
             HandshakeMessage::Certificate(msg) => msg.size(),
             HandshakeMessage::ServerKeyExchange(msg) => msg.size(),
             HandshakeMessage::CertificateRequest(msg) => msg.size(),
@@ -137,6 +170,10 @@ impl HandshakeMessage {
             HandshakeMessage::CertificateVerify(msg) => msg.size(),
             HandshakeMessage::ClientKeyExchange(msg) => msg.size(),
             HandshakeMessage::Finished(msg) => msg.size(),
+            HandshakeMessage::EncryptedExtensions(msg) => msg.size(),
+            HandshakeMessage::Certificate13(msg) => msg.size(),
+            HandshakeMessage::CertificateRequest13(msg) => msg.size(),
+            HandshakeMessage::KeyUpdate(msg) => msg.size(),
         }
     }
 
@@ -145,6 +182,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.marshal(writer)?,
             HandshakeMessage::ServerHello(msg) => msg.marshal(writer)?,
             HandshakeMessage::HelloVerifyRequest(msg) => msg.marshal(writer)?,
+            HandshakeMessage::NewSessionTicket(msg) => msg.marshal(writer)?,
             HandshakeMessage::Certificate(msg) => msg.marshal(writer)?,
             HandshakeMessage::ServerKeyExchange(msg) => msg.marshal(writer)?,
             HandshakeMessage::CertificateRequest(msg) => msg.marshal(writer)?,
@@ -152,10 +190,26 @@ impl HandshakeMessage {
             HandshakeMessage::CertificateVerify(msg) => msg.marshal(writer)?,
             HandshakeMessage::ClientKeyExchange(msg) => msg.marshal(writer)?,
             HandshakeMessage::Finished(msg) => msg.marshal(writer)?,
+            HandshakeMessage::EncryptedExtensions(msg) => msg.marshal(writer)?,
+            HandshakeMessage::Certificate13(msg) => msg.marshal(writer)?,
+            HandshakeMessage::CertificateRequest13(msg) => msg.marshal(writer)?,
+            HandshakeMessage::KeyUpdate(msg) => msg.marshal(writer)?,
         }
 
         Ok(())
     }
+
+    // marshal_transcript returns the message as it enters the DTLS 1.3
+    // transcript, with a TLS handshake header instead of the DTLS one.
+    // https://www.rfc-editor.org/rfc/rfc9147#section-5.2
+    pub(crate) fn marshal_transcript(&self) -> Result<Vec<u8>> {
+        let size = self.size() as u32;
+        let mut out = vec![self.handshake_type() as u8];
+        out.extend_from_slice(&size.to_be_bytes()[1..]);
+        self.marshal(&mut out)?;
+
+        Ok(out)
+    }
 }
 
 // The handshake protocol is responsible for selecting a cipher spec and
@@ -199,9 +253,25 @@ impl Handshake {
     }
 
     pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        Handshake::unmarshal_version(reader, false)
+    }
+
+    // unmarshal_dtls13 parses the handshake messages of a DTLS 1.3 handshake,
+    // Certificate and CertificateRequest differ from DTLS 1.2.
+    pub fn unmarshal_dtls13<R: Read>(reader: &mut R) -> Result<Self> {
+        Handshake::unmarshal_version(reader, true)
+    }
+
+    fn unmarshal_version<R: Read>(reader: &mut R, dtls13: bool) -> Result<Self> {
         let handshake_header = HandshakeHeader::unmarshal(reader)?;
 
         let handshake_message = match handshake_header.handshake_type {
+            HandshakeType::Certificate if dtls13 => {
+                HandshakeMessage::Certificate13(HandshakeMessageCertificate13::unmarshal(reader)?)
+            }
+            HandshakeType::CertificateRequest if dtls13 => HandshakeMessage::CertificateRequest13(
+                HandshakeMessageCertificateRequest13::unmarshal(reader)?,
+            ),
             HandshakeType::ClientHello => {
                 HandshakeMessage::ClientHello(HandshakeMessageClientHello::unmarshal(reader)?)
             }
@@ -211,6 +281,9 @@ impl Handshake {
             HandshakeType::HelloVerifyRequest => HandshakeMessage::HelloVerifyRequest(
                 HandshakeMessageHelloVerifyRequest::unmarshal(reader)?,
             ),
+            HandshakeType::NewSessionTicket => HandshakeMessage::NewSessionTicket(
+                HandshakeMessageNewSessionTicket::unmarshal(reader)?,
+            ),
             HandshakeType::Certificate => {
                 HandshakeMessage::Certificate(HandshakeMessageCertificate::unmarshal(reader)?)
             }
@@ -232,6 +305,12 @@ impl Handshake {
             HandshakeType::Finished => {
                 HandshakeMessage::Finished(HandshakeMessageFinished::unmarshal(reader)?)
             }
+            HandshakeType::EncryptedExtensions => HandshakeMessage::EncryptedExtensions(
+                HandshakeMessageEncryptedExtensions::unmarshal(reader)?,
+            ),
+            HandshakeType::KeyUpdate => {
+                HandshakeMessage::KeyUpdate(HandshakeMessageKeyUpdate::unmarshal(reader)?)
+            }
             _ => return Err(Error::ErrNotImplemented),
         };
 
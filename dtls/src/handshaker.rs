@@ -1,10 +1,12 @@
 use crate::cipher_suite::*;
 use crate::config::*;
 use crate::conn::*;
+use crate::connection_id::*;
 use crate::content::*;
 use crate::crypto::*;
 use crate::error::*;
 use crate::extension::extension_use_srtp::*;
+use crate::session::*;
 use crate::signature_hash_algorithm::*;
 
 use log::*;
@@ -91,6 +93,12 @@ pub(crate) struct HandshakeConfig {
     pub(crate) client_cert_verifier: Option<Arc<dyn rustls::ClientCertVerifier>>,
     pub(crate) retransmit_interval: tokio::time::Duration,
     pub(crate) initial_epoch: u16,
+    pub(crate) session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+    pub(crate) session_ticket_key: Option<Vec<u8>>,
+    // Key clients store their session under
+    pub(crate) session_key: Vec<u8>,
+    pub(crate) enable_dtls13: bool, // Offer or accept DTLS 1.3
+    pub(crate) connection_id_generator: Option<ConnectionIdGenerator>,
     //log           logging.LeveledLogger
     //mu sync.Mutex
 }
@@ -115,6 +123,11 @@ impl Default for HandshakeConfig {
             client_cert_verifier: None,
             retransmit_interval: tokio::time::Duration::from_secs(0),
             initial_epoch: 0,
+            session_store: None,
+            session_ticket_key: None,
+            session_key: vec![],
+            enable_dtls13: false,
+            connection_id_generator: None,
         }
     }
 }
@@ -278,7 +291,8 @@ impl DTLSConn {
                 }
             }
         }
-        if epoch != next_epoch {
+        // DTLS 1.3 flights may already have moved to a later epoch
+        if next_epoch > self.get_local_epoch() {
             trace!(
                 "[handshake:{}] -> changeCipherSpec (epoch: {})",
                 srv_cli_str(self.state.is_client),
@@ -1,6 +1,7 @@
 #![warn(rust_2018_idioms)]
 #![allow(dead_code)]
 
+pub mod ack;
 pub mod alert;
 pub mod application_data;
 pub mod change_cipher_spec;
@@ -9,6 +10,7 @@ pub mod client_certificate_type;
 pub mod compression_methods;
 pub mod config;
 pub mod conn;
+pub mod connection_id;
 pub mod content;
 pub mod crypto;
 pub mod curve;
@@ -21,6 +23,7 @@ pub mod handshaker;
 pub mod listener;
 pub mod prf;
 pub mod record_layer;
+pub mod session;
 pub mod signature_hash_algorithm;
 pub mod state;
 
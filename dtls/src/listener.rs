@@ -1,5 +1,6 @@
 use crate::config::*;
 use crate::conn::DTLSConn;
+use crate::connection_id::*;
 use crate::content::ContentType;
 use crate::error::Result;
 use crate::record_layer::record_layer_header::RecordLayerHeader;
@@ -44,6 +45,13 @@ pub async fn listen<A: 'static + ToSocketAddrs>(laddr: A, config: Config) -> Res
         ..Default::default()
     };
 
+    // Records carrying a connection ID are routed to their conn whatever
+    // their source address, so the connection survives NAT rebinding
+    if let Some(connection_id_generator) = &config.connection_id_generator {
+        lc.datagram_router = Some(cid_datagram_router(connection_id_generator().len()));
+        lc.connection_identifier = Some(cid_conn_identifier());
+    }
+
     let parent = Arc::new(lc.listen(laddr).await?);
     Ok(DTLSListener { parent, config })
 }
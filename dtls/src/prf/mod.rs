@@ -7,9 +7,10 @@ use std::fmt;
 use hmac::{Hmac, Mac, NewMac};
 use sha1::Sha1;
 use sha2::Digest;
-use sha2::Sha256;
+use sha2::{Sha256, Sha384};
 
 type HmacSha256 = Hmac<Sha256>;
+type HmacSha384 = Hmac<Sha384>;
 type HmacSha1 = Hmac<Sha1>;
 
 use crate::cipher_suite::CipherSuiteHash;
@@ -141,11 +142,14 @@ fn elliptic_curve_pre_master_secret(
 //
 // https://tools.ietf.org/html/rfc4346w
 fn hmac_sha(h: CipherSuiteHash, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
-    let mut mac = match h {
-        CipherSuiteHash::Sha256 => {
-            HmacSha256::new_varkey(key).map_err(|e| Error::Other(e.to_string()))?
-        }
-    };
+    match h {
+        CipherSuiteHash::Sha256 => hmac::<HmacSha256>(key, data),
+        CipherSuiteHash::Sha384 => hmac::<HmacSha384>(key, data),
+    }
+}
+
+fn hmac<M: Mac + NewMac>(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = M::new_varkey(key).map_err(|e| Error::Other(e.to_string()))?;
     mac.update(data);
     let result = mac.finalize();
     let code_bytes = result.into_bytes();
@@ -252,11 +256,7 @@ pub(crate) fn prf_verify_data(
     label: &str,
     h: CipherSuiteHash,
 ) -> Result<Vec<u8>> {
-    let mut hasher = match h {
-        CipherSuiteHash::Sha256 => Sha256::new(),
-    };
-    hasher.update(handshake_bodies);
-    let result = hasher.finalize();
+    let result = prf_transcript_hash(handshake_bodies, h);
     let mut seed = label.as_bytes().to_vec();
     seed.extend_from_slice(&result);
 
@@ -314,3 +314,185 @@ pub(crate) fn prf_mac(
 
     Ok(result.into_bytes().to_vec())
 }
+
+pub(crate) const PRF_DTLS13_LABEL_PREFIX: &str = "dtls13";
+pub(crate) const PRF_DERIVED_LABEL: &str = "derived";
+pub(crate) const PRF_CLIENT_HANDSHAKE_TRAFFIC_LABEL: &str = "c hs traffic";
+pub(crate) const PRF_SERVER_HANDSHAKE_TRAFFIC_LABEL: &str = "s hs traffic";
+pub(crate) const PRF_CLIENT_APPLICATION_TRAFFIC_LABEL: &str = "c ap traffic";
+pub(crate) const PRF_SERVER_APPLICATION_TRAFFIC_LABEL: &str = "s ap traffic";
+pub(crate) const PRF_EXPORTER_MASTER_LABEL: &str = "exp master";
+pub(crate) const PRF_TRAFFIC_UPDATE_LABEL: &str = "traffic upd";
+pub(crate) const PRF_FINISHED_LABEL: &str = "finished";
+pub(crate) const PRF_EXPORTER_LABEL: &str = "exporter";
+pub(crate) const PRF_KEY_LABEL: &str = "key";
+pub(crate) const PRF_IV_LABEL: &str = "iv";
+pub(crate) const PRF_SN_LABEL: &str = "sn";
+
+// Record protection keys derived from a DTLS 1.3 traffic secret
+// https://www.rfc-editor.org/rfc/rfc9147#section-4.2.3
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct TrafficKeys {
+    pub(crate) write_key: Vec<u8>,
+    pub(crate) write_iv: Vec<u8>,
+    pub(crate) sn_key: Vec<u8>,
+}
+
+pub(crate) fn prf_transcript_hash(handshake_bodies: &[u8], h: CipherSuiteHash) -> Vec<u8> {
+    match h {
+        CipherSuiteHash::Sha256 => Sha256::digest(handshake_bodies).to_vec(),
+        CipherSuiteHash::Sha384 => Sha384::digest(handshake_bodies).to_vec(),
+    }
+}
+
+// HKDF-Extract, an absent salt is a string of hash length zeros
+// https://tools.ietf.org/html/rfc5869#section-2.2
+pub(crate) fn prf_hkdf_extract(salt: &[u8], ikm: &[u8], h: CipherSuiteHash) -> Result<Vec<u8>> {
+    if salt.is_empty() {
+        hmac_sha(h, &vec![0u8; h.size()], ikm)
+    } else {
+        hmac_sha(h, salt, ikm)
+    }
+}
+
+// HKDF-Expand
+// https://tools.ietf.org/html/rfc5869#section-2.3
+pub(crate) fn prf_hkdf_expand(
+    prk: &[u8],
+    info: &[u8],
+    length: usize,
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    let mut last_round = vec![];
+    let mut out = vec![];
+
+    let iterations = ((length as f64) / (h.size() as f64)).ceil() as usize;
+    for i in 0..iterations {
+        let mut data = last_round;
+        data.extend_from_slice(info);
+        data.push((i + 1) as u8);
+        last_round = hmac_sha(h, prk, &data)?;
+
+        out.extend_from_slice(&last_round);
+    }
+
+    Ok(out[..length].to_vec())
+}
+
+// HKDF-Expand-Label with the "dtls13" label prefix of DTLS 1.3
+// https://www.rfc-editor.org/rfc/rfc9147#section-5.9
+pub(crate) fn prf_hkdf_expand_label(
+    secret: &[u8],
+    label: &str,
+    context: &[u8],
+    length: usize,
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    let full_label_len = PRF_DTLS13_LABEL_PREFIX.len() + label.len();
+
+    let mut hkdf_label = Vec::with_capacity(2 + 1 + full_label_len + 1 + context.len());
+    hkdf_label.extend_from_slice(&(length as u16).to_be_bytes());
+    hkdf_label.push(full_label_len as u8);
+    hkdf_label.extend_from_slice(PRF_DTLS13_LABEL_PREFIX.as_bytes());
+    hkdf_label.extend_from_slice(label.as_bytes());
+    hkdf_label.push(context.len() as u8);
+    hkdf_label.extend_from_slice(context);
+
+    prf_hkdf_expand(secret, &hkdf_label, length, h)
+}
+
+// Derive-Secret, the transcript is already hashed by the caller
+// https://www.rfc-editor.org/rfc/rfc8446#section-7.1
+pub(crate) fn prf_derive_secret(
+    secret: &[u8],
+    label: &str,
+    transcript_hash: &[u8],
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    prf_hkdf_expand_label(secret, label, transcript_hash, h.size(), h)
+}
+
+// prf_handshake_secret runs the key schedule from the (EC)DHE shared secret
+// up to the Handshake Secret, no PSK is used so the Early Secret is derived
+// from zeros.
+pub(crate) fn prf_handshake_secret(shared_secret: &[u8], h: CipherSuiteHash) -> Result<Vec<u8>> {
+    let early_secret = prf_hkdf_extract(&[], &vec![0u8; h.size()], h)?;
+    let derived = prf_derive_secret(
+        &early_secret,
+        PRF_DERIVED_LABEL,
+        &prf_transcript_hash(&[], h),
+        h,
+    )?;
+    prf_hkdf_extract(&derived, shared_secret, h)
+}
+
+// prf_main_secret derives the Master Secret of the key schedule that all
+// application traffic secrets are derived from.
+pub(crate) fn prf_main_secret(handshake_secret: &[u8], h: CipherSuiteHash) -> Result<Vec<u8>> {
+    let derived = prf_derive_secret(
+        handshake_secret,
+        PRF_DERIVED_LABEL,
+        &prf_transcript_hash(&[], h),
+        h,
+    )?;
+    prf_hkdf_extract(&derived, &vec![0u8; h.size()], h)
+}
+
+pub(crate) fn prf_traffic_keys(
+    traffic_secret: &[u8],
+    key_len: usize,
+    iv_len: usize,
+    h: CipherSuiteHash,
+) -> Result<TrafficKeys> {
+    Ok(TrafficKeys {
+        write_key: prf_hkdf_expand_label(traffic_secret, PRF_KEY_LABEL, &[], key_len, h)?,
+        write_iv: prf_hkdf_expand_label(traffic_secret, PRF_IV_LABEL, &[], iv_len, h)?,
+        sn_key: prf_hkdf_expand_label(traffic_secret, PRF_SN_LABEL, &[], key_len, h)?,
+    })
+}
+
+// prf_next_traffic_secret computes the traffic secret that replaces the
+// current one after a KeyUpdate
+// https://www.rfc-editor.org/rfc/rfc8446#section-7.2
+pub(crate) fn prf_next_traffic_secret(
+    traffic_secret: &[u8],
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    prf_hkdf_expand_label(traffic_secret, PRF_TRAFFIC_UPDATE_LABEL, &[], h.size(), h)
+}
+
+// prf_finished_verify_data computes the verify_data of a DTLS 1.3 Finished
+// message from the handshake traffic secret of the sender.
+// https://www.rfc-editor.org/rfc/rfc8446#section-4.4.4
+pub(crate) fn prf_finished_verify_data(
+    base_key: &[u8],
+    transcript_hash: &[u8],
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    let finished_key = prf_hkdf_expand_label(base_key, PRF_FINISHED_LABEL, &[], h.size(), h)?;
+    hmac_sha(h, &finished_key, transcript_hash)
+}
+
+// prf_exporter implements the DTLS 1.3 exporter interface
+// https://www.rfc-editor.org/rfc/rfc8446#section-7.5
+pub(crate) fn prf_exporter(
+    exporter_master_secret: &[u8],
+    label: &str,
+    context: &[u8],
+    length: usize,
+    h: CipherSuiteHash,
+) -> Result<Vec<u8>> {
+    let secret = prf_derive_secret(
+        exporter_master_secret,
+        label,
+        &prf_transcript_hash(&[], h),
+        h,
+    )?;
+    prf_hkdf_expand_label(
+        &secret,
+        PRF_EXPORTER_LABEL,
+        &prf_transcript_hash(context, h),
+        length,
+        h,
+    )
+}
@@ -263,3 +263,91 @@ fn test_verify_data() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_hkdf() -> Result<()> {
+    // RFC 5869 Appendix A.1
+    let ikm = vec![0x0b; 22];
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    let expected_prk = vec![
+        0x07, 0x77, 0x09, 0x36, 0x2c, 0x2e, 0x32, 0xdf, 0x0d, 0xdc, 0x3f, 0x0d, 0xc4, 0x7b, 0xba,
+        0x63, 0x90, 0xb6, 0xc7, 0x3b, 0xb5, 0x0f, 0x9c, 0x31, 0x22, 0xec, 0x84, 0x4a, 0xd7, 0xc2,
+        0xb3, 0xe5,
+    ];
+    let expected_okm = vec![
+        0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36, 0x2f,
+        0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56, 0xec, 0xc4,
+        0xc5, 0xbf, 0x34, 0x00, 0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65,
+    ];
+
+    let prk = prf_hkdf_extract(&salt, &ikm, CipherSuiteHash::Sha256)?;
+    assert_eq!(
+        expected_prk, prk,
+        "PRK exp: {:?} actual: {:?}",
+        expected_prk, prk
+    );
+
+    let okm = prf_hkdf_expand(&prk, &info, 42, CipherSuiteHash::Sha256)?;
+    assert_eq!(
+        expected_okm, okm,
+        "OKM exp: {:?} actual: {:?}",
+        expected_okm, okm
+    );
+
+    // Early Secret without PSK, RFC 8448 Section 3
+    let expected_early_secret = vec![
+        0x33, 0xad, 0x0a, 0x1c, 0x60, 0x7e, 0xc0, 0x3b, 0x09, 0xe6, 0xcd, 0x98, 0x93, 0x68, 0x0c,
+        0xe2, 0x10, 0xad, 0xf3, 0x00, 0xaa, 0x1f, 0x26, 0x60, 0xe1, 0xb2, 0x2e, 0x10, 0xf1, 0x70,
+        0xf9, 0x2a,
+    ];
+    let early_secret = prf_hkdf_extract(&[], &[0u8; 32], CipherSuiteHash::Sha256)?;
+    assert_eq!(
+        expected_early_secret, early_secret,
+        "EarlySecret exp: {:?} actual: {:?}",
+        expected_early_secret, early_secret
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_prf_sha384() -> Result<()> {
+    // TLS 1.2 PRF test vector for P_SHA384
+    let secret = vec![
+        0xb8, 0x0b, 0x73, 0x3d, 0x6c, 0xee, 0xfc, 0xdc, 0x71, 0x56, 0x6e, 0xa4, 0x8e, 0x55, 0x67,
+        0xdf,
+    ];
+    let mut seed = b"test label".to_vec();
+    seed.extend_from_slice(&[
+        0xcd, 0x66, 0x5c, 0xf6, 0xa8, 0x44, 0x7d, 0xd6, 0xff, 0x8b, 0x27, 0x55, 0x5e, 0xdb, 0x74,
+        0x65,
+    ]);
+    let expected_output = vec![
+        0x7b, 0x0c, 0x18, 0xe9, 0xce, 0xd4, 0x10, 0xed, 0x18, 0x04, 0xf2, 0xcf, 0xa3, 0x4a, 0x33,
+        0x6a, 0x1c, 0x14, 0xdf, 0xfb, 0x49, 0x00, 0xbb, 0x5f, 0xd7, 0x94, 0x21, 0x07, 0xe8, 0x1c,
+        0x83, 0xcd, 0xe9, 0xca, 0x0f, 0xaa, 0x60, 0xbe, 0x9f, 0xe3, 0x4f, 0x82, 0xb1, 0x23, 0x3c,
+        0x91, 0x46, 0xa0, 0xe5, 0x34, 0xcb, 0x40, 0x0f, 0xed, 0x27, 0x00, 0x88, 0x4f, 0x9d, 0xc2,
+        0x36, 0xf8, 0x0e, 0xdd, 0x8b, 0xfa, 0x96, 0x11, 0x44, 0xc9, 0xe8, 0xd7, 0x92, 0xec, 0xa7,
+        0x22, 0xa7, 0xb3, 0x2f, 0xc3, 0xd4, 0x16, 0xd4, 0x73, 0xeb, 0xc2, 0xc5, 0xfd, 0x4a, 0xbf,
+        0xda, 0xd0, 0x5d, 0x91, 0x84, 0x25, 0x9b, 0x5b, 0xf8, 0xcd, 0x4d, 0x90, 0xfa, 0x0d, 0x31,
+        0xe2, 0xde, 0xc4, 0x79, 0xe4, 0xf1, 0xa2, 0x60, 0x66, 0xf2, 0xee, 0xa9, 0xa6, 0x92, 0x36,
+        0xa3, 0xe5, 0x26, 0x55, 0xc9, 0xe9, 0xae, 0xe6, 0x91, 0xc8, 0xf3, 0xa2, 0x68, 0x54, 0x30,
+        0x8d, 0x5e, 0xaa, 0x3b, 0xe8, 0x5e, 0x09, 0x90, 0x70, 0x3d, 0x73, 0xe5, 0x6f,
+    ];
+
+    let output = prf_p_hash(
+        &secret,
+        &seed,
+        expected_output.len(),
+        CipherSuiteHash::Sha384,
+    )?;
+
+    assert_eq!(
+        expected_output, output,
+        "P_SHA384 exp: {:?} actual: {:?}",
+        expected_output, output
+    );
+
+    Ok(())
+}
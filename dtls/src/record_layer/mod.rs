@@ -1,15 +1,19 @@
+pub mod record_layer_cid_header;
 pub mod record_layer_header;
+pub mod record_layer_unified_header;
 
 #[cfg(test)]
 mod record_layer_test;
 
 use super::content::*;
 use super::error::*;
+use crate::ack::Ack;
 use crate::alert::Alert;
 use crate::application_data::ApplicationData;
 use crate::change_cipher_spec::ChangeCipherSpec;
 use crate::handshake::Handshake;
 use record_layer_header::*;
+use record_layer_unified_header::*;
 
 use std::io::{Read, Write};
 
@@ -66,6 +70,7 @@ impl RecordLayer {
                 Content::ChangeCipherSpec(ChangeCipherSpec::unmarshal(reader)?)
             }
             ContentType::Handshake => Content::Handshake(Handshake::unmarshal(reader)?),
+            ContentType::Ack => Content::Ack(Ack::unmarshal(reader)?),
             _ => return Err(Error::Other("Invalid Content Type".to_owned())),
         };
 
@@ -82,18 +87,54 @@ impl RecordLayer {
 // two DTLS messages into the same datagram: in the same record or in
 // separate records.
 // https://tools.ietf.org/html/rfc6347#section-4.2.3
+//
+// DTLS 1.3 encrypted records use the unified header, a record without a
+// length field extends to the end of the datagram.
+// https://www.rfc-editor.org/rfc/rfc9147#section-4
 pub(crate) fn unpack_datagram(buf: &[u8]) -> Result<Vec<Vec<u8>>> {
+    unpack_datagram_with_cid(buf, 0)
+}
+
+// unpack_datagram_with_cid also splits tls12_cid records, their length field
+// follows a connection ID of connection_id_len bytes.
+// https://www.rfc-editor.org/rfc/rfc9146#section-4
+pub(crate) fn unpack_datagram_with_cid(
+    buf: &[u8],
+    connection_id_len: usize,
+) -> Result<Vec<Vec<u8>>> {
     let mut out = vec![];
 
     let mut offset = 0;
     while buf.len() != offset {
-        if buf.len() - offset <= RECORD_LAYER_HEADER_SIZE {
+        if is_unified_header(buf[offset]) {
+            let mut reader = &buf[offset..];
+            let h = RecordLayerUnifiedHeader::unmarshal(&mut reader)
+                .map_err(|_| Error::ErrInvalidPacketLength)?;
+            let pkt_len = match h.content_len {
+                Some(content_len) => h.size() + content_len as usize,
+                None => buf.len() - offset,
+            };
+            if offset + pkt_len > buf.len() {
+                return Err(Error::ErrInvalidPacketLength);
+            }
+
+            out.push(buf[offset..offset + pkt_len].to_vec());
+            offset += pkt_len;
+            continue;
+        }
+
+        let header_size = if buf[offset] == ContentType::Tls12Cid as u8 {
+            RECORD_LAYER_HEADER_SIZE + connection_id_len
+        } else {
+            RECORD_LAYER_HEADER_SIZE
+        };
+        if buf.len() - offset <= header_size {
             return Err(Error::ErrInvalidPacketLength);
         }
 
-        let pkt_len = RECORD_LAYER_HEADER_SIZE
-            + (((buf[offset + RECORD_LAYER_HEADER_SIZE - 2] as usize) << 8)
-                | buf[offset + RECORD_LAYER_HEADER_SIZE - 1] as usize);
+        let pkt_len = header_size
+            + (((buf[offset + header_size - 2] as usize) << 8)
+                | buf[offset + header_size - 1] as usize);
         if offset + pkt_len > buf.len() {
             return Err(Error::ErrInvalidPacketLength);
         }
@@ -0,0 +1,127 @@
+use super::record_layer_header::*;
+use crate::content::*;
+use crate::error::*;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+// Encrypted DTLS 1.2 records sent to a peer which negotiated a connection ID
+// carry the tls12_cid content type and the connection ID after the sequence
+// number, the real content type is moved into the encrypted
+// DTLSInnerPlaintext.
+//  struct {
+//      ContentType outer_type = tls12_cid;
+//      ProtocolVersion version;
+//      uint16 epoch;
+//      uint48 sequence_number;
+//      opaque cid[cid_length];
+//      uint16 length;
+//      opaque enc_content[DTLSCiphertext.length];
+//  } DTLSCiphertext;
+// https://www.rfc-editor.org/rfc/rfc9146#section-4
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RecordLayerCidHeader {
+    pub protocol_version: ProtocolVersion,
+    pub epoch: u16,
+    pub sequence_number: u64, // uint48 in spec
+    pub connection_id: Vec<u8>,
+    pub content_len: u16,
+}
+
+impl RecordLayerCidHeader {
+    pub fn size(&self) -> usize {
+        RECORD_LAYER_HEADER_SIZE + self.connection_id.len()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.sequence_number > MAX_SEQUENCE_NUMBER {
+            return Err(Error::ErrSequenceNumberOverflow);
+        }
+
+        writer.write_u8(ContentType::Tls12Cid as u8)?;
+        writer.write_u8(self.protocol_version.major)?;
+        writer.write_u8(self.protocol_version.minor)?;
+        writer.write_u16::<BigEndian>(self.epoch)?;
+
+        let be: [u8; 8] = self.sequence_number.to_be_bytes();
+        writer.write_all(&be[2..])?; // uint48 in spec
+
+        writer.write_all(&self.connection_id)?;
+        writer.write_u16::<BigEndian>(self.content_len)?;
+
+        Ok(writer.flush()?)
+    }
+
+    // The connection ID length is not sent on the wire, the receiver knows the
+    // length of the connection ID it asked for.
+    pub fn unmarshal<R: Read>(reader: &mut R, connection_id_len: usize) -> Result<Self> {
+        let content_type: ContentType = reader.read_u8()?.into();
+        if content_type != ContentType::Tls12Cid {
+            return Err(Error::ErrInvalidContentType);
+        }
+        let major = reader.read_u8()?;
+        let minor = reader.read_u8()?;
+        let epoch = reader.read_u16::<BigEndian>()?;
+
+        // SequenceNumber is stored as uint48, make into uint64
+        let mut be: [u8; 8] = [0u8; 8];
+        reader.read_exact(&mut be[2..])?;
+        let sequence_number = u64::from_be_bytes(be);
+
+        let protocol_version = ProtocolVersion { major, minor };
+        if protocol_version != PROTOCOL_VERSION1_2 {
+            return Err(Error::ErrUnsupportedProtocolVersion);
+        }
+
+        let mut connection_id = vec![0u8; connection_id_len];
+        reader.read_exact(&mut connection_id)?;
+        let content_len = reader.read_u16::<BigEndian>()?;
+
+        Ok(RecordLayerCidHeader {
+            protocol_version,
+            epoch,
+            sequence_number,
+            connection_id,
+            content_len,
+        })
+    }
+
+    // inner_plaintext appends the real content type to the content, no
+    // padding is added.
+    pub fn inner_plaintext(content_type: ContentType, content: &[u8]) -> Vec<u8> {
+        let mut inner = Vec::with_capacity(content.len() + 1);
+        inner.extend_from_slice(content);
+        inner.push(content_type as u8);
+        inner
+    }
+
+    // record_from_inner_plaintext strips the padding of a decrypted
+    // DTLSInnerPlaintext and returns the record with the real content type
+    // and a DTLS 1.2 header, so it can be handled like any other record.
+    pub fn record_from_inner_plaintext(&self, mut inner: Vec<u8>) -> Result<Vec<u8>> {
+        // The last non-zero byte is the content type
+        while let Some(&last) = inner.last() {
+            if last != 0 {
+                break;
+            }
+            inner.pop();
+        }
+        let content_type = match inner.pop() {
+            Some(t) => ContentType::from(t),
+            None => return Err(Error::ErrInvalidContentType),
+        };
+
+        let h = RecordLayerHeader {
+            content_type,
+            protocol_version: self.protocol_version,
+            epoch: self.epoch,
+            sequence_number: self.sequence_number,
+            content_len: inner.len() as u16,
+        };
+
+        let mut r = Vec::with_capacity(RECORD_LAYER_HEADER_SIZE + inner.len());
+        h.marshal(&mut r)?;
+        r.extend_from_slice(&inner);
+        Ok(r)
+    }
+}
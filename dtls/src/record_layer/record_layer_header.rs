@@ -11,6 +11,9 @@ pub const MAX_SEQUENCE_NUMBER: u64 = 0x0000FFFFFFFFFFFF;
 pub const DTLS1_2MAJOR: u8 = 0xfe;
 pub const DTLS1_2MINOR: u8 = 0xfd;
 
+pub const DTLS1_3MAJOR: u8 = 0xfe;
+pub const DTLS1_3MINOR: u8 = 0xfc;
+
 pub const DTLS1_0MAJOR: u8 = 0xfe;
 pub const DTLS1_0MINOR: u8 = 0xff;
 
@@ -26,6 +29,12 @@ pub const PROTOCOL_VERSION1_2: ProtocolVersion = ProtocolVersion {
     major: DTLS1_2MAJOR,
     minor: DTLS1_2MINOR,
 };
+// DTLS 1.3 is negotiated with the supported_versions extension only, its
+// records keep using the DTLS 1.2 version number on the wire.
+pub const PROTOCOL_VERSION1_3: ProtocolVersion = ProtocolVersion {
+    major: DTLS1_3MAJOR,
+    minor: DTLS1_3MINOR,
+};
 
 // https://tools.ietf.org/html/rfc4346#section-6.2.1
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -1,4 +1,6 @@
+use super::record_layer_cid_header::*;
 use super::record_layer_header::*;
+use super::record_layer_unified_header::*;
 use super::*;
 use crate::change_cipher_spec::ChangeCipherSpec;
 
@@ -35,6 +37,33 @@ fn test_udp_decode() -> Result<()> {
             ],
             None,
         ),
+        (
+            "Unified header with length, multi packet",
+            vec![
+                0x2e, 0x00, 0x05, 0x00, 0x02, 0xaa, 0xbb, 0x14, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x12, 0x00, 0x01, 0x01,
+            ],
+            vec![
+                vec![0x2e, 0x00, 0x05, 0x00, 0x02, 0xaa, 0xbb],
+                vec![
+                    0x14, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x01,
+                    0x01,
+                ],
+            ],
+            None,
+        ),
+        (
+            "Unified header without length",
+            vec![0x23, 0x05, 0xaa, 0xbb, 0xcc],
+            vec![vec![0x23, 0x05, 0xaa, 0xbb, 0xcc]],
+            None,
+        ),
+        (
+            "Unified header declared invalid length",
+            vec![0x2e, 0x00, 0x05, 0x00, 0x03, 0xaa, 0xbb],
+            vec![],
+            Some(Error::ErrInvalidPacketLength),
+        ),
         (
             "Invalid packet length",
             vec![0x14, 0xfe],
@@ -127,3 +156,107 @@ fn test_record_layer_round_trip() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_record_layer_unified_header_round_trip() -> Result<()> {
+    let tests = vec![
+        (
+            "16 bit sequence number with length",
+            vec![0x2e, 0x12, 0x34, 0x00, 0x20],
+            RecordLayerUnifiedHeader {
+                epoch: 2,
+                sequence_number: 0x1234,
+                sequence_number_len: 2,
+                content_len: Some(0x20),
+            },
+        ),
+        (
+            "8 bit sequence number without length",
+            vec![0x23, 0x56],
+            RecordLayerUnifiedHeader {
+                epoch: 3,
+                sequence_number: 0x56,
+                sequence_number_len: 1,
+                content_len: None,
+            },
+        ),
+    ];
+
+    for (name, data, want) in tests {
+        let mut reader = BufReader::new(data.as_slice());
+        let h = RecordLayerUnifiedHeader::unmarshal(&mut reader)?;
+        assert_eq!(
+            want, h,
+            "{} unified header unmarshal: got {:?}, want {:?}",
+            name, h, want
+        );
+        assert_eq!(data.len(), h.size(), "{} unified header size", name);
+
+        let mut data2 = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(data2.as_mut());
+            h.marshal(&mut writer)?;
+        }
+        assert_eq!(
+            data, data2,
+            "{} unified header marshal: got {:?}, want {:?}",
+            name, data2, data
+        );
+    }
+
+    // Connection IDs are not negotiated for DTLS 1.3
+    let mut reader = BufReader::new([0x3e, 0x00, 0x00, 0x00, 0x00].as_ref());
+    assert_eq!(
+        RecordLayerUnifiedHeader::unmarshal(&mut reader),
+        Err(Error::ErrInvalidUnifiedHeader)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_record_layer_cid_header_round_trip() -> Result<()> {
+    let data = vec![
+        0x19, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0xaa, 0xbb, 0xcc, 0x00,
+        0x03, 0x01, 0x02, 0x03,
+    ];
+    let want = RecordLayerCidHeader {
+        protocol_version: PROTOCOL_VERSION1_2,
+        epoch: 1,
+        sequence_number: 7,
+        connection_id: vec![0xaa, 0xbb, 0xcc],
+        content_len: 3,
+    };
+
+    let mut reader = BufReader::new(data.as_slice());
+    let h = RecordLayerCidHeader::unmarshal(&mut reader, 3)?;
+    assert_eq!(
+        want, h,
+        "cid header unmarshal: got {:?}, want {:?}",
+        h, want
+    );
+    assert_eq!(data.len() - 3, h.size(), "cid header size");
+
+    let mut data2 = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(data2.as_mut());
+        h.marshal(&mut writer)?;
+    }
+    assert_eq!(&data[..h.size()], data2.as_slice(), "cid header marshal");
+
+    // The length of a tls12_cid record follows the connection ID
+    let pkts = unpack_datagram_with_cid(&data, 3)?;
+    assert_eq!(vec![data.clone()], pkts, "cid record UDP decode");
+
+    // The real content type is the last non-zero byte of the inner plaintext
+    let mut inner = RecordLayerCidHeader::inner_plaintext(ContentType::ApplicationData, &[0x01]);
+    inner.extend_from_slice(&[0x00, 0x00]);
+    let record = h.record_from_inner_plaintext(inner)?;
+    assert_eq!(
+        record,
+        vec![0x17, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x01, 0x01,],
+        "cid record from inner plaintext"
+    );
+
+    Ok(())
+}
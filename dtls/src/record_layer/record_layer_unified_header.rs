@@ -0,0 +1,105 @@
+use crate::error::*;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+pub const UNIFIED_HEADER_FIXED_BITS: u8 = 0x20;
+pub const UNIFIED_HEADER_FIXED_BITS_MASK: u8 = 0xe0;
+pub const UNIFIED_HEADER_CID_BIT: u8 = 0x10;
+pub const UNIFIED_HEADER_SEQUENCE_NUMBER_BIT: u8 = 0x08;
+pub const UNIFIED_HEADER_LENGTH_BIT: u8 = 0x04;
+pub const UNIFIED_HEADER_EPOCH_MASK: u8 = 0x03;
+
+// is_unified_header tells a DTLS 1.3 encrypted record from a DTLSPlaintext
+// one by its first byte.
+pub fn is_unified_header(first_byte: u8) -> bool {
+    first_byte & UNIFIED_HEADER_FIXED_BITS_MASK == UNIFIED_HEADER_FIXED_BITS
+}
+
+// DTLS 1.3 encrypted records carry a compact unified header, only the low
+// bits of the epoch and the sequence number are sent and the sequence number
+// is encrypted.
+//  0 1 2 3 4 5 6 7
+// +-+-+-+-+-+-+-+-+
+// |0|0|1|C|S|L|E E|
+// +-+-+-+-+-+-+-+-+
+// | Connection ID |   Legend:
+// | (if any,      |
+// /  length as    /   C   - Connection ID (CID) present
+// |  negotiated)  |   S   - Sequence number length
+// +-+-+-+-+-+-+-+-+   L   - Length present
+// |  8 or 16 bit  |   E   - Epoch
+// |Sequence Number|
+// +-+-+-+-+-+-+-+-+
+// | 16 bit Length |
+// | (if present)  |
+// +-+-+-+-+-+-+-+-+
+// https://www.rfc-editor.org/rfc/rfc9147#section-4
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct RecordLayerUnifiedHeader {
+    pub epoch: u16,                 // low two bits on the wire
+    pub sequence_number: u64,       // low 8 or 16 bits on the wire
+    pub sequence_number_len: usize, // 1 or 2
+    pub content_len: Option<u16>,
+}
+
+impl RecordLayerUnifiedHeader {
+    pub fn size(&self) -> usize {
+        1 + self.sequence_number_len + if self.content_len.is_some() { 2 } else { 0 }
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut first_byte =
+            UNIFIED_HEADER_FIXED_BITS | (self.epoch as u8 & UNIFIED_HEADER_EPOCH_MASK);
+        if self.sequence_number_len == 2 {
+            first_byte |= UNIFIED_HEADER_SEQUENCE_NUMBER_BIT;
+        } else if self.sequence_number_len != 1 {
+            return Err(Error::ErrInvalidUnifiedHeader);
+        }
+        if self.content_len.is_some() {
+            first_byte |= UNIFIED_HEADER_LENGTH_BIT;
+        }
+        writer.write_u8(first_byte)?;
+
+        if self.sequence_number_len == 2 {
+            writer.write_u16::<BigEndian>(self.sequence_number as u16)?;
+        } else {
+            writer.write_u8(self.sequence_number as u8)?;
+        }
+
+        if let Some(content_len) = self.content_len {
+            writer.write_u16::<BigEndian>(content_len)?;
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let first_byte = reader.read_u8()?;
+        // Connection IDs are not negotiated for DTLS 1.3
+        if !is_unified_header(first_byte) || first_byte & UNIFIED_HEADER_CID_BIT != 0 {
+            return Err(Error::ErrInvalidUnifiedHeader);
+        }
+
+        let epoch = (first_byte & UNIFIED_HEADER_EPOCH_MASK) as u16;
+        let (sequence_number, sequence_number_len) =
+            if first_byte & UNIFIED_HEADER_SEQUENCE_NUMBER_BIT != 0 {
+                (reader.read_u16::<BigEndian>()? as u64, 2)
+            } else {
+                (reader.read_u8()? as u64, 1)
+            };
+
+        let content_len = if first_byte & UNIFIED_HEADER_LENGTH_BIT != 0 {
+            Some(reader.read_u16::<BigEndian>()?)
+        } else {
+            None
+        };
+
+        Ok(RecordLayerUnifiedHeader {
+            epoch,
+            sequence_number,
+            sequence_number_len,
+            content_len,
+        })
+    }
+}
@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod session_test;
+
+use crate::cipher_suite::*;
+use crate::error::*;
+
+use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(crate) const SESSION_ID_LENGTH: usize = 32;
+pub(crate) const SESSION_TICKET_KEY_LENGTH: usize = 32;
+pub(crate) const SESSION_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+const SESSION_TICKET_NONCE_LENGTH: usize = 12;
+const DEFAULT_SESSION_STORE_CAPACITY: usize = 1024;
+
+/// Session holds what a DTLS 1.2 session is resumed from with an
+/// abbreviated handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// id is the session ID the server assigned to the session, servers
+    /// which issued a ticket may leave it empty.
+    pub id: Vec<u8>,
+    /// secret is the master secret of the session.
+    pub secret: Vec<u8>,
+    /// cipher_suite is the cipher suite the session was established with.
+    pub cipher_suite: CipherSuiteId,
+    /// extended_master_secret tells whether secret is an extended master
+    /// secret (RFC 7627).
+    pub extended_master_secret: bool,
+    /// ticket is the RFC 5077 session ticket the server issued, only
+    /// clients store it.
+    pub ticket: Vec<u8>,
+    /// peer_certificates are the certificates the peer presented in the full
+    /// handshake, a resumed handshake carries none.
+    pub peer_certificates: Vec<Vec<u8>>,
+    /// issued_at is when the session was established, it isn't resumed once
+    /// it is older than a day.
+    pub issued_at: SystemTime,
+}
+
+impl Session {
+    /// is_expired returns true if the session is too old to be resumed.
+    pub fn is_expired(&self) -> bool {
+        match SystemTime::now().duration_since(self.issued_at) {
+            Ok(age) => age > SESSION_LIFETIME,
+            Err(_) => false,
+        }
+    }
+}
+
+/// SessionStore caches the sessions to resume. Clients store the session
+/// of a server under its address and name, servers store the sessions
+/// under their session ID.
+pub trait SessionStore {
+    /// set stores session under key, replacing the previous one.
+    fn set(&self, key: &[u8], session: Session) -> Result<()>;
+    /// get returns the session stored under key, if any.
+    fn get(&self, key: &[u8]) -> Result<Option<Session>>;
+    /// del removes the session stored under key.
+    fn del(&self, key: &[u8]) -> Result<()>;
+}
+
+/// MemorySessionStore is a SessionStore keeping the sessions in memory.
+/// Once it holds capacity sessions, storing another one evicts the oldest.
+pub struct MemorySessionStore {
+    sessions: Mutex<MemorySessions>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct MemorySessions {
+    by_key: HashMap<Vec<u8>, Session>,
+    // keys in the order they were stored, oldest first
+    order: VecDeque<Vec<u8>>,
+}
+
+impl Default for MemorySessionStore {
+    fn default() -> Self {
+        MemorySessionStore::with_capacity(DEFAULT_SESSION_STORE_CAPACITY)
+    }
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        MemorySessionStore::default()
+    }
+
+    /// with_capacity returns a MemorySessionStore holding at most capacity sessions.
+    pub fn with_capacity(capacity: usize) -> Self {
+        MemorySessionStore {
+            sessions: Mutex::new(MemorySessions::default()),
+            capacity,
+        }
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn set(&self, key: &[u8], session: Session) -> Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.by_key.insert(key.to_vec(), session).is_some() {
+            sessions.order.retain(|k| k != key);
+        }
+        sessions.order.push_back(key.to_vec());
+
+        while sessions.order.len() > self.capacity {
+            if let Some(oldest) = sessions.order.pop_front() {
+                sessions.by_key.remove(&oldest);
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Session>> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions.by_key.get(key).cloned())
+    }
+
+    fn del(&self, key: &[u8]) -> Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.by_key.remove(key).is_some() {
+            sessions.order.retain(|k| k != key);
+        }
+        Ok(())
+    }
+}
+
+// SessionTicketState is the state a server encrypts into a session ticket
+#[derive(Serialize, Deserialize)]
+struct SessionTicketState {
+    secret: Vec<u8>,
+    cipher_suite_id: u16,
+    extended_master_secret: bool,
+    peer_certificates: Vec<Vec<u8>>,
+    issued_at: u64,
+}
+
+// encrypt_session_ticket seals the state of session with AES-256-GCM under
+// key, the ticket is the random nonce followed by the ciphertext.
+pub(crate) fn encrypt_session_ticket(key: &[u8], session: &Session) -> Result<Vec<u8>> {
+    if key.len() != SESSION_TICKET_KEY_LENGTH {
+        return Err(Error::ErrInvalidSessionTicketKey);
+    }
+
+    let state = SessionTicketState {
+        secret: session.secret.clone(),
+        cipher_suite_id: session.cipher_suite as u16,
+        extended_master_secret: session.extended_master_secret,
+        peer_certificates: session.peer_certificates.clone(),
+        issued_at: session
+            .issued_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    let plain_text = match bincode::serialize(&state) {
+        Ok(p) => p,
+        Err(err) => return Err(Error::Other(err.to_string())),
+    };
+
+    let mut nonce = [0u8; SESSION_TICKET_NONCE_LENGTH];
+    rand::thread_rng().fill(&mut nonce);
+
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+    let cipher_text = cipher
+        .encrypt(GenericArray::from_slice(&nonce), plain_text.as_ref())
+        .map_err(|_| Error::ErrInvalidSessionTicket)?;
+
+    let mut ticket = nonce.to_vec();
+    ticket.extend_from_slice(&cipher_text);
+    Ok(ticket)
+}
+
+// decrypt_session_ticket opens a ticket sealed by encrypt_session_ticket and
+// checks it has not expired.
+pub(crate) fn decrypt_session_ticket(key: &[u8], ticket: &[u8]) -> Result<Session> {
+    if key.len() != SESSION_TICKET_KEY_LENGTH {
+        return Err(Error::ErrInvalidSessionTicketKey);
+    }
+    if ticket.len() < SESSION_TICKET_NONCE_LENGTH {
+        return Err(Error::ErrInvalidSessionTicket);
+    }
+
+    let (nonce, cipher_text) = ticket.split_at(SESSION_TICKET_NONCE_LENGTH);
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+    let plain_text = cipher
+        .decrypt(GenericArray::from_slice(nonce), cipher_text)
+        .map_err(|_| Error::ErrInvalidSessionTicket)?;
+
+    let state: SessionTicketState =
+        bincode::deserialize(&plain_text).map_err(|_| Error::ErrInvalidSessionTicket)?;
+
+    let session = Session {
+        id: vec![],
+        secret: state.secret,
+        cipher_suite: state.cipher_suite_id.into(),
+        extended_master_secret: state.extended_master_secret,
+        ticket: ticket.to_vec(),
+        peer_certificates: state.peer_certificates,
+        issued_at: UNIX_EPOCH + Duration::from_secs(state.issued_at),
+    };
+    if session.is_expired() {
+        return Err(Error::ErrInvalidSessionTicket);
+    }
+
+    Ok(session)
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+fn test_session() -> Session {
+    Session {
+        id: vec![],
+        secret: vec![0x42; 48],
+        cipher_suite: CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Gcm_Sha256,
+        extended_master_secret: true,
+        ticket: vec![],
+        peer_certificates: vec![vec![0x30, 0x82], vec![0x30, 0x81]],
+        issued_at: SystemTime::now(),
+    }
+}
+
+#[test]
+fn test_session_ticket() -> Result<()> {
+    let key = vec![0x01; SESSION_TICKET_KEY_LENGTH];
+    let session = test_session();
+
+    let ticket = encrypt_session_ticket(&key, &session)?;
+    let decrypted = decrypt_session_ticket(&key, &ticket)?;
+    assert_eq!(decrypted.secret, session.secret);
+    assert_eq!(decrypted.cipher_suite, session.cipher_suite);
+    assert_eq!(
+        decrypted.extended_master_secret,
+        session.extended_master_secret
+    );
+    assert_eq!(decrypted.ticket, ticket);
+    assert_eq!(decrypted.peer_certificates, session.peer_certificates);
+
+    // tickets of the same session differ by their nonce
+    assert_ne!(encrypt_session_ticket(&key, &session)?, ticket);
+
+    Ok(())
+}
+
+#[test]
+fn test_session_ticket_invalid() -> Result<()> {
+    let key = vec![0x01; SESSION_TICKET_KEY_LENGTH];
+    let ticket = encrypt_session_ticket(&key, &test_session())?;
+
+    let mut tampered = ticket.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    assert_eq!(
+        decrypt_session_ticket(&key, &tampered),
+        Err(Error::ErrInvalidSessionTicket)
+    );
+
+    let other_key = vec![0x02; SESSION_TICKET_KEY_LENGTH];
+    assert_eq!(
+        decrypt_session_ticket(&other_key, &ticket),
+        Err(Error::ErrInvalidSessionTicket)
+    );
+    assert_eq!(
+        decrypt_session_ticket(&key, &ticket[..8]),
+        Err(Error::ErrInvalidSessionTicket)
+    );
+    assert_eq!(
+        encrypt_session_ticket(&key[..16], &test_session()),
+        Err(Error::ErrInvalidSessionTicketKey)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_session_store() -> Result<()> {
+    let store = MemorySessionStore::new();
+    assert_eq!(store.get(b"key")?, None);
+
+    let session = test_session();
+    store.set(b"key", session.clone())?;
+    assert_eq!(store.get(b"key")?, Some(session));
+    assert_eq!(store.get(b"other")?, None);
+
+    store.del(b"key")?;
+    assert_eq!(store.get(b"key")?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_session_expired() -> Result<()> {
+    let key = vec![0x01; SESSION_TICKET_KEY_LENGTH];
+    let mut session = test_session();
+    assert!(!session.is_expired());
+
+    session.issued_at = SystemTime::now() - SESSION_LIFETIME - Duration::from_secs(60);
+    assert!(session.is_expired());
+
+    let ticket = encrypt_session_ticket(&key, &session)?;
+    assert_eq!(
+        decrypt_session_ticket(&key, &ticket),
+        Err(Error::ErrInvalidSessionTicket)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_session_store_capacity() -> Result<()> {
+    let store = MemorySessionStore::with_capacity(2);
+    store.set(b"a", test_session())?;
+    store.set(b"b", test_session())?;
+    // storing a key again makes it the newest
+    store.set(b"a", test_session())?;
+    store.set(b"c", test_session())?;
+
+    assert_eq!(store.get(b"b")?, None);
+    assert!(store.get(b"a")?.is_some());
+    assert!(store.get(b"c")?.is_some());
+
+    store.del(b"a")?;
+    store.set(b"d", test_session())?;
+    assert!(store.get(b"c")?.is_some());
+    assert!(store.get(b"d")?.is_some());
+
+    Ok(())
+}
@@ -4,13 +4,14 @@ use super::curve::named_curve::*;
 use super::extension::extension_use_srtp::SrtpProtectionProfile;
 use super::handshake::handshake_random::*;
 use super::prf::*;
+use super::session::*;
 use crate::error::*;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::io::{BufWriter, Cursor};
 use std::marker::{Send, Sync};
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use util::KeyingMaterialExporter;
@@ -46,6 +47,23 @@ pub struct State {
     pub(crate) local_verify_data: Vec<u8>,         // cached VerifyData
     pub(crate) local_key_signature: Vec<u8>,       // cached keySignature
     pub(crate) peer_certificates_verified: bool,
+    pub(crate) session_id: Vec<u8>,
+    pub(crate) offered_session: Option<Session>, // Session the client tries to resume
+    pub(crate) session_ticket: Vec<u8>,          // cached NewSessionTicket
+    pub(crate) session_ticket_negotiated: bool,
+    pub(crate) did_resume: bool,
+
+    pub(crate) dtls13: Arc<AtomicBool>, // Did we negotiate DTLS 1.3
+    pub(crate) hello_retry_transcript: Vec<u8>, // ClientHello1 and HelloRetryRequest of the transcript
+    pub(crate) handshake_secret: Vec<u8>,
+    pub(crate) client_handshake_traffic_secret: Vec<u8>,
+    pub(crate) server_handshake_traffic_secret: Vec<u8>,
+    pub(crate) certificate_request_context: Vec<u8>,
+
+    // Connection IDs of RFC 9146, None if not negotiated. The local one is
+    // what the peer sends in its records, the remote one what we send.
+    pub(crate) local_connection_id: Arc<Mutex<Option<Vec<u8>>>>,
+    pub(crate) remote_connection_id: Arc<Mutex<Option<Vec<u8>>>>,
     //pub(crate) replay_detector: Vec<Box<dyn ReplayDetector + Send + Sync>>,
 }
 
@@ -95,6 +113,21 @@ impl Default for State {
             local_verify_data: vec![],           // cached VerifyData
             local_key_signature: vec![],         // cached keySignature
             peer_certificates_verified: false,
+            session_id: vec![],
+            offered_session: None,
+            session_ticket: vec![],
+            session_ticket_negotiated: false,
+            did_resume: false,
+
+            dtls13: Arc::new(AtomicBool::new(false)),
+            hello_retry_transcript: vec![],
+            handshake_secret: vec![],
+            client_handshake_traffic_secret: vec![],
+            server_handshake_traffic_secret: vec![],
+            certificate_request_context: vec![],
+
+            local_connection_id: Arc::new(Mutex::new(None)),
+            remote_connection_id: Arc::new(Mutex::new(None)),
             //replay_detector: vec![],
         }
     }
@@ -133,7 +166,8 @@ impl State {
         let remote_epoch = self.remote_epoch.load(Ordering::SeqCst);
         let sequence_number = {
             let lsn = self.local_sequence_number.lock().await;
-            lsn[local_epoch as usize]
+            // A DTLS 1.3 epoch may not have carried any record yet
+            lsn.get(local_epoch as usize).copied().unwrap_or(0)
         };
         let cipher_suite_id = {
             let cipher_suite = self.cipher_suite.lock().await;
@@ -265,12 +299,32 @@ impl KeyingMaterialExporter for State {
 
         if self.local_epoch.load(Ordering::SeqCst) == 0 {
             return Err(HandshakeInProgress);
-        } else if !context.is_empty() {
-            return Err(ContextUnsupported);
         } else if INVALID_KEYING_LABELS.contains(&label) {
             return Err(ReservedExportKeyingMaterial);
         }
 
+        // DTLS 1.3 exports from the exporter master secret kept as master_secret
+        // https://www.rfc-editor.org/rfc/rfc8446#section-7.5
+        {
+            let cipher_suite = self.cipher_suite.lock().await;
+            if let Some(cipher_suite) = &*cipher_suite {
+                if cipher_suite.id().is_dtls13() {
+                    return prf_exporter(
+                        &self.master_secret,
+                        label,
+                        context,
+                        length,
+                        cipher_suite.hash_func(),
+                    )
+                    .map_err(|err| Hash(err.to_string()));
+                }
+            }
+        }
+
+        if !context.is_empty() {
+            return Err(ContextUnsupported);
+        }
+
         let mut local_random = vec![];
         {
             let mut writer = BufWriter::<&mut Vec<u8>>::new(local_random.as_mut());
@@ -2,12 +2,14 @@ use super::*;
 use crate::error::*;
 use crate::mdns::*;
 use crate::network_type::*;
+use crate::tcp_mux::TCPMux;
 use crate::udp_network::UDPNetwork;
 use crate::url::*;
 
 use util::vnet::net::*;
 
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// The interval at which the agent performs candidate checks in the connecting phase.
@@ -64,6 +66,11 @@ pub struct AgentConfig {
     /// See [`UDPNetwork`]
     pub udp_network: UDPNetwork,
 
+    /// Enables ICE-TCP passive candidates, incoming TCP connections are accepted by the mux.
+    /// Active TCP candidates are used to reach remote passive candidates when TCP network
+    /// types are enabled, regardless of this option.
+    pub tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
+
     /// It is used to perform connectivity checks. The values MUST be unguessable, with at least
     /// 128 bits of random number generator output used to generate the password, and at least 24
     /// bits of output to generate the username fragment.
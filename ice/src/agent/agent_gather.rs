@@ -12,15 +12,36 @@ use crate::candidate::candidate_host::CandidateHostConfig;
 use crate::candidate::candidate_relay::CandidateRelayConfig;
 use crate::candidate::candidate_server_reflexive::CandidateServerReflexiveConfig;
 use crate::candidate::*;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+use turn::stun_conn::StunConn;
 use waitgroup::WaitGroup;
 
 const STUN_GATHER_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// InsecureServerCertVerifier accepts any certificate presented by a TURN server,
+/// it is used when AgentConfig.insecure_skip_verify is set.
+struct InsecureServerCertVerifier;
+
+impl rustls::ServerCertVerifier for InsecureServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> std::result::Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
 pub(crate) struct GatherCandidatesInternalParams {
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     pub(crate) candidate_types: Vec<CandidateType>,
     pub(crate) urls: Vec<Url>,
     pub(crate) network_types: Vec<NetworkType>,
@@ -37,6 +58,7 @@ pub(crate) struct GatherCandidatesInternalParams {
 
 struct GatherCandidatesLocalParams {
     udp_network: UDPNetwork,
+    tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     network_types: Vec<NetworkType>,
     mdns_mode: MulticastDnsMode,
     mdns_name: String,
@@ -57,6 +79,16 @@ struct GatherCandidatesLocalUDPMuxParams {
     udp_mux: Arc<dyn UDPMux + Send + Sync>,
 }
 
+struct GatherCandidatesLocalTCPMuxParams {
+    network_types: Vec<NetworkType>,
+    interface_filter: Arc<Option<InterfaceFilterFn>>,
+    ip_filter: Arc<Option<IpFilterFn>>,
+    ext_ip_mapper: Arc<Option<ExternalIpMapper>>,
+    net: Arc<Net>,
+    agent_internal: Arc<AgentInternal>,
+    tcp_mux: Arc<dyn TCPMux + Send + Sync>,
+}
+
 struct GatherCandidatesSrflxMappedParasm {
     network_types: Vec<NetworkType>,
     port_max: u16,
@@ -91,6 +123,7 @@ impl Agent {
                 CandidateType::Host => {
                     let local_params = GatherCandidatesLocalParams {
                         udp_network: params.udp_network.clone(),
+                        tcp_mux: params.tcp_mux.clone(),
                         network_types: params.network_types.clone(),
                         mdns_mode: params.mdns_mode,
                         mdns_name: params.mdns_name.clone(),
@@ -194,6 +227,7 @@ impl Agent {
     async fn gather_candidates_local(params: GatherCandidatesLocalParams) {
         let (
             udp_network,
+            tcp_mux,
             network_types,
             mdns_mode,
             mdns_name,
@@ -204,6 +238,7 @@ impl Agent {
             agent_internal,
         ) = (
             params.udp_network,
+            params.tcp_mux,
             params.network_types,
             params.mdns_mode,
             params.mdns_name,
@@ -214,8 +249,25 @@ impl Agent {
             params.agent_internal,
         );
 
+        // Handle ICE TCP passive mode
+        if let Some(tcp_mux) = tcp_mux {
+            let result = Self::gather_candidates_local_tcp_mux(GatherCandidatesLocalTCPMuxParams {
+                network_types: network_types.clone(),
+                interface_filter: Arc::clone(&interface_filter),
+                ip_filter: Arc::clone(&ip_filter),
+                ext_ip_mapper: Arc::clone(&ext_ip_mapper),
+                net: Arc::clone(&net),
+                agent_internal: Arc::clone(&agent_internal),
+                tcp_mux,
+            })
+            .await;
+
+            if let Err(err) = result {
+                log::error!("Failed to gather local candidates using TCP mux: {}", err);
+            }
+        }
+
         // If we wanna use UDP mux, do so
-        // FIXME: We still need to support TCP in combination with this option
         if let UDPNetwork::Muxed(udp_mux) = udp_network {
             let result = Self::gather_candidates_local_udp_mux(GatherCandidatesLocalUDPMuxParams {
                 network_types,
@@ -235,7 +287,10 @@ impl Agent {
             return;
         }
 
-        let ips = local_interfaces(&net, &*interface_filter, &*ip_filter, &network_types).await;
+        // Filter out non UDP network types
+        let udp_network_types: Vec<_> = network_types.into_iter().filter(|n| n.is_udp()).collect();
+
+        let ips = local_interfaces(&net, &interface_filter, &ip_filter, &udp_network_types).await;
         for ip in ips {
             let mut mapped_ip = ip;
 
@@ -261,27 +316,8 @@ impl Agent {
                 mapped_ip.to_string()
             };
 
-            //TODO: for network in networks
             let network = UDP.to_owned();
             if let UDPNetwork::Ephemeral(ephemeral_config) = &udp_network {
-                /*TODO:switch network {
-                case tcp:
-                    // Handle ICE TCP passive mode
-
-                    a.log.Debugf("GetConn by ufrag: %s\n", a.localUfrag)
-                    conn, err = a.tcpMux.GetConnByUfrag(a.localUfrag)
-                    if err != nil {
-                        if !errors.Is(err, ErrTCPMuxNotInitialized) {
-                            a.log.Warnf("error getting tcp conn by ufrag: %s %s %s\n", network, ip, a.localUfrag)
-                        }
-                        continue
-                    }
-                    port = conn.LocalAddr().(*net.TCPAddr).Port
-                    tcpType = TCPTypePassive
-                    // is there a way to verify that the listen address is even
-                    // accessible from the current interface.
-                case udp:*/
-
                 let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
                     &net,
                     ephemeral_config.port_max(),
@@ -466,6 +502,84 @@ impl Agent {
         Ok(())
     }
 
+    async fn gather_candidates_local_tcp_mux(
+        params: GatherCandidatesLocalTCPMuxParams,
+    ) -> Result<()> {
+        let (
+            tcp_mux,
+            agent_internal,
+            interface_filter,
+            ip_filter,
+            ext_ip_mapper,
+            net,
+            network_types,
+        ) = (
+            params.tcp_mux,
+            params.agent_internal,
+            params.interface_filter,
+            params.ip_filter,
+            params.ext_ip_mapper,
+            params.net,
+            params.network_types,
+        );
+        // Filter out non TCP network types
+        let relevant_network_types: Vec<_> =
+            network_types.into_iter().filter(|n| n.is_tcp()).collect();
+        if relevant_network_types.is_empty() {
+            return Ok(());
+        }
+
+        let local_ips =
+            local_interfaces(&net, &interface_filter, &ip_filter, &relevant_network_types).await;
+
+        let candidate_ip = ext_ip_mapper
+            .as_ref() // Arc
+            .as_ref() // Option
+            .and_then(|mapper| {
+                if mapper.candidate_type != CandidateType::Host {
+                    return None;
+                }
+
+                local_ips
+                    .iter()
+                    .find_map(|ip| mapper.find_external_ip(&ip.to_string()).ok())
+            })
+            .or_else(|| local_ips.iter().copied().next());
+
+        let candidate_ip = match candidate_ip {
+            None => return Err(Error::ErrCandidateIpNotFound),
+            Some(ip) => ip,
+        };
+
+        let ufrag = {
+            let ufrag_pwd = agent_internal.ufrag_pwd.lock().await;
+
+            ufrag_pwd.local_ufrag.clone()
+        };
+
+        let conn = tcp_mux.get_conn(&ufrag).await?;
+        let port = conn.local_addr().await?.port();
+
+        let host_config = CandidateHostConfig {
+            base_config: CandidateBaseConfig {
+                network: TCP.to_owned(),
+                address: candidate_ip.to_string(),
+                port,
+                conn: Some(conn),
+                component: COMPONENT_RTP,
+                ..Default::default()
+            },
+            tcp_type: TcpType::Passive,
+        };
+
+        let candidate: Arc<dyn Candidate + Send + Sync> =
+            Arc::new(host_config.new_candidate_host().await?);
+
+        agent_internal.add_candidate(&candidate).await?;
+
+        Ok(())
+    }
+
     async fn gather_candidates_srflx_mapped(params: GatherCandidatesSrflxMappedParasm) {
         let (network_types, port_max, port_min, ext_ip_mapper, net, agent_internal) = (
             params.network_types,
@@ -768,35 +882,88 @@ impl Agent {
 
                 let turn_server_addr = format!("{}:{}", url.host, url.port);
 
-                let (loc_conn, rel_addr, rel_port) =
-                    if url.proto == ProtoType::Udp && url.scheme == SchemeType::Turn {
-                        let loc_conn = match net2.bind(SocketAddr::from_str("0.0.0.0:0")?).await {
-                            Ok(c) => c,
-                            Err(err) => {
-                                log::warn!(
-                                    "[{}]: Failed to listen due to error: {}",
-                                    agent_internal2.get_name(),
-                                    err
-                                );
-                                return Ok(());
-                            }
-                        };
+                let (loc_conn, rel_addr, rel_port): (Arc<dyn Conn + Send + Sync>, String, u16) =
+                    match (url.proto, url.scheme) {
+                        (ProtoType::Udp, SchemeType::Turn) => {
+                            let loc_conn = match net2.bind(SocketAddr::from_str("0.0.0.0:0")?).await
+                            {
+                                Ok(c) => c,
+                                Err(err) => {
+                                    log::warn!(
+                                        "[{}]: Failed to listen due to error: {}",
+                                        agent_internal2.get_name(),
+                                        err
+                                    );
+                                    return Ok(());
+                                }
+                            };
 
-                        let local_addr = loc_conn.local_addr().await?;
-                        let rel_addr = local_addr.ip().to_string();
-                        let rel_port = local_addr.port();
-                        (loc_conn, rel_addr, rel_port)
-                    /*TODO: case url.proto == ProtoType::UDP && url.scheme == SchemeType::TURNS{
-                    case a.proxyDialer != nil && url.Proto == ProtoTypeTCP && (url.Scheme == SchemeTypeTURN || url.Scheme == SchemeTypeTURNS):
-                    case url.Proto == ProtoTypeTCP && url.Scheme == SchemeTypeTURN:
-                    case url.Proto == ProtoTypeTCP && url.Scheme == SchemeTypeTURNS:*/
-                    } else {
-                        log::warn!(
-                            "[{}]: Unable to handle URL in gather_candidates_relay {}",
-                            agent_internal2.get_name(),
-                            url
-                        );
-                        return Ok(());
+                            let local_addr = loc_conn.local_addr().await?;
+                            let rel_addr = local_addr.ip().to_string();
+                            let rel_port = local_addr.port();
+                            (loc_conn, rel_addr, rel_port)
+                        }
+                        (ProtoType::Tcp, SchemeType::Turn)
+                        | (ProtoType::Tcp, SchemeType::Turns)
+                            if !net2.is_virtual() =>
+                        {
+                            let tcp_conn = match TcpStream::connect(&turn_server_addr).await {
+                                Ok(c) => c,
+                                Err(err) => {
+                                    log::warn!(
+                                        "[{}]: Failed to dial TCP address {}: {}",
+                                        agent_internal2.get_name(),
+                                        turn_server_addr,
+                                        err
+                                    );
+                                    return Ok(());
+                                }
+                            };
+
+                            let local_addr = tcp_conn.local_addr()?;
+                            let remote_addr = tcp_conn.peer_addr()?;
+                            let rel_addr = local_addr.ip().to_string();
+                            let rel_port = local_addr.port();
+                            if url.scheme == SchemeType::Turn {
+                                (
+                                    Arc::new(StunConn::new(tcp_conn, local_addr, remote_addr)),
+                                    rel_addr,
+                                    rel_port,
+                                )
+                            } else {
+                                let tls_conn = match Self::dial_tls(
+                                    tcp_conn,
+                                    &url.host,
+                                    agent_internal2.insecure_skip_verify,
+                                )
+                                .await
+                                {
+                                    Ok(c) => c,
+                                    Err(err) => {
+                                        log::warn!(
+                                            "[{}]: Failed to dial TLS address {}: {}",
+                                            agent_internal2.get_name(),
+                                            turn_server_addr,
+                                            err
+                                        );
+                                        return Ok(());
+                                    }
+                                };
+                                (
+                                    Arc::new(StunConn::new(tls_conn, local_addr, remote_addr)),
+                                    rel_addr,
+                                    rel_port,
+                                )
+                            }
+                        }
+                        _ => {
+                            log::warn!(
+                                "[{}]: Unable to handle URL in gather_candidates_relay {}",
+                                agent_internal2.get_name(),
+                                url
+                            );
+                            return Ok(());
+                        }
                     };
 
                 let cfg = turn::client::ClientConfig {
@@ -901,4 +1068,41 @@ impl Agent {
 
         wg.wait().await;
     }
+
+    /// dial_tls performs a TLS handshake on a TCP connection to a TURN server. Unless
+    /// insecure_skip_verify is set, the certificate is checked against the web PKI roots.
+    /// Certificates of servers given by IP address can't be verified, they are only
+    /// accepted with insecure_skip_verify.
+    pub(crate) async fn dial_tls(
+        tcp_conn: TcpStream,
+        host: &str,
+        insecure_skip_verify: bool,
+    ) -> Result<TlsStream<TcpStream>> {
+        let mut config = rustls::ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        if insecure_skip_verify {
+            config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(InsecureServerCertVerifier));
+        }
+
+        let dns_name = if host.parse::<IpAddr>().is_ok() {
+            if !insecure_skip_verify {
+                return Err(Error::Other(format!(
+                    "TLS server certificate of IP address {} can't be verified",
+                    host
+                )));
+            }
+            // IP addresses are not sent as SNI (RFC 6066), the name is never used
+            config.enable_sni = false;
+            webpki::DNSNameRef::try_from_ascii_str("turn.invalid")
+        } else {
+            webpki::DNSNameRef::try_from_ascii_str(host)
+        }
+        .map_err(|err| Error::Other(format!("invalid TLS server name {}: {}", host, err)))?;
+        let connector = TlsConnector::from(Arc::new(config));
+        Ok(connector.connect(dns_name, tcp_conn).await?)
+    }
 }
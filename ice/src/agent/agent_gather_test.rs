@@ -491,3 +491,44 @@ async fn test_vnet_gather_muxed_udp() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_gather_dial_tls_ip_host() -> Result<()> {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()])
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let cert_der = rustls::Certificate(
+        cert.serialize_der()
+            .map_err(|err| Error::Other(err.to_string()))?,
+    );
+    let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+    server_config
+        .set_single_cert(
+            vec![cert_der],
+            rustls::PrivateKey(cert.serialize_private_key_der()),
+        )
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse_url(&format!(
+        "turns:127.0.0.1:{}?transport=tcp",
+        listener.local_addr()?.port()
+    ))?;
+    tokio::spawn(async move {
+        while let Ok((tcp_stream, _)) = listener.accept().await {
+            let _ = acceptor.accept(tcp_stream).await;
+        }
+    });
+
+    let turn_server_addr = format!("{}:{}", url.host, url.port);
+    let tcp_conn = tokio::net::TcpStream::connect(&turn_server_addr).await?;
+    assert!(
+        Agent::dial_tls(tcp_conn, &url.host, false).await.is_err(),
+        "the certificate of an IP address can't be verified"
+    );
+
+    let tcp_conn = tokio::net::TcpStream::connect(&turn_server_addr).await?;
+    Agent::dial_tls(tcp_conn, &url.host, true).await?;
+
+    Ok(())
+}
@@ -407,11 +407,41 @@ impl AgentInternal {
         }
     }
 
+    /// Returns false for TCP candidate pairs that can never connect (RFC 6544 section 6.2):
+    /// an active candidate only reaches the passive candidate it connects to and a passive
+    /// candidate only accepts connections.
+    async fn can_pair(
+        local: &Arc<dyn Candidate + Send + Sync>,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) -> bool {
+        if !local.network_type().is_tcp() {
+            return true;
+        }
+
+        match local.tcp_type() {
+            TcpType::Active => {
+                if remote.tcp_type() != TcpType::Passive {
+                    return false;
+                }
+                match local.get_conn() {
+                    Some(conn) => conn.remote_addr().await == Some(remote.addr().await),
+                    None => false,
+                }
+            }
+            TcpType::Passive => remote.tcp_type() != TcpType::Passive,
+            TcpType::SimultaneousOpen | TcpType::Unspecified => true,
+        }
+    }
+
     pub(crate) async fn add_pair(
         &self,
         local: Arc<dyn Candidate + Send + Sync>,
         remote: Arc<dyn Candidate + Send + Sync>,
     ) {
+        if !Self::can_pair(&local, &remote).await {
+            return;
+        }
+
         let p = Arc::new(CandidatePair::new(
             local,
             remote,
@@ -612,6 +642,33 @@ impl AgentInternal {
         Ok(())
     }
 
+    /// Adds a local active TCP candidate created for a remote passive candidate, it is only
+    /// paired with that remote candidate.
+    pub(crate) async fn add_active_tcp_candidate(
+        self: &Arc<Self>,
+        local: &Arc<dyn Candidate + Send + Sync>,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) {
+        let initialized_ch = {
+            let started_ch_tx = self.started_ch_tx.lock().await;
+            (*started_ch_tx).as_ref().map(|tx| tx.subscribe())
+        };
+
+        self.start_candidate(local, initialized_ch).await;
+
+        {
+            let mut local_candidates = self.local_candidates.lock().await;
+            local_candidates
+                .entry(local.network_type())
+                .or_insert_with(Vec::new)
+                .push(Arc::clone(local));
+        }
+
+        self.add_pair(Arc::clone(local), Arc::clone(remote)).await;
+
+        self.request_connectivity_check();
+    }
+
     pub(crate) async fn close(&self) -> Result<()> {
         {
             let mut done_tx = self.done_tx.lock().await;
@@ -922,7 +979,7 @@ impl AgentInternal {
             }
 
             if remote_candidate.is_none() {
-                let (ip, port, network_type) = (remote.ip(), remote.port(), NetworkType::Udp4);
+                let (ip, port, network_type) = (remote.ip(), remote.port(), local.network_type());
 
                 let prflx_candidate_config = CandidatePeerReflexiveConfig {
                     base_config: CandidateBaseConfig {
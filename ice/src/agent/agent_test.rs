@@ -2235,3 +2235,65 @@ async fn test_lite_lifecycle() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_agent_ice_tcp() -> Result<()> {
+    use crate::tcp_mux::{TCPMuxDefault, TCPMuxParams};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("0.0.0.0:0").await?;
+    let tcp_mux = TCPMuxDefault::new(TCPMuxParams::new(listener))?;
+
+    // a only accepts passive TCP connections, b only connects actively over TCP
+    let a_agent = Arc::new(
+        Agent::new(AgentConfig {
+            network_types: vec![NetworkType::Tcp4],
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            tcp_mux: Some(Arc::clone(&tcp_mux) as Arc<dyn TCPMux + Send + Sync>),
+            ..Default::default()
+        })
+        .await?,
+    );
+    let b_agent = Arc::new(
+        Agent::new(AgentConfig {
+            network_types: vec![NetworkType::Tcp4],
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            ..Default::default()
+        })
+        .await?,
+    );
+
+    let (a_notifier, mut a_connected) = on_connected();
+    a_agent.on_connection_state_change(a_notifier).await;
+    let (b_notifier, mut b_connected) = on_connected();
+    b_agent.on_connection_state_change(b_notifier).await;
+
+    let (a_conn, b_conn) = tokio::time::timeout(
+        Duration::from_secs(10),
+        connect_with_vnet(&a_agent, &b_agent),
+    )
+    .await
+    .expect("ICE-TCP connection timed out")?;
+    let _ = a_connected.recv().await;
+    let _ = b_connected.recv().await;
+
+    for (agent, tcp_type) in [(&a_agent, TcpType::Passive), (&b_agent, TcpType::Active)] {
+        let pair = agent
+            .get_selected_candidate_pair()
+            .await
+            .expect("a selected pair");
+        assert_eq!(pair.local.network_type(), NetworkType::Tcp4);
+        assert_eq!(pair.local.tcp_type(), tcp_type);
+    }
+
+    b_conn.send(b"hello").await?;
+    let mut buf = vec![0u8; 16];
+    let n = a_conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+
+    a_agent.close().await?;
+    b_agent.close().await?;
+    tcp_mux.close().await?;
+
+    Ok(())
+}
@@ -235,9 +235,16 @@ pub(crate) async fn add_vnet_stun(wan_net: Arc<net::Net>) -> Result<turn::server
                 },
             ),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
         channel_bind_timeout: Duration::from_secs(0),
+        // the agents of the test are on private networks
+        policy: turn::policy::PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
     })
     .await?;
 
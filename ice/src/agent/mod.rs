@@ -14,15 +14,19 @@ pub mod agent_selector;
 pub mod agent_stats;
 pub mod agent_transport;
 
+use crate::candidate::candidate_base::CandidateBaseConfig;
+use crate::candidate::candidate_host::CandidateHostConfig;
 use crate::candidate::*;
 use crate::error::*;
 use crate::external_ip_mapper::*;
 use crate::mdns::*;
 use crate::network_type::*;
 use crate::state::*;
+use crate::tcp_mux::{ActiveTCPConn, TCPMux};
 use crate::udp_mux::UDPMux;
 use crate::udp_network::UDPNetwork;
 use crate::url::*;
+use crate::util::local_interfaces;
 use agent_config::*;
 use agent_internal::*;
 use agent_stats::*;
@@ -31,7 +35,7 @@ use mdns::conn::*;
 use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr};
 use stun::{agent::*, attributes::*, fingerprint::*, integrity::*, message::*, xoraddr::*};
-use util::{vnet::net::*, Buffer};
+use util::{vnet::net::*, Buffer, Conn};
 
 use crate::agent::agent_gather::GatherCandidatesInternalParams;
 use crate::rand::*;
@@ -96,6 +100,7 @@ pub struct Agent {
     pub(crate) internal: Arc<AgentInternal>,
 
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     pub(crate) interface_filter: Arc<Option<InterfaceFilterFn>>,
     pub(crate) ip_filter: Arc<Option<IpFilterFn>>,
     pub(crate) mdns_mode: MulticastDnsMode,
@@ -194,6 +199,7 @@ impl Agent {
 
         let agent = Self {
             udp_network: config.udp_network,
+            tcp_mux: config.tcp_mux,
             internal: Arc::new(ai),
             interface_filter: Arc::clone(&config.interface_filter),
             ip_filter: Arc::clone(&config.ip_filter),
@@ -298,6 +304,16 @@ impl Agent {
                     }
                 }
             });
+        } else if c.network_type().is_tcp() && c.tcp_type() == TcpType::Passive {
+            let ai = Arc::clone(&self.internal);
+            let candidate = Arc::clone(c);
+            let local_candidates = self.active_tcp_candidates(c).await;
+            tokio::spawn(async move {
+                ai.add_remote_candidate(&candidate).await;
+                for local in local_candidates {
+                    ai.add_active_tcp_candidate(&local, &candidate).await;
+                }
+            });
         } else {
             let ai = Arc::clone(&self.internal);
             let candidate = Arc::clone(c);
@@ -309,6 +325,86 @@ impl Agent {
         Ok(())
     }
 
+    /// Creates the active TCP candidates connecting to a remote passive candidate, one for
+    /// every local interface of the same address family (RFC 6544 section 5.1).
+    async fn active_tcp_candidates(
+        &self,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) -> Vec<Arc<dyn Candidate + Send + Sync>> {
+        let network_type = remote.network_type();
+        if !self.network_types.contains(&network_type) {
+            return vec![];
+        }
+
+        if self.net.is_virtual() {
+            log::warn!("active TCP candidates are not supported with vnet");
+            return vec![];
+        }
+
+        let mut candidates = vec![];
+        let remote_addr = remote.addr().await;
+        let local_ips = local_interfaces(
+            &self.net,
+            &self.interface_filter,
+            &self.ip_filter,
+            &[network_type],
+        )
+        .await;
+        for ip in local_ips {
+            let conn = match ActiveTCPConn::new(ip, remote_addr) {
+                Ok(conn) => conn,
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: could not create active TCP connection from {} to {}: {}",
+                        self.internal.get_name(),
+                        ip,
+                        remote_addr,
+                        err
+                    );
+                    continue;
+                }
+            };
+            let port = match conn.local_addr().await {
+                Ok(addr) => addr.port(),
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: could not get local addr: {}",
+                        self.internal.get_name(),
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            let host_config = CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: TCP.to_owned(),
+                    address: ip.to_string(),
+                    port,
+                    component: remote.component(),
+                    conn: Some(Arc::new(conn)),
+                    ..CandidateBaseConfig::default()
+                },
+                tcp_type: TcpType::Active,
+            };
+
+            match host_config.new_candidate_host().await {
+                Ok(candidate) => candidates.push(Arc::new(candidate) as _),
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: Failed to create active TCP candidate: {} {}: {}",
+                        self.internal.get_name(),
+                        ip,
+                        port,
+                        err
+                    );
+                }
+            }
+        }
+
+        candidates
+    }
+
     /// Returns the local candidates.
     pub async fn get_local_candidates(&self) -> Result<Vec<Arc<dyn Candidate + Send + Sync>>> {
         let mut res = vec![];
@@ -348,6 +444,11 @@ impl Agent {
             udp_mux.remove_conn_by_ufrag(&ufrag).await;
         }
 
+        if let Some(tcp_mux) = &self.tcp_mux {
+            let (ufrag, _) = self.get_local_user_credentials().await;
+            tcp_mux.remove_conn_by_ufrag(&ufrag).await;
+        }
+
         //FIXME: deadlock here
         self.internal.close().await
     }
@@ -457,6 +558,7 @@ impl Agent {
 
         let params = GatherCandidatesInternalParams {
             udp_network: self.udp_network.clone(),
+            tcp_mux: self.tcp_mux.clone(),
             candidate_types: self.candidate_types.clone(),
             urls: self.urls.clone(),
             network_types: self.network_types.clone(),
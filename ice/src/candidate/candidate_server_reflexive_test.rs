@@ -39,7 +39,14 @@ async fn test_server_reflexive_only_connection() -> Result<()> {
                 net: Arc::new(util::vnet::net::Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         channel_bind_timeout: Duration::from_secs(0),
+        // the agents of the test are on private networks
+        policy: turn::policy::PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
     })
     .await?;
 
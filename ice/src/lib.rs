@@ -12,6 +12,7 @@ pub mod priority;
 pub mod rand;
 pub mod state;
 pub mod stats;
+pub mod tcp_mux;
 pub mod tcp_type;
 pub mod udp_mux;
 pub mod udp_network;
@@ -0,0 +1,170 @@
+use std::{io, net::IpAddr, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpSocket;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::time::{timeout, Duration};
+
+use util::{Conn, Error};
+
+use super::{read_framed_packet, write_framed_packet};
+
+/// How long connecting to a remote passive candidate may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many received packets are queued before the reader is paused.
+const PACKET_QUEUE_SIZE: usize = 128;
+
+type ConnResult<T> = Result<T, util::Error>;
+
+/// ActiveTCPConn is the connection of an active ICE-TCP candidate (RFC 6544). It connects to
+/// a single remote passive candidate in the background and implements a packet oriented
+/// [`Conn`] on top of it, packets are framed as described in RFC 4571.
+pub(crate) struct ActiveTCPConn {
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
+    packets_rx: Mutex<mpsc::Receiver<Vec<u8>>>,
+    closed_watch_tx: Mutex<Option<watch::Sender<()>>>,
+}
+
+impl ActiveTCPConn {
+    /// Binds a socket to an ephemeral port on `local_ip` and starts connecting it to
+    /// `remote_addr`.
+    pub(crate) fn new(local_ip: IpAddr, remote_addr: SocketAddr) -> io::Result<Self> {
+        let socket = if local_ip.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        socket.bind(SocketAddr::new(local_ip, 0))?;
+        let local_addr = socket.local_addr()?;
+
+        let (closed_watch_tx, mut closed_watch_rx) = watch::channel(());
+        let (packets_tx, packets_rx) = mpsc::channel(PACKET_QUEUE_SIZE);
+        let writer = Arc::new(Mutex::new(None));
+
+        let cloned_writer = Arc::clone(&writer);
+        tokio::spawn(async move {
+            let stream = tokio::select! {
+                res = timeout(CONNECT_TIMEOUT, socket.connect(remote_addr)) => {
+                    match res {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(err)) => {
+                            log::debug!("Failed to connect to {}: {}", remote_addr, err);
+                            return;
+                        }
+                        Err(_) => {
+                            log::debug!("Timed out connecting to {}", remote_addr);
+                            return;
+                        }
+                    }
+                }
+                _ = closed_watch_rx.changed() => return,
+            };
+
+            let (mut reader, writer) = stream.into_split();
+            {
+                let mut cloned_writer = cloned_writer.lock().await;
+                *cloned_writer = Some(writer);
+            }
+
+            loop {
+                tokio::select! {
+                    res = read_framed_packet(&mut reader) => {
+                        match res {
+                            Ok(packet) => {
+                                if packets_tx.send(packet).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(err) => {
+                                log::debug!("Closing tcp connection to {}: {}", remote_addr, err);
+                                break;
+                            }
+                        }
+                    }
+                    _ = closed_watch_rx.changed() => break,
+                }
+            }
+
+            let mut cloned_writer = cloned_writer.lock().await;
+            cloned_writer.take();
+        });
+
+        Ok(Self {
+            local_addr,
+            remote_addr,
+            writer,
+            packets_rx: Mutex::new(packets_rx),
+            closed_watch_tx: Mutex::new(Some(closed_watch_tx)),
+        })
+    }
+}
+
+#[async_trait]
+impl Conn for ActiveTCPConn {
+    async fn connect(&self, _addr: SocketAddr) -> ConnResult<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> ConnResult<usize> {
+        let mut packets_rx = self.packets_rx.lock().await;
+        match packets_rx.recv().await {
+            Some(packet) => {
+                if buf.len() < packet.len() {
+                    return Err(Error::ErrBufferShort);
+                }
+                buf[..packet.len()].copy_from_slice(&packet);
+                Ok(packet.len())
+            }
+            None => Err(Error::ErrUseClosedNetworkConn),
+        }
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> ConnResult<(usize, SocketAddr)> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> ConnResult<usize> {
+        let mut writer = self.writer.lock().await;
+        match &mut *writer {
+            Some(writer) => {
+                write_framed_packet(writer, buf).await?;
+                Ok(buf.len())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("not connected to {}", self.remote_addr),
+            )
+            .into()),
+        }
+    }
+
+    // send_to writes to the connection, the only reachable target is its remote address
+    async fn send_to(&self, buf: &[u8], _target: SocketAddr) -> ConnResult<usize> {
+        self.send(buf).await
+    }
+
+    async fn local_addr(&self) -> ConnResult<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> ConnResult<()> {
+        let mut closed_tx = self.closed_watch_tx.lock().await;
+        if closed_tx.take().is_none() {
+            return Err(Error::ErrAlreadyClosed);
+        }
+
+        let mut writer = self.writer.lock().await;
+        writer.take();
+
+        Ok(())
+    }
+}
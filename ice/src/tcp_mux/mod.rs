@@ -0,0 +1,309 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+use util::{Conn, Error};
+
+use async_trait::async_trait;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex};
+use tokio::time::{sleep, timeout, Duration};
+
+mod active_tcp_conn;
+pub(crate) use active_tcp_conn::ActiveTCPConn;
+
+mod tcp_mux_conn;
+pub use tcp_mux_conn::{TCPMuxConn, TCPMuxConnParams};
+
+#[cfg(test)]
+mod tcp_mux_test;
+
+use stun::{
+    attributes::ATTR_USERNAME,
+    message::{is_message as is_stun_message, Message as STUNMessage, BINDING_REQUEST},
+};
+
+use crate::candidate::RECEIVE_MTU;
+
+/// How long an accepted connection has to send its first binding request before it is dropped.
+const FIRST_PACKET_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long accepting pauses after an error, e.g. when out of file descriptors.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Reads a single packet framed as described in RFC 4571 section 2: every packet is preceded
+/// by its length as a 16 bit unsigned integer in network byte order.
+pub(crate) async fn read_framed_packet<R>(reader: &mut R) -> io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).await?;
+
+    let mut packet = vec![0u8; u16::from_be_bytes(header) as usize];
+    reader.read_exact(&mut packet).await?;
+
+    Ok(packet)
+}
+
+/// Writes a single packet framed as described in RFC 4571 section 2.
+pub(crate) async fn write_framed_packet<W>(writer: &mut W, buf: &[u8]) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    if buf.len() > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "packet too large for RFC 4571 framing",
+        ));
+    }
+
+    let mut packet = Vec::with_capacity(2 + buf.len());
+    packet.extend_from_slice(&(buf.len() as u16).to_be_bytes());
+    packet.extend_from_slice(buf);
+
+    writer.write_all(&packet).await
+}
+
+#[async_trait]
+pub trait TCPMux {
+    /// Close the muxing.
+    async fn close(&self) -> Result<(), Error>;
+
+    /// Get the underlying connection for a given ufrag.
+    async fn get_conn(self: Arc<Self>, ufrag: &str) -> Result<Arc<dyn Conn + Send + Sync>, Error>;
+
+    /// Remove the underlying connection for a given ufrag.
+    async fn remove_conn_by_ufrag(&self, ufrag: &str);
+}
+
+pub struct TCPMuxParams {
+    listener: TcpListener,
+}
+
+impl TCPMuxParams {
+    pub fn new(listener: TcpListener) -> Self {
+        Self { listener }
+    }
+}
+
+/// TCPMuxDefault accepts ICE-TCP connections (RFC 6544) on a single listener and hands them to
+/// the agent whose local ufrag matches the USERNAME of the first binding request received on a
+/// connection. Packets are framed as described in RFC 4571.
+pub struct TCPMuxDefault {
+    /// The address the listener is bound to.
+    local_addr: SocketAddr,
+
+    /// Maps from ufrag to the underlying connection.
+    conns: Mutex<HashMap<String, TCPMuxConn>>,
+
+    // Close sender
+    closed_watch_tx: Mutex<Option<watch::Sender<()>>>,
+}
+
+impl TCPMuxDefault {
+    pub fn new(params: TCPMuxParams) -> Result<Arc<Self>, Error> {
+        let (closed_watch_tx, closed_watch_rx) = watch::channel(());
+
+        let mux = Arc::new(Self {
+            local_addr: params.listener.local_addr()?,
+            conns: Mutex::default(),
+            closed_watch_tx: Mutex::new(Some(closed_watch_tx)),
+        });
+
+        let cloned_mux = Arc::clone(&mux);
+        cloned_mux.start_accept_worker(params.listener, closed_watch_rx);
+
+        Ok(mux)
+    }
+
+    pub async fn is_closed(&self) -> bool {
+        self.closed_watch_tx.lock().await.is_none()
+    }
+
+    /// Returns the address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the ufrag of the agent a connection belongs to, based on the first packet
+    /// received on it, which must be a STUN binding request.
+    fn ufrag_from_stun_message(buffer: &[u8], addr: &SocketAddr) -> Option<String> {
+        if !is_stun_message(buffer) {
+            log::warn!("First packet from {} is not a STUN message", addr);
+            return None;
+        }
+
+        let mut message = STUNMessage::new();
+        if let Err(err) = message.unmarshal_binary(buffer) {
+            log::warn!("Failed to handle decode ICE from {}: {}", addr, err);
+            return None;
+        }
+
+        if message.typ != BINDING_REQUEST {
+            log::warn!(
+                "First STUN message from {} is not a binding request: {}",
+                addr,
+                message.typ
+            );
+            return None;
+        }
+
+        let (attr, found) = message.attributes.get(ATTR_USERNAME);
+        if !found {
+            log::warn!("No username attribute in STUN message from {}", addr);
+            return None;
+        }
+
+        match String::from_utf8(attr.value) {
+            Ok(s) => s.split(':').next().map(ToOwned::to_owned),
+            // Per the RFC this shouldn't happen
+            // https://datatracker.ietf.org/doc/html/rfc5389#section-15.3
+            Err(err) => {
+                log::warn!(
+                    "Failed to decode USERNAME from STUN message as UTF-8: {}",
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    async fn handle_conn(self: Arc<Self>, mut stream: TcpStream, addr: SocketAddr) {
+        let buffer = match timeout(FIRST_PACKET_TIMEOUT, read_framed_packet(&mut stream)).await {
+            Ok(Ok(buffer)) => buffer,
+            Ok(Err(err)) => {
+                log::warn!("Failed to read first packet from {}: {}", addr, err);
+                return;
+            }
+            Err(_) => {
+                log::warn!("Timed out reading first packet from {}", addr);
+                return;
+            }
+        };
+
+        if buffer.len() > RECEIVE_MTU {
+            log::warn!("First packet from {} is too large", addr);
+            return;
+        }
+
+        let ufrag = match Self::ufrag_from_stun_message(&buffer, &addr) {
+            Some(ufrag) => ufrag,
+            None => return,
+        };
+
+        let conn = {
+            let conns = self.conns.lock().await;
+            conns.get(&ufrag).cloned()
+        };
+
+        match conn {
+            None => {
+                log::trace!("Dropping connection from {} for unknown ufrag", addr);
+            }
+            Some(conn) => {
+                if let Err(err) = conn.add_stream(stream, addr, buffer).await {
+                    log::warn!("Failed to add connection from {}: {}", addr, err);
+                }
+            }
+        }
+    }
+
+    fn start_accept_worker(
+        self: Arc<Self>,
+        listener: TcpListener,
+        mut closed_watch_rx: watch::Receiver<()>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    res = listener.accept() => {
+                        match res {
+                            Ok((stream, addr)) => {
+                                tokio::spawn(Arc::clone(&self).handle_conn(stream, addr));
+                            }
+                            Err(err) => {
+                                log::error!("Could not accept tcp connection: {}", err);
+                                // Errors such as EMFILE persist for a while, don't spin on them
+                                tokio::select! {
+                                    _ = sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                                    _ = closed_watch_rx.changed() => return,
+                                }
+                            }
+                        }
+                    }
+                    _ = closed_watch_rx.changed() => {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl TCPMux for TCPMuxDefault {
+    async fn close(&self) -> Result<(), Error> {
+        if self.is_closed().await {
+            return Err(Error::ErrAlreadyClosed);
+        }
+
+        let mut closed_tx = self.closed_watch_tx.lock().await;
+
+        if let Some(tx) = closed_tx.take() {
+            let _ = tx.send(());
+            drop(closed_tx);
+
+            let old_conns = {
+                let mut conns = self.conns.lock().await;
+
+                std::mem::take(&mut (*conns))
+            };
+
+            // NOTE: We don't wait for these closure to complete
+            for (_, conn) in old_conns {
+                conn.close();
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_conn(self: Arc<Self>, ufrag: &str) -> Result<Arc<dyn Conn + Send + Sync>, Error> {
+        if self.is_closed().await {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let mut conns = self.conns.lock().await;
+        if let Some(conn) = conns.get(ufrag) {
+            return Ok(Arc::new(conn.clone()) as Arc<dyn Conn + Send + Sync>);
+        }
+
+        let muxed_conn = TCPMuxConn::new(TCPMuxConnParams {
+            local_addr: self.local_addr,
+            key: ufrag.into(),
+        });
+        let mut close_rx = muxed_conn.close_rx();
+        let cloned_self = Arc::clone(&self);
+        let cloned_ufrag = ufrag.to_string();
+        tokio::spawn(async move {
+            let _ = close_rx.changed().await;
+
+            cloned_self.remove_conn_by_ufrag(&cloned_ufrag).await;
+        });
+
+        conns.insert(ufrag.into(), muxed_conn.clone());
+
+        Ok(Arc::new(muxed_conn) as Arc<dyn Conn + Send + Sync>)
+    }
+
+    async fn remove_conn_by_ufrag(&self, ufrag: &str) {
+        let removed_conn = {
+            let mut conns = self.conns.lock().await;
+            conns.remove(ufrag)
+        };
+
+        if let Some(conn) = removed_conn {
+            conn.close();
+        }
+    }
+}
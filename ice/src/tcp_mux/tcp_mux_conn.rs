@@ -0,0 +1,239 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch, Mutex};
+
+use util::{Conn, Error};
+
+use super::{read_framed_packet, write_framed_packet};
+
+/// How many received packets are queued per connection before the readers are paused.
+const PACKET_QUEUE_SIZE: usize = 128;
+
+/// Parameters for a [`TCPMuxConn`].
+pub struct TCPMuxConnParams {
+    /// Local address of the listener.
+    pub local_addr: SocketAddr,
+    /// Static key identifying the connection.
+    pub key: String,
+}
+
+type ConnResult<T> = Result<T, util::Error>;
+
+/// A TCP mux connection. It is a packet oriented [`Conn`] on top of all TCP connections
+/// accepted for one ufrag, the remote address of a packet selects the TCP connection.
+#[derive(Clone)]
+pub struct TCPMuxConn {
+    /// Close Receiver. A copy of this can be obtained via [`close_rx`].
+    closed_watch_rx: watch::Receiver<bool>,
+
+    inner: Arc<TCPMuxConnInner>,
+}
+
+impl TCPMuxConn {
+    /// Creates a new [`TCPMuxConn`].
+    pub fn new(params: TCPMuxConnParams) -> Self {
+        let (closed_watch_tx, closed_watch_rx) = watch::channel(false);
+        let (packets_tx, packets_rx) = mpsc::channel(PACKET_QUEUE_SIZE);
+
+        Self {
+            closed_watch_rx,
+            inner: Arc::new(TCPMuxConnInner {
+                params,
+                closed_watch_tx: Mutex::new(Some(closed_watch_tx)),
+                writers: Mutex::new(HashMap::new()),
+                packets_tx,
+                packets_rx: Mutex::new(packets_rx),
+            }),
+        }
+    }
+
+    /// Returns a key identifying this connection.
+    pub fn key(&self) -> &str {
+        &self.inner.params.key
+    }
+
+    /// Returns true if this connection is closed.
+    pub async fn is_closed(&self) -> bool {
+        self.inner.closed_watch_tx.lock().await.is_none()
+    }
+
+    /// Gets a copy of the close [`tokio::sync::watch::Receiver`] that fires when this
+    /// connection is closed.
+    pub fn close_rx(&self) -> watch::Receiver<bool> {
+        self.closed_watch_rx.clone()
+    }
+
+    /// Closes this connection and all TCP connections accepted for it.
+    pub fn close(&self) {
+        let inner = Arc::clone(&self.inner);
+        tokio::spawn(async move {
+            inner.close().await;
+        });
+    }
+
+    /// Gets the list of the remote addresses of the TCP connections.
+    pub async fn get_addresses(&self) -> Vec<SocketAddr> {
+        let writers = self.inner.writers.lock().await;
+        writers.keys().copied().collect()
+    }
+
+    /// Adds an accepted TCP connection, `first_packet` is the packet that was already read
+    /// from it to find this connection.
+    pub async fn add_stream(
+        &self,
+        stream: TcpStream,
+        addr: SocketAddr,
+        first_packet: Vec<u8>,
+    ) -> ConnResult<()> {
+        if self.is_closed().await {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let (mut reader, writer) = stream.into_split();
+        {
+            let mut writers = self.inner.writers.lock().await;
+            if writers.contains_key(&addr) {
+                return Err(Error::Other(format!(
+                    "connection from {} already exists",
+                    addr
+                )));
+            }
+            writers.insert(addr, Arc::new(Mutex::new(writer)));
+        }
+
+        let packets_tx = self.inner.packets_tx.clone();
+        if packets_tx.send((first_packet, addr)).await.is_err() {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let mut closed_watch_rx = self.closed_watch_rx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    res = read_framed_packet(&mut reader) => {
+                        match res {
+                            Ok(packet) => {
+                                if packets_tx.send((packet, addr)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(err) => {
+                                log::debug!("Closing tcp connection from {}: {}", addr, err);
+                                break;
+                            }
+                        }
+                    }
+                    _ = closed_watch_rx.changed() => break,
+                }
+            }
+
+            let mut writers = inner.writers.lock().await;
+            writers.remove(&addr);
+        });
+
+        log::debug!("Registered {} for {}", addr, self.key());
+
+        Ok(())
+    }
+}
+
+struct TCPMuxConnInner {
+    params: TCPMuxConnParams,
+
+    /// Close Sender. We'll send a value on this channel when we close
+    closed_watch_tx: Mutex<Option<watch::Sender<bool>>>,
+
+    /// Write halves of the accepted TCP connections by remote address.
+    writers: Mutex<HashMap<SocketAddr, Arc<Mutex<OwnedWriteHalf>>>>,
+
+    packets_tx: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    packets_rx: Mutex<mpsc::Receiver<(Vec<u8>, SocketAddr)>>,
+}
+
+impl TCPMuxConnInner {
+    async fn close(&self) {
+        let mut closed_tx = self.closed_watch_tx.lock().await;
+
+        if let Some(tx) = closed_tx.take() {
+            let _ = tx.send(true);
+            drop(closed_tx);
+
+            let mut writers = self.writers.lock().await;
+            writers.clear();
+        }
+    }
+}
+
+#[async_trait]
+impl Conn for TCPMuxConn {
+    async fn connect(&self, _addr: SocketAddr) -> ConnResult<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Not applicable").into())
+    }
+
+    async fn recv(&self, _buf: &mut [u8]) -> ConnResult<usize> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Not applicable").into())
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> ConnResult<(usize, SocketAddr)> {
+        let mut closed_watch_rx = self.closed_watch_rx.clone();
+        let mut packets_rx = self.inner.packets_rx.lock().await;
+
+        tokio::select! {
+            packet = packets_rx.recv() => {
+                match packet {
+                    Some((packet, addr)) => {
+                        if buf.len() < packet.len() {
+                            return Err(Error::ErrBufferShort);
+                        }
+                        buf[..packet.len()].copy_from_slice(&packet);
+                        Ok((packet.len(), addr))
+                    }
+                    None => Err(Error::ErrUseClosedNetworkConn),
+                }
+            }
+            _ = closed_watch_rx.changed() => Err(Error::ErrUseClosedNetworkConn),
+        }
+    }
+
+    async fn send(&self, _buf: &[u8]) -> ConnResult<usize> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Not applicable").into())
+    }
+
+    async fn send_to(&self, buf: &[u8], target: SocketAddr) -> ConnResult<usize> {
+        let writer = {
+            let writers = self.inner.writers.lock().await;
+            writers.get(&target).cloned()
+        };
+
+        match writer {
+            Some(writer) => {
+                let mut writer = writer.lock().await;
+                write_framed_packet(&mut *writer, buf).await?;
+                Ok(buf.len())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("no tcp connection from {}", target),
+            )
+            .into()),
+        }
+    }
+
+    async fn local_addr(&self) -> ConnResult<SocketAddr> {
+        Ok(self.inner.params.local_addr)
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    async fn close(&self) -> ConnResult<()> {
+        self.inner.close().await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::net::Ipv4Addr;
+
+use super::*;
+use crate::error::Result;
+use stun::agent::TransactionId;
+use stun::message::{Setter, BINDING_REQUEST};
+use stun::textattrs::Username;
+
+use tokio::time::sleep;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn binding_request(ufrag: &str) -> Result<Vec<u8>> {
+    let mut m = STUNMessage::new();
+    m.build(&[
+        Box::new(BINDING_REQUEST),
+        Box::new(TransactionId::new()),
+        Box::new(Username::new(
+            ATTR_USERNAME,
+            format!("{}:otherufrag", ufrag),
+        )) as Box<dyn Setter>,
+    ])?;
+    Ok(m.raw)
+}
+
+#[tokio::test]
+async fn test_tcp_mux() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let listener_addr = listener.local_addr()?;
+
+    let tcp_mux = TCPMuxDefault::new(TCPMuxParams::new(listener))?;
+    assert_eq!(tcp_mux.local_addr(), listener_addr);
+    let tcp_mux_dyn = Arc::clone(&tcp_mux) as Arc<dyn TCPMux + Send + Sync>;
+
+    let conn = Arc::clone(&tcp_mux_dyn).get_conn("ufrag1").await?;
+    assert_eq!(conn.local_addr().await?, listener_addr);
+
+    // connections for unknown ufrags are dropped
+    let mut unknown = TcpStream::connect(listener_addr).await?;
+    write_framed_packet(&mut unknown, &binding_request("ufrag2")?).await?;
+    let mut buf = [0u8; 1];
+    let n = timeout(TIMEOUT, unknown.read(&mut buf))
+        .await
+        .expect("timed out")?;
+    assert_eq!(n, 0, "connection should be closed");
+
+    let mut remote = TcpStream::connect(listener_addr).await?;
+    let remote_addr = remote.local_addr()?;
+    let stun_msg = binding_request("ufrag1")?;
+    write_framed_packet(&mut remote, &stun_msg).await?;
+    write_framed_packet(&mut remote, b"after the binding request").await?;
+
+    let mut buffer = vec![0u8; RECEIVE_MTU];
+    let (n, addr) = timeout(TIMEOUT, conn.recv_from(&mut buffer))
+        .await
+        .expect("timed out")?;
+    assert_eq!(&buffer[..n], &stun_msg[..]);
+    assert_eq!(addr, remote_addr);
+
+    let (n, addr) = timeout(TIMEOUT, conn.recv_from(&mut buffer))
+        .await
+        .expect("timed out")?;
+    assert_eq!(&buffer[..n], b"after the binding request");
+    assert_eq!(addr, remote_addr);
+
+    conn.send_to(b"response", remote_addr).await?;
+    let packet = timeout(TIMEOUT, read_framed_packet(&mut remote))
+        .await
+        .expect("timed out")?;
+    assert_eq!(packet, b"response");
+
+    assert!(
+        conn.send_to(b"response", listener_addr).await.is_err(),
+        "sending without a connection from the target should fail"
+    );
+
+    tcp_mux.close().await?;
+    assert!(
+        timeout(TIMEOUT, conn.recv_from(&mut buffer))
+            .await
+            .expect("timed out")
+            .is_err(),
+        "reading after TCPMuxDefault is closed should fail"
+    );
+    assert!(
+        tcp_mux_dyn.get_conn("ufrag3").await.is_err(),
+        "getting connections after TCPMuxDefault is closed should fail"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_active_tcp_conn() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let listener_addr = listener.local_addr()?;
+
+    let conn = ActiveTCPConn::new(Ipv4Addr::LOCALHOST.into(), listener_addr)?;
+    let local_addr = conn.local_addr().await?;
+    assert_eq!(conn.remote_addr().await, Some(listener_addr));
+
+    let (mut stream, addr) = timeout(TIMEOUT, listener.accept())
+        .await
+        .expect("timed out")?;
+    assert_eq!(addr, local_addr);
+
+    // the connection is established in the background
+    let mut result = conn.send_to(b"request", listener_addr).await;
+    for _ in 0..100 {
+        if result.is_ok() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+        result = conn.send_to(b"request", listener_addr).await;
+    }
+    assert_eq!(result?, 7);
+
+    let packet = timeout(TIMEOUT, read_framed_packet(&mut stream))
+        .await
+        .expect("timed out")?;
+    assert_eq!(packet, b"request");
+
+    write_framed_packet(&mut stream, b"response").await?;
+    let mut buffer = vec![0u8; RECEIVE_MTU];
+    let (n, addr) = timeout(TIMEOUT, conn.recv_from(&mut buffer))
+        .await
+        .expect("timed out")?;
+    assert_eq!(&buffer[..n], b"response");
+    assert_eq!(addr, listener_addr);
+
+    conn.close().await?;
+    let mut buf = [0u8; 1];
+    let n = timeout(TIMEOUT, stream.read(&mut buf))
+        .await
+        .expect("timed out")?;
+    assert_eq!(n, 0, "connection should be closed");
+
+    Ok(())
+}
@@ -21,6 +21,8 @@ pub enum Error {
     ErrShortBuffer,
     #[error("Invalid buffer size")]
     ErrInvalidSize,
+    #[error("Invalid FlexFEC packet")]
+    ErrInvalidFlexFecPacket,
 
     #[error("{0}")]
     Srtp(#[from] srtp::Error),
@@ -0,0 +1,194 @@
+use crate::error::{Error, Result};
+use crate::flexfec::flexfec_03::{recover, FecHeader};
+use crate::{Attributes, RTPReader};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use util::{Marshal, MarshalSize, Unmarshal};
+
+struct PendingRepairPacket {
+    header: FecHeader,
+    payload: Bytes,
+}
+
+pub(super) struct DecoderStreamInternal {
+    max_media_packets: usize,
+    max_repair_packets: usize,
+    media_packets: HashMap<u16, rtp::packet::Packet>,
+    media_order: VecDeque<u16>,
+    repair_packets: VecDeque<PendingRepairPacket>,
+    recovered: VecDeque<rtp::packet::Packet>,
+}
+
+impl DecoderStreamInternal {
+    pub(super) fn new(max_media_packets: usize, max_repair_packets: usize) -> Self {
+        DecoderStreamInternal {
+            max_media_packets,
+            max_repair_packets,
+            media_packets: HashMap::new(),
+            media_order: VecDeque::new(),
+            repair_packets: VecDeque::new(),
+            recovered: VecDeque::new(),
+        }
+    }
+
+    /// add_media stores a received media packet, it returns false if the packet
+    /// was seen (or recovered) before.
+    pub(super) fn add_media(&mut self, pkt: &rtp::packet::Packet) -> bool {
+        if !self.insert_media(pkt) {
+            return false;
+        }
+        self.recover();
+        true
+    }
+
+    pub(super) fn add_repair(&mut self, header: FecHeader, payload: Bytes) {
+        if self.repair_packets.len() >= self.max_repair_packets {
+            self.repair_packets.pop_front();
+        }
+        self.repair_packets
+            .push_back(PendingRepairPacket { header, payload });
+        self.recover();
+    }
+
+    pub(super) fn pop_recovered(&mut self) -> Option<rtp::packet::Packet> {
+        self.recovered.pop_front()
+    }
+
+    fn insert_media(&mut self, pkt: &rtp::packet::Packet) -> bool {
+        let seq = pkt.header.sequence_number;
+        if self.media_packets.contains_key(&seq) {
+            return false;
+        }
+
+        if self.media_order.len() >= self.max_media_packets {
+            if let Some(oldest) = self.media_order.pop_front() {
+                self.media_packets.remove(&oldest);
+            }
+        }
+        self.media_order.push_back(seq);
+        self.media_packets.insert(seq, pkt.clone());
+        true
+    }
+
+    /// recover rebuilds missing media packets from the pending repair packets, a recovered
+    /// packet may in turn allow another repair packet to recover one more packet.
+    fn recover(&mut self) {
+        loop {
+            let mut recovered = None;
+            let mut i = 0;
+            while i < self.repair_packets.len() {
+                let repair = &self.repair_packets[i];
+                let received: Vec<&rtp::packet::Packet> = repair
+                    .header
+                    .protected
+                    .iter()
+                    .filter_map(|offset| {
+                        let seq = repair.header.sequence_number_base.wrapping_add(*offset);
+                        self.media_packets.get(&seq)
+                    })
+                    .collect();
+
+                let missing = repair.header.protected.len() - received.len();
+                if missing == 0 {
+                    self.repair_packets.remove(i);
+                    continue;
+                }
+                if missing == 1 {
+                    match recover(&repair.header, &repair.payload, &received) {
+                        Ok(pkt) => recovered = Some(pkt),
+                        Err(err) => log::debug!("failed recovering packet: {}", err),
+                    }
+                    self.repair_packets.remove(i);
+                    if recovered.is_some() {
+                        break;
+                    }
+                    continue;
+                }
+                i += 1;
+            }
+
+            match recovered {
+                Some(pkt) => {
+                    if self.insert_media(&pkt) {
+                        self.recovered.push_back(pkt);
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+pub(super) struct DecoderStream {
+    pub(super) internal: Mutex<DecoderStreamInternal>,
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+}
+
+impl DecoderStream {
+    pub(super) fn new(
+        max_media_packets: usize,
+        max_repair_packets: usize,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Self {
+        DecoderStream {
+            internal: Mutex::new(DecoderStreamInternal::new(
+                max_media_packets,
+                max_repair_packets,
+            )),
+            parent_rtp_reader: reader,
+        }
+    }
+
+    fn pop_recovered(&self) -> Option<rtp::packet::Packet> {
+        let mut internal = self.internal.lock().unwrap();
+        internal.pop_recovered()
+    }
+}
+
+fn write_packet(pkt: &rtp::packet::Packet, buf: &mut [u8]) -> Result<usize> {
+    if buf.len() < pkt.marshal_size() {
+        return Err(Error::ErrShortBuffer);
+    }
+    Ok(pkt.marshal_to(buf)?)
+}
+
+/// RTPReader is used by Interceptor.bind_remote_stream.
+#[async_trait]
+impl RTPReader for DecoderStream {
+    /// read a rtp packet, recovered packets are returned before newly received ones
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        if let Some(pkt) = self.pop_recovered() {
+            return Ok((write_packet(&pkt, buf)?, a.clone()));
+        }
+
+        loop {
+            let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+            let mut b = &buf[..n];
+            let pkt = rtp::packet::Packet::unmarshal(&mut b)?;
+            let (added, recovered) = {
+                let mut internal = self.internal.lock().unwrap();
+                let added = internal.add_media(&pkt);
+                (added, internal.pop_recovered())
+            };
+            if !added {
+                // the packet was recovered and returned already
+                continue;
+            }
+
+            if let Some(recovered) = recovered {
+                // keep the order in which recovered and received packets are returned
+                {
+                    let mut internal = self.internal.lock().unwrap();
+                    internal.recovered.push_back(pkt);
+                }
+                return Ok((write_packet(&recovered, buf)?, attr));
+            }
+
+            return Ok((n, attr));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::*;
+use crate::flexfec::flexfec_03::{masks, FlexFecEncoder03};
+use crate::flexfec::ProtectionMask;
+use crate::mock::mock_stream::MockStream;
+use crate::test::timeout_or_fail;
+
+use std::time::Duration;
+
+fn media_packet(seq_num: u16) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 1,
+            payload_type: 96,
+            sequence_number: seq_num,
+            timestamp: 3000 * (seq_num as u32 / 2),
+            marker: seq_num % 2 == 1,
+            ..Default::default()
+        },
+        payload: vec![seq_num as u8; 50 + seq_num as usize % 7].into(),
+    }
+}
+
+#[tokio::test]
+async fn test_decoder_interceptor() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Decoder::builder().build("")?;
+
+    let media = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            ssrc_forward_error_correction: 2,
+            payload_type_forward_error_correction: 118,
+            ..Default::default()
+        },
+        Arc::clone(&icpr),
+    )
+    .await;
+    let repair = MockStream::new(
+        &StreamInfo {
+            ssrc: 2,
+            payload_type: 118,
+            mime_type: MIME_TYPE_FLEXFEC03.to_owned(),
+            ..Default::default()
+        },
+        Arc::clone(&icpr),
+    )
+    .await;
+
+    let media_packets: Vec<rtp::packet::Packet> = (65530..65538u32)
+        .map(|seq_num| media_packet(seq_num as u16))
+        .collect();
+    let mut encoder = FlexFecEncoder03::new(118, 2);
+    let repair_packets = encoder.encode(
+        &media_packets[..6],
+        &masks(ProtectionMask::Interleaved, 6, 2),
+    )?;
+    assert_eq!(repair_packets.len(), 2);
+
+    // lose the packets at offset 4 and 5, each one is protected by a different repair packet
+    for (i, p) in media_packets.iter().enumerate().take(4) {
+        media.receive_rtp(p.clone()).await;
+        let received = timeout_or_fail(Duration::from_millis(10), media.read_rtp())
+            .await
+            .expect("A read packet")
+            .expect("Not an error");
+        assert_eq!(received, media_packets[i]);
+    }
+    for p in repair_packets {
+        repair.receive_rtp(p.clone()).await;
+        let received = timeout_or_fail(Duration::from_millis(10), repair.read_rtp())
+            .await
+            .expect("A read packet")
+            .expect("Not an error");
+        assert_eq!(received, p, "repair packets are passed through");
+    }
+    media.receive_rtp(media_packets[6].clone()).await;
+
+    let mut received = vec![];
+    for _ in 0..3 {
+        let p = timeout_or_fail(Duration::from_millis(10), media.read_rtp())
+            .await
+            .expect("A read packet")
+            .expect("Not an error");
+        received.push(p);
+    }
+    assert_eq!(received[0], media_packets[4]);
+    assert_eq!(received[1], media_packets[5]);
+    assert_eq!(received[2], media_packets[6]);
+
+    // a recovered packet arriving late isn't passed on twice
+    media.receive_rtp(media_packets[5].clone()).await;
+    media.receive_rtp(media_packets[7].clone()).await;
+    let p = timeout_or_fail(Duration::from_millis(10), media.read_rtp())
+        .await
+        .expect("A read packet")
+        .expect("Not an error");
+    assert_eq!(p, media_packets[7]);
+
+    media.close().await?;
+    repair.close().await?;
+
+    Ok(())
+}
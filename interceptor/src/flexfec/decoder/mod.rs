@@ -0,0 +1,206 @@
+mod decoder_stream;
+#[cfg(test)]
+mod decoder_test;
+
+use crate::error::Result;
+use crate::flexfec::flexfec_03::FecHeader;
+use crate::flexfec::{stream_support_flexfec, MIME_TYPE_FLEXFEC03};
+use crate::stream_info::StreamInfo;
+use crate::{
+    Attributes, Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter,
+};
+use decoder_stream::DecoderStream;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use util::Unmarshal;
+
+const DEFAULT_MAX_MEDIA_PACKETS: usize = 512;
+const DEFAULT_MAX_REPAIR_PACKETS: usize = 64;
+
+/// DecoderBuilder can be used to configure the FlexFEC Decoder Interceptor
+#[derive(Default)]
+pub struct DecoderBuilder {
+    max_media_packets: Option<usize>,
+    max_repair_packets: Option<usize>,
+}
+
+impl DecoderBuilder {
+    /// with_max_media_packets sets how many received media packets are kept for recovery.
+    pub fn with_max_media_packets(mut self, max_media_packets: usize) -> DecoderBuilder {
+        self.max_media_packets = Some(max_media_packets);
+        self
+    }
+
+    /// with_max_repair_packets sets how many repair packets are kept while they can't be used yet.
+    pub fn with_max_repair_packets(mut self, max_repair_packets: usize) -> DecoderBuilder {
+        self.max_repair_packets = Some(max_repair_packets);
+        self
+    }
+}
+
+impl InterceptorBuilder for DecoderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(Decoder {
+            internal: Arc::new(DecoderInternal {
+                max_media_packets: self.max_media_packets.unwrap_or(DEFAULT_MAX_MEDIA_PACKETS),
+                max_repair_packets: self
+                    .max_repair_packets
+                    .unwrap_or(DEFAULT_MAX_REPAIR_PACKETS),
+                streams: Mutex::new(HashMap::new()),
+                repair_ssrcs: Mutex::new(HashMap::new()),
+            }),
+        }))
+    }
+}
+
+struct DecoderInternal {
+    max_media_packets: usize,
+    max_repair_packets: usize,
+    // media ssrc to stream
+    streams: Mutex<HashMap<u32, Arc<DecoderStream>>>,
+    // repair ssrc to media ssrc
+    repair_ssrcs: Mutex<HashMap<u32, u32>>,
+}
+
+/// RepairReader reads the FlexFEC repair stream and hands the repair packets
+/// to the media stream they protect.
+struct RepairReader {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    internal: Arc<DecoderInternal>,
+}
+
+#[async_trait]
+impl RTPReader for RepairReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+        let mut b = &buf[..n];
+        let pkt = rtp::packet::Packet::unmarshal(&mut b)?;
+        let (header, header_size) = match FecHeader::unmarshal(&pkt.payload) {
+            Ok(h) => h,
+            Err(err) => {
+                log::debug!("dropping invalid FlexFEC packet: {}", err);
+                return Ok((n, attr));
+            }
+        };
+
+        let stream = {
+            let streams = self.internal.streams.lock().await;
+            streams.get(&header.protected_ssrc).cloned()
+        };
+        if let Some(stream) = stream {
+            let mut internal = stream.internal.lock().unwrap();
+            internal.add_repair(header, pkt.payload.slice(header_size..));
+        }
+
+        Ok((n, attr))
+    }
+}
+
+/// Decoder recovers lost media packets of incoming streams from the FlexFEC-03
+/// repair packets received on the associated FEC-FR ssrc.
+pub struct Decoder {
+    internal: Arc<DecoderInternal>,
+}
+
+impl Decoder {
+    /// builder returns a new DecoderBuilder.
+    pub fn builder() -> DecoderBuilder {
+        DecoderBuilder::default()
+    }
+
+    async fn is_repair_stream(&self, info: &StreamInfo) -> bool {
+        if info.mime_type.eq_ignore_ascii_case(MIME_TYPE_FLEXFEC03) {
+            return true;
+        }
+        let repair_ssrcs = self.internal.repair_ssrcs.lock().await;
+        repair_ssrcs.contains_key(&info.ssrc)
+    }
+}
+
+#[async_trait]
+impl Interceptor for Decoder {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream returns a reader which recovers lost packets of a media
+    /// stream, or one which collects the repair packets of a FlexFEC stream.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        if self.is_repair_stream(info).await {
+            return Arc::new(RepairReader {
+                parent_rtp_reader: reader,
+                internal: Arc::clone(&self.internal),
+            });
+        }
+
+        if !stream_support_flexfec(info) {
+            return reader;
+        }
+
+        let stream = Arc::new(DecoderStream::new(
+            self.internal.max_media_packets,
+            self.internal.max_repair_packets,
+            reader,
+        ));
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+        {
+            let mut repair_ssrcs = self.internal.repair_ssrcs.lock().await;
+            repair_ssrcs.insert(info.ssrc_forward_error_correction, info.ssrc);
+        }
+
+        stream
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        {
+            let mut streams = self.internal.streams.lock().await;
+            streams.remove(&info.ssrc);
+        }
+        let mut repair_ssrcs = self.internal.repair_ssrcs.lock().await;
+        repair_ssrcs.remove(&info.ssrc_forward_error_correction);
+    }
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
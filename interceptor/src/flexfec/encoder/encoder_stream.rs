@@ -0,0 +1,87 @@
+use crate::error::Result;
+use crate::flexfec::flexfec_03::FlexFecEncoder03;
+use crate::{Attributes, RTPWriter};
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+struct EncoderStreamInternal {
+    encoder: FlexFecEncoder03,
+    media_packets: Vec<rtp::packet::Packet>,
+    last_sequence_number: Option<u16>,
+}
+
+pub(super) struct EncoderStream {
+    internal: Mutex<EncoderStreamInternal>,
+    media_ssrc: u32,
+    masks: Vec<Vec<u16>>,
+    num_media_packets: usize,
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+impl EncoderStream {
+    pub(super) fn new(
+        media_ssrc: u32,
+        payload_type: u8,
+        ssrc: u32,
+        masks: Vec<Vec<u16>>,
+        num_media_packets: usize,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        EncoderStream {
+            internal: Mutex::new(EncoderStreamInternal {
+                encoder: FlexFecEncoder03::new(payload_type, ssrc),
+                media_packets: Vec::with_capacity(num_media_packets),
+                last_sequence_number: None,
+            }),
+            media_ssrc,
+            masks,
+            num_media_packets,
+            next_rtp_writer: writer,
+        }
+    }
+}
+
+/// RTPWriter is used by Interceptor.bind_local_stream.
+#[async_trait]
+impl RTPWriter for EncoderStream {
+    /// write a rtp packet, once a block of media packets was written the repair packets follow
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        let n = self.next_rtp_writer.write(pkt, a).await?;
+
+        // Retransmissions, on the RTX SSRC or resent on the media SSRC, were
+        // protected when they were first sent
+        if pkt.header.ssrc != self.media_ssrc {
+            return Ok(n);
+        }
+
+        let repair_packets = {
+            let mut internal = self.internal.lock().await;
+            let sequence_number = pkt.header.sequence_number;
+            if let Some(last) = internal.last_sequence_number {
+                if (sequence_number.wrapping_sub(last) as i16) <= 0 {
+                    return Ok(n);
+                }
+            }
+            internal.last_sequence_number = Some(sequence_number);
+
+            internal.media_packets.push(pkt.clone());
+            if internal.media_packets.len() < self.num_media_packets {
+                return Ok(n);
+            }
+
+            let media_packets = std::mem::take(&mut internal.media_packets);
+            internal.encoder.encode(&media_packets, &self.masks)?
+        };
+
+        let attributes = Attributes::new();
+        for p in &repair_packets {
+            if let Err(err) = self.next_rtp_writer.write(p, &attributes).await {
+                log::warn!("failed sending FlexFEC packet: {}", err);
+            }
+        }
+
+        Ok(n)
+    }
+}
@@ -0,0 +1,162 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::test::timeout_or_fail;
+
+use std::time::Duration;
+use tokio::time::error::Elapsed;
+
+#[tokio::test]
+async fn test_encoder_interceptor() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Encoder::builder()
+        .with_num_media_packets(4)
+        .with_num_fec_packets(2)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            ssrc_forward_error_correction: 2,
+            payload_type_forward_error_correction: 118,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in 10..18u16 {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc: 1,
+                    payload_type: 96,
+                    sequence_number: seq_num,
+                    ..Default::default()
+                },
+                payload: vec![seq_num as u8; 100].into(),
+            })
+            .await?;
+
+        let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!(p.header.sequence_number, seq_num);
+
+        if (seq_num - 10) % 4 == 3 {
+            let mut fec_seq_num = None;
+            for _ in 0..2 {
+                let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+                    .await
+                    .expect("A repair packet");
+                assert_eq!(p.header.ssrc, 2);
+                assert_eq!(p.header.payload_type, 118);
+                if let Some(fec_seq_num) = fec_seq_num {
+                    assert_eq!(p.header.sequence_number, u16::wrapping_add(fec_seq_num, 1));
+                }
+                fec_seq_num = Some(p.header.sequence_number);
+            }
+        }
+    }
+
+    // no repair packets for a partial block
+    let result: std::result::Result<Option<rtp::packet::Packet>, Elapsed> =
+        tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(result.is_err(), "no more packets expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encoder_interceptor_skips_retransmissions() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Encoder::builder()
+        .with_num_media_packets(2)
+        .with_num_fec_packets(1)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            ssrc_forward_error_correction: 2,
+            payload_type_forward_error_correction: 118,
+            ssrc_retransmission: 3,
+            payload_type_retransmission: 97,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // a media packet, its RTX retransmission, a resend on the media SSRC and
+    // the next media packet, only the two media packets form the block
+    for (ssrc, seq_num) in [(1, 10u16), (3, 500), (1, 10), (1, 11)] {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc,
+                    payload_type: 96,
+                    sequence_number: seq_num,
+                    ..Default::default()
+                },
+                payload: vec![seq_num as u8; 100].into(),
+            })
+            .await?;
+
+        let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!((p.header.ssrc, p.header.sequence_number), (ssrc, seq_num));
+    }
+
+    let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+        .await
+        .expect("A repair packet");
+    assert_eq!(p.header.ssrc, 2);
+
+    let result: std::result::Result<Option<rtp::packet::Packet>, Elapsed> =
+        tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(result.is_err(), "no more packets expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encoder_interceptor_without_fec_ssrc() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> =
+        Encoder::builder().with_num_media_packets(1).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    stream
+        .write_rtp(&rtp::packet::Packet {
+            header: rtp::header::Header {
+                ssrc: 1,
+                ..Default::default()
+            },
+            payload: vec![1u8; 10].into(),
+        })
+        .await?;
+    let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+        .await
+        .expect("A packet");
+    assert_eq!(p.header.ssrc, 1);
+
+    let result: std::result::Result<Option<rtp::packet::Packet>, Elapsed> =
+        tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(result.is_err(), "no repair packets expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+mod encoder_stream;
+#[cfg(test)]
+mod encoder_test;
+
+use crate::error::Result;
+use crate::flexfec::{stream_support_flexfec, ProtectionMask};
+use crate::stream_info::StreamInfo;
+use crate::{Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
+use encoder_stream::EncoderStream;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+const DEFAULT_NUM_MEDIA_PACKETS: usize = 10;
+const DEFAULT_NUM_FEC_PACKETS: usize = 2;
+
+/// EncoderBuilder can be used to configure the FlexFEC Encoder Interceptor
+#[derive(Default)]
+pub struct EncoderBuilder {
+    num_media_packets: Option<usize>,
+    num_fec_packets: Option<usize>,
+    mask_type: Option<ProtectionMask>,
+}
+
+impl EncoderBuilder {
+    /// with_num_media_packets sets how many media packets are protected as one block.
+    /// At most 109 packets can be protected by a single repair packet.
+    pub fn with_num_media_packets(mut self, num_media_packets: usize) -> EncoderBuilder {
+        self.num_media_packets = Some(num_media_packets);
+        self
+    }
+
+    /// with_num_fec_packets sets how many repair packets are generated for every block.
+    pub fn with_num_fec_packets(mut self, num_fec_packets: usize) -> EncoderBuilder {
+        self.num_fec_packets = Some(num_fec_packets);
+        self
+    }
+
+    /// with_mask_type sets how the media packets of a block are spread over the repair packets.
+    pub fn with_mask_type(mut self, mask_type: ProtectionMask) -> EncoderBuilder {
+        self.mask_type = Some(mask_type);
+        self
+    }
+}
+
+impl InterceptorBuilder for EncoderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let num_media_packets = std::cmp::min(
+            self.num_media_packets.unwrap_or(DEFAULT_NUM_MEDIA_PACKETS),
+            super::flexfec_03::MAX_PROTECTED_PACKETS,
+        );
+        let num_fec_packets = std::cmp::min(
+            self.num_fec_packets.unwrap_or(DEFAULT_NUM_FEC_PACKETS),
+            num_media_packets,
+        );
+
+        Ok(Arc::new(Encoder {
+            num_media_packets,
+            num_fec_packets,
+            mask_type: self.mask_type.unwrap_or(ProtectionMask::Interleaved),
+        }))
+    }
+}
+
+/// Encoder generates FlexFEC-03 repair packets for outgoing streams that
+/// negotiated a FEC-FR ssrc group, the repair packets are sent on that SSRC.
+pub struct Encoder {
+    num_media_packets: usize,
+    num_fec_packets: usize,
+    mask_type: ProtectionMask,
+}
+
+impl Encoder {
+    /// builder returns a new EncoderBuilder.
+    pub fn builder() -> EncoderBuilder {
+        EncoderBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for Encoder {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        if !stream_support_flexfec(info) || self.num_fec_packets == 0 {
+            return writer;
+        }
+
+        Arc::new(EncoderStream::new(
+            info.ssrc,
+            info.payload_type_forward_error_correction,
+            info.ssrc_forward_error_correction,
+            super::flexfec_03::masks(self.mask_type, self.num_media_packets, self.num_fec_packets),
+            self.num_media_packets,
+            writer,
+        ))
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
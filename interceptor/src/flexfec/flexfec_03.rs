@@ -0,0 +1,333 @@
+use super::ProtectionMask;
+use crate::error::{Error, Result};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::packet::Packet;
+use util::{Marshal, MarshalSize, Unmarshal};
+
+const RTP_HEADER_SIZE: usize = 12;
+
+/// Size of the FlexFEC-03 header protecting one SSRC, depending on the number of mask chunks
+const HEADER_SIZES: [usize; 3] = [20, 24, 32];
+/// Number of packets which can be protected by one, two or three mask chunks
+const MASK_CHUNK_BITS: [usize; 3] = [15, 46, 109];
+
+/// MAX_PROTECTED_PACKETS is the number of media packets a single FlexFEC-03 packet can protect
+pub(crate) const MAX_PROTECTED_PACKETS: usize = 109;
+
+/// FecHeader is the parsed FlexFEC-03 header of a repair packet with the flexible mask:
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |R|F|P|X|  CC   |M| PT recovery |        length recovery        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                          TS recovery                          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   SSRCCount   |                    reserved                   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                             SSRC_i                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |           SN base_i           |k|          Mask [0-14]        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |k|                   Mask [15-45] (optional)                   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |k|                                                             |
+/// +-+                   Mask [46-108] (optional)                  |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+/// https://datatracker.ietf.org/doc/html/draft-ietf-payload-flexible-fec-scheme-03
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FecHeader {
+    /// P, X and CC bits of the protected packets xored together
+    pub(crate) recovery_bits: u8,
+    /// M and PT of the protected packets xored together
+    pub(crate) recovery_marker_payload_type: u8,
+    pub(crate) length_recovery: u16,
+    pub(crate) timestamp_recovery: u32,
+    pub(crate) protected_ssrc: u32,
+    pub(crate) sequence_number_base: u16,
+    /// offsets of the protected packets relative to sequence_number_base
+    pub(crate) protected: Vec<u16>,
+}
+
+impl FecHeader {
+    pub(crate) fn unmarshal(payload: &[u8]) -> Result<(Self, usize)> {
+        if payload.len() < HEADER_SIZES[0] {
+            return Err(Error::ErrInvalidFlexFecPacket);
+        }
+        if payload[0] & 0xC0 != 0 {
+            // retransmissions and fixed masks aren't supported
+            return Err(Error::ErrInvalidFlexFecPacket);
+        }
+        if payload[8] != 1 {
+            return Err(Error::ErrInvalidFlexFecPacket);
+        }
+
+        let mut protected = vec![];
+        let mut header_size = HEADER_SIZES[0];
+        let mask0 = u16::from_be_bytes([payload[18], payload[19]]);
+        push_mask_bits(&mut protected, mask0 as u64, 15, 0);
+        if mask0 & 0x8000 == 0 {
+            if payload.len() < HEADER_SIZES[1] {
+                return Err(Error::ErrInvalidFlexFecPacket);
+            }
+            header_size = HEADER_SIZES[1];
+            let mask1 = u32::from_be_bytes([payload[20], payload[21], payload[22], payload[23]]);
+            push_mask_bits(&mut protected, mask1 as u64, 31, 15);
+            if mask1 & 0x8000_0000 == 0 {
+                if payload.len() < HEADER_SIZES[2] {
+                    return Err(Error::ErrInvalidFlexFecPacket);
+                }
+                header_size = HEADER_SIZES[2];
+                let mut mask2 = [0u8; 8];
+                mask2.copy_from_slice(&payload[24..32]);
+                push_mask_bits(&mut protected, u64::from_be_bytes(mask2), 63, 46);
+            }
+        }
+
+        Ok((
+            FecHeader {
+                recovery_bits: payload[0] & 0x3F,
+                recovery_marker_payload_type: payload[1],
+                length_recovery: u16::from_be_bytes([payload[2], payload[3]]),
+                timestamp_recovery: u32::from_be_bytes([
+                    payload[4], payload[5], payload[6], payload[7],
+                ]),
+                protected_ssrc: u32::from_be_bytes([
+                    payload[12],
+                    payload[13],
+                    payload[14],
+                    payload[15],
+                ]),
+                sequence_number_base: u16::from_be_bytes([payload[16], payload[17]]),
+                protected,
+            },
+            header_size,
+        ))
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) {
+        let max_offset = self.protected.iter().max().copied().unwrap_or(0) as usize;
+        let chunks = MASK_CHUNK_BITS
+            .iter()
+            .position(|bits| max_offset < *bits)
+            .unwrap_or(2)
+            + 1;
+
+        let mut masks = [0u64; 3];
+        for offset in &self.protected {
+            let offset = *offset as usize;
+            if offset < 15 {
+                masks[0] |= 1 << (14 - offset);
+            } else if offset < 46 {
+                masks[1] |= 1 << (30 - (offset - 15));
+            } else if offset < MAX_PROTECTED_PACKETS {
+                masks[2] |= 1 << (62 - (offset - 46));
+            }
+        }
+        // the k bit marks the last mask chunk
+        match chunks {
+            1 => masks[0] |= 0x8000,
+            2 => masks[1] |= 0x8000_0000,
+            _ => masks[2] |= 0x8000_0000_0000_0000,
+        }
+
+        buf.put_u8(self.recovery_bits & 0x3F);
+        buf.put_u8(self.recovery_marker_payload_type);
+        buf.put_u16(self.length_recovery);
+        buf.put_u32(self.timestamp_recovery);
+        buf.put_u8(1); // SSRCCount
+        buf.put_u8(0);
+        buf.put_u16(0);
+        buf.put_u32(self.protected_ssrc);
+        buf.put_u16(self.sequence_number_base);
+        buf.put_u16(masks[0] as u16);
+        if chunks > 1 {
+            buf.put_u32(masks[1] as u32);
+        }
+        if chunks > 2 {
+            buf.put_u64(masks[2]);
+        }
+    }
+}
+
+fn push_mask_bits(protected: &mut Vec<u16>, mask: u64, bits: usize, first_offset: u16) {
+    for i in 0..bits {
+        if mask & (1 << (bits - 1 - i)) != 0 {
+            protected.push(first_offset + i as u16);
+        }
+    }
+}
+
+/// xor_into xors src into dst, growing dst with zeros if needed
+fn xor_into(dst: &mut Vec<u8>, src: &[u8]) {
+    if dst.len() < src.len() {
+        dst.resize(src.len(), 0);
+    }
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+/// masks returns the offsets of the media packets protected by each repair packet
+pub(crate) fn masks(
+    mask_type: ProtectionMask,
+    num_media_packets: usize,
+    num_fec_packets: usize,
+) -> Vec<Vec<u16>> {
+    let mut masks = vec![vec![]; num_fec_packets];
+    if num_fec_packets == 0 {
+        return masks;
+    }
+    for j in 0..std::cmp::min(num_media_packets, MAX_PROTECTED_PACKETS) {
+        let i = match mask_type {
+            ProtectionMask::Interleaved => j % num_fec_packets,
+            ProtectionMask::Bursty => j * num_fec_packets / num_media_packets,
+        };
+        masks[i].push(j as u16);
+    }
+    masks
+}
+
+/// FlexFecEncoder03 generates FlexFEC-03 repair packets for a block of media packets
+pub(crate) struct FlexFecEncoder03 {
+    payload_type: u8,
+    ssrc: u32,
+    sequence_number: u16,
+}
+
+impl FlexFecEncoder03 {
+    pub(crate) fn new(payload_type: u8, ssrc: u32) -> Self {
+        FlexFecEncoder03 {
+            payload_type,
+            ssrc,
+            sequence_number: rand::random::<u16>(),
+        }
+    }
+
+    /// encode returns one repair packet per mask, the masks hold offsets into media_packets
+    pub(crate) fn encode(
+        &mut self,
+        media_packets: &[Packet],
+        masks: &[Vec<u16>],
+    ) -> Result<Vec<Packet>> {
+        let first = match media_packets.first() {
+            Some(first) => first,
+            None => return Ok(vec![]),
+        };
+        let last = &media_packets[media_packets.len() - 1];
+
+        let mut repair_packets = vec![];
+        for mask in masks {
+            let mut header = FecHeader {
+                recovery_bits: 0,
+                recovery_marker_payload_type: 0,
+                length_recovery: 0,
+                timestamp_recovery: 0,
+                protected_ssrc: first.header.ssrc,
+                sequence_number_base: first.header.sequence_number,
+                protected: vec![],
+            };
+            let mut repair_payload = vec![];
+            for offset in mask {
+                let media = match media_packets.get(*offset as usize) {
+                    Some(media) => media,
+                    None => continue,
+                };
+                let raw = media.marshal()?;
+                header.recovery_bits ^= raw[0];
+                header.recovery_marker_payload_type ^= raw[1];
+                header.length_recovery ^= (raw.len() - RTP_HEADER_SIZE) as u16;
+                header.timestamp_recovery ^= media.header.timestamp;
+                header.protected.push(
+                    media
+                        .header
+                        .sequence_number
+                        .wrapping_sub(header.sequence_number_base),
+                );
+                xor_into(&mut repair_payload, &raw[RTP_HEADER_SIZE..]);
+            }
+            if header.protected.is_empty() {
+                continue;
+            }
+
+            let mut payload = BytesMut::new();
+            header.marshal_to(&mut payload);
+            payload.extend_from_slice(&repair_payload);
+
+            repair_packets.push(Packet {
+                header: rtp::header::Header {
+                    version: 2,
+                    payload_type: self.payload_type,
+                    sequence_number: self.sequence_number,
+                    timestamp: last.header.timestamp,
+                    ssrc: self.ssrc,
+                    ..Default::default()
+                },
+                payload: payload.freeze(),
+            });
+            self.sequence_number = self.sequence_number.wrapping_add(1);
+        }
+
+        Ok(repair_packets)
+    }
+}
+
+/// recover rebuilds the single protected packet missing from received
+/// using the repair packet's header and payload.
+pub(crate) fn recover(
+    header: &FecHeader,
+    repair_payload: &[u8],
+    received: &[&Packet],
+) -> Result<Packet> {
+    let mut first_byte = header.recovery_bits;
+    let mut second_byte = header.recovery_marker_payload_type;
+    let mut length = header.length_recovery;
+    let mut timestamp = header.timestamp_recovery;
+    let mut payload = repair_payload.to_vec();
+    let mut received_offsets = vec![];
+
+    for media in received {
+        let raw = media.marshal()?;
+        first_byte ^= raw[0] & 0x3F;
+        second_byte ^= raw[1];
+        length ^= (raw.len() - RTP_HEADER_SIZE) as u16;
+        timestamp ^= media.header.timestamp;
+        xor_into(&mut payload, &raw[RTP_HEADER_SIZE..]);
+        received_offsets.push(
+            media
+                .header
+                .sequence_number
+                .wrapping_sub(header.sequence_number_base),
+        );
+    }
+
+    let missing: Vec<u16> = header
+        .protected
+        .iter()
+        .filter(|offset| !received_offsets.contains(offset))
+        .copied()
+        .collect();
+    if missing.len() != 1 || payload.len() < length as usize {
+        return Err(Error::ErrInvalidFlexFecPacket);
+    }
+
+    let mut raw = BytesMut::with_capacity(RTP_HEADER_SIZE + length as usize);
+    raw.put_u8(0x80 | first_byte);
+    raw.put_u8(second_byte);
+    raw.put_u16(header.sequence_number_base.wrapping_add(missing[0]));
+    raw.put_u32(timestamp);
+    raw.put_u32(header.protected_ssrc);
+    raw.extend_from_slice(&payload[..length as usize]);
+
+    let mut raw: Bytes = raw.freeze();
+    let pkt = Packet::unmarshal(&mut raw)?;
+    if pkt.marshal_size() != RTP_HEADER_SIZE + length as usize {
+        return Err(Error::ErrInvalidFlexFecPacket);
+    }
+
+    Ok(pkt)
+}
@@ -0,0 +1,96 @@
+use super::flexfec_03::*;
+use super::*;
+use crate::error::{Error, Result};
+
+use rtp::packet::Packet;
+
+fn media_packets(count: usize, first_seq_num: u16) -> Vec<Packet> {
+    (0..count)
+        .map(|i| Packet {
+            header: rtp::header::Header {
+                version: 2,
+                ssrc: 0x1234_5678,
+                payload_type: 96,
+                sequence_number: first_seq_num.wrapping_add(i as u16),
+                timestamp: 90_000 + i as u32 * 30,
+                marker: i % 3 == 0,
+                ..Default::default()
+            },
+            payload: vec![i as u8 ^ 0x5a; 10 + i * 3].into(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_protection_masks() {
+    assert_eq!(
+        masks(ProtectionMask::Interleaved, 5, 2),
+        vec![vec![0, 2, 4], vec![1, 3]]
+    );
+    assert_eq!(
+        masks(ProtectionMask::Bursty, 5, 2),
+        vec![vec![0, 1, 2], vec![3, 4]]
+    );
+    assert_eq!(
+        masks(ProtectionMask::Bursty, 3, 3),
+        vec![vec![0], vec![1], vec![2]]
+    );
+}
+
+#[test]
+fn test_fec_header_mask_sizes() -> Result<()> {
+    for (count, header_size) in [(10, 20), (40, 24), (109, 32)] {
+        let media = media_packets(count, 65500);
+        let mut encoder = FlexFecEncoder03::new(118, 1);
+        let repair = encoder.encode(&media, &masks(ProtectionMask::Interleaved, count, 1))?;
+        assert_eq!(repair.len(), 1);
+
+        let (header, size) = FecHeader::unmarshal(&repair[0].payload)?;
+        assert_eq!(size, header_size, "header size for {} packets", count);
+        assert_eq!(header.protected_ssrc, 0x1234_5678);
+        assert_eq!(header.sequence_number_base, 65500);
+        assert_eq!(
+            header.protected,
+            (0..count as u16).collect::<Vec<u16>>(),
+            "protected offsets for {} packets",
+            count
+        );
+    }
+
+    assert_eq!(
+        FecHeader::unmarshal(&[0u8; 10]),
+        Err(Error::ErrInvalidFlexFecPacket)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_recover() -> Result<()> {
+    let media = media_packets(12, 65530);
+    let mut encoder = FlexFecEncoder03::new(118, 1);
+    let repair = encoder.encode(&media, &masks(ProtectionMask::Bursty, 12, 3))?;
+    assert_eq!(repair.len(), 3);
+
+    for (i, lost) in [1usize, 4, 11].iter().enumerate() {
+        let (header, size) = FecHeader::unmarshal(&repair[i].payload)?;
+        let received: Vec<&Packet> = header
+            .protected
+            .iter()
+            .map(|offset| &media[*offset as usize])
+            .filter(|p| p.header.sequence_number != media[*lost].header.sequence_number)
+            .collect();
+
+        let recovered = recover(&header, &repair[i].payload[size..], &received)?;
+        assert_eq!(recovered, media[*lost]);
+    }
+
+    // nothing can be recovered if more than one packet is missing
+    let (header, size) = FecHeader::unmarshal(&repair[0].payload)?;
+    assert_eq!(
+        recover(&header, &repair[0].payload[size..], &[&media[0]]),
+        Err(Error::ErrInvalidFlexFecPacket)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+mod flexfec_03;
+#[cfg(test)]
+mod flexfec_test;
+
+pub mod decoder;
+pub mod encoder;
+
+use crate::stream_info::StreamInfo;
+
+/// MIME_TYPE_FLEXFEC03 is the mime type of FlexFEC-03 repair streams.
+pub const MIME_TYPE_FLEXFEC03: &str = "video/flexfec-03";
+
+/// ProtectionMask selects which media packets of a block each repair packet protects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProtectionMask {
+    /// Interleaved protects media packet j with repair packet j % num_fec_packets,
+    /// so a burst of consecutive losses can be recovered from different repair packets.
+    Interleaved,
+    /// Bursty protects consecutive runs of media packets with the same repair packet,
+    /// which recovers scattered single losses.
+    Bursty,
+}
+
+fn stream_support_flexfec(info: &StreamInfo) -> bool {
+    info.ssrc_forward_error_correction != 0
+}
@@ -0,0 +1,438 @@
+use super::feedback_adapter::Acknowledgment;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// packets sent within this interval are treated as a single burst
+const BURST_INTERVAL: Duration = Duration::from_millis(5);
+
+const TRENDLINE_WINDOW_SIZE: usize = 20;
+const TRENDLINE_SMOOTHING: f64 = 0.9;
+const TRENDLINE_THRESHOLD_GAIN: f64 = 4.0;
+const TRENDLINE_MAX_DELTAS: usize = 60;
+
+const OVERUSE_TIME_THRESHOLD_MS: f64 = 10.0;
+const THRESHOLD_INITIAL_MS: f64 = 12.5;
+const THRESHOLD_MIN_MS: f64 = 6.0;
+const THRESHOLD_MAX_MS: f64 = 600.0;
+const THRESHOLD_K_UP: f64 = 0.0087;
+const THRESHOLD_K_DOWN: f64 = 0.039;
+const THRESHOLD_MAX_OUTLIER_MS: f64 = 15.0;
+const THRESHOLD_MAX_TIME_DELTA_MS: f64 = 100.0;
+
+const RATE_INCREASE_FACTOR: f64 = 1.08;
+const RATE_DECREASE_FACTOR: f64 = 0.85;
+const EXPECTED_PACKET_SIZE_BITS: f64 = 1200.0 * 8.0;
+const MIN_ADDITIVE_INCREASE_BPS: f64 = 4000.0;
+const RECEIVED_RATE_WINDOW_US: i64 = 500_000;
+
+/// BandwidthUsage is the state of the network as signaled by the overuse detector
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BandwidthUsage {
+    Normal,
+    Underusing,
+    Overusing,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RateControlState {
+    Hold,
+    Increase,
+    Decrease,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ArrivalGroup {
+    first_departure: Instant,
+    last_departure: Instant,
+    last_arrival: i64,
+}
+
+/// TrendlineEstimator fits a line to the smoothed accumulated one way delay
+/// variation of the last arrival groups; its slope tells whether queues grow.
+struct TrendlineEstimator {
+    first_arrival_ms: Option<f64>,
+    accumulated_delay: f64,
+    smoothed_delay: f64,
+    num_deltas: usize,
+    window: VecDeque<(f64, f64)>,
+    trend: f64,
+}
+
+impl TrendlineEstimator {
+    fn new() -> Self {
+        TrendlineEstimator {
+            first_arrival_ms: None,
+            accumulated_delay: 0.0,
+            smoothed_delay: 0.0,
+            num_deltas: 0,
+            window: VecDeque::with_capacity(TRENDLINE_WINDOW_SIZE + 1),
+            trend: 0.0,
+        }
+    }
+
+    /// update adds a delay variation sample and returns the modified trend
+    fn update(&mut self, arrival_ms: f64, delay_variation_ms: f64) -> f64 {
+        self.num_deltas = std::cmp::min(self.num_deltas + 1, 1000);
+        self.accumulated_delay += delay_variation_ms;
+        self.smoothed_delay = TRENDLINE_SMOOTHING * self.smoothed_delay
+            + (1.0 - TRENDLINE_SMOOTHING) * self.accumulated_delay;
+
+        let first_arrival_ms = *self.first_arrival_ms.get_or_insert(arrival_ms);
+        self.window
+            .push_back((arrival_ms - first_arrival_ms, self.smoothed_delay));
+        if self.window.len() > TRENDLINE_WINDOW_SIZE {
+            self.window.pop_front();
+        }
+        if self.window.len() == TRENDLINE_WINDOW_SIZE {
+            if let Some(trend) = linear_fit_slope(&self.window) {
+                self.trend = trend;
+            }
+        }
+
+        std::cmp::min(self.num_deltas, TRENDLINE_MAX_DELTAS) as f64
+            * self.trend
+            * TRENDLINE_THRESHOLD_GAIN
+    }
+}
+
+fn linear_fit_slope(points: &VecDeque<(f64, f64)>) -> Option<f64> {
+    let n = points.len() as f64;
+    let (sum_x, sum_y) = points
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (avg_x, avg_y) = (sum_x / n, sum_y / n);
+
+    let mut numerator = 0.0;
+    let mut denominator = 0.0;
+    for (x, y) in points {
+        numerator += (x - avg_x) * (y - avg_y);
+        denominator += (x - avg_x) * (x - avg_x);
+    }
+    if denominator == 0.0 {
+        None
+    } else {
+        Some(numerator / denominator)
+    }
+}
+
+/// OveruseDetector compares the modified trend against an adaptive threshold
+struct OveruseDetector {
+    threshold: f64,
+    last_update_ms: Option<f64>,
+    time_over_using: Option<f64>,
+    overuse_counter: usize,
+    prev_trend: f64,
+    usage: BandwidthUsage,
+}
+
+impl OveruseDetector {
+    fn new() -> Self {
+        OveruseDetector {
+            threshold: THRESHOLD_INITIAL_MS,
+            last_update_ms: None,
+            time_over_using: None,
+            overuse_counter: 0,
+            prev_trend: 0.0,
+            usage: BandwidthUsage::Normal,
+        }
+    }
+
+    fn detect(
+        &mut self,
+        modified_trend: f64,
+        trend: f64,
+        inter_departure_ms: f64,
+        now_ms: f64,
+    ) -> BandwidthUsage {
+        if modified_trend > self.threshold {
+            let time_over_using = match self.time_over_using {
+                Some(t) => t + inter_departure_ms,
+                None => inter_departure_ms / 2.0,
+            };
+            self.time_over_using = Some(time_over_using);
+            self.overuse_counter += 1;
+            if time_over_using > OVERUSE_TIME_THRESHOLD_MS
+                && self.overuse_counter > 1
+                && trend >= self.prev_trend
+            {
+                self.time_over_using = Some(0.0);
+                self.overuse_counter = 0;
+                self.usage = BandwidthUsage::Overusing;
+            }
+        } else if modified_trend < -self.threshold {
+            self.time_over_using = None;
+            self.overuse_counter = 0;
+            self.usage = BandwidthUsage::Underusing;
+        } else {
+            self.time_over_using = None;
+            self.overuse_counter = 0;
+            self.usage = BandwidthUsage::Normal;
+        }
+        self.prev_trend = trend;
+
+        self.update_threshold(modified_trend, now_ms);
+
+        self.usage
+    }
+
+    fn update_threshold(&mut self, modified_trend: f64, now_ms: f64) {
+        let last_update_ms = *self.last_update_ms.get_or_insert(now_ms);
+        if modified_trend.abs() > self.threshold + THRESHOLD_MAX_OUTLIER_MS {
+            // avoid adapting the threshold to sudden spikes like route changes
+            self.last_update_ms = Some(now_ms);
+            return;
+        }
+
+        let k = if modified_trend.abs() < self.threshold {
+            THRESHOLD_K_DOWN
+        } else {
+            THRESHOLD_K_UP
+        };
+        let time_delta_ms = (now_ms - last_update_ms).clamp(0.0, THRESHOLD_MAX_TIME_DELTA_MS);
+        self.threshold += k * (modified_trend.abs() - self.threshold) * time_delta_ms;
+        self.threshold = self.threshold.clamp(THRESHOLD_MIN_MS, THRESHOLD_MAX_MS);
+        self.last_update_ms = Some(now_ms);
+    }
+}
+
+/// AimdRateController increases the estimate while the network is not
+/// overused and cuts it back to a fraction of the received rate when it is.
+struct AimdRateController {
+    state: RateControlState,
+    bitrate: f64,
+    min_bitrate: f64,
+    max_bitrate: f64,
+    link_capacity: Option<f64>,
+    last_update: Option<Instant>,
+    last_decrease: Option<Instant>,
+}
+
+impl AimdRateController {
+    fn new(initial_bitrate: f64, min_bitrate: f64, max_bitrate: f64) -> Self {
+        AimdRateController {
+            state: RateControlState::Increase,
+            bitrate: initial_bitrate,
+            min_bitrate,
+            max_bitrate,
+            link_capacity: None,
+            last_update: None,
+            last_decrease: None,
+        }
+    }
+
+    fn update(
+        &mut self,
+        usage: BandwidthUsage,
+        received_rate: Option<f64>,
+        rtt: Duration,
+        now: Instant,
+    ) -> f64 {
+        match usage {
+            BandwidthUsage::Overusing => self.state = RateControlState::Decrease,
+            BandwidthUsage::Underusing => self.state = RateControlState::Hold,
+            BandwidthUsage::Normal => {
+                if self.state == RateControlState::Hold {
+                    self.state = RateControlState::Increase;
+                }
+            }
+        }
+
+        let elapsed = match self.last_update {
+            Some(last_update) => now.duration_since(last_update),
+            None => Duration::from_secs(0),
+        };
+        let elapsed = std::cmp::min(elapsed, Duration::from_secs(1)).as_secs_f64();
+
+        match self.state {
+            RateControlState::Hold => {}
+            RateControlState::Increase => {
+                if let (Some(capacity), Some(received_rate)) = (self.link_capacity, received_rate) {
+                    // the link capacity changed, go back to probing multiplicatively
+                    if received_rate > capacity * 1.5 {
+                        self.link_capacity = None;
+                    }
+                }
+
+                let increase = if self.link_capacity.is_some() {
+                    let response_time = (rtt + Duration::from_millis(100)).as_secs_f64();
+                    f64::max(
+                        MIN_ADDITIVE_INCREASE_BPS,
+                        EXPECTED_PACKET_SIZE_BITS / response_time,
+                    ) * elapsed
+                } else {
+                    self.bitrate * (RATE_INCREASE_FACTOR.powf(elapsed) - 1.0)
+                };
+
+                let mut bitrate = self.bitrate + increase;
+                if let Some(received_rate) = received_rate {
+                    // never grow far beyond what actually gets through
+                    let limit = 1.5 * received_rate + 10_000.0;
+                    if bitrate > limit {
+                        bitrate = f64::max(self.bitrate, limit);
+                    }
+                }
+                self.bitrate = bitrate;
+            }
+            RateControlState::Decrease => {
+                let recently_decreased = match self.last_decrease {
+                    Some(last_decrease) => now.duration_since(last_decrease) < rtt,
+                    None => false,
+                };
+                if !recently_decreased {
+                    let received_rate = received_rate.unwrap_or(self.bitrate);
+                    self.bitrate = f64::min(self.bitrate, RATE_DECREASE_FACTOR * received_rate);
+                    self.link_capacity = Some(match self.link_capacity {
+                        Some(capacity) => 0.95 * capacity + 0.05 * received_rate,
+                        None => received_rate,
+                    });
+                    self.last_decrease = Some(now);
+                }
+                self.state = RateControlState::Hold;
+            }
+        }
+
+        self.bitrate = self.bitrate.clamp(self.min_bitrate, self.max_bitrate);
+        self.last_update = Some(now);
+
+        self.bitrate
+    }
+}
+
+/// DelayBasedBwe estimates the available bandwidth from the one way delay
+/// variation between groups of packets reported by transport wide feedback.
+pub(crate) struct DelayBasedBwe {
+    current_group: Option<ArrivalGroup>,
+    previous_group: Option<ArrivalGroup>,
+    received: VecDeque<(i64, usize)>,
+    trendline: TrendlineEstimator,
+    detector: OveruseDetector,
+    rate_controller: AimdRateController,
+    modified_trend: f64,
+}
+
+impl DelayBasedBwe {
+    pub(crate) fn new(initial_bitrate: u64, min_bitrate: u64, max_bitrate: u64) -> Self {
+        DelayBasedBwe {
+            current_group: None,
+            previous_group: None,
+            received: VecDeque::new(),
+            trendline: TrendlineEstimator::new(),
+            detector: OveruseDetector::new(),
+            rate_controller: AimdRateController::new(
+                initial_bitrate as f64,
+                min_bitrate as f64,
+                max_bitrate as f64,
+            ),
+            modified_trend: 0.0,
+        }
+    }
+
+    /// on_acknowledgments processes the acknowledgments of one feedback packet
+    /// and returns the new delay based estimate in bits per second.
+    pub(crate) fn on_acknowledgments(
+        &mut self,
+        acks: &[Acknowledgment],
+        rtt: Duration,
+        now: Instant,
+    ) -> u64 {
+        for ack in acks {
+            if let Some(arrival) = ack.arrival {
+                self.on_received(ack.departure, arrival, ack.size);
+            }
+        }
+
+        let received_rate = self.received_rate();
+        self.rate_controller
+            .update(self.detector.usage, received_rate, rtt, now) as u64
+    }
+
+    pub(crate) fn bitrate(&self) -> u64 {
+        self.rate_controller.bitrate as u64
+    }
+
+    pub(crate) fn usage(&self) -> BandwidthUsage {
+        self.detector.usage
+    }
+
+    pub(crate) fn modified_trend(&self) -> f64 {
+        self.modified_trend
+    }
+
+    pub(crate) fn threshold(&self) -> f64 {
+        self.detector.threshold
+    }
+
+    /// received_rate returns the rate at which acknowledged packets arrived
+    /// at the remote during the last window, in bits per second.
+    pub(crate) fn received_rate(&self) -> Option<f64> {
+        let (first, last) = match (self.received.front(), self.received.back()) {
+            (Some(first), Some(last)) if self.received.len() > 1 => (first.0, last.0),
+            _ => return None,
+        };
+        let window_us = std::cmp::max(last - first, RECEIVED_RATE_WINDOW_US / 5);
+        let bytes: usize = self.received.iter().map(|(_, size)| size).sum();
+
+        Some(bytes as f64 * 8.0 * 1_000_000.0 / window_us as f64)
+    }
+
+    fn on_received(&mut self, departure: Instant, arrival: i64, size: usize) {
+        self.received.push_back((arrival, size));
+        while let Some((first, _)) = self.received.front() {
+            if arrival - *first <= RECEIVED_RATE_WINDOW_US {
+                break;
+            }
+            self.received.pop_front();
+        }
+
+        let group = match &mut self.current_group {
+            Some(group) => group,
+            None => {
+                self.current_group = Some(ArrivalGroup {
+                    first_departure: departure,
+                    last_departure: departure,
+                    last_arrival: arrival,
+                });
+                return;
+            }
+        };
+
+        if departure < group.first_departure {
+            // reordered packet belonging to an already completed group
+            return;
+        }
+        if departure.duration_since(group.first_departure) <= BURST_INTERVAL {
+            group.last_departure = std::cmp::max(group.last_departure, departure);
+            group.last_arrival = std::cmp::max(group.last_arrival, arrival);
+            return;
+        }
+
+        let completed = *group;
+        if let Some(previous) = self.previous_group {
+            let inter_departure_ms = completed
+                .last_departure
+                .duration_since(previous.last_departure)
+                .as_secs_f64()
+                * 1000.0;
+            let inter_arrival_ms = (completed.last_arrival - previous.last_arrival) as f64 / 1000.0;
+            let arrival_ms = completed.last_arrival as f64 / 1000.0;
+
+            self.modified_trend = self
+                .trendline
+                .update(arrival_ms, inter_arrival_ms - inter_departure_ms);
+            self.detector.detect(
+                self.modified_trend,
+                self.trendline.trend,
+                inter_departure_ms,
+                arrival_ms,
+            );
+        }
+        self.previous_group = Some(completed);
+        self.current_group = Some(ArrivalGroup {
+            first_departure: departure,
+            last_departure: departure,
+            last_arrival: arrival,
+        });
+    }
+}
@@ -0,0 +1,107 @@
+use rtcp::transport_feedbacks::transport_layer_cc::{
+    PacketStatusChunk, SymbolTypeTcc, TransportLayerCc,
+};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Sent packets are forgotten after this long, well before the 16 bit
+// transport wide sequence number can wrap around at realistic rates.
+const SEND_HISTORY_DURATION: Duration = Duration::from_secs(5);
+
+struct SentPacket {
+    departure: Instant,
+    size: usize,
+}
+
+/// Acknowledgment matches a sent packet with its reported arrival
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Acknowledgment {
+    pub(crate) sequence_number: u16,
+    pub(crate) size: usize,
+    pub(crate) departure: Instant,
+    /// arrival is the arrival time in microseconds on the remote clock, None if the packet was lost
+    pub(crate) arrival: Option<i64>,
+}
+
+/// FeedbackAdapter remembers outgoing packets and converts transport wide
+/// congestion control feedback into per packet acknowledgments.
+#[derive(Default)]
+pub(crate) struct FeedbackAdapter {
+    history: HashMap<u16, SentPacket>,
+    order: VecDeque<(u16, Instant)>,
+}
+
+impl FeedbackAdapter {
+    pub(crate) fn on_sent(&mut self, sequence_number: u16, size: usize, now: Instant) {
+        while let Some((seq, departure)) = self.order.front() {
+            if now.duration_since(*departure) < SEND_HISTORY_DURATION {
+                break;
+            }
+            if let Some(p) = self.history.get(seq) {
+                if p.departure == *departure {
+                    self.history.remove(seq);
+                }
+            }
+            self.order.pop_front();
+        }
+
+        self.history.insert(
+            sequence_number,
+            SentPacket {
+                departure: now,
+                size,
+            },
+        );
+        self.order.push_back((sequence_number, now));
+    }
+
+    pub(crate) fn on_feedback(&mut self, feedback: &TransportLayerCc) -> Vec<Acknowledgment> {
+        let mut symbols = Vec::with_capacity(feedback.packet_status_count as usize);
+        for chunk in &feedback.packet_chunks {
+            match chunk {
+                PacketStatusChunk::RunLengthChunk(c) => {
+                    for _ in 0..c.run_length {
+                        symbols.push(c.packet_status_symbol);
+                    }
+                }
+                PacketStatusChunk::StatusVectorChunk(c) => {
+                    symbols.extend_from_slice(&c.symbol_list);
+                }
+            }
+        }
+        // status vectors may be padded with unused symbols
+        symbols.truncate(feedback.packet_status_count as usize);
+
+        let mut acks = Vec::with_capacity(symbols.len());
+        let mut deltas = feedback.recv_deltas.iter();
+        let mut arrival = feedback.reference_time as i64 * 64000;
+        let mut sequence_number = feedback.base_sequence_number;
+        for symbol in symbols {
+            let received = match symbol {
+                SymbolTypeTcc::PacketReceivedSmallDelta
+                | SymbolTypeTcc::PacketReceivedLargeDelta => {
+                    if let Some(d) = deltas.next() {
+                        arrival += d.delta;
+                        Some(arrival)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(p) = self.history.remove(&sequence_number) {
+                acks.push(Acknowledgment {
+                    sequence_number,
+                    size: p.size,
+                    departure: p.departure,
+                    arrival: received,
+                });
+            }
+            sequence_number = sequence_number.wrapping_add(1);
+        }
+
+        acks
+    }
+}
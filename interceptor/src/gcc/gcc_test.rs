@@ -0,0 +1,202 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::stream_info::RTPHeaderExtension;
+use crate::test::timeout_or_fail;
+use crate::twcc::Recorder;
+
+use util::Marshal;
+
+#[test]
+fn test_feedback_adapter() {
+    let base = Instant::now();
+    let mut adapter = FeedbackAdapter::default();
+    for i in 0..5u16 {
+        adapter.on_sent(
+            i.wrapping_add(65533),
+            1000 + i as usize,
+            base + Duration::from_millis(i as u64 * 10),
+        );
+    }
+
+    let mut recorder = Recorder::new(5000);
+    for i in [0u16, 1, 3, 4] {
+        recorder.record(1, i.wrapping_add(65533), 1_000_000 + i as i64 * 10_250);
+    }
+    let pkts = recorder.build_feedback_packet();
+    assert_eq!(pkts.len(), 1);
+    let feedback = pkts[0]
+        .as_any()
+        .downcast_ref::<TransportLayerCc>()
+        .expect("a TransportLayerCc");
+
+    let acks = adapter.on_feedback(feedback);
+    assert_eq!(acks.len(), 5);
+    for (i, ack) in acks.iter().enumerate() {
+        assert_eq!(ack.sequence_number, (i as u16).wrapping_add(65533));
+        assert_eq!(ack.size, 1000 + i);
+        assert_eq!(ack.departure, base + Duration::from_millis(i as u64 * 10));
+        if i == 2 {
+            assert_eq!(ack.arrival, None, "packet 2 was lost");
+        } else {
+            assert_eq!(ack.arrival, Some(1_000_000 + i as i64 * 10_250));
+        }
+    }
+
+    // acknowledged packets are forgotten
+    assert!(adapter.on_feedback(feedback).is_empty());
+}
+
+fn run_delay_based(bwe: &mut DelayBasedBwe, queuing_delay_us: i64) -> Vec<BandwidthUsage> {
+    let base = Instant::now();
+    let mut usages = vec![];
+    let mut acks = vec![];
+    for i in 0..300u16 {
+        let departure = base + Duration::from_millis(i as u64 * 10);
+        acks.push(feedback_adapter::Acknowledgment {
+            sequence_number: i,
+            size: 1200,
+            departure,
+            arrival: Some(i as i64 * (10_000 + queuing_delay_us)),
+        });
+        if acks.len() == 10 {
+            bwe.on_acknowledgments(&acks, DEFAULT_RTT, departure + Duration::from_millis(50));
+            usages.push(bwe.usage());
+            acks.clear();
+        }
+    }
+    usages
+}
+
+#[test]
+fn test_delay_based_bwe_increase() {
+    let mut bwe = DelayBasedBwe::new(300_000, 10_000, 10_000_000);
+    let usages = run_delay_based(&mut bwe, 0);
+
+    assert!(usages.iter().all(|u| *u == BandwidthUsage::Normal));
+    assert!(
+        bwe.bitrate() > 300_000,
+        "estimate should grow without queuing delay, got {}",
+        bwe.bitrate()
+    );
+    let received_rate = bwe.received_rate().expect("a received rate");
+    assert!((received_rate - 960_000.0).abs() < 50_000.0);
+}
+
+#[test]
+fn test_delay_based_bwe_overuse() {
+    let mut bwe = DelayBasedBwe::new(5_000_000, 10_000, 10_000_000);
+    let usages = run_delay_based(&mut bwe, 2_000);
+
+    assert!(usages.contains(&BandwidthUsage::Overusing));
+    assert!(
+        bwe.bitrate() < 800_000,
+        "estimate should drop below the received rate, got {}",
+        bwe.bitrate()
+    );
+}
+
+#[test]
+fn test_loss_based_bwe() {
+    let now = Instant::now();
+    let mut bwe = LossBasedBwe::new(1_000_000, 100_000, 2_000_000);
+
+    // moderate loss holds the estimate
+    assert_eq!(bwe.on_loss(0.05, now), 1_000_000);
+
+    // high loss decreases the estimate
+    assert_eq!(bwe.on_loss(0.2, now), 900_000);
+
+    // low loss increases the estimate, but not more often than allowed
+    assert_eq!(bwe.on_loss(0.0, now), 945_000);
+    assert_eq!(bwe.on_loss(0.0, now + Duration::from_millis(100)), 945_000);
+    assert_eq!(bwe.on_loss(0.0, now + Duration::from_millis(300)), 992_250);
+
+    // the estimate never leaves the configured range
+    for _ in 0..20 {
+        bwe.on_loss(1.0, now);
+    }
+    assert_eq!(bwe.bitrate(), 100_000);
+}
+
+#[tokio::test]
+async fn test_send_side_bwe_interceptor() -> Result<()> {
+    let created = Arc::new(std::sync::Mutex::new(None));
+    let created2 = Arc::clone(&created);
+    let icpr: Arc<dyn Interceptor + Send + Sync> = SendSideBwe::builder()
+        .with_initial_bitrate(1_000_000)
+        .with_on_new_bwe(Box::new(move |_id: &str, bwe: Arc<SendSideBwe>| {
+            *created2.lock().unwrap() = Some(bwe);
+        }))
+        .build("")?;
+    let bwe = created
+        .lock()
+        .unwrap()
+        .take()
+        .expect("on_new_bwe should have been called");
+    assert_eq!(bwe.target_bitrate(), 1_000_000);
+    let mut target_bitrate = bwe.subscribe();
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            rtp_header_extensions: vec![RTPHeaderExtension {
+                uri: TRANSPORT_CC_URI.to_owned(),
+                id: 1,
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    let mut recorder = Recorder::new(5000);
+    for i in 0..10u16 {
+        let mut pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                ssrc: 1,
+                sequence_number: i,
+                ..Default::default()
+            },
+            payload: vec![0u8; 1000].into(),
+        };
+        let tcc_ext = TransportCcExtension {
+            transport_sequence: i,
+        };
+        pkt.header.set_extension(1, tcc_ext.marshal()?)?;
+        stream.write_rtp(&pkt).await?;
+
+        timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        recorder.record(1, i, i as i64 * 1000);
+    }
+
+    stream.receive_rtcp(recorder.build_feedback_packet()).await;
+    stream
+        .receive_rtcp(vec![Box::new(ReceiverReport {
+            ssrc: 5000,
+            reports: vec![ReceptionReport {
+                ssrc: 1,
+                fraction_lost: 128,
+                ..Default::default()
+            }],
+            ..Default::default()
+        })])
+        .await;
+
+    timeout_or_fail(Duration::from_millis(50), target_bitrate.changed())
+        .await
+        .expect("target bitrate should change");
+    assert_eq!(*target_bitrate.borrow(), 750_000);
+    assert_eq!(bwe.target_bitrate(), 750_000);
+
+    let stats = bwe.get_stats().await;
+    assert_eq!(stats.target_bitrate, 750_000);
+    assert_eq!(stats.loss_based_bitrate, 750_000);
+    assert!((stats.loss - 0.5).abs() < f64::EPSILON);
+    assert!(stats.received_bitrate.is_some());
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+const LOSS_LOW: f64 = 0.02;
+const LOSS_HIGH: f64 = 0.1;
+const LOSS_INCREASE_FACTOR: f64 = 1.05;
+const LOSS_INCREASE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// LossBasedBwe adapts the estimate to the fraction of packets reported lost
+/// in receiver reports, as described in
+/// https://datatracker.ietf.org/doc/html/draft-ietf-rmcat-gcc-02#section-6
+pub(crate) struct LossBasedBwe {
+    bitrate: f64,
+    min_bitrate: f64,
+    max_bitrate: f64,
+    loss: f64,
+    last_increase: Option<Instant>,
+}
+
+impl LossBasedBwe {
+    pub(crate) fn new(initial_bitrate: u64, min_bitrate: u64, max_bitrate: u64) -> Self {
+        LossBasedBwe {
+            bitrate: initial_bitrate as f64,
+            min_bitrate: min_bitrate as f64,
+            max_bitrate: max_bitrate as f64,
+            loss: 0.0,
+            last_increase: None,
+        }
+    }
+
+    /// on_loss updates the estimate with the fraction of lost packets in [0, 1]
+    /// and returns the new loss based estimate in bits per second.
+    pub(crate) fn on_loss(&mut self, loss: f64, now: Instant) -> u64 {
+        self.loss = loss;
+        if loss > LOSS_HIGH {
+            self.bitrate *= 1.0 - 0.5 * loss;
+        } else if loss < LOSS_LOW {
+            let can_increase = match self.last_increase {
+                Some(last_increase) => now.duration_since(last_increase) >= LOSS_INCREASE_INTERVAL,
+                None => true,
+            };
+            if can_increase {
+                self.bitrate *= LOSS_INCREASE_FACTOR;
+                self.last_increase = Some(now);
+            }
+        }
+        self.bitrate = self.bitrate.clamp(self.min_bitrate, self.max_bitrate);
+
+        self.bitrate as u64
+    }
+
+    pub(crate) fn set_bitrate(&mut self, bitrate: u64) {
+        self.bitrate = bitrate as f64;
+    }
+
+    pub(crate) fn bitrate(&self) -> u64 {
+        self.bitrate as u64
+    }
+
+    pub(crate) fn loss(&self) -> f64 {
+        self.loss
+    }
+}
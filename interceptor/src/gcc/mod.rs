@@ -0,0 +1,393 @@
+mod delay_based;
+mod feedback_adapter;
+#[cfg(test)]
+mod gcc_test;
+mod loss_based;
+
+use crate::error::Result;
+use crate::stream_info::StreamInfo;
+use crate::twcc::sender::TRANSPORT_CC_URI;
+use crate::{
+    Attributes, Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter,
+};
+use delay_based::DelayBasedBwe;
+use feedback_adapter::FeedbackAdapter;
+use loss_based::LossBasedBwe;
+
+pub use delay_based::BandwidthUsage;
+
+use async_trait::async_trait;
+use rtcp::receiver_report::ReceiverReport;
+use rtcp::reception_report::ReceptionReport;
+use rtcp::sender_report::SenderReport;
+use rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+use rtp::extension::transport_cc_extension::TransportCcExtension;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::time::Instant;
+use util::{MarshalSize, Unmarshal};
+
+const DEFAULT_INITIAL_BITRATE: u64 = 300_000;
+const DEFAULT_MIN_BITRATE: u64 = 30_000;
+const DEFAULT_MAX_BITRATE: u64 = 50_000_000;
+const DEFAULT_RTT: Duration = Duration::from_millis(100);
+
+/// FnNewBwe is called with the interceptor id and the estimator every time a
+/// SendSideBwe is built, so the application can hand it to its encoders.
+pub type FnNewBwe = Box<dyn Fn(&str, Arc<SendSideBwe>) + Send + Sync>;
+
+/// SendSideBweBuilder is a InterceptorBuilder for a SendSideBwe Interceptor.
+/// It has to be registered before the twcc::sender interceptor, so that its
+/// writer sees the transport wide sequence numbers of outgoing packets.
+#[derive(Default)]
+pub struct SendSideBweBuilder {
+    initial_bitrate: Option<u64>,
+    min_bitrate: Option<u64>,
+    max_bitrate: Option<u64>,
+    on_new_bwe: Option<FnNewBwe>,
+}
+
+impl SendSideBweBuilder {
+    /// with_initial_bitrate sets the estimate in bits per second used before any feedback arrived.
+    pub fn with_initial_bitrate(mut self, initial_bitrate: u64) -> SendSideBweBuilder {
+        self.initial_bitrate = Some(initial_bitrate);
+        self
+    }
+
+    /// with_min_bitrate sets the lower bound of the estimate in bits per second.
+    pub fn with_min_bitrate(mut self, min_bitrate: u64) -> SendSideBweBuilder {
+        self.min_bitrate = Some(min_bitrate);
+        self
+    }
+
+    /// with_max_bitrate sets the upper bound of the estimate in bits per second.
+    pub fn with_max_bitrate(mut self, max_bitrate: u64) -> SendSideBweBuilder {
+        self.max_bitrate = Some(max_bitrate);
+        self
+    }
+
+    /// with_on_new_bwe sets a callback which receives every SendSideBwe built.
+    pub fn with_on_new_bwe(mut self, on_new_bwe: FnNewBwe) -> SendSideBweBuilder {
+        self.on_new_bwe = Some(on_new_bwe);
+        self
+    }
+}
+
+impl InterceptorBuilder for SendSideBweBuilder {
+    fn build(&self, id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let min_bitrate = self.min_bitrate.unwrap_or(DEFAULT_MIN_BITRATE);
+        let max_bitrate =
+            std::cmp::max(self.max_bitrate.unwrap_or(DEFAULT_MAX_BITRATE), min_bitrate);
+        let initial_bitrate = self
+            .initial_bitrate
+            .unwrap_or(DEFAULT_INITIAL_BITRATE)
+            .clamp(min_bitrate, max_bitrate);
+
+        let (target_bitrate_tx, target_bitrate_rx) = watch::channel(initial_bitrate);
+        let bwe = Arc::new(SendSideBwe {
+            internal: Arc::new(SendSideBweInternal {
+                state: Mutex::new(BweState::new(initial_bitrate, min_bitrate, max_bitrate)),
+                target_bitrate_tx,
+                target_bitrate_rx,
+            }),
+        });
+
+        if let Some(f) = &self.on_new_bwe {
+            f(id, Arc::clone(&bwe));
+        }
+
+        Ok(bwe)
+    }
+}
+
+/// GccStats is a snapshot of the internal state of the estimator
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GccStats {
+    /// target_bitrate is the bitrate in bits per second the application should send at
+    pub target_bitrate: u64,
+    /// delay_based_bitrate is the estimate of the delay based controller
+    pub delay_based_bitrate: u64,
+    /// loss_based_bitrate is the estimate of the loss based controller
+    pub loss_based_bitrate: u64,
+    /// received_bitrate is the rate at which acknowledged packets arrived, if known
+    pub received_bitrate: Option<u64>,
+    /// usage is the latest signal of the overuse detector
+    pub usage: BandwidthUsage,
+    /// delay_trend is the modified delay trend compared against delay_threshold
+    pub delay_trend: f64,
+    /// delay_threshold is the adaptive threshold of the overuse detector in milliseconds
+    pub delay_threshold: f64,
+    /// loss is the fraction of lost packets from the latest receiver report
+    pub loss: f64,
+    /// rtt is the round trip time derived from transport wide feedback
+    pub rtt: Duration,
+}
+
+struct BweState {
+    feedback_adapter: FeedbackAdapter,
+    delay_based: DelayBasedBwe,
+    loss_based: LossBasedBwe,
+    // the loss based estimate only applies once the remote reported losses
+    loss_reported: bool,
+    rtt: Option<Duration>,
+    min_bitrate: u64,
+    max_bitrate: u64,
+}
+
+impl BweState {
+    fn new(initial_bitrate: u64, min_bitrate: u64, max_bitrate: u64) -> Self {
+        BweState {
+            feedback_adapter: FeedbackAdapter::default(),
+            delay_based: DelayBasedBwe::new(initial_bitrate, min_bitrate, max_bitrate),
+            loss_based: LossBasedBwe::new(initial_bitrate, min_bitrate, max_bitrate),
+            loss_reported: false,
+            rtt: None,
+            min_bitrate,
+            max_bitrate,
+        }
+    }
+
+    fn on_sent(&mut self, sequence_number: u16, size: usize, now: Instant) {
+        self.feedback_adapter.on_sent(sequence_number, size, now);
+    }
+
+    fn on_transport_cc(&mut self, feedback: &TransportLayerCc, now: Instant) -> u64 {
+        let acks = self.feedback_adapter.on_feedback(feedback);
+
+        // the most recently sent acknowledged packet bounds the round trip time best
+        if let Some(departure) = acks
+            .iter()
+            .filter(|ack| ack.arrival.is_some())
+            .map(|ack| ack.departure)
+            .max()
+        {
+            let sample = now.duration_since(departure);
+            self.rtt = Some(match self.rtt {
+                Some(rtt) => rtt.mul_f64(0.875) + sample.mul_f64(0.125),
+                None => sample,
+            });
+        }
+
+        self.delay_based.on_acknowledgments(&acks, self.rtt(), now);
+
+        self.target_bitrate()
+    }
+
+    fn on_reception_reports(&mut self, reports: &[ReceptionReport], now: Instant) -> u64 {
+        if reports.is_empty() {
+            return self.target_bitrate();
+        }
+
+        let fraction_lost: f64 = reports.iter().map(|r| r.fraction_lost as f64).sum();
+        let loss = fraction_lost / reports.len() as f64 / 256.0;
+        if !self.loss_reported {
+            // start from the estimate the delay based controller reached so far
+            self.loss_based.set_bitrate(self.target_bitrate());
+        }
+        self.loss_based.on_loss(loss, now);
+        self.loss_reported = true;
+
+        self.target_bitrate()
+    }
+
+    fn rtt(&self) -> Duration {
+        self.rtt.unwrap_or(DEFAULT_RTT)
+    }
+
+    fn target_bitrate(&self) -> u64 {
+        let mut target = self.delay_based.bitrate();
+        if self.loss_reported {
+            target = std::cmp::min(target, self.loss_based.bitrate());
+        }
+        target.clamp(self.min_bitrate, self.max_bitrate)
+    }
+
+    fn stats(&self) -> GccStats {
+        GccStats {
+            target_bitrate: self.target_bitrate(),
+            delay_based_bitrate: self.delay_based.bitrate(),
+            loss_based_bitrate: self.loss_based.bitrate(),
+            received_bitrate: self.delay_based.received_rate().map(|r| r as u64),
+            usage: self.delay_based.usage(),
+            delay_trend: self.delay_based.modified_trend(),
+            delay_threshold: self.delay_based.threshold(),
+            loss: self.loss_based.loss(),
+            rtt: self.rtt(),
+        }
+    }
+}
+
+struct SendSideBweInternal {
+    state: Mutex<BweState>,
+    target_bitrate_tx: watch::Sender<u64>,
+    // keeps the channel open while nobody is subscribed
+    target_bitrate_rx: watch::Receiver<u64>,
+}
+
+impl SendSideBweInternal {
+    fn publish(&self, target_bitrate: u64) {
+        if *self.target_bitrate_rx.borrow() != target_bitrate {
+            let _ = self.target_bitrate_tx.send(target_bitrate);
+        }
+    }
+}
+
+pub struct SendSideBweRtcpReader {
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+    internal: Arc<SendSideBweInternal>,
+}
+
+#[async_trait]
+impl RTCPReader for SendSideBweRtcpReader {
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = { self.parent_rtcp_reader.read(buf, a).await? };
+
+        let mut b = &buf[..n];
+        let pkts = rtcp::packet::unmarshal(&mut b)?;
+        let now = Instant::now();
+        for p in &pkts {
+            let target_bitrate = if let Some(tcc) = p.as_any().downcast_ref::<TransportLayerCc>() {
+                let mut state = self.internal.state.lock().await;
+                state.on_transport_cc(tcc, now)
+            } else if let Some(rr) = p.as_any().downcast_ref::<ReceiverReport>() {
+                let mut state = self.internal.state.lock().await;
+                state.on_reception_reports(&rr.reports, now)
+            } else if let Some(sr) = p.as_any().downcast_ref::<SenderReport>() {
+                let mut state = self.internal.state.lock().await;
+                state.on_reception_reports(&sr.reports, now)
+            } else {
+                continue;
+            };
+            self.internal.publish(target_bitrate);
+        }
+
+        Ok((n, attr))
+    }
+}
+
+pub struct SendSideBweRtpWriter {
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    internal: Arc<SendSideBweInternal>,
+    hdr_ext_id: u8,
+}
+
+#[async_trait]
+impl RTPWriter for SendSideBweRtpWriter {
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        if let Some(mut ext) = pkt.header.get_extension(self.hdr_ext_id) {
+            if let Ok(tcc_ext) = TransportCcExtension::unmarshal(&mut ext) {
+                let mut state = self.internal.state.lock().await;
+                state.on_sent(
+                    tcc_ext.transport_sequence,
+                    pkt.marshal_size(),
+                    Instant::now(),
+                );
+            }
+        }
+
+        self.next_rtp_writer.write(pkt, a).await
+    }
+}
+
+/// SendSideBwe estimates the available send bandwidth with Google Congestion Control as specified in:
+/// https://datatracker.ietf.org/doc/html/draft-ietf-rmcat-gcc-02
+/// A delay based controller is fed by transport wide congestion control feedback and a
+/// loss based controller by receiver reports; the target bitrate is the lower of both.
+pub struct SendSideBwe {
+    internal: Arc<SendSideBweInternal>,
+}
+
+impl SendSideBwe {
+    /// builder returns a new SendSideBweBuilder.
+    pub fn builder() -> SendSideBweBuilder {
+        SendSideBweBuilder::default()
+    }
+
+    /// target_bitrate returns the current target bitrate in bits per second.
+    pub fn target_bitrate(&self) -> u64 {
+        *self.internal.target_bitrate_rx.borrow()
+    }
+
+    /// subscribe returns a receiver which is notified whenever the target bitrate changes.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.internal.target_bitrate_rx.clone()
+    }
+
+    /// get_stats returns a snapshot of the estimator state.
+    pub async fn get_stats(&self) -> GccStats {
+        let state = self.internal.state.lock().await;
+        state.stats()
+    }
+}
+
+#[async_trait]
+impl Interceptor for SendSideBwe {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(SendSideBweRtcpReader {
+            internal: Arc::clone(&self.internal),
+            parent_rtcp_reader: reader,
+        }) as Arc<dyn RTCPReader + Send + Sync>
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream returns a writer that records the transport wide
+    /// sequence number, size and departure time of each outgoing packet.
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        let mut hdr_ext_id = 0u8;
+        for e in &info.rtp_header_extensions {
+            if e.uri == TRANSPORT_CC_URI {
+                hdr_ext_id = e.id as u8;
+                break;
+            }
+        }
+        if hdr_ext_id == 0 {
+            // Packets without transport wide sequence numbers can't be acknowledged
+            return writer;
+        }
+
+        Arc::new(SendSideBweRtpWriter {
+            next_rtp_writer: writer,
+            internal: Arc::clone(&self.internal),
+            hdr_ext_id,
+        })
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -12,9 +12,13 @@ use stream_info::StreamInfo;
 
 pub mod chain;
 mod error;
+pub mod flexfec;
+pub mod gcc;
 pub mod mock;
 pub mod nack;
 pub mod noop;
+pub mod pacer;
+pub mod red;
 pub mod registry;
 pub mod report;
 pub mod stats;
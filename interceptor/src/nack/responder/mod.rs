@@ -74,7 +74,7 @@ impl ResponderInternal {
                 move |seq: u16| -> Pin<Box<dyn Future<Output = bool> + Send + 'static>> {
                     let stream3 = Arc::clone(&stream2);
                     Box::pin(async move {
-                        if let Some(p) = stream3.get(seq).await {
+                        if let Some(p) = stream3.retransmission(seq).await {
                             let a = Attributes::new();
                             if let Err(err) = stream3.next_rtp_writer.write(&p, &a).await {
                                 log::warn!("failed resending nacked packet: {}", err);
@@ -116,7 +116,8 @@ impl RTCPReader for ResponderRtcpReader {
     }
 }
 
-/// Responder responds to nack feedback messages
+/// Responder responds to nack feedback messages. Lost packets are resent on a separate
+/// RFC 4588 retransmission stream if the StreamInfo has one, and on the media stream otherwise.
 pub struct Responder {
     internal: Arc<ResponderInternal>,
 }
@@ -162,7 +163,7 @@ impl Interceptor for Responder {
             return writer;
         }
 
-        let stream = Arc::new(ResponderStream::new(self.internal.log2_size, writer));
+        let stream = Arc::new(ResponderStream::new(self.internal.log2_size, info, writer));
         {
             let mut streams = self.internal.streams.lock().await;
             streams.insert(info.ssrc, Arc::clone(&stream));
@@ -1,8 +1,11 @@
 use crate::error::Result;
 use crate::nack::UINT16SIZE_HALF;
+use crate::stream_info::StreamInfo;
 use crate::{Attributes, RTPWriter};
 
 use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -61,15 +64,38 @@ impl ResponderStreamInternal {
     }
 }
 
+/// RtxStream holds the state of a RFC 4588 retransmission stream
+struct RtxStream {
+    ssrc: u32,
+    payload_type: u8,
+    sequence_number: AtomicU16,
+}
+
 pub(super) struct ResponderStream {
     internal: Mutex<ResponderStreamInternal>,
+    rtx: Option<RtxStream>,
     pub(super) next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
 }
 
 impl ResponderStream {
-    pub(super) fn new(log2_size: u8, writer: Arc<dyn RTPWriter + Send + Sync>) -> Self {
+    pub(super) fn new(
+        log2_size: u8,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        let rtx = if info.ssrc_retransmission != 0 && info.payload_type_retransmission != 0 {
+            Some(RtxStream {
+                ssrc: info.ssrc_retransmission,
+                payload_type: info.payload_type_retransmission,
+                sequence_number: AtomicU16::new(rand::random::<u16>()),
+            })
+        } else {
+            None
+        };
+
         ResponderStream {
             internal: Mutex::new(ResponderStreamInternal::new(log2_size)),
+            rtx,
             next_rtp_writer: writer,
         }
     }
@@ -83,6 +109,32 @@ impl ResponderStream {
         let internal = self.internal.lock().await;
         internal.get(seq).cloned()
     }
+
+    /// retransmission returns the packet to resend for seq. If a retransmission stream
+    /// is negotiated, the packet is wrapped into a RTX packet with the original sequence
+    /// number (OSN) prepended to the payload, otherwise it is resent as is.
+    pub(super) async fn retransmission(&self, seq: u16) -> Option<rtp::packet::Packet> {
+        let pkt = self.get(seq).await?;
+        let rtx = match &self.rtx {
+            Some(rtx) => rtx,
+            None => return Some(pkt),
+        };
+
+        let mut payload = BytesMut::with_capacity(2 + pkt.payload.len());
+        payload.put_u16(pkt.header.sequence_number);
+        payload.extend_from_slice(&pkt.payload);
+
+        let mut header = pkt.header;
+        header.ssrc = rtx.ssrc;
+        header.payload_type = rtx.payload_type;
+        header.sequence_number = rtx.sequence_number.fetch_add(1, Ordering::SeqCst);
+        header.padding = false;
+
+        Some(rtp::packet::Packet {
+            header,
+            payload: payload.freeze(),
+        })
+    }
 }
 
 /// RTPWriter is used by Interceptor.bind_local_stream.
@@ -78,3 +78,83 @@ async fn test_responder_interceptor() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_responder_interceptor_rtx() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> =
+        Responder::builder().with_log2_size(3).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            ssrc_retransmission: 2,
+            payload_type_retransmission: 97,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in [10u16, 11, 12] {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc: 1,
+                    payload_type: 96,
+                    sequence_number: seq_num,
+                    ..Default::default()
+                },
+                payload: vec![seq_num as u8; 4].into(),
+            })
+            .await?;
+
+        let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!(seq_num, p.header.sequence_number);
+    }
+
+    stream
+        .receive_rtcp(vec![Box::new(TransportLayerNack {
+            media_ssrc: 1,
+            sender_ssrc: 2,
+            nacks: vec![NackPair {
+                packet_id: 10,
+                lost_packets: 0b10,
+            }],
+        })])
+        .await;
+
+    let mut rtx_seq_num = None;
+    for seq_num in [10u16, 12] {
+        let p = timeout_or_fail(Duration::from_millis(50), stream.written_rtp())
+            .await
+            .expect("A retransmission");
+        assert_eq!(p.header.ssrc, 2);
+        assert_eq!(p.header.payload_type, 97);
+        if let Some(rtx_seq_num) = rtx_seq_num {
+            assert_eq!(
+                p.header.sequence_number,
+                u16::wrapping_add(rtx_seq_num, 1),
+                "rtx sequence numbers should be consecutive"
+            );
+        }
+        rtx_seq_num = Some(p.header.sequence_number);
+
+        let mut expected = seq_num.to_be_bytes().to_vec();
+        expected.extend_from_slice(&[seq_num as u8; 4]);
+        assert_eq!(p.payload.as_ref(), expected.as_slice());
+    }
+
+    let result = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(result.is_err(), "no more rtp packets expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+mod pacer_queue;
+mod pacer_stream;
+#[cfg(test)]
+mod pacer_test;
+
+use crate::error::Result;
+use crate::stream_info::StreamInfo;
+use crate::{Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
+use pacer_queue::PacerQueue;
+use pacer_stream::PacerStream;
+
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{Instant, MissedTickBehavior};
+use waitgroup::WaitGroup;
+
+const DEFAULT_BITRATE: u64 = 2_000_000;
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(5);
+const DEFAULT_MAX_QUEUE_SIZE: usize = 1024;
+// the default burst budget allows sending 40ms worth of data at once
+const DEFAULT_BURST_DURATION: Duration = Duration::from_millis(40);
+const MIN_BURST_SIZE: usize = 1500;
+
+/// FnNewPacer is called with the interceptor id and the pacer every time a
+/// Pacer is built, so the application can adjust its rate later on.
+pub type FnNewPacer = Box<dyn Fn(&str, Arc<Pacer>) + Send + Sync>;
+
+/// PacerBuilder is a InterceptorBuilder for a Pacer Interceptor.
+/// Register it before the NACK responder so retransmissions are paced and
+/// prioritized as well.
+#[derive(Default)]
+pub struct PacerBuilder {
+    bitrate: Option<u64>,
+    burst_size: Option<usize>,
+    interval: Option<Duration>,
+    max_queue_size: Option<usize>,
+    on_new_pacer: Option<FnNewPacer>,
+}
+
+impl PacerBuilder {
+    /// with_bitrate sets the rate in bits per second at which packets are released.
+    pub fn with_bitrate(mut self, bitrate: u64) -> PacerBuilder {
+        self.bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_burst_size sets how many bytes may be sent at once after the pacer was idle.
+    pub fn with_burst_size(mut self, burst_size: usize) -> PacerBuilder {
+        self.burst_size = Some(burst_size);
+        self
+    }
+
+    /// with_interval sets how often queued packets are released.
+    pub fn with_interval(mut self, interval: Duration) -> PacerBuilder {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// with_max_queue_size sets how many packets are queued per SSRC before the oldest are dropped.
+    pub fn with_max_queue_size(mut self, max_queue_size: usize) -> PacerBuilder {
+        self.max_queue_size = Some(max_queue_size);
+        self
+    }
+
+    /// with_on_new_pacer sets a callback which receives every Pacer built.
+    pub fn with_on_new_pacer(mut self, on_new_pacer: FnNewPacer) -> PacerBuilder {
+        self.on_new_pacer = Some(on_new_pacer);
+        self
+    }
+}
+
+impl InterceptorBuilder for PacerBuilder {
+    fn build(&self, id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let bitrate = self.bitrate.unwrap_or(DEFAULT_BITRATE);
+        let burst_size = if let Some(burst_size) = self.burst_size {
+            burst_size
+        } else {
+            std::cmp::max(
+                (bitrate as f64 / 8.0 * DEFAULT_BURST_DURATION.as_secs_f64()) as usize,
+                MIN_BURST_SIZE,
+            )
+        };
+
+        let (close_tx, close_rx) = mpsc::channel(1);
+        let pacer = Arc::new(Pacer {
+            internal: Arc::new(PacerInternal {
+                bitrate: AtomicU64::new(bitrate),
+                burst_size,
+                interval: self.interval.unwrap_or(DEFAULT_INTERVAL),
+                queue: Mutex::new(PacerQueue::new(
+                    self.max_queue_size.unwrap_or(DEFAULT_MAX_QUEUE_SIZE),
+                )),
+                close_rx: Mutex::new(Some(close_rx)),
+            }),
+            wg: Mutex::new(Some(WaitGroup::new())),
+            close_tx: Mutex::new(Some(close_tx)),
+        });
+
+        if let Some(f) = &self.on_new_pacer {
+            f(id, Arc::clone(&pacer));
+        }
+
+        Ok(pacer)
+    }
+}
+
+pub(crate) struct PacerInternal {
+    bitrate: AtomicU64,
+    burst_size: usize,
+    interval: Duration,
+    queue: Mutex<PacerQueue>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+/// Pacer queues outgoing RTP packets per SSRC and releases them at a
+/// configured rate, so large frames don't leave as a single burst.
+pub struct Pacer {
+    internal: Arc<PacerInternal>,
+
+    wg: Mutex<Option<WaitGroup>>,
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl Pacer {
+    /// builder returns a new PacerBuilder.
+    pub fn builder() -> PacerBuilder {
+        PacerBuilder::default()
+    }
+
+    /// bitrate returns the pacing rate in bits per second.
+    pub fn bitrate(&self) -> u64 {
+        self.internal.bitrate.load(Ordering::SeqCst)
+    }
+
+    /// set_bitrate changes the pacing rate in bits per second.
+    pub fn set_bitrate(&self, bitrate: u64) {
+        self.internal.bitrate.store(bitrate, Ordering::SeqCst);
+    }
+
+    /// queue_len returns the number of packets waiting to be sent.
+    pub async fn queue_len(&self) -> usize {
+        let queue = self.internal.queue.lock().await;
+        queue.len()
+    }
+
+    async fn is_closed(&self) -> bool {
+        let close_tx = self.close_tx.lock().await;
+        close_tx.is_none()
+    }
+
+    async fn run(internal: Arc<PacerInternal>, mut close_rx: mpsc::Receiver<()>) {
+        let mut ticker = tokio::time::interval(internal.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut budget = internal.burst_size as f64;
+        let mut last_tick = Instant::now();
+        loop {
+            tokio::select! {
+                _ = ticker.tick() =>{
+                    let now = Instant::now();
+                    let elapsed = now.duration_since(last_tick).as_secs_f64();
+                    last_tick = now;
+
+                    let bitrate = internal.bitrate.load(Ordering::SeqCst) as f64;
+                    budget = f64::min(budget + bitrate / 8.0 * elapsed, internal.burst_size as f64);
+
+                    let pkts = {
+                        let mut queue = internal.queue.lock().await;
+                        let mut pkts = vec![];
+                        while budget > 0.0 {
+                            if let Some((writer, p)) = queue.pop() {
+                                budget -= p.size as f64;
+                                pkts.push((writer, p));
+                            } else {
+                                break;
+                            }
+                        }
+                        pkts
+                    };
+
+                    for (writer, p) in pkts {
+                        if let Err(err) = writer.write(&p.pkt, &p.attributes).await {
+                            log::warn!("failed sending paced packet: {}", err);
+                        }
+                    }
+                }
+                _ = close_rx.recv() =>{
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for Pacer {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream returns a writer that queues outgoing packets, they
+    /// are forwarded to the given writer once the pacing budget allows.
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        if self.is_closed().await {
+            return writer;
+        }
+
+        // the first bound stream starts releasing queued packets
+        let close_rx = {
+            let mut close_rx = self.internal.close_rx.lock().await;
+            close_rx.take()
+        };
+        if let Some(close_rx) = close_rx {
+            let mut w = {
+                let wait_group = self.wg.lock().await;
+                wait_group.as_ref().map(|wg| wg.worker())
+            };
+            let internal = Arc::clone(&self.internal);
+            tokio::spawn(async move {
+                let _d = w.take();
+                Pacer::run(internal, close_rx).await;
+            });
+        }
+
+        let audio = info.mime_type.to_lowercase().starts_with("audio/");
+        {
+            let mut queue = self.internal.queue.lock().await;
+            queue.add_stream(info.ssrc, audio, Arc::clone(&writer));
+            if info.ssrc_retransmission != 0 {
+                queue.add_associated_stream(info.ssrc_retransmission, info.ssrc, true);
+            }
+            if info.ssrc_forward_error_correction != 0 {
+                queue.add_associated_stream(info.ssrc_forward_error_correction, info.ssrc, false);
+            }
+        }
+
+        Arc::new(PacerStream::new(writer, Arc::clone(&self.internal)))
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, info: &StreamInfo) {
+        let mut queue = self.internal.queue.lock().await;
+        queue.remove_stream(info.ssrc);
+    }
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        {
+            let mut close_tx = self.close_tx.lock().await;
+            close_tx.take();
+        }
+
+        {
+            let mut wait_group = self.wg.lock().await;
+            if let Some(wg) = wait_group.take() {
+                wg.wait().await;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{Attributes, RTPWriter};
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use util::MarshalSize;
+
+pub(crate) struct QueuedPacket {
+    pub(crate) pkt: rtp::packet::Packet,
+    pub(crate) attributes: Attributes,
+    pub(crate) size: usize,
+    order: u64,
+}
+
+struct SsrcQueue {
+    audio: bool,
+    writer: Arc<dyn RTPWriter + Send + Sync>,
+    retransmissions: VecDeque<QueuedPacket>,
+    media: VecDeque<QueuedPacket>,
+    last_sequence_number: Option<u16>,
+}
+
+impl SsrcQueue {
+    fn len(&self) -> usize {
+        self.retransmissions.len() + self.media.len()
+    }
+
+    /// priority returns the class of the next packet of the given queue, lower is sent first
+    fn priority(&self, retransmission: bool) -> u8 {
+        if self.audio {
+            0
+        } else if retransmission {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// PacerQueue holds the packets waiting to be sent, one queue per SSRC.
+/// Audio is sent first, then video retransmissions and then video; packets
+/// of the same class leave in the order they were queued. Packets of the RTX
+/// and FEC SSRCs of a stream share the queue of its media SSRC.
+pub(crate) struct PacerQueue {
+    queues: HashMap<u32, SsrcQueue>,
+    // SSRC of an RTX or FEC stream -> (media SSRC, whether it carries retransmissions)
+    associated: HashMap<u32, (u32, bool)>,
+    max_queue_size: usize,
+    next_order: u64,
+}
+
+impl PacerQueue {
+    pub(crate) fn new(max_queue_size: usize) -> Self {
+        PacerQueue {
+            queues: HashMap::new(),
+            associated: HashMap::new(),
+            max_queue_size,
+            next_order: 0,
+        }
+    }
+
+    pub(crate) fn add_stream(
+        &mut self,
+        ssrc: u32,
+        audio: bool,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) {
+        self.queues.insert(
+            ssrc,
+            SsrcQueue {
+                audio,
+                writer,
+                retransmissions: VecDeque::new(),
+                media: VecDeque::new(),
+                last_sequence_number: None,
+            },
+        );
+    }
+
+    /// add_associated_stream queues the packets of ssrc, an RTX or FEC stream, with
+    /// the ones of media_ssrc. RTX packets are all retransmissions.
+    pub(crate) fn add_associated_stream(
+        &mut self,
+        ssrc: u32,
+        media_ssrc: u32,
+        retransmission: bool,
+    ) {
+        self.associated.insert(ssrc, (media_ssrc, retransmission));
+    }
+
+    pub(crate) fn remove_stream(&mut self, ssrc: u32) {
+        self.queues.remove(&ssrc);
+        self.associated
+            .retain(|_, (media_ssrc, _)| *media_ssrc != ssrc);
+    }
+
+    /// push queues a packet, it returns false if the SSRC isn't bound
+    pub(crate) fn push(&mut self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> bool {
+        let (ssrc, associated) = match self.associated.get(&pkt.header.ssrc) {
+            Some((media_ssrc, retransmission)) => (*media_ssrc, Some(*retransmission)),
+            None => (pkt.header.ssrc, None),
+        };
+        let queue = match self.queues.get_mut(&ssrc) {
+            Some(queue) => queue,
+            None => return false,
+        };
+
+        // a media packet not newer than the last one seen is a retransmission,
+        // the sequence numbers of RTX and FEC streams are of their own
+        let retransmission = match associated {
+            Some(retransmission) => retransmission,
+            None => {
+                let sequence_number = pkt.header.sequence_number;
+                let retransmission = match queue.last_sequence_number {
+                    Some(last) => (sequence_number.wrapping_sub(last) as i16) <= 0,
+                    None => false,
+                };
+                if !retransmission {
+                    queue.last_sequence_number = Some(sequence_number);
+                }
+                retransmission
+            }
+        };
+
+        if queue.len() >= self.max_queue_size {
+            let dropped = if queue.media.is_empty() {
+                queue.retransmissions.pop_front()
+            } else {
+                queue.media.pop_front()
+            };
+            if let Some(dropped) = dropped {
+                log::debug!(
+                    "pacer queue for ssrc {} is full, dropping packet {}",
+                    pkt.header.ssrc,
+                    dropped.pkt.header.sequence_number
+                );
+            }
+        }
+
+        let queued = QueuedPacket {
+            size: pkt.marshal_size(),
+            pkt: pkt.clone(),
+            attributes: attributes.clone(),
+            order: self.next_order,
+        };
+        self.next_order += 1;
+
+        if retransmission {
+            queue.retransmissions.push_back(queued);
+        } else {
+            queue.media.push_back(queued);
+        }
+
+        true
+    }
+
+    /// pop removes the packet which should be sent next
+    pub(crate) fn pop(&mut self) -> Option<(Arc<dyn RTPWriter + Send + Sync>, QueuedPacket)> {
+        let mut next: Option<(u8, u64, u32, bool)> = None;
+        for (ssrc, queue) in &self.queues {
+            for (retransmission, head) in [
+                (true, queue.retransmissions.front()),
+                (false, queue.media.front()),
+            ] {
+                if let Some(head) = head {
+                    let candidate = (
+                        queue.priority(retransmission),
+                        head.order,
+                        *ssrc,
+                        retransmission,
+                    );
+                    let better = match next {
+                        Some(n) => (candidate.0, candidate.1) < (n.0, n.1),
+                        None => true,
+                    };
+                    if better {
+                        next = Some(candidate);
+                    }
+                }
+            }
+        }
+
+        let (_, _, ssrc, retransmission) = next?;
+        let queue = self.queues.get_mut(&ssrc)?;
+        let queued = if retransmission {
+            queue.retransmissions.pop_front()
+        } else {
+            queue.media.pop_front()
+        }?;
+
+        Some((Arc::clone(&queue.writer), queued))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.queues.values().all(|q| q.len() == 0)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queues.values().map(|q| q.len()).sum()
+    }
+}
@@ -0,0 +1,39 @@
+use super::*;
+use crate::Attributes;
+
+use util::MarshalSize;
+
+pub(super) struct PacerStream {
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+    internal: Arc<PacerInternal>,
+}
+
+impl PacerStream {
+    pub(super) fn new(
+        next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+        internal: Arc<PacerInternal>,
+    ) -> Self {
+        PacerStream {
+            next_rtp_writer,
+            internal,
+        }
+    }
+}
+
+/// RTPWriter is used by Interceptor.bind_local_stream.
+#[async_trait]
+impl RTPWriter for PacerStream {
+    /// write a rtp packet
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        let queued = {
+            let mut queue = self.internal.queue.lock().await;
+            queue.push(pkt, a)
+        };
+        if !queued {
+            // the stream was unbound already, don't hold its packets back
+            return self.next_rtp_writer.write(pkt, a).await;
+        }
+
+        Ok(pkt.marshal_size())
+    }
+}
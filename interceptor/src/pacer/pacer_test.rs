@@ -0,0 +1,208 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::test::timeout_or_fail;
+use crate::{Attributes, RTPWriterFn};
+
+fn new_packet(ssrc: u32, sequence_number: u16, payload_size: usize) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            ssrc,
+            sequence_number,
+            ..Default::default()
+        },
+        payload: vec![0u8; payload_size].into(),
+    }
+}
+
+fn noop_writer() -> Arc<dyn RTPWriter + Send + Sync> {
+    Arc::new(RTPWriterFn(Box::new(
+        |pkt: &rtp::packet::Packet, _a: &Attributes| {
+            let n = pkt.payload.len();
+            Box::pin(async move { Ok(n) })
+        },
+    )))
+}
+
+#[test]
+fn test_pacer_queue_priority() {
+    let mut queue = PacerQueue::new(100);
+    queue.add_stream(1, false, noop_writer());
+    queue.add_stream(2, true, noop_writer());
+
+    let a = Attributes::new();
+    assert!(queue.push(&new_packet(1, 10, 100), &a));
+    assert!(queue.push(&new_packet(1, 11, 100), &a));
+    assert!(queue.push(&new_packet(1, 12, 100), &a));
+    assert!(queue.push(&new_packet(2, 50, 100), &a));
+    // 11 was queued before, so this is a retransmission
+    assert!(queue.push(&new_packet(1, 11, 100), &a));
+    assert!(queue.push(&new_packet(2, 51, 100), &a));
+    assert!(
+        !queue.push(&new_packet(3, 1, 100), &a),
+        "ssrc 3 isn't bound"
+    );
+    assert_eq!(queue.len(), 6);
+
+    let mut order = vec![];
+    while let Some((_, p)) = queue.pop() {
+        order.push((p.pkt.header.ssrc, p.pkt.header.sequence_number));
+    }
+    assert_eq!(
+        order,
+        vec![(2, 50), (2, 51), (1, 11), (1, 10), (1, 11), (1, 12)]
+    );
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_pacer_queue_associated_streams() {
+    let mut queue = PacerQueue::new(100);
+    queue.add_stream(1, false, noop_writer());
+    queue.add_associated_stream(10, 1, true);
+    queue.add_associated_stream(20, 1, false);
+
+    let a = Attributes::new();
+    assert!(queue.push(&new_packet(1, 100, 100), &a));
+    assert!(queue.push(&new_packet(1, 101, 100), &a));
+    // FEC packets are paced like media
+    assert!(queue.push(&new_packet(20, 5, 100), &a));
+    // RTX packets are retransmissions, whatever their sequence number
+    assert!(queue.push(&new_packet(10, 1000, 100), &a));
+    assert!(queue.push(&new_packet(1, 102, 100), &a));
+    assert_eq!(queue.len(), 5);
+
+    let mut order = vec![];
+    while let Some((_, p)) = queue.pop() {
+        order.push((p.pkt.header.ssrc, p.pkt.header.sequence_number));
+    }
+    assert_eq!(
+        order,
+        vec![(10, 1000), (1, 100), (1, 101), (20, 5), (1, 102)]
+    );
+
+    queue.remove_stream(1);
+    assert!(
+        !queue.push(&new_packet(10, 1001, 100), &a),
+        "the RTX stream is unbound with its media stream"
+    );
+}
+
+#[test]
+fn test_pacer_queue_max_size() {
+    let mut queue = PacerQueue::new(2);
+    queue.add_stream(1, false, noop_writer());
+
+    let a = Attributes::new();
+    for seq in 0..4 {
+        assert!(queue.push(&new_packet(1, seq, 100), &a));
+    }
+    assert_eq!(queue.len(), 2);
+
+    let (_, p) = queue.pop().expect("a packet");
+    assert_eq!(
+        p.pkt.header.sequence_number, 2,
+        "the oldest packets are dropped"
+    );
+
+    queue.remove_stream(1);
+    assert!(queue.pop().is_none());
+}
+
+#[tokio::test]
+async fn test_pacer_interceptor() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Pacer::builder()
+        .with_bitrate(400_000)
+        .with_burst_size(2000)
+        .with_interval(Duration::from_millis(5))
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    let start = Instant::now();
+    for seq in 0..10 {
+        stream.write_rtp(&new_packet(1, seq, 1000)).await?;
+    }
+
+    for seq in 0..10 {
+        let p = timeout_or_fail(Duration::from_millis(500), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!(p.header.sequence_number, seq);
+    }
+
+    // the burst budget covers two packets, the other eight leave at 50 kB/s
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(120),
+        "packets were not paced, sent within {:?}",
+        elapsed
+    );
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pacer_interceptor_rtx() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Pacer::builder()
+        .with_bitrate(400_000)
+        .with_burst_size(1000)
+        .with_interval(Duration::from_millis(5))
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ssrc_retransmission: 2,
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // The packets are queued until the next tick, the retransmissions on the
+    // RTX SSRC leave first but are paced as well
+    let start = Instant::now();
+    for seq in 0..2 {
+        stream.write_rtp(&new_packet(1, seq, 1000)).await?;
+    }
+    for seq in 500..504 {
+        stream.write_rtp(&new_packet(2, seq, 1000)).await?;
+    }
+
+    let mut order = vec![];
+    for i in 0..6 {
+        let p = timeout_or_fail(Duration::from_millis(500), stream.written_rtp())
+            .await
+            .expect("A packet");
+        order.push((p.header.ssrc, p.header.sequence_number));
+
+        if i == 3 {
+            // the budget covers one packet, the others leave at 50 kB/s
+            let elapsed = start.elapsed();
+            assert!(
+                elapsed >= Duration::from_millis(30),
+                "retransmissions were not paced, sent within {:?}",
+                elapsed
+            );
+        }
+    }
+    assert_eq!(
+        order,
+        vec![(2, 500), (2, 501), (2, 502), (2, 503), (1, 0), (1, 1)]
+    );
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::error::{Error, Result};
+use crate::{Attributes, RTPReader};
+
+use async_trait::async_trait;
+use rtp::codecs::red::RedPacket;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use util::{Marshal, MarshalSize, Unmarshal};
+
+/// How many sequence numbers before the newest one are remembered to drop duplicates
+const RECEIVED_WINDOW: u16 = 128;
+
+struct DecoderStreamInternal {
+    highest_sequence_number: Option<u16>,
+    received: VecDeque<u16>,
+    pending: VecDeque<(rtp::packet::Packet, Attributes)>,
+    // sequence number and timestamp of the newest packet
+    last_packet: Option<(u16, u32)>,
+    // timestamp increment between consecutive packets
+    frame_duration: Option<u32>,
+}
+
+impl DecoderStreamInternal {
+    /// is_new returns true if seq wasn't returned before and is recent enough to be returned now
+    fn is_new(&self, seq: u16) -> bool {
+        let highest = match self.highest_sequence_number {
+            Some(highest) => highest,
+            None => return true,
+        };
+        let behind = highest.wrapping_sub(seq);
+        if behind != 0 && behind < RECEIVED_WINDOW {
+            !self.received.contains(&seq)
+        } else {
+            // newer than anything seen
+            behind >= u16::MAX / 2
+        }
+    }
+
+    fn mark_received(&mut self, seq: u16) {
+        match self.highest_sequence_number {
+            Some(highest) if (seq.wrapping_sub(highest) as i16) <= 0 => {}
+            _ => self.highest_sequence_number = Some(seq),
+        }
+        if self.received.len() >= RECEIVED_WINDOW as usize {
+            self.received.pop_front();
+        }
+        self.received.push_back(seq);
+    }
+
+    /// update_frame_duration learns the timestamp increment of a frame from consecutive packets
+    fn update_frame_duration(&mut self, seq: u16, timestamp: u32) {
+        match self.last_packet {
+            Some((last_seq, _)) if (seq.wrapping_sub(last_seq) as i16) <= 0 => return,
+            Some((last_seq, last_timestamp)) if seq == last_seq.wrapping_add(1) => {
+                let duration = timestamp.wrapping_sub(last_timestamp);
+                if duration != 0 && duration < u32::MAX / 2 {
+                    self.frame_duration = Some(duration);
+                }
+            }
+            _ => {}
+        }
+        self.last_packet = Some((seq, timestamp));
+    }
+
+    /// block_sequence_number returns the sequence number of a redundant block. RED only
+    /// carries its timestamp offset, the block is recovered if the offset is a whole
+    /// number of frames.
+    fn block_sequence_number(&self, seq: u16, timestamp_offset: u32) -> Option<u16> {
+        let frame_duration = self.frame_duration?;
+        if timestamp_offset == 0 || timestamp_offset % frame_duration != 0 {
+            return None;
+        }
+        let frames = timestamp_offset / frame_duration;
+        if frames >= RECEIVED_WINDOW as u32 {
+            return None;
+        }
+        Some(seq.wrapping_sub(frames as u16))
+    }
+
+    /// add unwraps a received packet, it queues the recovered frames followed by the primary frame
+    fn add(&mut self, red_payload_type: u8, pkt: rtp::packet::Packet, attr: Attributes) {
+        let seq = pkt.header.sequence_number;
+        self.update_frame_duration(seq, pkt.header.timestamp);
+        if pkt.header.payload_type != red_payload_type {
+            if self.is_new(seq) {
+                self.mark_received(seq);
+                self.pending.push_back((pkt, attr));
+            }
+            return;
+        }
+
+        let red = match RedPacket::unmarshal(&pkt.payload) {
+            Ok(red) => red,
+            Err(err) => {
+                log::debug!("dropping invalid RED packet: {}", err);
+                return;
+            }
+        };
+
+        // only frames following the first received one are recovered
+        let started = self.highest_sequence_number.is_some();
+        for block in red.redundant {
+            let block_seq = match self.block_sequence_number(seq, block.timestamp_offset) {
+                Some(block_seq) => block_seq,
+                None => {
+                    log::trace!(
+                        "can't place redundant block with timestamp offset {}",
+                        block.timestamp_offset
+                    );
+                    continue;
+                }
+            };
+            if !started || !self.is_new(block_seq) {
+                continue;
+            }
+            self.mark_received(block_seq);
+
+            let mut header = pkt.header.clone();
+            header.sequence_number = block_seq;
+            header.timestamp = pkt.header.timestamp.wrapping_sub(block.timestamp_offset);
+            header.payload_type = block.payload_type;
+            header.marker = false;
+            self.pending.push_back((
+                rtp::packet::Packet {
+                    header,
+                    payload: block.payload,
+                },
+                attr.clone(),
+            ));
+        }
+
+        if self.is_new(seq) {
+            self.mark_received(seq);
+            let mut header = pkt.header;
+            header.payload_type = red.primary.payload_type;
+            self.pending.push_back((
+                rtp::packet::Packet {
+                    header,
+                    payload: red.primary.payload,
+                },
+                attr,
+            ));
+        }
+    }
+}
+
+pub(super) struct DecoderStream {
+    payload_type: u8,
+    internal: Mutex<DecoderStreamInternal>,
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+}
+
+impl DecoderStream {
+    pub(super) fn new(payload_type: u8, reader: Arc<dyn RTPReader + Send + Sync>) -> Self {
+        DecoderStream {
+            payload_type,
+            internal: Mutex::new(DecoderStreamInternal {
+                highest_sequence_number: None,
+                received: VecDeque::new(),
+                pending: VecDeque::new(),
+                last_packet: None,
+                frame_duration: None,
+            }),
+            parent_rtp_reader: reader,
+        }
+    }
+
+    fn pop_pending(&self) -> Option<(rtp::packet::Packet, Attributes)> {
+        let mut internal = self.internal.lock().unwrap();
+        internal.pending.pop_front()
+    }
+}
+
+/// RTPReader is used by Interceptor.bind_remote_stream.
+#[async_trait]
+impl RTPReader for DecoderStream {
+    /// read a rtp packet of the primary encoding
+    async fn read(&self, buf: &mut [u8], a: &Attributes) -> Result<(usize, Attributes)> {
+        loop {
+            if let Some((pkt, attr)) = self.pop_pending() {
+                if buf.len() < pkt.marshal_size() {
+                    return Err(Error::ErrShortBuffer);
+                }
+                return Ok((pkt.marshal_to(buf)?, attr));
+            }
+
+            let (n, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+            let mut b = &buf[..n];
+            let pkt = rtp::packet::Packet::unmarshal(&mut b)?;
+            let mut internal = self.internal.lock().unwrap();
+            internal.add(self.payload_type, pkt, attr);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::test::timeout_or_fail;
+
+use bytes::Bytes;
+use rtp::codecs::red::{RedBlock, RedPacket};
+use std::time::Duration;
+use tokio::time::error::Elapsed;
+
+fn red_packet(seq_num: u16, distance: u16) -> Result<rtp::packet::Packet> {
+    let mut redundant = vec![];
+    for d in (1..=distance).rev() {
+        if d <= seq_num {
+            redundant.push(RedBlock {
+                payload_type: 111,
+                timestamp_offset: 960 * d as u32,
+                payload: Bytes::from(vec![(seq_num - d) as u8; 10]),
+            });
+        }
+    }
+    let red = RedPacket {
+        redundant,
+        primary: RedBlock {
+            payload_type: 111,
+            timestamp_offset: 0,
+            payload: Bytes::from(vec![seq_num as u8; 10]),
+        },
+    };
+
+    Ok(rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 1,
+            payload_type: 63,
+            sequence_number: seq_num,
+            timestamp: 960 * seq_num as u32,
+            ..Default::default()
+        },
+        payload: red.marshal()?,
+    })
+}
+
+#[tokio::test]
+async fn test_decoder_interceptor() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Decoder::builder().build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type_redundancy: 63,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // packets 2 and 3 are lost, 5 arrives twice
+    let mut expected = vec![];
+    for seq_num in [0u16, 1, 4, 5, 5, 6] {
+        stream.receive_rtp(red_packet(seq_num, 2)?).await;
+        if expected.contains(&seq_num) {
+            continue;
+        }
+        if seq_num == 4 {
+            expected.extend([2, 3]);
+        }
+        expected.push(seq_num);
+    }
+
+    for seq_num in expected {
+        let p = timeout_or_fail(Duration::from_millis(10), stream.read_rtp())
+            .await
+            .expect("A read packet")
+            .expect("Not an error");
+        assert_eq!(p.header.sequence_number, seq_num);
+        assert_eq!(p.header.payload_type, 111);
+        assert_eq!(p.header.timestamp, 960 * seq_num as u32);
+        assert_eq!(p.payload, Bytes::from(vec![seq_num as u8; 10]));
+    }
+
+    let result: std::result::Result<Option<Result<rtp::packet::Packet>>, Elapsed> =
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp()).await;
+    assert!(result.is_err(), "no duplicates expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+fn red_packet_with_offsets(seq_num: u16, offsets: &[u32]) -> Result<rtp::packet::Packet> {
+    let timestamp = 960 * seq_num as u32;
+    let red = RedPacket {
+        redundant: offsets
+            .iter()
+            .map(|offset| RedBlock {
+                payload_type: 111,
+                timestamp_offset: *offset,
+                payload: Bytes::from(vec![0xAA; 10]),
+            })
+            .collect(),
+        primary: RedBlock {
+            payload_type: 111,
+            timestamp_offset: 0,
+            payload: Bytes::from(vec![seq_num as u8; 10]),
+        },
+    };
+
+    Ok(rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 1,
+            payload_type: 63,
+            sequence_number: seq_num,
+            timestamp,
+            ..Default::default()
+        },
+        payload: red.marshal()?,
+    })
+}
+
+#[tokio::test]
+async fn test_decoder_interceptor_block_position() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Decoder::builder().build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type_redundancy: 63,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // The redundancy of packet 5 is the frame two packets back, packet 3, and
+    // a block which isn't a whole number of frames back, which is dropped
+    for (seq_num, offsets) in [
+        (0u16, vec![]),
+        (1, vec![]),
+        (2, vec![]),
+        (5, vec![1920, 500]),
+    ] {
+        stream
+            .receive_rtp(red_packet_with_offsets(seq_num, &offsets)?)
+            .await;
+    }
+
+    for (seq_num, timestamp) in [(0u16, 0u32), (1, 960), (2, 1920), (3, 2880), (5, 4800)] {
+        let p = timeout_or_fail(Duration::from_millis(10), stream.read_rtp())
+            .await
+            .expect("A read packet")
+            .expect("Not an error");
+        assert_eq!(p.header.sequence_number, seq_num);
+        assert_eq!(p.header.timestamp, timestamp);
+    }
+
+    let result: std::result::Result<Option<Result<rtp::packet::Packet>>, Elapsed> =
+        tokio::time::timeout(Duration::from_millis(10), stream.read_rtp()).await;
+    assert!(result.is_err(), "no more packets expected");
+
+    stream.close().await?;
+
+    Ok(())
+}
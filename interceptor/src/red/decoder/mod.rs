@@ -0,0 +1,90 @@
+mod decoder_stream;
+#[cfg(test)]
+mod decoder_test;
+
+use crate::error::Result;
+use crate::red::stream_support_red;
+use crate::stream_info::StreamInfo;
+use crate::{Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
+use decoder_stream::DecoderStream;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// DecoderBuilder can be used to configure the RED Decoder Interceptor
+#[derive(Default)]
+pub struct DecoderBuilder {}
+
+impl InterceptorBuilder for DecoderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(Decoder {}))
+    }
+}
+
+/// Decoder unwraps incoming RFC 2198 RED payloads. Frames lost on the way are
+/// recovered from the redundancy of later packets and duplicates are dropped,
+/// so readers only see packets of the primary encoding.
+pub struct Decoder {}
+
+impl Decoder {
+    /// builder returns a new DecoderBuilder.
+    pub fn builder() -> DecoderBuilder {
+        DecoderBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for Decoder {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        if !stream_support_red(info) {
+            return reader;
+        }
+
+        Arc::new(DecoderStream::new(info.payload_type_redundancy, reader))
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::Result;
+use crate::{Attributes, RTPWriter};
+
+use async_trait::async_trait;
+use rtp::codecs::red::{RedBlock, RedPacket, RED_MAX_BLOCK_LENGTH, RED_MAX_TIMESTAMP_OFFSET};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+struct Frame {
+    payload_type: u8,
+    timestamp: u32,
+    payload: bytes::Bytes,
+}
+
+pub(super) struct EncoderStream {
+    payload_type: u8,
+    distance: usize,
+    max_payload_size: usize,
+    // the previous frames, oldest first
+    frames: Mutex<VecDeque<Frame>>,
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+impl EncoderStream {
+    pub(super) fn new(
+        payload_type: u8,
+        distance: usize,
+        max_payload_size: usize,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        EncoderStream {
+            payload_type,
+            distance,
+            max_payload_size,
+            frames: Mutex::new(VecDeque::with_capacity(distance)),
+            next_rtp_writer: writer,
+        }
+    }
+
+    /// redundant_blocks picks the previous frames that fit into the packet, newer frames are preferred
+    fn redundant_blocks(
+        &self,
+        frames: &VecDeque<Frame>,
+        pkt: &rtp::packet::Packet,
+    ) -> Vec<RedBlock> {
+        let mut size = pkt.payload.len() + 1;
+        let mut blocks = vec![];
+        for frame in frames.iter().rev() {
+            let timestamp_offset = pkt.header.timestamp.wrapping_sub(frame.timestamp);
+            if timestamp_offset > RED_MAX_TIMESTAMP_OFFSET
+                || frame.payload.len() > RED_MAX_BLOCK_LENGTH
+            {
+                break;
+            }
+            size += frame.payload.len() + 4;
+            if size > self.max_payload_size {
+                break;
+            }
+            blocks.push(RedBlock {
+                payload_type: frame.payload_type,
+                timestamp_offset,
+                payload: frame.payload.clone(),
+            });
+        }
+        blocks.reverse();
+        blocks
+    }
+}
+
+/// RTPWriter is used by Interceptor.bind_local_stream.
+#[async_trait]
+impl RTPWriter for EncoderStream {
+    /// write a rtp packet, wrapped into a RED payload together with the previous frames
+    async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        if pkt.header.payload_type == self.payload_type {
+            return self.next_rtp_writer.write(pkt, a).await;
+        }
+
+        let red = {
+            let mut frames = self.frames.lock().await;
+            let red = RedPacket {
+                redundant: self.redundant_blocks(&frames, pkt),
+                primary: RedBlock {
+                    payload_type: pkt.header.payload_type,
+                    timestamp_offset: 0,
+                    payload: pkt.payload.clone(),
+                },
+            };
+
+            if self.distance > 0 {
+                if frames.len() >= self.distance {
+                    frames.pop_front();
+                }
+                frames.push_back(Frame {
+                    payload_type: pkt.header.payload_type,
+                    timestamp: pkt.header.timestamp,
+                    payload: pkt.payload.clone(),
+                });
+            }
+
+            red
+        };
+
+        let mut header = pkt.header.clone();
+        header.payload_type = self.payload_type;
+        let red_pkt = rtp::packet::Packet {
+            header,
+            payload: red.marshal()?,
+        };
+
+        self.next_rtp_writer.write(&red_pkt, a).await
+    }
+}
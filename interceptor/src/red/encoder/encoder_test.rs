@@ -0,0 +1,73 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::test::timeout_or_fail;
+
+use bytes::Bytes;
+use rtp::codecs::red::RedPacket;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_encoder_interceptor() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Encoder::builder()
+        .with_distance(2)
+        .with_max_payload_size(100)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 111,
+            payload_type_redundancy: 63,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    let payload_sizes = [10usize, 20, 30, 70, 10];
+    for (i, size) in payload_sizes.iter().enumerate() {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc: 1,
+                    payload_type: 111,
+                    sequence_number: i as u16,
+                    timestamp: 960 * i as u32,
+                    ..Default::default()
+                },
+                payload: vec![i as u8; *size].into(),
+            })
+            .await?;
+
+        let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!(p.header.payload_type, 63);
+        assert_eq!(p.header.sequence_number, i as u16);
+
+        let red = RedPacket::unmarshal(&p.payload)?;
+        assert_eq!(red.primary.payload_type, 111);
+        assert_eq!(red.primary.payload, Bytes::from(vec![i as u8; *size]));
+
+        let redundant: Vec<(u8, u32, usize)> = red
+            .redundant
+            .iter()
+            .map(|b| (b.payload[0], b.timestamp_offset, b.payload.len()))
+            .collect();
+        let expected = match i {
+            0 => vec![],
+            1 => vec![(0, 960, 10)],
+            2 => vec![(0, 1920, 10), (1, 960, 20)],
+            // the frames before the 70 byte one don't fit next to it
+            3 => vec![],
+            // the older frame doesn't fit next to the 70 byte one
+            4 => vec![(3, 960, 70)],
+            _ => unreachable!(),
+        };
+        assert_eq!(redundant, expected, "packet {}", i);
+    }
+
+    stream.close().await?;
+
+    Ok(())
+}
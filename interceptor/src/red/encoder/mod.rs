@@ -0,0 +1,121 @@
+mod encoder_stream;
+#[cfg(test)]
+mod encoder_test;
+
+use crate::error::Result;
+use crate::red::stream_support_red;
+use crate::stream_info::StreamInfo;
+use crate::{Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
+use encoder_stream::EncoderStream;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+const DEFAULT_DISTANCE: usize = 1;
+const DEFAULT_MAX_PAYLOAD_SIZE: usize = 1000;
+
+/// EncoderBuilder can be used to configure the RED Encoder Interceptor
+#[derive(Default)]
+pub struct EncoderBuilder {
+    distance: Option<usize>,
+    max_payload_size: Option<usize>,
+}
+
+impl EncoderBuilder {
+    /// with_distance sets how many previous frames are sent along with every frame.
+    pub fn with_distance(mut self, distance: usize) -> EncoderBuilder {
+        self.distance = Some(distance);
+        self
+    }
+
+    /// with_max_payload_size sets the size RED payloads may grow to, older frames
+    /// which don't fit are left out.
+    pub fn with_max_payload_size(mut self, max_payload_size: usize) -> EncoderBuilder {
+        self.max_payload_size = Some(max_payload_size);
+        self
+    }
+}
+
+impl InterceptorBuilder for EncoderBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(Encoder {
+            distance: self.distance.unwrap_or(DEFAULT_DISTANCE),
+            max_payload_size: self.max_payload_size.unwrap_or(DEFAULT_MAX_PAYLOAD_SIZE),
+        }))
+    }
+}
+
+/// Encoder wraps outgoing audio frames into RFC 2198 RED payloads which
+/// carry the previous frames as redundancy, for streams that negotiated audio/red.
+pub struct Encoder {
+    distance: usize,
+    max_payload_size: usize,
+}
+
+impl Encoder {
+    /// builder returns a new EncoderBuilder.
+    pub fn builder() -> EncoderBuilder {
+        EncoderBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for Encoder {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        if !stream_support_red(info) {
+            return writer;
+        }
+
+        Arc::new(EncoderStream::new(
+            info.payload_type_redundancy,
+            self.distance,
+            self.max_payload_size,
+            writer,
+        ))
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
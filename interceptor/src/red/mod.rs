@@ -0,0 +1,8 @@
+use crate::stream_info::StreamInfo;
+
+pub mod decoder;
+pub mod encoder;
+
+fn stream_support_red(info: &StreamInfo) -> bool {
+    info.payload_type_redundancy != 0
+}
@@ -20,6 +20,11 @@ pub struct StreamInfo {
     pub channels: u16,
     pub sdp_fmtp_line: String,
     pub rtcp_feedback: Vec<RTCPFeedback>,
+    pub ssrc_forward_error_correction: u32,
+    pub payload_type_forward_error_correction: u8,
+    pub payload_type_redundancy: u8,
+    pub ssrc_retransmission: u32,
+    pub payload_type_retransmission: u8,
 }
 
 /// RTCPFeedback signals the connection to use additional RTCP packet types.
@@ -0,0 +1,198 @@
+use super::*;
+
+const CLOCK_RATE: u32 = 90000;
+// 30 frames per second
+const FRAME_DURATION: Duration = Duration::from_nanos(33_333_333);
+
+fn new_packet(sequence_number: u16, timestamp: u32) -> Packet {
+    Packet {
+        header: rtp::header::Header {
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn pop_all(jb: &mut JitterBuffer, now: Instant) -> Vec<Frame> {
+    let mut frames = vec![];
+    while let Some(frame) = jb.pop(now) {
+        frames.push(frame);
+    }
+    frames
+}
+
+#[test]
+fn test_jitter_buffer_holds_packets() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(CLOCK_RATE).with_min_delay(Duration::from_millis(50));
+
+    assert!(jb.pop(start).is_none(), "empty buffer must not emit frames");
+    assert_eq!(jb.next_playout_time(), None);
+
+    jb.push(new_packet(10, 3000), start);
+    jb.push(new_packet(11, 3000), start);
+    assert_eq!(jb.len(), 2);
+    assert_eq!(
+        jb.next_playout_time(),
+        Some(start + Duration::from_millis(50))
+    );
+
+    assert!(
+        jb.pop(start).is_none(),
+        "frame must be held for the target delay"
+    );
+    assert!(jb.pop(start + Duration::from_millis(49)).is_none());
+
+    let frame = jb
+        .pop(start + Duration::from_millis(50))
+        .expect("frame should be due");
+    assert_eq!(frame.packet_timestamp, 3000);
+    assert_eq!(frame.packets.len(), 2);
+    assert_eq!(frame.lost_packets, 0);
+    assert!(jb.is_empty());
+}
+
+#[test]
+fn test_jitter_buffer_reorders() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(CLOCK_RATE);
+
+    // three frames of two packets each, arriving out of order
+    let arrivals = [(1, 0), (0, 0), (3, 3000), (2, 3000), (5, 6000), (4, 6000)];
+    for (seq, ts) in arrivals {
+        jb.push(new_packet(seq, ts), start);
+    }
+
+    let frames = pop_all(&mut jb, start + Duration::from_secs(1));
+    assert_eq!(frames.len(), 3);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.packet_timestamp, i as u32 * 3000);
+        let seqs: Vec<u16> = frame
+            .packets
+            .iter()
+            .map(|p| p.header.sequence_number)
+            .collect();
+        assert_eq!(seqs, vec![i as u16 * 2, i as u16 * 2 + 1]);
+    }
+    assert_eq!(jb.stats().frames_emitted, 3);
+}
+
+#[test]
+fn test_jitter_buffer_loss_and_late_packets() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(CLOCK_RATE);
+
+    // sequence numbers wrap around, 65535 and 1 are lost
+    jb.push(new_packet(65533, 0), start);
+    jb.push(new_packet(65534, 3000), start);
+    jb.push(new_packet(0, 3000), start);
+    jb.push(new_packet(2, 6000), start);
+
+    let frames = pop_all(&mut jb, start + Duration::from_secs(1));
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].lost_packets, 0);
+    assert_eq!(
+        frames[1].lost_packets, 1,
+        "65535 is missing within the frame"
+    );
+    assert_eq!(frames[2].lost_packets, 1, "1 is missing before the frame");
+
+    let stats = jb.stats();
+    assert_eq!(stats.packets_lost, 2);
+
+    // the lost packet shows up after its frame was played out, it is late
+    // instead of lost then
+    jb.push(new_packet(1, 6000), start + Duration::from_secs(1));
+    jb.push(new_packet(1, 6000), start + Duration::from_secs(1));
+    jb.push(new_packet(65534, 3000), start + Duration::from_secs(1));
+    jb.push(new_packet(3, 9000), start + Duration::from_secs(1));
+    jb.push(new_packet(3, 9000), start + Duration::from_secs(1));
+
+    let stats = jb.stats();
+    assert_eq!(stats.packets_received, 9);
+    assert_eq!(stats.packets_lost, 1);
+    assert_eq!(stats.packets_late, 1);
+    assert_eq!(stats.packets_duplicate, 3);
+    assert_eq!(jb.len(), 1);
+}
+
+#[test]
+fn test_jitter_buffer_adapts_target_delay() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(CLOCK_RATE)
+        .with_min_delay(Duration::from_millis(10))
+        .with_max_delay(Duration::from_millis(200));
+
+    // packets arrive exactly on time
+    for i in 0..50u32 {
+        jb.push(new_packet(i as u16, i * 3000), start + FRAME_DURATION * i);
+    }
+    assert!(jb.stats().jitter < Duration::from_millis(1));
+    assert_eq!(jb.target_delay(), Duration::from_millis(10));
+    pop_all(
+        &mut jb,
+        start + FRAME_DURATION * 50 + Duration::from_secs(1),
+    );
+
+    // every other packet is delayed by 20ms
+    for i in 50..150u32 {
+        let delay = if i % 2 == 0 {
+            Duration::from_millis(20)
+        } else {
+            Duration::from_millis(0)
+        };
+        jb.push(
+            new_packet(i as u16, i * 3000),
+            start + FRAME_DURATION * i + delay,
+        );
+    }
+    let stats = jb.stats();
+    assert!(
+        stats.jitter > Duration::from_millis(15),
+        "jitter should follow the delay variation, got {:?}",
+        stats.jitter
+    );
+    assert!(stats.target_delay > Duration::from_millis(60));
+    assert!(stats.target_delay <= Duration::from_millis(200));
+
+    // frames are still emitted in order once their playout time is due
+    let frames = pop_all(
+        &mut jb,
+        start + FRAME_DURATION * 150 + Duration::from_secs(1),
+    );
+    assert_eq!(frames.len(), 100);
+    assert!(frames
+        .windows(2)
+        .all(|w| w[0].packet_timestamp < w[1].packet_timestamp));
+}
+
+#[test]
+fn test_jitter_buffer_delay_bounds() {
+    let start = Instant::now();
+
+    // a minimum above the default maximum raises the maximum
+    let mut jb = JitterBuffer::new(CLOCK_RATE).with_min_delay(Duration::from_secs(1));
+    for i in 0..10u32 {
+        let delay = Duration::from_millis(if i % 2 == 0 { 30 } else { 0 });
+        jb.push(
+            new_packet(i as u16, i * 3000),
+            start + FRAME_DURATION * i + delay,
+        );
+    }
+    assert_eq!(jb.target_delay(), Duration::from_secs(1));
+
+    // a maximum below the minimum lowers the minimum
+    let mut jb = JitterBuffer::new(CLOCK_RATE)
+        .with_min_delay(Duration::from_millis(100))
+        .with_max_delay(Duration::from_millis(50));
+    for i in 0..10u32 {
+        let delay = Duration::from_millis(if i % 2 == 0 { 30 } else { 0 });
+        jb.push(
+            new_packet(i as u16, i * 3000),
+            start + FRAME_DURATION * i + delay,
+        );
+    }
+    assert!(jb.target_delay() <= Duration::from_millis(50));
+}
@@ -0,0 +1,312 @@
+#[cfg(test)]
+mod jitter_buffer_test;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use rtp::packet::Packet;
+
+/// The target delay is this many times the estimated interarrival jitter.
+const JITTER_MULTIPLIER: f64 = 4.0;
+/// How fast the target delay shrinks again once the jitter went down,
+/// the target delay grows immediately.
+const TARGET_DELAY_DECAY: f64 = 0.99;
+
+/// How many sequence numbers before the next one to play out are remembered
+/// as lost, so a late arrival is moved from the lost to the late packets.
+const LOST_HISTORY: i64 = 1024;
+
+const DEFAULT_MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_millis(500);
+
+/// A Frame holds the packets sharing one RTP timestamp, in sequence number order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The RTP timestamp of all packets of this frame.
+    pub packet_timestamp: u32,
+
+    /// The packets of this frame, in sequence number order.
+    pub packets: Vec<Packet>,
+
+    /// The number of packets that were lost since the previous frame or within this frame.
+    pub lost_packets: u64,
+}
+
+/// Statistics collected by a [`JitterBuffer`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JitterBufferStats {
+    /// The number of packets pushed into the buffer.
+    pub packets_received: u64,
+
+    /// The number of packets which never arrived.
+    pub packets_lost: u64,
+
+    /// The number of packets dropped because they arrived after their frame was
+    /// played out. They are no longer counted as lost.
+    pub packets_late: u64,
+
+    /// The number of packets dropped because they were already buffered or played out.
+    pub packets_duplicate: u64,
+
+    /// The number of frames emitted.
+    pub frames_emitted: u64,
+
+    /// The interarrival jitter as defined in RFC 3550 section 6.4.1.
+    pub jitter: Duration,
+
+    /// The delay packets are currently held for.
+    pub target_delay: Duration,
+}
+
+struct BufferedPacket {
+    packet: Packet,
+    timestamp: i64,
+}
+
+/// JitterBuffer reorders RTP packets and holds them for an adaptive delay
+/// derived from the interarrival jitter, so that frames can be played out
+/// smoothly. Packets read from a `TrackRemote` are pushed together with
+/// their arrival time and frames are popped once their playout time is due.
+///
+/// The buffer is standalone and opt-in: `TrackRemote` doesn't use it, a
+/// receiver which wants smooth playout feeds it the packets it reads.
+pub struct JitterBuffer {
+    clock_rate: u32,
+    min_delay: Duration,
+    max_delay: Duration,
+
+    packets: BTreeMap<i64, BufferedPacket>,
+
+    start: Option<Instant>,
+    highest_sequence_number: Option<i64>,
+    last_timestamp: Option<(u32, i64)>,
+    // next sequence number to be played out
+    next_sequence_number: Option<i64>,
+    // recent sequence numbers counted as lost
+    lost_sequence_numbers: BTreeSet<i64>,
+
+    // interarrival jitter and transit times in RTP timestamp units
+    jitter: f64,
+    last_transit: Option<f64>,
+    min_transit: Option<f64>,
+    target_delay: Duration,
+
+    stats: JitterBufferStats,
+}
+
+impl JitterBuffer {
+    /// Constructs a new JitterBuffer for a stream using the given clock rate.
+    pub fn new(clock_rate: u32) -> Self {
+        Self {
+            clock_rate,
+            min_delay: DEFAULT_MIN_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            packets: BTreeMap::new(),
+            start: None,
+            highest_sequence_number: None,
+            last_timestamp: None,
+            next_sequence_number: None,
+            lost_sequence_numbers: BTreeSet::new(),
+            jitter: 0.0,
+            last_transit: None,
+            min_transit: None,
+            target_delay: DEFAULT_MIN_DELAY,
+            stats: JitterBufferStats::default(),
+        }
+    }
+
+    /// Sets the minimum delay packets are held for.
+    ///
+    /// The maximum delay is raised to `min_delay` if it is lower.
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = std::cmp::max(self.max_delay, min_delay);
+        self.target_delay = min_delay;
+        self
+    }
+
+    /// Sets the maximum delay packets are held for, regardless of the jitter.
+    ///
+    /// The minimum delay is lowered to `max_delay` if it is higher.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self.min_delay = std::cmp::min(self.min_delay, max_delay);
+        self.target_delay = std::cmp::min(self.target_delay, max_delay);
+        self
+    }
+
+    /// Adds a packet which arrived at `arrival` to the buffer.
+    ///
+    /// Packets arriving after their frame was played out and duplicates are dropped.
+    pub fn push(&mut self, packet: Packet, arrival: Instant) {
+        self.stats.packets_received += 1;
+
+        let sequence_number = self.unwrap_sequence_number(packet.header.sequence_number);
+        if let Some(next) = self.next_sequence_number {
+            if sequence_number < next {
+                if self.lost_sequence_numbers.remove(&sequence_number) {
+                    self.stats.packets_lost -= 1;
+                    self.stats.packets_late += 1;
+                } else if sequence_number < next - LOST_HISTORY {
+                    self.stats.packets_late += 1;
+                } else {
+                    // it was played out already
+                    self.stats.packets_duplicate += 1;
+                }
+                return;
+            }
+        }
+        if self.packets.contains_key(&sequence_number) {
+            self.stats.packets_duplicate += 1;
+            return;
+        }
+
+        let timestamp = self.unwrap_timestamp(packet.header.timestamp);
+        self.update_jitter(timestamp, arrival);
+
+        self.packets
+            .insert(sequence_number, BufferedPacket { packet, timestamp });
+    }
+
+    /// Returns the next frame if its playout time is due at `now`.
+    pub fn pop(&mut self, now: Instant) -> Option<Frame> {
+        let playout_time = self.next_playout_time()?;
+        if playout_time > now {
+            return None;
+        }
+
+        let (&first, head) = self.packets.iter().next()?;
+        let timestamp = head.timestamp;
+
+        let mut lost_packets = 0;
+        if let Some(next) = self.next_sequence_number {
+            lost_packets += (first - next) as u64;
+            self.mark_lost(next, first);
+        }
+        let mut expected = first;
+        let mut packets = vec![];
+        while let Some((&sequence_number, p)) = self.packets.iter().next() {
+            if p.timestamp != timestamp {
+                break;
+            }
+            lost_packets += (sequence_number - expected) as u64;
+            self.mark_lost(expected, sequence_number);
+            expected = sequence_number + 1;
+            if let Some(p) = self.packets.remove(&sequence_number) {
+                packets.push(p.packet);
+            }
+        }
+        self.next_sequence_number = Some(expected);
+        self.lost_sequence_numbers = self
+            .lost_sequence_numbers
+            .split_off(&(expected - LOST_HISTORY));
+
+        self.stats.packets_lost += lost_packets;
+        self.stats.frames_emitted += 1;
+
+        Some(Frame {
+            packet_timestamp: packets[0].header.timestamp,
+            packets,
+            lost_packets,
+        })
+    }
+
+    /// Returns the time at which the next frame is due, if any packet is buffered.
+    pub fn next_playout_time(&self) -> Option<Instant> {
+        let start = self.start?;
+        let min_transit = self.min_transit?;
+        let head = self.packets.values().next()?;
+
+        let offset = (head.timestamp as f64 + min_transit) / self.clock_rate as f64;
+        let playout_time = if offset >= 0.0 {
+            start + Duration::from_secs_f64(offset)
+        } else {
+            start
+                .checked_sub(Duration::from_secs_f64(-offset))
+                .unwrap_or(start)
+        };
+
+        Some(playout_time + self.target_delay)
+    }
+
+    /// Returns the current delay packets are held for.
+    pub fn target_delay(&self) -> Duration {
+        self.target_delay
+    }
+
+    /// Returns the number of buffered packets.
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Returns true if no packets are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Returns the statistics collected so far.
+    pub fn stats(&self) -> JitterBufferStats {
+        JitterBufferStats {
+            jitter: Duration::from_secs_f64(self.jitter / self.clock_rate as f64),
+            target_delay: self.target_delay,
+            ..self.stats
+        }
+    }
+
+    /// mark_lost remembers the sequence numbers from `start` up to `end` as lost
+    fn mark_lost(&mut self, start: i64, end: i64) {
+        for sequence_number in std::cmp::max(start, end - LOST_HISTORY)..end {
+            self.lost_sequence_numbers.insert(sequence_number);
+        }
+    }
+
+    fn unwrap_sequence_number(&mut self, sequence_number: u16) -> i64 {
+        let unwrapped = match self.highest_sequence_number {
+            Some(highest) => highest + sequence_number.wrapping_sub(highest as u16) as i16 as i64,
+            None => sequence_number as i64,
+        };
+        match self.highest_sequence_number {
+            Some(highest) if highest >= unwrapped => {}
+            _ => self.highest_sequence_number = Some(unwrapped),
+        }
+        unwrapped
+    }
+
+    fn unwrap_timestamp(&mut self, timestamp: u32) -> i64 {
+        let unwrapped = match self.last_timestamp {
+            Some((last, last_unwrapped)) => {
+                last_unwrapped + timestamp.wrapping_sub(last) as i32 as i64
+            }
+            None => timestamp as i64,
+        };
+        self.last_timestamp = Some((timestamp, unwrapped));
+        unwrapped
+    }
+
+    fn update_jitter(&mut self, timestamp: i64, arrival: Instant) {
+        let start = *self.start.get_or_insert(arrival);
+        let arrival = match arrival.checked_duration_since(start) {
+            Some(d) => d.as_secs_f64() * self.clock_rate as f64,
+            None => 0.0,
+        };
+
+        // RFC 3550 section 6.4.1
+        let transit = arrival - timestamp as f64;
+        if let Some(last_transit) = self.last_transit {
+            let d = (transit - last_transit).abs();
+            self.jitter += (d - self.jitter) / 16.0;
+        }
+        self.last_transit = Some(transit);
+
+        // the packet with the shortest transit time defines when playout starts
+        match self.min_transit {
+            Some(min_transit) if min_transit <= transit => {}
+            _ => self.min_transit = Some(transit),
+        }
+
+        let jitter = Duration::from_secs_f64(self.jitter / self.clock_rate as f64);
+        let desired = jitter.mul_f64(JITTER_MULTIPLIER);
+        let decayed = self.target_delay.mul_f64(TARGET_DELAY_DECAY);
+        self.target_delay = std::cmp::max(desired, decayed).clamp(self.min_delay, self.max_delay);
+    }
+}
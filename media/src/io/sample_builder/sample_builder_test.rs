@@ -1496,3 +1496,38 @@ fn test_sample_builder_data() {
     // only the last packet should be dropped
     assert_eq!(j, 0x1FFFF);
 }
+
+#[test]
+fn test_sample_builder_av1() {
+    let mut s = SampleBuilder::new(10, rtp::codecs::av1::Av1Packet::default(), 90000);
+    let payloads = [
+        // Temporal unit 1: a single OBU fragmented across two packets
+        (0, 1000, false, bytes!(0x50, 0x30, 0xAA)),
+        (1, 1000, true, bytes!(0x90, 0xBB)),
+        // Temporal unit 2: two aggregated OBUs
+        (2, 4000, true, bytes!(0x20, 0x01, 0x08, 0x30, 0xCC)),
+        (3, 7000, true, bytes!(0x10, 0x30, 0xDD)),
+    ];
+    for (sequence_number, timestamp, marker, payload) in payloads {
+        s.push(Packet {
+            header: Header {
+                sequence_number,
+                timestamp,
+                marker,
+                ..Default::default()
+            },
+            payload,
+        });
+    }
+
+    let sample = s.pop().expect("first temporal unit");
+    assert_eq!(sample.data, bytes!(0x32, 0x02, 0xAA, 0xBB));
+    assert_eq!(sample.packet_timestamp, 1000);
+    assert_eq!(sample.duration, Duration::from_secs_f64(3000.0 / 90000.0));
+
+    let sample = s.pop().expect("second temporal unit");
+    assert_eq!(sample.data, bytes!(0x0A, 0x00, 0x32, 0x01, 0xCC));
+    assert_eq!(sample.packet_timestamp, 4000);
+
+    assert!(s.pop().is_none());
+}
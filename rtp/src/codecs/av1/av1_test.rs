@@ -0,0 +1,246 @@
+use super::*;
+
+#[test]
+fn test_av1_payload() -> Result<()> {
+    let mut pck = Av1Payloader::default();
+
+    // Positive MTU, empty payload
+    let empty = Bytes::from_static(&[]);
+    let result = pck.payload(100, &empty)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    // 0 MTU, small payload
+    let small_payload = Bytes::from_static(&[0x32, 0x03, 0xAA, 0xBB, 0xCC]);
+    let result = pck.payload(0, &small_payload)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    // MTU too small to carry any OBU data
+    let result = pck.payload(2, &small_payload)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    // Temporal delimiter is dropped and obu_size is removed
+    let temporal_unit = Bytes::from_static(&[0x12, 0x00, 0x32, 0x03, 0xAA, 0xBB, 0xCC]);
+    let result = pck.payload(100, &temporal_unit)?;
+    assert_eq!(
+        result,
+        vec![Bytes::from_static(&[0x10, 0x30, 0xAA, 0xBB, 0xCC])],
+        "Single OBU packetization failed"
+    );
+
+    // Sequence header sets N and is aggregated with the frame
+    let temporal_unit = Bytes::from_static(&[
+        0x12, 0x00, 0x0A, 0x02, 0x01, 0x02, 0x32, 0x03, 0xAA, 0xBB, 0xCC,
+    ]);
+    let result = pck.payload(100, &temporal_unit)?;
+    assert_eq!(
+        result,
+        vec![Bytes::from_static(&[
+            0x28, 0x03, 0x08, 0x01, 0x02, 0x30, 0xAA, 0xBB, 0xCC
+        ])],
+        "OBU aggregation failed"
+    );
+
+    // More than three OBUs are all preceded by a length field
+    let temporal_unit = Bytes::from_static(&[
+        0x32, 0x01, 0x01, 0x32, 0x01, 0x02, 0x32, 0x01, 0x03, 0x32, 0x01, 0x04,
+    ]);
+    let result = pck.payload(100, &temporal_unit)?;
+    assert_eq!(
+        result,
+        vec![Bytes::from_static(&[
+            0x00, 0x02, 0x30, 0x01, 0x02, 0x30, 0x02, 0x02, 0x30, 0x03, 0x02, 0x30, 0x04
+        ])],
+        "OBU aggregation with W=0 failed"
+    );
+
+    // Large OBU fragmented across multiple RTP Packets
+    let temporal_unit = Bytes::from_static(&[0x30, 0xAA, 0xBB, 0xCC, 0xDD]);
+    let result = pck.payload(4, &temporal_unit)?;
+    assert_eq!(
+        result,
+        vec![
+            Bytes::from_static(&[0x50, 0x30, 0xAA]),
+            Bytes::from_static(&[0xD0, 0xBB, 0xCC]),
+            Bytes::from_static(&[0x90, 0xDD]),
+        ],
+        "OBU fragmentation failed"
+    );
+
+    // Every packet respects the MTU
+    let large_obu: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let mut temporal_unit = vec![0x0A, 0x02, 0x01, 0x02, 0x32, 0xE8, 0x07];
+    temporal_unit.extend(large_obu);
+    let result = pck.payload(100, &Bytes::from(temporal_unit))?;
+    assert_eq!(result.len(), 11, "Unexpected number of packets");
+    for p in &result {
+        assert!(p.len() <= 100, "Packet exceeds the MTU");
+    }
+
+    // Malformed obu_size
+    let corrupted = Bytes::from_static(&[0x32, 0x05, 0xAA]);
+    let result = pck.payload(100, &corrupted);
+    assert_eq!(result, Err(Error::ErrAv1CorruptedPacket));
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_depacketize() -> Result<()> {
+    let mut pck = Av1Packet::default();
+
+    // Empty packet
+    let empty_bytes = Bytes::from_static(&[]);
+    let result = pck.depacketize(&empty_bytes);
+    assert!(result.is_err(), "Result should be err in case of error");
+
+    // Aggregation header only
+    let small_bytes = Bytes::from_static(&[0x10]);
+    let result = pck.depacketize(&small_bytes);
+    assert!(result.is_err(), "Result should be err in case of error");
+
+    // Single OBU without length field
+    let raw_bytes = Bytes::from_static(&[0x10, 0x30, 0xAA, 0xBB, 0xCC]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x32, 0x03, 0xAA, 0xBB, 0xCC]),
+        "Single OBU depacketization failed"
+    );
+    assert!(!pck.z, "Z must be false");
+    assert!(!pck.y, "Y must be false");
+    assert_eq!(pck.w, 1, "W must be 1");
+    assert!(!pck.n, "N must be false");
+
+    // Aggregated OBUs starting a new coded video sequence
+    let raw_bytes = Bytes::from_static(&[0x28, 0x03, 0x08, 0x01, 0x02, 0x30, 0xAA, 0xBB, 0xCC]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x0A, 0x02, 0x01, 0x02, 0x32, 0x03, 0xAA, 0xBB, 0xCC]),
+        "Aggregated OBU depacketization failed"
+    );
+    assert_eq!(pck.w, 2, "W must be 2");
+    assert!(pck.n, "N must be true");
+    assert_eq!(pck.obu_elements.len(), 2, "Two OBU elements expected");
+
+    // W=0, every element has a length field
+    let raw_bytes = Bytes::from_static(&[0x00, 0x02, 0x30, 0x01, 0x02, 0x30, 0x02]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x32, 0x01, 0x01, 0x32, 0x01, 0x02]),
+        "W=0 depacketization failed"
+    );
+
+    // Length field larger than the packet
+    let raw_bytes = Bytes::from_static(&[0x00, 0x05, 0x30, 0x01]);
+    let result = pck.depacketize(&raw_bytes);
+    assert_eq!(result, Err(Error::ErrAv1CorruptedPacket));
+
+    // Fewer elements than announced by W
+    let raw_bytes = Bytes::from_static(&[0x30, 0x02, 0x30, 0x01]);
+    let result = pck.depacketize(&raw_bytes);
+    assert_eq!(result, Err(Error::ErrAv1CorruptedPacket));
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_depacketize_fragmented() -> Result<()> {
+    let mut pck = Av1Packet::default();
+
+    let fragments = [
+        Bytes::from_static(&[0x50, 0x30, 0xAA]),
+        Bytes::from_static(&[0xD0, 0xBB, 0xCC]),
+        Bytes::from_static(&[0x90, 0xDD]),
+    ];
+
+    assert!(pck.depacketize(&fragments[0])?.is_empty());
+    assert!(pck.depacketize(&fragments[1])?.is_empty());
+    assert_eq!(
+        pck.depacketize(&fragments[2])?,
+        Bytes::from_static(&[0x32, 0x04, 0xAA, 0xBB, 0xCC, 0xDD]),
+        "Fragmented OBU reassembly failed"
+    );
+
+    // A continuation without its beginning is dropped
+    let mut pck = Av1Packet::default();
+    assert!(pck.depacketize(&fragments[1])?.is_empty());
+    assert!(pck.depacketize(&fragments[2])?.is_empty());
+
+    // The following OBU is still recovered
+    let raw_bytes = Bytes::from_static(&[0xA0, 0x01, 0xDD, 0x30, 0xAA]);
+    assert_eq!(
+        pck.depacketize(&raw_bytes)?,
+        Bytes::from_static(&[0x32, 0x01, 0xAA]),
+        "OBU following a lost fragment should be recovered"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_payload_roundtrip() -> Result<()> {
+    let mut payloader = Av1Payloader::default();
+    let mut depacketizer = Av1Packet::default();
+
+    let frame: Vec<u8> = (0..3000).map(|i| (i % 251) as u8).collect();
+    let mut temporal_unit = vec![0x12, 0x00, 0x0A, 0x02, 0x01, 0x02, 0x32, 0xB8, 0x17];
+    temporal_unit.extend(&frame);
+
+    let mut expected = temporal_unit[2..].to_vec();
+    expected.truncate(4);
+    expected.extend([0x32, 0xB8, 0x17]);
+    expected.extend(&frame);
+
+    for mtu in [3, 10, 100, 1188] {
+        let payloads = payloader.payload(mtu, &Bytes::from(temporal_unit.clone()))?;
+        let mut out = vec![];
+        for (i, payload) in payloads.iter().enumerate() {
+            assert!(payload.len() <= mtu, "Packet exceeds the MTU");
+            if i == 0 {
+                assert!(depacketizer.is_partition_head(payload));
+            }
+            out.extend(depacketizer.depacketize(payload)?);
+        }
+        assert_eq!(out, expected, "Roundtrip failed for mtu {}", mtu);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_is_partition_head() -> Result<()> {
+    let av1 = Av1Packet::default();
+
+    assert!(
+        !av1.is_partition_head(&Bytes::new()),
+        "empty nalu must not be a partition head"
+    );
+
+    assert!(
+        av1.is_partition_head(&Bytes::from_static(&[0x10, 0x30])),
+        "packet starting a new OBU must be a partition head"
+    );
+
+    assert!(
+        !av1.is_partition_head(&Bytes::from_static(&[0x90, 0x30])),
+        "packet continuing an OBU must not be a partition head"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_leb128() -> Result<()> {
+    for value in [0u32, 1, 0x7F, 0x80, 0x3FFF, 0x4000, u32::MAX] {
+        let mut out = BytesMut::new();
+        write_leb128(&mut out, value);
+        assert_eq!(out.len(), leb128_size(value));
+        assert_eq!(read_leb128(&out)?, (value, out.len()));
+    }
+
+    assert_eq!(read_leb128(&[0x80]), Err(Error::ErrAv1CorruptedPacket));
+
+    Ok(())
+}
@@ -0,0 +1,368 @@
+#[cfg(test)]
+mod av1_test;
+
+use crate::{
+    error::{Error, Result},
+    packetizer::{Depacketizer, Payloader},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+pub const AV1_AGGREGATION_HEADER_SIZE: usize = 1;
+
+pub const AV1_Z_BITMASK: u8 = 0x80;
+pub const AV1_Y_BITMASK: u8 = 0x40;
+pub const AV1_W_BITMASK: u8 = 0x30;
+pub const AV1_W_BITSHIFT: u8 = 4;
+pub const AV1_N_BITMASK: u8 = 0x08;
+
+pub const OBU_TYPE_BITMASK: u8 = 0x78;
+pub const OBU_TYPE_BITSHIFT: u8 = 3;
+pub const OBU_EXTENSION_FLAG_BITMASK: u8 = 0x04;
+pub const OBU_HAS_SIZE_FIELD_BITMASK: u8 = 0x02;
+
+pub const OBU_TYPE_SEQUENCE_HEADER: u8 = 1;
+pub const OBU_TYPE_TEMPORAL_DELIMITER: u8 = 2;
+pub const OBU_TYPE_TILE_LIST: u8 = 8;
+
+/// Maximum number of OBU elements that can be signalled through the W field,
+/// in which case the last element carries no length field.
+const AV1_MAX_W_ELEMENTS: usize = 3;
+
+/// Av1Payloader payloads AV1 packets
+///
+/// The payloader expects a temporal unit in the low overhead bitstream format
+/// (Section 5 of the AV1 specification), which is what encoders and IVF/OBU
+/// containers produce.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Av1Payloader {}
+
+impl Av1Payloader {
+    /// Splits a temporal unit into OBUs with the obu_size field removed,
+    /// dropping the OBUs that must not be transmitted over RTP.
+    fn parse_obus(payload: &Bytes) -> Result<Vec<Bytes>> {
+        let mut obus = vec![];
+        let mut index = 0;
+        while index < payload.len() {
+            let obu_header = payload[index];
+            let header_size = if obu_header & OBU_EXTENSION_FLAG_BITMASK != 0 {
+                2
+            } else {
+                1
+            };
+            if index + header_size > payload.len() {
+                return Err(Error::ErrAv1CorruptedPacket);
+            }
+
+            let (obu_size, size_len) = if obu_header & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+                let (obu_size, size_len) = read_leb128(&payload[index + header_size..])?;
+                (obu_size as usize, size_len)
+            } else {
+                // Without obu_size the OBU extends to the end of the temporal unit
+                (payload.len() - index - header_size, 0)
+            };
+
+            let data_start = index + header_size + size_len;
+            let data_end = data_start + obu_size;
+            if data_end > payload.len() {
+                return Err(Error::ErrAv1CorruptedPacket);
+            }
+
+            let obu_type = (obu_header & OBU_TYPE_BITMASK) >> OBU_TYPE_BITSHIFT;
+            // https://aomediacodec.github.io/av1-rtp-spec/#5-packetization-rules
+            // Temporal delimiter and tile list OBUs should be removed when transmitted
+            if obu_type != OBU_TYPE_TEMPORAL_DELIMITER && obu_type != OBU_TYPE_TILE_LIST {
+                let mut obu = BytesMut::with_capacity(header_size + obu_size);
+                obu.put_u8(obu_header & !OBU_HAS_SIZE_FIELD_BITMASK);
+                obu.put(&payload[index + 1..index + header_size]);
+                obu.put(&payload[data_start..data_end]);
+                obus.push(obu.freeze());
+            }
+
+            index = data_end;
+        }
+
+        Ok(obus)
+    }
+
+    fn emit(elements: &mut Vec<Bytes>, z: bool, y: bool, n: bool, payloads: &mut Vec<Bytes>) {
+        if elements.is_empty() {
+            return;
+        }
+
+        let w = if elements.len() <= AV1_MAX_W_ELEMENTS {
+            elements.len()
+        } else {
+            0
+        };
+
+        let mut aggregation_header = (w as u8) << AV1_W_BITSHIFT;
+        if z {
+            aggregation_header |= AV1_Z_BITMASK;
+        }
+        if y {
+            aggregation_header |= AV1_Y_BITMASK;
+        }
+        if n {
+            aggregation_header |= AV1_N_BITMASK;
+        }
+
+        let mut out = BytesMut::new();
+        out.put_u8(aggregation_header);
+        let elements_len = elements.len();
+        for (i, element) in elements.drain(..).enumerate() {
+            // When W is set the last element has no length field
+            if w == 0 || i != elements_len - 1 {
+                write_leb128(&mut out, element.len() as u32);
+            }
+            out.put(element);
+        }
+        payloads.push(out.freeze());
+    }
+}
+
+impl Payloader for Av1Payloader {
+    /// Payload fragments an AV1 temporal unit across one or more byte arrays
+    fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
+        // At least one byte of OBU data must fit alongside its length field
+        if payload.is_empty() || mtu <= AV1_AGGREGATION_HEADER_SIZE + 1 {
+            return Ok(vec![]);
+        }
+
+        /*
+         * https://aomediacodec.github.io/av1-rtp-spec/#44-av1-aggregation-header
+         *
+         *  0 1 2 3 4 5 6 7
+         * +-+-+-+-+-+-+-+-+
+         * |Z|Y| W |N|-|-|-|
+         * +-+-+-+-+-+-+-+-+
+         *
+         * Z: MUST be set to 1 if the first OBU element is an OBU fragment that is a
+         *    continuation of an OBU fragment from the previous packet.
+         * Y: MUST be set to 1 if the last OBU element is an OBU fragment that will
+         *    continue in the next packet.
+         * W: two bit field that describes the number of OBU elements in the packet.
+         *    When W is 0 every OBU element is preceded by a length field.
+         * N: MUST be set to 1 if the packet is the first packet of a coded video
+         *    sequence.
+         */
+        let obus = Av1Payloader::parse_obus(payload)?;
+        let mut payloads = vec![];
+
+        // A temporal unit carrying a sequence header starts a new coded video sequence
+        let mut n = obus.iter().any(|obu| {
+            (obu[0] & OBU_TYPE_BITMASK) >> OBU_TYPE_BITSHIFT == OBU_TYPE_SEQUENCE_HEADER
+        });
+
+        let max_payload_size = mtu - AV1_AGGREGATION_HEADER_SIZE;
+        let mut elements = vec![];
+        let mut remaining = max_payload_size;
+        let mut z = false;
+        for obu in obus {
+            let mut obu_index = 0;
+            while obu_index < obu.len() {
+                // Every element is budgeted with a length field so that the
+                // packet fits the MTU whichever W is chosen when emitting.
+                let mut available = remaining.saturating_sub(leb128_size(remaining as u32));
+                if available == 0 {
+                    Av1Payloader::emit(&mut elements, z, false, n, &mut payloads);
+                    z = false;
+                    n = false;
+                    remaining = max_payload_size;
+                    available = remaining - leb128_size(remaining as u32);
+                }
+
+                let fragment_size = std::cmp::min(available, obu.len() - obu_index);
+                elements.push(obu.slice(obu_index..obu_index + fragment_size));
+                remaining -= leb128_size(fragment_size as u32) + fragment_size;
+                obu_index += fragment_size;
+
+                if obu_index < obu.len() {
+                    // The OBU continues in the next packet
+                    Av1Payloader::emit(&mut elements, z, true, n, &mut payloads);
+                    z = true;
+                    n = false;
+                    remaining = max_payload_size;
+                }
+            }
+        }
+        Av1Payloader::emit(&mut elements, z, false, n, &mut payloads);
+
+        Ok(payloads)
+    }
+
+    fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
+/// Av1Packet represents the AV1 aggregation header that is stored in the payload of an RTP Packet
+///
+/// Depacketized OBUs are returned in the low overhead bitstream format, with the
+/// obu_size field set, so that the output of consecutive packets of a temporal
+/// unit can be concatenated into a decodable frame.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Av1Packet {
+    /// first OBU element is a continuation of an OBU fragment from the previous packet
+    pub z: bool,
+    /// last OBU element will continue in the next packet
+    pub y: bool,
+    /// number of OBU elements in the packet, 0 if every element has a length field
+    pub w: u8,
+    /// packet is the first packet of a coded video sequence
+    pub n: bool,
+
+    /// OBU elements of the last depacketized packet, as carried in the payload
+    pub obu_elements: Vec<Bytes>,
+
+    obu_buffer: Option<BytesMut>,
+}
+
+impl Depacketizer for Av1Packet {
+    /// depacketize parses the passed byte slice and stores the result in the Av1Packet this method is called upon
+    fn depacketize(&mut self, packet: &Bytes) -> Result<Bytes> {
+        if packet.len() <= AV1_AGGREGATION_HEADER_SIZE {
+            return Err(Error::ErrShortPacket);
+        }
+
+        let b0 = packet[0];
+        self.z = b0 & AV1_Z_BITMASK != 0;
+        self.y = b0 & AV1_Y_BITMASK != 0;
+        self.w = (b0 & AV1_W_BITMASK) >> AV1_W_BITSHIFT;
+        self.n = b0 & AV1_N_BITMASK != 0;
+
+        self.obu_elements.clear();
+        let mut index = AV1_AGGREGATION_HEADER_SIZE;
+        while index < packet.len() {
+            let is_last = self.w != 0 && self.obu_elements.len() + 1 == self.w as usize;
+            let element_size = if is_last {
+                packet.len() - index
+            } else {
+                let (element_size, size_len) = read_leb128(&packet[index..])?;
+                index += size_len;
+                element_size as usize
+            };
+
+            if index + element_size > packet.len() {
+                return Err(Error::ErrAv1CorruptedPacket);
+            }
+            self.obu_elements
+                .push(packet.slice(index..index + element_size));
+            index += element_size;
+
+            if is_last {
+                break;
+            }
+        }
+
+        if self.w != 0 && self.obu_elements.len() != self.w as usize {
+            return Err(Error::ErrAv1CorruptedPacket);
+        }
+
+        let mut payload = BytesMut::new();
+        let elements_len = self.obu_elements.len();
+        for (i, element) in self.obu_elements.iter().enumerate() {
+            let obu = if i == 0 && self.z {
+                // Continuation of a fragmented OBU, which is dropped if its
+                // beginning was never received.
+                match self.obu_buffer.take() {
+                    Some(mut obu_buffer) => {
+                        obu_buffer.put(&**element);
+                        obu_buffer
+                    }
+                    None => continue,
+                }
+            } else {
+                // A new OBU starts, any incomplete OBU left is discarded
+                self.obu_buffer = None;
+                BytesMut::from(&**element)
+            };
+
+            if i == elements_len - 1 && self.y {
+                self.obu_buffer = Some(obu);
+                break;
+            }
+
+            write_obu(&mut payload, &obu)?;
+        }
+
+        Ok(payload.freeze())
+    }
+
+    /// is_partition_head checks whether the first OBU element starts a new OBU
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        if payload.is_empty() {
+            false
+        } else {
+            payload[0] & AV1_Z_BITMASK == 0
+        }
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+}
+
+/// Writes an OBU with the obu_has_size_field flag set and the obu_size field filled in
+fn write_obu(out: &mut BytesMut, obu: &[u8]) -> Result<()> {
+    if obu.is_empty() {
+        return Ok(());
+    }
+
+    let obu_header = obu[0];
+    let header_size = if obu_header & OBU_EXTENSION_FLAG_BITMASK != 0 {
+        2
+    } else {
+        1
+    };
+    if obu.len() < header_size {
+        return Err(Error::ErrAv1CorruptedPacket);
+    }
+
+    out.put_u8(obu_header | OBU_HAS_SIZE_FIELD_BITMASK);
+    out.put(&obu[1..header_size]);
+    if obu_header & OBU_HAS_SIZE_FIELD_BITMASK != 0 {
+        // Already in the low overhead bitstream format
+        out.put(&obu[header_size..]);
+    } else {
+        write_leb128(out, (obu.len() - header_size) as u32);
+        out.put(&obu[header_size..]);
+    }
+
+    Ok(())
+}
+
+/// Reads an unsigned leb128 value, returning the value and the number of bytes read
+pub(crate) fn read_leb128(b: &[u8]) -> Result<(u32, usize)> {
+    let mut value: u64 = 0;
+    for (i, &byte) in b.iter().enumerate().take(8) {
+        value |= ((byte & 0x7F) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            if value > u32::MAX as u64 {
+                return Err(Error::ErrAv1CorruptedPacket);
+            }
+            return Ok((value as u32, i + 1));
+        }
+    }
+
+    Err(Error::ErrAv1CorruptedPacket)
+}
+
+/// Writes an unsigned leb128 value
+pub(crate) fn write_leb128(out: &mut BytesMut, mut value: u32) {
+    while value >= 0x80 {
+        out.put_u8((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.put_u8(value as u8);
+}
+
+/// Returns the number of bytes needed to leb128 encode a value
+pub(crate) fn leb128_size(mut value: u32) -> usize {
+    let mut size = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        size += 1;
+    }
+    size
+}
@@ -37,7 +37,7 @@ pub const OUTPUT_STAP_AHEADER: u8 = 0x78;
 pub static ANNEXB_NALUSTART_CODE: Bytes = Bytes::from_static(&[0x00, 0x00, 0x00, 0x01]);
 
 impl H264Payloader {
-    fn next_ind(nalu: &Bytes, start: usize) -> (isize, isize) {
+    pub(crate) fn next_ind(nalu: &Bytes, start: usize) -> (isize, isize) {
         let mut zero_count = 0;
 
         for (i, &b) in nalu[start..].iter().enumerate() {
@@ -889,3 +889,100 @@ fn test_h265_packet_real() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_h265_payload() -> Result<()> {
+    let mut pck = H265Payloader::default();
+
+    // Positive MTU, empty payload
+    let empty = Bytes::from_static(&[]);
+    let result = pck.payload(100, &empty)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    // 0 MTU, small payload
+    let small_payload = Bytes::from_static(&[0x02, 0x01, 0xaa, 0xbb, 0xcc]);
+    let result = pck.payload(0, &small_payload)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    // Single NAL unit, with and without start code
+    let result = pck.payload(100, &small_payload)?;
+    assert_eq!(result, vec![small_payload.clone()], "Single NALU failed");
+    let annexb = Bytes::from_static(&[0x00, 0x00, 0x00, 0x01, 0x02, 0x01, 0xaa, 0xbb, 0xcc]);
+    let result = pck.payload(100, &annexb)?;
+    assert_eq!(result, vec![small_payload], "Single NALU failed");
+
+    // VPS and SPS are aggregated
+    let parameter_sets = Bytes::from_static(&[
+        0x00, 0x00, 0x00, 0x01, 0x40, 0x01, 0x0c, 0x00, 0x00, 0x01, 0x42, 0x01, 0x01, 0x02,
+    ]);
+    let aggregated = Bytes::from_static(&[
+        0x60, 0x01, 0x00, 0x03, 0x40, 0x01, 0x0c, 0x00, 0x04, 0x42, 0x01, 0x01, 0x02,
+    ]);
+    let result = pck.payload(100, &parameter_sets)?;
+    assert_eq!(result, vec![aggregated.clone()], "Aggregation failed");
+
+    let mut depacketizer = H265Packet::default();
+    depacketizer.depacketize(&result[0])?;
+    if let H265Payload::H265AggregationPacket(packet) = depacketizer.payload() {
+        let first_unit = packet.first_unit().expect("first unit");
+        assert_eq!(
+            first_unit.nal_unit(),
+            Bytes::from_static(&[0x40, 0x01, 0x0c])
+        );
+        assert_eq!(packet.other_units().len(), 1);
+        assert_eq!(
+            packet.other_units()[0].nal_unit(),
+            Bytes::from_static(&[0x42, 0x01, 0x01, 0x02])
+        );
+    } else {
+        panic!("expected an aggregation packet");
+    }
+
+    // Aggregation exceeding the MTU falls back to single NAL units
+    let result = pck.payload(12, &parameter_sets)?;
+    assert_eq!(
+        result,
+        vec![
+            Bytes::from_static(&[0x40, 0x01, 0x0c]),
+            Bytes::from_static(&[0x42, 0x01, 0x01, 0x02]),
+        ],
+        "Aggregation should not exceed the MTU"
+    );
+
+    // Aggregation disabled
+    let mut no_aggregation = H265Payloader {
+        skip_aggregation: true,
+    };
+    let result = no_aggregation.payload(100, &parameter_sets)?;
+    assert_eq!(result.len(), 2, "Aggregation should be skipped");
+
+    // Large NAL unit split across multiple RTP packets
+    let large_payload = Bytes::from_static(&[
+        0x00, 0x00, 0x01, 0x26, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a,
+    ]);
+    let large_payload_packetized = vec![
+        Bytes::from_static(&[0x62, 0x01, 0x93, 0x01, 0x02, 0x03]),
+        Bytes::from_static(&[0x62, 0x01, 0x13, 0x04, 0x05, 0x06]),
+        Bytes::from_static(&[0x62, 0x01, 0x13, 0x07, 0x08, 0x09]),
+        Bytes::from_static(&[0x62, 0x01, 0x53, 0x0a]),
+    ];
+    let result = pck.payload(6, &large_payload)?;
+    assert_eq!(result, large_payload_packetized, "FU packetization failed");
+
+    for (i, payload) in result.iter().enumerate() {
+        depacketizer.depacketize(payload)?;
+        if let H265Payload::H265FragmentationUnitPacket(packet) = depacketizer.payload() {
+            assert_eq!(packet.fu_header().s(), i == 0);
+            assert_eq!(packet.fu_header().e(), i == result.len() - 1);
+            assert_eq!(packet.fu_header().fu_type(), 19);
+        } else {
+            panic!("expected a fragmentation unit packet");
+        }
+    }
+
+    // MTU too small to fragment
+    let result = pck.payload(3, &large_payload)?;
+    assert!(result.is_empty(), "Generated payload should be empty");
+
+    Ok(())
+}
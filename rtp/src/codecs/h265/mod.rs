@@ -1,6 +1,7 @@
+use crate::codecs::h264::H264Payloader;
 use crate::error::{Error, Result};
-use crate::packetizer::Depacketizer;
-use bytes::Bytes;
+use crate::packetizer::{Depacketizer, Payloader};
+use bytes::{BufMut, Bytes, BytesMut};
 
 #[cfg(test)]
 mod h265_test;
@@ -794,3 +795,174 @@ impl Depacketizer for H265Packet {
         marker
     }
 }
+
+/// H265Payloader payloads H265 packets
+///
+/// Access units are expected in Annex B format. NAL units that fit in the MTU are
+/// sent as Single NAL Unit packets or grouped into Aggregation Packets, larger
+/// ones are split into Fragmentation Units. DONL is never emitted, so the stream
+/// must be negotiated with `sprop-max-don-diff` equal to 0.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct H265Payloader {
+    /// skip_aggregation disables Aggregation Packets, every NAL unit is sent on its own.
+    pub skip_aggregation: bool,
+}
+
+impl H265Payloader {
+    fn emit(
+        &self,
+        nalu: &Bytes,
+        mtu: usize,
+        aggregation: &mut Vec<Bytes>,
+        payloads: &mut Vec<Bytes>,
+    ) {
+        if nalu.len() <= H265NALU_HEADER_SIZE {
+            return;
+        }
+
+        if nalu.len() > mtu {
+            H265Payloader::flush_aggregation(aggregation, payloads);
+            H265Payloader::emit_fragmented(nalu, mtu, payloads);
+            return;
+        }
+
+        if self.skip_aggregation {
+            payloads.push(nalu.clone());
+            return;
+        }
+
+        // Each aggregation unit is prefixed by its 16-bit size
+        let aggregation_size =
+            H265NALU_HEADER_SIZE + aggregation.iter().map(|unit| 2 + unit.len()).sum::<usize>();
+        if !aggregation.is_empty() && aggregation_size + 2 + nalu.len() > mtu {
+            H265Payloader::flush_aggregation(aggregation, payloads);
+        }
+
+        if H265NALU_HEADER_SIZE + 2 + nalu.len() > mtu {
+            // Too large to be aggregated with any other NAL unit
+            payloads.push(nalu.clone());
+        } else {
+            aggregation.push(nalu.clone());
+        }
+    }
+
+    fn flush_aggregation(aggregation: &mut Vec<Bytes>, payloads: &mut Vec<Bytes>) {
+        match aggregation.len() {
+            0 => {}
+            1 => payloads.push(aggregation.remove(0)),
+            _ => {
+                // The F bit is set if any aggregated NAL unit has it set, LayerId
+                // and TID are the lowest values of the aggregated NAL units.
+                let mut f = false;
+                let mut layer_id = u8::MAX;
+                let mut tid = u8::MAX;
+                for unit in aggregation.iter() {
+                    let header = H265NALUHeader::new(unit[0], unit[1]);
+                    f |= header.f();
+                    layer_id = std::cmp::min(layer_id, header.layer_id());
+                    tid = std::cmp::min(tid, header.tid());
+                }
+
+                let payload_header = ((f as u16) << 15)
+                    | ((H265NALU_AGGREGATION_PACKET_TYPE as u16) << 9)
+                    | ((layer_id as u16) << 3)
+                    | tid as u16;
+
+                let mut out = BytesMut::new();
+                out.put_u16(payload_header);
+                for unit in aggregation.drain(..) {
+                    out.put_u16(unit.len() as u16);
+                    out.put(unit);
+                }
+                payloads.push(out.freeze());
+            }
+        }
+    }
+
+    fn emit_fragmented(nalu: &Bytes, mtu: usize, payloads: &mut Vec<Bytes>) {
+        const TOTAL_HEADER_SIZE: usize = H265NALU_HEADER_SIZE + H265FRAGMENTATION_UNIT_HEADER_SIZE;
+        if mtu <= TOTAL_HEADER_SIZE {
+            return;
+        }
+        let max_fragment_size = mtu - TOTAL_HEADER_SIZE;
+
+        let header = H265NALUHeader::new(nalu[0], nalu[1]);
+        // PayloadHdr copies F, LayerId and TID from the fragmented NAL unit
+        const TYPE_MASK: u16 = 0b01111110 << 8;
+        let payload_header =
+            (header.0 & !TYPE_MASK) | ((H265NALU_FRAGMENTATION_UNIT_TYPE as u16) << 9);
+
+        // The NAL unit header is not included in the FU payload, it is conveyed
+        // by the PayloadHdr and the FuType of the FU header.
+        let nalu_data = nalu.slice(H265NALU_HEADER_SIZE..);
+        let mut nalu_data_index = 0;
+        while nalu_data_index < nalu_data.len() {
+            let current_fragment_size =
+                std::cmp::min(max_fragment_size, nalu_data.len() - nalu_data_index);
+
+            let mut fu_header = header.nalu_type();
+            if nalu_data_index == 0 {
+                // Set start bit
+                fu_header |= 1 << 7;
+            } else if nalu_data_index + current_fragment_size == nalu_data.len() {
+                // Set end bit
+                fu_header |= 1 << 6;
+            }
+
+            let mut out = BytesMut::with_capacity(TOTAL_HEADER_SIZE + current_fragment_size);
+            out.put_u16(payload_header);
+            out.put_u8(fu_header);
+            out.put(&nalu_data[nalu_data_index..nalu_data_index + current_fragment_size]);
+            payloads.push(out.freeze());
+
+            nalu_data_index += current_fragment_size;
+        }
+    }
+}
+
+impl Payloader for H265Payloader {
+    /// Payload fragments a H265 packet across one or more byte arrays
+    fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
+        if payload.is_empty() || mtu == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut payloads = vec![];
+        let mut aggregation = vec![];
+
+        let (mut next_ind_start, mut next_ind_len) = H264Payloader::next_ind(payload, 0);
+        if next_ind_start == -1 {
+            self.emit(payload, mtu, &mut aggregation, &mut payloads);
+        } else {
+            while next_ind_start != -1 {
+                let prev_start = (next_ind_start + next_ind_len) as usize;
+                let (next_ind_start2, next_ind_len2) = H264Payloader::next_ind(payload, prev_start);
+                next_ind_start = next_ind_start2;
+                next_ind_len = next_ind_len2;
+                if next_ind_start != -1 {
+                    self.emit(
+                        &payload.slice(prev_start..next_ind_start as usize),
+                        mtu,
+                        &mut aggregation,
+                        &mut payloads,
+                    );
+                } else {
+                    // Emit until end of stream, no end indicator found
+                    self.emit(
+                        &payload.slice(prev_start..),
+                        mtu,
+                        &mut aggregation,
+                        &mut payloads,
+                    );
+                }
+            }
+        }
+        H265Payloader::flush_aggregation(&mut aggregation, &mut payloads);
+
+        Ok(payloads)
+    }
+
+    fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
+        Box::new(*self)
+    }
+}
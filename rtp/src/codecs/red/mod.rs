@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod red_test;
+
+use crate::error::{Error, Result};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// The largest timestamp offset a redundant block header can carry
+pub const RED_MAX_TIMESTAMP_OFFSET: u32 = (1 << 14) - 1;
+/// The largest payload a redundant block header can carry
+pub const RED_MAX_BLOCK_LENGTH: usize = (1 << 10) - 1;
+
+const RED_BLOCK_HEADER_SIZE: usize = 4;
+const RED_PRIMARY_HEADER_SIZE: usize = 1;
+const RED_FOLLOW_BIT: u8 = 0x80;
+const RED_PAYLOAD_TYPE_MASK: u8 = 0x7F;
+
+/// RedBlock is a single encoding carried in a RED payload
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct RedBlock {
+    pub payload_type: u8,
+    /// timestamp_offset is how much older the block is than the RTP timestamp of the packet,
+    /// it is always zero for the primary block
+    pub timestamp_offset: u32,
+    pub payload: Bytes,
+}
+
+/// RedPacket represents the payload of a RED packet as defined in RFC 2198:
+///
+/// ```text
+///  0                   1                    2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |F|   block PT  |  timestamp offset         |   block length    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |0|   Block PT  |
+/// +-+-+-+-+-+-+-+-+
+/// ```
+/// One header with F=1 precedes the final header of the primary encoding for every
+/// redundant block, the block data follows the headers in the same order.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct RedPacket {
+    /// redundant holds the older encodings, oldest first
+    pub redundant: Vec<RedBlock>,
+    pub primary: RedBlock,
+}
+
+impl RedPacket {
+    /// marshal serializes the RED payload
+    pub fn marshal(&self) -> Result<Bytes> {
+        let size = self.redundant.len() * RED_BLOCK_HEADER_SIZE
+            + RED_PRIMARY_HEADER_SIZE
+            + self
+                .redundant
+                .iter()
+                .map(|b| b.payload.len())
+                .sum::<usize>()
+            + self.primary.payload.len();
+
+        let mut buf = BytesMut::with_capacity(size);
+        for block in &self.redundant {
+            if block.timestamp_offset > RED_MAX_TIMESTAMP_OFFSET
+                || block.payload.len() > RED_MAX_BLOCK_LENGTH
+            {
+                return Err(Error::ErrRedBlockTooLarge);
+            }
+            let header = (block.timestamp_offset << 10) | block.payload.len() as u32;
+            buf.put_u8(RED_FOLLOW_BIT | (block.payload_type & RED_PAYLOAD_TYPE_MASK));
+            buf.put_u8((header >> 16) as u8);
+            buf.put_u16(header as u16);
+        }
+        buf.put_u8(self.primary.payload_type & RED_PAYLOAD_TYPE_MASK);
+
+        for block in &self.redundant {
+            buf.extend_from_slice(&block.payload);
+        }
+        buf.extend_from_slice(&self.primary.payload);
+
+        Ok(buf.freeze())
+    }
+
+    /// unmarshal parses the passed RED payload
+    pub fn unmarshal(payload: &Bytes) -> Result<Self> {
+        let mut redundant = vec![];
+        let mut offset = 0;
+        loop {
+            if offset >= payload.len() {
+                return Err(Error::ErrRedCorruptedPacket);
+            }
+            if payload[offset] & RED_FOLLOW_BIT == 0 {
+                break;
+            }
+            if offset + RED_BLOCK_HEADER_SIZE > payload.len() {
+                return Err(Error::ErrRedCorruptedPacket);
+            }
+
+            let header = ((payload[offset + 1] as u32) << 16)
+                | ((payload[offset + 2] as u32) << 8)
+                | payload[offset + 3] as u32;
+            redundant.push((
+                payload[offset] & RED_PAYLOAD_TYPE_MASK,
+                header >> 10,
+                (header & 0x3FF) as usize,
+            ));
+            offset += RED_BLOCK_HEADER_SIZE;
+        }
+        let primary_payload_type = payload[offset] & RED_PAYLOAD_TYPE_MASK;
+        offset += RED_PRIMARY_HEADER_SIZE;
+
+        let mut blocks = Vec::with_capacity(redundant.len());
+        for (payload_type, timestamp_offset, length) in redundant {
+            if offset + length > payload.len() {
+                return Err(Error::ErrRedCorruptedPacket);
+            }
+            blocks.push(RedBlock {
+                payload_type,
+                timestamp_offset,
+                payload: payload.slice(offset..offset + length),
+            });
+            offset += length;
+        }
+
+        Ok(RedPacket {
+            redundant: blocks,
+            primary: RedBlock {
+                payload_type: primary_payload_type,
+                timestamp_offset: 0,
+                payload: payload.slice(offset..),
+            },
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use super::*;
+
+#[test]
+fn test_red_packet_marshal_unmarshal() -> Result<()> {
+    let packet = RedPacket {
+        redundant: vec![
+            RedBlock {
+                payload_type: 111,
+                timestamp_offset: 1920,
+                payload: Bytes::from_static(&[0x01, 0x02, 0x03]),
+            },
+            RedBlock {
+                payload_type: 111,
+                timestamp_offset: 960,
+                payload: Bytes::from_static(&[0x04, 0x05]),
+            },
+        ],
+        primary: RedBlock {
+            payload_type: 111,
+            timestamp_offset: 0,
+            payload: Bytes::from_static(&[0x06, 0x07, 0x08, 0x09]),
+        },
+    };
+
+    let raw = packet.marshal()?;
+    assert_eq!(
+        raw,
+        Bytes::from_static(&[
+            0xEF, 0x1E, 0x00, 0x03, // redundant block 1: PT 111, offset 1920, length 3
+            0xEF, 0x0F, 0x00, 0x02, // redundant block 2: PT 111, offset 960, length 2
+            0x6F, // primary block: PT 111
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
+        ])
+    );
+    assert_eq!(RedPacket::unmarshal(&raw)?, packet);
+
+    Ok(())
+}
+
+#[test]
+fn test_red_packet_primary_only() -> Result<()> {
+    let raw = Bytes::from_static(&[0x6F, 0xAA, 0xBB]);
+    let packet = RedPacket::unmarshal(&raw)?;
+    assert!(packet.redundant.is_empty());
+    assert_eq!(packet.primary.payload_type, 111);
+    assert_eq!(packet.primary.payload, Bytes::from_static(&[0xAA, 0xBB]));
+    assert_eq!(packet.marshal()?, raw);
+
+    Ok(())
+}
+
+#[test]
+fn test_red_packet_errors() {
+    for raw in [
+        &[][..],
+        // truncated block header
+        &[0xEF, 0x00][..],
+        // no primary header
+        &[0xEF, 0x00, 0x00, 0x00][..],
+        // block longer than the payload
+        &[0xEF, 0x00, 0x00, 0x05, 0x6F, 0x01][..],
+    ] {
+        assert_eq!(
+            RedPacket::unmarshal(&Bytes::copy_from_slice(raw)),
+            Err(Error::ErrRedCorruptedPacket),
+            "{:?}",
+            raw
+        );
+    }
+
+    let packet = RedPacket {
+        redundant: vec![RedBlock {
+            payload_type: 111,
+            timestamp_offset: RED_MAX_TIMESTAMP_OFFSET + 1,
+            payload: Bytes::from_static(&[0x01]),
+        }],
+        ..Default::default()
+    };
+    assert_eq!(packet.marshal(), Err(Error::ErrRedBlockTooLarge));
+}
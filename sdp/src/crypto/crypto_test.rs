@@ -0,0 +1,107 @@
+use super::*;
+
+use std::io::BufReader;
+
+const EXAMPLE_ATTR_CRYPTO1: &str =
+    "crypto:1 AES_CM_128_HMAC_SHA1_80 inline:WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz|2^20|1:4";
+const EXAMPLE_ATTR_CRYPTO2: &str = "crypto:2 AES_CM_128_HMAC_SHA1_32 inline:NzB4d1BINUAvLEw6UzF3WSJ+PSdFcGdUJShpX1Zj|2^20|1:32;inline:MTIzNDU2Nzg5QUJDREUwMTIzNDU2Nzg5QUJjZGVm|2^20|2:32 KDR=1 UNENCRYPTED_SRTCP";
+const EXAMPLE_ATTR_CRYPTO3: &str =
+    "crypto:3 AEAD_AES_256_GCM inline:M2YwNDIwZTY0MjExNWQ2OGRiNDRhYjM0ZGVhMjgxOTM5YWVlNjI2MTg3";
+const FAILING_ATTR_CRYPTO1: &str = "crypto:1 AES_CM_128_HMAC_SHA1_80";
+const FAILING_ATTR_CRYPTO2: &str =
+    "crypto:a AES_CM_128_HMAC_SHA1_80 inline:WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz";
+const FAILING_ATTR_CRYPTO3: &str = "crypto:1 AES_CM_128_HMAC_SHA1_80 inline";
+
+#[test]
+fn test_crypto() -> Result<()> {
+    let passingtests = [
+        EXAMPLE_ATTR_CRYPTO1,
+        EXAMPLE_ATTR_CRYPTO2,
+        EXAMPLE_ATTR_CRYPTO3,
+    ];
+    let failingtests = [
+        FAILING_ATTR_CRYPTO1,
+        FAILING_ATTR_CRYPTO2,
+        FAILING_ATTR_CRYPTO3,
+    ];
+
+    for (i, u) in passingtests.iter().enumerate() {
+        let mut reader = BufReader::new(u.as_bytes());
+        let actual = Crypto::unmarshal(&mut reader)?;
+        assert_eq!(*u, actual.marshal(), "{}: {} vs {}", i, u, actual.marshal());
+    }
+
+    for u in failingtests {
+        let mut reader = BufReader::new(u.as_bytes());
+        let actual = Crypto::unmarshal(&mut reader);
+        assert!(actual.is_err(), "{} should fail", u);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_crypto_key_params() -> Result<()> {
+    let mut reader = BufReader::new(EXAMPLE_ATTR_CRYPTO2.as_bytes());
+    let c = Crypto::unmarshal(&mut reader)?;
+
+    assert_eq!(c.tag, 2);
+    assert_eq!(c.suite, CRYPTO_SUITE_AES_CM_128_HMAC_SHA1_32);
+    assert_eq!(
+        c.key_params,
+        vec![
+            KeyParam {
+                key_method: KEY_METHOD_INLINE.to_owned(),
+                key_salt: "NzB4d1BINUAvLEw6UzF3WSJ+PSdFcGdUJShpX1Zj".to_owned(),
+                lifetime: Some("2^20".to_owned()),
+                mki: Some("1:32".to_owned()),
+            },
+            KeyParam {
+                key_method: KEY_METHOD_INLINE.to_owned(),
+                key_salt: "MTIzNDU2Nzg5QUJDREUwMTIzNDU2Nzg5QUJjZGVm".to_owned(),
+                lifetime: Some("2^20".to_owned()),
+                mki: Some("2:32".to_owned()),
+            },
+        ]
+    );
+    assert_eq!(c.session_params, vec!["KDR=1", "UNENCRYPTED_SRTCP"]);
+
+    let c = Crypto {
+        tag: 1,
+        suite: CRYPTO_SUITE_AEAD_AES_128_GCM.to_owned(),
+        key_params: vec![KeyParam::new_inline(
+            "MTIzNDU2Nzg5QUJDREUwMTIzNDU2Nzg5QUJjZGVm".to_owned(),
+        )],
+        session_params: vec![],
+    };
+    assert_eq!(
+        c.marshal(),
+        "crypto:1 AEAD_AES_128_GCM inline:MTIzNDU2Nzg5QUJDREUwMTIzNDU2Nzg5QUJjZGVm"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_key_param_lifetime_and_mki() -> Result<()> {
+    let key_salt = "WVNfX19zZW1jdGwgKCkgewkyMjA7fQp9CnVubGVz";
+    let tests = [
+        ("inline:KEY|2^20|1:4", Some("2^20"), Some("1:4")),
+        ("inline:KEY|1048576|1:4", Some("1048576"), Some("1:4")),
+        ("inline:KEY|2^20", Some("2^20"), None),
+        ("inline:KEY|1:4", None, Some("1:4")),
+        ("inline:KEY", None, None),
+    ];
+
+    for (param, lifetime, mki) in tests {
+        let param = param.replace("KEY", key_salt);
+        let key_param = KeyParam::unmarshal(&param)?;
+        assert_eq!(key_param.key_method, KEY_METHOD_INLINE, "{}", param);
+        assert_eq!(key_param.key_salt, key_salt, "{}", param);
+        assert_eq!(key_param.lifetime.as_deref(), lifetime, "{}", param);
+        assert_eq!(key_param.mki.as_deref(), mki, "{}", param);
+        assert_eq!(key_param.to_string(), param);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod crypto_test;
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+
+use std::fmt;
+use std::io;
+
+/// Crypto-suites of SRTP for SDES
+pub const CRYPTO_SUITE_AES_CM_128_HMAC_SHA1_80: &str = "AES_CM_128_HMAC_SHA1_80";
+pub const CRYPTO_SUITE_AES_CM_128_HMAC_SHA1_32: &str = "AES_CM_128_HMAC_SHA1_32";
+pub const CRYPTO_SUITE_AES_256_CM_HMAC_SHA1_80: &str = "AES_256_CM_HMAC_SHA1_80";
+pub const CRYPTO_SUITE_AES_256_CM_HMAC_SHA1_32: &str = "AES_256_CM_HMAC_SHA1_32";
+pub const CRYPTO_SUITE_AEAD_AES_128_GCM: &str = "AEAD_AES_128_GCM";
+pub const CRYPTO_SUITE_AEAD_AES_256_GCM: &str = "AEAD_AES_256_GCM";
+
+/// The only key method defined for SRTP
+pub const KEY_METHOD_INLINE: &str = "inline";
+
+/// KeyParam is one key of a crypto attribute: the base64 encoded master key
+/// and salt, followed by the optional lifetime and MKI
+/// <https://tools.ietf.org/html/rfc4568#section-6.1>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyParam {
+    pub key_method: String,
+    pub key_salt: String,
+    pub lifetime: Option<String>,
+    pub mki: Option<String>,
+}
+
+impl fmt::Display for KeyParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = format!("{}:{}", self.key_method, self.key_salt);
+        if let Some(lifetime) = &self.lifetime {
+            output += format!("|{}", lifetime).as_str();
+        }
+
+        if let Some(mki) = &self.mki {
+            output += format!("|{}", mki).as_str();
+        }
+
+        write!(f, "{}", output)
+    }
+}
+
+impl KeyParam {
+    /// new_inline creates an inline key from the base64 encoded master key
+    /// and salt
+    pub fn new_inline(key_salt: String) -> Self {
+        KeyParam {
+            key_method: KEY_METHOD_INLINE.to_owned(),
+            key_salt,
+            ..Default::default()
+        }
+    }
+
+    fn unmarshal(param: &str) -> Result<Self> {
+        let (key_method, key_info) = match param.split_once(':') {
+            Some((key_method, key_info)) if !key_info.is_empty() => (key_method, key_info),
+            _ => return Err(Error::ParseCrypto(param.to_owned())),
+        };
+
+        let mut fields = key_info.split('|');
+        let key_salt = fields.next().unwrap_or_default().to_owned();
+
+        // Both are optional, the MKI is told apart from the lifetime by the
+        // ':' between its value and its length
+        let mut lifetime = None;
+        let mut mki = None;
+        for field in fields {
+            if field.contains(':') {
+                mki = Some(field.to_owned());
+            } else {
+                lifetime = Some(field.to_owned());
+            }
+        }
+
+        Ok(KeyParam {
+            key_method: key_method.to_owned(),
+            key_salt,
+            lifetime,
+            mki,
+        })
+    }
+}
+
+/// Crypto represents a SDES crypto attribute, which signals the crypto-suite
+/// and the keys of a SRTP stream
+/// <https://tools.ietf.org/html/rfc4568#section-9.1>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Crypto {
+    pub tag: u32,
+    pub suite: String,
+    pub key_params: Vec<KeyParam>,
+    pub session_params: Vec<String>,
+}
+
+impl fmt::Display for Crypto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key_params: Vec<String> = self.key_params.iter().map(|k| k.to_string()).collect();
+        let mut output = format!("{} {} {}", self.tag, self.suite, key_params.join(";"));
+
+        for session_param in &self.session_params {
+            output += format!(" {}", session_param).as_str();
+        }
+
+        write!(f, "{}", output)
+    }
+}
+
+impl Crypto {
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: "crypto".to_string(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal creates a Crypto from a string
+    pub fn unmarshal<R: io::BufRead>(reader: &mut R) -> Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let parts: Vec<&str> = line.trim().splitn(2, ':').collect();
+        if parts.len() != 2 {
+            return Err(Error::ParseCrypto(line));
+        }
+
+        let fields: Vec<&str> = parts[1].split_whitespace().collect();
+        if fields.len() < 3 {
+            return Err(Error::ParseCrypto(line));
+        }
+
+        let tag = fields[0].parse::<u32>()?;
+        if tag > 999_999_999 {
+            return Err(Error::ParseCrypto(format!(
+                "{} -- crypto tag must be at most 9 digits",
+                fields[0]
+            )));
+        }
+
+        let mut key_params = vec![];
+        for param in fields[2].split(';') {
+            key_params.push(KeyParam::unmarshal(param)?);
+        }
+
+        Ok(Crypto {
+            tag,
+            suite: fields[1].to_owned(),
+            key_params,
+            session_params: fields[3..].iter().map(|p| p.to_string()).collect(),
+        })
+    }
+
+    /// marshal creates a string from a Crypto
+    pub fn marshal(&self) -> String {
+        "crypto:".to_string() + self.to_string().as_str()
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::fmt;
 use url::Url;
 
+use crate::crypto::*;
 use crate::description::common::*;
 use crate::extmap::*;
 
@@ -175,6 +176,11 @@ impl MediaDescription {
         self.with_property_attribute(e.marshal())
     }
 
+    /// with_crypto adds a SDES crypto attribute to the media description
+    pub fn with_crypto(self, c: Crypto) -> Self {
+        self.with_value_attribute("crypto".to_string(), c.to_string())
+    }
+
     /// with_transport_cc_extmap adds an extmap to the media description
     pub fn with_transport_cc_extmap(self) -> Self {
         let uri = {
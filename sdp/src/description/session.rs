@@ -29,11 +29,14 @@ pub const ATTR_KEY_RECV_ONLY: &str = "recvonly";
 pub const ATTR_KEY_SEND_ONLY: &str = "sendonly";
 pub const ATTR_KEY_SEND_RECV: &str = "sendrecv";
 pub const ATTR_KEY_EXT_MAP: &str = "extmap";
+pub const ATTR_KEY_CRYPTEX: &str = "cryptex";
+pub const ATTR_KEY_CRYPTO: &str = "crypto";
 
 /// Constants for semantic tokens used in JSEP
 pub const SEMANTIC_TOKEN_LIP_SYNCHRONIZATION: &str = "LS";
 pub const SEMANTIC_TOKEN_FLOW_IDENTIFICATION: &str = "FID";
 pub const SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION: &str = "FEC";
+pub const SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION_FRAMEWORK: &str = "FEC-FR";
 pub const SEMANTIC_TOKEN_WEBRTC_MEDIA_STREAMS: &str = "WMS";
 
 /// Version describes the value provided by the "v=" field which gives
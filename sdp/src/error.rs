@@ -34,6 +34,8 @@ pub enum Error {
     ParseUrl(#[from] url::ParseError),
     #[error("parse extmap: {0}")]
     ParseExtMap(String),
+    #[error("parse crypto: {0}")]
+    ParseCrypto(String),
     #[error("{} --> {} <-- {}", .s.substring(0,*.p), .s.substring(*.p, *.p+1), .s.substring(*.p+1, .s.len()))]
     SyntaxError { s: String, p: usize },
 }
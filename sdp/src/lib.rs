@@ -1,6 +1,7 @@
 #![warn(rust_2018_idioms)]
 #![allow(dead_code)]
 
+pub mod crypto;
 pub mod description;
 pub mod direction;
 pub mod extmap;
@@ -1,11 +1,8 @@
-use aes_gcm::{
-    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
-    Aes128Gcm, Nonce,
-};
+use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead, Payload};
 use byteorder::{BigEndian, ByteOrder};
 use bytes::{Bytes, BytesMut};
 
-use super::Cipher;
+use super::*;
 use crate::{
     error::{Error, Result},
     key_derivation::*,
@@ -16,15 +13,15 @@ pub const CIPHER_AEAD_AES_GCM_AUTH_TAG_LEN: usize = 16;
 
 const RTCP_ENCRYPTION_FLAG: u8 = 0x80;
 
-/// AEAD Cipher based on AES.
-pub(crate) struct CipherAeadAesGcm {
-    srtp_cipher: aes_gcm::Aes128Gcm,
-    srtcp_cipher: aes_gcm::Aes128Gcm,
+/// AEAD Cipher based on AES, `C` is `Aes128Gcm` or `Aes256Gcm`.
+pub(crate) struct CipherAeadAesGcm<C> {
+    srtp_cipher: C,
+    srtcp_cipher: C,
     srtp_session_salt: Vec<u8>,
     srtcp_session_salt: Vec<u8>,
 }
 
-impl Cipher for CipherAeadAesGcm {
+impl<C: Aead + NewAead> Cipher for CipherAeadAesGcm<C> {
     fn auth_tag_len(&self) -> usize {
         CIPHER_AEAD_AES_GCM_AUTH_TAG_LEN
     }
@@ -44,8 +41,27 @@ impl Cipher for CipherAeadAesGcm {
 
         let nonce = self.rtp_initialization_vector(header, roc);
 
+        if is_cryptex(header) {
+            // The CSRCs and the header extension are encrypted along with the
+            // payload, only the fixed header and the preamble are authenticated
+            writer.extend_from_slice(payload);
+            let payload_offset = cryptex_swap_in(&mut writer, header);
+            let encrypted = self.srtp_cipher.encrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: &writer[payload_offset..],
+                    aad: &writer[..payload_offset],
+                },
+            )?;
+
+            writer.truncate(payload_offset);
+            writer.extend(encrypted);
+            cryptex_swap_out(&mut writer, header);
+            return Ok(writer.freeze());
+        }
+
         let encrypted = self.srtp_cipher.encrypt(
-            Nonce::from_slice(&nonce),
+            GenericArray::from_slice(&nonce),
             Payload {
                 msg: payload,
                 aad: &writer,
@@ -67,9 +83,29 @@ impl Cipher for CipherAeadAesGcm {
         }
 
         let nonce = self.rtp_initialization_vector(header, roc);
+
+        if is_cryptex(header) {
+            let mut swapped = ciphertext.to_vec();
+            let payload_offset = cryptex_swap_in(&mut swapped, header);
+            let decrypted_msg: Vec<u8> = self.srtp_cipher.decrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: &swapped[payload_offset..],
+                    aad: &swapped[..payload_offset],
+                },
+            )?;
+
+            let mut writer = BytesMut::with_capacity(payload_offset + decrypted_msg.len());
+            writer.extend_from_slice(&swapped[..payload_offset]);
+            writer.extend(decrypted_msg);
+            cryptex_swap_out(&mut writer, header);
+            cryptex_restore_profile(&mut writer, header);
+            return Ok(writer.freeze());
+        }
+
         let payload_offset = header.marshal_size();
         let decrypted_msg: Vec<u8> = self.srtp_cipher.decrypt(
-            Nonce::from_slice(&nonce),
+            GenericArray::from_slice(&nonce),
             Payload {
                 msg: &ciphertext[payload_offset..],
                 aad: &ciphertext[..payload_offset],
@@ -88,7 +124,7 @@ impl Cipher for CipherAeadAesGcm {
         let aad = self.rtcp_additional_authenticated_data(decrypted, srtcp_index);
 
         let encrypted_data = self.srtcp_cipher.encrypt(
-            Nonce::from_slice(&iv),
+            GenericArray::from_slice(&iv),
             Payload {
                 msg: &decrypted[8..],
                 aad: &aad,
@@ -112,7 +148,7 @@ impl Cipher for CipherAeadAesGcm {
         let aad = self.rtcp_additional_authenticated_data(encrypted, srtcp_index);
 
         let decrypted_data = self.srtcp_cipher.decrypt(
-            Nonce::from_slice(&nonce),
+            GenericArray::from_slice(&nonce),
             Payload {
                 msg: &encrypted[8..(encrypted.len() - SRTCP_INDEX_SIZE)],
                 aad: &aad,
@@ -134,9 +170,9 @@ impl Cipher for CipherAeadAesGcm {
     }
 }
 
-impl CipherAeadAesGcm {
+impl<C: Aead + NewAead> CipherAeadAesGcm<C> {
     /// Create a new AEAD instance.
-    pub(crate) fn new(master_key: &[u8], master_salt: &[u8]) -> Result<CipherAeadAesGcm> {
+    pub(crate) fn new(master_key: &[u8], master_salt: &[u8]) -> Result<CipherAeadAesGcm<C>> {
         let srtp_session_key = aes_cm_key_derivation(
             LABEL_SRTP_ENCRYPTION,
             master_key,
@@ -147,7 +183,7 @@ impl CipherAeadAesGcm {
 
         let srtp_block = GenericArray::from_slice(&srtp_session_key);
 
-        let srtp_cipher = Aes128Gcm::new(srtp_block);
+        let srtp_cipher = C::new(srtp_block);
 
         let srtcp_session_key = aes_cm_key_derivation(
             LABEL_SRTCP_ENCRYPTION,
@@ -159,14 +195,14 @@ impl CipherAeadAesGcm {
 
         let srtcp_block = GenericArray::from_slice(&srtcp_session_key);
 
-        let srtcp_cipher = Aes128Gcm::new(srtcp_block);
+        let srtcp_cipher = C::new(srtcp_block);
 
         let srtp_session_salt = aes_cm_key_derivation(
             LABEL_SRTP_SALT,
             master_key,
             master_salt,
             0,
-            master_key.len(),
+            master_salt.len(),
         )?;
 
         let srtcp_session_salt = aes_cm_key_derivation(
@@ -174,7 +210,7 @@ impl CipherAeadAesGcm {
             master_key,
             master_salt,
             0,
-            master_key.len(),
+            master_salt.len(),
         )?;
 
         Ok(CipherAeadAesGcm {
@@ -1,4 +1,4 @@
-use super::Cipher;
+use super::*;
 use crate::error::Result;
 use crate::{error::Error, key_derivation::*, protection_profile::*};
 use util::marshal::*;
@@ -6,7 +6,7 @@ use util::marshal::*;
 use aes::cipher::generic_array::GenericArray;
 use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
 use bytes::{BufMut, Bytes, BytesMut};
-use ctr::cipher::{NewCipher, StreamCipher, StreamCipherSeek};
+use ctr::cipher::{NewCipher, StreamCipher};
 use hmac::{Hmac, Mac, NewMac};
 use sha1::Sha1;
 use std::io::BufWriter;
@@ -14,10 +14,12 @@ use subtle::ConstantTimeEq;
 
 type HmacSha1 = Hmac<Sha1>;
 type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
 
 pub const CIPHER_AES_CM_HMAC_SHA1AUTH_TAG_LEN: usize = 10;
 
 pub(crate) struct CipherAesCmHmacSha1 {
+    profile: ProtectionProfile,
     srtp_session_key: Vec<u8>,
     srtp_session_salt: Vec<u8>,
     srtp_session_auth: HmacSha1,
@@ -29,7 +31,7 @@ pub(crate) struct CipherAesCmHmacSha1 {
 }
 
 impl CipherAesCmHmacSha1 {
-    pub fn new(master_key: &[u8], master_salt: &[u8]) -> Result<Self> {
+    pub fn new(profile: ProtectionProfile, master_key: &[u8], master_salt: &[u8]) -> Result<Self> {
         let srtp_session_key = aes_cm_key_derivation(
             LABEL_SRTP_ENCRYPTION,
             master_key,
@@ -60,7 +62,7 @@ impl CipherAesCmHmacSha1 {
             master_salt.len(),
         )?;
 
-        let auth_key_len = ProtectionProfile::Aes128CmHmacSha1_80.auth_key_len();
+        let auth_key_len = profile.auth_key_len();
 
         let srtp_session_auth_tag = aes_cm_key_derivation(
             LABEL_SRTP_AUTHENTICATION_TAG,
@@ -83,6 +85,7 @@ impl CipherAesCmHmacSha1 {
             .map_err(|e| Error::Other(e.to_string()))?;
 
         Ok(CipherAesCmHmacSha1 {
+            profile,
             srtp_session_key,
             srtp_session_salt,
             srtp_session_auth,
@@ -149,17 +152,34 @@ impl CipherAesCmHmacSha1 {
         let code_bytes = result.into_bytes();
 
         // Truncate the hash to the first AUTH_TAG_SIZE bytes.
-        code_bytes[0..self.auth_tag_len()].to_vec()
+        code_bytes[0..self.rtcp_auth_tag_len()].to_vec()
+    }
+
+    fn rtcp_auth_tag_len(&self) -> usize {
+        self.profile.rtcp_auth_tag_len()
+    }
+
+    /// Encrypts or decrypts buf in place with AES in counter mode, the AES
+    /// variant follows the length of the session key
+    fn apply_keystream(key: &[u8], counter: &[u8], buf: &mut [u8]) {
+        let nonce = GenericArray::from_slice(counter);
+        if key.len() == 32 {
+            let mut stream = Aes256Ctr::new(GenericArray::from_slice(key), nonce);
+            stream.apply_keystream(buf);
+        } else {
+            let mut stream = Aes128Ctr::new(GenericArray::from_slice(key), nonce);
+            stream.apply_keystream(buf);
+        }
     }
 }
 
 impl Cipher for CipherAesCmHmacSha1 {
     fn auth_tag_len(&self) -> usize {
-        CIPHER_AES_CM_HMAC_SHA1AUTH_TAG_LEN
+        self.profile.auth_tag_len()
     }
 
     fn get_rtcp_index(&self, input: &[u8]) -> usize {
-        let tail_offset = input.len() - (self.auth_tag_len() + SRTCP_INDEX_SIZE);
+        let tail_offset = input.len() - (self.rtcp_auth_tag_len() + SRTCP_INDEX_SIZE);
         (BigEndian::read_u32(&input[tail_offset..tail_offset + SRTCP_INDEX_SIZE]) & !(1 << 31))
             as usize
     }
@@ -187,11 +207,20 @@ impl Cipher for CipherAesCmHmacSha1 {
             header.ssrc,
             &self.srtp_session_salt,
         )?;
-        let key = GenericArray::from_slice(&self.srtp_session_key);
-        let nonce = GenericArray::from_slice(&counter);
-        let mut stream = Aes128Ctr::new(key, nonce);
-        let payload_offset = header.marshal_size();
-        stream.apply_keystream(&mut writer[payload_offset..]);
+        let cryptex = is_cryptex(header);
+        let payload_offset = if cryptex {
+            cryptex_swap_in(&mut writer, header)
+        } else {
+            header.marshal_size()
+        };
+        Self::apply_keystream(
+            &self.srtp_session_key,
+            &counter,
+            &mut writer[payload_offset..],
+        );
+        if cryptex {
+            cryptex_swap_out(&mut writer, header);
+        }
 
         // Generate the auth tag.
         let auth_tag = self.generate_srtp_auth_tag(&writer, roc)?;
@@ -236,19 +265,28 @@ impl Cipher for CipherAesCmHmacSha1 {
             &self.srtp_session_salt,
         )?;
 
-        let key = GenericArray::from_slice(&self.srtp_session_key);
-        let nonce = GenericArray::from_slice(&counter);
-        let mut stream = Aes128Ctr::new(key, nonce);
-        let payload_offset = header.marshal_size();
-        stream.seek(0);
-        stream.apply_keystream(&mut writer[payload_offset..]);
+        let cryptex = is_cryptex(header);
+        let payload_offset = if cryptex {
+            cryptex_swap_in(&mut writer, header)
+        } else {
+            header.marshal_size()
+        };
+        Self::apply_keystream(
+            &self.srtp_session_key,
+            &counter,
+            &mut writer[payload_offset..],
+        );
+        if cryptex {
+            cryptex_swap_out(&mut writer, header);
+            cryptex_restore_profile(&mut writer, header);
+        }
 
         Ok(writer.freeze())
     }
 
     fn encrypt_rtcp(&mut self, decrypted: &[u8], srtcp_index: usize, ssrc: u32) -> Result<Bytes> {
         let mut writer =
-            BytesMut::with_capacity(decrypted.len() + SRTCP_INDEX_SIZE + self.auth_tag_len());
+            BytesMut::with_capacity(decrypted.len() + SRTCP_INDEX_SIZE + self.rtcp_auth_tag_len());
 
         // Write the decrypted to the destination buffer.
         writer.extend_from_slice(decrypted);
@@ -261,11 +299,9 @@ impl Cipher for CipherAesCmHmacSha1 {
             &self.srtcp_session_salt,
         )?;
 
-        let key = GenericArray::from_slice(&self.srtcp_session_key);
-        let nonce = GenericArray::from_slice(&counter);
-        let mut stream = Aes128Ctr::new(key, nonce);
-
-        stream.apply_keystream(
+        Self::apply_keystream(
+            &self.srtcp_session_key,
+            &counter,
             &mut writer[rtcp::header::HEADER_LENGTH + rtcp::header::SSRC_LENGTH..],
         );
 
@@ -280,14 +316,14 @@ impl Cipher for CipherAesCmHmacSha1 {
     }
 
     fn decrypt_rtcp(&mut self, encrypted: &[u8], srtcp_index: usize, ssrc: u32) -> Result<Bytes> {
-        if encrypted.len() < self.auth_tag_len() + SRTCP_INDEX_SIZE {
+        if encrypted.len() < self.rtcp_auth_tag_len() + SRTCP_INDEX_SIZE {
             return Err(Error::SrtcpTooSmall(
                 encrypted.len(),
-                self.auth_tag_len() + SRTCP_INDEX_SIZE,
+                self.rtcp_auth_tag_len() + SRTCP_INDEX_SIZE,
             ));
         }
 
-        let tail_offset = encrypted.len() - (self.auth_tag_len() + SRTCP_INDEX_SIZE);
+        let tail_offset = encrypted.len() - (self.rtcp_auth_tag_len() + SRTCP_INDEX_SIZE);
 
         let mut writer = BytesMut::with_capacity(tail_offset);
 
@@ -299,8 +335,8 @@ impl Cipher for CipherAesCmHmacSha1 {
         }
 
         // Split the auth tag and the cipher text into two parts.
-        let actual_tag = &encrypted[encrypted.len() - self.auth_tag_len()..];
-        let cipher_text = &encrypted[..encrypted.len() - self.auth_tag_len()];
+        let actual_tag = &encrypted[encrypted.len() - self.rtcp_auth_tag_len()..];
+        let cipher_text = &encrypted[..encrypted.len() - self.rtcp_auth_tag_len()];
 
         // Generate the auth tag we expect to see from the ciphertext.
         let expected_tag = self.generate_srtcp_auth_tag(cipher_text);
@@ -318,12 +354,9 @@ impl Cipher for CipherAesCmHmacSha1 {
             &self.srtcp_session_salt,
         )?;
 
-        let key = GenericArray::from_slice(&self.srtcp_session_key);
-        let nonce = GenericArray::from_slice(&counter);
-        let mut stream = Aes128Ctr::new(key, nonce);
-
-        stream.seek(0);
-        stream.apply_keystream(
+        Self::apply_keystream(
+            &self.srtcp_session_key,
+            &counter,
             &mut writer[rtcp::header::HEADER_LENGTH + rtcp::header::SSRC_LENGTH..],
         );
 
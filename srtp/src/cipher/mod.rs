@@ -2,9 +2,21 @@ pub mod cipher_aead_aes_gcm;
 pub mod cipher_aes_cm_hmac_sha1;
 
 use bytes::Bytes;
+use rtp::header::{EXTENSION_PROFILE_ONE_BYTE, EXTENSION_PROFILE_TWO_BYTE};
+use util::marshal::*;
 
 use crate::error::Result;
 
+/// "defined by profile" values of a header extension encrypted with cryptex,
+/// they replace the RFC 8285 one-byte and two-byte values on the wire
+/// https://www.rfc-editor.org/rfc/rfc9335#section-5.1
+pub(crate) const CRYPTEX_PROFILE_ONE_BYTE: u16 = 0xC0DE;
+pub(crate) const CRYPTEX_PROFILE_TWO_BYTE: u16 = 0xC2DE;
+
+/// Offset of the encrypted portion of a cryptex packet once the header
+/// extension preamble is moved ahead of the CSRC list
+const CRYPTEX_ENCRYPTED_OFFSET: usize = rtp::header::CSRC_OFFSET + 4;
+
 ///NOTE: Auth tag and AEAD auth tag are placed at the different position in SRTCP
 ///
 ///In non-AEAD cipher, the authentication tag is placed *after* the ESRTCP word
@@ -59,3 +71,75 @@ pub(crate) trait Cipher {
     /// Decrypt RTCP payload.
     fn decrypt_rtcp(&mut self, payload: &[u8], srtcp_index: usize, ssrc: u32) -> Result<Bytes>;
 }
+
+/// is_cryptex tells whether the CSRC list and header extension of the packet
+/// are encrypted with cryptex
+pub(crate) fn is_cryptex(header: &rtp::header::Header) -> bool {
+    header.extension
+        && (header.extension_profile == CRYPTEX_PROFILE_ONE_BYTE
+            || header.extension_profile == CRYPTEX_PROFILE_TWO_BYTE)
+}
+
+/// cryptex_header returns the header to send for a packet encrypted with
+/// cryptex: the RFC 8285 header extension becomes an opaque block marked with
+/// the cryptex profile, and a packet with CSRCs but without header extension
+/// gets an empty one. None is returned if there is nothing cryptex can
+/// protect, or for RFC 3550 header extensions which it does not cover.
+pub(crate) fn cryptex_header(header: &rtp::header::Header) -> Result<Option<rtp::header::Header>> {
+    let mut cryptex = header.clone();
+    if header.extension {
+        cryptex.extension_profile = match header.extension_profile {
+            EXTENSION_PROFILE_ONE_BYTE => CRYPTEX_PROFILE_ONE_BYTE,
+            EXTENSION_PROFILE_TWO_BYTE => CRYPTEX_PROFILE_TWO_BYTE,
+            _ => return Ok(None),
+        };
+
+        let raw = header.marshal()?;
+        let extension_offset =
+            rtp::header::CSRC_OFFSET + header.csrc.len() * rtp::header::CSRC_LENGTH + 4;
+        cryptex.extensions = vec![rtp::header::Extension {
+            id: 0,
+            payload: raw.slice(extension_offset..),
+        }];
+    } else if !header.csrc.is_empty() {
+        cryptex.extension = true;
+        cryptex.extension_profile = CRYPTEX_PROFILE_ONE_BYTE;
+        cryptex.extensions = vec![rtp::header::Extension {
+            id: 0,
+            payload: Bytes::new(),
+        }];
+    } else {
+        return Ok(None);
+    }
+
+    Ok(Some(cryptex))
+}
+
+/// cryptex_swap_in moves the header extension preamble of a cryptex packet
+/// ahead of the CSRC list, so the CSRCs, the header extension and the payload
+/// are encrypted as one range starting at the returned offset
+/// https://www.rfc-editor.org/rfc/rfc9335#section-5.1
+pub(crate) fn cryptex_swap_in(buf: &mut [u8], header: &rtp::header::Header) -> usize {
+    let csrc_len = header.csrc.len() * rtp::header::CSRC_LENGTH;
+    buf[rtp::header::CSRC_OFFSET..CRYPTEX_ENCRYPTED_OFFSET + csrc_len].rotate_right(4);
+    CRYPTEX_ENCRYPTED_OFFSET
+}
+
+/// cryptex_swap_out restores the order of the CSRC list and the header
+/// extension preamble changed by cryptex_swap_in
+pub(crate) fn cryptex_swap_out(buf: &mut [u8], header: &rtp::header::Header) {
+    let csrc_len = header.csrc.len() * rtp::header::CSRC_LENGTH;
+    buf[rtp::header::CSRC_OFFSET..CRYPTEX_ENCRYPTED_OFFSET + csrc_len].rotate_left(4);
+}
+
+/// cryptex_restore_profile puts back the RFC 8285 "defined by profile" value
+/// in a decrypted cryptex packet
+pub(crate) fn cryptex_restore_profile(buf: &mut [u8], header: &rtp::header::Header) {
+    let offset = rtp::header::CSRC_OFFSET + header.csrc.len() * rtp::header::CSRC_LENGTH;
+    let profile = if header.extension_profile == CRYPTEX_PROFILE_TWO_BYTE {
+        EXTENSION_PROFILE_TWO_BYTE
+    } else {
+        EXTENSION_PROFILE_ONE_BYTE
+    };
+    buf[offset..offset + 2].copy_from_slice(&profile.to_be_bytes());
+}
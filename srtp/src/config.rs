@@ -1,4 +1,4 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::{option::*, protection_profile::*};
 use util::KeyingMaterialExporter;
 
@@ -78,4 +78,30 @@ impl Config {
 
         Ok(())
     }
+
+    /// extract_session_keys_from_sdes sets the Config SessionKeys from the
+    /// inline keys of SDES crypto attributes, each of them is the master key
+    /// followed by the master salt. This behavior is defined in RFC4568:
+    /// https://tools.ietf.org/html/rfc4568#section-6.1
+    pub fn extract_session_keys_from_sdes(
+        &mut self,
+        local_key_salt: &[u8],
+        remote_key_salt: &[u8],
+    ) -> Result<()> {
+        let key_len = self.profile.key_len();
+        let key_salt_len = key_len + self.profile.salt_len();
+
+        for key_salt in [local_key_salt, remote_key_salt] {
+            if key_salt.len() != key_salt_len {
+                return Err(Error::SdesKeyLength(key_salt_len, key_salt.len()));
+            }
+        }
+
+        self.keys.local_master_key = local_key_salt[..key_len].to_vec();
+        self.keys.local_master_salt = local_key_salt[key_len..].to_vec();
+        self.keys.remote_master_key = remote_key_salt[..key_len].to_vec();
+        self.keys.remote_master_salt = remote_key_salt[key_len..].to_vec();
+
+        Ok(())
+    }
 }
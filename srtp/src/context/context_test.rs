@@ -1,5 +1,6 @@
 use super::*;
 use crate::key_derivation::*;
+use util::marshal::*;
 
 use bytes::Bytes;
 use lazy_static::lazy_static;
@@ -305,3 +306,448 @@ fn test_decrypt_rtcp() {
 
     assert_eq!(gotten_decrypted_rtcp_packet, *DECRYPTED_RTCP_PACKET)
 }
+
+fn new_test_context(profile: ProtectionProfile) -> Result<Context> {
+    let master_key: Vec<u8> = (0..profile.key_len() as u8).collect();
+    let master_salt: Vec<u8> = (0x40..0x40 + profile.salt_len() as u8).collect();
+
+    Context::new(&master_key, &master_salt, profile, None, None)
+}
+
+#[test]
+fn test_protection_profiles() -> Result<()> {
+    let profiles = vec![
+        ProtectionProfile::Aes128CmHmacSha1_80,
+        ProtectionProfile::Aes128CmHmacSha1_32,
+        ProtectionProfile::Aes256CmHmacSha1_80,
+        ProtectionProfile::Aes256CmHmacSha1_32,
+        ProtectionProfile::AeadAes128Gcm,
+        ProtectionProfile::AeadAes256Gcm,
+    ];
+
+    for profile in profiles {
+        let mut encrypt_context = new_test_context(profile)?;
+        let mut decrypt_context = new_test_context(profile)?;
+
+        let encrypted_rtp = encrypt_context.encrypt_rtp(&DECRYPTED_RTP_PACKET)?;
+        assert_eq!(
+            DECRYPTED_RTP_PACKET.len() + profile.auth_tag_len(),
+            encrypted_rtp.len(),
+            "{:?} SRTP auth tag length",
+            profile
+        );
+        let decrypted_rtp = decrypt_context.decrypt_rtp(&encrypted_rtp)?;
+        assert_eq!(*DECRYPTED_RTP_PACKET, decrypted_rtp, "{:?} SRTP", profile);
+
+        let encrypted_rtcp = encrypt_context.encrypt_rtcp(&DECRYPTED_RTCP_PACKET)?;
+        assert_eq!(
+            DECRYPTED_RTCP_PACKET.len() + SRTCP_INDEX_SIZE + profile.rtcp_auth_tag_len(),
+            encrypted_rtcp.len(),
+            "{:?} SRTCP auth tag length",
+            profile
+        );
+        let decrypted_rtcp = decrypt_context.decrypt_rtcp(&encrypted_rtcp)?;
+        assert_eq!(
+            *DECRYPTED_RTCP_PACKET, decrypted_rtcp,
+            "{:?} SRTCP",
+            profile
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_truncated_auth_tag() -> Result<()> {
+    // The 32-bit tag profiles only truncate the SRTP tag, SRTCP is unchanged
+    for (full, truncated) in [
+        (
+            ProtectionProfile::Aes128CmHmacSha1_80,
+            ProtectionProfile::Aes128CmHmacSha1_32,
+        ),
+        (
+            ProtectionProfile::Aes256CmHmacSha1_80,
+            ProtectionProfile::Aes256CmHmacSha1_32,
+        ),
+    ] {
+        let rtp_full = new_test_context(full)?.encrypt_rtp(&DECRYPTED_RTP_PACKET)?;
+        let rtp_truncated = new_test_context(truncated)?.encrypt_rtp(&DECRYPTED_RTP_PACKET)?;
+        assert_eq!(
+            rtp_full[..rtp_truncated.len()],
+            rtp_truncated[..],
+            "{:?} SRTP",
+            truncated
+        );
+
+        let rtcp_full = new_test_context(full)?.encrypt_rtcp(&DECRYPTED_RTCP_PACKET)?;
+        let rtcp_truncated = new_test_context(truncated)?.encrypt_rtcp(&DECRYPTED_RTCP_PACKET)?;
+        assert_eq!(rtcp_full, rtcp_truncated, "{:?} SRTCP", truncated);
+    }
+
+    Ok(())
+}
+
+fn new_cryptex_test_packet(
+    csrc: Vec<u32>,
+    extension_profile: u16,
+    extensions: Vec<rtp::header::Extension>,
+) -> Bytes {
+    let pkt = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            extension: !extensions.is_empty(),
+            payload_type: 96,
+            sequence_number: 5000,
+            timestamp: 3653407706,
+            ssrc: 0xcafebabe,
+            csrc,
+            extension_profile,
+            extensions,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x01, 0x02, 0x03, 0x04, 0x05]),
+    };
+    pkt.marshal().unwrap()
+}
+
+#[test]
+fn test_cryptex() -> Result<()> {
+    let audio_level = rtp::header::Extension {
+        id: 1,
+        payload: Bytes::from_static(&[0xaa]),
+    };
+    let mid = rtp::header::Extension {
+        id: 2,
+        payload: Bytes::from_static(&[0xbb, 0xcc, 0xdd]),
+    };
+
+    let profiles = vec![
+        ProtectionProfile::Aes128CmHmacSha1_80,
+        ProtectionProfile::Aes256CmHmacSha1_32,
+        ProtectionProfile::AeadAes128Gcm,
+        ProtectionProfile::AeadAes256Gcm,
+    ];
+
+    for profile in profiles {
+        for (name, csrc, extension_profile, cryptex_profile) in [
+            (
+                "one-byte",
+                vec![],
+                rtp::header::EXTENSION_PROFILE_ONE_BYTE,
+                0xC0DE,
+            ),
+            (
+                "one-byte with CSRCs",
+                vec![0x11111111, 0x22222222],
+                rtp::header::EXTENSION_PROFILE_ONE_BYTE,
+                0xC0DE,
+            ),
+            (
+                "two-byte with CSRCs",
+                vec![0x11111111],
+                rtp::header::EXTENSION_PROFILE_TWO_BYTE,
+                0xC2DE,
+            ),
+        ] {
+            let decrypted = new_cryptex_test_packet(
+                csrc.clone(),
+                extension_profile,
+                vec![audio_level.clone(), mid.clone()],
+            );
+
+            let mut encrypt_context = new_test_context(profile)?;
+            encrypt_context.set_cryptex(true);
+            let encrypted = encrypt_context.encrypt_rtp(&decrypted)?;
+
+            // The header extension is sent as an opaque cryptex block
+            let header = rtp::header::Header::unmarshal(&mut encrypted.clone())?;
+            assert_eq!(
+                header.extension_profile, cryptex_profile,
+                "{:?} {} profile",
+                profile, name
+            );
+            if !csrc.is_empty() {
+                assert_ne!(header.csrc, csrc, "{:?} {} CSRCs", profile, name);
+            }
+            let header_len = header.marshal_size();
+            assert_ne!(
+                encrypted[header_len - 8..header_len],
+                decrypted[header_len - 8..header_len],
+                "{:?} {} header extension",
+                profile,
+                name
+            );
+
+            // The receiver does not need to be told, the profile marks cryptex
+            let mut decrypt_context = new_test_context(profile)?;
+            let got = decrypt_context.decrypt_rtp(&encrypted)?;
+            assert_eq!(got, decrypted, "{:?} {}", profile, name);
+        }
+
+        // Cryptex adds an empty header extension to protect the CSRCs
+        let decrypted = new_cryptex_test_packet(vec![0x11111111], 0, vec![]);
+        let mut encrypt_context = new_test_context(profile)?;
+        encrypt_context.set_cryptex(true);
+        let encrypted = encrypt_context.encrypt_rtp(&decrypted)?;
+        let header = rtp::header::Header::unmarshal(&mut encrypted.clone())?;
+        assert!(header.extension, "{:?} empty header extension", profile);
+        assert_eq!(header.extension_profile, 0xC0DE);
+        assert_ne!(header.csrc, vec![0x11111111]);
+
+        let mut decrypt_context = new_test_context(profile)?;
+        let got = decrypt_context.decrypt_rtp(&encrypted)?;
+        let header = rtp::header::Header::unmarshal(&mut got.clone())?;
+        assert_eq!(header.csrc, vec![0x11111111]);
+        assert_eq!(
+            header.extension_profile,
+            rtp::header::EXTENSION_PROFILE_ONE_BYTE
+        );
+        assert!(header.extensions.is_empty());
+        assert_eq!(got[header.marshal_size()..], decrypted[16..]);
+    }
+
+    Ok(())
+}
+
+// (name, decrypted, encrypted) packets of RFC 9335 Appendix A
+type CryptexVector = (&'static str, &'static [u8], &'static [u8]);
+
+// https://www.rfc-editor.org/rfc/rfc9335#appendix-A.1
+const CRYPTEX_AES_CM_VECTORS: &[CryptexVector] = &[
+    (
+        "one-byte header extension",
+        &[
+            0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xbe, 0xde,
+            0x00, 0x01, 0x51, 0x00, 0x02, 0x00, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc0, 0xde,
+            0x00, 0x01, 0xeb, 0x92, 0x36, 0x52, 0x51, 0xc3, 0xe0, 0x36, 0xf8, 0xde, 0x27, 0xe9,
+            0xc2, 0x7e, 0xe3, 0xe0, 0xb4, 0x65, 0x1d, 0x9f, 0xbc, 0x42, 0x18, 0xa7, 0x02, 0x44,
+            0x52, 0x2f, 0x34, 0xa5,
+        ],
+    ),
+    (
+        "two-byte header extension",
+        &[
+            0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x10, 0x00,
+            0x00, 0x01, 0x05, 0x02, 0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc2, 0xde,
+            0x00, 0x01, 0x4e, 0xd9, 0xcc, 0x4e, 0x6a, 0x71, 0x2b, 0x30, 0x96, 0xc5, 0xca, 0x77,
+            0x33, 0x9d, 0x42, 0x04, 0xce, 0x0d, 0x77, 0x39, 0x6c, 0xab, 0x69, 0x58, 0x5f, 0xbc,
+            0xe3, 0x81, 0x94, 0xa5,
+        ],
+    ),
+    (
+        "one-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x01, 0x51, 0x00, 0x02, 0x00,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x8b, 0xb6,
+            0xe1, 0x2b, 0x5c, 0xff, 0x16, 0xdd, 0xc0, 0xde, 0x00, 0x01, 0x92, 0x83, 0x8c, 0x8c,
+            0x09, 0xe5, 0x83, 0x93, 0xe1, 0xde, 0x3a, 0x9a, 0x74, 0x73, 0x4d, 0x67, 0x45, 0x67,
+            0x13, 0x38, 0xc3, 0xac, 0xf1, 0x1d, 0xa2, 0xdf, 0x84, 0x23, 0xbe, 0xe0,
+        ],
+    ),
+    (
+        "two-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x01, 0x05, 0x02, 0x00, 0x02,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xf7, 0x0e,
+            0x51, 0x3e, 0xb9, 0x0b, 0x9b, 0x25, 0xc2, 0xde, 0x00, 0x01, 0xbb, 0xed, 0x48, 0x48,
+            0xfa, 0xa6, 0x44, 0x66, 0x5f, 0x3d, 0x7f, 0x34, 0x12, 0x59, 0x14, 0xe9, 0xf4, 0xd0,
+            0xae, 0x92, 0x3c, 0x6f, 0x47, 0x9b, 0x95, 0xa0, 0xf7, 0xb5, 0x31, 0x33,
+        ],
+    ),
+    (
+        "empty one-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x00, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x71, 0x30,
+            0xb6, 0xab, 0xfe, 0x2a, 0xb0, 0xe3, 0xc0, 0xde, 0x00, 0x00, 0xe3, 0xd9, 0xf6, 0x4b,
+            0x25, 0xc9, 0xe7, 0x4c, 0xb4, 0xcf, 0x8e, 0x43, 0xfb, 0x92, 0xe3, 0x78, 0x1c, 0x2c,
+            0x0c, 0xea, 0xb6, 0xb3, 0xa4, 0x99, 0xa1, 0x4c,
+        ],
+    ),
+    (
+        "empty two-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x00, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xcb, 0xf2,
+            0x4c, 0x12, 0x43, 0x30, 0xe1, 0xc8, 0xc2, 0xde, 0x00, 0x00, 0x59, 0x9d, 0xd4, 0x5b,
+            0xc9, 0xd6, 0x87, 0xb6, 0x03, 0xe8, 0xb5, 0x9d, 0x77, 0x1f, 0xd3, 0x8e, 0x88, 0xb1,
+            0x70, 0xe0, 0xcd, 0x31, 0xe1, 0x25, 0xea, 0xbe,
+        ],
+    ),
+];
+
+// https://www.rfc-editor.org/rfc/rfc9335#appendix-A.2
+const CRYPTEX_AEAD_VECTORS: &[CryptexVector] = &[
+    (
+        "one-byte header extension",
+        &[
+            0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xbe, 0xde,
+            0x00, 0x01, 0x51, 0x00, 0x02, 0x00, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc0, 0xde,
+            0x00, 0x01, 0x39, 0x97, 0x2d, 0xc9, 0x57, 0x2c, 0x4d, 0x99, 0xe8, 0xfc, 0x35, 0x5d,
+            0xe7, 0x43, 0xfb, 0x2e, 0x94, 0xf9, 0xd8, 0xff, 0x54, 0xe7, 0x2f, 0x41, 0x93, 0xbb,
+            0xc5, 0xc7, 0x4f, 0xfa, 0xb0, 0xfa, 0x9f, 0xa0, 0xfb, 0xeb,
+        ],
+    ),
+    (
+        "two-byte header extension",
+        &[
+            0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x10, 0x00,
+            0x00, 0x01, 0x05, 0x02, 0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc2, 0xde,
+            0x00, 0x01, 0xbb, 0x75, 0xa4, 0xc5, 0x45, 0xcd, 0x1f, 0x41, 0x3b, 0xdb, 0x7d, 0xaa,
+            0x2b, 0x1e, 0x32, 0x63, 0xde, 0x31, 0x36, 0x67, 0xc9, 0x63, 0x24, 0x90, 0x81, 0xb3,
+            0x5a, 0x65, 0xf5, 0xcb, 0x6c, 0x88, 0xb3, 0x94, 0x23, 0x5f,
+        ],
+    ),
+    (
+        "one-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x01, 0x51, 0x00, 0x02, 0x00,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x63, 0xbb,
+            0xcc, 0xc4, 0xa7, 0xf6, 0x95, 0xc4, 0xc0, 0xde, 0x00, 0x01, 0x8a, 0xd7, 0xc7, 0x1f,
+            0xac, 0x70, 0xa8, 0x0c, 0x92, 0x86, 0x6b, 0x4c, 0x6b, 0xa9, 0x85, 0x46, 0xef, 0x91,
+            0x35, 0x86, 0xe9, 0x5f, 0xfa, 0xaf, 0xfe, 0x95, 0x68, 0x85, 0xbb, 0x06, 0x47, 0xa8,
+            0xbc, 0x09, 0x4a, 0xc8,
+        ],
+    ),
+    (
+        "two-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x01, 0x05, 0x02, 0x00, 0x02,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x36, 0x80,
+            0x52, 0x4f, 0x8d, 0x31, 0x2b, 0x00, 0xc2, 0xde, 0x00, 0x01, 0xc7, 0x8d, 0x12, 0x00,
+            0x38, 0x42, 0x2b, 0xc1, 0x11, 0xa7, 0x18, 0x7a, 0x18, 0x24, 0x6f, 0x98, 0x0c, 0x05,
+            0x9c, 0xc6, 0xbc, 0x9d, 0xf8, 0xb6, 0x26, 0x39, 0x4e, 0xca, 0x34, 0x4e, 0x4b, 0x05,
+            0xd8, 0x0f, 0xea, 0x83,
+        ],
+    ),
+    (
+        "empty one-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x00, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x15, 0xb6,
+            0xbb, 0x43, 0x37, 0x90, 0x6f, 0xff, 0xc0, 0xde, 0x00, 0x00, 0xb7, 0xb9, 0x64, 0x53,
+            0x7a, 0x2b, 0x03, 0xab, 0x7b, 0xa5, 0x38, 0x9c, 0xe9, 0x33, 0x17, 0x12, 0x6b, 0x5d,
+            0x97, 0x4d, 0xf3, 0x0c, 0x68, 0x84, 0xdc, 0xb6, 0x51, 0xc5, 0xe1, 0x20, 0xc1, 0xda,
+        ],
+    ),
+    (
+        "empty two-byte header extension and CSRCs",
+        &[
+            0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00, 0x01,
+            0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x00, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ],
+        &[
+            0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xdc, 0xb3,
+            0x8c, 0x9e, 0x48, 0xbf, 0x95, 0xf4, 0xc2, 0xde, 0x00, 0x00, 0x61, 0xee, 0x43, 0x2c,
+            0xf9, 0x20, 0x31, 0x70, 0x76, 0x61, 0x32, 0x58, 0xd3, 0xce, 0x42, 0x36, 0xc0, 0x6a,
+            0xc4, 0x29, 0x68, 0x1a, 0xd0, 0x84, 0x13, 0x51, 0x2d, 0xc9, 0x8b, 0x52, 0x07, 0xd8,
+        ],
+    ),
+];
+
+#[test]
+fn test_cryptex_vectors() -> Result<()> {
+    let aes_cm_master_key = [
+        0xe1, 0xf9, 0x7a, 0x0d, 0x3e, 0x01, 0x8b, 0xe0, 0xd6, 0x4f, 0xa3, 0x2c, 0x06, 0xde, 0x41,
+        0x39,
+    ];
+    let aes_cm_master_salt = [
+        0x0e, 0xc6, 0x75, 0xad, 0x49, 0x8a, 0xfe, 0xeb, 0xb6, 0x96, 0x0b, 0x3a, 0xab, 0xe6,
+    ];
+
+    for (profile, master_key, master_salt, vectors) in [
+        (
+            ProtectionProfile::Aes128CmHmacSha1_80,
+            &aes_cm_master_key[..],
+            &aes_cm_master_salt[..],
+            CRYPTEX_AES_CM_VECTORS,
+        ),
+        (
+            ProtectionProfile::AeadAes128Gcm,
+            &MASTER_KEY[..],
+            &MASTER_SALT[..],
+            CRYPTEX_AEAD_VECTORS,
+        ),
+    ] {
+        for (name, decrypted, encrypted) in vectors {
+            let mut encrypt_context = Context::new(master_key, master_salt, profile, None, None)?;
+            encrypt_context.set_cryptex(true);
+            let got = encrypt_context.encrypt_rtp(decrypted)?;
+            assert_eq!(&got[..], *encrypted, "{:?} {} encrypted", profile, name);
+
+            let mut decrypt_context = Context::new(master_key, master_salt, profile, None, None)?;
+            let got = decrypt_context.decrypt_rtp(encrypted)?;
+            assert_eq!(&got[..], *decrypted, "{:?} {} decrypted", profile, name);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cryptex_disabled() -> Result<()> {
+    let decrypted = new_cryptex_test_packet(
+        vec![0x11111111],
+        rtp::header::EXTENSION_PROFILE_ONE_BYTE,
+        vec![rtp::header::Extension {
+            id: 1,
+            payload: Bytes::from_static(&[0xaa]),
+        }],
+    );
+
+    let encrypted = new_test_context(CIPHER_CONTEXT_ALGO)?.encrypt_rtp(&decrypted)?;
+    let header = rtp::header::Header::unmarshal(&mut encrypted.clone())?;
+    let header_len = header.marshal_size();
+    assert_eq!(encrypted[..header_len], decrypted[..header_len]);
+
+    Ok(())
+}
@@ -11,6 +11,7 @@ use crate::{
     option::*, protection_profile::*,
 };
 
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
 use std::collections::HashMap;
 use util::replay_detector::*;
 
@@ -107,6 +108,8 @@ pub struct Context {
 
     new_srtp_replay_detector: ContextOption,
     new_srtcp_replay_detector: ContextOption,
+
+    cryptex: bool,
 }
 
 impl Context {
@@ -128,12 +131,18 @@ impl Context {
         }
 
         let cipher: Box<dyn Cipher + Send> = match profile {
-            ProtectionProfile::Aes128CmHmacSha1_80 => {
-                Box::new(CipherAesCmHmacSha1::new(master_key, master_salt)?)
+            ProtectionProfile::Aes128CmHmacSha1_80
+            | ProtectionProfile::Aes128CmHmacSha1_32
+            | ProtectionProfile::Aes256CmHmacSha1_80
+            | ProtectionProfile::Aes256CmHmacSha1_32 => {
+                Box::new(CipherAesCmHmacSha1::new(profile, master_key, master_salt)?)
             }
 
             ProtectionProfile::AeadAes128Gcm => {
-                Box::new(CipherAeadAesGcm::new(master_key, master_salt)?)
+                Box::new(CipherAeadAesGcm::<Aes128Gcm>::new(master_key, master_salt)?)
+            }
+            ProtectionProfile::AeadAes256Gcm => {
+                Box::new(CipherAeadAesGcm::<Aes256Gcm>::new(master_key, master_salt)?)
             }
         };
 
@@ -155,9 +164,18 @@ impl Context {
             srtcp_ssrc_states: HashMap::new(),
             new_srtp_replay_detector: srtp_ctx_opt,
             new_srtcp_replay_detector: srtcp_ctx_opt,
+            cryptex: false,
         })
     }
 
+    /// set_cryptex enables the encryption of the CSRC list and the header
+    /// extensions of outgoing RTP packets with cryptex. Incoming packets
+    /// encrypted with cryptex are always decrypted.
+    /// https://www.rfc-editor.org/rfc/rfc9335
+    pub fn set_cryptex(&mut self, cryptex: bool) {
+        self.cryptex = cryptex;
+    }
+
     fn get_srtp_ssrc_state(&mut self, ssrc: u32) -> Option<&mut SrtpSsrcState> {
         let s = SrtpSsrcState {
             ssrc,
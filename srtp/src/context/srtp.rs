@@ -62,9 +62,16 @@ impl Context {
             }
         }
 
-        let dst = self
-            .cipher
-            .encrypt_rtp(&plaintext[header.marshal_size()..], header, roc)?;
+        let payload = &plaintext[header.marshal_size()..];
+        let cryptex_header = if self.cryptex {
+            cryptex_header(header)?
+        } else {
+            None
+        };
+        let dst = match &cryptex_header {
+            Some(cryptex_header) => self.cipher.encrypt_rtp(payload, cryptex_header, roc)?,
+            None => self.cipher.encrypt_rtp(payload, header, roc)?,
+        };
 
         {
             if let Some(state) = self.get_srtp_ssrc_state(header.ssrc) {
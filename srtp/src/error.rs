@@ -46,6 +46,8 @@ pub enum Error {
     SrtpMasterKeyLength(usize, usize),
     #[error("SRTP Salt must be len {0}, got {1}")]
     SrtpSaltLength(usize, usize),
+    #[error("SDES inline key must be len {0}, got {1}")]
+    SdesKeyLength(usize, usize),
     #[error("SyntaxError: {0}")]
     ExtMapParse(String),
     #[error("ssrc {0} not exist in srtp_ssrc_state")]
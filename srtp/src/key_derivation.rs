@@ -1,6 +1,6 @@
 use aes::cipher::generic_array::GenericArray;
 use aes::cipher::NewBlockCipher;
-use aes::{Aes128, BlockEncrypt};
+use aes::{Aes128, Aes256, BlockEncrypt};
 
 use byteorder::{BigEndian, WriteBytesExt};
 use std::io::BufWriter;
@@ -16,6 +16,9 @@ pub const LABEL_SRTCP_SALT: u8 = 0x05;
 
 pub(crate) const SRTCP_INDEX_SIZE: usize = 4;
 
+const AES_BLOCK_SIZE: usize = 16;
+const AES_256_KEY_LEN: usize = 32;
+
 pub(crate) fn aes_cm_key_derivation(
     label: u8,
     master_key: &[u8],
@@ -33,30 +36,43 @@ pub(crate) fn aes_cm_key_derivation(
     // concatenation of the encryption key label 0x00 with (index DIV kdr),
     // - index is 'rollover count' and DIV is 'divided by'
 
-    let n_master_key = master_key.len();
     let n_master_salt = master_salt.len();
 
-    let mut prf_in = vec![0u8; n_master_key];
+    let mut prf_in = vec![0u8; AES_BLOCK_SIZE];
     prf_in[..n_master_salt].copy_from_slice(master_salt);
 
     prf_in[7] ^= label;
 
     //The resulting value is then AES encrypted using the master key to get the cipher key.
+    //AES-256 master keys use AES-256 in the same way https://tools.ietf.org/html/rfc6188#section-3
+    if master_key.len() == AES_256_KEY_LEN {
+        Ok(aes_cm_prf::<Aes256>(master_key, prf_in, out_len))
+    } else {
+        Ok(aes_cm_prf::<Aes128>(master_key, prf_in, out_len))
+    }
+}
+
+fn aes_cm_prf<C: NewBlockCipher + BlockEncrypt>(
+    master_key: &[u8],
+    mut prf_in: Vec<u8>,
+    out_len: usize,
+) -> Vec<u8> {
     let key = GenericArray::from_slice(master_key);
-    let block = Aes128::new(key);
+    let block = C::new(key);
 
-    let mut out = vec![0u8; ((out_len + n_master_key) / n_master_key) * n_master_key];
-    for (i, n) in (0..out_len).step_by(n_master_key).enumerate() {
+    let mut out = vec![0u8; ((out_len + AES_BLOCK_SIZE) / AES_BLOCK_SIZE) * AES_BLOCK_SIZE];
+    for (i, n) in (0..out_len).step_by(AES_BLOCK_SIZE).enumerate() {
         //BigEndian.PutUint16(prfIn[nMasterKey-2:], i)
-        prf_in[n_master_key - 2] = ((i >> 8) & 0xFF) as u8;
-        prf_in[n_master_key - 1] = (i & 0xFF) as u8;
+        prf_in[AES_BLOCK_SIZE - 2] = ((i >> 8) & 0xFF) as u8;
+        prf_in[AES_BLOCK_SIZE - 1] = (i & 0xFF) as u8;
 
-        out[n..n + n_master_key].copy_from_slice(&prf_in);
-        let out_key = GenericArray::from_mut_slice(&mut out[n..n + n_master_key]);
+        out[n..n + AES_BLOCK_SIZE].copy_from_slice(&prf_in);
+        let out_key = GenericArray::from_mut_slice(&mut out[n..n + AES_BLOCK_SIZE]);
         block.encrypt_block(out_key);
     }
 
-    Ok(out[..out_len].to_vec())
+    out.truncate(out_len);
+    out
 }
 
 /// Generate IV https://tools.ietf.org/html/rfc3711#section-4.1.1
@@ -164,6 +180,64 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_valid_session_keys_aes_256() -> Result<()> {
+        // AES_256_CM key derivation test vectors from https://tools.ietf.org/html/rfc6188#section-7
+        let master_key = vec![
+            0xf0, 0xf0, 0x49, 0x14, 0xb5, 0x13, 0xf2, 0x76, 0x3a, 0x1b, 0x1f, 0xa1, 0x30, 0xf1,
+            0x0e, 0x29, 0x98, 0xf6, 0xf6, 0xe4, 0x3e, 0x43, 0x09, 0xd1, 0xe6, 0x22, 0xa0, 0xe3,
+            0x32, 0xb9, 0xf1, 0xb6,
+        ];
+        let master_salt = vec![
+            0x3b, 0x04, 0x80, 0x3d, 0xe5, 0x1e, 0xe7, 0xc9, 0x64, 0x23, 0xab, 0x5b, 0x78, 0xd2,
+        ];
+
+        let expected_session_key = vec![
+            0x5b, 0xa1, 0x06, 0x4e, 0x30, 0xec, 0x51, 0x61, 0x3c, 0xad, 0x92, 0x6c, 0x5a, 0x28,
+            0xef, 0x73, 0x1e, 0xc7, 0xfb, 0x39, 0x7f, 0x70, 0xa9, 0x60, 0x65, 0x3c, 0xaf, 0x06,
+            0x55, 0x4c, 0xd8, 0xc4,
+        ];
+        let expected_session_salt = vec![
+            0xfa, 0x31, 0x79, 0x16, 0x85, 0xca, 0x44, 0x4a, 0x9e, 0x07, 0xc6, 0xc6, 0x4e, 0x93,
+        ];
+        let expected_session_auth_tag = vec![
+            0xfd, 0x9c, 0x32, 0xd3, 0x9e, 0xd5, 0xfb, 0xb5, 0xa9, 0xdc, 0x96, 0xb3, 0x08, 0x18,
+            0x45, 0x4d, 0x13, 0x13, 0xdc, 0x05,
+        ];
+
+        let session_key = aes_cm_key_derivation(
+            LABEL_SRTP_ENCRYPTION,
+            &master_key,
+            &master_salt,
+            0,
+            master_key.len(),
+        )?;
+        assert_eq!(session_key, expected_session_key, "Session Key");
+
+        let session_salt = aes_cm_key_derivation(
+            LABEL_SRTP_SALT,
+            &master_key,
+            &master_salt,
+            0,
+            master_salt.len(),
+        )?;
+        assert_eq!(session_salt, expected_session_salt, "Session Salt");
+
+        let session_auth_tag = aes_cm_key_derivation(
+            LABEL_SRTP_AUTHENTICATION_TAG,
+            &master_key,
+            &master_salt,
+            0,
+            ProtectionProfile::Aes256CmHmacSha1_80.auth_key_len(),
+        )?;
+        assert_eq!(
+            session_auth_tag, expected_session_auth_tag,
+            "Session Auth Tag"
+        );
+
+        Ok(())
+    }
+
     // This test asserts that calling aesCmKeyDerivation with a non-zero indexOverKdr fails
     // Currently this isn't supported, but the API makes sure we can add this in the future
     #[test]
@@ -1,9 +1,17 @@
+use crate::error::{Error, Result};
+
 /// ProtectionProfile specifies Cipher and AuthTag details, similar to TLS cipher suite
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum ProtectionProfile {
     Aes128CmHmacSha1_80 = 0x0001,
+    Aes128CmHmacSha1_32 = 0x0002,
+    // AES-256 counter mode profiles of RFC 6188, their DTLS-SRTP values are
+    // the ones of draft-ietf-avt-srtp-big-aes and are not registered by IANA
+    Aes256CmHmacSha1_80 = 0x0003,
+    Aes256CmHmacSha1_32 = 0x0004,
     AeadAes128Gcm = 0x0007,
+    AeadAes256Gcm = 0x0008,
 }
 
 impl Default for ProtectionProfile {
@@ -13,30 +21,80 @@ impl Default for ProtectionProfile {
 }
 
 impl ProtectionProfile {
-    pub(crate) fn key_len(&self) -> usize {
+    /// from_crypto_suite returns the profile of a SDES crypto-suite
+    /// https://tools.ietf.org/html/rfc4568#section-6.2
+    pub fn from_crypto_suite(crypto_suite: &str) -> Result<Self> {
+        match crypto_suite {
+            "AES_CM_128_HMAC_SHA1_80" => Ok(ProtectionProfile::Aes128CmHmacSha1_80),
+            "AES_CM_128_HMAC_SHA1_32" => Ok(ProtectionProfile::Aes128CmHmacSha1_32),
+            "AES_256_CM_HMAC_SHA1_80" => Ok(ProtectionProfile::Aes256CmHmacSha1_80),
+            "AES_256_CM_HMAC_SHA1_32" => Ok(ProtectionProfile::Aes256CmHmacSha1_32),
+            "AEAD_AES_128_GCM" => Ok(ProtectionProfile::AeadAes128Gcm),
+            "AEAD_AES_256_GCM" => Ok(ProtectionProfile::AeadAes256Gcm),
+            _ => Err(Error::ErrNoSuchSrtpProfile),
+        }
+    }
+
+    /// crypto_suite returns the name of the profile in SDES crypto attributes,
+    /// defined by RFC 4568, RFC 6188 and RFC 7714
+    pub fn crypto_suite(&self) -> &'static str {
+        match *self {
+            ProtectionProfile::Aes128CmHmacSha1_80 => "AES_CM_128_HMAC_SHA1_80",
+            ProtectionProfile::Aes128CmHmacSha1_32 => "AES_CM_128_HMAC_SHA1_32",
+            ProtectionProfile::Aes256CmHmacSha1_80 => "AES_256_CM_HMAC_SHA1_80",
+            ProtectionProfile::Aes256CmHmacSha1_32 => "AES_256_CM_HMAC_SHA1_32",
+            ProtectionProfile::AeadAes128Gcm => "AEAD_AES_128_GCM",
+            ProtectionProfile::AeadAes256Gcm => "AEAD_AES_256_GCM",
+        }
+    }
+
+    pub fn key_len(&self) -> usize {
         match *self {
-            ProtectionProfile::Aes128CmHmacSha1_80 | ProtectionProfile::AeadAes128Gcm => 16,
+            ProtectionProfile::Aes128CmHmacSha1_80
+            | ProtectionProfile::Aes128CmHmacSha1_32
+            | ProtectionProfile::AeadAes128Gcm => 16,
+            ProtectionProfile::Aes256CmHmacSha1_80
+            | ProtectionProfile::Aes256CmHmacSha1_32
+            | ProtectionProfile::AeadAes256Gcm => 32,
         }
     }
 
-    pub(crate) fn salt_len(&self) -> usize {
+    pub fn salt_len(&self) -> usize {
         match *self {
-            ProtectionProfile::Aes128CmHmacSha1_80 => 14,
-            ProtectionProfile::AeadAes128Gcm => 12,
+            ProtectionProfile::Aes128CmHmacSha1_80
+            | ProtectionProfile::Aes128CmHmacSha1_32
+            | ProtectionProfile::Aes256CmHmacSha1_80
+            | ProtectionProfile::Aes256CmHmacSha1_32 => 14,
+            ProtectionProfile::AeadAes128Gcm | ProtectionProfile::AeadAes256Gcm => 12,
         }
     }
 
     pub(crate) fn auth_tag_len(&self) -> usize {
         match *self {
-            ProtectionProfile::Aes128CmHmacSha1_80 => 10, //CIPHER_AES_CM_HMAC_SHA1AUTH_TAG_LEN,
-            ProtectionProfile::AeadAes128Gcm => 16,       //CIPHER_AEAD_AES_GCM_AUTH_TAG_LEN,
+            ProtectionProfile::Aes128CmHmacSha1_80 | ProtectionProfile::Aes256CmHmacSha1_80 => 10, //CIPHER_AES_CM_HMAC_SHA1AUTH_TAG_LEN,
+            ProtectionProfile::Aes128CmHmacSha1_32 | ProtectionProfile::Aes256CmHmacSha1_32 => 4,
+            ProtectionProfile::AeadAes128Gcm | ProtectionProfile::AeadAes256Gcm => 16, //CIPHER_AEAD_AES_GCM_AUTH_TAG_LEN,
+        }
+    }
+
+    /// SRTCP keeps the 80-bit tag for the profiles with a 32-bit SRTP tag
+    /// https://tools.ietf.org/html/rfc5764#section-4.1.2
+    pub(crate) fn rtcp_auth_tag_len(&self) -> usize {
+        match *self {
+            ProtectionProfile::Aes128CmHmacSha1_32 | ProtectionProfile::Aes256CmHmacSha1_32 => {
+                ProtectionProfile::Aes128CmHmacSha1_80.auth_tag_len()
+            }
+            _ => self.auth_tag_len(),
         }
     }
 
     pub(crate) fn auth_key_len(&self) -> usize {
         match *self {
-            ProtectionProfile::Aes128CmHmacSha1_80 => 20,
-            ProtectionProfile::AeadAes128Gcm => 0,
+            ProtectionProfile::Aes128CmHmacSha1_80
+            | ProtectionProfile::Aes128CmHmacSha1_32
+            | ProtectionProfile::Aes256CmHmacSha1_80
+            | ProtectionProfile::Aes256CmHmacSha1_32 => 20,
+            ProtectionProfile::AeadAes128Gcm | ProtectionProfile::AeadAes256Gcm => 0,
         }
     }
 }
@@ -223,6 +223,13 @@ impl Session {
         }
     }
 
+    /// set_cryptex enables the encryption of the CSRC list and the header
+    /// extensions of the RTP packets written to the session
+    pub async fn set_cryptex(&self, cryptex: bool) {
+        let mut local_context = self.local_context.lock().await;
+        local_context.set_cryptex(cryptex);
+    }
+
     pub async fn close(&self) -> Result<()> {
         self.close_session_tx.send(()).await?;
 
@@ -152,6 +152,118 @@ async fn test_session_srtp_listen() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_session_srtp_stream_write() -> Result<()> {
+    let (sa, sb) = build_session_srtp_pair().await?;
+    let read_stream = sb.open(TEST_SSRC).await;
+
+    let received = rtp::packet::Packet {
+        header: rtp::header::Header {
+            ssrc: TEST_SSRC,
+            sequence_number: 1,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x01]),
+    };
+    sa.write_rtp(&received).await?;
+
+    let mut read_buffer = vec![0u8; 64];
+    let (_, header) = read_stream.read_rtp(&mut read_buffer).await?;
+    assert_eq!(header.sequence_number, 1);
+
+    // A written packet is read like a decrypted one, in order
+    let written = rtp::packet::Packet {
+        header: rtp::header::Header {
+            ssrc: TEST_SSRC,
+            sequence_number: 2,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x02]),
+    };
+    read_stream.write(&written.marshal()?).await?;
+    sa.write_rtp(&rtp::packet::Packet {
+        header: rtp::header::Header {
+            sequence_number: 3,
+            ..written.header.clone()
+        },
+        payload: Bytes::from_static(&[0x03]),
+    })
+    .await?;
+
+    for want in [2, 3] {
+        let (n, header) = read_stream.read_rtp(&mut read_buffer).await?;
+        assert_eq!(header.sequence_number, want);
+        assert_eq!(read_buffer[n - 1], want as u8);
+    }
+
+    sa.close().await?;
+    sb.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_srtp_sdes() -> Result<()> {
+    let test_payload = Bytes::from_static(&[0x00, 0x01, 0x03, 0x04]);
+    let mut read_buffer = BytesMut::with_capacity(RTP_HEADER_SIZE + test_payload.len());
+    read_buffer.resize(RTP_HEADER_SIZE + test_payload.len(), 0u8);
+
+    let ua = UdpSocket::bind("127.0.0.1:0").await?;
+    let ub = UdpSocket::bind("127.0.0.1:0").await?;
+
+    ua.connect(ub.local_addr()?).await?;
+    ub.connect(ua.local_addr()?).await?;
+
+    let profile = ProtectionProfile::AeadAes256Gcm;
+    let key_salt_a: Vec<u8> = (0..(profile.key_len() + profile.salt_len()) as u8).collect();
+    let key_salt_b: Vec<u8> = key_salt_a.iter().rev().cloned().collect();
+
+    let mut ca = Config {
+        profile,
+        ..Default::default()
+    };
+    ca.extract_session_keys_from_sdes(&key_salt_a, &key_salt_b)?;
+    let mut cb = Config {
+        profile,
+        ..Default::default()
+    };
+    cb.extract_session_keys_from_sdes(&key_salt_b, &key_salt_a)?;
+
+    let mut cc = Config {
+        profile,
+        ..Default::default()
+    };
+    let result = cc.extract_session_keys_from_sdes(&key_salt_a[1..], &key_salt_b);
+    assert!(
+        result.is_err(),
+        "inline key with a wrong length should fail"
+    );
+
+    let sa = Session::new(Arc::new(ua), ca, true).await?;
+    let sb = Session::new(Arc::new(ub), cb, true).await?;
+
+    let packet = rtp::packet::Packet {
+        header: rtp::header::Header {
+            ssrc: TEST_SSRC,
+            ..Default::default()
+        },
+        payload: test_payload.clone(),
+    };
+
+    let read_stream = sb.open(TEST_SSRC).await;
+
+    sa.write_rtp(&packet).await?;
+
+    read_stream.read(&mut read_buffer).await?;
+
+    assert_eq!(&test_payload[..], &read_buffer[RTP_HEADER_SIZE..]);
+
+    sa.close().await?;
+    sb.close().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_session_srtp_multi_ssrc() -> Result<()> {
     let ssrcs = vec![5000, 5001, 5002];
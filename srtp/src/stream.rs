@@ -78,6 +78,12 @@ impl Stream {
         Ok((n, header))
     }
 
+    /// write queues a packet which is already decrypted, it is read after the
+    /// packets received before, e.g. an unwrapped RTX retransmission
+    pub async fn write(&self, buf: &[u8]) -> Result<usize> {
+        Ok(self.buffer.write(buf).await?)
+    }
+
     /// Close removes the ReadStream from the session and cleans up any associated state
     pub async fn close(&self) -> Result<()> {
         self.buffer.close().await;
@@ -0,0 +1,96 @@
+use stun::server::*;
+use stun::Error;
+
+use clap::{App, Arg};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::signal;
+use util::Conn;
+
+async fn bind(ip: &str, port: &str) -> Result<Arc<dyn Conn + Send + Sync>, Error> {
+    let conn = UdpSocket::bind(format!("{}:{}", ip, port)).await?;
+    println!("Listening on: {}", conn.local_addr()?);
+    Ok(Arc::new(conn))
+}
+
+// cargo run --color=always --package stun --example stun_server -- --host 192.168.1.2 --alt-host 192.168.1.3
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let mut app = App::new("STUN Server")
+        .version("0.1.0")
+        .author("Rain Liu <yliu@webrtc.rs>")
+        .about("An example of STUN Server")
+        .arg(
+            Arg::with_name("FULLHELP")
+                .help("Prints more detailed help information")
+                .long("fullhelp"),
+        )
+        .arg(
+            Arg::with_name("host")
+                .required_unless("FULLHELP")
+                .takes_value(true)
+                .default_value("0.0.0.0")
+                .long("host")
+                .help("IP Address that STUN server listens on."),
+        )
+        .arg(
+            Arg::with_name("port")
+                .takes_value(true)
+                .default_value("3478")
+                .long("port")
+                .help("Listening port."),
+        )
+        .arg(
+            Arg::with_name("alt-host")
+                .takes_value(true)
+                .long("alt-host")
+                .help("Alternate IP Address for NAT behavior discovery (RFC 5780)."),
+        )
+        .arg(
+            Arg::with_name("alt-port")
+                .takes_value(true)
+                .default_value("3479")
+                .long("alt-port")
+                .help("Alternate listening port."),
+        );
+
+    let matches = app.clone().get_matches();
+
+    if matches.is_present("FULLHELP") {
+        app.print_long_help().unwrap();
+        std::process::exit(0);
+    }
+
+    let host = matches.value_of("host").unwrap();
+    let port = matches.value_of("port").unwrap();
+
+    let conn = bind(host, port).await?;
+    let alternate_conns = if let Some(alt_host) = matches.value_of("alt-host") {
+        let alt_port = matches.value_of("alt-port").unwrap();
+        Some(AlternateConns {
+            alternate_port: bind(host, alt_port).await?,
+            alternate_ip: bind(alt_host, port).await?,
+            alternate_ip_port: bind(alt_host, alt_port).await?,
+        })
+    } else {
+        None
+    };
+
+    let server = Server::new(ServerConfig {
+        conn,
+        alternate_conns,
+        software: "webrtc-rs stun server".to_owned(),
+    })
+    .await?;
+
+    if let Some(other_address) = server.other_address() {
+        println!("Other address: {}", other_address);
+    }
+
+    println!("Waiting for Ctrl-C...");
+    signal::ctrl_c().await.expect("failed to listen for event");
+    println!("\nClosing connection now...");
+    server.close().await?;
+
+    Ok(())
+}
@@ -77,7 +77,12 @@ fn test_attr_type_range() -> Result<()> {
         assert!(!(a.optional() || !a.required()), "should be required");
     }
 
-    let tests = vec![ATTR_SOFTWARE, ATTR_ICE_CONTROLLED, ATTR_ORIGIN];
+    let tests = vec![
+        ATTR_SOFTWARE,
+        ATTR_ICE_CONTROLLED,
+        ATTR_ORIGIN,
+        ATTR_ADDITIONAL_ADDRESS_FAMILY,
+    ];
     for a in tests {
         assert!(!(a.required() || !a.optional()), "should be optional");
     }
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod changereq_test;
+
+use crate::attributes::*;
+use crate::checks::*;
+use crate::error::*;
+use crate::message::*;
+
+use std::fmt;
+
+const CHANGE_REQUEST_SIZE: usize = 4;
+const CHANGE_IP_FLAG: u8 = 0x04;
+const CHANGE_PORT_FLAG: u8 = 0x02;
+
+/// ChangeRequest represents CHANGE-REQUEST attribute.
+///
+/// It asks the server to send the response from its alternate IP address
+/// and/or alternate port, which are given by its OTHER-ADDRESS attribute.
+///
+/// RFC 5780 Section 7.2
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ChangeRequest {
+    pub change_ip: bool,
+    pub change_port: bool,
+}
+
+impl fmt::Display for ChangeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "change ip: {}, change port: {}",
+            self.change_ip, self.change_port
+        )
+    }
+}
+
+impl Setter for ChangeRequest {
+    /// add_to adds CHANGE-REQUEST to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = [0u8; CHANGE_REQUEST_SIZE];
+        if self.change_ip {
+            v[3] |= CHANGE_IP_FLAG;
+        }
+        if self.change_port {
+            v[3] |= CHANGE_PORT_FLAG;
+        }
+        m.add(ATTR_CHANGE_REQUEST, &v);
+        Ok(())
+    }
+}
+
+impl Getter for ChangeRequest {
+    /// get_from decodes CHANGE-REQUEST from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_CHANGE_REQUEST)?;
+        check_size(ATTR_CHANGE_REQUEST, v.len(), CHANGE_REQUEST_SIZE)?;
+        self.change_ip = v[3] & CHANGE_IP_FLAG != 0;
+        self.change_port = v[3] & CHANGE_PORT_FLAG != 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn test_change_request() -> Result<()> {
+    for (change_ip, change_port) in [(false, false), (true, false), (false, true), (true, true)] {
+        let mut m = Message::new();
+        let r = ChangeRequest {
+            change_ip,
+            change_port,
+        };
+        r.add_to(&mut m)?;
+        m.write_header();
+
+        //"GetFrom"
+        {
+            let mut decoded = Message::new();
+            decoded.write(&m.raw)?;
+            let mut got = ChangeRequest::default();
+            got.get_from(&decoded)?;
+            assert_eq!(got, r, "decoded {}, expected {}", got, r);
+        }
+    }
+
+    let mut m = Message::new();
+    ChangeRequest {
+        change_ip: true,
+        change_port: true,
+    }
+    .add_to(&mut m)?;
+    assert_eq!(m.get(ATTR_CHANGE_REQUEST)?, vec![0, 0, 0, 0x06]);
+
+    //"HandleErr"
+    {
+        let mut m = Message::new();
+        let mut handle = ChangeRequest::default();
+        assert_eq!(
+            handle.get_from(&m),
+            Err(Error::ErrAttributeNotFound),
+            "should be not found"
+        );
+        m.add(ATTR_CHANGE_REQUEST, &[1, 2, 3]);
+        let result = handle.get_from(&m);
+        assert!(
+            matches!(&result, Err(err) if is_attr_size_invalid(err)),
+            "IsAttrSizeInvalid should be true"
+        );
+    }
+
+    Ok(())
+}
@@ -59,6 +59,14 @@ pub enum Error {
     ErrSchemeType,
     #[error("invalid hostname")]
     ErrHost,
+    #[error("alternate addresses must have the primary or alternate IP address and port")]
+    ErrInvalidAlternateAddress,
+    #[error("server is closed")]
+    ErrServerClosed,
+    #[error("no OTHER-ADDRESS in response, server does not support NAT behavior discovery")]
+    ErrNoOtherAddress,
+    #[error("unsupported password algorithm")]
+    ErrUnsupportedPasswordAlgorithm,
     #[error("{0}")]
     Other(String),
     #[error("url parse: {0}")]
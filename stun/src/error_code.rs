@@ -77,7 +77,7 @@ impl Getter for ErrorCodeAttribute {
 }
 
 // ErrorCode is code for ERROR-CODE attribute.
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Default, Debug)]
 pub struct ErrorCode(pub u16);
 
 impl Setter for ErrorCode {
@@ -7,7 +7,7 @@ use crate::error::*;
 use crate::message::*;
 
 use md5::{Digest, Md5};
-use ring::hmac;
+use ring::{digest, hmac};
 use std::fmt;
 
 // separator for credentials.
@@ -115,3 +115,104 @@ impl MessageIntegrity {
         check_hmac(&v, &expected)
     }
 }
+
+// MessageIntegritySha256 represents MESSAGE-INTEGRITY-SHA256 attribute.
+//
+// RFC 8489 Section 14.6
+#[derive(Default, Clone)]
+pub struct MessageIntegritySha256(pub Vec<u8>);
+
+fn new_hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mac = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&mac, message).as_ref().to_vec()
+}
+
+impl fmt::Display for MessageIntegritySha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KEY: 0x{:x?}", self.0)
+    }
+}
+
+impl Setter for MessageIntegritySha256 {
+    // add_to adds MESSAGE-INTEGRITY-SHA256 attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        for a in &m.attributes.0 {
+            // Message should not contain FINGERPRINT attribute
+            // before MESSAGE-INTEGRITY-SHA256.
+            if a.typ == ATTR_FINGERPRINT {
+                return Err(Error::ErrFingerprintBeforeIntegrity);
+            }
+        }
+        let length = m.length;
+        m.length += (MESSAGE_INTEGRITY_SHA256_SIZE + ATTRIBUTE_HEADER_SIZE) as u32;
+        m.write_length();
+        let v = new_hmac_sha256(&self.0, &m.raw);
+        m.length = length;
+
+        m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v);
+
+        Ok(())
+    }
+}
+
+pub(crate) const MESSAGE_INTEGRITY_SHA256_SIZE: usize = 32;
+// the HMAC in MESSAGE-INTEGRITY-SHA256 may be truncated to 16 bytes.
+const MIN_MESSAGE_INTEGRITY_SHA256_SIZE: usize = 16;
+
+impl MessageIntegritySha256 {
+    // new_long_term_integrity returns new MessageIntegritySha256 with key for
+    // long-term credentials. Password, username, and realm must be SASL-prepared.
+    pub fn new_long_term_integrity(username: String, realm: String, password: String) -> Self {
+        let s = [username, realm, password].join(CREDENTIALS_SEP);
+
+        MessageIntegritySha256(
+            digest::digest(&digest::SHA256, s.as_bytes())
+                .as_ref()
+                .to_vec(),
+        )
+    }
+
+    // new_short_term_integrity returns new MessageIntegritySha256 with key for
+    // short-term credentials. Password must be SASL-prepared.
+    pub fn new_short_term_integrity(password: String) -> Self {
+        MessageIntegritySha256(password.as_bytes().to_vec())
+    }
+
+    // Check checks MESSAGE-INTEGRITY-SHA256 attribute, which may be truncated.
+    pub fn check(&self, m: &mut Message) -> Result<()> {
+        let v = m.get(ATTR_MESSAGE_INTEGRITY_SHA256)?;
+        if v.len() < MIN_MESSAGE_INTEGRITY_SHA256_SIZE
+            || v.len() > MESSAGE_INTEGRITY_SHA256_SIZE
+            || v.len() % 4 != 0
+        {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+
+        // Adjusting length in header to match m.Raw that was
+        // used when computing HMAC.
+
+        let length = m.length as usize;
+        let mut after_integrity = false;
+        let mut size_reduced = 0;
+
+        for a in &m.attributes.0 {
+            if after_integrity {
+                size_reduced += nearest_padded_value_length(a.length as usize);
+                size_reduced += ATTRIBUTE_HEADER_SIZE;
+            }
+            if a.typ == ATTR_MESSAGE_INTEGRITY_SHA256 {
+                after_integrity = true;
+            }
+        }
+        m.length -= size_reduced as u32;
+        m.write_length();
+        // start_of_hmac should be first byte of integrity attribute.
+        let start_of_hmac =
+            MESSAGE_HEADER_SIZE + m.length as usize - (ATTRIBUTE_HEADER_SIZE + v.len());
+        let b = &m.raw[..start_of_hmac]; // data before integrity attribute
+        let expected = new_hmac_sha256(&self.0, b);
+        m.length = length as u32;
+        m.write_length(); // writing length back
+        check_hmac(&v, &expected[..v.len()])
+    }
+}
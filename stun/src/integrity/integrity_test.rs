@@ -91,3 +91,79 @@ fn test_message_integrity_before_fingerprint() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_message_integrity_sha256() -> Result<()> {
+    let i = MessageIntegritySha256::new_long_term_integrity(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+    );
+    assert_eq!(i.0.len(), 32);
+
+    let mut m = Message::new();
+    m.write_header();
+    let a = TextAttribute {
+        attr: ATTR_SOFTWARE,
+        text: "software".to_owned(),
+    };
+    a.add_to(&mut m)?;
+    i.add_to(&mut m)?;
+    FINGERPRINT.add_to(&mut m)?;
+    m.write_header();
+    assert_eq!(m.get(ATTR_MESSAGE_INTEGRITY_SHA256)?.len(), 32);
+
+    let mut d_m = Message::new();
+    d_m.raw = m.raw.clone();
+    d_m.decode()?;
+    i.check(&mut d_m)?;
+
+    let result = MessageIntegrity(i.0.clone()).check(&mut d_m);
+    assert!(result.is_err(), "no MESSAGE-INTEGRITY");
+    let result = MessageIntegritySha256::new_short_term_integrity("pwd".to_owned()).check(&mut d_m);
+    assert_eq!(result, Err(Error::ErrIntegrityMismatch));
+
+    Ok(())
+}
+
+#[test]
+fn test_message_integrity_sha256_truncated() -> Result<()> {
+    let i = MessageIntegritySha256::new_short_term_integrity("password".to_owned());
+
+    for size in [16, 20, 28, 12] {
+        let mut m = Message::new();
+        m.write_header();
+        // the HMAC covers the length of the truncated attribute
+        m.length += (ATTRIBUTE_HEADER_SIZE + size) as u32;
+        m.write_length();
+        let mac = hmac::Key::new(hmac::HMAC_SHA256, &i.0);
+        let expected = hmac::sign(&mac, &m.raw[..MESSAGE_HEADER_SIZE]);
+        m.length = 0;
+        m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &expected.as_ref()[..size]);
+        m.write_header();
+
+        let mut d_m = Message::new();
+        d_m.raw = m.raw.clone();
+        d_m.decode()?;
+        let result = i.check(&mut d_m);
+        if size < 16 {
+            assert_eq!(result, Err(Error::ErrAttributeSizeInvalid));
+        } else {
+            assert!(result.is_ok(), "truncated to {} should be valid", size);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_message_integrity_sha256_before_fingerprint() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    FINGERPRINT.add_to(&mut m)?;
+    let i = MessageIntegritySha256::new_short_term_integrity("password".to_owned());
+    let result = i.add_to(&mut m);
+    assert_eq!(result, Err(Error::ErrFingerprintBeforeIntegrity));
+
+    Ok(())
+}
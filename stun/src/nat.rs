@@ -0,0 +1,316 @@
+#[cfg(test)]
+mod nat_test;
+
+use crate::addr::*;
+use crate::agent::*;
+use crate::attributes::*;
+use crate::changereq::*;
+use crate::client::*;
+use crate::error::*;
+use crate::error_code::*;
+use crate::message::*;
+use crate::xoraddr::*;
+
+use async_trait::async_trait;
+use util::vnet::nat::EndpointDependencyType;
+use util::Conn;
+
+use std::fmt;
+use std::marker::{Send, Sync};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration};
+
+const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_DISCOVERY_RTO: Duration = Duration::from_millis(100);
+
+/// NatBehavior is the behavior of the NATs between a socket and a STUN
+/// server, discovered as described in RFC 5780 Section 4.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NatBehavior {
+    /// mapped_address is the XOR-MAPPED-ADDRESS of the socket seen by the
+    /// primary address of the server.
+    pub mapped_address: SocketAddr,
+    pub mapping_behavior: EndpointDependencyType,
+    pub filtering_behavior: EndpointDependencyType,
+}
+
+impl fmt::Display for NatBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mapped address {}, {:?} mapping, {:?} filtering",
+            self.mapped_address, self.mapping_behavior, self.filtering_behavior
+        )
+    }
+}
+
+/// NatBehaviorDiscovery discovers the mapping and filtering behaviors of
+/// the NATs between a socket and a STUN server, which must support
+/// CHANGE-REQUEST and OTHER-ADDRESS (RFC 5780).
+pub struct NatBehaviorDiscovery {
+    conn: Arc<dyn Conn + Send + Sync>,
+    server: SocketAddr,
+    rto: Duration,
+    timeout: Duration,
+}
+
+impl NatBehaviorDiscovery {
+    /// new creates a discovery sending Binding requests from conn, which
+    /// must not be connected, to the primary address of server.
+    pub fn new(conn: Arc<dyn Conn + Send + Sync>, server: SocketAddr) -> Self {
+        NatBehaviorDiscovery {
+            conn,
+            server,
+            rto: DEFAULT_DISCOVERY_RTO,
+            timeout: DEFAULT_DISCOVERY_TIMEOUT,
+        }
+    }
+
+    /// with_rto sets the RTO of the Binding requests.
+    pub fn with_rto(mut self, rto: Duration) -> Self {
+        self.rto = rto;
+        self
+    }
+
+    /// with_timeout sets how long to wait for each Binding response, a
+    /// filtering test without response past timeout means the NAT drops
+    /// the response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// discover runs the filtering and the mapping behavior discoveries.
+    pub async fn discover(&self) -> Result<NatBehavior> {
+        // the mapping tests send to the alternate address of the server,
+        // which would then pass the filtering of the NAT
+        let filtering_behavior = self.discover_filtering().await?;
+        let (mapped_address, mapping_behavior) = self.discover_mapping().await?;
+
+        let behavior = NatBehavior {
+            mapped_address,
+            mapping_behavior,
+            filtering_behavior,
+        };
+        log::info!("NAT behavior to {}: {}", self.server, behavior);
+
+        Ok(behavior)
+    }
+
+    /// mapping_behavior discovers whether the mapping of the socket depends
+    /// on the destination address or port (RFC 5780 Section 4.3).
+    pub async fn mapping_behavior(&self) -> Result<EndpointDependencyType> {
+        let (mapped_address, mapping_behavior) = self.discover_mapping().await?;
+        log::info!(
+            "NAT mapping behavior to {}: {:?}, mapped address {}",
+            self.server,
+            mapping_behavior,
+            mapped_address
+        );
+
+        Ok(mapping_behavior)
+    }
+
+    /// filtering_behavior discovers whether the NAT drops the packets from
+    /// an address or port the socket has not sent to (RFC 5780 Section 4.4).
+    /// The socket must not have sent to the alternate address of the server.
+    pub async fn filtering_behavior(&self) -> Result<EndpointDependencyType> {
+        let filtering_behavior = self.discover_filtering().await?;
+        log::info!(
+            "NAT filtering behavior to {}: {:?}",
+            self.server,
+            filtering_behavior
+        );
+
+        Ok(filtering_behavior)
+    }
+
+    async fn discover_mapping(&self) -> Result<(SocketAddr, EndpointDependencyType)> {
+        // Test I: the mapped address and the alternate address of the server
+        let res = self.binding_request(self.server, None).await?;
+        let other_address = get_other_address(&res)?;
+        let mapped_address = get_mapped_address(&res)?;
+
+        // Test II: the alternate IP address and the primary port
+        let res = self
+            .binding_request(
+                SocketAddr::new(other_address.ip(), self.server.port()),
+                None,
+            )
+            .await?;
+        let mapped_address2 = get_mapped_address(&res)?;
+        if mapped_address2 == mapped_address {
+            return Ok((mapped_address, EndpointDependencyType::EndpointIndependent));
+        }
+
+        // Test III: the alternate IP address and port
+        let res = self.binding_request(other_address, None).await?;
+        let mapped_address3 = get_mapped_address(&res)?;
+        if mapped_address3 == mapped_address2 {
+            Ok((
+                mapped_address,
+                EndpointDependencyType::EndpointAddrDependent,
+            ))
+        } else {
+            Ok((
+                mapped_address,
+                EndpointDependencyType::EndpointAddrPortDependent,
+            ))
+        }
+    }
+
+    async fn discover_filtering(&self) -> Result<EndpointDependencyType> {
+        // Test I: the server must support RFC 5780
+        let res = self.binding_request(self.server, None).await?;
+        get_other_address(&res)?;
+
+        // Test II: a response from the alternate IP address and port
+        if self
+            .filtering_test(ChangeRequest {
+                change_ip: true,
+                change_port: true,
+            })
+            .await?
+        {
+            return Ok(EndpointDependencyType::EndpointIndependent);
+        }
+
+        // Test III: a response from the primary IP address and alternate port
+        if self
+            .filtering_test(ChangeRequest {
+                change_ip: false,
+                change_port: true,
+            })
+            .await?
+        {
+            Ok(EndpointDependencyType::EndpointAddrDependent)
+        } else {
+            Ok(EndpointDependencyType::EndpointAddrPortDependent)
+        }
+    }
+
+    // filtering_test returns whether the response to a CHANGE-REQUEST
+    // passed the NAT.
+    async fn filtering_test(&self, change_request: ChangeRequest) -> Result<bool> {
+        match self
+            .binding_request(self.server, Some(change_request))
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(Error::ErrTransactionTimeOut) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // binding_request sends a Binding request to server and returns its
+    // success response.
+    async fn binding_request(
+        &self,
+        server: SocketAddr,
+        change_request: Option<ChangeRequest>,
+    ) -> Result<Message> {
+        let mut msg = Message::new();
+        msg.build(&[Box::new(TransactionId::new()), Box::new(BINDING_REQUEST)])?;
+        if let Some(change_request) = change_request {
+            change_request.add_to(&mut msg)?;
+        }
+
+        let mut client = ClientBuilder::new()
+            .with_conn(Arc::new(DestinationConn {
+                conn: Arc::clone(&self.conn),
+                server,
+            }))
+            .with_rto(self.rto)
+            .build()?;
+
+        let (handler_tx, mut handler_rx) = mpsc::unbounded_channel();
+        let result = match client.send(&msg, Some(Arc::new(handler_tx))).await {
+            Ok(_) => match time::timeout(self.timeout, handler_rx.recv()).await {
+                Ok(Some(event)) => event.event_body,
+                Ok(None) => Err(Error::ErrClientClosed),
+                Err(_) => Err(Error::ErrTransactionTimeOut),
+            },
+            Err(err) => Err(err),
+        };
+        client.close().await?;
+
+        let res = result?;
+        if res.typ == BINDING_SUCCESS {
+            return Ok(res);
+        }
+
+        let mut code = ErrorCodeAttribute::default();
+        code.get_from(&res)?;
+        Err(Error::Other(format!(
+            "unexpected response {} from {}: {}",
+            res.typ, server, code
+        )))
+    }
+}
+
+fn get_mapped_address(m: &Message) -> Result<SocketAddr> {
+    let mut addr = XorMappedAddress::default();
+    addr.get_from(m)?;
+    Ok(SocketAddr::new(addr.ip, addr.port))
+}
+
+fn get_other_address(m: &Message) -> Result<SocketAddr> {
+    let mut addr = OtherAddress::default();
+    if addr.get_from_as(m, ATTR_OTHER_ADDRESS).is_err() {
+        return Err(Error::ErrNoOtherAddress);
+    }
+    Ok(SocketAddr::new(addr.ip, addr.port))
+}
+
+// DestinationConn sends to a server address from a shared socket, and
+// receives from any address: the responses to a CHANGE-REQUEST come from
+// another address of the server. Closing it leaves the socket open.
+struct DestinationConn {
+    conn: Arc<dyn Conn + Send + Sync>,
+    server: SocketAddr,
+}
+
+#[async_trait]
+impl Conn for DestinationConn {
+    async fn connect(&self, _addr: SocketAddr) -> std::result::Result<(), util::Error> {
+        Err(util::Error::Other("Not applicable".to_owned()))
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> std::result::Result<usize, util::Error> {
+        let (n, _) = self.conn.recv_from(buf).await?;
+        Ok(n)
+    }
+
+    async fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> std::result::Result<(usize, SocketAddr), util::Error> {
+        self.conn.recv_from(buf).await
+    }
+
+    async fn send(&self, buf: &[u8]) -> std::result::Result<usize, util::Error> {
+        self.conn.send_to(buf, self.server).await
+    }
+
+    async fn send_to(
+        &self,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> std::result::Result<usize, util::Error> {
+        self.conn.send_to(buf, target).await
+    }
+
+    async fn local_addr(&self) -> std::result::Result<SocketAddr, util::Error> {
+        self.conn.local_addr().await
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.server)
+    }
+
+    async fn close(&self) -> std::result::Result<(), util::Error> {
+        Ok(())
+    }
+}
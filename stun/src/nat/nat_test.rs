@@ -0,0 +1,131 @@
+use super::*;
+use crate::server::*;
+
+use std::str::FromStr;
+use tokio::sync::Mutex;
+use util::vnet::net::*;
+use util::vnet::router::*;
+use util::vnet::*;
+
+const SERVER_IP: &str = "1.2.3.4";
+const SERVER_ALTERNATE_IP: &str = "1.2.3.5";
+const SERVER_PORT: u16 = 3478;
+const SERVER_ALTERNATE_PORT: u16 = 3479;
+const NAT_IP: &str = "5.6.7.8";
+const LOCAL_IP: &str = "192.168.0.2";
+
+async fn bind(net: &Arc<Net>, ip: &str, port: u16) -> Result<Arc<dyn Conn + Send + Sync>> {
+    let addr = SocketAddr::from_str(&format!("{}:{}", ip, port))
+        .map_err(|err| Error::Other(err.to_string()))?;
+    Ok(net.bind(addr).await?)
+}
+
+// discover runs the discovery from behind a NAT simulating nat_type to a
+// RFC 5780 server.
+async fn discover(nat_type: nat::NatType) -> Result<NatBehavior> {
+    let wan = Arc::new(Mutex::new(Router::new(RouterConfig {
+        cidr: "0.0.0.0/0".to_owned(),
+        ..Default::default()
+    })?));
+    let wnet = Arc::new(Net::new(Some(NetConfig {
+        static_ips: vec![SERVER_IP.to_owned(), SERVER_ALTERNATE_IP.to_owned()],
+        ..Default::default()
+    })));
+    let lan = Arc::new(Mutex::new(Router::new(RouterConfig {
+        static_ips: vec![NAT_IP.to_owned()],
+        cidr: "192.168.0.0/24".to_owned(),
+        nat_type: Some(nat_type),
+        ..Default::default()
+    })?));
+    let lnet = Arc::new(Net::new(Some(NetConfig {
+        static_ips: vec![LOCAL_IP.to_owned()],
+        ..Default::default()
+    })));
+
+    for (net, router) in [(&wnet, &wan), (&lnet, &lan)] {
+        let nic = net.get_nic()?;
+        router.lock().await.add_net(Arc::clone(&nic)).await?;
+        nic.lock().await.set_router(Arc::clone(router)).await?;
+    }
+    wan.lock().await.add_router(Arc::clone(&lan)).await?;
+    lan.lock().await.set_router(Arc::clone(&wan)).await?;
+    wan.lock().await.start().await?;
+
+    let server = Server::new(ServerConfig {
+        conn: bind(&wnet, SERVER_IP, SERVER_PORT).await?,
+        alternate_conns: Some(AlternateConns {
+            alternate_port: bind(&wnet, SERVER_IP, SERVER_ALTERNATE_PORT).await?,
+            alternate_ip: bind(&wnet, SERVER_ALTERNATE_IP, SERVER_PORT).await?,
+            alternate_ip_port: bind(&wnet, SERVER_ALTERNATE_IP, SERVER_ALTERNATE_PORT).await?,
+        }),
+        software: String::new(),
+    })
+    .await?;
+
+    let conn = bind(&lnet, LOCAL_IP, 0).await?;
+    let server_addr = SocketAddr::from_str(&format!("{}:{}", SERVER_IP, SERVER_PORT))
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let result = NatBehaviorDiscovery::new(conn, server_addr)
+        .with_timeout(Duration::from_millis(500))
+        .discover()
+        .await;
+
+    server.close().await?;
+    wan.lock().await.stop().await?;
+
+    result
+}
+
+#[tokio::test]
+async fn test_nat_behavior_discovery() -> Result<()> {
+    let behaviors = [
+        EndpointDependencyType::EndpointIndependent,
+        EndpointDependencyType::EndpointAddrDependent,
+        EndpointDependencyType::EndpointAddrPortDependent,
+    ];
+
+    for mapping_behavior in behaviors {
+        for filtering_behavior in behaviors {
+            let behavior = discover(nat::NatType {
+                mapping_behavior,
+                filtering_behavior,
+                ..Default::default()
+            })
+            .await?;
+
+            assert_eq!(behavior.mapping_behavior, mapping_behavior);
+            assert_eq!(behavior.filtering_behavior, filtering_behavior);
+            assert_eq!(behavior.mapped_address.ip().to_string(), NAT_IP);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nat_behavior_discovery_without_other_address() -> Result<()> {
+    let conn: Arc<dyn Conn + Send + Sync> =
+        Arc::new(tokio::net::UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr().await?;
+    let server = Server::new(ServerConfig {
+        conn,
+        alternate_conns: None,
+        software: String::new(),
+    })
+    .await?;
+
+    let conn = Arc::new(tokio::net::UdpSocket::bind("127.0.0.1:0").await?);
+    let discovery = NatBehaviorDiscovery::new(conn, server_addr);
+    assert_eq!(
+        discovery.mapping_behavior().await,
+        Err(Error::ErrNoOtherAddress)
+    );
+    assert_eq!(
+        discovery.filtering_behavior().await,
+        Err(Error::ErrNoOtherAddress)
+    );
+
+    server.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod password_algorithm_test;
+
+use crate::attributes::*;
+use crate::error::*;
+use crate::integrity::*;
+use crate::message::*;
+
+use std::fmt;
+
+// PASSWORD_ALGORITHM_MD5 derives the long-term credential key with MD5.
+//
+// RFC 8489 Section 18.5.1.1
+pub const PASSWORD_ALGORITHM_MD5: u16 = 0x0001;
+// PASSWORD_ALGORITHM_SHA256 derives the long-term credential key with SHA-256.
+//
+// RFC 8489 Section 18.5.1.2
+pub const PASSWORD_ALGORITHM_SHA256: u16 = 0x0002;
+
+// algorithm and parameters length are 16 bit.
+const PASSWORD_ALGORITHM_HEADER_SIZE: usize = 4;
+
+// PasswordAlgorithm represents PASSWORD-ALGORITHM attribute.
+//
+// RFC 8489 Section 14.12
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PasswordAlgorithm {
+    pub algorithm: u16,
+    pub parameters: Vec<u8>,
+}
+
+impl fmt::Display for PasswordAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.algorithm {
+            PASSWORD_ALGORITHM_MD5 => write!(f, "MD5"),
+            PASSWORD_ALGORITHM_SHA256 => write!(f, "SHA-256"),
+            algorithm => write!(f, "0x{:x}", algorithm),
+        }
+    }
+}
+
+impl Setter for PasswordAlgorithm {
+    // add_to adds PASSWORD-ALGORITHM attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = vec![];
+        self.encode(&mut v);
+        m.add(ATTR_PASSWORD_ALGORITHM, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithm {
+    // get_from decodes PASSWORD-ALGORITHM from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHM)?;
+        let (a, n) = PasswordAlgorithm::decode(&v)?;
+        if n != v.len() {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+        *self = a;
+        Ok(())
+    }
+}
+
+impl PasswordAlgorithm {
+    // new returns PasswordAlgorithm of algorithm without parameters.
+    pub fn new(algorithm: u16) -> Self {
+        PasswordAlgorithm {
+            algorithm,
+            parameters: vec![],
+        }
+    }
+
+    // key returns the long-term credential key of username, realm and password
+    // derived by the algorithm. Password, username, and realm must be SASL-prepared.
+    pub fn key(&self, username: String, realm: String, password: String) -> Result<Vec<u8>> {
+        match self.algorithm {
+            PASSWORD_ALGORITHM_MD5 => {
+                Ok(MessageIntegrity::new_long_term_integrity(username, realm, password).0)
+            }
+            PASSWORD_ALGORITHM_SHA256 => {
+                Ok(MessageIntegritySha256::new_long_term_integrity(username, realm, password).0)
+            }
+            _ => Err(Error::ErrUnsupportedPasswordAlgorithm),
+        }
+    }
+
+    fn encode(&self, v: &mut Vec<u8>) {
+        v.extend_from_slice(&self.algorithm.to_be_bytes());
+        v.extend_from_slice(&(self.parameters.len() as u16).to_be_bytes());
+        v.extend_from_slice(&self.parameters);
+        v.resize(
+            v.len() + nearest_padded_value_length(self.parameters.len()) - self.parameters.len(),
+            0,
+        );
+    }
+
+    // decode returns the PasswordAlgorithm at the start of v and its padded size.
+    fn decode(v: &[u8]) -> Result<(Self, usize)> {
+        if v.len() < PASSWORD_ALGORITHM_HEADER_SIZE {
+            return Err(Error::ErrUnexpectedEof);
+        }
+        let algorithm = u16::from_be_bytes([v[0], v[1]]);
+        let length = u16::from_be_bytes([v[2], v[3]]) as usize;
+        let end = PASSWORD_ALGORITHM_HEADER_SIZE + length;
+        if v.len() < end {
+            return Err(Error::ErrUnexpectedEof);
+        }
+
+        Ok((
+            PasswordAlgorithm {
+                algorithm,
+                parameters: v[PASSWORD_ALGORITHM_HEADER_SIZE..end].to_vec(),
+            },
+            std::cmp::min(
+                PASSWORD_ALGORITHM_HEADER_SIZE + nearest_padded_value_length(length),
+                v.len(),
+            ),
+        ))
+    }
+}
+
+// PasswordAlgorithms represents PASSWORD-ALGORITHMS attribute, the algorithms
+// supported by the server in decreasing order of preference.
+//
+// RFC 8489 Section 14.11
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PasswordAlgorithms(pub Vec<PasswordAlgorithm>);
+
+impl fmt::Display for PasswordAlgorithms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: Vec<String> = self.0.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}", s.join(", "))
+    }
+}
+
+impl Setter for PasswordAlgorithms {
+    // add_to adds PASSWORD-ALGORITHMS attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = vec![];
+        for a in &self.0 {
+            a.encode(&mut v);
+        }
+        m.add(ATTR_PASSWORD_ALGORITHMS, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithms {
+    // get_from decodes PASSWORD-ALGORITHMS from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHMS)?;
+        self.0.clear();
+        let mut first = 0;
+        while first < v.len() {
+            let (a, n) = PasswordAlgorithm::decode(&v[first..])?;
+            self.0.push(a);
+            first += n;
+        }
+        Ok(())
+    }
+}
+
+impl PasswordAlgorithms {
+    // select returns the most preferred algorithm among supported.
+    pub fn select(&self, supported: &[u16]) -> Option<&PasswordAlgorithm> {
+        self.0.iter().find(|a| supported.contains(&a.algorithm))
+    }
+}
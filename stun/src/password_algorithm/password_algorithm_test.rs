@@ -0,0 +1,96 @@
+use super::*;
+
+#[test]
+fn test_password_algorithm() -> Result<()> {
+    for a in [
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256),
+        PasswordAlgorithm {
+            algorithm: 0x1234,
+            parameters: vec![1, 2, 3],
+        },
+    ] {
+        let mut m = Message::new();
+        a.add_to(&mut m)?;
+        m.write_header();
+
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut got = PasswordAlgorithm::default();
+        got.get_from(&decoded)?;
+        assert_eq!(got, a, "decoded {}, expected {}", got, a);
+    }
+
+    let mut m = Message::new();
+    m.add(ATTR_PASSWORD_ALGORITHM, &[0, 1, 0, 4]);
+    let mut got = PasswordAlgorithm::default();
+    assert_eq!(got.get_from(&m), Err(Error::ErrUnexpectedEof));
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithms() -> Result<()> {
+    let algorithms = PasswordAlgorithms(vec![
+        PasswordAlgorithm {
+            algorithm: 0x1234,
+            parameters: vec![1, 2, 3, 4, 5],
+        },
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256),
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+    ]);
+    assert_eq!(algorithms.to_string(), "0x1234, SHA-256, MD5");
+
+    let mut m = Message::new();
+    algorithms.add_to(&mut m)?;
+    m.write_header();
+    assert_eq!(m.get(ATTR_PASSWORD_ALGORITHMS)?.len(), 20);
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    let mut got = PasswordAlgorithms::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, algorithms);
+
+    assert_eq!(
+        got.select(&[PASSWORD_ALGORITHM_MD5, PASSWORD_ALGORITHM_SHA256]),
+        Some(&PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256))
+    );
+    assert_eq!(got.select(&[0x4321]), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithm_key() -> Result<()> {
+    let md5 = PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5).key(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+    )?;
+    assert_eq!(
+        md5,
+        MessageIntegrity::new_long_term_integrity(
+            "user".to_owned(),
+            "realm".to_owned(),
+            "pass".to_owned()
+        )
+        .0
+    );
+
+    let sha256 = PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256).key(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+    )?;
+    assert_eq!(sha256.len(), 32);
+
+    let result = PasswordAlgorithm::new(0x1234).key(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+    );
+    assert_eq!(result, Err(Error::ErrUnsupportedPasswordAlgorithm));
+
+    Ok(())
+}
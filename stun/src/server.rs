@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod server_test;
+
+use crate::addr::*;
+use crate::agent::*;
+use crate::attributes::*;
+use crate::changereq::*;
+use crate::error::*;
+use crate::error_code::*;
+use crate::fingerprint::*;
+use crate::message::*;
+use crate::textattrs::*;
+use crate::uattrs::*;
+use crate::xoraddr::*;
+
+use util::Conn;
+
+use std::marker::{Send, Sync};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+
+const MAX_MESSAGE_SIZE: usize = 1500;
+
+/// AlternateConns are the sockets of a server on its alternate IP address and
+/// alternate port, they enable NAT behavior discovery (RFC 5780).
+pub struct AlternateConns {
+    /// alternate_port is bound to the primary IP address and the alternate port.
+    pub alternate_port: Arc<dyn Conn + Send + Sync>,
+    /// alternate_ip is bound to the alternate IP address and the primary port.
+    pub alternate_ip: Arc<dyn Conn + Send + Sync>,
+    /// alternate_ip_port is bound to the alternate IP address and port.
+    pub alternate_ip_port: Arc<dyn Conn + Send + Sync>,
+}
+
+/// ServerConfig configures a STUN server.
+pub struct ServerConfig {
+    /// conn is bound to the primary IP address and port of the server.
+    pub conn: Arc<dyn Conn + Send + Sync>,
+    /// alternate_conns make the server answer CHANGE-REQUEST and add
+    /// OTHER-ADDRESS and RESPONSE-ORIGIN to its responses (RFC 5780). All
+    /// sockets must then be bound to specific IP addresses.
+    pub alternate_conns: Option<AlternateConns>,
+    /// software is sent in the SOFTWARE attribute of the responses, if not empty.
+    pub software: String,
+}
+
+struct ServerConn {
+    conn: Arc<dyn Conn + Send + Sync>,
+    addr: SocketAddr,
+}
+
+// the sockets of a server, indexed by ALTERNATE_IP | ALTERNATE_PORT
+const ALTERNATE_PORT: usize = 0b01;
+const ALTERNATE_IP: usize = 0b10;
+
+/// Server answers STUN Binding requests with the XOR-MAPPED-ADDRESS of the
+/// client (RFC 5389), and with alternate sockets, supports the NAT behavior
+/// discovery of RFC 5780.
+pub struct Server {
+    conns: Arc<Vec<ServerConn>>,
+    close_tx: Mutex<Option<watch::Sender<bool>>>,
+}
+
+impl Server {
+    /// new creates a STUN server and starts serving the sockets of config.
+    pub async fn new(config: ServerConfig) -> Result<Self> {
+        let mut conns = vec![ServerConn {
+            addr: config.conn.local_addr().await?,
+            conn: config.conn,
+        }];
+        if let Some(alternate_conns) = config.alternate_conns {
+            for conn in [
+                alternate_conns.alternate_port,
+                alternate_conns.alternate_ip,
+                alternate_conns.alternate_ip_port,
+            ] {
+                conns.push(ServerConn {
+                    addr: conn.local_addr().await?,
+                    conn,
+                });
+            }
+            validate_alternate_addrs(&conns)?;
+        }
+
+        let software = if config.software.is_empty() {
+            None
+        } else {
+            Some(Software::new(ATTR_SOFTWARE, config.software))
+        };
+
+        let conns = Arc::new(conns);
+        let software = Arc::new(software);
+        let (close_tx, close_rx) = watch::channel(false);
+        for index in 0..conns.len() {
+            let conns = Arc::clone(&conns);
+            let software = Arc::clone(&software);
+            let mut close_rx = close_rx.clone();
+
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; MAX_MESSAGE_SIZE];
+                loop {
+                    let (n, src_addr) = tokio::select! {
+                        result = conns[index].conn.recv_from(&mut buf) => match result {
+                            Ok(v) => v,
+                            Err(_) => break,
+                        },
+                        _ = close_rx.changed() => break,
+                    };
+
+                    // requests which can't be answered are dropped
+                    let _ = Server::handle(&conns, index, &software, &buf[..n], src_addr).await;
+                }
+            });
+        }
+
+        Ok(Server {
+            conns,
+            close_tx: Mutex::new(Some(close_tx)),
+        })
+    }
+
+    /// other_address returns the alternate IP address and port of the
+    /// server, if it supports RFC 5780.
+    pub fn other_address(&self) -> Option<SocketAddr> {
+        self.conns
+            .get(ALTERNATE_IP | ALTERNATE_PORT)
+            .map(|server_conn| server_conn.addr)
+    }
+
+    /// close stops the server and closes its sockets.
+    pub async fn close(&self) -> Result<()> {
+        let close_tx = self.close_tx.lock().await.take();
+        if let Some(close_tx) = close_tx {
+            let _ = close_tx.send(true);
+        } else {
+            return Err(Error::ErrServerClosed);
+        }
+
+        for server_conn in self.conns.iter() {
+            server_conn.conn.close().await?;
+        }
+
+        Ok(())
+    }
+
+    // handle answers a message received on conns[index] from src_addr.
+    async fn handle(
+        conns: &[ServerConn],
+        index: usize,
+        software: &Option<Software>,
+        buf: &[u8],
+        src_addr: SocketAddr,
+    ) -> Result<()> {
+        if let Some((res, response_index)) =
+            Server::build_response_to(conns, index, software, buf, src_addr)?
+        {
+            conns[response_index]
+                .conn
+                .send_to(&res.raw, src_addr)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // build_response_to returns the response to a Binding request and the
+    // index of the socket it must be sent from.
+    fn build_response_to(
+        conns: &[ServerConn],
+        index: usize,
+        software: &Option<Software>,
+        buf: &[u8],
+        src_addr: SocketAddr,
+    ) -> Result<Option<(Message, usize)>> {
+        if !is_message(buf) {
+            return Ok(None);
+        }
+        let mut m = Message::new();
+        m.raw = buf.to_vec();
+        m.decode()?;
+        if m.typ != BINDING_REQUEST {
+            return Ok(None);
+        }
+
+        let mut change_request = ChangeRequest::default();
+        if m.contains(ATTR_CHANGE_REQUEST) {
+            if conns.len() == 1 {
+                // CHANGE-REQUEST is comprehension-required, but the server
+                // has no alternate address
+                let res = build_response(
+                    &m,
+                    BINDING_ERROR,
+                    vec![
+                        Box::new(CODE_UNKNOWN_ATTRIBUTE),
+                        Box::new(UnknownAttributes(vec![ATTR_CHANGE_REQUEST])),
+                    ],
+                    &[],
+                    software,
+                )?;
+                return Ok(Some((res, index)));
+            }
+            if change_request.get_from(&m).is_err() {
+                let res = build_response(
+                    &m,
+                    BINDING_ERROR,
+                    vec![Box::new(CODE_BAD_REQUEST)],
+                    &[],
+                    software,
+                )?;
+                return Ok(Some((res, index)));
+            }
+        }
+
+        let attrs: Vec<Box<dyn Setter>> = vec![Box::new(XorMappedAddress {
+            ip: src_addr.ip(),
+            port: src_addr.port(),
+        })];
+        let mut addrs = vec![];
+        let mut response_index = index;
+        if conns.len() > 1 {
+            if change_request.change_ip {
+                response_index ^= ALTERNATE_IP;
+            }
+            if change_request.change_port {
+                response_index ^= ALTERNATE_PORT;
+            }
+
+            addrs.push((
+                ATTR_OTHER_ADDRESS,
+                conns[index ^ (ALTERNATE_IP | ALTERNATE_PORT)].addr,
+            ));
+            addrs.push((ATTR_RESPONSE_ORIGIN, conns[response_index].addr));
+        }
+
+        let res = build_response(&m, BINDING_SUCCESS, attrs, &addrs, software)?;
+        Ok(Some((res, response_index)))
+    }
+}
+
+// validate_alternate_addrs checks that the sockets of a RFC 5780 server are
+// bound to the two IP addresses and two ports of the server.
+fn validate_alternate_addrs(conns: &[ServerConn]) -> Result<()> {
+    let primary = conns[0].addr;
+    let alternate = conns[ALTERNATE_IP | ALTERNATE_PORT].addr;
+    if primary.ip().is_unspecified()
+        || alternate.ip().is_unspecified()
+        || primary.ip() == alternate.ip()
+        || primary.port() == alternate.port()
+    {
+        return Err(Error::ErrInvalidAlternateAddress);
+    }
+
+    for (index, server_conn) in conns.iter().enumerate() {
+        let ip = if index & ALTERNATE_IP != 0 {
+            alternate.ip()
+        } else {
+            primary.ip()
+        };
+        let port = if index & ALTERNATE_PORT != 0 {
+            alternate.port()
+        } else {
+            primary.port()
+        };
+        if server_conn.addr != SocketAddr::new(ip, port) {
+            return Err(Error::ErrInvalidAlternateAddress);
+        }
+    }
+
+    Ok(())
+}
+
+// build_response builds a response of type typ to m with attrs, then the
+// OTHER-ADDRESS and RESPONSE-ORIGIN attributes of addrs, SOFTWARE and
+// FINGERPRINT.
+fn build_response(
+    m: &Message,
+    typ: MessageType,
+    mut attrs: Vec<Box<dyn Setter>>,
+    addrs: &[(AttrType, SocketAddr)],
+    software: &Option<Software>,
+) -> Result<Message> {
+    let mut setters: Vec<Box<dyn Setter>> =
+        vec![Box::new(TransactionId(m.transaction_id.0)), Box::new(typ)];
+    setters.append(&mut attrs);
+
+    let mut res = Message::new();
+    res.build(&setters)?;
+    // OtherAddress and ResponseOrigin are aliases of MappedAddress, which
+    // adds MAPPED-ADDRESS as a Setter
+    for (t, addr) in addrs {
+        MappedAddress {
+            ip: addr.ip(),
+            port: addr.port(),
+        }
+        .add_to_as(&mut res, *t)?;
+    }
+    if let Some(software) = software {
+        software.add_to(&mut res)?;
+    }
+    FINGERPRINT.add_to(&mut res)?;
+
+    Ok(res)
+}
@@ -0,0 +1,171 @@
+use super::*;
+
+use std::net::Ipv4Addr;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout, Duration};
+
+async fn bind(addr: &str) -> Result<Arc<dyn Conn + Send + Sync>> {
+    Ok(Arc::new(UdpSocket::bind(addr).await?))
+}
+
+async fn binding_request(
+    client: &UdpSocket,
+    server_addr: SocketAddr,
+    change_request: Option<ChangeRequest>,
+) -> Result<(Message, SocketAddr)> {
+    let mut req = Message::new();
+    req.build(&[Box::new(TransactionId::new()), Box::new(BINDING_REQUEST)])?;
+    if let Some(change_request) = change_request {
+        change_request.add_to(&mut req)?;
+    }
+    client.send_to(&req.raw, server_addr).await?;
+
+    let mut buf = vec![0u8; MAX_MESSAGE_SIZE];
+    let (n, from) = timeout(Duration::from_secs(5), client.recv_from(&mut buf))
+        .await
+        .expect("no response from server")?;
+
+    let mut res = Message::new();
+    res.raw = buf[..n].to_vec();
+    res.decode()?;
+    assert_eq!(res.transaction_id, req.transaction_id);
+    Ok((res, from))
+}
+
+fn get_addr(m: &Message, t: AttrType) -> Result<SocketAddr> {
+    let mut addr = MappedAddress::default();
+    addr.get_from_as(m, t)?;
+    Ok(SocketAddr::new(addr.ip, addr.port))
+}
+
+#[tokio::test]
+async fn test_server_binding() -> Result<()> {
+    let conn = bind("127.0.0.1:0").await?;
+    let server_addr = conn.local_addr().await?;
+    let server = Server::new(ServerConfig {
+        conn,
+        alternate_conns: None,
+        software: "test".to_owned(),
+    })
+    .await?;
+    assert_eq!(server.other_address(), None);
+
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let (res, from) = binding_request(&client, server_addr, None).await?;
+    assert_eq!(res.typ, BINDING_SUCCESS);
+    assert_eq!(from, server_addr);
+
+    let mut xor_addr = XorMappedAddress::default();
+    xor_addr.get_from(&res)?;
+    assert_eq!(
+        SocketAddr::new(xor_addr.ip, xor_addr.port),
+        client.local_addr()?
+    );
+    assert!(!res.contains(ATTR_OTHER_ADDRESS));
+    assert!(!res.contains(ATTR_RESPONSE_ORIGIN));
+    assert_eq!(res.get(ATTR_SOFTWARE)?, b"test".to_vec());
+    FINGERPRINT.check(&res)?;
+
+    // CHANGE-REQUEST is not supported without alternate address
+    let (res, _) = binding_request(&client, server_addr, Some(ChangeRequest::default())).await?;
+    assert_eq!(res.typ, BINDING_ERROR);
+    let mut code = ErrorCodeAttribute::default();
+    code.get_from(&res)?;
+    assert_eq!(code.code, CODE_UNKNOWN_ATTRIBUTE);
+    let mut unknown = UnknownAttributes(vec![]);
+    unknown.get_from(&res)?;
+    assert_eq!(unknown.0, vec![ATTR_CHANGE_REQUEST]);
+
+    server.close().await?;
+    assert!(server.close().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_alternate_address() -> Result<()> {
+    // 127.0.0.2 is not available on every platform
+    let alternate_ip = match UdpSocket::bind("127.0.0.2:0").await {
+        Ok(conn) => conn,
+        Err(_) => return Ok(()),
+    };
+    let alternate_port = conn_port(&alternate_ip)?;
+    drop(alternate_ip);
+
+    let conn = bind("127.0.0.1:0").await?;
+    let primary = conn.local_addr().await?;
+    let alternate = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 2).into(), alternate_port);
+    let addrs = [
+        primary,
+        SocketAddr::new(primary.ip(), alternate.port()),
+        SocketAddr::new(alternate.ip(), primary.port()),
+        alternate,
+    ];
+
+    let server = Server::new(ServerConfig {
+        conn,
+        alternate_conns: Some(AlternateConns {
+            alternate_port: bind(&addrs[1].to_string()).await?,
+            alternate_ip: bind(&addrs[2].to_string()).await?,
+            alternate_ip_port: bind(&addrs[3].to_string()).await?,
+        }),
+        software: String::new(),
+    })
+    .await?;
+    assert_eq!(server.other_address(), Some(alternate));
+
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    for (change_ip, change_port, expected) in [
+        (false, false, addrs[0]),
+        (false, true, addrs[1]),
+        (true, false, addrs[2]),
+        (true, true, addrs[3]),
+    ] {
+        let (res, from) = binding_request(
+            &client,
+            primary,
+            Some(ChangeRequest {
+                change_ip,
+                change_port,
+            }),
+        )
+        .await?;
+        assert_eq!(res.typ, BINDING_SUCCESS);
+        assert_eq!(from, expected);
+        assert_eq!(get_addr(&res, ATTR_RESPONSE_ORIGIN)?, expected);
+        assert_eq!(get_addr(&res, ATTR_OTHER_ADDRESS)?, alternate);
+        assert!(!res.contains(ATTR_MAPPED_ADDRESS));
+        assert!(!res.contains(ATTR_SOFTWARE));
+    }
+
+    // requests to the alternate address advertise the primary address
+    let (res, from) = binding_request(&client, alternate, None).await?;
+    assert_eq!(from, alternate);
+    assert_eq!(get_addr(&res, ATTR_OTHER_ADDRESS)?, primary);
+
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_invalid_alternate_address() -> Result<()> {
+    let conn = bind("127.0.0.1:0").await?;
+    let result = Server::new(ServerConfig {
+        conn,
+        alternate_conns: Some(AlternateConns {
+            alternate_port: bind("127.0.0.1:0").await?,
+            alternate_ip: bind("127.0.0.1:0").await?,
+            alternate_ip_port: bind("127.0.0.1:0").await?,
+        }),
+        software: String::new(),
+    })
+    .await;
+    assert!(matches!(result, Err(Error::ErrInvalidAlternateAddress)));
+
+    Ok(())
+}
+
+fn conn_port(conn: &UdpSocket) -> Result<u16> {
+    Ok(conn.local_addr()?.port())
+}
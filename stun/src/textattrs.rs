@@ -28,6 +28,19 @@ pub type Realm = TextAttribute;
 // RFC 5389 Section 15.8
 pub type Nonce = TextAttribute;
 
+// NONCE_COOKIE starts the NONCE of the servers advertising a security
+// feature set, encoded in the next 4 characters.
+//
+// RFC 8489 Section 9.2
+pub const NONCE_COOKIE: &str = "obMatJos2";
+const SECURITY_FEATURES_LEN: usize = 4;
+
+// SECURITY_FEATURE_PASSWORD_ALGORITHMS means the server supports the
+// PASSWORD-ALGORITHMS negotiation.
+pub const SECURITY_FEATURE_PASSWORD_ALGORITHMS: u32 = 1 << 23;
+// SECURITY_FEATURE_USERNAME_ANONYMITY means the server supports USERHASH.
+pub const SECURITY_FEATURE_USERNAME_ANONYMITY: u32 = 1 << 22;
+
 // Software is SOFTWARE attribute.
 //
 // RFC 5389 Section 15.10
@@ -92,4 +105,32 @@ impl TextAttribute {
         let text = String::from_utf8(a)?;
         Ok(TextAttribute { attr, text })
     }
+
+    // new_nonce_with_security_features returns a NONCE advertising the security
+    // features, followed by nonce.
+    pub fn new_nonce_with_security_features(features: u32, nonce: &str) -> Self {
+        let features = base64::encode(&features.to_be_bytes()[1..]);
+        TextAttribute {
+            attr: ATTR_NONCE,
+            text: format!("{}{}{}", NONCE_COOKIE, features, nonce),
+        }
+    }
+
+    // security_features returns the security features advertised by a NONCE,
+    // none if it does not start with NONCE_COOKIE.
+    pub fn security_features(&self) -> u32 {
+        let features = match self
+            .text
+            .strip_prefix(NONCE_COOKIE)
+            .and_then(|rest| rest.get(..SECURITY_FEATURES_LEN))
+        {
+            Some(features) => base64::decode(features).unwrap_or_default(),
+            None => return 0,
+        };
+        if features.len() != 3 {
+            return 0;
+        }
+
+        u32::from_be_bytes([0, features[0], features[1], features[2]])
+    }
 }
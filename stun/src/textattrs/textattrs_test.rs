@@ -315,3 +315,28 @@ fn test_nonce_add_to() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_nonce_security_features() -> Result<()> {
+    let n = TextAttribute::new_nonce_with_security_features(
+        SECURITY_FEATURE_PASSWORD_ALGORITHMS | SECURITY_FEATURE_USERNAME_ANONYMITY,
+        "f//499k954d6OL34",
+    );
+    assert_eq!(n.text, "obMatJos2wAAAf//499k954d6OL34");
+    assert_eq!(
+        n.security_features(),
+        SECURITY_FEATURE_PASSWORD_ALGORITHMS | SECURITY_FEATURE_USERNAME_ANONYMITY
+    );
+
+    for text in [
+        "f//499k954d6OL34",
+        "obMatJos2",
+        "obMatJos2g\u{e9}",
+        "obMatJos2!!!!",
+    ] {
+        let n = TextAttribute::new(ATTR_NONCE, text.to_owned());
+        assert_eq!(n.security_features(), 0, "{}", text);
+    }
+
+    Ok(())
+}
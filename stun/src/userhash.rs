@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod userhash_test;
+
+use crate::attributes::*;
+use crate::checks::*;
+use crate::error::*;
+use crate::integrity::CREDENTIALS_SEP;
+use crate::message::*;
+
+use ring::digest;
+use std::fmt;
+
+const USER_HASH_SIZE: usize = 32;
+
+// UserHash represents USERHASH attribute, which replaces USERNAME when the
+// server supports username anonymity.
+//
+// RFC 8489 Section 14.4
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct UserHash(pub Vec<u8>);
+
+impl fmt::Display for UserHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x?}", self.0)
+    }
+}
+
+impl Setter for UserHash {
+    // add_to adds USERHASH attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        check_size(ATTR_USER_HASH, self.0.len(), USER_HASH_SIZE)?;
+        m.add(ATTR_USER_HASH, &self.0);
+        Ok(())
+    }
+}
+
+impl Getter for UserHash {
+    // get_from decodes USERHASH from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_USER_HASH)?;
+        check_size(ATTR_USER_HASH, v.len(), USER_HASH_SIZE)?;
+        self.0 = v;
+        Ok(())
+    }
+}
+
+impl UserHash {
+    // new returns the USERHASH of username in realm. Username and realm must
+    // be SASL-prepared.
+    pub fn new(username: &str, realm: &str) -> Self {
+        let s = format!("{}{}{}", username, CREDENTIALS_SEP, realm);
+        UserHash(
+            digest::digest(&digest::SHA256, s.as_bytes())
+                .as_ref()
+                .to_vec(),
+        )
+    }
+}
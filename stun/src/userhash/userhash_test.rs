@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn test_user_hash() -> Result<()> {
+    // RFC 8489 Appendix B.1
+    let h = UserHash::new(
+        "\u{30de}\u{30c8}\u{30ea}\u{30c3}\u{30af}\u{30b9}",
+        "example.org",
+    );
+    assert_eq!(
+        h.0,
+        vec![
+            0x4a, 0x3c, 0xf3, 0x8f, 0xef, 0x69, 0x92, 0xbd, 0xa9, 0x52, 0xc6, 0x78, 0x04, 0x17,
+            0xda, 0x0f, 0x24, 0x81, 0x94, 0x15, 0x56, 0x9e, 0x60, 0xb2, 0x05, 0xc4, 0x6e, 0x41,
+            0x40, 0x7f, 0x17, 0x04,
+        ]
+    );
+
+    let mut m = Message::new();
+    h.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    let mut got = UserHash::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, h);
+
+    let mut m = Message::new();
+    m.add(ATTR_USER_HASH, &[1, 2, 3, 4]);
+    assert_eq!(got.get_from(&m), Err(Error::ErrAttributeSizeInvalid));
+    assert!(UserHash(vec![1]).add_to(&mut m).is_err());
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use turn::auth::*;
+use turn::policy::PolicyConfig;
 use turn::relay::relay_static::*;
 use turn::server::{config::*, *};
 use turn::Error;
@@ -122,9 +123,12 @@ async fn main() -> Result<(), Error> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: realm.to_owned(),
         auth_handler: Arc::new(MyAuthHandler::new(cred_map)),
         channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig::default(),
+        event_handler: None,
     })
     .await?;
 
@@ -3,6 +3,10 @@ mod allocation_manager_test;
 
 use super::*;
 use crate::error::*;
+use crate::events::EventHandler;
+use crate::policy::Policy;
+use crate::proto::connid::ConnectionId;
+use crate::proto::reqfamily::{RequestedAddressFamily, REQUESTED_FAMILY_IPV4};
 use crate::relay::*;
 
 use futures::future;
@@ -13,13 +17,18 @@ use util::Conn;
 // ManagerConfig a bag of config params for Manager.
 pub struct ManagerConfig {
     pub relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+    pub policy: Arc<Policy>,
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 // Manager is used to hold active allocations
 pub struct Manager {
     allocations: AllocationMap,
     reservations: Arc<Mutex<HashMap<String, u16>>>,
+    peer_connections: PeerConnectionMap,
     relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+    policy: Arc<Policy>,
+    event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 impl Manager {
@@ -28,10 +37,18 @@ impl Manager {
         Manager {
             allocations: Arc::new(Mutex::new(HashMap::new())),
             reservations: Arc::new(Mutex::new(HashMap::new())),
+            peer_connections: Arc::new(Mutex::new(HashMap::new())),
             relay_addr_generator: config.relay_addr_generator,
+            policy: config.policy,
+            event_handler: config.event_handler,
         }
     }
 
+    // policy returns the policy the allocations are subject to
+    pub fn policy(&self) -> &Arc<Policy> {
+        &self.policy
+    }
+
     // Close closes the manager and closes all allocations it manages
     pub async fn close(&self) -> Result<()> {
         let allocations = self.allocations.lock().await;
@@ -41,13 +58,31 @@ impl Manager {
         Ok(())
     }
 
+    // event_handler returns the handler notified of the events of the allocations
+    pub fn event_handler(&self) -> Option<&Arc<dyn EventHandler + Send + Sync>> {
+        self.event_handler.as_ref()
+    }
+
+    // allocations_info returns a snapshot of every allocation
+    pub async fn allocations_info(&self) -> Vec<AllocationInfo> {
+        let allocations = self.allocations.lock().await;
+        allocations.values().map(|a| a.info()).collect()
+    }
+
     // get_allocation fetches the allocation matching the passed FiveTuple
     pub async fn get_allocation(&self, five_tuple: &FiveTuple) -> Option<Arc<Allocation>> {
         let allocations = self.allocations.lock().await;
         allocations.get(five_tuple).map(Arc::clone)
     }
 
-    // create_allocation creates a new allocation and starts relaying
+    // supports_family returns whether the relay address generator allocates relayed
+    // addresses of family
+    pub fn supports_family(&self, family: RequestedAddressFamily) -> bool {
+        self.relay_addr_generator
+            .supports_family(family == REQUESTED_FAMILY_IPV4)
+    }
+
+    // create_allocation creates a new allocation with an IPv4 relayed address and starts relaying
     pub async fn create_allocation(
         &self,
         five_tuple: FiveTuple,
@@ -55,6 +90,32 @@ impl Manager {
         requested_port: u16,
         lifetime: Duration,
         username: Username,
+    ) -> Result<Arc<Allocation>> {
+        self.create_allocation_with_family(
+            five_tuple,
+            turn_socket,
+            requested_port,
+            lifetime,
+            username,
+            REQUESTED_FAMILY_IPV4,
+            false,
+        )
+        .await
+    }
+
+    // create_allocation_with_family creates a new allocation with a relayed address of family
+    // and starts relaying. If additional_ipv6 is set, the allocation gets an IPv6 relayed
+    // address as well (RFC 8656), unless the relay address generator fails to allocate it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_allocation_with_family(
+        &self,
+        five_tuple: FiveTuple,
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        requested_port: u16,
+        lifetime: Duration,
+        username: Username,
+        family: RequestedAddressFamily,
+        additional_ipv6: bool,
     ) -> Result<Arc<Allocation>> {
         if lifetime == Duration::from_secs(0) {
             return Err(Error::ErrLifetimeZero);
@@ -64,10 +125,24 @@ impl Manager {
             return Err(Error::ErrDupeFiveTuple);
         }
 
-        let (relay_socket, relay_addr) = self
+        let use_ipv4 = family == REQUESTED_FAMILY_IPV4;
+        if !self.relay_addr_generator.supports_family(use_ipv4) {
+            return Err(Error::ErrAddressFamilyNotSupported);
+        }
+
+        self.policy
+            .acquire_allocation(&username.text, five_tuple.src_addr)?;
+        let (relay_socket, relay_addr) = match self
             .relay_addr_generator
-            .allocate_conn(true, requested_port)
-            .await?;
+            .allocate_conn(use_ipv4, requested_port)
+            .await
+        {
+            Ok(v) => v,
+            Err(err) => {
+                self.policy.release_allocation(&username.text);
+                return Err(err);
+            }
+        };
         let mut a = Allocation::new(
             turn_socket,
             relay_socket,
@@ -76,6 +151,16 @@ impl Manager {
             username,
         );
         a.allocations = Some(Arc::clone(&self.allocations));
+        a.policy = Some(Arc::clone(&self.policy));
+        a.rate_limiter = self.policy.rate_limiter();
+        a.event_handler = self.event_handler.clone();
+
+        if additional_ipv6 && self.relay_addr_generator.supports_family(false) {
+            match self.relay_addr_generator.allocate_conn(false, 0).await {
+                Ok(v) => a.additional_relay = Some(v),
+                Err(err) => log::warn!("failed to allocate additional IPv6 relay: {}", err),
+            }
+        }
 
         log::debug!("listening on relay addr: {:?}", a.relay_addr);
         a.start(lifetime).await;
@@ -87,9 +172,107 @@ impl Manager {
             allocations.insert(five_tuple, Arc::clone(&a));
         }
 
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_allocation_created(&a.info());
+        }
+
         Ok(a)
     }
 
+    // create_tcp_allocation creates a new TCP allocation (RFC 6062) with a relayed address of
+    // family and starts accepting connections from peers
+    pub async fn create_tcp_allocation(
+        &self,
+        five_tuple: FiveTuple,
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        lifetime: Duration,
+        username: Username,
+        family: RequestedAddressFamily,
+    ) -> Result<Arc<Allocation>> {
+        if lifetime == Duration::from_secs(0) {
+            return Err(Error::ErrLifetimeZero);
+        }
+
+        if self.get_allocation(&five_tuple).await.is_some() {
+            return Err(Error::ErrDupeFiveTuple);
+        }
+
+        let use_ipv4 = family == REQUESTED_FAMILY_IPV4;
+        if !self.relay_addr_generator.supports_family(use_ipv4) {
+            return Err(Error::ErrAddressFamilyNotSupported);
+        }
+
+        self.policy
+            .acquire_allocation(&username.text, five_tuple.src_addr)?;
+        let (listener, listener_addr, relay_addr) = match self
+            .relay_addr_generator
+            .allocate_listener(use_ipv4, 0)
+            .await
+        {
+            Ok((listener, relay_addr)) => match listener.local_addr() {
+                Ok(listener_addr) => (listener, listener_addr, relay_addr),
+                Err(err) => {
+                    self.policy.release_allocation(&username.text);
+                    return Err(err.into());
+                }
+            },
+            Err(err) => {
+                self.policy.release_allocation(&username.text);
+                return Err(err);
+            }
+        };
+        let mut a = Allocation::new_tcp(
+            turn_socket,
+            listener_addr,
+            relay_addr,
+            five_tuple.clone(),
+            username,
+            Arc::clone(&self.peer_connections),
+        );
+        a.allocations = Some(Arc::clone(&self.allocations));
+        a.policy = Some(Arc::clone(&self.policy));
+        a.rate_limiter = self.policy.rate_limiter();
+        a.event_handler = self.event_handler.clone();
+
+        log::debug!("listening on tcp relay addr: {:?}", a.relay_addr);
+        a.start(lifetime).await;
+        a.tcp_connection_handler(listener);
+
+        let a = Arc::new(a);
+        {
+            let mut allocations = self.allocations.lock().await;
+            allocations.insert(five_tuple, Arc::clone(&a));
+        }
+
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_allocation_created(&a.info());
+        }
+
+        Ok(a)
+    }
+
+    // take_peer_connection removes the peer connection waiting for a ConnectionBind with the
+    // given CONNECTION-ID, if it belongs to an allocation of username, and returns it with
+    // its allocation
+    pub async fn take_peer_connection(
+        &self,
+        id: ConnectionId,
+        username: &str,
+    ) -> Option<(PeerConnection, Arc<Allocation>)> {
+        let five_tuple = {
+            let peer_connections = self.peer_connections.lock().await;
+            peer_connections.get(&id)?.five_tuple.clone()
+        };
+
+        let a = self.get_allocation(&five_tuple).await?;
+        if a.username.text != username {
+            return None;
+        }
+
+        let mut peer_connections = self.peer_connections.lock().await;
+        peer_connections.remove(&id).map(|c| (c, a))
+    }
+
     // delete_allocation removes an allocation
     pub async fn delete_allocation(&self, five_tuple: &FiveTuple) {
         let allocation = self.allocations.lock().await.remove(five_tuple);
@@ -156,9 +339,12 @@ impl Manager {
         reservations.get(reservation_token).copied()
     }
 
-    // get_random_even_port returns a random un-allocated udp4 port
-    pub async fn get_random_even_port(&self) -> Result<u16> {
-        let (_, addr) = self.relay_addr_generator.allocate_conn(true, 0).await?;
+    // get_random_even_port returns a random un-allocated udp port of family
+    pub async fn get_random_even_port(&self, family: RequestedAddressFamily) -> Result<u16> {
+        let (_, addr) = self
+            .relay_addr_generator
+            .allocate_conn(family == REQUESTED_FAMILY_IPV4, 0)
+            .await?;
         Ok(addr.port())
     }
 }
@@ -13,6 +13,8 @@ fn new_test_manager() -> Manager {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        policy: Arc::new(Policy::default()),
+        event_handler: None,
     };
     Manager::new(config)
 }
@@ -81,7 +83,7 @@ async fn test_packet_handler() -> Result<()> {
         a.add_channel_bind(channel_bind.clone(), DEFAULT_LIFETIME)
             .await?;
 
-        a.relay_socket.local_addr().await?.port()
+        a.relay_addr.port()
     };
 
     let relay_addr_with_host_str = format!("127.0.0.1:{}", port);
@@ -5,12 +5,16 @@ pub mod allocation_manager;
 pub mod channel_bind;
 pub mod five_tuple;
 pub mod permission;
+pub mod tcp_relay;
 
 use crate::error::*;
-use crate::proto::{chandata::*, channum::*, data::*, peeraddr::*, *};
+use crate::events::EventHandler;
+use crate::policy::{Policy, RateLimiter};
+use crate::proto::{chandata::*, channum::*, data::*, peeraddr::*, reqfamily::*, *};
 use channel_bind::*;
 use five_tuple::*;
 use permission::*;
+use tcp_relay::*;
 
 use stun::{agent::*, message::*, textattrs::Username};
 
@@ -20,7 +24,10 @@ use std::{
     collections::HashMap,
     marker::{Send, Sync},
     net::SocketAddr,
-    sync::{atomic::AtomicBool, atomic::Ordering, Arc, Mutex as StdMutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
+    },
 };
 use tokio::{
     sync::{mpsc, Mutex},
@@ -31,18 +38,72 @@ const RTP_MTU: usize = 1500;
 
 pub type AllocationMap = Arc<Mutex<HashMap<FiveTuple, Arc<Allocation>>>>;
 
+// AllocationInfo is a snapshot of an allocation and its counters
+#[derive(Debug, Clone)]
+pub struct AllocationInfo {
+    pub five_tuple: FiveTuple,
+    pub username: String,
+    pub relay_addr: SocketAddr,
+    // IPv6 relayed address of a dual-stack allocation (RFC 8656)
+    pub additional_relay_addr: Option<SocketAddr>,
+    // transport protocol between the server and the peers
+    pub protocol: Protocol,
+    // time since the allocation was created
+    pub age: Duration,
+    // data relayed from the client to peers, a packet is a datagram for UDP
+    // allocations and a write to the connection for TCP allocations
+    pub bytes_to_peers: u64,
+    pub packets_to_peers: u64,
+    // data relayed from peers to the client
+    pub bytes_from_peers: u64,
+    pub packets_from_peers: u64,
+}
+
+// RelayCounters counts the data relayed by an allocation
+#[derive(Default)]
+pub(crate) struct RelayCounters {
+    bytes_to_peers: AtomicU64,
+    packets_to_peers: AtomicU64,
+    bytes_from_peers: AtomicU64,
+    packets_from_peers: AtomicU64,
+}
+
+impl RelayCounters {
+    pub(crate) fn add_to_peers(&self, n: usize) {
+        self.bytes_to_peers.fetch_add(n as u64, Ordering::Relaxed);
+        self.packets_to_peers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_from_peers(&self, n: usize) {
+        self.bytes_from_peers.fetch_add(n as u64, Ordering::Relaxed);
+        self.packets_from_peers.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 // Allocation is tied to a FiveTuple and relays traffic
 // use create_allocation and get_allocation to operate
 pub struct Allocation {
     protocol: Protocol,
     turn_socket: Arc<dyn Conn + Send + Sync>,
     pub(crate) relay_addr: SocketAddr,
-    pub(crate) relay_socket: Arc<dyn Conn + Send + Sync>,
+    // the relay socket of a UDP allocation
+    pub(crate) relay_socket: Option<Arc<dyn Conn + Send + Sync>>,
+    // the IPv6 relay socket and relayed address of a dual-stack UDP allocation (RFC 8656)
+    pub(crate) additional_relay: Option<(Arc<dyn Conn + Send + Sync>, SocketAddr)>,
+    // the relay of a TCP allocation
+    pub(crate) tcp_relay: Option<Arc<TcpRelay>>,
     five_tuple: FiveTuple,
     username: Username,
     permissions: Arc<Mutex<HashMap<String, Permission>>>,
     channel_bindings: Arc<Mutex<HashMap<ChannelNumber, ChannelBind>>>,
     pub(crate) allocations: Option<AllocationMap>,
+    // the policy which counts the allocation in the quota of its username
+    pub(crate) policy: Option<Arc<Policy>>,
+    // limits the relayed data, if the policy has a bandwidth limit
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
+    pub(crate) counters: Arc<RelayCounters>,
+    created_at: Instant,
     reset_tx: StdMutex<Option<mpsc::Sender<Duration>>>,
     timer_expired: Arc<AtomicBool>,
     closed: AtomicBool, // Option<mpsc::Receiver<()>>,
@@ -65,18 +126,115 @@ impl Allocation {
             protocol: PROTO_UDP,
             turn_socket,
             relay_addr,
-            relay_socket,
+            relay_socket: Some(relay_socket),
+            additional_relay: None,
+            tcp_relay: None,
+            five_tuple,
+            username,
+            permissions: Arc::new(Mutex::new(HashMap::new())),
+            channel_bindings: Arc::new(Mutex::new(HashMap::new())),
+            allocations: None,
+            policy: None,
+            rate_limiter: None,
+            event_handler: None,
+            counters: Arc::new(RelayCounters::default()),
+            created_at: Instant::now(),
+            reset_tx: StdMutex::new(None),
+            timer_expired: Arc::new(AtomicBool::new(false)),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    // creates a new instance of a TCP allocation (RFC 6062), listener_addr is the local
+    // address of the listener of the relayed transport address.
+    pub fn new_tcp(
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        listener_addr: SocketAddr,
+        relay_addr: SocketAddr,
+        five_tuple: FiveTuple,
+        username: Username,
+        peer_connections: PeerConnectionMap,
+    ) -> Self {
+        Allocation {
+            protocol: PROTO_TCP,
+            turn_socket,
+            relay_addr,
+            relay_socket: None,
+            additional_relay: None,
+            tcp_relay: Some(Arc::new(TcpRelay::new(listener_addr, peer_connections))),
             five_tuple,
             username,
             permissions: Arc::new(Mutex::new(HashMap::new())),
             channel_bindings: Arc::new(Mutex::new(HashMap::new())),
             allocations: None,
+            policy: None,
+            rate_limiter: None,
+            event_handler: None,
+            counters: Arc::new(RelayCounters::default()),
+            created_at: Instant::now(),
             reset_tx: StdMutex::new(None),
             timer_expired: Arc::new(AtomicBool::new(false)),
             closed: AtomicBool::new(false),
         }
     }
 
+    // protocol returns the transport protocol between the server and the peers
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    // username returns the username the allocation was created with
+    pub fn username(&self) -> &str {
+        &self.username.text
+    }
+
+    // info returns a snapshot of the allocation and its counters
+    pub fn info(&self) -> AllocationInfo {
+        AllocationInfo {
+            five_tuple: self.five_tuple.clone(),
+            username: self.username.text.clone(),
+            relay_addr: self.relay_addr,
+            additional_relay_addr: self.additional_relay.as_ref().map(|(_, addr)| *addr),
+            protocol: self.protocol,
+            age: self.created_at.elapsed(),
+            bytes_to_peers: self.counters.bytes_to_peers.load(Ordering::Relaxed),
+            packets_to_peers: self.counters.packets_to_peers.load(Ordering::Relaxed),
+            bytes_from_peers: self.counters.bytes_from_peers.load(Ordering::Relaxed),
+            packets_from_peers: self.counters.packets_from_peers.load(Ordering::Relaxed),
+        }
+    }
+
+    // has_family returns whether the allocation has a relayed address of family
+    pub fn has_family(&self, family: RequestedAddressFamily) -> bool {
+        RequestedAddressFamily::of(&self.relay_addr) == family
+            || matches!(&self.additional_relay, Some((_, addr)) if RequestedAddressFamily::of(addr) == family)
+    }
+
+    // has_peer_family returns whether the allocation has a relayed address of the address
+    // family of peer
+    pub fn has_peer_family(&self, peer: &SocketAddr) -> bool {
+        self.has_family(RequestedAddressFamily::of(peer))
+    }
+
+    // relay_socket_for returns the relay socket of a UDP allocation which sends to peer
+    pub(crate) fn relay_socket_for(
+        &self,
+        peer: &SocketAddr,
+    ) -> Result<&Arc<dyn Conn + Send + Sync>> {
+        let relay_socket = self
+            .relay_socket
+            .as_ref()
+            .ok_or(Error::ErrNotUdpAllocation)?;
+        if self.relay_addr.is_ipv4() == peer.is_ipv4() {
+            return Ok(relay_socket);
+        }
+
+        match &self.additional_relay {
+            Some((relay_socket, addr)) if addr.is_ipv4() == peer.is_ipv4() => Ok(relay_socket),
+            _ => Err(Error::ErrPeerAddressFamilyMismatch),
+        }
+    }
+
     // has_permission gets the Permission from the allocation
     pub async fn has_permission(&self, addr: &SocketAddr) -> bool {
         let permissions = self.permissions.lock().await;
@@ -97,11 +255,16 @@ impl Allocation {
 
         p.permissions = Some(Arc::clone(&self.permissions));
         p.start(PERMISSION_TIMEOUT).await;
+        let peer = p.addr;
 
         {
             let mut permissions = self.permissions.lock().await;
             permissions.insert(fingerprint, p);
         }
+
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_permission_created(&self.five_tuple, peer);
+        }
     }
 
     // remove_permission removes the net.Addr's fingerprint from the allocation's permissions
@@ -145,11 +308,16 @@ impl Allocation {
         c.channel_bindings = Some(Arc::clone(&self.channel_bindings));
         c.start(lifetime).await;
 
+        let number = c.number;
         {
             let mut channel_bindings = self.channel_bindings.lock().await;
             channel_bindings.insert(c.number, c);
         }
 
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_channel_bound(&self.five_tuple, number.0, peer);
+        }
+
         // Channel binds also refresh permissions.
         self.add_permission(Permission::new(peer)).await;
 
@@ -204,8 +372,27 @@ impl Allocation {
 
         log::trace!("allocation with {} closed!", self.five_tuple);
 
-        let _ = self.turn_socket.close().await;
-        let _ = self.relay_socket.close().await;
+        if let Some(policy) = &self.policy {
+            policy.release_allocation(&self.username.text);
+        }
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_allocation_deleted(&self.info());
+        }
+
+        if let Some(tcp_relay) = &self.tcp_relay {
+            tcp_relay.close(&self.five_tuple).await;
+        }
+
+        // the connection of a TCP or TLS client may outlive its allocation
+        if self.five_tuple.protocol != PROTO_TCP {
+            let _ = self.turn_socket.close().await;
+        }
+        if let Some(relay_socket) = &self.relay_socket {
+            let _ = relay_socket.close().await;
+        }
+        if let Some((relay_socket, _)) = &self.additional_relay {
+            let _ = relay_socket.close().await;
+        }
 
         Ok(())
     }
@@ -261,6 +448,10 @@ impl Allocation {
         if let Some(tx) = reset_tx {
             let _ = tx.send(lifetime).await;
         }
+
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.on_allocation_refreshed(&self.five_tuple, lifetime);
+        }
     }
 
     //  https://tools.ietf.org/html/rfc5766#section-10.3
@@ -283,13 +474,28 @@ impl Allocation {
     //  transport address of the received UDP datagram.  The Data indication
     //  is then sent on the 5-tuple associated with the allocation.
     async fn packet_handler(&self) {
+        if let Some(relay_socket) = &self.relay_socket {
+            self.relay_packet_handler(Arc::clone(relay_socket), self.relay_addr);
+        }
+        if let Some((relay_socket, relay_addr)) = &self.additional_relay {
+            self.relay_packet_handler(Arc::clone(relay_socket), *relay_addr);
+        }
+    }
+
+    // relay_packet_handler relays the datagrams received by relay_socket, one of the relay
+    // sockets of the allocation, to the client
+    fn relay_packet_handler(
+        &self,
+        relay_socket: Arc<dyn Conn + Send + Sync>,
+        relay_addr: SocketAddr,
+    ) {
         let five_tuple = self.five_tuple.clone();
-        let relay_addr = self.relay_addr;
-        let relay_socket = Arc::clone(&self.relay_socket);
         let turn_socket = Arc::clone(&self.turn_socket);
         let allocations = self.allocations.clone();
         let channel_bindings = Arc::clone(&self.channel_bindings);
         let permissions = Arc::clone(&self.permissions);
+        let rate_limiter = self.rate_limiter.clone();
+        let counters = Arc::clone(&self.counters);
 
         tokio::spawn(async move {
             let mut buffer = vec![0u8; RTP_MTU];
@@ -313,6 +519,17 @@ impl Allocation {
                     src_addr
                 );
 
+                if let Some(rate_limiter) = &rate_limiter {
+                    if !rate_limiter.allow(n) {
+                        log::trace!(
+                            "dropping {} bytes from {}, rate limit exceeded",
+                            n,
+                            src_addr
+                        );
+                        continue;
+                    }
+                }
+
                 let cb_number = {
                     let mut cb_number = None;
                     let cbs = channel_bindings.lock().await;
@@ -333,15 +550,16 @@ impl Allocation {
                     };
                     channel_data.encode();
 
-                    if let Err(err) = turn_socket
+                    match turn_socket
                         .send_to(&channel_data.raw, five_tuple.src_addr)
                         .await
                     {
-                        log::error!(
+                        Ok(_) => counters.add_from_peers(n),
+                        Err(err) => log::error!(
                             "Failed to send ChannelData from allocation {} {}",
                             src_addr,
                             err
-                        );
+                        ),
                     }
                 } else {
                     let exist = {
@@ -381,14 +599,13 @@ impl Allocation {
                                 src_addr,
                                 five_tuple.src_addr
                             );
-                            if let Err(err) =
-                                turn_socket.send_to(&msg.raw, five_tuple.src_addr).await
-                            {
-                                log::error!(
+                            match turn_socket.send_to(&msg.raw, five_tuple.src_addr).await {
+                                Ok(_) => counters.add_from_peers(n),
+                                Err(err) => log::error!(
                                     "Failed to send DataIndication from allocation {} {}",
                                     src_addr,
                                     err
-                                );
+                                ),
                             }
                         }
                     } else {
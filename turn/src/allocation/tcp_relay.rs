@@ -0,0 +1,294 @@
+use super::*;
+use crate::policy::RateLimiter;
+use crate::proto::connid::ConnectionId;
+
+use std::collections::HashSet;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+// CONNECTION_BIND_TIMEOUT is how long a peer connection waits for the client to bind a data
+// connection to it before it is closed.
+//
+// RFC 6062 Section 5.3
+pub(crate) const CONNECTION_BIND_TIMEOUT: Duration = Duration::from_secs(30);
+
+// CONNECT_TIMEOUT is how long the server tries to connect to a peer on a Connect request.
+pub(crate) const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// ACCEPT_ERROR_BACKOFF is how long accepting peers pauses after an error, e.g. when out of
+// file descriptors.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+const TCP_BUFFER_SIZE: usize = 16 * 1024;
+
+// PeerConnection is a TCP connection with a peer of a TCP allocation, which waits for the
+// client to bind a data connection to it with a ConnectionBind request.
+pub struct PeerConnection {
+    pub(crate) stream: TcpStream,
+    pub(crate) peer_addr: SocketAddr,
+    pub(crate) five_tuple: FiveTuple,
+}
+
+pub type PeerConnectionMap = Arc<Mutex<HashMap<ConnectionId, PeerConnection>>>;
+
+// TcpRelay is the state of a TCP allocation (RFC 6062). Peers connect to its listener, and
+// the connections to peers are opened from the address of the listener.
+pub(crate) struct TcpRelay {
+    pub(crate) listener_addr: SocketAddr,
+    // peers with a pending or active connection
+    peers: Mutex<HashSet<SocketAddr>>,
+    // transactions of the Connect requests in progress, to recognize retransmissions
+    connecting: Mutex<HashSet<TransactionId>>,
+    // connections waiting for a ConnectionBind, shared by all allocations of a Manager
+    peer_connections: PeerConnectionMap,
+    closed_tx: StdMutex<Option<watch::Sender<()>>>,
+    closed_rx: watch::Receiver<()>,
+}
+
+impl TcpRelay {
+    pub(crate) fn new(listener_addr: SocketAddr, peer_connections: PeerConnectionMap) -> Self {
+        let (closed_tx, closed_rx) = watch::channel(());
+        TcpRelay {
+            listener_addr,
+            peers: Mutex::new(HashSet::new()),
+            connecting: Mutex::new(HashSet::new()),
+            peer_connections,
+            closed_tx: StdMutex::new(Some(closed_tx)),
+            closed_rx,
+        }
+    }
+
+    // add_peer reserves peer for a new connection, it returns false if
+    // there already is a pending or active connection with peer.
+    pub(crate) async fn add_peer(&self, peer: SocketAddr) -> bool {
+        let mut peers = self.peers.lock().await;
+        peers.insert(peer)
+    }
+
+    pub(crate) async fn remove_peer(&self, peer: &SocketAddr) {
+        let mut peers = self.peers.lock().await;
+        peers.remove(peer);
+    }
+
+    // start_connect marks the Connect request transaction_id as in progress, it returns false
+    // if it already is, i.e. the request is a retransmission.
+    pub(crate) async fn start_connect(&self, transaction_id: TransactionId) -> bool {
+        let mut connecting = self.connecting.lock().await;
+        connecting.insert(transaction_id)
+    }
+
+    pub(crate) async fn end_connect(&self, transaction_id: &TransactionId) {
+        let mut connecting = self.connecting.lock().await;
+        connecting.remove(transaction_id);
+    }
+
+    // add_peer_connection stores a connection with a peer under a new CONNECTION-ID until
+    // the client binds it. It is closed if that doesn't happen within CONNECTION_BIND_TIMEOUT.
+    pub(crate) async fn add_peer_connection(
+        self: &Arc<Self>,
+        stream: TcpStream,
+        peer_addr: SocketAddr,
+        five_tuple: FiveTuple,
+    ) -> ConnectionId {
+        let id = {
+            let mut peer_connections = self.peer_connections.lock().await;
+            let mut id = ConnectionId(rand::random());
+            while peer_connections.contains_key(&id) {
+                id = ConnectionId(rand::random());
+            }
+            peer_connections.insert(
+                id,
+                PeerConnection {
+                    stream,
+                    peer_addr,
+                    five_tuple,
+                },
+            );
+            id
+        };
+
+        let tcp_relay = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(CONNECTION_BIND_TIMEOUT).await;
+
+            let pending = {
+                let mut peer_connections = tcp_relay.peer_connections.lock().await;
+                peer_connections.remove(&id)
+            };
+            if let Some(pending) = pending {
+                log::debug!(
+                    "connection {} with {} was not bound in time",
+                    id,
+                    pending.peer_addr
+                );
+                tcp_relay.remove_peer(&pending.peer_addr).await;
+            }
+        });
+
+        id
+    }
+
+    // relay_data_connection relays data between a client data connection and the connection
+    // with its peer, until either of them is closed or the allocation is closed. The
+    // rate_limiter of the allocation slows both directions down if it has a bandwidth limit,
+    // the relayed data is added to its counters.
+    pub(crate) fn relay_data_connection(
+        self: &Arc<Self>,
+        conn: Arc<dyn Conn + Send + Sync>,
+        peer_conn: PeerConnection,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        counters: Arc<RelayCounters>,
+    ) {
+        let tcp_relay = Arc::clone(self);
+        let mut closed_rx = self.closed_rx.clone();
+
+        tokio::spawn(async move {
+            let peer_addr = peer_conn.peer_addr;
+            let (mut peer_rd, mut peer_wr) = peer_conn.stream.into_split();
+
+            let client_to_peer = async {
+                let mut buf = vec![0u8; TCP_BUFFER_SIZE];
+                loop {
+                    let n = match conn.recv(&mut buf).await {
+                        Ok(n) => n,
+                        Err(_) => break,
+                    };
+                    if let Some(rate_limiter) = &rate_limiter {
+                        tokio::time::sleep(rate_limiter.reserve(n)).await;
+                    }
+                    if peer_wr.write_all(&buf[..n]).await.is_err() {
+                        break;
+                    }
+                    counters.add_to_peers(n);
+                }
+            };
+
+            let peer_to_client = async {
+                let mut buf = vec![0u8; TCP_BUFFER_SIZE];
+                loop {
+                    let n = match peer_rd.read(&mut buf).await {
+                        Ok(n) if n > 0 => n,
+                        _ => break,
+                    };
+                    if let Some(rate_limiter) = &rate_limiter {
+                        tokio::time::sleep(rate_limiter.reserve(n)).await;
+                    }
+                    if conn.send(&buf[..n]).await.is_err() {
+                        break;
+                    }
+                    counters.add_from_peers(n);
+                }
+            };
+
+            tokio::select! {
+                _ = client_to_peer => {},
+                _ = peer_to_client => {},
+                _ = closed_rx.changed() => {},
+            }
+
+            log::debug!("data connection with {} closed", peer_addr);
+            let _ = peer_wr.shutdown().await;
+            let _ = conn.close().await;
+            tcp_relay.remove_peer(&peer_addr).await;
+        });
+    }
+
+    // close stops the listener and the data connections, and drops the peer connections
+    // of the allocation which were not bound yet.
+    pub(crate) async fn close(&self, five_tuple: &FiveTuple) {
+        if let Some(closed_tx) = self.closed_tx.lock().unwrap().take() {
+            let _ = closed_tx.send(());
+        }
+
+        let mut peer_connections = self.peer_connections.lock().await;
+        peer_connections.retain(|_, pending| pending.five_tuple != *five_tuple);
+    }
+}
+
+impl Allocation {
+    // tcp_connection_handler accepts the connections of peers to the relayed transport address
+    // of a TCP allocation, and offers them to the client with ConnectionAttempt indications.
+    //
+    // RFC 6062 Section 5.3
+    pub(crate) fn tcp_connection_handler(&self, listener: TcpListener) {
+        let tcp_relay = match &self.tcp_relay {
+            Some(tcp_relay) => Arc::clone(tcp_relay),
+            None => return,
+        };
+        let five_tuple = self.five_tuple.clone();
+        let turn_socket = Arc::clone(&self.turn_socket);
+        let permissions = Arc::clone(&self.permissions);
+        let mut closed_rx = tcp_relay.closed_rx.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer_addr) = tokio::select! {
+                    res = listener.accept() => match res {
+                        Ok(v) => v,
+                        Err(err) => {
+                            log::warn!("failed to accept tcp connection of peer: {}", err);
+                            // Errors such as EMFILE persist for a while, don't spin on them
+                            tokio::select! {
+                                _ = tokio::time::sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                                _ = closed_rx.changed() => break,
+                            }
+                        }
+                    },
+                    _ = closed_rx.changed() => break,
+                };
+
+                let has_permission = {
+                    let ps = permissions.lock().await;
+                    ps.get(&addr2ipfingerprint(&peer_addr)).is_some()
+                };
+                if !has_permission {
+                    log::info!(
+                        "No Permission exists for {} on allocation {}",
+                        peer_addr,
+                        tcp_relay.listener_addr
+                    );
+                    continue;
+                }
+                if !tcp_relay.add_peer(peer_addr).await {
+                    log::info!("connection with {} already exists", peer_addr);
+                    continue;
+                }
+
+                let id = tcp_relay
+                    .add_peer_connection(stream, peer_addr, five_tuple.clone())
+                    .await;
+
+                let mut msg = Message::new();
+                if let Err(err) = msg.build(&[
+                    Box::new(TransactionId::new()),
+                    Box::new(MessageType::new(
+                        METHOD_CONNECTION_ATTEMPT,
+                        CLASS_INDICATION,
+                    )),
+                    Box::new(id),
+                    Box::new(PeerAddress {
+                        ip: peer_addr.ip(),
+                        port: peer_addr.port(),
+                    }),
+                ]) {
+                    log::error!(
+                        "Failed to build ConnectionAttempt for {}: {}",
+                        peer_addr,
+                        err
+                    );
+                    continue;
+                }
+
+                log::debug!("connection attempt {} from {}", id, peer_addr);
+                if let Err(err) = turn_socket.send_to(&msg.raw, five_tuple.src_addr).await {
+                    log::error!(
+                        "Failed to send ConnectionAttempt for {}: {}",
+                        peer_addr,
+                        err
+                    );
+                }
+            }
+        });
+    }
+}
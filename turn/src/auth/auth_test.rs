@@ -39,6 +39,7 @@ fn test_generate_auth_key() -> Result<()> {
 #[tokio::test]
 async fn test_new_long_term_auth_handler() -> Result<()> {
     use crate::client::*;
+    use crate::policy::PolicyConfig;
     use crate::relay::relay_static::*;
     use crate::server::{config::*, *};
 
@@ -67,9 +68,12 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(LongTermAuthHandler::new(SHARED_SECRET.to_string())),
         channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig::default(),
+        event_handler: None,
     })
     .await?;
 
@@ -7,10 +7,83 @@ use std::net::SocketAddr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use md5::{Digest, Md5};
-use ring::hmac;
+use ring::{digest, hmac};
+use stun::integrity::*;
+use stun::message::*;
+use stun::password_algorithm::*;
 
 pub trait AuthHandler {
     fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>>;
+
+    // password_algorithms returns the PASSWORD-ALGORITHMS offered to the clients, in
+    // decreasing order of preference. auth_handle only returns MD5 keys.
+    fn password_algorithms(&self) -> Vec<PasswordAlgorithm> {
+        vec![PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5)]
+    }
+
+    // auth_handle_with_algorithm returns the key of username derived with one of the
+    // password_algorithms.
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        src_addr: SocketAddr,
+        algorithm: &PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        if algorithm.algorithm == PASSWORD_ALGORITHM_MD5 {
+            self.auth_handle(username, realm, src_addr)
+        } else {
+            Err(Error::ErrUnsupportedPasswordAlgorithm)
+        }
+    }
+
+    // supports_user_hash returns whether the clients may send USERHASH instead of USERNAME.
+    fn supports_user_hash(&self) -> bool {
+        false
+    }
+
+    // username_from_user_hash returns the username whose USERHASH in realm is user_hash.
+    fn username_from_user_hash(
+        &self,
+        _user_hash: &[u8],
+        _realm: &str,
+        _src_addr: SocketAddr,
+    ) -> Result<String> {
+        Err(Error::ErrNoSuchUser)
+    }
+}
+
+// Integrity is the MESSAGE-INTEGRITY or MESSAGE-INTEGRITY-SHA256 attribute authenticating
+// the messages of a client.
+#[derive(Clone)]
+pub enum Integrity {
+    Sha1(MessageIntegrity),
+    Sha256(MessageIntegritySha256),
+}
+
+impl Default for Integrity {
+    fn default() -> Self {
+        Integrity::Sha1(MessageIntegrity::default())
+    }
+}
+
+impl Setter for Integrity {
+    fn add_to(&self, m: &mut Message) -> std::result::Result<(), stun::Error> {
+        match self {
+            Integrity::Sha1(mi) => mi.add_to(m),
+            Integrity::Sha256(mi) => mi.add_to(m),
+        }
+    }
+}
+
+impl Integrity {
+    // check checks the integrity attribute of m.
+    pub fn check(&self, m: &mut Message) -> std::result::Result<(), stun::Error> {
+        match self {
+            Integrity::Sha1(mi) => mi.check(m),
+            Integrity::Sha256(mi) => mi.check(m),
+        }
+    }
 }
 
 // generate_long_term_credentials can be used to create credentials valid for [duration] time
@@ -42,17 +115,49 @@ pub fn generate_auth_key(username: &str, realm: &str, password: &str) -> Vec<u8>
     h.finalize().as_slice().to_vec()
 }
 
+// generate_auth_key_sha256 is generate_auth_key for the SHA-256 password algorithm
+pub fn generate_auth_key_sha256(username: &str, realm: &str, password: &str) -> Vec<u8> {
+    let s = format!("{}:{}:{}", username, realm, password);
+
+    digest::digest(&digest::SHA256, s.as_bytes())
+        .as_ref()
+        .to_vec()
+}
+
 pub struct LongTermAuthHandler {
     shared_secret: String,
 }
 
 impl AuthHandler for LongTermAuthHandler {
     fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>> {
+        self.auth_handle_with_algorithm(
+            username,
+            realm,
+            src_addr,
+            &PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+        )
+    }
+
+    fn password_algorithms(&self) -> Vec<PasswordAlgorithm> {
+        vec![
+            PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256),
+            PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+        ]
+    }
+
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        src_addr: SocketAddr,
+        algorithm: &PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
         log::trace!(
-            "Authentication username={} realm={} src_addr={}",
+            "Authentication username={} realm={} src_addr={} algorithm={}",
             username,
             realm,
-            src_addr
+            src_addr,
+            algorithm
         );
 
         let t = Duration::from_secs(username.parse::<u64>()?);
@@ -64,7 +169,7 @@ impl AuthHandler for LongTermAuthHandler {
         }
 
         let password = long_term_credentials(username, &self.shared_secret);
-        Ok(generate_auth_key(username, realm, &password))
+        Ok(algorithm.key(username.to_owned(), realm.to_owned(), password)?)
     }
 }
 
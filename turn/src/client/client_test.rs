@@ -1,5 +1,6 @@
 use super::*;
 use crate::auth::*;
+use crate::policy::PolicyConfig;
 use crate::relay::relay_static::*;
 use crate::server::{config::*, *};
 
@@ -147,9 +148,16 @@ async fn test_client_nonce_expiration() -> Result<()> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler {}),
         channel_bind_timeout: Duration::from_secs(0),
+        // the peers of the test are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
     })
     .await?;
 
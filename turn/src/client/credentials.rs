@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod credentials_test;
+
+use crate::auth::Integrity;
+use crate::error::*;
+
+use stun::attributes::*;
+use stun::integrity::*;
+use stun::message::*;
+use stun::password_algorithm::*;
+use stun::textattrs::*;
+use stun::userhash::*;
+
+// Credentials authenticate the requests of the client with its long-term credentials,
+// using the password algorithm and the username anonymity supported by the server.
+//
+// RFC 8489 Section 9.2
+#[derive(Default, Clone)]
+pub(crate) struct Credentials {
+    username: Username,
+    user_hash: Option<UserHash>,
+    realm: Realm,
+    password_algorithms: Option<(PasswordAlgorithms, PasswordAlgorithm)>,
+    integrity: Integrity,
+}
+
+impl Setter for Credentials {
+    fn add_to(&self, m: &mut Message) -> std::result::Result<(), stun::Error> {
+        if let Some(user_hash) = &self.user_hash {
+            user_hash.add_to(m)?;
+        } else {
+            self.username.add_to(m)?;
+        }
+        self.realm.add_to(m)?;
+        if let Some((password_algorithms, password_algorithm)) = &self.password_algorithms {
+            password_algorithms.add_to(m)?;
+            password_algorithm.add_to(m)?;
+        }
+        self.integrity.add_to(m)
+    }
+}
+
+impl Credentials {
+    // new returns the credentials of username for the realm and the security features
+    // advertised by the NONCE of the 401 response res.
+    pub(crate) fn new(
+        username: &Username,
+        password: &str,
+        realm: &Realm,
+        res: &Message,
+    ) -> Result<Self> {
+        let nonce = Nonce::get_from_as(res, ATTR_NONCE)?;
+        let security_features = nonce.security_features();
+
+        let password_algorithms = if security_features & SECURITY_FEATURE_PASSWORD_ALGORITHMS != 0 {
+            let mut password_algorithms = PasswordAlgorithms::default();
+            password_algorithms.get_from(res)?;
+            let password_algorithm = password_algorithms
+                .select(&[PASSWORD_ALGORITHM_SHA256, PASSWORD_ALGORITHM_MD5])
+                .cloned()
+                .ok_or(Error::ErrUnsupportedPasswordAlgorithm)?;
+            Some((password_algorithms, password_algorithm))
+        } else {
+            None
+        };
+
+        let key = match &password_algorithms {
+            Some((_, password_algorithm)) => password_algorithm.key(
+                username.text.clone(),
+                realm.text.clone(),
+                password.to_owned(),
+            )?,
+            None => {
+                MessageIntegrity::new_long_term_integrity(
+                    username.text.clone(),
+                    realm.text.clone(),
+                    password.to_owned(),
+                )
+                .0
+            }
+        };
+
+        // servers advertising security features support MESSAGE-INTEGRITY-SHA256
+        let integrity = if security_features != 0 {
+            Integrity::Sha256(MessageIntegritySha256(key))
+        } else {
+            Integrity::Sha1(MessageIntegrity(key))
+        };
+
+        let user_hash = if security_features & SECURITY_FEATURE_USERNAME_ANONYMITY != 0 {
+            Some(UserHash::new(&username.text, &realm.text))
+        } else {
+            None
+        };
+
+        Ok(Credentials {
+            username: username.clone(),
+            user_hash,
+            realm: realm.clone(),
+            password_algorithms,
+            integrity,
+        })
+    }
+}
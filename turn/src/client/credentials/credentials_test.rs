@@ -0,0 +1,94 @@
+use super::*;
+use crate::auth::*;
+
+use stun::agent::*;
+
+fn unauthorized_response(
+    nonce: Nonce,
+    password_algorithms: Option<PasswordAlgorithms>,
+) -> Result<Message> {
+    let mut res = Message::new();
+    res.build(&[Box::new(TransactionId::new()), Box::new(nonce)])?;
+    if let Some(password_algorithms) = password_algorithms {
+        password_algorithms.add_to(&mut res)?;
+    }
+    Ok(res)
+}
+
+fn request(credentials: &Credentials) -> Result<Message> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(TransactionId::new()),
+        Box::new(credentials.clone()),
+    ])?;
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    Ok(decoded)
+}
+
+#[test]
+fn test_credentials_legacy() -> Result<()> {
+    let username = Username::new(ATTR_USERNAME, "user".to_owned());
+    let realm = Realm::new(ATTR_REALM, "webrtc.rs".to_owned());
+    let res = unauthorized_response(Nonce::new(ATTR_NONCE, "nonce".to_owned()), None)?;
+
+    let credentials = Credentials::new(&username, "pass", &realm, &res)?;
+    let mut m = request(&credentials)?;
+    assert_eq!(Username::get_from_as(&m, ATTR_USERNAME)?.text, "user");
+    assert_eq!(Realm::get_from_as(&m, ATTR_REALM)?.text, "webrtc.rs");
+    assert!(!m.contains(ATTR_USER_HASH));
+    assert!(!m.contains(ATTR_PASSWORD_ALGORITHM));
+    assert!(!m.contains(ATTR_MESSAGE_INTEGRITY_SHA256));
+    MessageIntegrity(generate_auth_key("user", "webrtc.rs", "pass")).check(&mut m)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_credentials_security_features() -> Result<()> {
+    let username = Username::new(ATTR_USERNAME, "user".to_owned());
+    let realm = Realm::new(ATTR_REALM, "webrtc.rs".to_owned());
+    let password_algorithms = PasswordAlgorithms(vec![
+        PasswordAlgorithm::new(0x1234),
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256),
+    ]);
+    let res = unauthorized_response(
+        Nonce::new_nonce_with_security_features(
+            SECURITY_FEATURE_PASSWORD_ALGORITHMS | SECURITY_FEATURE_USERNAME_ANONYMITY,
+            "nonce",
+        ),
+        Some(password_algorithms.clone()),
+    )?;
+
+    let credentials = Credentials::new(&username, "pass", &realm, &res)?;
+    let mut m = request(&credentials)?;
+    assert!(!m.contains(ATTR_USERNAME));
+    let mut user_hash = UserHash::default();
+    user_hash.get_from(&m)?;
+    assert_eq!(user_hash, UserHash::new("user", "webrtc.rs"));
+
+    // the algorithms are echoed, the most preferred supported one is selected
+    let mut got_algorithms = PasswordAlgorithms::default();
+    got_algorithms.get_from(&m)?;
+    assert_eq!(got_algorithms, password_algorithms);
+    let mut got_algorithm = PasswordAlgorithm::default();
+    got_algorithm.get_from(&m)?;
+    assert_eq!(
+        got_algorithm,
+        PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5)
+    );
+
+    assert!(!m.contains(ATTR_MESSAGE_INTEGRITY));
+    MessageIntegritySha256(generate_auth_key("user", "webrtc.rs", "pass")).check(&mut m)?;
+
+    // no supported algorithm
+    let res = unauthorized_response(
+        Nonce::new_nonce_with_security_features(SECURITY_FEATURE_PASSWORD_ALGORITHMS, "nonce"),
+        Some(PasswordAlgorithms(vec![PasswordAlgorithm::new(0x1234)])),
+    )?;
+    assert!(Credentials::new(&username, "pass", &realm, &res).is_err());
+
+    Ok(())
+}
@@ -2,17 +2,22 @@
 mod client_test;
 
 pub mod binding;
+pub mod credentials;
 pub mod periodic_timer;
 pub mod permission;
 pub mod relay_conn;
+pub mod tcp_alloc;
 pub mod transaction;
 
 use crate::error::*;
 use crate::proto::{
-    chandata::*, data::*, lifetime::*, peeraddr::*, relayaddr::*, reqtrans::*, PROTO_UDP,
+    chandata::*, connid::*, data::*, lifetime::*, peeraddr::*, relayaddr::*, reqfamily::*,
+    reqtrans::*, Protocol, PROTO_TCP, PROTO_UDP,
 };
 use binding::*;
+use credentials::*;
 use relay_conn::*;
+use tcp_alloc::*;
 use transaction::*;
 
 use std::net::SocketAddr;
@@ -22,7 +27,6 @@ use stun::agent::*;
 use stun::attributes::*;
 use stun::error_code::*;
 use stun::fingerprint::*;
-use stun::integrity::*;
 use stun::message::*;
 use stun::textattrs::*;
 use stun::xoraddr::*;
@@ -34,6 +38,7 @@ use async_trait::async_trait;
 const DEFAULT_RTO_IN_MS: u16 = 200;
 const MAX_DATA_BUFFER_SIZE: usize = u16::MAX as usize; // message size limit for Chromium
 const MAX_READ_QUEUE_SIZE: usize = 1024;
+const MAX_CONN_ATTEMPT_QUEUE_SIZE: usize = 64;
 
 //              interval [msec]
 // 0: 0 ms      +500
@@ -65,12 +70,13 @@ struct ClientInternal {
     username: Username,
     password: String,
     realm: Realm,
-    integrity: MessageIntegrity,
+    credentials: Credentials,
     software: Software,
     tr_map: Arc<Mutex<TransactionMap>>,
     binding_mgr: Arc<Mutex<BindingManager>>,
     rto_in_ms: u16,
     read_ch_tx: Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+    conn_attempt_tx: Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
 }
 
 #[async_trait]
@@ -201,8 +207,9 @@ impl ClientInternal {
             } else {
                 DEFAULT_RTO_IN_MS
             },
-            integrity: MessageIntegrity::new_short_term_integrity(String::new()),
+            credentials: Credentials::default(),
             read_ch_tx: Arc::new(Mutex::new(None)),
+            conn_attempt_tx: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -219,6 +226,7 @@ impl ClientInternal {
         let stun_serv_str = self.stun_serv_addr.clone();
         let tr_map = Arc::clone(&self.tr_map);
         let read_ch_tx = Arc::clone(&self.read_ch_tx);
+        let conn_attempt_tx = Arc::clone(&self.conn_attempt_tx);
         let binding_mgr = Arc::clone(&self.binding_mgr);
 
         tokio::spawn(async move {
@@ -237,6 +245,7 @@ impl ClientInternal {
 
                 if let Err(err) = ClientInternal::handle_inbound(
                     &read_ch_tx,
+                    &conn_attempt_tx,
                     &buf[..n],
                     from,
                     &stun_serv_str,
@@ -263,6 +272,7 @@ impl ClientInternal {
     // If an error is returned, the caller should discard the packet regardless.
     async fn handle_inbound(
         read_ch_tx: &Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+        conn_attempt_tx: &Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
         data: &[u8],
         from: SocketAddr,
         stun_serv_str: &str,
@@ -288,7 +298,8 @@ impl ClientInternal {
         //  - Non-STUN message from the STUN server
 
         if is_message(data) {
-            ClientInternal::handle_stun_message(tr_map, read_ch_tx, data, from).await
+            ClientInternal::handle_stun_message(tr_map, read_ch_tx, conn_attempt_tx, data, from)
+                .await
         } else if ChannelData::is_channel_data(data) {
             ClientInternal::handle_channel_data(binding_mgr, read_ch_tx, data).await
         } else if !stun_serv_str.is_empty() && from.to_string() == *stun_serv_str {
@@ -304,6 +315,7 @@ impl ClientInternal {
     async fn handle_stun_message(
         tr_map: &Arc<Mutex<TransactionMap>>,
         read_ch_tx: &Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+        conn_attempt_tx: &Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
         data: &[u8],
         mut from: SocketAddr,
     ) -> Result<()> {
@@ -331,6 +343,22 @@ impl ClientInternal {
                 log::debug!("data indication received from {}", from);
 
                 let _ = ClientInternal::handle_inbound_relay_conn(read_ch_tx, &data.0, from).await;
+            } else if msg.typ.method == METHOD_CONNECTION_ATTEMPT {
+                let mut peer_addr = PeerAddress::default();
+                peer_addr.get_from(&msg)?;
+                let peer_addr = SocketAddr::new(peer_addr.ip, peer_addr.port);
+
+                let mut id = ConnectionId::default();
+                id.get_from(&msg)?;
+
+                log::debug!("connection attempt {} received from {}", id, peer_addr);
+
+                let conn_attempt_tx = conn_attempt_tx.lock().await;
+                if let Some(tx) = &*conn_attempt_tx {
+                    if tx.try_send(ConnectionAttempt { id, peer_addr }).is_err() {
+                        log::warn!("connection attempt queue full");
+                    }
+                }
             }
 
             return Ok(());
@@ -427,6 +455,10 @@ impl ClientInternal {
             let mut read_ch_tx = self.read_ch_tx.lock().await;
             read_ch_tx.take();
         }
+        {
+            let mut conn_attempt_tx = self.conn_attempt_tx.lock().await;
+            conn_attempt_tx.take();
+        }
         {
             let mut tm = self.tr_map.lock().await;
             tm.close_and_delete_all();
@@ -480,8 +512,14 @@ impl ClientInternal {
         bm.find_by_number(ch_num).map(|b| b.addr)
     }
 
-    // Allocate sends a TURN allocation request to the given transport address
-    async fn allocate(&mut self) -> Result<RelayConnConfig> {
+    // Allocate sends a TURN allocation request to the given transport address,
+    // protocol is the transport protocol between the server and peers, family the
+    // address family of the relayed address if the client requests one.
+    async fn allocate(
+        &mut self,
+        protocol: Protocol,
+        family: Option<RequestedAddressFamily>,
+    ) -> Result<RelayConnConfig> {
         {
             let read_ch_tx = self.read_ch_tx.lock().await;
             log::debug!("allocate check: read_ch_tx_opt = {}", read_ch_tx.is_some());
@@ -490,15 +528,22 @@ impl ClientInternal {
             }
         }
 
-        let mut msg = Message::new();
-        msg.build(&[
-            Box::new(TransactionId::new()),
-            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
-            Box::new(RequestedTransport {
-                protocol: PROTO_UDP,
-            }),
-            Box::new(FINGERPRINT),
-        ])?;
+        // the setters are dropped before the transaction, they are not Send
+        let mut msg = {
+            let mut setters: Vec<Box<dyn Setter>> = vec![
+                Box::new(TransactionId::new()),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+                Box::new(RequestedTransport { protocol }),
+            ];
+            if let Some(family) = family {
+                setters.push(Box::new(family));
+            }
+            setters.push(Box::new(FINGERPRINT));
+
+            let mut msg = Message::new();
+            msg.build(&setters)?;
+            msg
+        };
 
         log::debug!("client.Allocate call PerformTransaction 1");
         let tr_res = self
@@ -510,25 +555,23 @@ impl ClientInternal {
         let nonce = Nonce::get_from_as(&res, ATTR_NONCE)?;
         self.realm = Realm::get_from_as(&res, ATTR_REALM)?;
 
-        self.integrity = MessageIntegrity::new_long_term_integrity(
-            self.username.text.clone(),
-            self.realm.text.clone(),
-            self.password.clone(),
-        );
+        self.credentials = Credentials::new(&self.username, &self.password, &self.realm, &res)?;
 
         // Trying to authorize.
-        msg.build(&[
-            Box::new(TransactionId::new()),
-            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
-            Box::new(RequestedTransport {
-                protocol: PROTO_UDP,
-            }),
-            Box::new(self.username.clone()),
-            Box::new(self.realm.clone()),
-            Box::new(nonce.clone()),
-            Box::new(self.integrity.clone()),
-            Box::new(FINGERPRINT),
-        ])?;
+        {
+            let mut setters: Vec<Box<dyn Setter>> = vec![
+                Box::new(TransactionId::new()),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+                Box::new(RequestedTransport { protocol }),
+            ];
+            if let Some(family) = family {
+                setters.push(Box::new(family));
+            }
+            setters.push(Box::new(nonce.clone()));
+            setters.push(Box::new(self.credentials.clone()));
+            setters.push(Box::new(FINGERPRINT));
+            msg.build(&setters)?;
+        }
 
         log::debug!("client.Allocate call PerformTransaction 2");
         let tr_res = self
@@ -564,7 +607,7 @@ impl ClientInternal {
 
         Ok(RelayConnConfig {
             relayed_addr,
-            integrity: self.integrity.clone(),
+            credentials: self.credentials.clone(),
             nonce,
             lifetime: lifetime.0,
             binding_mgr: Arc::clone(&self.binding_mgr),
@@ -595,12 +638,40 @@ impl Client {
     pub async fn allocate(&self) -> Result<impl Conn> {
         let config = {
             let mut ci = self.client_internal.lock().await;
-            ci.allocate().await?
+            ci.allocate(PROTO_UDP, None).await?
         };
 
         Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
     }
 
+    // allocate_with_family creates an allocation with a relayed address of family, e.g.
+    // REQUESTED_FAMILY_IPV6 to relay to IPv6 peers (RFC 8656). Servers which don't
+    // support the family reject it with a 440 (Address Family not Supported) error.
+    pub async fn allocate_with_family(&self, family: RequestedAddressFamily) -> Result<impl Conn> {
+        let config = {
+            let mut ci = self.client_internal.lock().await;
+            ci.allocate(PROTO_UDP, Some(family)).await?
+        };
+
+        Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
+    }
+
+    // allocate_tcp creates a TCP allocation (RFC 6062), which relays TCP connections with peers.
+    // The client must be connected to the server over TCP or TLS.
+    pub async fn allocate_tcp(&self) -> Result<TcpAllocation> {
+        let (config, conn_attempt_rx) = {
+            let mut ci = self.client_internal.lock().await;
+            let config = ci.allocate(PROTO_TCP, None).await?;
+
+            let (conn_attempt_tx, conn_attempt_rx) = mpsc::channel(MAX_CONN_ATTEMPT_QUEUE_SIZE);
+            let mut conn_attempt_tx_opt = ci.conn_attempt_tx.lock().await;
+            *conn_attempt_tx_opt = Some(conn_attempt_tx);
+            (config, conn_attempt_rx)
+        };
+
+        Ok(TcpAllocation::new(Arc::clone(&self.client_internal), config, conn_attempt_rx).await)
+    }
+
     pub async fn close(&self) -> Result<()> {
         let mut ci = self.client_internal.lock().await;
         ci.close().await;
@@ -3,17 +3,18 @@ mod relay_conn_test;
 
 // client implements the API for a TURN client
 use super::binding::*;
+use super::credentials::*;
 use super::periodic_timer::*;
 use super::permission::*;
 use super::transaction::*;
 use crate::proto;
+use crate::proto::connid::ConnectionId;
 use crate::Error;
 
 use stun::agent::*;
 use stun::attributes::*;
 use stun::error_code::*;
 use stun::fingerprint::*;
-use stun::integrity::*;
 use stun::message::*;
 use stun::textattrs::*;
 
@@ -27,8 +28,8 @@ use tokio::time::{Duration, Instant};
 
 use async_trait::async_trait;
 
-const PERM_REFRESH_INTERVAL: Duration = Duration::from_secs(120);
-const MAX_RETRY_ATTEMPTS: u16 = 3;
+pub(crate) const PERM_REFRESH_INTERVAL: Duration = Duration::from_secs(120);
+pub(crate) const MAX_RETRY_ATTEMPTS: u16 = 3;
 
 pub(crate) struct InboundData {
     pub(crate) data: Vec<u8>,
@@ -53,7 +54,7 @@ pub trait RelayConnObserver {
 // RelayConnConfig is a set of configuration params use by NewUDPConn
 pub(crate) struct RelayConnConfig {
     pub(crate) relayed_addr: SocketAddr,
-    pub(crate) integrity: MessageIntegrity,
+    pub(crate) credentials: Credentials,
     pub(crate) nonce: Nonce,
     pub(crate) lifetime: Duration,
     pub(crate) binding_mgr: Arc<Mutex<BindingManager>>,
@@ -65,7 +66,7 @@ pub struct RelayConnInternal<T: 'static + RelayConnObserver + Send + Sync> {
     relayed_addr: SocketAddr,
     perm_map: PermissionMap,
     binding_mgr: Arc<Mutex<BindingManager>>,
-    integrity: MessageIntegrity,
+    credentials: Credentials,
     nonce: Nonce,
     lifetime: Duration,
 }
@@ -192,13 +193,13 @@ impl<T: RelayConnObserver + Send + Sync> Conn for RelayConn<T> {
 
 impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     // new creates a new instance of UDPConn
-    fn new(obs: Arc<Mutex<T>>, config: RelayConnConfig) -> Self {
+    pub(crate) fn new(obs: Arc<Mutex<T>>, config: RelayConnConfig) -> Self {
         RelayConnInternal {
             obs,
             relayed_addr: config.relayed_addr,
             perm_map: PermissionMap::new(),
             binding_mgr: config.binding_mgr,
-            integrity: config.integrity,
+            credentials: config.credentials,
             nonce: config.nonce,
             lifetime: config.lifetime,
         }
@@ -211,24 +212,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     // On packet-oriented connections, write timeouts are rare.
     async fn send_to(&mut self, p: &[u8], addr: SocketAddr) -> Result<usize, Error> {
         // check if we have a permission for the destination IP addr
-        let perm = if let Some(perm) = self.perm_map.find(&addr) {
-            Arc::clone(perm)
-        } else {
-            let perm = Arc::new(Permission::default());
-            self.perm_map.insert(&addr, Arc::clone(&perm));
-            perm
-        };
-
-        let mut result = Ok(());
-        for _ in 0..MAX_RETRY_ATTEMPTS {
-            result = self.create_perm(&perm, addr).await;
-            if let Err(err) = &result {
-                if Error::ErrTryAgain != *err {
-                    break;
-                }
-            }
-        }
-        result?;
+        self.create_permission(addr).await?;
 
         let number = {
             let (bind_st, bind_at, bind_number, bind_addr) = {
@@ -254,7 +238,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                     let binding_mgr = Arc::clone(&self.binding_mgr);
                     let rc_obs = Arc::clone(&self.obs);
                     let nonce = self.nonce.clone();
-                    let integrity = self.integrity.clone();
+                    let credentials = self.credentials.clone();
                     {
                         let mut bm = binding_mgr.lock().await;
                         if let Some(b) = bm.get_by_addr(&bind_addr) {
@@ -267,7 +251,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                             bind_addr,
                             bind_number,
                             nonce,
-                            integrity,
+                            credentials,
                         )
                         .await;
 
@@ -318,7 +302,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 let binding_mgr = Arc::clone(&self.binding_mgr);
                 let rc_obs = Arc::clone(&self.obs);
                 let nonce = self.nonce.clone();
-                let integrity = self.integrity.clone();
+                let credentials = self.credentials.clone();
                 {
                     let mut bm = binding_mgr.lock().await;
                     if let Some(b) = bm.get_by_addr(&bind_addr) {
@@ -327,7 +311,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 }
                 tokio::spawn(async move {
                     let result =
-                        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity)
+                        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, credentials)
                             .await;
 
                     {
@@ -357,6 +341,29 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     }
 
     // This func-block would block, per destination IP (, or perm), until
+    // create_permission makes sure there is a permission for the IP address of addr,
+    // it is refreshed until the allocation is closed.
+    pub(crate) async fn create_permission(&mut self, addr: SocketAddr) -> Result<(), Error> {
+        let perm = if let Some(perm) = self.perm_map.find(&addr) {
+            Arc::clone(perm)
+        } else {
+            let perm = Arc::new(Permission::default());
+            self.perm_map.insert(&addr, Arc::clone(&perm));
+            perm
+        };
+
+        let mut result = Ok(());
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            result = self.create_perm(&perm, addr).await;
+            if let Err(err) = &result {
+                if Error::ErrTryAgain != *err {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
     // the perm state becomes "requested". Purpose of this is to guarantee
     // the order of packets (within the same perm).
     // Note that CreatePermission transaction may not be complete before
@@ -390,7 +397,6 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     async fn create_permissions(&mut self, addrs: &[SocketAddr]) -> Result<(), Error> {
         let res = {
             let msg = {
-                let obs = self.obs.lock().await;
                 let mut setters: Vec<Box<dyn Setter>> = vec![
                     Box::new(TransactionId::new()),
                     Box::new(MessageType::new(METHOD_CREATE_PERMISSION, CLASS_REQUEST)),
@@ -400,10 +406,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                     setters.push(Box::new(socket_addr2peer_address(addr)));
                 }
 
-                setters.push(Box::new(obs.username()));
-                setters.push(Box::new(obs.realm()));
                 setters.push(Box::new(self.nonce.clone()));
-                setters.push(Box::new(self.integrity.clone()));
+                setters.push(Box::new(self.credentials.clone()));
                 setters.push(Box::new(FINGERPRINT));
 
                 let mut msg = Message::new();
@@ -438,6 +442,73 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         Ok(())
     }
 
+    // connect asks the server to open a TCP connection from the relayed address to addr,
+    // it returns the CONNECTION-ID of the connection to bind a data connection to.
+    //
+    // RFC 6062 Section 4.3
+    pub(crate) async fn connect(&mut self, addr: SocketAddr) -> Result<ConnectionId, Error> {
+        let res = {
+            let msg = {
+                let mut msg = Message::new();
+                msg.build(&[
+                    Box::new(TransactionId::new()),
+                    Box::new(MessageType::new(METHOD_CONNECT, CLASS_REQUEST)),
+                    Box::new(socket_addr2peer_address(&addr)),
+                    Box::new(self.nonce.clone()),
+                    Box::new(self.credentials.clone()),
+                    Box::new(FINGERPRINT),
+                ])?;
+                msg
+            };
+
+            let mut obs = self.obs.lock().await;
+            let turn_server_addr = obs.turn_server_addr();
+
+            log::debug!("TcpAllocation.connect call PerformTransaction 1");
+            let tr_res = obs
+                .perform_transaction(&msg, &turn_server_addr, false)
+                .await?;
+
+            tr_res.msg
+        };
+
+        if res.typ.class == CLASS_ERROR_RESPONSE {
+            let mut code = ErrorCodeAttribute::default();
+            let result = code.get_from(&res);
+            if result.is_err() {
+                return Err(Error::Other(format!("{}", res.typ)));
+            } else if code.code == CODE_STALE_NONCE {
+                self.set_nonce_from_msg(&res);
+                return Err(Error::ErrTryAgain);
+            } else {
+                return Err(Error::Other(format!("{} (error {})", res.typ, code)));
+            }
+        }
+
+        let mut id = ConnectionId::default();
+        id.get_from(&res)?;
+
+        log::debug!("connected to {} (connection {})", addr, id);
+        Ok(id)
+    }
+
+    // connection_bind_request builds the ConnectionBind request for the connection with a peer
+    // identified by id, to be sent on a new connection to the TURN server.
+    //
+    // RFC 6062 Section 4.4
+    pub(crate) async fn connection_bind_request(&self, id: ConnectionId) -> Result<Message, Error> {
+        let mut msg = Message::new();
+        msg.build(&[
+            Box::new(TransactionId::new()),
+            Box::new(MessageType::new(METHOD_CONNECTION_BIND, CLASS_REQUEST)),
+            Box::new(id),
+            Box::new(self.nonce.clone()),
+            Box::new(self.credentials.clone()),
+            Box::new(FINGERPRINT),
+        ])?;
+        Ok(msg)
+    }
+
     pub fn set_nonce_from_msg(&mut self, msg: &Message) {
         // Update nonce
         match Nonce::get_from_as(msg, ATTR_NONCE) {
@@ -469,10 +540,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 Box::new(TransactionId::new()),
                 Box::new(MessageType::new(METHOD_REFRESH, CLASS_REQUEST)),
                 Box::new(proto::lifetime::Lifetime(lifetime)),
-                Box::new(obs.username()),
-                Box::new(obs.realm()),
                 Box::new(self.nonce.clone()),
-                Box::new(self.integrity.clone()),
+                Box::new(self.credentials.clone()),
                 Box::new(FINGERPRINT),
             ])?;
 
@@ -537,7 +606,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         bind_addr: SocketAddr,
         bind_number: u16,
         nonce: Nonce,
-        integrity: MessageIntegrity,
+        credentials: Credentials,
     ) -> Result<(), Error> {
         let (msg, turn_server_addr) = {
             let obs = rc_obs.lock().await;
@@ -547,10 +616,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 Box::new(MessageType::new(METHOD_CHANNEL_BIND, CLASS_REQUEST)),
                 Box::new(socket_addr2peer_address(&bind_addr)),
                 Box::new(proto::channum::ChannelNumber(bind_number)),
-                Box::new(obs.username()),
-                Box::new(obs.realm()),
                 Box::new(nonce),
-                Box::new(integrity),
+                Box::new(credentials),
                 Box::new(FINGERPRINT),
             ];
 
@@ -49,7 +49,7 @@ async fn test_relay_conn() -> Result<()> {
 
     let config = RelayConnConfig {
         relayed_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 0),
-        integrity: MessageIntegrity::default(),
+        credentials: Credentials::default(),
         nonce: Nonce::new(ATTR_NONCE, "nonce".to_owned()),
         lifetime: Duration::from_secs(0),
         binding_mgr: Arc::new(Mutex::new(BindingManager::new())),
@@ -70,10 +70,10 @@ async fn test_relay_conn() -> Result<()> {
     //let binding_mgr = Arc::clone(&rci.binding_mgr);
     let rc_obs = Arc::clone(&rci.obs);
     let nonce = rci.nonce.clone();
-    let integrity = rci.integrity.clone();
+    let credentials = rci.credentials.clone();
 
     if let Err(err) =
-        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity).await
+        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, credentials).await
     {
         assert!(Error::ErrUnexpectedResponse != err);
     } else {
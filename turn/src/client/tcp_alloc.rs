@@ -0,0 +1,159 @@
+use super::periodic_timer::*;
+use super::relay_conn::*;
+use super::ClientInternal;
+use crate::error::*;
+use crate::proto::connid::ConnectionId;
+
+use stun::error_code::*;
+use stun::message::*;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use util::Conn;
+
+// ConnectionAttempt is a connection of a peer to the relayed transport address, announced by
+// the server with a ConnectionAttempt indication.
+pub(crate) struct ConnectionAttempt {
+    pub(crate) id: ConnectionId,
+    pub(crate) peer_addr: SocketAddr,
+}
+
+// TcpAllocation is a TCP allocation (RFC 6062) on a TURN server, which relays TCP connections
+// between the client and peers. Connections are opened with connect or accepted with accept,
+// and then bound to a new connection to the TURN server with connection_bind, which carries
+// the data of the peer from then on.
+pub struct TcpAllocation {
+    relayed_addr: SocketAddr,
+    conn_attempt_rx: Mutex<mpsc::Receiver<ConnectionAttempt>>,
+    relay_conn: Arc<Mutex<RelayConnInternal<ClientInternal>>>,
+    refresh_alloc_timer: PeriodicTimer,
+    refresh_perms_timer: PeriodicTimer,
+}
+
+impl TcpAllocation {
+    // new creates a new instance of TcpAllocation
+    pub(super) async fn new(
+        obs: Arc<Mutex<ClientInternal>>,
+        config: RelayConnConfig,
+        conn_attempt_rx: mpsc::Receiver<ConnectionAttempt>,
+    ) -> Self {
+        log::debug!("initial lifetime: {} seconds", config.lifetime.as_secs());
+
+        let a = TcpAllocation {
+            refresh_alloc_timer: PeriodicTimer::new(TimerIdRefresh::Alloc, config.lifetime / 2),
+            refresh_perms_timer: PeriodicTimer::new(TimerIdRefresh::Perms, PERM_REFRESH_INTERVAL),
+            relayed_addr: config.relayed_addr,
+            conn_attempt_rx: Mutex::new(conn_attempt_rx),
+            relay_conn: Arc::new(Mutex::new(RelayConnInternal::new(obs, config))),
+        };
+
+        let rci1 = Arc::clone(&a.relay_conn);
+        let rci2 = Arc::clone(&a.relay_conn);
+
+        if a.refresh_alloc_timer.start(rci1).await {
+            log::debug!("refresh_alloc_timer started");
+        }
+        if a.refresh_perms_timer.start(rci2).await {
+            log::debug!("refresh_perms_timer started");
+        }
+
+        a
+    }
+
+    // relayed_addr returns the relayed transport address peers connect to.
+    pub fn relayed_addr(&self) -> SocketAddr {
+        self.relayed_addr
+    }
+
+    // create_permission allows connections with the IP address of peer, in both directions.
+    pub async fn create_permission(&self, peer: SocketAddr) -> Result<()> {
+        let mut relay_conn = self.relay_conn.lock().await;
+        relay_conn.create_permission(peer).await
+    }
+
+    // connect opens a TCP connection to peer from the relayed transport address. The returned
+    // CONNECTION-ID must be bound with connection_bind within 30 seconds.
+    pub async fn connect(&self, peer: SocketAddr) -> Result<ConnectionId> {
+        let mut relay_conn = self.relay_conn.lock().await;
+        relay_conn.create_permission(peer).await?;
+
+        let mut result = Err(Error::ErrTryAgain);
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            result = relay_conn.connect(peer).await;
+            match &result {
+                Err(err) if Error::ErrTryAgain == *err => {}
+                _ => break,
+            }
+        }
+        result
+    }
+
+    // accept waits for the next connection of a peer to the relayed transport address. The
+    // returned CONNECTION-ID must be bound with connection_bind within 30 seconds.
+    pub async fn accept(&self) -> Result<(ConnectionId, SocketAddr)> {
+        let mut conn_attempt_rx = self.conn_attempt_rx.lock().await;
+        match conn_attempt_rx.recv().await {
+            Some(attempt) => Ok((attempt.id, attempt.peer_addr)),
+            None => Err(Error::ErrAlreadyClosed),
+        }
+    }
+
+    // connection_bind binds conn, a new TCP or TLS connection to the TURN server wrapped in a
+    // StunConn, to the connection with a peer. Once it succeeded, conn carries the data
+    // exchanged with the peer.
+    pub async fn connection_bind(
+        &self,
+        conn: &(dyn Conn + Send + Sync),
+        id: ConnectionId,
+    ) -> Result<()> {
+        let mut buf = vec![0u8; super::MAX_DATA_BUFFER_SIZE];
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            let msg = {
+                let relay_conn = self.relay_conn.lock().await;
+                relay_conn.connection_bind_request(id).await?
+            };
+
+            // the connection is reliable, so the request is not retransmitted
+            conn.send(&msg.raw).await?;
+            let res = loop {
+                let n = conn.recv(&mut buf).await?;
+                let mut res = Message::new();
+                res.raw = buf[..n].to_vec();
+                res.decode()?;
+                if res.transaction_id == msg.transaction_id {
+                    break res;
+                }
+                log::debug!("discarding unexpected {} on data connection", res.typ);
+            };
+
+            if res.typ.class == CLASS_ERROR_RESPONSE {
+                let mut code = ErrorCodeAttribute::default();
+                let result = code.get_from(&res);
+                if result.is_err() {
+                    return Err(Error::Other(format!("{}", res.typ)));
+                } else if code.code == CODE_STALE_NONCE {
+                    let mut relay_conn = self.relay_conn.lock().await;
+                    relay_conn.set_nonce_from_msg(&res);
+                    continue;
+                } else {
+                    return Err(Error::Other(format!("{} (error {})", res.typ, code)));
+                }
+            }
+
+            log::debug!("connection {} bound", id);
+            return Ok(());
+        }
+
+        Err(Error::ErrTryAgain)
+    }
+
+    // close deletes the allocation. Data connections bound to it are closed by the server.
+    pub async fn close(&self) -> Result<()> {
+        self.refresh_alloc_timer.stop().await;
+        self.refresh_perms_timer.stop().await;
+
+        let mut relay_conn = self.relay_conn.lock().await;
+        relay_conn.close().await
+    }
+}
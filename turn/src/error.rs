@@ -120,6 +120,10 @@ pub enum Error {
     ErrDuplicatedNonce,
     #[error("no such user exists")]
     ErrNoSuchUser,
+    #[error("unsupported password algorithm")]
+    ErrUnsupportedPasswordAlgorithm,
+    #[error("PASSWORD-ALGORITHMS does not match the algorithms offered by the server")]
+    ErrPasswordAlgorithmsMismatch,
     #[error("unexpected class")]
     ErrUnexpectedClass,
     #[error("unexpected method")]
@@ -152,6 +156,36 @@ pub enum Error {
     ErrNoSuchChannelBind,
     #[error("failed writing to socket")]
     ErrFailedWriteSocket,
+    #[error("RelayAddressGenerator does not support TCP allocations")]
+    ErrTcpRelayUnsupported,
+    #[error("TCP relaying must be requested over TCP or TLS")]
+    ErrTcpRelayOverUdp,
+    #[error("TCP allocations don't support DONT-FRAGMENT, EVEN-PORT and RESERVATION-TOKEN")]
+    ErrTcpAllocationUnsupportedAttribute,
+    #[error("allocation is not a TCP allocation")]
+    ErrNotTcpAllocation,
+    #[error("allocation is not a UDP allocation")]
+    ErrNotUdpAllocation,
+    #[error("allocation quota reached")]
+    ErrAllocationQuotaReached,
+    #[error("allocation denied by policy")]
+    ErrAllocationDenied,
+    #[error("peer address denied")]
+    ErrPeerAddressDenied,
+    #[error("connection to peer already exists")]
+    ErrConnectionAlreadyExists,
+    #[error("no pending connection for CONNECTION-ID")]
+    ErrNoSuchConnection,
+    #[error("requested address family is not supported")]
+    ErrAddressFamilyNotSupported,
+    #[error("REQUESTED-ADDRESS-FAMILY and ADDITIONAL-ADDRESS-FAMILY are mutually exclusive")]
+    ErrRequestedAndAdditionalAddressFamily,
+    #[error("ADDITIONAL-ADDRESS-FAMILY must be IPv6")]
+    ErrAdditionalAddressFamilyNotIpv6,
+    #[error("peer address family doesn't match the allocation")]
+    ErrPeerAddressFamilyMismatch,
+    #[error("RelayAddressGenerator does not support its address family")]
+    ErrRelayAddressFamilyMismatch,
     #[error("parse int: {0}")]
     ParseInt(#[from] ParseIntError),
     #[error("parse addr: {0}")]
@@ -0,0 +1,30 @@
+use crate::allocation::five_tuple::FiveTuple;
+use crate::allocation::AllocationInfo;
+
+use std::net::SocketAddr;
+use tokio::time::Duration;
+
+// EventHandler is notified of what a TURN server does, e.g. to export it to monitoring.
+// The methods are called inline by the tasks of the server, so they must not block.
+pub trait EventHandler {
+    // on_allocation_created is called once an allocation is created
+    fn on_allocation_created(&self, _info: &AllocationInfo) {}
+
+    // on_allocation_refreshed is called when a client refreshes its allocation for lifetime
+    fn on_allocation_refreshed(&self, _five_tuple: &FiveTuple, _lifetime: Duration) {}
+
+    // on_allocation_deleted is called once an allocation is deleted by its client, expired
+    // or was closed with the server, info holds its final counters
+    fn on_allocation_deleted(&self, _info: &AllocationInfo) {}
+
+    // on_permission_created is called when an allocation gets a permission for the IP
+    // address of peer
+    fn on_permission_created(&self, _five_tuple: &FiveTuple, _peer: SocketAddr) {}
+
+    // on_channel_bound is called when a channel of an allocation is bound to peer
+    fn on_channel_bound(&self, _five_tuple: &FiveTuple, _number: u16, _peer: SocketAddr) {}
+
+    // on_auth_failed is called when a request fails authentication because of an unknown
+    // username or a wrong password
+    fn on_auth_failed(&self, _src_addr: SocketAddr, _username: &str, _realm: &str) {}
+}
@@ -6,8 +6,11 @@ pub mod allocation;
 pub mod auth;
 pub mod client;
 mod error;
+pub mod events;
+pub mod policy;
 pub mod proto;
 pub mod relay;
 pub mod server;
+pub mod stun_conn;
 
 pub use error::Error;
@@ -0,0 +1,263 @@
+#[cfg(test)]
+mod policy_test;
+
+use crate::error::*;
+
+use ipnet::{IpNet, Ipv6Net};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+
+// PolicyHandler lets operators add their own checks on top of the built-in ones of Policy.
+// Returning an error rejects the allocation or peer.
+pub trait PolicyHandler {
+    // check_allocation is called before an allocation is created for an authenticated client
+    fn check_allocation(&self, _username: &str, _src_addr: SocketAddr) -> Result<()> {
+        Ok(())
+    }
+
+    // check_peer is called before a permission, channel binding or TCP connection with
+    // peer is made for an allocation of username
+    fn check_peer(&self, _username: &str, _peer: SocketAddr) -> Result<()> {
+        Ok(())
+    }
+}
+
+// PolicyConfig configures the limits the TURN server enforces on its clients
+pub struct PolicyConfig {
+    // max_allocations_per_user caps the allocations a username holds at the same time,
+    // across all listeners. 0 means unlimited.
+    pub max_allocations_per_user: usize,
+
+    // max_bytes_per_second caps the data relayed by an allocation, in both directions
+    // combined. UDP packets over the limit are dropped, TCP connections are slowed down.
+    // 0 means unlimited.
+    pub max_bytes_per_second: u64,
+
+    // denied_peers are the networks which clients can't relay to or receive from.
+    // Defaults to default_denied_peers.
+    pub denied_peers: Vec<IpNet>,
+
+    // policy_handler is called for the checks which pass the limits above
+    pub policy_handler: Option<Arc<dyn PolicyHandler + Send + Sync>>,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        PolicyConfig {
+            max_allocations_per_user: 0,
+            max_bytes_per_second: 0,
+            denied_peers: default_denied_peers(),
+            policy_handler: None,
+        }
+    }
+}
+
+// default_denied_peers returns the unspecified, loopback, link-local, private (RFC 1918,
+// RFC 4193), shared (RFC 6598), multicast and broadcast networks and the NAT64 prefix
+// (RFC 6052), so that a TURN server can't be used to reach the services of the host or its
+// internal network. The IPv4 networks are also denied in their IPv4-mapped form.
+pub fn default_denied_peers() -> Vec<IpNet> {
+    let v4: Vec<IpNet> = [
+        "0.0.0.0/8",
+        "127.0.0.0/8",
+        "10.0.0.0/8",
+        "172.16.0.0/12",
+        "192.168.0.0/16",
+        "169.254.0.0/16",
+        "100.64.0.0/10",
+        "224.0.0.0/4",
+        "255.255.255.255/32",
+    ]
+    .iter()
+    .filter_map(|net| net.parse().ok())
+    .collect();
+    let v6: Vec<IpNet> = [
+        "::/128",
+        "::1/128",
+        "fe80::/10",
+        "fc00::/7",
+        "ff00::/8",
+        "64:ff9b::/96",
+    ]
+    .iter()
+    .filter_map(|net| net.parse().ok())
+    .collect();
+    let v4_mapped: Vec<IpNet> = v4
+        .iter()
+        .filter_map(|net| match net {
+            IpNet::V4(net) => {
+                Ipv6Net::new(net.network().to_ipv6_mapped(), net.prefix_len() + 96).ok()
+            }
+            IpNet::V6(_) => None,
+        })
+        .map(IpNet::V6)
+        .collect();
+
+    [v4, v6, v4_mapped].concat()
+}
+
+// Policy enforces a PolicyConfig, it is shared by all the allocation managers of a server
+pub struct Policy {
+    max_allocations_per_user: usize,
+    max_bytes_per_second: u64,
+    denied_peers: Vec<IpNet>,
+    policy_handler: Option<Arc<dyn PolicyHandler + Send + Sync>>,
+    allocations: Mutex<HashMap<String, usize>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::new(PolicyConfig::default())
+    }
+}
+
+impl Policy {
+    pub fn new(config: PolicyConfig) -> Self {
+        Policy {
+            max_allocations_per_user: config.max_allocations_per_user,
+            max_bytes_per_second: config.max_bytes_per_second,
+            denied_peers: config.denied_peers,
+            policy_handler: config.policy_handler,
+            allocations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // acquire_allocation counts a new allocation of username, it fails with
+    // ErrAllocationQuotaReached if the quota of username is reached, or ErrAllocationDenied
+    // if the policy handler rejects the allocation. Every successful call must be paired
+    // with a release_allocation.
+    pub fn acquire_allocation(&self, username: &str, src_addr: SocketAddr) -> Result<()> {
+        if let Some(policy_handler) = &self.policy_handler {
+            if let Err(err) = policy_handler.check_allocation(username, src_addr) {
+                log::info!(
+                    "allocation of {} from {} denied: {}",
+                    username,
+                    src_addr,
+                    err
+                );
+                return Err(Error::ErrAllocationDenied);
+            }
+        }
+
+        let mut allocations = self.allocations.lock().unwrap();
+        let count = allocations.entry(username.to_owned()).or_insert(0);
+        if self.max_allocations_per_user != 0 && *count >= self.max_allocations_per_user {
+            return Err(Error::ErrAllocationQuotaReached);
+        }
+        *count += 1;
+
+        Ok(())
+    }
+
+    // release_allocation uncounts an allocation of username
+    pub fn release_allocation(&self, username: &str) {
+        let mut allocations = self.allocations.lock().unwrap();
+        if let Some(count) = allocations.get_mut(username) {
+            *count -= 1;
+            if *count == 0 {
+                allocations.remove(username);
+            }
+        }
+    }
+
+    // allocation_count returns the number of allocations username holds
+    pub fn allocation_count(&self, username: &str) -> usize {
+        let allocations = self.allocations.lock().unwrap();
+        allocations.get(username).copied().unwrap_or(0)
+    }
+
+    // check_peer fails if an allocation of username must not relay data with peer
+    pub fn check_peer(&self, username: &str, peer: SocketAddr) -> Result<()> {
+        // An IPv4-mapped peer is denied by the networks of either form
+        let ip = peer.ip();
+        let unmapped_ip = match ip {
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => IpAddr::V4(ip),
+                None => IpAddr::V6(ip),
+            },
+            ip => ip,
+        };
+        if self
+            .denied_peers
+            .iter()
+            .any(|net| net.contains(&ip) || net.contains(&unmapped_ip))
+        {
+            return Err(Error::ErrPeerAddressDenied);
+        }
+
+        if let Some(policy_handler) = &self.policy_handler {
+            policy_handler.check_peer(username, peer)?;
+        }
+
+        Ok(())
+    }
+
+    // rate_limiter returns a new limiter for the relayed data of an allocation, if there is
+    // a limit
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        if self.max_bytes_per_second == 0 {
+            None
+        } else {
+            Some(Arc::new(RateLimiter::new(self.max_bytes_per_second)))
+        }
+    }
+}
+
+// RateLimiter is a token bucket which allows bytes_per_second on average, with bursts of up
+// to one second worth of data.
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        RateLimiter {
+            bytes_per_second,
+            state: Mutex::new(RateLimiterState {
+                tokens: bytes_per_second as f64,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn refill(&self, state: &mut RateLimiterState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+        let burst = self.bytes_per_second as f64;
+        state.tokens = (state.tokens + elapsed * burst).min(burst);
+        state.updated_at = now;
+    }
+
+    // allow consumes n bytes if they are within the limit, the caller drops the data
+    // otherwise. It is used for datagrams.
+    pub fn allow(&self, n: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        if state.tokens < n as f64 {
+            return false;
+        }
+        state.tokens -= n as f64;
+        true
+    }
+
+    // reserve consumes n bytes and returns how long the caller must wait before sending
+    // them to stay within the limit. It is used for streams.
+    pub fn reserve(&self, n: usize) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        state.tokens -= n as f64;
+        if state.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.bytes_per_second as f64)
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use super::*;
+
+use std::str::FromStr;
+
+struct TestPolicyHandler;
+
+impl PolicyHandler for TestPolicyHandler {
+    fn check_allocation(&self, username: &str, _src_addr: SocketAddr) -> Result<()> {
+        if username == "banned" {
+            Err(Error::ErrFakeErr)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_peer(&self, _username: &str, peer: SocketAddr) -> Result<()> {
+        if peer.port() == 25 {
+            Err(Error::ErrFakeErr)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_policy_allocation_quota() -> Result<()> {
+    let src_addr = SocketAddr::from_str("1.2.3.4:5000")?;
+    let policy = Policy::new(PolicyConfig {
+        max_allocations_per_user: 2,
+        ..Default::default()
+    });
+
+    policy.acquire_allocation("user", src_addr)?;
+    policy.acquire_allocation("user", src_addr)?;
+    assert_eq!(
+        policy.acquire_allocation("user", src_addr),
+        Err(Error::ErrAllocationQuotaReached)
+    );
+    assert_eq!(policy.allocation_count("user"), 2);
+
+    // the quota is per username
+    policy.acquire_allocation("other", src_addr)?;
+
+    policy.release_allocation("user");
+    assert_eq!(policy.allocation_count("user"), 1);
+    policy.acquire_allocation("user", src_addr)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_policy_denied_peers() -> Result<()> {
+    let policy = Policy::default();
+
+    for peer in &[
+        "127.0.0.1:80",
+        "10.1.2.3:80",
+        "172.16.0.1:80",
+        "172.31.255.255:80",
+        "192.168.1.1:80",
+        "0.0.0.0:80",
+        "0.1.2.3:80",
+        "169.254.169.254:80",
+        "100.64.0.1:80",
+        "100.127.255.255:80",
+        "224.0.0.1:80",
+        "239.255.255.250:80",
+        "255.255.255.255:80",
+        "[::1]:80",
+        "[::]:80",
+        "[fe80::1]:80",
+        "[febf::1]:80",
+        "[fc00::1]:80",
+        "[fd12:3456::1]:80",
+        "[ff02::1]:80",
+        "[ff05::1:3]:80",
+        "[64:ff9b::a00:1]:80",
+        "[64:ff9b::7f00:1]:80",
+        "[::ffff:192.168.1.1]:80",
+        "[::ffff:127.0.0.1]:80",
+        "[::ffff:0.0.0.0]:80",
+        "[::ffff:169.254.169.254]:80",
+        "[::ffff:100.64.0.1]:80",
+        "[::ffff:224.0.0.1]:80",
+        "[::ffff:255.255.255.255]:80",
+    ] {
+        assert_eq!(
+            policy.check_peer("user", SocketAddr::from_str(peer)?),
+            Err(Error::ErrPeerAddressDenied),
+            "{} should be denied",
+            peer
+        );
+    }
+
+    for peer in &[
+        "1.2.3.4:80",
+        "172.32.0.1:80",
+        "169.255.0.1:80",
+        "100.63.255.255:80",
+        "100.128.0.1:80",
+        "223.255.255.255:80",
+        "240.0.0.1:80",
+        "[2001:db8::1]:80",
+        "[64:ff9b:1::a00:1]:80",
+        "[fec0::1]:80",
+        "[fe00::1]:80",
+        "[::ffff:1.2.3.4]:80",
+    ] {
+        policy.check_peer("user", SocketAddr::from_str(peer)?)?;
+    }
+
+    let policy = Policy::new(PolicyConfig {
+        denied_peers: vec![],
+        ..Default::default()
+    });
+    policy.check_peer("user", SocketAddr::from_str("127.0.0.1:80")?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_default_denied_peers_v4_mapped() -> Result<()> {
+    // The IPv4-mapped networks match on their own, e.g. when the list is extended
+    let denied_peers = default_denied_peers();
+    for ip in &[
+        "::ffff:0.0.0.1",
+        "::ffff:127.0.0.1",
+        "::ffff:10.1.2.3",
+        "::ffff:172.16.0.1",
+        "::ffff:192.168.1.1",
+        "::ffff:169.254.1.1",
+        "::ffff:100.64.0.1",
+        "::ffff:224.0.0.1",
+        "::ffff:255.255.255.255",
+    ] {
+        let ip = IpAddr::from_str(ip)?;
+        assert!(
+            denied_peers.iter().any(|net| net.contains(&ip)),
+            "{} should be denied",
+            ip
+        );
+    }
+
+    let ip = IpAddr::from_str("::ffff:1.2.3.4")?;
+    assert!(!denied_peers.iter().any(|net| net.contains(&ip)));
+
+    Ok(())
+}
+
+#[test]
+fn test_policy_handler() -> Result<()> {
+    let src_addr = SocketAddr::from_str("1.2.3.4:5000")?;
+    let policy = Policy::new(PolicyConfig {
+        policy_handler: Some(Arc::new(TestPolicyHandler)),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        policy.acquire_allocation("banned", src_addr),
+        Err(Error::ErrAllocationDenied)
+    );
+    assert_eq!(policy.allocation_count("banned"), 0);
+    policy.acquire_allocation("user", src_addr)?;
+
+    assert!(policy
+        .check_peer("user", SocketAddr::from_str("5.6.7.8:25")?)
+        .is_err());
+    policy.check_peer("user", SocketAddr::from_str("5.6.7.8:80")?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_rate_limiter() {
+    let rate_limiter = RateLimiter::new(1000);
+
+    // bursts of up to one second are allowed
+    assert!(rate_limiter.allow(600));
+    assert!(rate_limiter.allow(400));
+    assert!(!rate_limiter.allow(100));
+
+    // streams wait for the bytes they consumed beyond the limit
+    let wait = rate_limiter.reserve(500);
+    assert!(
+        wait > Duration::from_millis(400) && wait <= Duration::from_millis(500),
+        "unexpected wait {:?}",
+        wait
+    );
+}
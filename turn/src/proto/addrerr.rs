@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod addrerr_test;
+
+use super::reqfamily::RequestedAddressFamily;
+
+use std::fmt;
+use stun::attributes::*;
+use stun::checks::*;
+use stun::error_code::ErrorCode;
+use stun::message::*;
+
+// AddressErrorCode represents the ADDRESS-ERROR-CODE attribute. It is included
+// in a successful Allocate response to tell the client why the relayed transport
+// address of one of the families it requested could not be allocated.
+//
+// RFC 8656 Section 18.12
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AddressErrorCode {
+    pub family: RequestedAddressFamily,
+    pub code: ErrorCode,
+    pub reason: Vec<u8>,
+}
+
+impl fmt::Display for AddressErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}",
+            self.family,
+            self.code.0,
+            String::from_utf8_lossy(&self.reason)
+        )
+    }
+}
+
+// constants for ADDRESS-ERROR-CODE encoding, the same as for ERROR-CODE except
+// for the family in the first byte.
+const ADDRESS_ERROR_CODE_FAMILY_BYTE: usize = 0;
+const ADDRESS_ERROR_CODE_CLASS_BYTE: usize = 2;
+const ADDRESS_ERROR_CODE_NUMBER_BYTE: usize = 3;
+const ADDRESS_ERROR_CODE_REASON_START: usize = 4;
+const ADDRESS_ERROR_CODE_REASON_MAX_B: usize = 763;
+const ADDRESS_ERROR_CODE_MODULO: u16 = 100;
+
+impl Setter for AddressErrorCode {
+    // AddTo adds ADDRESS-ERROR-CODE to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        check_overflow(
+            ATTR_ADDRESS_ERROR_CODE,
+            self.reason.len() + ADDRESS_ERROR_CODE_REASON_START,
+            ADDRESS_ERROR_CODE_REASON_MAX_B + ADDRESS_ERROR_CODE_REASON_START,
+        )?;
+
+        let mut v = Vec::with_capacity(ADDRESS_ERROR_CODE_REASON_START + self.reason.len());
+        v.push(self.family.0);
+        v.push(0);
+        v.push((self.code.0 / ADDRESS_ERROR_CODE_MODULO) as u8); // hundred digit
+        v.push((self.code.0 % ADDRESS_ERROR_CODE_MODULO) as u8); // error code modulo 100
+        v.extend_from_slice(&self.reason);
+        m.add(ATTR_ADDRESS_ERROR_CODE, &v);
+        Ok(())
+    }
+}
+
+impl Getter for AddressErrorCode {
+    // GetFrom decodes ADDRESS-ERROR-CODE from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        let v = m.get(ATTR_ADDRESS_ERROR_CODE)?;
+        if v.len() < ADDRESS_ERROR_CODE_REASON_START {
+            return Err(stun::Error::ErrUnexpectedEof);
+        }
+
+        let class = v[ADDRESS_ERROR_CODE_CLASS_BYTE] as u16;
+        let number = v[ADDRESS_ERROR_CODE_NUMBER_BYTE] as u16;
+        self.family = RequestedAddressFamily(v[ADDRESS_ERROR_CODE_FAMILY_BYTE]);
+        self.code = ErrorCode(class * ADDRESS_ERROR_CODE_MODULO + number);
+        self.reason = v[ADDRESS_ERROR_CODE_REASON_START..].to_vec();
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use super::super::reqfamily::REQUESTED_FAMILY_IPV6;
+use super::*;
+
+use stun::error_code::CODE_ADDR_FAMILY_NOT_SUPPORTED;
+
+#[test]
+fn test_address_error_code() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let a = AddressErrorCode {
+        family: REQUESTED_FAMILY_IPV6,
+        code: CODE_ADDR_FAMILY_NOT_SUPPORTED,
+        reason: b"Address Family not Supported".to_vec(),
+    };
+    assert_eq!(a.to_string(), "IPv6: 440: Address Family not Supported");
+    a.add_to(&mut m)?;
+    m.write_header();
+
+    //"GetFrom"
+    {
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut got = AddressErrorCode::default();
+        got.get_from(&decoded)?;
+        assert_eq!(got, a, "Decoded {}, expected {}", got, a);
+
+        //"HandleErr"
+        {
+            let mut m = Message::new();
+            let mut handle = AddressErrorCode::default();
+            if let Err(err) = handle.get_from(&m) {
+                assert_eq!(
+                    stun::Error::ErrAttributeNotFound,
+                    err,
+                    "{} should be not found",
+                    err
+                );
+            } else {
+                panic!("expected error, but got ok");
+            }
+            m.add(ATTR_ADDRESS_ERROR_CODE, &[2, 0, 4]);
+            assert_eq!(
+                handle.get_from(&m),
+                Err(stun::Error::ErrUnexpectedEof),
+                "should fail on a truncated value"
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn test_connection_id() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let id = ConnectionId(0x12345678);
+    assert_eq!(id.to_string(), "305419896", "bad string {}", id);
+    id.add_to(&mut m)?;
+    m.write_header();
+
+    //"GetFrom"
+    {
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut got = ConnectionId::default();
+        got.get_from(&decoded)?;
+        assert_eq!(got, id, "Decoded {}, expected {}", got, id);
+
+        //"HandleErr"
+        {
+            let mut m = Message::new();
+            let mut handle = ConnectionId::default();
+            if let Err(err) = handle.get_from(&m) {
+                assert_eq!(
+                    stun::Error::ErrAttributeNotFound,
+                    err,
+                    "{} should be not found",
+                    err
+                );
+            } else {
+                panic!("expected error, but got ok");
+            }
+            m.add(ATTR_CONNECTION_ID, &[1, 2, 3]);
+            if let Err(err) = handle.get_from(&m) {
+                assert!(
+                    is_attr_size_invalid(&err),
+                    "IsAttrSizeInvalid should be true"
+                );
+            } else {
+                panic!("expected error, got ok");
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -2,8 +2,10 @@
 mod proto_test;
 
 pub mod addr;
+pub mod addrerr;
 pub mod chandata;
 pub mod channum;
+pub mod connid;
 pub mod data;
 pub mod dontfrag;
 pub mod evenport;
@@ -67,3 +69,18 @@ pub fn send_indication() -> MessageType {
 pub fn refresh_request() -> MessageType {
     MessageType::new(METHOD_REFRESH, CLASS_REQUEST)
 }
+
+// connect_request is shorthand for connect request message type.
+pub fn connect_request() -> MessageType {
+    MessageType::new(METHOD_CONNECT, CLASS_REQUEST)
+}
+
+// connection_bind_request is shorthand for connection bind request message type.
+pub fn connection_bind_request() -> MessageType {
+    MessageType::new(METHOD_CONNECTION_BIND, CLASS_REQUEST)
+}
+
+// connection_attempt_indication is shorthand for connection attempt indication message type.
+pub fn connection_attempt_indication() -> MessageType {
+    MessageType::new(METHOD_CONNECTION_ATTEMPT, CLASS_INDICATION)
+}
@@ -6,6 +6,7 @@ use stun::checks::*;
 use stun::message::*;
 
 use std::fmt;
+use std::net::SocketAddr;
 
 // Values for RequestedAddressFamily as defined in RFC 6156 Section 4.1.1.
 pub const REQUESTED_FAMILY_IPV4: RequestedAddressFamily = RequestedAddressFamily(0x01);
@@ -13,9 +14,20 @@ pub const REQUESTED_FAMILY_IPV6: RequestedAddressFamily = RequestedAddressFamily
 
 // RequestedAddressFamily represents the REQUESTED-ADDRESS-FAMILY Attribute as
 // defined in RFC 6156 Section 4.1.1.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub struct RequestedAddressFamily(pub u8);
 
+impl RequestedAddressFamily {
+    // of returns the family of addr
+    pub fn of(addr: &SocketAddr) -> Self {
+        if addr.is_ipv4() {
+            REQUESTED_FAMILY_IPV4
+        } else {
+            REQUESTED_FAMILY_IPV6
+        }
+    }
+}
+
 impl fmt::Display for RequestedAddressFamily {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match *self {
@@ -29,28 +41,20 @@ impl fmt::Display for RequestedAddressFamily {
 
 const REQUESTED_FAMILY_SIZE: usize = 4;
 
-impl Setter for RequestedAddressFamily {
-    // AddTo adds REQUESTED-ADDRESS-FAMILY to message.
-    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+impl RequestedAddressFamily {
+    fn add_to_as(&self, m: &mut Message, t: AttrType) -> Result<(), stun::Error> {
         let mut v = vec![0; REQUESTED_FAMILY_SIZE];
         v[0] = self.0;
         // b[1:4] is RFFU = 0.
         // The RFFU field MUST be set to zero on transmission and MUST be
         // ignored on reception. It is reserved for future uses.
-        m.add(ATTR_REQUESTED_ADDRESS_FAMILY, &v);
+        m.add(t, &v);
         Ok(())
     }
-}
 
-impl Getter for RequestedAddressFamily {
-    // GetFrom decodes REQUESTED-ADDRESS-FAMILY from message.
-    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
-        let v = m.get(ATTR_REQUESTED_ADDRESS_FAMILY)?;
-        check_size(
-            ATTR_REQUESTED_ADDRESS_FAMILY,
-            v.len(),
-            REQUESTED_FAMILY_SIZE,
-        )?;
+    fn get_from_as(&mut self, m: &Message, t: AttrType) -> Result<(), stun::Error> {
+        let v = m.get(t)?;
+        check_size(t, v.len(), REQUESTED_FAMILY_SIZE)?;
 
         if v[0] != REQUESTED_FAMILY_IPV4.0 && v[0] != REQUESTED_FAMILY_IPV6.0 {
             return Err(stun::Error::Other("ErrInvalidRequestedFamilyValue".into()));
@@ -59,3 +63,39 @@ impl Getter for RequestedAddressFamily {
         Ok(())
     }
 }
+
+impl Setter for RequestedAddressFamily {
+    // AddTo adds REQUESTED-ADDRESS-FAMILY to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        self.add_to_as(m, ATTR_REQUESTED_ADDRESS_FAMILY)
+    }
+}
+
+impl Getter for RequestedAddressFamily {
+    // GetFrom decodes REQUESTED-ADDRESS-FAMILY from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        self.get_from_as(m, ATTR_REQUESTED_ADDRESS_FAMILY)
+    }
+}
+
+// AdditionalAddressFamily represents the ADDITIONAL-ADDRESS-FAMILY Attribute,
+// with which a client requests an IPv6 relayed transport address in addition
+// to the IPv4 one. It is encoded like REQUESTED-ADDRESS-FAMILY.
+//
+// RFC 8656 Section 18.11
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct AdditionalAddressFamily(pub RequestedAddressFamily);
+
+impl Setter for AdditionalAddressFamily {
+    // AddTo adds ADDITIONAL-ADDRESS-FAMILY to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        self.0.add_to_as(m, ATTR_ADDITIONAL_ADDRESS_FAMILY)
+    }
+}
+
+impl Getter for AdditionalAddressFamily {
+    // GetFrom decodes ADDITIONAL-ADDRESS-FAMILY from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        self.0.get_from_as(m, ATTR_ADDITIONAL_ADDRESS_FAMILY)
+    }
+}
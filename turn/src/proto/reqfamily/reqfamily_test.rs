@@ -76,3 +76,23 @@ fn test_requested_address_family_add_to() -> Result<(), stun::Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_additional_address_family_add_to() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let a = AdditionalAddressFamily(REQUESTED_FAMILY_IPV6);
+    a.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    assert!(
+        !decoded.contains(ATTR_REQUESTED_ADDRESS_FAMILY),
+        "should not be encoded as REQUESTED-ADDRESS-FAMILY"
+    );
+    let mut got = AdditionalAddressFamily::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, a, "Decoded {:?}, expected {:?}", got, a);
+
+    Ok(())
+}
@@ -1,14 +1,19 @@
+pub mod relay_dual_stack;
 pub mod relay_none;
 pub mod relay_range;
 pub mod relay_static;
 
-use crate::error::Result;
+use crate::error::*;
 
 use util::Conn;
 
 use async_trait::async_trait;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+// TCP_LISTEN_BACKLOG is the backlog of the listeners of TCP relayed transport addresses
+const TCP_LISTEN_BACKLOG: u32 = 128;
 
 // RelayAddressGenerator is used to generate a RelayAddress when creating an allocation.
 // You can use one of the provided ones or provide your own.
@@ -17,10 +22,70 @@ pub trait RelayAddressGenerator {
     // validate confirms that the RelayAddressGenerator is properly initialized
     fn validate(&self) -> Result<()>;
 
+    // supports_family returns whether relayed addresses of the IPv4 family (use_ipv4) or
+    // of the IPv6 family can be allocated. Generators which don't override it are IPv4 only.
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        use_ipv4
+    }
+
     // Allocate a RelayAddress
     async fn allocate_conn(
         &self,
         use_ipv4: bool,
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)>;
+
+    // Allocate a TCP listener for the RelayAddress of a TCP allocation (RFC 6062).
+    // Generators which can't relay over TCP keep this default.
+    async fn allocate_listener(
+        &self,
+        _use_ipv4: bool,
+        _requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        Err(Error::ErrTcpRelayUnsupported)
+    }
+}
+
+// is_ipv4_address returns whether address, the listening address of a generator, is an
+// IPv4 address. IPv6 addresses are written in brackets, e.g. "[::]", host names are
+// assumed to be IPv4.
+fn is_ipv4_address(address: &str) -> bool {
+    address
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .map(|ip| ip.is_ipv4())
+        .unwrap_or(true)
+}
+
+fn new_tcp_socket(addr: SocketAddr) -> Result<TcpSocket> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    Ok(socket)
+}
+
+// bind_tcp_relay binds a listener for a TCP relayed transport address, the peers
+// connect to it and connect_tcp_relay opens connections to peers from its address.
+pub fn bind_tcp_relay(addr: SocketAddr) -> Result<TcpListener> {
+    let socket = new_tcp_socket(addr)?;
+    // SO_REUSEPORT is only set once the address is bound, so that the bind
+    // still fails if the port is used by another relay
+    socket.bind(addr)?;
+    #[cfg(unix)]
+    socket.set_reuseport(true)?;
+    Ok(socket.listen(TCP_LISTEN_BACKLOG)?)
+}
+
+// connect_tcp_relay opens a connection to peer from the address of a listener bound
+// by bind_tcp_relay.
+pub async fn connect_tcp_relay(local_addr: SocketAddr, peer: SocketAddr) -> Result<TcpStream> {
+    let socket = new_tcp_socket(local_addr)?;
+    #[cfg(unix)]
+    socket.set_reuseport(true)?;
+    socket.bind(local_addr)?;
+    Ok(socket.connect(peer).await?)
 }
@@ -0,0 +1,67 @@
+use super::*;
+use crate::error::*;
+
+use async_trait::async_trait;
+
+// RelayAddressGeneratorDualStack allocates IPv4 and IPv6 relayed addresses (RFC 8656) with a
+// generator for each family, e.g. two RelayAddressGeneratorStatic with the relay_address and
+// the listening address of their family. IPv6 listening addresses are written in brackets,
+// such as "[::]".
+pub struct RelayAddressGeneratorDualStack {
+    // ipv4 allocates the IPv4 relayed addresses
+    pub ipv4: Box<dyn RelayAddressGenerator + Send + Sync>,
+
+    // ipv6 allocates the IPv6 relayed addresses
+    pub ipv6: Box<dyn RelayAddressGenerator + Send + Sync>,
+}
+
+impl RelayAddressGeneratorDualStack {
+    fn generator(&self, use_ipv4: bool) -> &(dyn RelayAddressGenerator + Send + Sync) {
+        if use_ipv4 {
+            self.ipv4.as_ref()
+        } else {
+            self.ipv6.as_ref()
+        }
+    }
+}
+
+#[async_trait]
+impl RelayAddressGenerator for RelayAddressGeneratorDualStack {
+    // validate confirms that both generators are properly initialized for their family
+    fn validate(&self) -> Result<()> {
+        self.ipv4.validate()?;
+        self.ipv6.validate()?;
+
+        if !self.ipv4.supports_family(true) || !self.ipv6.supports_family(false) {
+            Err(Error::ErrRelayAddressFamilyMismatch)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        self.generator(use_ipv4).supports_family(use_ipv4)
+    }
+
+    // Allocate a PacketConn (UDP) RelayAddress of the given family
+    async fn allocate_conn(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        self.generator(use_ipv4)
+            .allocate_conn(use_ipv4, requested_port)
+            .await
+    }
+
+    // Allocate a TCP listener for the RelayAddress of a TCP allocation of the given family
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        self.generator(use_ipv4)
+            .allocate_listener(use_ipv4, requested_port)
+            .await
+    }
+}
@@ -22,6 +22,11 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
         }
     }
 
+    // supports_family returns whether address has the given family
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        is_ipv4_address(&self.address) == use_ipv4
+    }
+
     // Allocate a PacketConn (UDP) RelayAddress
     async fn allocate_conn(
         &self,
@@ -36,4 +41,23 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
         let relay_addr = conn.local_addr().await?;
         Ok((conn, relay_addr))
     }
+
+    // Allocate a TCP listener for the RelayAddress of a TCP allocation
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayUnsupported);
+        }
+
+        let addr = self
+            .net
+            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .await?;
+        let listener = bind_tcp_relay(addr)?;
+        let relay_addr = listener.local_addr()?;
+        Ok((listener, relay_addr))
+    }
 }
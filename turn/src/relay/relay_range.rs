@@ -42,6 +42,11 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
         }
     }
 
+    // supports_family returns whether relay_address has the given family
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        self.relay_address.is_ipv4() == use_ipv4
+    }
+
     // Allocate a PacketConn (UDP) relay_address
     async fn allocate_conn(
         &self,
@@ -83,4 +88,50 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
 
         Err(Error::ErrMaxRetriesExceeded)
     }
+
+    // Allocate a TCP listener for the RelayAddress of a TCP allocation
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayUnsupported);
+        }
+
+        let max_retries = if self.max_retries == 0 {
+            10
+        } else {
+            self.max_retries
+        };
+
+        if requested_port != 0 {
+            let addr = self
+                .net
+                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+                .await?;
+            let listener = bind_tcp_relay(addr)?;
+            let mut relay_addr = listener.local_addr()?;
+            relay_addr.set_ip(self.relay_address);
+            return Ok((listener, relay_addr));
+        }
+
+        for _ in 0..max_retries {
+            let port = self.min_port + rand::random::<u16>() % (self.max_port - self.min_port + 1);
+            let addr = self
+                .net
+                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, port))
+                .await?;
+            let listener = match bind_tcp_relay(addr) {
+                Ok(listener) => listener,
+                Err(_) => continue,
+            };
+
+            let mut relay_addr = listener.local_addr()?;
+            relay_addr.set_ip(self.relay_address);
+            return Ok((listener, relay_addr));
+        }
+
+        Err(Error::ErrMaxRetriesExceeded)
+    }
 }
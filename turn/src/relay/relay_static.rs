@@ -28,6 +28,11 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
         }
     }
 
+    // supports_family returns whether relay_address has the given family
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        self.relay_address.is_ipv4() == use_ipv4
+    }
+
     // Allocate a PacketConn (UDP) RelayAddress
     async fn allocate_conn(
         &self,
@@ -43,4 +48,24 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
         relay_addr.set_ip(self.relay_address);
         return Ok((conn, relay_addr));
     }
+
+    // Allocate a TCP listener for the RelayAddress of a TCP allocation
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayUnsupported);
+        }
+
+        let addr = self
+            .net
+            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .await?;
+        let listener = bind_tcp_relay(addr)?;
+        let mut relay_addr = listener.local_addr()?;
+        relay_addr.set_ip(self.relay_address);
+        Ok((listener, relay_addr))
+    }
 }
@@ -1,7 +1,10 @@
 use crate::auth::*;
 use crate::error::*;
+use crate::events::EventHandler;
+use crate::policy::PolicyConfig;
 use crate::relay::*;
 
+use util::conn::Listener;
 use util::Conn;
 
 use std::sync::Arc;
@@ -22,12 +25,32 @@ impl ConnConfig {
     }
 }
 
+// ListenerConfig is used for TCP and TLS listeners
+pub struct ListenerConfig {
+    // Clients connected to the listener are served with stream framing,
+    // see stun_conn::StunListener
+    pub listener: Arc<dyn Listener + Send + Sync>,
+
+    // When an allocation is generated the RelayAddressGenerator
+    // creates the net.PacketConn and returns the IP/Port it is available at
+    pub relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+}
+
+impl ListenerConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.relay_addr_generator.validate()
+    }
+}
+
 // ServerConfig configures the Pion TURN Server
 pub struct ServerConfig {
     // conn_configs are a list of all the turn listeners
     // Each listener can have custom behavior around the creation of Relays
     pub conn_configs: Vec<ConnConfig>,
 
+    // listener_configs are a list of all the TCP and TLS turn listeners
+    pub listener_configs: Vec<ListenerConfig>,
+
     // realm sets the realm for this server
     pub realm: String,
 
@@ -36,17 +59,27 @@ pub struct ServerConfig {
 
     // channel_bind_timeout sets the lifetime of channel binding. Defaults to 10 minutes.
     pub channel_bind_timeout: Duration,
+
+    // policy sets the allocation quotas, bandwidth limits and denied peers of the server
+    pub policy: PolicyConfig,
+
+    // event_handler is notified of the allocations, permissions, channel bindings and
+    // authentication failures of the server
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 impl ServerConfig {
     pub fn validate(&self) -> Result<()> {
-        if self.conn_configs.is_empty() {
+        if self.conn_configs.is_empty() && self.listener_configs.is_empty() {
             return Err(Error::ErrNoAvailableConns);
         }
 
         for cc in &self.conn_configs {
             cc.validate()?;
         }
+        for lc in &self.listener_configs {
+            lc.validate()?;
+        }
         Ok(())
     }
 }
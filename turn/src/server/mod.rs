@@ -5,8 +5,12 @@ pub mod config;
 pub mod request;
 
 use crate::{
-    allocation::allocation_manager::*, auth::AuthHandler, error::*,
-    proto::lifetime::DEFAULT_LIFETIME,
+    allocation::{allocation_manager::*, five_tuple::FiveTuple, AllocationInfo},
+    auth::AuthHandler,
+    error::*,
+    policy::Policy,
+    proto::{lifetime::DEFAULT_LIFETIME, Protocol, PROTO_TCP, PROTO_UDP},
+    stun_conn::MAX_FRAME_SIZE,
 };
 use config::*;
 use request::*;
@@ -16,11 +20,11 @@ use std::{collections::HashMap, sync::Arc};
 use tokio::{
     sync::{
         broadcast::{self, error::RecvError},
-        mpsc, oneshot, Mutex,
+        mpsc, watch, Mutex,
     },
     time::{Duration, Instant},
 };
-use util::Conn;
+use util::{conn::Listener, Conn};
 
 const INBOUND_MTU: usize = 1500;
 
@@ -39,6 +43,7 @@ impl Server {
         config.validate()?;
 
         let (command_tx, _) = broadcast::channel(16);
+        let policy = Arc::new(Policy::new(config.policy));
         let mut s = Server {
             auth_handler: config.auth_handler,
             realm: config.realm,
@@ -60,16 +65,55 @@ impl Server {
             let conn = p.conn;
             let allocation_manager = Arc::new(Manager::new(ManagerConfig {
                 relay_addr_generator: p.relay_addr_generator,
+                policy: Arc::clone(&policy),
+                event_handler: config.event_handler.clone(),
             }));
+            let (close_tx, close_rx) = watch::channel(false);
 
+            tokio::spawn(Server::handle_commands(
+                Arc::clone(&allocation_manager),
+                handle_rx,
+                close_tx,
+            ));
             tokio::spawn(Server::read_loop(
                 conn,
+                PROTO_UDP,
                 allocation_manager,
                 nonces,
                 auth_handler,
                 realm,
                 channel_bind_timeout,
+                close_rx,
+            ));
+        }
+
+        for p in config.listener_configs.into_iter() {
+            let nonces = Arc::clone(&s.nonces);
+            let auth_handler = Arc::clone(&s.auth_handler);
+            let realm = s.realm.clone();
+            let channel_bind_timeout = s.channel_bind_timeout;
+            let handle_rx = command_tx.subscribe();
+            let listener = p.listener;
+            let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+                relay_addr_generator: p.relay_addr_generator,
+                policy: Arc::clone(&policy),
+                event_handler: config.event_handler.clone(),
+            }));
+            let (close_tx, close_rx) = watch::channel(false);
+
+            tokio::spawn(Server::handle_commands(
+                Arc::clone(&allocation_manager),
                 handle_rx,
+                close_tx,
+            ));
+            tokio::spawn(Server::accept_loop(
+                listener,
+                allocation_manager,
+                nonces,
+                auth_handler,
+                realm,
+                channel_bind_timeout,
+                close_rx,
             ));
         }
 
@@ -92,43 +136,120 @@ impl Server {
         }
     }
 
-    async fn read_loop(
-        conn: Arc<dyn Conn + Send + Sync>,
+    /// Returns a snapshot of all live [`crate::allocation::Allocation`]s, with their
+    /// five-tuple, username, age and relay counters.
+    pub async fn allocations_info(&self) -> Result<Vec<AllocationInfo>> {
+        let tx = self.command_tx.lock().await.clone();
+        if let Some(tx) = tx {
+            let (infos_tx, mut infos_rx) = mpsc::channel(1);
+            tx.send(Command::GetAllocationsInfo(infos_tx))
+                .map_err(|_| Error::ErrClosed)?;
+
+            // every allocation manager answers, then drops its sender
+            let mut infos = vec![];
+            while let Some(mut manager_infos) = infos_rx.recv().await {
+                infos.append(&mut manager_infos);
+            }
+
+            Ok(infos)
+        } else {
+            Err(Error::ErrClosed)
+        }
+    }
+
+    // handle_commands applies the commands sent by the public methods to one allocation
+    // manager, and signals close_tx once the server is closed.
+    async fn handle_commands(
+        allocation_manager: Arc<Manager>,
+        mut handle_rx: broadcast::Receiver<Command>,
+        close_tx: watch::Sender<bool>,
+    ) {
+        loop {
+            match handle_rx.recv().await {
+                Ok(Command::DeleteAllocations(name, _)) => {
+                    allocation_manager
+                        .delete_allocations_by_username(name.as_str())
+                        .await;
+                    continue;
+                }
+                Ok(Command::GetAllocationsInfo(infos_tx)) => {
+                    let _ = infos_tx
+                        .send(allocation_manager.allocations_info().await)
+                        .await;
+                    continue;
+                }
+                Err(RecvError::Closed) | Ok(Command::Close(_)) => {
+                    let _ = close_tx.send(true);
+                    break;
+                }
+                Err(RecvError::Lagged(n)) => {
+                    log::warn!("Turn server has lagged by {} messages", n);
+                    continue;
+                }
+            }
+        }
+    }
+
+    // accept_loop serves every client connected to a TCP or TLS listener with its own
+    // read_loop, all of them share the allocation manager of the listener.
+    #[allow(clippy::too_many_arguments)]
+    async fn accept_loop(
+        listener: Arc<dyn Listener + Send + Sync>,
         allocation_manager: Arc<Manager>,
         nonces: Arc<Mutex<HashMap<String, Instant>>>,
         auth_handler: Arc<dyn AuthHandler + Send + Sync>,
         realm: String,
         channel_bind_timeout: Duration,
-        mut handle_rx: broadcast::Receiver<Command>,
+        mut close_rx: watch::Receiver<bool>,
     ) {
-        let mut buf = vec![0u8; INBOUND_MTU];
-
-        let (mut close_tx, mut close_rx) = oneshot::channel::<()>();
-
-        tokio::spawn({
-            let allocation_manager = Arc::clone(&allocation_manager);
-
-            async move {
-                loop {
-                    match handle_rx.recv().await {
-                        Ok(Command::DeleteAllocations(name, _)) => {
-                            allocation_manager
-                                .delete_allocations_by_username(name.as_str())
-                                .await;
-                            continue;
-                        }
-                        Err(RecvError::Closed) | Ok(Command::Close(_)) => {
-                            close_rx.close();
+        loop {
+            let conn = tokio::select! {
+                v = listener.accept() => {
+                    match v {
+                        Ok((conn, _)) => conn,
+                        Err(err) => {
+                            log::debug!("exit accept loop on error: {}", err);
                             break;
                         }
-                        Err(RecvError::Lagged(n)) => {
-                            log::warn!("Turn server has lagged by {} messages", n);
-                            continue;
-                        }
                     }
-                }
-            }
-        });
+                },
+                _ = close_rx.changed() => break
+            };
+
+            tokio::spawn(Server::read_loop(
+                conn,
+                PROTO_TCP,
+                Arc::clone(&allocation_manager),
+                Arc::clone(&nonces),
+                Arc::clone(&auth_handler),
+                realm.clone(),
+                channel_bind_timeout,
+                close_rx.clone(),
+            ));
+        }
+
+        let _ = listener.close().await;
+        let _ = allocation_manager.close().await;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn read_loop(
+        conn: Arc<dyn Conn + Send + Sync>,
+        protocol: Protocol,
+        allocation_manager: Arc<Manager>,
+        nonces: Arc<Mutex<HashMap<String, Instant>>>,
+        auth_handler: Arc<dyn AuthHandler + Send + Sync>,
+        realm: String,
+        channel_bind_timeout: Duration,
+        mut close_rx: watch::Receiver<bool>,
+    ) {
+        // messages on a stream aren't limited by the MTU, a buffer too short for one
+        // would end the connection after it was already read
+        let mut buf = if protocol == PROTO_TCP {
+            vec![0u8; MAX_FRAME_SIZE]
+        } else {
+            vec![0u8; INBOUND_MTU]
+        };
 
         loop {
             let (n, addr) = tokio::select! {
@@ -141,13 +262,15 @@ impl Server {
                         }
                     }
                 },
-                _ = close_tx.closed() => break
+                _ = close_rx.changed() => break
             };
 
             let mut r = Request {
                 conn: Arc::clone(&conn),
                 src_addr: addr,
                 buff: buf[..n].to_vec(),
+                protocol,
+                is_data_connection: false,
                 allocation_manager: Arc::clone(&allocation_manager),
                 nonces: Arc::clone(&nonces),
                 auth_handler: Arc::clone(&auth_handler),
@@ -158,9 +281,28 @@ impl Server {
             if let Err(err) = r.handle_request().await {
                 log::error!("error when handling datagram: {}", err);
             }
+
+            if r.is_data_connection {
+                // the connection is relayed to a peer of a TCP allocation now
+                return;
+            }
         }
 
-        let _ = allocation_manager.close().await;
+        if protocol == PROTO_UDP {
+            let _ = allocation_manager.close().await;
+        } else if let (Some(src_addr), Ok(dst_addr)) =
+            (conn.remote_addr().await, conn.local_addr().await)
+        {
+            // a stream carries the single allocation of its client, which
+            // ends with the connection
+            allocation_manager
+                .delete_allocation(&FiveTuple {
+                    protocol,
+                    src_addr,
+                    dst_addr,
+                })
+                .await;
+        }
         let _ = conn.close().await;
     }
 
@@ -182,13 +324,16 @@ impl Server {
 }
 
 /// The protocol to communicate between the [`Server`]'s public methods
-/// and the tasks spawned in the [`read_loop`] method.
+/// and the tasks spawned in the [`Server::new`] method.
 #[derive(Clone)]
 enum Command {
     /// Command to delete [`crate::allocation::Allocation`] by provided
     /// `username`.
     DeleteAllocations(String, Arc<mpsc::Receiver<()>>),
 
+    /// Command to get the [`AllocationInfo`]s of all allocations.
+    GetAllocationsInfo(mpsc::Sender<Vec<AllocationInfo>>),
+
     /// Command to close the [`Server`].
     Close(Arc<mpsc::Receiver<()>>),
 }
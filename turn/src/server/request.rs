@@ -5,18 +5,23 @@ use crate::allocation::allocation_manager::*;
 use crate::allocation::channel_bind::ChannelBind;
 use crate::allocation::five_tuple::*;
 use crate::allocation::permission::Permission;
+use crate::allocation::tcp_relay::*;
 use crate::auth::*;
 use crate::error::*;
+use crate::proto::addrerr::AddressErrorCode;
 use crate::proto::chandata::ChannelData;
 use crate::proto::channum::ChannelNumber;
+use crate::proto::connid::ConnectionId;
 use crate::proto::data::Data;
 use crate::proto::evenport::EvenPort;
 use crate::proto::lifetime::*;
 use crate::proto::peeraddr::PeerAddress;
 use crate::proto::relayaddr::RelayedAddress;
+use crate::proto::reqfamily::*;
 use crate::proto::reqtrans::RequestedTransport;
 use crate::proto::rsrvtoken::ReservationToken;
 use crate::proto::*;
+use crate::relay::connect_tcp_relay;
 
 use stun::agent::*;
 use stun::attributes::*;
@@ -24,8 +29,10 @@ use stun::error_code::*;
 use stun::fingerprint::*;
 use stun::integrity::*;
 use stun::message::*;
+use stun::password_algorithm::*;
 use stun::textattrs::*;
 use stun::uattrs::*;
+use stun::userhash::*;
 use stun::xoraddr::*;
 
 use util::Conn;
@@ -36,7 +43,7 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::Mutex;
-use tokio::time::{Duration, Instant};
+use tokio::time::{timeout, Duration, Instant};
 
 use md5::{Digest, Md5};
 
@@ -49,6 +56,10 @@ pub struct Request {
     pub conn: Arc<dyn Conn + Send + Sync>,
     pub src_addr: SocketAddr,
     pub buff: Vec<u8>,
+    // transport protocol of conn, UDP or TCP (also used for TLS)
+    pub protocol: Protocol,
+    // set once conn became the data connection of a TCP allocation, it must not be read anymore
+    pub(crate) is_data_connection: bool,
 
     // Server State
     pub allocation_manager: Arc<Manager>,
@@ -71,6 +82,8 @@ impl Request {
             conn,
             src_addr,
             buff: vec![],
+            protocol: PROTO_UDP,
+            is_data_connection: false,
             allocation_manager,
             nonces: Arc::new(Mutex::new(HashMap::new())),
             auth_handler,
@@ -129,6 +142,8 @@ impl Request {
                 METHOD_CREATE_PERMISSION => self.handle_create_permission_request(m).await,
                 METHOD_CHANNEL_BIND => self.handle_channel_bind_request(m).await,
                 METHOD_BINDING => self.handle_binding_request(m).await,
+                METHOD_CONNECT => self.handle_connect_request(m).await,
+                METHOD_CONNECTION_BIND => self.handle_connection_bind_request(m).await,
                 _ => Err(Error::ErrUnexpectedClass),
             }
         } else {
@@ -140,8 +155,8 @@ impl Request {
         &mut self,
         m: &Message,
         calling_method: Method,
-    ) -> Result<Option<(Username, MessageIntegrity)>> {
-        if !m.contains(ATTR_MESSAGE_INTEGRITY) {
+    ) -> Result<Option<(Username, Integrity)>> {
+        if !m.contains(ATTR_MESSAGE_INTEGRITY) && !m.contains(ATTR_MESSAGE_INTEGRITY_SHA256) {
             self.respond_with_nonce(m, calling_method, CODE_UNAUTHORIZED)
                 .await?;
             return Ok(None);
@@ -193,18 +208,53 @@ impl Request {
             build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
             return Ok(None);
         }
-        if let Err(err) = username_attr.get_from(m) {
+        if m.contains(ATTR_USER_HASH) {
+            let mut user_hash = UserHash::default();
+            if let Err(err) = user_hash.get_from(m) {
+                build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
+                return Ok(None);
+            }
+            match self.auth_handler.username_from_user_hash(
+                &user_hash.0,
+                &realm_attr.text,
+                self.src_addr,
+            ) {
+                Ok(username) => username_attr.text = username,
+                Err(_) => {
+                    username_attr.text = user_hash.to_string();
+                    self.auth_failed(&username_attr, &realm_attr);
+                    build_and_send_err(
+                        &self.conn,
+                        self.src_addr,
+                        bad_request_msg,
+                        Error::ErrNoSuchUser,
+                    )
+                    .await?;
+                    return Ok(None);
+                }
+            }
+        } else if let Err(err) = username_attr.get_from(m) {
             build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
             return Ok(None);
         }
 
-        let our_key = match self.auth_handler.auth_handle(
+        let password_algorithm = match self.password_algorithm(m) {
+            Ok(password_algorithm) => password_algorithm,
+            Err(err) => {
+                build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await?;
+                return Ok(None);
+            }
+        };
+
+        let our_key = match self.auth_handler.auth_handle_with_algorithm(
             &username_attr.to_string(),
             &realm_attr.to_string(),
             self.src_addr,
+            &password_algorithm,
         ) {
             Ok(key) => key,
             Err(_) => {
+                self.auth_failed(&username_attr, &realm_attr);
                 build_and_send_err(
                     &self.conn,
                     self.src_addr,
@@ -216,8 +266,13 @@ impl Request {
             }
         };
 
-        let mi = MessageIntegrity(our_key);
+        let mi = if m.contains(ATTR_MESSAGE_INTEGRITY_SHA256) {
+            Integrity::Sha256(MessageIntegritySha256(our_key))
+        } else {
+            Integrity::Sha1(MessageIntegrity(our_key))
+        };
         if let Err(err) = mi.check(&mut m.clone()) {
+            self.auth_failed(&username_attr, &realm_attr);
             build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
             Ok(None)
         } else {
@@ -225,13 +280,62 @@ impl Request {
         }
     }
 
+    // password_algorithm returns the password algorithm of the key of a request, MD5 if
+    // it has no PASSWORD-ALGORITHM.
+    //
+    // RFC 8489 Section 9.2.4
+    fn password_algorithm(&self, m: &Message) -> Result<PasswordAlgorithm> {
+        let offered = self.auth_handler.password_algorithms();
+        let password_algorithm =
+            if m.contains(ATTR_PASSWORD_ALGORITHM) || m.contains(ATTR_PASSWORD_ALGORITHMS) {
+                // PASSWORD-ALGORITHMS must be the one sent by the server, to detect
+                // bidding down attacks
+                let mut password_algorithms = PasswordAlgorithms::default();
+                password_algorithms.get_from(m)?;
+                if password_algorithms.0 != offered {
+                    return Err(Error::ErrPasswordAlgorithmsMismatch);
+                }
+
+                let mut password_algorithm = PasswordAlgorithm::default();
+                password_algorithm.get_from(m)?;
+                password_algorithm
+            } else {
+                PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5)
+            };
+
+        if offered.contains(&password_algorithm) {
+            Ok(password_algorithm)
+        } else {
+            Err(Error::ErrUnsupportedPasswordAlgorithm)
+        }
+    }
+
+    fn auth_failed(&self, username: &Username, realm: &Realm) {
+        if let Some(event_handler) = self.allocation_manager.event_handler() {
+            event_handler.on_auth_failed(self.src_addr, &username.text, &realm.text);
+        }
+    }
+
     async fn respond_with_nonce(
         &mut self,
         m: &Message,
         calling_method: Method,
         response_code: ErrorCode,
     ) -> Result<()> {
-        let nonce = build_nonce()?;
+        // the security features of RFC 8489 are advertised in the nonce
+        let password_algorithms = self.auth_handler.password_algorithms();
+        let mut security_features = 0;
+        if password_algorithms != [PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5)] {
+            security_features |= SECURITY_FEATURE_PASSWORD_ALGORITHMS;
+        }
+        if self.auth_handler.supports_user_hash() {
+            security_features |= SECURITY_FEATURE_USERNAME_ANONYMITY;
+        }
+        let nonce = if security_features != 0 {
+            Nonce::new_nonce_with_security_features(security_features, &build_nonce()?).text
+        } else {
+            build_nonce()?
+        };
 
         {
             // Nonce has already been taken
@@ -242,18 +346,25 @@ impl Request {
             nonces.insert(nonce.clone(), Instant::now());
         }
 
-        let msg = build_msg(
-            m.transaction_id,
-            MessageType::new(calling_method, CLASS_ERROR_RESPONSE),
-            vec![
+        // the setters are dropped before sending, they are not Send
+        let msg = {
+            let mut attrs: Vec<Box<dyn Setter>> = vec![
                 Box::new(ErrorCodeAttribute {
                     code: response_code,
                     reason: vec![],
                 }),
                 Box::new(Nonce::new(ATTR_NONCE, nonce)),
                 Box::new(Realm::new(ATTR_REALM, self.realm.clone())),
-            ],
-        )?;
+            ];
+            if security_features & SECURITY_FEATURE_PASSWORD_ALGORITHMS != 0 {
+                attrs.push(Box::new(PasswordAlgorithms(password_algorithms)));
+            }
+            build_msg(
+                m.transaction_id,
+                MessageType::new(calling_method, CLASS_ERROR_RESPONSE),
+                attrs,
+            )?
+        };
 
         build_and_send(&self.conn, self.src_addr, msg).await
     }
@@ -295,7 +406,7 @@ impl Request {
         let five_tuple = FiveTuple {
             src_addr: self.src_addr,
             dst_addr: self.conn.local_addr().await?,
-            protocol: PROTO_UDP,
+            protocol: self.protocol,
         };
         let mut requested_port = 0;
         let mut reservation_token = "".to_owned();
@@ -332,6 +443,11 @@ impl Request {
         //    Request) error.  Otherwise, if the attribute is included but
         //    specifies a protocol other that UDP, the server rejects the
         //    request with a 442 (Unsupported Transport Protocol) error.
+        //
+        //    TCP is allowed as well (https://tools.ietf.org/html/rfc6062#section-5.1),
+        //    if the client connection transport is not TCP or TLS, or the request
+        //    contains a DONT-FRAGMENT, EVEN-PORT or RESERVATION-TOKEN attribute,
+        //    the server rejects the request with a 400 (Bad Request) error.
         let mut requested_transport = RequestedTransport::default();
         if let Err(err) = requested_transport.get_from(m) {
             let bad_request_msg = build_msg(
@@ -344,6 +460,28 @@ impl Request {
             )?;
             return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
                 .await;
+        } else if requested_transport.protocol == PROTO_TCP {
+            let err = if self.protocol != PROTO_TCP {
+                Some(Error::ErrTcpRelayOverUdp)
+            } else if m.contains(ATTR_DONT_FRAGMENT)
+                || m.contains(ATTR_EVEN_PORT)
+                || m.contains(ATTR_RESERVATION_TOKEN)
+            {
+                Some(Error::ErrTcpAllocationUnsupportedAttribute)
+            } else {
+                None
+            };
+            if let Some(err) = err {
+                let bad_request_msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_BAD_REQUEST,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await;
+            }
         } else if requested_transport.protocol != PROTO_UDP {
             let msg = build_msg(
                 m.transaction_id,
@@ -362,6 +500,70 @@ impl Request {
             .await;
         }
 
+        //    The server then checks the REQUESTED-ADDRESS-FAMILY and
+        //    ADDITIONAL-ADDRESS-FAMILY attributes
+        //    (https://tools.ietf.org/html/rfc8656#section-7.2).  If the request
+        //    contains both, if either is malformed, or if ADDITIONAL-ADDRESS-FAMILY
+        //    doesn't specify IPv6, the server rejects the request with a 400 (Bad
+        //    Request) error.  If the server doesn't support the requested family,
+        //    which is IPv4 without REQUESTED-ADDRESS-FAMILY, it rejects the request
+        //    with a 440 (Address Family not Supported) error.  If the IPv6 relayed
+        //    address requested with ADDITIONAL-ADDRESS-FAMILY can't be allocated, the
+        //    allocation only gets an IPv4 one and the success response contains an
+        //    ADDRESS-ERROR-CODE attribute.
+        let mut requested_family = REQUESTED_FAMILY_IPV4;
+        let mut additional_ipv6 = false;
+        let family_err = if m.contains(ATTR_REQUESTED_ADDRESS_FAMILY)
+            && m.contains(ATTR_ADDITIONAL_ADDRESS_FAMILY)
+        {
+            Some(Error::ErrRequestedAndAdditionalAddressFamily)
+        } else if m.contains(ATTR_ADDITIONAL_ADDRESS_FAMILY) {
+            let mut additional_family = AdditionalAddressFamily::default();
+            match additional_family.get_from(m) {
+                Ok(_) if additional_family.0 == REQUESTED_FAMILY_IPV6 => {
+                    additional_ipv6 = true;
+                    None
+                }
+                Ok(_) => Some(Error::ErrAdditionalAddressFamilyNotIpv6),
+                Err(err) => Some(err.into()),
+            }
+        } else if m.contains(ATTR_REQUESTED_ADDRESS_FAMILY) {
+            match requested_family.get_from(m) {
+                Ok(_) => None,
+                Err(err) => Some(err.into()),
+            }
+        } else {
+            None
+        };
+        if let Some(err) = family_err {
+            let bad_request_msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_BAD_REQUEST,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await;
+        }
+        if !self.allocation_manager.supports_family(requested_family) {
+            let msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_ADDR_FAMILY_NOT_SUPPORTED,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                msg,
+                Error::ErrAddressFamilyNotSupported,
+            )
+            .await;
+        }
+
         // 4. The request may contain a DONT-FRAGMENT attribute.  If it does,
         //    but the server does not support sending UDP datagrams with the DF
         //    bit set to 1 (see Section 12), then the server treats the DONT-
@@ -429,7 +631,11 @@ impl Request {
             let mut random_port = 1;
 
             while random_port % 2 != 0 {
-                random_port = match self.allocation_manager.get_random_even_port().await {
+                random_port = match self
+                    .allocation_manager
+                    .get_random_even_port(requested_family)
+                    .await
+                {
                     Ok(port) => port,
                     Err(err) => {
                         let insufficent_capacity_msg = build_msg(
@@ -461,47 +667,67 @@ impl Request {
         //    server is free to define this allocation quota any way it wishes,
         //    but SHOULD define it based on the username used to authenticate
         //    the request, and not on the client's transport address.
+        //
+        //    The quota is enforced by the policy of the allocation manager,
+        //    which also rejects allocations denied by the policy handler with
+        //    a 403 (Forbidden) error.
 
         // 8. Also at any point, the server MAY choose to reject the request
         //    with a 300 (Try Alternate) error if it wishes to redirect the
         //    client to a different server.  The use of this error code and
         //    attribute follow the specification in [RFC5389].
         let lifetime_duration = allocation_lifetime(m);
-        let a = match self
-            .allocation_manager
-            .create_allocation(
-                five_tuple,
-                Arc::clone(&self.conn),
-                requested_port,
-                lifetime_duration,
-                username,
-            )
-            .await
-        {
+        let result = if requested_transport.protocol == PROTO_TCP {
+            self.allocation_manager
+                .create_tcp_allocation(
+                    five_tuple,
+                    Arc::clone(&self.conn),
+                    lifetime_duration,
+                    username,
+                    requested_family,
+                )
+                .await
+        } else {
+            self.allocation_manager
+                .create_allocation_with_family(
+                    five_tuple,
+                    Arc::clone(&self.conn),
+                    requested_port,
+                    lifetime_duration,
+                    username,
+                    requested_family,
+                    additional_ipv6,
+                )
+                .await
+        };
+        let a = match result {
             Ok(a) => a,
             Err(err) => {
-                let insufficent_capacity_msg = build_msg(
+                let code = match err {
+                    Error::ErrAllocationQuotaReached => CODE_ALLOC_QUOTA_REACHED,
+                    Error::ErrAllocationDenied => CODE_FORBIDDEN,
+                    Error::ErrAddressFamilyNotSupported => CODE_ADDR_FAMILY_NOT_SUPPORTED,
+                    _ => CODE_INSUFFICIENT_CAPACITY,
+                };
+                let msg = build_msg(
                     m.transaction_id,
                     MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
                     vec![Box::new(ErrorCodeAttribute {
-                        code: CODE_INSUFFICIENT_CAPACITY,
+                        code,
                         reason: vec![],
                     })],
                 )?;
-                return build_and_send_err(
-                    &self.conn,
-                    self.src_addr,
-                    insufficent_capacity_msg,
-                    err,
-                )
-                .await;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
             }
         };
 
         // Once the allocation is created, the server replies with a success
         // response.  The success response contains:
         //   * An XOR-RELAYED-ADDRESS attribute containing the relayed transport
-        //     address.
+        //     address, and a second one with the IPv6 relayed transport address
+        //     of a dual-stack allocation.
+        //   * An ADDRESS-ERROR-CODE attribute if the IPv6 relayed transport
+        //     address requested with ADDITIONAL-ADDRESS-FAMILY was not allocated.
         //   * A LIFETIME attribute containing the current value of the time-to-
         //     expiry timer.
         //   * A RESERVATION-TOKEN attribute (if a second relayed transport
@@ -532,6 +758,28 @@ impl Request {
                 }),
             ];
 
+            if let Some((_, addr)) = &a.additional_relay {
+                response_attrs.push(Box::new(RelayedAddress {
+                    ip: addr.ip(),
+                    port: addr.port(),
+                }));
+            } else if additional_ipv6 {
+                let code = if requested_transport.protocol == PROTO_UDP
+                    && self
+                        .allocation_manager
+                        .supports_family(REQUESTED_FAMILY_IPV6)
+                {
+                    CODE_INSUFFICIENT_CAPACITY
+                } else {
+                    CODE_ADDR_FAMILY_NOT_SUPPORTED
+                };
+                response_attrs.push(Box::new(AddressErrorCode {
+                    family: REQUESTED_FAMILY_IPV6,
+                    code,
+                    reason: vec![],
+                }));
+            }
+
             if !reservation_token.is_empty() {
                 response_attrs.push(Box::new(ReservationToken(
                     reservation_token.as_bytes().to_vec(),
@@ -564,9 +812,35 @@ impl Request {
         let five_tuple = FiveTuple {
             src_addr: self.src_addr,
             dst_addr: self.conn.local_addr().await?,
-            protocol: PROTO_UDP,
+            protocol: self.protocol,
         };
 
+        // A REQUESTED-ADDRESS-FAMILY which doesn't match the family of the
+        // allocation is rejected with a 443 (Peer Address Family Mismatch) error.
+        // https://tools.ietf.org/html/rfc8656#section-7.3
+        let mut requested_family = RequestedAddressFamily::default();
+        if requested_family.get_from(m).is_ok() {
+            if let Some(a) = self.allocation_manager.get_allocation(&five_tuple).await {
+                if !a.has_family(requested_family) {
+                    let msg = build_msg(
+                        m.transaction_id,
+                        MessageType::new(METHOD_REFRESH, CLASS_ERROR_RESPONSE),
+                        vec![Box::new(ErrorCodeAttribute {
+                            code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                            reason: vec![],
+                        })],
+                    )?;
+                    return build_and_send_err(
+                        &self.conn,
+                        self.src_addr,
+                        msg,
+                        Error::ErrPeerAddressFamilyMismatch,
+                    )
+                    .await;
+                }
+            }
+        }
+
         if lifetime_duration != Duration::from_secs(0) {
             let a = self.allocation_manager.get_allocation(&five_tuple).await;
             if let Some(a) = a {
@@ -598,7 +872,7 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr().await?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
@@ -612,7 +886,7 @@ impl Request {
                 log::debug!("no MessageIntegrity");
                 return Ok(());
             };
-            let mut add_count = 0;
+            let mut peers = vec![];
 
             {
                 for attr in &m.attributes.0 {
@@ -622,26 +896,50 @@ impl Request {
 
                     let mut peer_address = PeerAddress::default();
                     if peer_address.get_from(m).is_err() {
-                        add_count = 0;
+                        peers.clear();
                         break;
                     }
 
-                    log::debug!(
-                        "adding permission for {}",
-                        format!("{}:{}", peer_address.ip, peer_address.port)
-                    );
+                    peers.push(SocketAddr::new(peer_address.ip, peer_address.port));
+                }
+            }
 
-                    a.add_permission(Permission::new(SocketAddr::new(
-                        peer_address.ip,
-                        peer_address.port,
-                    )))
-                    .await;
-                    add_count += 1;
+            // the permissions are only installed if the allocation has a relayed
+            // address of the family of all the peers, and the policy allows them
+            for peer in &peers {
+                let result = if a.has_peer_family(peer) {
+                    self.allocation_manager
+                        .policy()
+                        .check_peer(a.username(), *peer)
+                } else {
+                    Err(Error::ErrPeerAddressFamilyMismatch)
+                };
+                if let Err(err) = result {
+                    let code = if err == Error::ErrPeerAddressFamilyMismatch {
+                        CODE_PEER_ADDR_FAMILY_MISMATCH
+                    } else {
+                        CODE_FORBIDDEN
+                    };
+                    let msg = build_msg(
+                        m.transaction_id,
+                        MessageType::new(METHOD_CREATE_PERMISSION, CLASS_ERROR_RESPONSE),
+                        vec![Box::new(ErrorCodeAttribute {
+                            code,
+                            reason: vec![],
+                        })],
+                    )?;
+                    return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
                 }
             }
 
+            for peer in &peers {
+                log::debug!("adding permission for {}", peer);
+
+                a.add_permission(Permission::new(*peer)).await;
+            }
+
             let mut resp_class = CLASS_SUCCESS_RESPONSE;
-            if add_count == 0 {
+            if peers.is_empty() {
                 resp_class = CLASS_ERROR_RESPONSE;
             }
 
@@ -665,7 +963,7 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr().await?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
@@ -683,10 +981,19 @@ impl Request {
                 return Err(Error::ErrNoPermission);
             }
 
-            let l = a.relay_socket.send_to(&data_attr.0, msg_dst).await?;
+            if let Some(rate_limiter) = &a.rate_limiter {
+                if !rate_limiter.allow(data_attr.0.len()) {
+                    log::trace!("dropping data for {}, rate limit exceeded", msg_dst);
+                    return Ok(());
+                }
+            }
+
+            let relay_socket = a.relay_socket_for(&msg_dst)?;
+            let l = relay_socket.send_to(&data_attr.0, msg_dst).await?;
             if l != data_attr.0.len() {
                 Err(Error::ErrShortWrite)
             } else {
+                a.counters.add_to_peers(l);
                 Ok(())
             }
         } else {
@@ -702,7 +1009,7 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr().await?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
@@ -723,6 +1030,18 @@ impl Request {
                     log::debug!("no MessageIntegrity");
                     return Ok(());
                 };
+            // channels can't be bound to TCP allocations
+            // https://tools.ietf.org/html/rfc6062#section-5.2
+            if a.relay_socket.is_none() {
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    bad_request_msg,
+                    Error::ErrNotUdpAllocation,
+                )
+                .await;
+            }
+
             let mut channel = ChannelNumber::default();
             if let Err(err) = channel.get_from(m) {
                 return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
@@ -735,6 +1054,31 @@ impl Request {
                     .await;
             }
 
+            let peer = SocketAddr::new(peer_addr.ip, peer_addr.port);
+            let result = if a.has_peer_family(&peer) {
+                self.allocation_manager
+                    .policy()
+                    .check_peer(a.username(), peer)
+            } else {
+                Err(Error::ErrPeerAddressFamilyMismatch)
+            };
+            if let Err(err) = result {
+                let code = if err == Error::ErrPeerAddressFamilyMismatch {
+                    CODE_PEER_ADDR_FAMILY_MISMATCH
+                } else {
+                    CODE_FORBIDDEN
+                };
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CHANNEL_BIND, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
+            }
+
             log::debug!(
                 "binding channel {} to {}",
                 channel,
@@ -742,11 +1086,8 @@ impl Request {
             );
 
             let result = {
-                a.add_channel_bind(
-                    ChannelBind::new(channel, SocketAddr::new(peer_addr.ip, peer_addr.port)),
-                    self.channel_bind_timeout,
-                )
-                .await
+                a.add_channel_bind(ChannelBind::new(channel, peer), self.channel_bind_timeout)
+                    .await
             };
             if let Err(err) = result {
                 return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await;
@@ -763,6 +1104,239 @@ impl Request {
         }
     }
 
+    // https://tools.ietf.org/html/rfc6062#section-5.2
+    pub(crate) async fn handle_connect_request(&mut self, m: &Message) -> Result<()> {
+        log::debug!("received ConnectRequest from {}", self.src_addr);
+
+        let five_tuple = FiveTuple {
+            src_addr: self.src_addr,
+            dst_addr: self.conn.local_addr().await?,
+            protocol: self.protocol,
+        };
+        let a = self.allocation_manager.get_allocation(&five_tuple).await;
+
+        if let Some(a) = a {
+            let bad_request_msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_BAD_REQUEST,
+                    reason: vec![],
+                })],
+            )?;
+
+            let (_, message_integrity) =
+                if let Some(mi) = self.authenticate_request(m, METHOD_CONNECT).await? {
+                    mi
+                } else {
+                    log::debug!("no MessageIntegrity");
+                    return Ok(());
+                };
+
+            let tcp_relay = if let Some(tcp_relay) = &a.tcp_relay {
+                Arc::clone(tcp_relay)
+            } else {
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    bad_request_msg,
+                    Error::ErrNotTcpAllocation,
+                )
+                .await;
+            };
+
+            let mut peer_addr = PeerAddress::default();
+            if let Err(err) = peer_addr.get_from(m) {
+                return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
+                    .await;
+            }
+            let peer = SocketAddr::new(peer_addr.ip, peer_addr.port);
+
+            let result = if !a.has_peer_family(&peer) {
+                Err(Error::ErrPeerAddressFamilyMismatch)
+            } else if a.has_permission(&peer).await {
+                self.allocation_manager
+                    .policy()
+                    .check_peer(a.username(), peer)
+            } else {
+                Err(Error::ErrNoPermission)
+            };
+            if let Err(err) = result {
+                let code = if err == Error::ErrPeerAddressFamilyMismatch {
+                    CODE_PEER_ADDR_FAMILY_MISMATCH
+                } else {
+                    CODE_FORBIDDEN
+                };
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
+            }
+
+            if !tcp_relay.start_connect(m.transaction_id).await {
+                log::debug!(
+                    "ignoring retransmitted ConnectRequest from {}",
+                    self.src_addr
+                );
+                return Ok(());
+            }
+
+            if !tcp_relay.add_peer(peer).await {
+                tcp_relay.end_connect(&m.transaction_id).await;
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_CONN_ALREADY_EXISTS,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrConnectionAlreadyExists,
+                )
+                .await;
+            }
+
+            log::debug!("connecting to {}", peer);
+
+            // connecting may take a while, the response is sent from its own task
+            // to keep handling the requests of the client meanwhile
+            let conn = Arc::clone(&self.conn);
+            let src_addr = self.src_addr;
+            let transaction_id = m.transaction_id;
+            tokio::spawn(async move {
+                let msg = match timeout(
+                    CONNECT_TIMEOUT,
+                    connect_tcp_relay(tcp_relay.listener_addr, peer),
+                )
+                .await
+                {
+                    Ok(Ok(stream)) => {
+                        let id = tcp_relay
+                            .add_peer_connection(stream, peer, five_tuple)
+                            .await;
+                        build_msg(
+                            transaction_id,
+                            MessageType::new(METHOD_CONNECT, CLASS_SUCCESS_RESPONSE),
+                            vec![Box::new(id), Box::new(message_integrity)],
+                        )
+                    }
+                    result => {
+                        match result {
+                            Ok(Err(err)) => log::debug!("failed to connect to {}: {}", peer, err),
+                            _ => log::debug!("timed out connecting to {}", peer),
+                        }
+                        tcp_relay.remove_peer(&peer).await;
+                        build_msg(
+                            transaction_id,
+                            MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                            vec![Box::new(ErrorCodeAttribute {
+                                code: CODE_CONN_TIMEOUT_OR_FAILURE,
+                                reason: vec![],
+                            })],
+                        )
+                    }
+                };
+
+                tcp_relay.end_connect(&transaction_id).await;
+
+                let result = match msg {
+                    Ok(msg) => build_and_send(&conn, src_addr, msg).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    log::error!("Failed to send Connect response to {}: {}", src_addr, err);
+                }
+            });
+
+            Ok(())
+        } else {
+            Err(Error::ErrNoAllocationFound)
+        }
+    }
+
+    // https://tools.ietf.org/html/rfc6062#section-5.4
+    pub(crate) async fn handle_connection_bind_request(&mut self, m: &Message) -> Result<()> {
+        log::debug!("received ConnectionBindRequest from {}", self.src_addr);
+
+        let bad_request_msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_CONNECTION_BIND, CLASS_ERROR_RESPONSE),
+            vec![Box::new(ErrorCodeAttribute {
+                code: CODE_BAD_REQUEST,
+                reason: vec![],
+            })],
+        )?;
+
+        if self.protocol != PROTO_TCP {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrTcpRelayOverUdp,
+            )
+            .await;
+        }
+
+        let (username, message_integrity) =
+            if let Some(mi) = self.authenticate_request(m, METHOD_CONNECTION_BIND).await? {
+                mi
+            } else {
+                log::debug!("no MessageIntegrity");
+                return Ok(());
+            };
+
+        let mut id = ConnectionId::default();
+        if let Err(err) = id.get_from(m) {
+            return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
+                .await;
+        }
+
+        let (peer_conn, a) = if let Some(v) = self
+            .allocation_manager
+            .take_peer_connection(id, &username.text)
+            .await
+        {
+            v
+        } else {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrNoSuchConnection,
+            )
+            .await;
+        };
+        let tcp_relay = a.tcp_relay.as_ref().ok_or(Error::ErrNotTcpAllocation)?;
+
+        let msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_CONNECTION_BIND, CLASS_SUCCESS_RESPONSE),
+            vec![Box::new(message_integrity)],
+        )?;
+        build_and_send(&self.conn, self.src_addr, msg).await?;
+
+        // from now on the connection carries the data of the peer
+        log::debug!("connection {} bound to {}", id, peer_conn.peer_addr);
+        tcp_relay.relay_data_connection(
+            Arc::clone(&self.conn),
+            peer_conn,
+            a.rate_limiter.clone(),
+            Arc::clone(&a.counters),
+        );
+        self.is_data_connection = true;
+
+        Ok(())
+    }
+
     pub(crate) async fn handle_channel_data(&mut self, c: &ChannelData) -> Result<()> {
         log::debug!("received ChannelData from {}", self.src_addr);
 
@@ -771,17 +1345,26 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr().await?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
         if let Some(a) = a {
             let channel = a.get_channel_addr(&c.number).await;
             if let Some(peer) = channel {
-                let l = a.relay_socket.send_to(&c.data, peer).await?;
+                if let Some(rate_limiter) = &a.rate_limiter {
+                    if !rate_limiter.allow(c.data.len()) {
+                        log::trace!("dropping data for {}, rate limit exceeded", peer);
+                        return Ok(());
+                    }
+                }
+
+                let relay_socket = a.relay_socket_for(&peer)?;
+                let l = relay_socket.send_to(&c.data, peer).await?;
                 if l != c.data.len() {
                     Err(Error::ErrShortWrite)
                 } else {
+                    a.counters.add_to_peers(l);
                     Ok(())
                 }
             } else {
@@ -1,5 +1,6 @@
 use super::*;
-use crate::relay::relay_none::*;
+use crate::policy::Policy;
+use crate::relay::{relay_dual_stack::*, relay_none::*};
 
 use std::{net::IpAddr, str::FromStr};
 use tokio::{
@@ -69,6 +70,8 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        policy: Arc::new(Policy::default()),
+        event_handler: None,
     }));
 
     let socket = SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5000);
@@ -117,3 +120,311 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
 
     Ok(())
 }
+
+// allocate sends an Allocate request with attrs to a new Request of allocation_manager, and
+// returns the response
+async fn allocate(
+    allocation_manager: &Arc<Manager>,
+    attrs: Vec<Box<dyn Setter>>,
+) -> Result<Message> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let mut r = Request::new(
+        conn,
+        client.local_addr()?,
+        Arc::clone(allocation_manager),
+        Arc::new(TestAuthHandler {}),
+    );
+    {
+        let mut nonces = r.nonces.lock().await;
+        nonces.insert(STATIC_KEY.to_owned(), Instant::now());
+    }
+
+    let mut setters: Vec<Box<dyn Setter>> = vec![
+        Box::new(TransactionId::new()),
+        Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+        Box::new(RequestedTransport {
+            protocol: PROTO_UDP,
+        }),
+    ];
+    setters.extend(attrs);
+    setters.push(Box::new(Username::new(ATTR_USERNAME, "user".to_owned())));
+    setters.push(Box::new(Realm::new(ATTR_REALM, STATIC_KEY.to_owned())));
+    setters.push(Box::new(Nonce::new(ATTR_NONCE, STATIC_KEY.to_owned())));
+    setters.push(Box::new(MessageIntegrity(STATIC_KEY.as_bytes().to_vec())));
+    let mut m = Message::new();
+    m.build(&setters)?;
+
+    // error responses are returned as errors after they are sent
+    let _ = r.handle_allocate_request(&m).await;
+
+    let mut buf = vec![0u8; 1500];
+    let n = client.recv(&mut buf).await?;
+    let mut res = Message::new();
+    res.raw = buf[..n].to_vec();
+    res.decode()?;
+    Ok(res)
+}
+
+fn error_code(m: &Message) -> Option<ErrorCode> {
+    let mut code = ErrorCodeAttribute::default();
+    code.get_from(m).ok().map(|_| code.code)
+}
+
+fn relayed_addrs(m: &Message) -> Vec<SocketAddr> {
+    m.attributes
+        .0
+        .iter()
+        .filter(|attr| attr.typ == ATTR_XOR_RELAYED_ADDRESS)
+        .map(|attr| {
+            // decode every XOR-RELAYED-ADDRESS, not only the first one
+            let mut single = Message::new();
+            single.transaction_id = m.transaction_id;
+            single.add(ATTR_XOR_RELAYED_ADDRESS, &attr.value);
+            let mut addr = RelayedAddress::default();
+            addr.get_from(&single).unwrap();
+            SocketAddr::new(addr.ip, addr.port)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_allocation_address_families() -> Result<()> {
+    let dual_stack_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorDualStack {
+            ipv4: Box::new(RelayAddressGeneratorNone {
+                address: "127.0.0.1".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+            ipv6: Box::new(RelayAddressGeneratorNone {
+                address: "[::1]".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        }),
+        policy: Arc::new(Policy::default()),
+        event_handler: None,
+    }));
+    let ipv4_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "127.0.0.1".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        policy: Arc::new(Policy::default()),
+        event_handler: None,
+    }));
+
+    // IPv4 by default
+    let res = allocate(&dual_stack_manager, vec![]).await?;
+    assert_eq!(res.typ.class, CLASS_SUCCESS_RESPONSE);
+    let addrs = relayed_addrs(&res);
+    assert!(addrs.len() == 1 && addrs[0].is_ipv4(), "{:?}", addrs);
+
+    // requested IPv6
+    let res = allocate(&dual_stack_manager, vec![Box::new(REQUESTED_FAMILY_IPV6)]).await?;
+    assert_eq!(res.typ.class, CLASS_SUCCESS_RESPONSE);
+    let addrs = relayed_addrs(&res);
+    assert!(addrs.len() == 1 && addrs[0].is_ipv6(), "{:?}", addrs);
+
+    // IPv4 and IPv6
+    let res = allocate(
+        &dual_stack_manager,
+        vec![Box::new(AdditionalAddressFamily(REQUESTED_FAMILY_IPV6))],
+    )
+    .await?;
+    assert_eq!(res.typ.class, CLASS_SUCCESS_RESPONSE);
+    let addrs = relayed_addrs(&res);
+    assert!(
+        addrs.len() == 2 && addrs[0].is_ipv4() && addrs[1].is_ipv6(),
+        "{:?}",
+        addrs
+    );
+    assert!(!res.contains(ATTR_ADDRESS_ERROR_CODE));
+
+    // both attributes, or an additional family other than IPv6
+    let res = allocate(
+        &dual_stack_manager,
+        vec![
+            Box::new(REQUESTED_FAMILY_IPV4),
+            Box::new(AdditionalAddressFamily(REQUESTED_FAMILY_IPV6)),
+        ],
+    )
+    .await?;
+    assert_eq!(error_code(&res), Some(CODE_BAD_REQUEST));
+    let res = allocate(
+        &dual_stack_manager,
+        vec![Box::new(AdditionalAddressFamily(REQUESTED_FAMILY_IPV4))],
+    )
+    .await?;
+    assert_eq!(error_code(&res), Some(CODE_BAD_REQUEST));
+
+    // IPv6 is not supported
+    let res = allocate(&ipv4_manager, vec![Box::new(REQUESTED_FAMILY_IPV6)]).await?;
+    assert_eq!(error_code(&res), Some(CODE_ADDR_FAMILY_NOT_SUPPORTED));
+    let res = allocate(
+        &ipv4_manager,
+        vec![Box::new(AdditionalAddressFamily(REQUESTED_FAMILY_IPV6))],
+    )
+    .await?;
+    assert_eq!(res.typ.class, CLASS_SUCCESS_RESPONSE);
+    let addrs = relayed_addrs(&res);
+    assert!(addrs.len() == 1 && addrs[0].is_ipv4(), "{:?}", addrs);
+    let mut address_error_code = AddressErrorCode::default();
+    address_error_code.get_from(&res)?;
+    assert_eq!(address_error_code.family, REQUESTED_FAMILY_IPV6);
+    assert_eq!(address_error_code.code, CODE_ADDR_FAMILY_NOT_SUPPORTED);
+
+    Ok(())
+}
+
+struct Sha256AuthHandler;
+impl AuthHandler for Sha256AuthHandler {
+    fn auth_handle(&self, _username: &str, _realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+        Err(Error::ErrUnsupportedPasswordAlgorithm)
+    }
+
+    fn password_algorithms(&self) -> Vec<PasswordAlgorithm> {
+        vec![PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)]
+    }
+
+    fn auth_handle_with_algorithm(
+        &self,
+        _username: &str,
+        _realm: &str,
+        _src_addr: SocketAddr,
+        algorithm: &PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        assert_eq!(algorithm.algorithm, PASSWORD_ALGORITHM_SHA256);
+        Ok(STATIC_KEY.as_bytes().to_vec())
+    }
+
+    fn supports_user_hash(&self) -> bool {
+        true
+    }
+
+    fn username_from_user_hash(
+        &self,
+        user_hash: &[u8],
+        realm: &str,
+        _src_addr: SocketAddr,
+    ) -> Result<String> {
+        if user_hash == UserHash::new("user", realm).0 {
+            Ok("user".to_owned())
+        } else {
+            Err(Error::ErrNoSuchUser)
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_authenticate_request_password_algorithms() -> Result<()> {
+    let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "127.0.0.1".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        policy: Arc::new(Policy::default()),
+        event_handler: None,
+    }));
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let mut r = Request::new(
+        conn,
+        client.local_addr()?,
+        allocation_manager,
+        Arc::new(Sha256AuthHandler {}),
+    );
+    {
+        let mut nonces = r.nonces.lock().await;
+        nonces.insert(STATIC_KEY.to_owned(), Instant::now());
+    }
+
+    let sha256 = PasswordAlgorithms(vec![PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)]);
+    let build = |attrs: Vec<Box<dyn Setter>>| -> Result<Message> {
+        let mut setters: Vec<Box<dyn Setter>> = vec![
+            Box::new(TransactionId::new()),
+            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+            Box::new(Realm::new(ATTR_REALM, STATIC_KEY.to_owned())),
+            Box::new(Nonce::new(ATTR_NONCE, STATIC_KEY.to_owned())),
+        ];
+        setters.extend(attrs);
+        let mut m = Message::new();
+        m.build(&setters)?;
+        Ok(m)
+    };
+    let mut buf = vec![0u8; 1500];
+
+    // the 401 advertises the security features and the algorithms
+    let m = build(vec![])?;
+    assert!(r.authenticate_request(&m, METHOD_ALLOCATE).await?.is_none());
+    let n = client.recv(&mut buf).await?;
+    let mut res = Message::new();
+    res.raw = buf[..n].to_vec();
+    res.decode()?;
+    assert_eq!(error_code(&res), Some(CODE_UNAUTHORIZED));
+    assert_eq!(
+        Nonce::get_from_as(&res, ATTR_NONCE)?.security_features(),
+        SECURITY_FEATURE_PASSWORD_ALGORITHMS | SECURITY_FEATURE_USERNAME_ANONYMITY
+    );
+    let mut password_algorithms = PasswordAlgorithms::default();
+    password_algorithms.get_from(&res)?;
+    assert_eq!(password_algorithms, sha256);
+
+    for (name, attrs) in [
+        (
+            "MD5 is not offered",
+            vec![
+                Box::new(Username::new(ATTR_USERNAME, "user".to_owned())) as Box<dyn Setter>,
+                Box::new(MessageIntegrity(STATIC_KEY.as_bytes().to_vec())),
+            ],
+        ),
+        (
+            "PASSWORD-ALGORITHMS is not the offered one",
+            vec![
+                Box::new(Username::new(ATTR_USERNAME, "user".to_owned())) as Box<dyn Setter>,
+                Box::new(PasswordAlgorithms(vec![
+                    PasswordAlgorithm::new(PASSWORD_ALGORITHM_MD5),
+                    PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256),
+                ])),
+                Box::new(PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)),
+                Box::new(MessageIntegritySha256(STATIC_KEY.as_bytes().to_vec())),
+            ],
+        ),
+        (
+            "unknown USERHASH",
+            vec![
+                Box::new(UserHash::new("nobody", STATIC_KEY)) as Box<dyn Setter>,
+                Box::new(sha256.clone()),
+                Box::new(PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)),
+                Box::new(MessageIntegritySha256(STATIC_KEY.as_bytes().to_vec())),
+            ],
+        ),
+    ] {
+        let m = build(attrs)?;
+        assert!(
+            r.authenticate_request(&m, METHOD_ALLOCATE).await.is_err(),
+            "{}",
+            name
+        );
+        let n = client.recv(&mut buf).await?;
+        let mut res = Message::new();
+        res.raw = buf[..n].to_vec();
+        res.decode()?;
+        assert_eq!(error_code(&res), Some(CODE_BAD_REQUEST), "{}", name);
+    }
+
+    let m = build(vec![
+        Box::new(UserHash::new("user", STATIC_KEY)),
+        Box::new(sha256.clone()),
+        Box::new(PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)),
+        Box::new(MessageIntegritySha256(STATIC_KEY.as_bytes().to_vec())),
+    ])?;
+    let (username, integrity) = r
+        .authenticate_request(&m, METHOD_ALLOCATE)
+        .await?
+        .expect("should authenticate");
+    assert_eq!(username.text, "user");
+    assert!(matches!(integrity, Integrity::Sha256(_)));
+
+    Ok(())
+}
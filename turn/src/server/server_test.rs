@@ -1,14 +1,22 @@
 use super::config::*;
 use super::*;
-use crate::auth::generate_auth_key;
+use crate::auth::{generate_auth_key, generate_auth_key_sha256};
 use crate::client::*;
 use crate::error::*;
+use crate::events::EventHandler;
+use crate::policy::PolicyConfig;
+use crate::proto::reqfamily::REQUESTED_FAMILY_IPV6;
+use crate::relay::relay_dual_stack::*;
 use crate::relay::relay_static::*;
 
 use crate::relay::relay_none::RelayAddressGeneratorNone;
+use crate::stun_conn::{StunConn, StunListener};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
-use tokio::net::UdpSocket;
+use stun::password_algorithm::*;
+use stun::userhash::UserHash;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::sync::mpsc;
 use util::{vnet::router::Nic, vnet::*};
 
@@ -55,9 +63,12 @@ async fn test_server_simple() -> Result<()> {
                 net: Arc::new(net::Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
         channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig::default(),
+        event_handler: None,
     })
     .await?;
 
@@ -93,6 +104,707 @@ async fn test_server_simple() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_server_tcp() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let server_addr = listener.local_addr()?;
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![],
+        listener_configs: vec![ListenerConfig {
+            listener: Arc::new(StunListener::new(listener)?),
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peers of the test are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let stream = TcpStream::connect(server_addr).await?;
+    let local_addr = stream.local_addr()?;
+    let conn = Arc::new(StunConn::new(stream, local_addr, server_addr));
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: server_addr.to_string(),
+        turn_serv_addr: server_addr.to_string(),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+
+    client.listen().await?;
+
+    let mapped_addr = client.send_binding_request().await?;
+    assert_eq!(mapped_addr, local_addr, "should match");
+
+    let relay_conn = client.allocate().await?;
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    let peer_addr = peer.local_addr()?;
+
+    relay_conn.send_to(b"hello", peer_addr).await?;
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = peer.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+    assert_eq!(from, relay_conn.local_addr().await?, "should match");
+
+    peer.send_to(b"world", from).await?;
+    let (n, from) = relay_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+    assert_eq!(from, peer_addr, "should match");
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_tcp_large_messages() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let server_addr = listener.local_addr()?;
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![],
+        listener_configs: vec![ListenerConfig {
+            listener: Arc::new(StunListener::new(listener)?),
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peers of the test are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let stream = TcpStream::connect(server_addr).await?;
+    let local_addr = stream.local_addr()?;
+    let conn = Arc::new(StunConn::new(stream, local_addr, server_addr));
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: server_addr.to_string(),
+        turn_serv_addr: server_addr.to_string(),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+
+    client.listen().await?;
+
+    let relay_conn = client.allocate().await?;
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    let peer_addr = peer.local_addr()?;
+
+    // the first message is a Send indication and binds a channel, once it is bound the
+    // messages are ChannelData
+    let large = vec![0xAAu8; 8000];
+    let mut buf = vec![0u8; 10000];
+    for _ in 0..3 {
+        relay_conn.send_to(&large, peer_addr).await?;
+        let (n, _) = tokio::time::timeout(Duration::from_secs(5), peer.recv_from(&mut buf))
+            .await
+            .expect("the relay should forward large messages")?;
+        assert_eq!(&buf[..n], &large[..]);
+
+        client.send_binding_request().await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // the allocation outlives messages larger than the MTU
+    let from = relay_conn.local_addr().await?;
+    peer.send_to(b"world", from).await?;
+    let (n, from) = relay_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+    assert_eq!(from, peer_addr, "should match");
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_tcp_relay() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let server_addr = listener.local_addr()?;
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![],
+        listener_configs: vec![ListenerConfig {
+            listener: Arc::new(StunListener::new(listener)?),
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "127.0.0.1".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peers of the test are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let stream = TcpStream::connect(server_addr).await?;
+    let local_addr = stream.local_addr()?;
+    let conn = Arc::new(StunConn::new(stream, local_addr, server_addr));
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: server_addr.to_string(),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    let allocation = client.allocate_tcp().await?;
+    let relayed_addr = allocation.relayed_addr();
+
+    // client initiated connection
+    let peer_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let peer_addr = peer_listener.local_addr()?;
+
+    let id = allocation.connect(peer_addr).await?;
+    let (mut peer, from) = peer_listener.accept().await?;
+    assert_eq!(from, relayed_addr, "should match");
+
+    let stream = TcpStream::connect(server_addr).await?;
+    let data_addr = stream.local_addr()?;
+    let data_conn = StunConn::new(stream, data_addr, server_addr);
+    allocation.connection_bind(&data_conn, id).await?;
+    assert!(data_conn.is_data_connection());
+
+    data_conn.send(b"hello").await?;
+    let mut buf = vec![0u8; 1500];
+    let n = peer.read(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+
+    peer.write_all(b"world").await?;
+    let n = data_conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+
+    // a connection with the same peer can't be opened twice
+    assert!(allocation.connect(peer_addr).await.is_err());
+
+    // peer initiated connection
+    let mut peer = TcpStream::connect(relayed_addr).await?;
+    let (id, from) = allocation.accept().await?;
+    assert_eq!(from, peer.local_addr()?, "should match");
+
+    let stream = TcpStream::connect(server_addr).await?;
+    let data_addr = stream.local_addr()?;
+    let data_conn = StunConn::new(stream, data_addr, server_addr);
+    allocation.connection_bind(&data_conn, id).await?;
+
+    peer.write_all(b"hello").await?;
+    let n = data_conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+
+    data_conn.send(b"world").await?;
+    let n = peer.read(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+
+    // a connection id can only be bound once
+    let stream = TcpStream::connect(server_addr).await?;
+    let data_addr = stream.local_addr()?;
+    let data_conn = StunConn::new(stream, data_addr, server_addr);
+    assert!(allocation.connection_bind(&data_conn, id).await.is_err());
+
+    // deleting the allocation closes its connections
+    allocation.close().await?;
+    let n = peer.read(&mut buf).await?;
+    assert_eq!(n, 0, "peer connection should be closed");
+
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_tcp_relay_over_udp() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig::default(),
+        event_handler: None,
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: format!("127.0.0.1:{}", server_port),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    // TCP allocations must be requested over TCP or TLS
+    assert!(client.allocate_tcp().await.is_err());
+
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_policy() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig {
+            max_allocations_per_user: 1,
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let mut clients = vec![];
+    for _ in 0..2 {
+        let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+        let client = Client::new(ClientConfig {
+            stun_serv_addr: String::new(),
+            turn_serv_addr: format!("127.0.0.1:{}", server_port),
+            username: "user".to_owned(),
+            password: "pass".to_owned(),
+            realm: String::new(),
+            software: String::new(),
+            rto_in_ms: 0,
+            conn,
+            vnet: None,
+        })
+        .await?;
+        client.listen().await?;
+        clients.push(client);
+    }
+
+    let relay_conn = clients[0].allocate().await?;
+
+    // the second allocation of the user exceeds the quota
+    let result = clients[1].allocate().await;
+    assert!(
+        matches!(&result, Err(Error::Other(s)) if s.contains("486")),
+        "should be rejected with 486"
+    );
+
+    // loopback peers are denied by default
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    let result = relay_conn.send_to(b"hello", peer.local_addr()?).await;
+    assert!(
+        matches!(&result, Err(err) if err.to_string().contains("403")),
+        "should be rejected with 403"
+    );
+
+    // the quota is released with the allocation
+    relay_conn.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let relay_conn = clients[1].allocate().await?;
+    relay_conn.close().await?;
+
+    for client in &clients {
+        client.close().await?;
+    }
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_ipv6_relay() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorDualStack {
+                ipv4: Box::new(RelayAddressGeneratorStatic {
+                    relay_address: IpAddr::from_str("127.0.0.1")?,
+                    address: "0.0.0.0".to_owned(),
+                    net: Arc::new(net::Net::new(None)),
+                }),
+                ipv6: Box::new(RelayAddressGeneratorStatic {
+                    relay_address: IpAddr::from_str("::1")?,
+                    address: "[::1]".to_owned(),
+                    net: Arc::new(net::Net::new(None)),
+                }),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peers are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: format!("127.0.0.1:{}", server_port),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    let relay_conn = client.allocate_with_family(REQUESTED_FAMILY_IPV6).await?;
+    assert!(relay_conn.local_addr().await?.is_ipv6());
+
+    let peer = UdpSocket::bind("[::1]:0").await?;
+    relay_conn.send_to(b"hello", peer.local_addr()?).await?;
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = peer.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+    assert_eq!(from, relay_conn.local_addr().await?);
+
+    peer.send_to(b"world", from).await?;
+    let (n, addr) = relay_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+    assert_eq!(addr, peer.local_addr()?);
+
+    // the allocation has no IPv4 relayed address
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    let result = relay_conn.send_to(b"hello", peer.local_addr()?).await;
+    assert!(
+        matches!(&result, Err(err) if err.to_string().contains("443")),
+        "should be rejected with 443"
+    );
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+// Sha256AuthHandler only accepts SHA-256 keys and anonymous usernames
+struct Sha256AuthHandler;
+
+impl AuthHandler for Sha256AuthHandler {
+    fn auth_handle(&self, _username: &str, _realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+        Err(Error::ErrFakeErr)
+    }
+
+    fn password_algorithms(&self) -> Vec<PasswordAlgorithm> {
+        vec![PasswordAlgorithm::new(PASSWORD_ALGORITHM_SHA256)]
+    }
+
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        _src_addr: SocketAddr,
+        algorithm: &PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        if username == "user" && algorithm.algorithm == PASSWORD_ALGORITHM_SHA256 {
+            Ok(generate_auth_key_sha256(username, realm, "pass"))
+        } else {
+            Err(Error::ErrFakeErr)
+        }
+    }
+
+    fn supports_user_hash(&self) -> bool {
+        true
+    }
+
+    fn username_from_user_hash(
+        &self,
+        user_hash: &[u8],
+        realm: &str,
+        _src_addr: SocketAddr,
+    ) -> Result<String> {
+        if user_hash == UserHash::new("user", realm).0 {
+            Ok("user".to_owned())
+        } else {
+            Err(Error::ErrNoSuchUser)
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_server_sha256_user_hash() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(Sha256AuthHandler {}),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peers are on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: None,
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: format!("127.0.0.1:{}", server_port),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    let relay_conn = client.allocate().await?;
+    let infos = server.allocations_info().await?;
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].username, "user");
+
+    // the permission and the data are authenticated with the same credentials
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    relay_conn.send_to(b"hello", peer.local_addr()?).await?;
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = peer.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+
+    peer.send_to(b"world", from).await?;
+    let (n, addr) = relay_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world");
+    assert_eq!(addr, peer.local_addr()?);
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct TestEventHandler {
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+impl EventHandler for TestEventHandler {
+    fn on_allocation_created(&self, info: &AllocationInfo) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("created {}", info.username));
+    }
+
+    fn on_allocation_deleted(&self, info: &AllocationInfo) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!(
+            "deleted {} {} {}",
+            info.username, info.packets_to_peers, info.packets_from_peers
+        ));
+    }
+
+    fn on_permission_created(&self, _five_tuple: &FiveTuple, peer: SocketAddr) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("permission {}", peer.ip()));
+    }
+
+    fn on_auth_failed(&self, _src_addr: SocketAddr, username: &str, _realm: &str) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("auth failed {}", username));
+    }
+}
+
+#[tokio::test]
+async fn test_server_events() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let event_handler = Arc::new(TestEventHandler::default());
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        channel_bind_timeout: Duration::from_secs(0),
+        // the peer is on loopback
+        policy: PolicyConfig {
+            denied_peers: vec![],
+            ..Default::default()
+        },
+        event_handler: Some(Arc::clone(&event_handler) as Arc<dyn EventHandler + Send + Sync>),
+    })
+    .await?;
+
+    let mut clients = vec![];
+    for password in &["pass", "wrong"] {
+        let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+        let client = Client::new(ClientConfig {
+            stun_serv_addr: String::new(),
+            turn_serv_addr: format!("127.0.0.1:{}", server_port),
+            username: "user".to_owned(),
+            password: password.to_string(),
+            realm: String::new(),
+            software: String::new(),
+            rto_in_ms: 0,
+            conn,
+            vnet: None,
+        })
+        .await?;
+        client.listen().await?;
+        clients.push(client);
+    }
+
+    assert!(clients[1].allocate().await.is_err(), "should fail auth");
+
+    let relay_conn = clients[0].allocate().await?;
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    relay_conn.send_to(b"hello", peer.local_addr()?).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = peer.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+    peer.send_to(b"world!", from).await?;
+    let (n, _) = relay_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"world!");
+
+    let infos = server.allocations_info().await?;
+    assert_eq!(infos.len(), 1, "should list the allocation");
+    let info = &infos[0];
+    assert_eq!(info.username, "user");
+    assert_eq!(info.relay_addr, relay_conn.local_addr().await?);
+    assert_eq!(info.five_tuple.protocol, PROTO_UDP);
+    assert_eq!(
+        (info.bytes_to_peers, info.packets_to_peers),
+        (5, 1),
+        "should count the data sent to the peer"
+    );
+    assert_eq!(
+        (info.bytes_from_peers, info.packets_from_peers),
+        (6, 1),
+        "should count the data received from the peer"
+    );
+
+    relay_conn.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.allocations_info().await?.is_empty());
+
+    let events = event_handler.events.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        vec![
+            "auth failed user",
+            "created user",
+            "permission 127.0.0.1",
+            "deleted user 1 1",
+        ]
+    );
+
+    for client in &clients {
+        client.close().await?;
+    }
+    server.close().await?;
+
+    Ok(())
+}
+
 struct VNet {
     wan: Arc<Mutex<router::Router>>,
     net0: Arc<net::Net>,
@@ -189,9 +901,12 @@ async fn build_vnet() -> Result<VNet> {
                 net: Arc::clone(&net0),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
         channel_bind_timeout: Duration::from_secs(0),
+        policy: PolicyConfig::default(),
+        event_handler: None,
     })
     .await?;
 
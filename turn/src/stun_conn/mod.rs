@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod stun_conn_test;
+
+mod stun_listener;
+pub use stun_listener::StunListener;
+
+use crate::proto::chandata::nearest_padded_value_length;
+use crate::proto::channum::ChannelNumber;
+
+use async_trait::async_trait;
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use stun::message::{is_message, MessageType, CLASS_SUCCESS_RESPONSE, METHOD_CONNECTION_BIND};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::Mutex;
+use util::conn::Conn;
+
+const STUN_HEADER_SIZE: usize = 20;
+const CHANNEL_DATA_HEADER_SIZE: usize = 4;
+const READ_CHUNK_SIZE: usize = 1500;
+
+/// MAX_FRAME_SIZE is the size of the largest TURN message on a stream, a STUN message
+/// with a 16 bit length. ChannelData messages are at most 65540 bytes with their padding.
+pub(crate) const MAX_FRAME_SIZE: usize = STUN_HEADER_SIZE + u16::MAX as usize;
+
+// frame_size returns the size of the TURN message at the beginning of buf, or None if not
+// enough of it was received yet to tell.
+//
+// Over TCP and TLS, TURN messages are self-delimiting (RFC 5766 Section 2.1): STUN messages
+// carry their length in the header and ChannelData messages are padded to a multiple of four.
+fn frame_size(buf: &[u8]) -> Result<Option<usize>, Error> {
+    if buf.len() < CHANNEL_DATA_HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    if buf[0] & 0xC0 == 0 {
+        // the two most significant bits of a STUN message are zero
+        Ok(Some(STUN_HEADER_SIZE + length))
+    } else if ChannelNumber(u16::from_be_bytes([buf[0], buf[1]])).valid() {
+        Ok(Some(
+            CHANNEL_DATA_HEADER_SIZE + nearest_padded_value_length(length),
+        ))
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "stream is neither STUN nor ChannelData",
+        ))
+    }
+}
+
+// is_connection_bind_success returns true if buf is a ConnectionBind success response. After
+// it, the connection carries the raw data of a peer (RFC 6062 Section 4.3 and 5.4).
+fn is_connection_bind_success(buf: &[u8]) -> bool {
+    is_message(buf)
+        && u16::from_be_bytes([buf[0], buf[1]])
+            == MessageType::new(METHOD_CONNECTION_BIND, CLASS_SUCCESS_RESPONSE).value()
+}
+
+struct StunConnReader<S> {
+    rd: ReadHalf<S>,
+    buf: Vec<u8>,
+}
+
+impl<S: AsyncRead> StunConnReader<S> {
+    // read_frame reads from the stream until a whole TURN message is buffered and returns it.
+    async fn read_frame(&mut self) -> Result<Vec<u8>, Error> {
+        let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            if let Some(size) = frame_size(&self.buf)? {
+                if self.buf.len() >= size {
+                    let rest = self.buf.split_off(size);
+                    return Ok(std::mem::replace(&mut self.buf, rest));
+                }
+            }
+
+            let n = self.rd.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "Unexpected EOF"));
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    // read_raw reads the stream as is, starting with what is left in the buffer.
+    async fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.buf.is_empty() {
+            let n = self.rd.read(buf).await?;
+            if n == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "Unexpected EOF"));
+            }
+            return Ok(n);
+        }
+
+        let n = std::cmp::min(buf.len(), self.buf.len());
+        buf[..n].copy_from_slice(&self.buf[..n]);
+        self.buf.drain(..n);
+        Ok(n)
+    }
+}
+
+/// StunConn wraps a stream oriented connection, like TCP or TLS, and implements a
+/// packet oriented Conn by splitting the stream into STUN and ChannelData messages.
+/// This allows to run a TURN client or server on top of it.
+///
+/// Once a ConnectionBind success response is sent or received, the connection is a data
+/// connection of a TCP allocation (RFC 6062) and recv returns the stream as is.
+pub struct StunConn<S> {
+    reader: Mutex<StunConnReader<S>>,
+    writer: Mutex<WriteHalf<S>>,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    raw: AtomicBool,
+}
+
+impl<S: AsyncRead + AsyncWrite> StunConn<S> {
+    /// new returns a StunConn for a connected stream with the given addresses.
+    pub fn new(stream: S, local_addr: SocketAddr, remote_addr: SocketAddr) -> Self {
+        let (rd, wr) = tokio::io::split(stream);
+        StunConn {
+            reader: Mutex::new(StunConnReader { rd, buf: vec![] }),
+            writer: Mutex::new(wr),
+            local_addr,
+            remote_addr,
+            raw: AtomicBool::new(false),
+        }
+    }
+
+    /// is_data_connection returns true once the connection carries the raw data of a peer.
+    pub fn is_data_connection(&self) -> bool {
+        self.raw.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + 'static> Conn for StunConn<S> {
+    async fn connect(&self, _addr: SocketAddr) -> util::Result<()> {
+        Err(Error::new(ErrorKind::Unsupported, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> util::Result<usize> {
+        let frame = {
+            let mut reader = self.reader.lock().await;
+            if self.is_data_connection() {
+                return Ok(reader.read_raw(buf).await?);
+            }
+            let frame = reader.read_frame().await?;
+            if is_connection_bind_success(&frame) {
+                self.raw.store(true, Ordering::SeqCst);
+            }
+            frame
+        };
+        if buf.len() < frame.len() {
+            return Err(util::Error::ErrBufferShort);
+        }
+        buf[..frame.len()].copy_from_slice(&frame);
+        Ok(frame.len())
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> util::Result<(usize, SocketAddr)> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> util::Result<usize> {
+        let mut writer = self.writer.lock().await;
+        writer.write_all(buf).await?;
+        if !self.is_data_connection() && is_connection_bind_success(buf) {
+            self.raw.store(true, Ordering::SeqCst);
+        }
+        Ok(buf.len())
+    }
+
+    // send_to writes to the stream, the only reachable target is its remote address
+    async fn send_to(&self, buf: &[u8], _target: SocketAddr) -> util::Result<usize> {
+        self.send(buf).await
+    }
+
+    async fn local_addr(&self) -> util::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    async fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> util::Result<()> {
+        let mut writer = self.writer.lock().await;
+        writer.shutdown().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+use crate::error::Result;
+use crate::proto::chandata::ChannelData;
+
+use std::str::FromStr;
+use std::sync::Arc;
+use stun::attributes::ATTR_SOFTWARE;
+use stun::message::{Message, BINDING_REQUEST};
+use stun::textattrs::TextAttribute;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsConnector;
+use util::conn::Listener;
+
+#[tokio::test]
+async fn test_stun_conn_framing() -> Result<()> {
+    let (client, mut server) = tokio::io::duplex(64);
+    let local_addr = SocketAddr::from_str("127.0.0.1:1234")?;
+    let remote_addr = SocketAddr::from_str("127.0.0.1:3478")?;
+    let conn = StunConn::new(client, local_addr, remote_addr);
+
+    let mut msg = Message::new();
+    msg.build(&[
+        Box::new(BINDING_REQUEST),
+        Box::new(TextAttribute::new(ATTR_SOFTWARE, "test".to_owned())),
+    ])?;
+    let mut ch = ChannelData {
+        number: ChannelNumber(0x4000),
+        data: vec![1, 2, 3, 4, 5],
+        ..Default::default()
+    };
+    ch.encode();
+    assert_eq!(ch.raw.len(), 12, "ChannelData should be padded");
+
+    // both messages are written in a single chunk, followed by a split one
+    let mut stream = msg.raw.clone();
+    stream.extend_from_slice(&ch.raw);
+    server.write_all(&stream).await?;
+    server.write_all(&msg.raw[..10]).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], msg.raw.as_slice());
+    assert_eq!(from, remote_addr);
+
+    let n = conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], ch.raw.as_slice());
+
+    server.write_all(&msg.raw[10..]).await?;
+    let n = conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], msg.raw.as_slice());
+
+    conn.send_to(&ch.raw, SocketAddr::from_str("10.0.0.1:1")?)
+        .await?;
+    let mut received = vec![0u8; ch.raw.len()];
+    server.read_exact(&mut received).await?;
+    assert_eq!(received, ch.raw);
+
+    server.write_all(&[0xFF; 8]).await?;
+    assert!(
+        conn.recv(&mut buf).await.is_err(),
+        "data which isn't STUN or ChannelData should fail"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stun_listener_tls() -> Result<()> {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()])
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let cert_der = rustls::Certificate(
+        cert.serialize_der()
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?,
+    );
+
+    let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+    server_config
+        .set_single_cert(
+            vec![cert_der.clone()],
+            rustls::PrivateKey(cert.serialize_private_key_der()),
+        )
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let listener_addr = listener.local_addr()?;
+    let listener = StunListener::with_tls(listener, Arc::new(server_config))?;
+    assert_eq!(listener.addr().await?, listener_addr);
+
+    let mut client_config = rustls::ClientConfig::new();
+    client_config
+        .root_store
+        .add(&cert_der)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let connector = TlsConnector::from(Arc::new(client_config));
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str("localhost")
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let tcp_stream = TcpStream::connect(listener_addr).await?;
+    let client_addr = tcp_stream.local_addr()?;
+    let mut client = connector.connect(dns_name, tcp_stream).await?;
+
+    let (conn, remote_addr) = listener.accept().await?;
+    assert_eq!(remote_addr, client_addr);
+    assert_eq!(conn.local_addr().await?, listener_addr);
+
+    let mut msg = Message::new();
+    msg.build(&[Box::new(BINDING_REQUEST)])?;
+    client.write_all(&msg.raw).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], msg.raw.as_slice());
+    assert_eq!(from, client_addr);
+
+    listener.close().await?;
+    assert!(
+        listener.accept().await.is_err(),
+        "accepting after close should fail"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use super::StunConn;
+
+use async_trait::async_trait;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::time::{sleep, timeout, Duration};
+use tokio_rustls::TlsAcceptor;
+use util::conn::{Conn, Listener};
+
+/// How long a client may take to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long accepting pauses after an error, e.g. when out of file descriptors.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+const ACCEPT_BACKLOG: usize = 128;
+
+type AcceptedConn = (Arc<dyn Conn + Send + Sync>, SocketAddr);
+
+/// StunListener accepts TCP connections, optionally secured with TLS, and returns them as
+/// [`StunConn`]s. It allows a TURN server to serve clients over TCP and TLS.
+pub struct StunListener {
+    local_addr: SocketAddr,
+    accept_rx: Mutex<mpsc::Receiver<AcceptedConn>>,
+    closed_tx: Mutex<Option<watch::Sender<()>>>,
+}
+
+impl StunListener {
+    /// new returns a StunListener for plain TCP connections.
+    pub fn new(listener: TcpListener) -> util::Result<Self> {
+        Self::listen(listener, None)
+    }
+
+    /// with_tls returns a StunListener that runs a TLS handshake on every accepted connection.
+    pub fn with_tls(
+        listener: TcpListener,
+        config: Arc<rustls::ServerConfig>,
+    ) -> util::Result<Self> {
+        Self::listen(listener, Some(TlsAcceptor::from(config)))
+    }
+
+    fn listen(listener: TcpListener, acceptor: Option<TlsAcceptor>) -> util::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (accept_tx, accept_rx) = mpsc::channel::<AcceptedConn>(ACCEPT_BACKLOG);
+        let (closed_tx, mut closed_rx) = watch::channel(());
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, remote_addr) = tokio::select! {
+                    res = listener.accept() => match res {
+                        Ok(v) => v,
+                        Err(err) => {
+                            log::warn!("failed to accept tcp connection: {}", err);
+                            // Errors such as EMFILE persist for a while, don't spin on them
+                            tokio::select! {
+                                _ = sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                                _ = closed_rx.changed() => break,
+                            }
+                        }
+                    },
+                    _ = closed_rx.changed() => break,
+                };
+
+                let accept_tx = accept_tx.clone();
+                match &acceptor {
+                    Some(acceptor) => {
+                        let acceptor = acceptor.clone();
+                        tokio::spawn(async move {
+                            let stream =
+                                match timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                                {
+                                    Ok(Ok(stream)) => stream,
+                                    Ok(Err(err)) => {
+                                        log::debug!(
+                                            "tls handshake with {} failed: {}",
+                                            remote_addr,
+                                            err
+                                        );
+                                        return;
+                                    }
+                                    Err(_) => {
+                                        log::debug!("tls handshake with {} timed out", remote_addr);
+                                        return;
+                                    }
+                                };
+                            let conn = StunConn::new(stream, local_addr, remote_addr);
+                            let _ = accept_tx.send((Arc::new(conn), remote_addr)).await;
+                        });
+                    }
+                    None => {
+                        let conn = StunConn::new(stream, local_addr, remote_addr);
+                        let _ = accept_tx.send((Arc::new(conn), remote_addr)).await;
+                    }
+                }
+            }
+        });
+
+        Ok(StunListener {
+            local_addr,
+            accept_rx: Mutex::new(accept_rx),
+            closed_tx: Mutex::new(Some(closed_tx)),
+        })
+    }
+}
+
+#[async_trait]
+impl Listener for StunListener {
+    /// accept waits for and returns the next connection to the listener.
+    async fn accept(&self) -> util::Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        let mut accept_rx = self.accept_rx.lock().await;
+        accept_rx.recv().await.ok_or(util::Error::ErrClosedListener)
+    }
+
+    /// close closes the listener.
+    /// Any blocked accept operations will be unblocked and return errors.
+    async fn close(&self) -> util::Result<()> {
+        let mut closed_tx = self.closed_tx.lock().await;
+        if closed_tx.take().is_none() {
+            return Err(util::Error::ErrClosedListener);
+        }
+        Ok(())
+    }
+
+    /// addr returns the listener's network address.
+    async fn addr(&self) -> util::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
@@ -3,7 +3,7 @@ use crate::error::Error;
 use crate::Buffer;
 
 use core::sync::atomic::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::AtomicBool;
@@ -16,6 +16,14 @@ const DEFAULT_LISTEN_BACKLOG: usize = 128; // same as Linux default
 pub type AcceptFilterFn =
     Box<dyn (Fn(&[u8]) -> Pin<Box<dyn Future<Output = bool> + Send + 'static>>) + Send + Sync>;
 
+/// DatagramRouterFn returns the identifier of the conn an incoming datagram
+/// belongs to, None routes the datagram by its source address.
+pub type DatagramRouterFn = Box<dyn (Fn(&[u8]) -> Option<String>) + Send + Sync>;
+
+/// ConnIdentifierFn returns the identifier a conn announces in an outgoing
+/// datagram, if any.
+pub type ConnIdentifierFn = Arc<dyn (Fn(&[u8]) -> Option<String>) + Send + Sync>;
+
 type AcceptDoneCh = (mpsc::Receiver<Arc<UdpConn>>, watch::Receiver<()>);
 
 /// listener is used in the [DTLS](https://github.com/webrtc-rs/dtls) and
@@ -39,7 +47,7 @@ impl Listener for ListenerImpl {
         tokio::select! {
             c = accept_ch_rx.recv() =>{
                 if let Some(c) = c{
-                    let raddr = c.raddr;
+                    let raddr = *c.raddr.lock().await;
                     Ok((c, raddr))
                 }else{
                     Err(Error::ErrClosedListenerAcceptCh)
@@ -87,6 +95,17 @@ pub struct ListenConfig {
     /// AcceptFilter determines whether the new conn should be made for
     /// the incoming packet. If not set, any packet creates new conn.
     pub accept_filter: Option<AcceptFilterFn>,
+
+    /// DatagramRouter routes incoming datagrams by an identifier they carry
+    /// rather than by their source address, such as the DTLS connection ID.
+    /// A routed datagram from a new address doesn't move the conn, recv_from
+    /// returns its source and the conn follows it only once the protocol
+    /// calls set_remote_addr. If not set, datagrams are routed by address.
+    pub datagram_router: Option<DatagramRouterFn>,
+
+    /// ConnectionIdentifier learns the identifier DatagramRouter returns
+    /// for a conn from the datagrams the conn sends.
+    pub connection_identifier: Option<ConnIdentifierFn>,
 }
 
 pub async fn listen<A: ToSocketAddrs>(laddr: A) -> Result<impl Listener> {
@@ -116,6 +135,8 @@ impl ListenConfig {
         let pconn = Arc::clone(&l.pconn);
         let accepting = Arc::clone(&l.accepting);
         let accept_filter = self.accept_filter.take();
+        let datagram_router = self.datagram_router.take();
+        let connection_identifier = self.connection_identifier.take();
         let accept_ch_tx = Arc::clone(&l.accept_ch_tx);
         let conns = Arc::clone(&l.conns);
         tokio::spawn(async move {
@@ -124,6 +145,8 @@ impl ListenConfig {
                 pconn,
                 accepting,
                 accept_filter,
+                datagram_router,
+                connection_identifier,
                 accept_ch_tx,
                 conns,
             )
@@ -137,11 +160,14 @@ impl ListenConfig {
     /// 1. Dispatching incoming packets to the correct Conn.
     ///    It can therefore not be ended until all Conns are closed.
     /// 2. Creating a new Conn when receiving from a new remote.
+    #[allow(clippy::too_many_arguments)]
     async fn read_loop(
         mut done_ch_rx: watch::Receiver<()>,
         pconn: Arc<dyn Conn + Send + Sync>,
         accepting: Arc<AtomicBool>,
         accept_filter: Option<AcceptFilterFn>,
+        datagram_router: Option<DatagramRouterFn>,
+        connection_identifier: Option<ConnIdentifierFn>,
         accept_ch_tx: Arc<Mutex<Option<mpsc::Sender<Arc<UdpConn>>>>>,
         conns: Arc<Mutex<HashMap<String, Arc<UdpConn>>>>,
     ) {
@@ -159,6 +185,8 @@ impl ListenConfig {
                                 &pconn,
                                 &accepting,
                                 &accept_filter,
+                                &datagram_router,
+                                &connection_identifier,
                                 &accept_ch_tx,
                                 &conns,
                                 raddr,
@@ -171,7 +199,7 @@ impl ListenConfig {
                             };
 
                             if let Some(conn) = udp_conn {
-                                let _ = conn.buffer.write(&buf[..n]).await;
+                                conn.deliver(&buf[..n], raddr).await;
                             }
                         }
                         Err(err) => {
@@ -184,10 +212,13 @@ impl ListenConfig {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn get_udp_conn(
         pconn: &Arc<dyn Conn + Send + Sync>,
         accepting: &Arc<AtomicBool>,
         accept_filter: &Option<AcceptFilterFn>,
+        datagram_router: &Option<DatagramRouterFn>,
+        connection_identifier: &Option<ConnIdentifierFn>,
         accept_ch_tx: &Arc<Mutex<Option<mpsc::Sender<Arc<UdpConn>>>>>,
         conns: &Arc<Mutex<HashMap<String, Arc<UdpConn>>>>,
         raddr: SocketAddr,
@@ -195,6 +226,14 @@ impl ListenConfig {
     ) -> Result<Option<Arc<UdpConn>>> {
         {
             let m = conns.lock().await;
+            // The identifier is not authenticated, the remote address of the
+            // conn is only changed by set_remote_addr
+            if let Some(id) = datagram_router.as_ref().and_then(|f| f(buf)) {
+                if let Some(conn) = m.get(&id) {
+                    return Ok(Some(conn.clone()));
+                }
+            }
+
             if let Some(conn) = m.get(raddr.to_string().as_str()) {
                 return Ok(Some(conn.clone()));
             }
@@ -210,7 +249,12 @@ impl ListenConfig {
             }
         }
 
-        let udp_conn = Arc::new(UdpConn::new(Arc::clone(pconn), Arc::clone(conns), raddr));
+        let udp_conn = Arc::new(UdpConn::new(
+            Arc::clone(pconn),
+            Arc::clone(conns),
+            raddr,
+            connection_identifier.clone(),
+        ));
         {
             let accept_ch = accept_ch_tx.lock().await;
             if let Some(tx) = &*accept_ch {
@@ -235,8 +279,12 @@ impl ListenConfig {
 pub struct UdpConn {
     pconn: Arc<dyn Conn + Send + Sync>,
     conns: Arc<Mutex<HashMap<String, Arc<UdpConn>>>>,
-    raddr: SocketAddr,
+    raddr: Mutex<SocketAddr>,
     buffer: Buffer,
+    // source addresses of the datagrams in buffer, in the same order
+    sources: Mutex<VecDeque<SocketAddr>>,
+    connection_identifier: Option<ConnIdentifierFn>,
+    id: Mutex<Option<String>>,
 }
 
 impl UdpConn {
@@ -244,12 +292,63 @@ impl UdpConn {
         pconn: Arc<dyn Conn + Send + Sync>,
         conns: Arc<Mutex<HashMap<String, Arc<UdpConn>>>>,
         raddr: SocketAddr,
+        connection_identifier: Option<ConnIdentifierFn>,
     ) -> Self {
         UdpConn {
             pconn,
             conns,
-            raddr,
+            raddr: Mutex::new(raddr),
             buffer: Buffer::new(0, 0),
+            sources: Mutex::new(VecDeque::new()),
+            connection_identifier,
+            id: Mutex::new(None),
+        }
+    }
+
+    /// deliver buffers a datagram received from source
+    async fn deliver(&self, buf: &[u8], source: SocketAddr) {
+        self.sources.lock().await.push_back(source);
+        if self.buffer.write(buf).await.is_err() {
+            self.sources.lock().await.pop_back();
+        }
+    }
+
+    /// read returns the next datagram and its source address
+    async fn read(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
+        let result = self.buffer.read(buf, None).await;
+        // A datagram longer than buf is consumed as well
+        let source = match &result {
+            Ok(_) | Err(Error::ErrBufferShort) => self.sources.lock().await.pop_front(),
+            Err(_) => None,
+        };
+        let n = result?;
+        match source {
+            Some(source) => Ok((n, source)),
+            None => Ok((n, *self.raddr.lock().await)),
+        }
+    }
+
+    /// learn_id registers the conn under the identifier announced in buf, so
+    /// the datagram router finds it when the remote address changes.
+    async fn learn_id(&self, buf: &[u8]) {
+        let connection_identifier = match &self.connection_identifier {
+            Some(f) => f,
+            None => return,
+        };
+
+        if self.id.lock().await.is_some() {
+            return;
+        }
+
+        if let Some(new_id) = connection_identifier(buf) {
+            // conns is always locked first, as the read loop does
+            let mut conns = self.conns.lock().await;
+            let mut id = self.id.lock().await;
+            let raddr = self.raddr.lock().await.to_string();
+            if let Some(conn) = conns.get(raddr.as_str()).cloned() {
+                conns.insert(new_id.clone(), conn);
+            }
+            *id = Some(new_id);
         }
     }
 }
@@ -261,16 +360,19 @@ impl Conn for UdpConn {
     }
 
     async fn recv(&self, buf: &mut [u8]) -> Result<usize> {
-        Ok(self.buffer.read(buf, None).await?)
+        let (n, _) = self.read(buf).await?;
+        Ok(n)
     }
 
     async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
-        let n = self.buffer.read(buf, None).await?;
-        Ok((n, self.raddr))
+        self.read(buf).await
     }
 
     async fn send(&self, buf: &[u8]) -> Result<usize> {
-        self.pconn.send_to(buf, self.raddr).await
+        self.learn_id(buf).await;
+
+        let raddr = *self.raddr.lock().await;
+        self.pconn.send_to(buf, raddr).await
     }
 
     async fn send_to(&self, buf: &[u8], target: SocketAddr) -> Result<usize> {
@@ -282,12 +384,28 @@ impl Conn for UdpConn {
     }
 
     async fn remote_addr(&self) -> Option<SocketAddr> {
-        Some(self.raddr)
+        Some(*self.raddr.lock().await)
+    }
+
+    async fn set_remote_addr(&self, addr: SocketAddr) -> Result<()> {
+        // conns is always locked first, as the read loop does
+        let mut conns = self.conns.lock().await;
+        let mut raddr = self.raddr.lock().await;
+        if *raddr != addr {
+            if let Some(conn) = conns.remove(raddr.to_string().as_str()) {
+                conns.insert(addr.to_string(), conn);
+            }
+            *raddr = addr;
+        }
+        Ok(())
     }
 
     async fn close(&self) -> Result<()> {
         let mut conns = self.conns.lock().await;
-        conns.remove(self.raddr.to_string().as_str());
+        conns.remove(self.raddr.lock().await.to_string().as_str());
+        if let Some(id) = &*self.id.lock().await {
+            conns.remove(id.as_str());
+        }
         Ok(())
     }
 }
@@ -154,6 +154,83 @@ async fn test_listener_accept_filter() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_listener_datagram_router() -> Result<()> {
+    // Datagrams starting with 0xCC carry the identifier in their second byte,
+    // conns announce it in datagrams starting with 0xDD
+    let datagram_router: Option<DatagramRouterFn> = Some(Box::new(|pkt: &[u8]| {
+        if pkt.len() >= 2 && pkt[0] == 0xCC {
+            Some(pkt[1].to_string())
+        } else {
+            None
+        }
+    }));
+    let connection_identifier: Option<ConnIdentifierFn> = Some(Arc::new(|pkt: &[u8]| {
+        if pkt.len() >= 2 && pkt[0] == 0xDD {
+            Some(pkt[1].to_string())
+        } else {
+            None
+        }
+    }));
+
+    let listener = ListenConfig {
+        datagram_router,
+        connection_identifier,
+        ..Default::default()
+    }
+    .listen("127.0.0.1:0")
+    .await?;
+
+    let conn1 = UdpSocket::bind("127.0.0.1:0").await?;
+    conn1.connect(listener.addr().await?).await?;
+    conn1.send(&[0x01]).await?;
+
+    let (l_conn, raddr) = listener.accept().await?;
+    assert_eq!(conn1.local_addr()?, raddr, "remote address should be match");
+
+    let mut buf = vec![0u8; 8];
+    let n = l_conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0x01]);
+
+    // Announce the identifier
+    l_conn.send(&[0xDD, 0x07]).await?;
+    let n = conn1.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0xDD, 0x07]);
+
+    // The remote moves to another address
+    let conn2 = UdpSocket::bind("127.0.0.1:0").await?;
+    conn2.connect(listener.addr().await?).await?;
+    conn2.send(&[0xCC, 0x07, 0x02]).await?;
+
+    let (n, source) = l_conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0xCC, 0x07, 0x02]);
+    assert_eq!(conn2.local_addr()?, source);
+
+    // The identifier alone doesn't move the conn
+    assert_eq!(Some(conn1.local_addr()?), l_conn.remote_addr().await);
+    l_conn.send(&[0x04]).await?;
+    let n = conn1.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0x04]);
+
+    // Until the protocol moves it
+    l_conn.set_remote_addr(source).await?;
+    assert_eq!(Some(conn2.local_addr()?), l_conn.remote_addr().await);
+
+    l_conn.send(&[0x03]).await?;
+    let n = conn2.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0x03]);
+
+    // A datagram with an unknown identifier is routed by its address
+    conn2.send(&[0xCC, 0x08]).await?;
+    let n = l_conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[0xCC, 0x08]);
+
+    l_conn.close().await?;
+    listener.close().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_listener_concurrent() -> Result<()> {
     const BACKLOG: usize = 2;
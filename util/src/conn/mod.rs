@@ -33,6 +33,13 @@ pub trait Conn {
     async fn local_addr(&self) -> Result<SocketAddr>;
     async fn remote_addr(&self) -> Option<SocketAddr>;
     async fn close(&self) -> Result<()>;
+
+    /// set_remote_addr moves the conn to a new remote address, once the
+    /// protocol above authenticated a datagram received from that address.
+    /// Conns which are not demultiplexed by address ignore it.
+    async fn set_remote_addr(&self, _addr: SocketAddr) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// A Listener is a generic network listener for connection-oriented protocols.
@@ -1,12 +1,17 @@
 #[cfg(test)]
 mod interceptor_registry_test;
 
-use crate::api::media_engine::MediaEngine;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_FLEXFEC03};
 use crate::error::Result;
-use crate::rtp_transceiver::rtp_codec::RTCRtpHeaderExtensionCapability;
+use crate::rtp_transceiver::rtp_codec::{
+    RTCRtpCodecCapability, RTCRtpCodecParameters, RTCRtpHeaderExtensionCapability,
+};
 use crate::rtp_transceiver::{rtp_codec::RTPCodecType, RTCPFeedback, TYPE_RTCP_FB_TRANSPORT_CC};
 
+use interceptor::flexfec::{decoder::Decoder, encoder::EncoderBuilder};
+use interceptor::gcc::SendSideBweBuilder;
 use interceptor::nack::{generator::Generator, responder::Responder};
+use interceptor::red;
 use interceptor::registry::Registry;
 use interceptor::report::{receiver::ReceiverReport, sender::SenderReport};
 use interceptor::twcc::{receiver::Receiver, sender::Sender};
@@ -24,6 +29,8 @@ pub fn register_default_interceptors(
 
     registry = configure_twcc_receiver_only(registry, media_engine)?;
 
+    registry = configure_red_receiver_only(registry);
+
     Ok(registry)
 }
 
@@ -60,6 +67,52 @@ pub fn configure_nack(mut registry: Registry, media_engine: &mut MediaEngine) ->
     registry
 }
 
+/// configure_red will setup everything necessary for sending audio/red (RFC 2198) encoded audio,
+/// which carries previous frames as redundancy, and for unwrapping received audio/red packets.
+/// The audio/red codec is registered by MediaEngine::register_default_codecs.
+pub fn configure_red(mut registry: Registry, encoder: red::encoder::EncoderBuilder) -> Registry {
+    registry.add(Box::new(encoder));
+    registry.add(Box::new(red::decoder::Decoder::builder()));
+    registry
+}
+
+/// configure_red_receiver_only will setup everything necessary for unwrapping received audio/red
+/// packets, recovering lost frames from their redundancy.
+pub fn configure_red_receiver_only(mut registry: Registry) -> Registry {
+    registry.add(Box::new(red::decoder::Decoder::builder()));
+    registry
+}
+
+/// configure_flexfec will setup everything necessary for protecting outgoing video with
+/// FlexFEC-03 repair packets and recovering lost incoming video packets from them.
+/// It has to be called before configure_nack, so lost packets are recovered before they
+/// are nacked. The FEC encoder is below the NACK responder then and sees the resent
+/// packets, it passes them on without protecting them again.
+pub fn configure_flexfec(
+    mut registry: Registry,
+    media_engine: &mut MediaEngine,
+    encoder: EncoderBuilder,
+) -> Result<Registry> {
+    media_engine.register_codec(
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_FLEXFEC03.to_owned(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "repair-window=10000000".to_owned(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: 118,
+            ..Default::default()
+        },
+        RTPCodecType::Video,
+    )?;
+
+    registry.add(Box::new(encoder));
+    registry.add(Box::new(Decoder::builder()));
+    Ok(registry)
+}
+
 /// configure_twcc will setup everything necessary for adding
 /// a TWCC header extension to outgoing RTP packets and generating TWCC reports.
 pub fn configure_twcc(mut registry: Registry, media_engine: &mut MediaEngine) -> Result<Registry> {
@@ -100,6 +153,18 @@ pub fn configure_twcc(mut registry: Registry, media_engine: &mut MediaEngine) ->
     Ok(registry)
 }
 
+/// configure_gcc will setup everything necessary for estimating the available send bandwidth
+/// with Google Congestion Control. The estimator is handed out by the callback set with
+/// SendSideBweBuilder::with_on_new_bwe and has to be registered ahead of the TWCC sender.
+pub fn configure_gcc(
+    mut registry: Registry,
+    media_engine: &mut MediaEngine,
+    bwe: SendSideBweBuilder,
+) -> Result<Registry> {
+    registry.add(Box::new(bwe));
+    configure_twcc(registry, media_engine)
+}
+
 /// configure_twcc_sender will setup everything necessary for adding
 /// a TWCC header extension to outgoing RTP packets. This will allow the remote peer to generate TWCC reports.
 pub fn configure_twcc_sender_only(
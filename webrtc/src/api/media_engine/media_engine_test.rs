@@ -638,6 +638,75 @@ async fn test_media_engine_double_register() -> Result<()> {
     Ok(())
 }
 
+/// The offered audio/red fmtp references the payload type of Opus in the offer
+#[tokio::test]
+async fn test_red_offer_fmtp() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    pc.add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+
+    let offer = pc.create_offer(None).await?;
+
+    let opus = Regex::new(r"(?m)^a=rtpmap:(\d+) opus/48000/2")
+        .unwrap()
+        .captures(offer.sdp.as_str())
+        .expect("opus in the offer")[1]
+        .to_owned();
+    let red = Regex::new(r"(?m)^a=rtpmap:(\d+) red/48000/2")
+        .unwrap()
+        .captures(offer.sdp.as_str())
+        .expect("red in the offer")[1]
+        .to_owned();
+    let fmtp = format!("a=fmtp:{} {}/{}\r\n", red, opus, opus);
+    assert!(offer.sdp.contains(&fmtp), "{} in {}", fmtp, offer.sdp);
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+/// audio/red is negotiated with the payload type of the remote offer, and its
+/// fmtp references the Opus payload type negotiated along with it
+#[tokio::test]
+async fn test_media_engine_red_remote_payload_type() -> Result<()> {
+    const RED_OFFER: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 109 100
+a=rtpmap:109 opus/48000/2
+a=fmtp:109 minptime=10;useinbandfec=1
+a=rtpmap:100 red/48000/2
+a=fmtp:100 109/109
+";
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    m.update_from_remote_description(&SessionDescription::unmarshal(&mut Cursor::new(
+        RED_OFFER.as_bytes(),
+    ))?)
+    .await?;
+    assert!(m.negotiated_audio.load(Ordering::SeqCst));
+
+    assert!(m.get_codec_by_payload(111).await.is_err());
+    assert!(m.get_codec_by_payload(63).await.is_err());
+
+    let (opus, _) = m.get_codec_by_payload(109).await?;
+    assert_eq!(opus.capability.mime_type, MIME_TYPE_OPUS);
+    let (red, _) = m.get_codec_by_payload(100).await?;
+    assert_eq!(red.capability.mime_type, MIME_TYPE_RED);
+    assert_eq!(
+        red.capability.sdp_fmtp_line,
+        format!("{}/{}", opus.payload_type, opus.payload_type)
+    );
+
+    Ok(())
+}
+
 async fn validate(m: &MediaEngine) -> Result<()> {
     m.update_header_extension(2, "test-extension", RTPCodecType::Audio)
         .await?;
@@ -780,3 +849,138 @@ a=rtpmap:111 opus/48000/2
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_media_engine_cryptex() -> Result<()> {
+    let must_parse = |raw: &str| -> Result<SessionDescription> {
+        let mut reader = Cursor::new(raw.as_bytes());
+        Ok(SessionDescription::unmarshal(&mut reader)?)
+    };
+
+    const OPUS_CRYPTEX: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+a=cryptex
+";
+    const OPUS: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+";
+    // Only one of the RTP media sections has a=cryptex
+    const OPUS_CRYPTEX_VP8: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+a=cryptex
+m=video 9 UDP/TLS/RTP/SAVPF 96
+a=rtpmap:96 VP8/90000
+";
+    // The rejected and the data sections don't need a=cryptex
+    const OPUS_CRYPTEX_REJECTED_VP8_DATA: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+a=cryptex
+m=video 0 UDP/TLS/RTP/SAVPF 96
+a=rtpmap:96 VP8/90000
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel
+";
+
+    for (cryptex, remote, expected) in [
+        (true, OPUS_CRYPTEX, true),
+        (true, OPUS, false),
+        (false, OPUS_CRYPTEX, false),
+        (true, OPUS_CRYPTEX_VP8, false),
+        (true, OPUS_CRYPTEX_REJECTED_VP8_DATA, true),
+    ] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.set_cryptex(cryptex);
+        m.update_from_remote_description(&must_parse(remote)?)
+            .await?;
+
+        assert_eq!(
+            expected,
+            m.negotiated_cryptex.load(Ordering::SeqCst),
+            "local cryptex {}, remote {}",
+            cryptex,
+            remote
+        );
+    }
+
+    // The offer and the answer both carry a=cryptex, and both peers send with it
+    let mut pcs = vec![];
+    for _ in 0..2 {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.set_cryptex(true);
+        let api = APIBuilder::new().with_media_engine(m).build();
+        let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+        pcs.push(pc);
+    }
+    let (offer_pc, answer_pc) = (&pcs[0], &pcs[1]);
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+
+    let re = Regex::new(r"(?m)^a=cryptex").unwrap();
+
+    let offer = offer_pc.create_offer(None).await?;
+    assert!(re.is_match(offer.sdp.as_str()));
+    offer_pc.set_local_description(offer.clone()).await?;
+    answer_pc.set_remote_description(offer).await?;
+
+    let answer = answer_pc.create_answer(None).await?;
+    assert!(re.is_match(answer.sdp.as_str()));
+    answer_pc.set_local_description(answer.clone()).await?;
+    offer_pc.set_remote_description(answer).await?;
+
+    for pc in [offer_pc, answer_pc] {
+        let transport = pc.sctp().transport();
+        assert!(transport.srtp_cryptex.load(Ordering::SeqCst));
+        pc.close().await?;
+    }
+
+    // An answer has no a=cryptex if the offer doesn't have it
+    let mut pcs = vec![];
+    for cryptex in [false, true] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.set_cryptex(cryptex);
+        let api = APIBuilder::new().with_media_engine(m).build();
+        let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+        pcs.push(pc);
+    }
+    let (offer_pc, answer_pc) = (&pcs[0], &pcs[1]);
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+
+    let offer = offer_pc.create_offer(None).await?;
+    assert!(!re.is_match(offer.sdp.as_str()));
+    offer_pc.set_local_description(offer.clone()).await?;
+    answer_pc.set_remote_description(offer).await?;
+
+    let answer = answer_pc.create_answer(None).await?;
+    assert!(!re.is_match(answer.sdp.as_str()));
+    answer_pc.set_local_description(answer.clone()).await?;
+    offer_pc.set_remote_description(answer).await?;
+
+    for pc in [offer_pc, answer_pc] {
+        let transport = pc.sctp().transport();
+        assert!(!transport.srtp_cryptex.load(Ordering::SeqCst));
+        pc.close().await?;
+    }
+
+    Ok(())
+}
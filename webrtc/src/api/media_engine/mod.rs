@@ -5,6 +5,7 @@ use crate::error::{Error, Result};
 use crate::peer_connection::sdp::{
     codecs_from_media_description, rtp_extensions_from_media_description,
 };
+use crate::peer_connection::MEDIA_SECTION_APPLICATION;
 use crate::rtp_transceiver::fmtp;
 use crate::rtp_transceiver::rtp_codec::{
     codec_parameters_fuzzy_search, CodecMatch, RTCRtpCodecCapability, RTCRtpCodecParameters,
@@ -17,7 +18,7 @@ use crate::stats::stats_collector::StatsCollector;
 use crate::stats::CodecStats;
 use crate::stats::StatsReportType::Codec;
 
-use sdp::description::session::SessionDescription;
+use sdp::description::session::{SessionDescription, ATTR_KEY_CRYPTEX};
 use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -27,6 +28,9 @@ use tokio::sync::Mutex;
 /// MIME_TYPE_H264 H264 MIME type.
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_H264: &str = "video/H264";
+/// MIME_TYPE_H265 H265 MIME type
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_H265: &str = "video/H265";
 /// MIME_TYPE_OPUS Opus MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_OPUS: &str = "audio/opus";
@@ -48,6 +52,15 @@ pub const MIME_TYPE_PCMU: &str = "audio/PCMU";
 /// MIME_TYPE_PCMA PCMA MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_PCMA: &str = "audio/PCMA";
+/// MIME_TYPE_RED RED (RFC 2198) redundant audio MIME type
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_RED: &str = "audio/red";
+/// MIME_TYPE_FLEXFEC03 FlexFEC-03 MIME type
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_FLEXFEC03: &str = "video/flexfec-03";
+/// MIME_TYPE_RTX RTX (RFC 4588) retransmission MIME type
+/// Note: Matching should be case insensitive.
+pub const MIME_TYPE_RTX: &str = "video/rtx";
 /// MIME_TYPE_TELEPHONE_EVENT telephone-event MIME type
 /// Note: Matching should be case insensitive.
 pub const MIME_TYPE_TELEPHONE_EVENT: &str = "audio/telephone-event";
@@ -93,6 +106,9 @@ pub struct MediaEngine {
     header_extensions: Vec<MediaEngineHeaderExtension>,
     proposed_header_extensions: Mutex<HashMap<isize, MediaEngineHeaderExtension>>,
     pub(crate) negotiated_header_extensions: Mutex<HashMap<isize, MediaEngineHeaderExtension>>,
+
+    pub(crate) cryptex: bool,
+    pub(crate) negotiated_cryptex: AtomicBool,
 }
 
 impl MediaEngine {
@@ -100,6 +116,7 @@ impl MediaEngine {
     /// register_default_codecs is not safe for concurrent use.
     pub fn register_default_codecs(&mut self) -> Result<()> {
         // Default Audio Codecs
+        let opus_payload_type = 111;
         for codec in vec![
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
@@ -109,7 +126,19 @@ impl MediaEngine {
                     sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
                     rtcp_feedback: vec![],
                 },
-                payload_type: 111,
+                payload_type: opus_payload_type,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RED.to_owned(),
+                    clock_rate: 48000,
+                    channels: 2,
+                    // RED carries Opus as both the primary and the redundant encoding
+                    sdp_fmtp_line: format!("{}/{}", opus_payload_type, opus_payload_type),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 63,
                 ..Default::default()
             },
             RTCRtpCodecParameters {
@@ -179,6 +208,17 @@ impl MediaEngine {
                 payload_type: 96,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=96".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 97,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_VP9.to_owned(),
@@ -190,6 +230,17 @@ impl MediaEngine {
                 payload_type: 98,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=98".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 99,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_VP9.to_owned(),
@@ -201,6 +252,17 @@ impl MediaEngine {
                 payload_type: 100,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=100".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 101,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_H264.to_owned(),
@@ -214,6 +276,17 @@ impl MediaEngine {
                 payload_type: 102,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=102".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 121,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_H264.to_owned(),
@@ -227,6 +300,17 @@ impl MediaEngine {
                 payload_type: 127,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=127".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 120,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_H264.to_owned(),
@@ -240,6 +324,17 @@ impl MediaEngine {
                 payload_type: 125,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=125".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 107,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_H264.to_owned(),
@@ -253,6 +348,17 @@ impl MediaEngine {
                 payload_type: 108,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=108".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 109,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: MIME_TYPE_H264.to_owned(),
@@ -274,11 +380,66 @@ impl MediaEngine {
                     sdp_fmtp_line:
                         "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640032"
                             .to_owned(),
-                    rtcp_feedback: video_rtcp_feedback,
+                    rtcp_feedback: video_rtcp_feedback.clone(),
                 },
                 payload_type: 123,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=123".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 119,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_AV1.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: video_rtcp_feedback.clone(),
+                },
+                payload_type: 41,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=41".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 42,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_H265.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "".to_owned(),
+                    rtcp_feedback: video_rtcp_feedback,
+                },
+                payload_type: 126,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=126".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 124,
+                ..Default::default()
+            },
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
                     mime_type: "video/ulpfec".to_owned(),
@@ -387,6 +548,15 @@ impl MediaEngine {
         Ok(())
     }
 
+    /// set_cryptex offers to encrypt the CSRCs and the header extensions
+    /// registered with register_header_extension with cryptex, by adding
+    /// `a=cryptex` to the media sections. RTP packets are sent with cryptex
+    /// once the remote peer accepts it, see
+    /// <https://www.rfc-editor.org/rfc/rfc9335>
+    pub fn set_cryptex(&mut self, cryptex: bool) {
+        self.cryptex = cryptex;
+    }
+
     /// register_feedback adds feedback mechanism to already registered codecs.
     pub fn register_feedback(&mut self, feedback: RTCPFeedback, typ: RTPCodecType) {
         match typ {
@@ -431,6 +601,7 @@ impl MediaEngine {
             video_codecs: self.video_codecs.clone(),
             audio_codecs: self.audio_codecs.clone(),
             header_extensions: self.header_extensions.clone(),
+            cryptex: self.cryptex,
             ..Default::default()
         }
     }
@@ -595,6 +766,25 @@ impl MediaEngine {
         &self,
         desc: &SessionDescription,
     ) -> Result<()> {
+        // a=cryptex may be at session level or in the media sections. The
+        // SRTP session is shared, so every accepted RTP media section must
+        // have it. The iterator is scoped so it doesn't live across the awaits
+        // below, which would keep the returned future from being Send.
+        let remote_cryptex = desc.attributes.iter().any(|a| a.key == ATTR_KEY_CRYPTEX) || {
+            let mut rtp_media = desc
+                .media_descriptions
+                .iter()
+                .filter(|media| {
+                    media.media_name.media != MEDIA_SECTION_APPLICATION
+                        && media.media_name.port.value != 0
+                })
+                .peekable();
+            rtp_media.peek().is_some()
+                && rtp_media.all(|media| media.attribute(ATTR_KEY_CRYPTEX).is_some())
+        };
+        self.negotiated_cryptex
+            .store(self.cryptex && remote_cryptex, Ordering::SeqCst);
+
         for media in &desc.media_descriptions {
             let typ = if !self.negotiated_audio.load(Ordering::SeqCst)
                 && media.media_name.media.to_lowercase() == "audio"
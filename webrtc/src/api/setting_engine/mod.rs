@@ -1,12 +1,14 @@
 #[cfg(test)]
 mod setting_engine_test;
 
+use crate::dtls_transport::default_srtp_protection_profiles;
 use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
 use ice::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
 use ice::mdns::MulticastDnsMode;
 use ice::network_type::NetworkType;
+use ice::tcp_mux::TCPMux;
 use ice::udp_network::UDPNetwork;
 
 use crate::error::{Error, Result};
@@ -69,13 +71,14 @@ pub struct SettingEngine {
     pub(crate) disable_srtcp_replay_protection: bool,
     pub(crate) vnet: Option<Arc<Net>>,
     //BufferFactory                             :func(packetType packetio.BufferPacketType, ssrc uint32) io.ReadWriteCloser,
-    //iceTCPMux                                 :ice.TCPMux,?
     //iceProxyDialer                            :proxy.Dialer,?
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) ice_tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     pub(crate) disable_media_engine_copy: bool,
     pub(crate) srtp_protection_profiles: Vec<SrtpProtectionProfile>,
     pub(crate) receive_mtu: usize,
     pub(crate) mid_generator: Option<Arc<dyn Fn(isize) -> String + Send + Sync>>,
+    pub(crate) sdes: bool,
 }
 
 impl SettingEngine {
@@ -87,6 +90,17 @@ impl SettingEngine {
             RECEIVE_MTU
         }
     }
+
+    /// get_srtp_protection_profiles returns the configured srtp protection profiles, or the
+    /// default ones if none are configured
+    pub(crate) fn get_srtp_protection_profiles(&self) -> Vec<SrtpProtectionProfile> {
+        if !self.srtp_protection_profiles.is_empty() {
+            self.srtp_protection_profiles.clone()
+        } else {
+            default_srtp_protection_profiles()
+        }
+    }
+
     /// detach_data_channels enables detaching data channels. When enabled
     /// data channels have to be detached in the OnOpen callback using the
     /// DataChannel.Detach method.
@@ -96,10 +110,20 @@ impl SettingEngine {
 
     /// set_srtp_protection_profiles allows the user to override the default srtp Protection Profiles
     /// The default srtp protection profiles are provided by the function `defaultSrtpProtectionProfiles`
+    /// All profiles of `SrtpProtectionProfile` are supported, the AES-256 ones included
     pub fn set_srtp_protection_profiles(&mut self, profiles: Vec<SrtpProtectionProfile>) {
         self.srtp_protection_profiles = profiles
     }
 
+    /// set_sdes runs the media without DTLS, the SRTP keys are exchanged in the
+    /// a=crypto attributes of the SDP instead (RFC 4568). This is meant for legacy
+    /// endpoints like SIP trunks: the keys are only as safe as the signaling channel.
+    /// ICE is still used, the crypto-suites come from the srtp protection profiles,
+    /// and data channels are not available as they need DTLS.
+    pub fn set_sdes(&mut self, sdes: bool) {
+        self.sdes = sdes;
+    }
+
     /// set_ice_timeouts sets the behavior around ICE Timeouts
     /// * disconnected_timeout is the duration without network activity before a Agent is considered disconnected. Default is 5 Seconds
     /// * failed_timeout is the duration without network activity before a Agent is considered failed after disconnected. Default is 25 Seconds
@@ -281,11 +305,12 @@ impl SettingEngine {
         self.sdp_media_level_fingerprints = sdp_media_level_fingerprints;
     }
 
-    // SetICETCPMux enables ICE-TCP when set to a non-nil value. Make sure that
-    // NetworkTypeTCP4 or NetworkTypeTCP6 is enabled as well.
-    //pub fn SetICETCPMux(&mut self, tcpMux ice.TCPMux) {
-    //    self.iceTCPMux = tcpMux
-    //}
+    /// set_ice_tcp_mux enables passive ICE-TCP candidates, incoming TCP connections are
+    /// accepted by the given mux. Make sure that NetworkType::Tcp4 or NetworkType::Tcp6 is
+    /// enabled as well.
+    pub fn set_ice_tcp_mux(&mut self, tcp_mux: Arc<dyn TCPMux + Send + Sync>) {
+        self.ice_tcp_mux = Some(tcp_mux);
+    }
 
     // SetICEProxyDialer sets the proxy dialer interface based on golang.org/x/net/proxy.
     //pub fn SetICEProxyDialer(&mut self, d proxy.Dialer) {
@@ -8,6 +8,7 @@ use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
     close_pair_now, new_pair, signal_pair, until_connection_state,
 };
+use crate::rtp_transceiver::rtp_codec::RTPCodecType;
 use ice::mdns::MulticastDnsMode;
 use ice::network_type::NetworkType;
 use regex::Regex;
@@ -209,3 +210,251 @@ async fn test_peer_connection_dtls_role_setting_engine_client() -> Result<()> {
 
     run_test(DTLSRole::Client).await
 }
+
+#[tokio::test]
+async fn test_peer_connection_srtp_protection_profiles() -> Result<()> {
+    let tests = vec![
+        (
+            SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm,
+            ProtectionProfile::AeadAes256Gcm,
+        ),
+        (
+            SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80,
+            ProtectionProfile::Aes256CmHmacSha1_80,
+        ),
+        (
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32,
+            ProtectionProfile::Aes128CmHmacSha1_32,
+        ),
+    ];
+
+    for (srtp_protection_profile, expected_profile) in tests {
+        let mut pcs = vec![];
+        for _ in 0..2 {
+            let mut s = SettingEngine::default();
+            s.set_srtp_protection_profiles(vec![srtp_protection_profile]);
+            s.set_ice_multicast_dns_mode(MulticastDnsMode::Disabled);
+            s.set_network_types(vec![NetworkType::Udp4]);
+            pcs.push(
+                APIBuilder::new()
+                    .with_setting_engine(s)
+                    .build()
+                    .new_peer_connection(RTCConfiguration::default())
+                    .await?,
+            );
+        }
+        let (mut offer_pc, mut answer_pc) = (pcs.remove(0), pcs.remove(0));
+
+        signal_pair(&mut offer_pc, &mut answer_pc).await?;
+
+        let wg = WaitGroup::new();
+        until_connection_state(&mut answer_pc, &wg, RTCPeerConnectionState::Connected).await;
+        wg.wait().await;
+
+        for pc in [&offer_pc, &answer_pc] {
+            let transport = pc.sctp().transport();
+            let profile = *transport.srtp_protection_profile.lock().await;
+            assert_eq!(
+                expected_profile as u8, profile as u8,
+                "{:?} negotiated {:?}",
+                srtp_protection_profile, profile
+            );
+        }
+
+        close_pair_now(&offer_pc, &answer_pc).await;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_sdes() -> Result<()> {
+    let mut pcs = vec![];
+    for profiles in [
+        vec![
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80,
+            SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm,
+        ],
+        vec![SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm],
+    ] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut s = SettingEngine::default();
+        s.set_sdes(true);
+        s.set_srtp_protection_profiles(profiles);
+        s.set_ice_multicast_dns_mode(MulticastDnsMode::Disabled);
+        s.set_network_types(vec![NetworkType::Udp4]);
+        pcs.push(
+            APIBuilder::new()
+                .with_media_engine(m)
+                .with_setting_engine(s)
+                .build()
+                .new_peer_connection(RTCConfiguration::default())
+                .await?,
+        );
+    }
+    let (mut offer_pc, mut answer_pc) = (pcs.remove(0), pcs.remove(0));
+
+    assert!(
+        offer_pc.create_data_channel("data", None).await.is_err(),
+        "data channels need DTLS"
+    );
+
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+
+    let offer = offer_pc.create_offer(None).await?;
+    assert!(offer.sdp.contains("m=audio 9 RTP/SAVPF "));
+    assert!(offer
+        .sdp
+        .contains("a=crypto:1 AES_CM_128_HMAC_SHA1_80 inline:"));
+    assert!(offer.sdp.contains("a=crypto:2 AEAD_AES_256_GCM inline:"));
+    assert!(!offer.sdp.contains("a=fingerprint"));
+
+    let mut offer_gathering_complete = offer_pc.gathering_complete_promise().await;
+    offer_pc.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+
+    answer_pc
+        .set_remote_description(
+            offer_pc
+                .local_description()
+                .await
+                .ok_or_else(|| Error::new("non local description".to_owned()))?,
+        )
+        .await?;
+
+    let answer = answer_pc.create_answer(None).await?;
+    assert!(answer.sdp.contains("a=crypto:2 AEAD_AES_256_GCM inline:"));
+    assert!(!answer.sdp.contains("a=crypto:1"));
+    assert!(!answer.sdp.contains("a=fingerprint"));
+
+    let mut answer_gathering_complete = answer_pc.gathering_complete_promise().await;
+    answer_pc.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+
+    offer_pc
+        .set_remote_description(
+            answer_pc
+                .local_description()
+                .await
+                .ok_or_else(|| Error::new("non local description".to_owned()))?,
+        )
+        .await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut offer_pc, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut answer_pc, &wg, RTCPeerConnectionState::Connected).await;
+    wg.wait().await;
+
+    let (offer_transport, answer_transport) =
+        (offer_pc.sctp().transport(), answer_pc.sctp().transport());
+    for transport in [&offer_transport, &answer_transport] {
+        let profile = *transport.srtp_protection_profile.lock().await;
+        assert_eq!(ProtectionProfile::AeadAes256Gcm as u8, profile as u8);
+        assert!(transport.get_srtp_session().await.is_some());
+        assert!(transport.get_srtcp_session().await.is_some());
+    }
+
+    {
+        let offer_sdes = offer_transport.sdes.lock().await;
+        let answer_sdes = answer_transport.sdes.lock().await;
+        let (offer_local, offer_remote) = offer_sdes.keys().expect("offer keys");
+        let (answer_local, answer_remote) = answer_sdes.keys().expect("answer keys");
+        assert_eq!(offer_local, answer_remote);
+        assert_eq!(offer_remote, answer_local);
+    }
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+fn sdes_remote_description(crypto_lines: &[String]) -> Result<sdp::SessionDescription> {
+    let mut sdp = "v=0\r\n\
+        o=- 4596489990601351948 2 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=audio 9 RTP/SAVPF 111\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        a=rtpmap:111 opus/48000/2\r\n"
+        .to_owned();
+    for line in crypto_lines {
+        sdp += &format!("a=crypto:{}\r\n", line);
+    }
+    Ok(sdp::SessionDescription::unmarshal(
+        &mut std::io::Cursor::new(sdp),
+    )?)
+}
+
+#[test]
+fn test_sdes_session_params() -> Result<()> {
+    let key = base64::encode([0x42u8; 30]);
+
+    // Crypto attributes with session parameters are skipped
+    let mut sdes = sdes::Sdes::new(&[ProtectionProfile::Aes128CmHmacSha1_80]);
+    sdes.set_remote(
+        &sdes_remote_description(&[
+            format!("1 AES_CM_128_HMAC_SHA1_80 inline:{} UNENCRYPTED_SRTP", key),
+            format!("2 AES_CM_128_HMAC_SHA1_80 inline:{} KDR=1", key),
+            format!("3 AES_CM_128_HMAC_SHA1_80 inline:{}", key),
+        ])?,
+        true,
+    )?;
+    assert_eq!(sdes.crypto_attributes()[0].tag, 3);
+
+    for param in [
+        "UNENCRYPTED_SRTP",
+        "UNENCRYPTED_SRTCP",
+        "UNAUTHENTICATED_SRTP",
+        "KDR=1",
+        "WSH=128",
+        "FEC_ORDER=FEC_SRTP",
+    ] {
+        let mut sdes = sdes::Sdes::new(&[ProtectionProfile::Aes128CmHmacSha1_80]);
+        let result = sdes.set_remote(
+            &sdes_remote_description(&[format!(
+                "1 AES_CM_128_HMAC_SHA1_80 inline:{} {}",
+                key, param
+            )])?,
+            true,
+        );
+        assert_eq!(result, Err(Error::ErrNoMatchingSdesCrypto), "{}", param);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_sdes_rekey() -> Result<()> {
+    let key = base64::encode([0x42u8; 30]);
+    let new_key = base64::encode([0x43u8; 30]);
+
+    let mut sdes = sdes::Sdes::new(&[ProtectionProfile::Aes128CmHmacSha1_80]);
+    sdes.set_remote(
+        &sdes_remote_description(&[format!("1 AES_CM_128_HMAC_SHA1_80 inline:{}", key)])?,
+        true,
+    )?;
+
+    // A re-offer with the same key keeps the session
+    sdes.set_remote(
+        &sdes_remote_description(&[format!("1 AES_CM_128_HMAC_SHA1_80 inline:{}", key)])?,
+        true,
+    )?;
+
+    // A re-offer changing the key can't be applied to the running session
+    assert_eq!(
+        sdes.set_remote(
+            &sdes_remote_description(&[format!("1 AES_CM_128_HMAC_SHA1_80 inline:{}", new_key)])?,
+            true,
+        ),
+        Err(Error::ErrSdesRekeyUnsupported)
+    );
+    assert_eq!(
+        sdes.keys().map(|(_, remote)| remote.to_vec()),
+        Some(vec![0x42u8; 30])
+    );
+
+    Ok(())
+}
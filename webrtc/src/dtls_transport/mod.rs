@@ -18,6 +18,7 @@ use tokio::sync::{mpsc, Mutex};
 use util::Conn;
 
 use dtls_role::*;
+use sdes::Sdes;
 
 use crate::api::setting_engine::SettingEngine;
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
@@ -39,6 +40,7 @@ pub mod dtls_fingerprint;
 pub mod dtls_parameters;
 pub mod dtls_role;
 pub mod dtls_transport_state;
+mod sdes;
 
 pub(crate) fn default_srtp_protection_profiles() -> Vec<SrtpProtectionProfile> {
     vec![
@@ -47,6 +49,30 @@ pub(crate) fn default_srtp_protection_profiles() -> Vec<SrtpProtectionProfile> {
     ]
 }
 
+/// protection_profile_from_dtls returns the SRTP profile of a DTLS-SRTP profile, None
+/// if it isn't supported
+pub(crate) fn protection_profile_from_dtls(
+    srtp_profile: SrtpProtectionProfile,
+) -> Option<ProtectionProfile> {
+    match srtp_profile {
+        SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm => Some(ProtectionProfile::AeadAes128Gcm),
+        SrtpProtectionProfile::Srtp_Aead_Aes_256_Gcm => Some(ProtectionProfile::AeadAes256Gcm),
+        SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80 => {
+            Some(ProtectionProfile::Aes128CmHmacSha1_80)
+        }
+        SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32 => {
+            Some(ProtectionProfile::Aes128CmHmacSha1_32)
+        }
+        SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_80 => {
+            Some(ProtectionProfile::Aes256CmHmacSha1_80)
+        }
+        SrtpProtectionProfile::Srtp_Aes256_Cm_Hmac_Sha1_32 => {
+            Some(ProtectionProfile::Aes256CmHmacSha1_32)
+        }
+        _ => None,
+    }
+}
+
 pub type OnDTLSTransportStateChangeHdlrFn = Box<
     dyn (FnMut(RTCDtlsTransportState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
         + Send
@@ -71,6 +97,7 @@ pub struct RTCDtlsTransport {
     pub(crate) conn: Mutex<Option<Arc<DTLSConn>>>,
 
     pub(crate) srtp_session: Mutex<Option<Arc<Session>>>,
+    pub(crate) srtp_cryptex: AtomicBool,
     pub(crate) srtcp_session: Mutex<Option<Arc<Session>>>,
     pub(crate) srtp_endpoint: Mutex<Option<Arc<Endpoint>>>,
     pub(crate) srtcp_endpoint: Mutex<Option<Arc<Endpoint>>>,
@@ -82,6 +109,8 @@ pub struct RTCDtlsTransport {
     pub(crate) srtp_ready_rx: Mutex<Option<mpsc::Receiver<()>>>,
 
     pub(crate) dtls_matcher: Option<MatchFunc>,
+
+    pub(crate) sdes: Mutex<Sdes>,
 }
 
 impl RTCDtlsTransport {
@@ -91,6 +120,17 @@ impl RTCDtlsTransport {
         setting_engine: Arc<SettingEngine>,
     ) -> Self {
         let (srtp_ready_tx, srtp_ready_rx) = mpsc::channel(1);
+        let sdes = if setting_engine.sdes {
+            Sdes::new(
+                &setting_engine
+                    .get_srtp_protection_profiles()
+                    .into_iter()
+                    .filter_map(protection_profile_from_dtls)
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            Sdes::default()
+        };
         RTCDtlsTransport {
             ice_transport,
             certificates,
@@ -100,6 +140,7 @@ impl RTCDtlsTransport {
             srtp_ready_rx: Mutex::new(Some(srtp_ready_rx)),
             state: AtomicU8::new(RTCDtlsTransportState::New as u8),
             dtls_matcher: Some(Box::new(match_dtls)),
+            sdes: Mutex::new(sdes),
             ..Default::default()
         }
     }
@@ -191,28 +232,23 @@ impl RTCDtlsTransport {
             srtp_config.remote_rtp_options = Some(srtp::option::srtp_no_replay_protection());
         }
 
-        if let Some(conn) = self.conn().await {
-            let conn_state = conn.connection_state().await;
-            srtp_config
-                .extract_session_keys_from_dtls(conn_state, self.role().await == DTLSRole::Client)
-                .await?;
-        } else {
-            return Err(Error::ErrDtlsTransportNotStarted);
-        }
+        self.extract_session_keys(&mut srtp_config).await?;
 
         {
             let mut srtp_session = self.srtp_session.lock().await;
             *srtp_session = {
                 let se = self.srtp_endpoint.lock().await;
                 if let Some(srtp_endpoint) = &*se {
-                    Some(Arc::new(
-                        Session::new(
-                            Arc::clone(srtp_endpoint) as Arc<dyn Conn + Send + Sync>,
-                            srtp_config,
-                            true,
-                        )
-                        .await?,
-                    ))
+                    let session = Session::new(
+                        Arc::clone(srtp_endpoint) as Arc<dyn Conn + Send + Sync>,
+                        srtp_config,
+                        true,
+                    )
+                    .await?;
+                    session
+                        .set_cryptex(self.srtp_cryptex.load(Ordering::SeqCst))
+                        .await;
+                    Some(Arc::new(session))
                 } else {
                     None
                 }
@@ -231,14 +267,7 @@ impl RTCDtlsTransport {
             srtcp_config.remote_rtcp_options = Some(srtp::option::srtcp_no_replay_protection());
         }
 
-        if let Some(conn) = self.conn().await {
-            let conn_state = conn.connection_state().await;
-            srtcp_config
-                .extract_session_keys_from_dtls(conn_state, self.role().await == DTLSRole::Client)
-                .await?;
-        } else {
-            return Err(Error::ErrDtlsTransportNotStarted);
-        }
+        self.extract_session_keys(&mut srtcp_config).await?;
 
         {
             let mut srtcp_session = self.srtcp_session.lock().await;
@@ -270,6 +299,56 @@ impl RTCDtlsTransport {
         Ok(())
     }
 
+    /// extract_session_keys sets the keys of a SRTP config, from the crypto
+    /// attributes with SDES or else from the DTLS connection
+    async fn extract_session_keys(&self, config: &mut srtp::config::Config) -> Result<()> {
+        if self.setting_engine.sdes {
+            let sdes = self.sdes.lock().await;
+            if let Some((local_key_salt, remote_key_salt)) = sdes.keys() {
+                config.extract_session_keys_from_sdes(local_key_salt, remote_key_salt)?;
+                Ok(())
+            } else {
+                Err(Error::ErrNoMatchingSdesCrypto)
+            }
+        } else if let Some(conn) = self.conn().await {
+            let conn_state = conn.connection_state().await;
+            config
+                .extract_session_keys_from_dtls(conn_state, self.role().await == DTLSRole::Client)
+                .await?;
+            Ok(())
+        } else {
+            Err(Error::ErrDtlsTransportNotStarted)
+        }
+    }
+
+    /// set_sdes_remote_description selects the SRTP keys of the crypto
+    /// attributes of the remote description
+    pub(crate) async fn set_sdes_remote_description(
+        &self,
+        desc: &sdp::description::session::SessionDescription,
+        remote_is_offer: bool,
+    ) -> Result<()> {
+        let mut sdes = self.sdes.lock().await;
+        sdes.set_remote(desc, remote_is_offer)
+    }
+
+    /// sdes_crypto_attributes returns the crypto attributes to add to the
+    /// media sections of the local description
+    pub(crate) async fn sdes_crypto_attributes(&self) -> Vec<sdp::crypto::Crypto> {
+        let sdes = self.sdes.lock().await;
+        sdes.crypto_attributes()
+    }
+
+    /// set_srtp_cryptex sets whether the RTP packets are sent with cryptex, as
+    /// negotiated with the remote description
+    pub(crate) async fn set_srtp_cryptex(&self, cryptex: bool) {
+        let srtp_session = self.srtp_session.lock().await;
+        self.srtp_cryptex.store(cryptex, Ordering::SeqCst);
+        if let Some(srtp_session) = &*srtp_session {
+            srtp_session.set_cryptex(cryptex).await;
+        }
+    }
+
     pub(crate) async fn get_srtp_session(&self) -> Option<Arc<Session>> {
         let srtp_session = self.srtp_session.lock().await;
         srtp_session.clone()
@@ -346,15 +425,7 @@ impl RTCDtlsTransport {
             self.role().await,
             dtls::config::Config {
                 certificates: vec![certificate],
-                srtp_protection_profiles: if !self
-                    .setting_engine
-                    .srtp_protection_profiles
-                    .is_empty()
-                {
-                    self.setting_engine.srtp_protection_profiles.clone()
-                } else {
-                    default_srtp_protection_profiles()
-                },
+                srtp_protection_profiles: self.setting_engine.get_srtp_protection_profiles(),
                 client_auth: ClientAuthType::RequireAnyClientCert,
                 insecure_skip_verify: true,
                 ..Default::default()
@@ -364,6 +435,10 @@ impl RTCDtlsTransport {
 
     /// start DTLS transport negotiation with the parameters of the remote DTLS transport
     pub async fn start(&self, remote_parameters: DTLSParameters) -> Result<()> {
+        if self.setting_engine.sdes {
+            return self.start_sdes(remote_parameters).await;
+        }
+
         let dtls_conn_result = if let Some(dtls_endpoint) =
             self.ice_transport.new_endpoint(Box::new(match_dtls)).await
         {
@@ -408,14 +483,9 @@ impl RTCDtlsTransport {
         let srtp_profile = dtls_conn.selected_srtpprotection_profile();
         {
             let mut srtp_protection_profile = self.srtp_protection_profile.lock().await;
-            *srtp_protection_profile = match srtp_profile {
-                dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm => {
-                    srtp::protection_profile::ProtectionProfile::AeadAes128Gcm
-                }
-                dtls::extension::extension_use_srtp::SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_80 => {
-                    srtp::protection_profile::ProtectionProfile::Aes128CmHmacSha1_80
-                }
-                _ => {
+            *srtp_protection_profile = match protection_profile_from_dtls(srtp_profile) {
+                Some(profile) => profile,
+                None => {
                     if let Err(err) = dtls_conn.close().await {
                         log::error!("{}", err);
                     }
@@ -465,6 +535,27 @@ impl RTCDtlsTransport {
         self.start_srtp().await
     }
 
+    /// start_sdes starts SRTP with the keys of the crypto attributes, there is
+    /// no DTLS handshake
+    async fn start_sdes(&self, remote_parameters: DTLSParameters) -> Result<()> {
+        self.prepare_transport(remote_parameters).await?;
+
+        let profile = {
+            let sdes = self.sdes.lock().await;
+            sdes.profile()
+        };
+        if let Some(profile) = profile {
+            let mut srtp_protection_profile = self.srtp_protection_profile.lock().await;
+            *srtp_protection_profile = profile;
+        } else {
+            self.state_change(RTCDtlsTransportState::Failed).await;
+            return Err(Error::ErrNoMatchingSdesCrypto);
+        }
+        self.state_change(RTCDtlsTransportState::Connected).await;
+
+        self.start_srtp().await
+    }
+
     /// stops and closes the DTLSTransport object.
     pub async fn stop(&self) -> Result<()> {
         // Try closing everything and collect the errors
@@ -0,0 +1,178 @@
+use std::io::Cursor;
+
+use rand::{thread_rng, Rng};
+use sdp::crypto::{Crypto, KeyParam, KEY_METHOD_INLINE};
+use sdp::description::session::{SessionDescription, ATTR_KEY_CRYPTO};
+use srtp::protection_profile::ProtectionProfile;
+
+use crate::error::{Error, Result};
+
+/// SdesSelection is the crypto attribute negotiated with the remote peer
+struct SdesSelection {
+    tag: u32,
+    profile: ProtectionProfile,
+    local_key_salt: Vec<u8>,
+    remote_key_salt: Vec<u8>,
+}
+
+/// Sdes keeps the SRTP keys exchanged in the crypto attributes of the SDP
+/// when the media runs without DTLS
+/// <https://tools.ietf.org/html/rfc4568>
+#[derive(Default)]
+pub(crate) struct Sdes {
+    /// master key and salt of each supported profile, the tag of a profile
+    /// in an offer is its index plus one
+    local_keys: Vec<(ProtectionProfile, Vec<u8>)>,
+    selection: Option<SdesSelection>,
+}
+
+impl Sdes {
+    pub(crate) fn new(profiles: &[ProtectionProfile]) -> Self {
+        let mut rng = thread_rng();
+        let local_keys = profiles
+            .iter()
+            .map(|profile| {
+                let mut key_salt = vec![0u8; profile.key_len() + profile.salt_len()];
+                rng.fill(key_salt.as_mut_slice());
+                (*profile, key_salt)
+            })
+            .collect();
+
+        Sdes {
+            local_keys,
+            selection: None,
+        }
+    }
+
+    /// crypto_attributes returns the negotiated crypto attribute, or all the
+    /// supported ones before the remote peer picked one of them
+    pub(crate) fn crypto_attributes(&self) -> Vec<Crypto> {
+        if let Some(selection) = &self.selection {
+            vec![Self::crypto(
+                selection.tag,
+                selection.profile,
+                &selection.local_key_salt,
+            )]
+        } else {
+            self.local_keys
+                .iter()
+                .enumerate()
+                .map(|(i, (profile, key_salt))| Self::crypto(i as u32 + 1, *profile, key_salt))
+                .collect()
+        }
+    }
+
+    fn crypto(tag: u32, profile: ProtectionProfile, key_salt: &[u8]) -> Crypto {
+        Crypto {
+            tag,
+            suite: profile.crypto_suite().to_owned(),
+            key_params: vec![KeyParam::new_inline(base64::encode(key_salt))],
+            session_params: vec![],
+        }
+    }
+
+    /// set_remote selects a crypto attribute of the remote description. From an
+    /// offer the first supported one is picked, an answer must carry one of
+    /// the tags we offered. Once selected, later descriptions must keep the
+    /// remote key, the SRTP session can't be rekeyed.
+    pub(crate) fn set_remote(
+        &mut self,
+        desc: &SessionDescription,
+        remote_is_offer: bool,
+    ) -> Result<()> {
+        let mut cryptos = vec![];
+        for media in &desc.media_descriptions {
+            for a in &media.attributes {
+                if a.key != ATTR_KEY_CRYPTO {
+                    continue;
+                }
+
+                let line = format!("{}:{}", a.key, a.value.as_deref().unwrap_or_default());
+                match Crypto::unmarshal(&mut Cursor::new(line)) {
+                    Ok(crypto) => cryptos.push(crypto),
+                    Err(err) => log::warn!("failed to parse crypto attribute: {}", err),
+                }
+            }
+        }
+
+        if let Some(selection) = &self.selection {
+            // The remote peer keeps the session if it still offers or answers
+            // the selected crypto-suite with the same key
+            let unchanged = cryptos.iter().any(|crypto| {
+                matches!(
+                    self.select(crypto, remote_is_offer),
+                    Some(s) if s.profile as u8 == selection.profile as u8
+                        && s.remote_key_salt == selection.remote_key_salt
+                )
+            });
+            return if unchanged {
+                Ok(())
+            } else {
+                Err(Error::ErrSdesRekeyUnsupported)
+            };
+        }
+
+        for crypto in &cryptos {
+            if let Some(selection) = self.select(crypto, remote_is_offer) {
+                self.selection = Some(selection);
+                return Ok(());
+            }
+        }
+
+        Err(Error::ErrNoMatchingSdesCrypto)
+    }
+
+    fn select(&self, crypto: &Crypto, remote_is_offer: bool) -> Option<SdesSelection> {
+        // None of the session parameters (UNENCRYPTED_SRTP, KDR=, WSH=, ...)
+        // are supported, a crypto attribute with any of them must be rejected
+        // https://www.rfc-editor.org/rfc/rfc4568#section-6.3
+        if !crypto.session_params.is_empty() {
+            return None;
+        }
+
+        let profile = ProtectionProfile::from_crypto_suite(&crypto.suite).ok()?;
+        let index = if remote_is_offer {
+            self.local_keys
+                .iter()
+                .position(|(p, _)| *p as u8 == profile as u8)?
+        } else {
+            let index = (crypto.tag as usize).checked_sub(1)?;
+            let (p, _) = self.local_keys.get(index)?;
+            if *p as u8 != profile as u8 {
+                return None;
+            }
+            index
+        };
+
+        // Only a single key without MKI is supported
+        let key_param = match crypto.key_params.as_slice() {
+            [key_param] if key_param.key_method == KEY_METHOD_INLINE && key_param.mki.is_none() => {
+                key_param
+            }
+            _ => return None,
+        };
+        let remote_key_salt = base64::decode(&key_param.key_salt).ok()?;
+        if remote_key_salt.len() != profile.key_len() + profile.salt_len() {
+            return None;
+        }
+
+        Some(SdesSelection {
+            tag: crypto.tag,
+            profile,
+            local_key_salt: self.local_keys[index].1.clone(),
+            remote_key_salt,
+        })
+    }
+
+    /// profile returns the negotiated protection profile
+    pub(crate) fn profile(&self) -> Option<ProtectionProfile> {
+        self.selection.as_ref().map(|s| s.profile)
+    }
+
+    /// keys returns the local and remote master key and salt
+    pub(crate) fn keys(&self) -> Option<(&[u8], &[u8])> {
+        self.selection
+            .as_ref()
+            .map(|s| (s.local_key_salt.as_slice(), s.remote_key_salt.as_slice()))
+    }
+}
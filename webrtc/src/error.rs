@@ -176,6 +176,21 @@ pub enum Error {
     #[error("DTLS Handshake completed and no SRTP Protection Profile was chosen")]
     ErrNoSRTPProtectionProfile,
 
+    /// ErrNoMatchingSdesCrypto indicates that SDES is enabled and the remote description has no
+    /// crypto attribute with a supported crypto-suite and key
+    #[error("no crypto attribute with a supported crypto-suite")]
+    ErrNoMatchingSdesCrypto,
+
+    /// ErrSdesRekeyUnsupported indicates that SDES is enabled and a later remote description
+    /// changed the SRTP keys, which requires a new SRTP session
+    #[error("changing the SRTP keys of a crypto attribute is not supported")]
+    ErrSdesRekeyUnsupported,
+
+    /// ErrSdesDataChannel indicates that a data channel was requested while SDES is enabled,
+    /// data channels need DTLS
+    #[error("data channels are not available with SDES")]
+    ErrSdesDataChannel,
+
     /// ErrFailedToGenerateCertificateFingerprint indicates that we failed to generate the fingerprint used for comparing certificates
     #[error("failed to generate certificate fingerprint")]
     ErrFailedToGenerateCertificateFingerprint,
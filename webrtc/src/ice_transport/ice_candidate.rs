@@ -7,6 +7,7 @@ use ice::candidate::candidate_peer_reflexive::CandidatePeerReflexiveConfig;
 use ice::candidate::candidate_relay::CandidateRelayConfig;
 use ice::candidate::candidate_server_reflexive::CandidateServerReflexiveConfig;
 use ice::candidate::Candidate;
+use ice::tcp_type::TcpType;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
@@ -74,12 +75,11 @@ impl RTCIceCandidate {
                         address: self.address.clone(),
                         port: self.port,
                         component: self.component,
-                        //tcp_type: ice.NewTCPType(c.TCPType),
                         foundation: self.foundation.clone(),
                         priority: self.priority,
                         ..Default::default()
                     },
-                    ..Default::default()
+                    tcp_type: TcpType::from(self.tcp_type.as_str()),
                 };
                 config.new_candidate_host().await?
             }
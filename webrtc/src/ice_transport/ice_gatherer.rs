@@ -135,7 +135,7 @@ impl RTCIceGatherer {
                 .clone(),
             local_ufrag: self.setting_engine.candidates.username_fragment.clone(),
             local_pwd: self.setting_engine.candidates.password.clone(),
-            //TODO: TCPMux:                 self.setting_engine.iceTCPMux,
+            tcp_mux: self.setting_engine.ice_tcp_mux.clone(),
             //TODO: ProxyDialer:            self.setting_engine.iceProxyDialer,
             ..Default::default()
         };
@@ -1362,6 +1362,21 @@ impl RTCPeerConnection {
                 .media_engine
                 .update_from_remote_description(parsed)
                 .await?;
+            self.internal
+                .dtls_transport
+                .set_srtp_cryptex(
+                    self.internal
+                        .media_engine
+                        .negotiated_cryptex
+                        .load(Ordering::SeqCst),
+                )
+                .await;
+            if self.internal.setting_engine.sdes {
+                self.internal
+                    .dtls_transport
+                    .set_sdes_remote_description(parsed, desc.sdp_type == RTCSdpType::Offer)
+                    .await?;
+            }
 
             let mut local_transceivers = self.get_transceivers().await;
             let remote_description = self.remote_description().await;
@@ -1557,7 +1572,12 @@ impl RTCPeerConnection {
                 }
             }
 
-            let (fingerprint, fingerprint_hash) = extract_fingerprint(parsed)?;
+            // With SDES there is no DTLS and so no fingerprint
+            let (fingerprint, fingerprint_hash) = if self.internal.setting_engine.sdes {
+                (String::new(), String::new())
+            } else {
+                extract_fingerprint(parsed)?
+            };
 
             // If one of the agents is lite and the other one is not, the lite agent must be the controlling agent.
             // If both or neither agents are lite the offering agent is controlling.
@@ -1849,6 +1869,10 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if self.internal.setting_engine.sdes {
+            return Err(Error::ErrSdesDataChannel);
+        }
+
         let mut params = DataChannelParameters {
             label: label.to_owned(),
             ordered: true,
@@ -9,6 +9,7 @@ use crate::stats::{
 };
 use crate::track::TrackStream;
 use crate::{SDES_REPAIR_RTP_STREAM_ID_URI, SDP_ATTRIBUTE_RID};
+use ::sdp::crypto::Crypto;
 use std::sync::atomic::AtomicIsize;
 use std::sync::Weak;
 
@@ -223,7 +224,8 @@ impl PeerConnectionInternal {
         self.start_rtp_receivers(&mut track_details, &current_transceivers, sdp_semantics)
             .await?;
         if let Some(parsed) = &remote_desc.parsed {
-            if have_application_media_section(parsed) {
+            // Data channels need DTLS, they aren't available with SDES
+            if have_application_media_section(parsed) && !self.setting_engine.sdes {
                 self.start_sctp().await;
             }
         }
@@ -794,11 +796,7 @@ impl PeerConnectionInternal {
             }
         }
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()?
-        } else {
-            return Err(Error::ErrNonCertificate);
-        };
+        let (dtls_fingerprints, crypto) = self.sdp_security_params().await?;
 
         let params = PopulateSdpParams {
             is_plan_b,
@@ -806,6 +804,8 @@ impl PeerConnectionInternal {
             is_icelite: self.setting_engine.candidates.ice_lite,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: self.ice_gathering_state(),
+            crypto,
+            cryptex: self.media_engine.cryptex,
         };
         populate_sdp(
             d,
@@ -985,11 +985,7 @@ impl PeerConnectionInternal {
             log::info!("Plan-B Offer detected; responding with Plan-B Answer");
         }
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()?
-        } else {
-            return Err(Error::ErrNonCertificate);
-        };
+        let (dtls_fingerprints, crypto) = self.sdp_security_params().await?;
 
         let params = PopulateSdpParams {
             is_plan_b: detected_plan_b,
@@ -997,6 +993,13 @@ impl PeerConnectionInternal {
             is_icelite: self.setting_engine.candidates.ice_lite,
             connection_role,
             ice_gathering_state: self.ice_gathering_state(),
+            crypto,
+            // An answer only accepts cryptex if the offer has it
+            cryptex: if include_unmatched {
+                self.media_engine.cryptex
+            } else {
+                self.media_engine.negotiated_cryptex.load(Ordering::SeqCst)
+            },
         };
         populate_sdp(
             d,
@@ -1010,6 +1013,20 @@ impl PeerConnectionInternal {
         .await
     }
 
+    /// sdp_security_params returns the DTLS fingerprints, or the SDES crypto
+    /// attributes when the media runs without DTLS
+    async fn sdp_security_params(&self) -> Result<(Vec<RTCDtlsFingerprint>, Vec<Crypto>)> {
+        if self.setting_engine.sdes {
+            return Ok((vec![], self.dtls_transport.sdes_crypto_attributes().await));
+        }
+
+        if let Some(cert) = self.dtls_transport.certificates.first() {
+            Ok((cert.get_fingerprints()?, vec![]))
+        } else {
+            Err(Error::ErrNonCertificate)
+        }
+    }
+
     pub(super) fn ice_gathering_state(&self) -> RTCIceGatheringState {
         match self.ice_gatherer.state() {
             RTCIceGathererState::New => RTCIceGatheringState::New,
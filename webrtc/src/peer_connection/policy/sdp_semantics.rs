@@ -74,7 +74,9 @@ mod test {
     use crate::track::track_local::TrackLocal;
 
     use sdp::description::media::MediaDescription;
-    use sdp::description::session::{SessionDescription, ATTR_KEY_SSRC};
+    use sdp::description::session::{
+        SessionDescription, ATTR_KEY_SSRC, ATTR_KEY_SSRCGROUP, SEMANTIC_TOKEN_FLOW_IDENTIFICATION,
+    };
     use std::collections::HashSet;
     use std::sync::Arc;
 
@@ -104,14 +106,29 @@ mod test {
             .collect()
     }
 
+    /// extract_ssrc_list returns the media SSRCs, RTX repair flows are left out
     fn extract_ssrc_list(md: &MediaDescription) -> Vec<String> {
+        let mut repair_ssrcs = HashSet::new();
+        for attr in &md.attributes {
+            if attr.key == ATTR_KEY_SSRCGROUP {
+                if let Some(value) = &attr.value {
+                    let fields: Vec<&str> = value.split_whitespace().collect();
+                    if fields.len() == 3 && fields[0] == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
+                        repair_ssrcs.insert(fields[2]);
+                    }
+                }
+            }
+        }
+
         let mut ssrcs = HashSet::new();
         for attr in &md.attributes {
             if attr.key == ATTR_KEY_SSRC {
                 if let Some(value) = &attr.value {
                     let fields: Vec<&str> = value.split_whitespace().collect();
                     if let Some(ssrc) = fields.first() {
-                        ssrcs.insert(*ssrc);
+                        if !repair_ssrcs.contains(ssrc) {
+                            ssrcs.insert(*ssrc);
+                        }
                     }
                 }
             }
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod sdp_test;
 
-use crate::api::media_engine::MediaEngine;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_FLEXFEC03, MIME_TYPE_RTX};
 use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_candidate::RTCIceCandidate;
@@ -22,6 +22,7 @@ use crate::peer_connection::MEDIA_SECTION_APPLICATION;
 use crate::SDP_ATTRIBUTE_RID;
 use ice::candidate::candidate_base::unmarshal_candidate;
 use ice::candidate::Candidate;
+use sdp::crypto::Crypto;
 use sdp::description::common::{Address, ConnectionInformation};
 use sdp::description::media::{MediaDescription, MediaName, RangedPort};
 use sdp::description::session::*;
@@ -43,6 +44,7 @@ pub(crate) struct TrackDetails {
     pub(crate) id: String,
     pub(crate) ssrcs: Vec<SSRC>,
     pub(crate) repair_ssrc: SSRC,
+    pub(crate) fec_ssrc: SSRC,
     pub(crate) rids: Vec<String>,
 }
 
@@ -74,6 +76,7 @@ pub(crate) fn track_details_from_sdp(
     for media in &s.media_descriptions {
         let mut tracks_in_media_section = vec![];
         let mut rtx_repair_flows = HashMap::new();
+        let mut fec_repair_flows = HashMap::new();
 
         // Plan B can have multiple tracks in a signle media section
         let mut stream_id = "";
@@ -128,6 +131,36 @@ pub(crate) fn track_details_from_sdp(
                                     rtx_repair_flow as SSRC,
                                 );
                             }
+                        } else if split[0] == SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION_FRAMEWORK {
+                            // Lines like `a=ssrc-group:FEC-FR 2231627014 1405937622` declare that the second SSRC
+                            // carries FlexFEC repair packets protecting the first as specified in RFC5956
+                            if split.len() == 3 {
+                                let base_ssrc = match split[1].parse::<u32>() {
+                                    Ok(ssrc) => ssrc,
+                                    Err(err) => {
+                                        log::warn!("Failed to parse SSRC: {}", err);
+                                        continue;
+                                    }
+                                };
+                                let fec_repair_flow = match split[2].parse::<u32>() {
+                                    Ok(n) => n,
+                                    Err(err) => {
+                                        log::warn!("Failed to parse SSRC: {}", err);
+                                        continue;
+                                    }
+                                };
+                                fec_repair_flows.insert(fec_repair_flow, base_ssrc);
+                                // Remove if the FEC flow was added as track before
+                                filter_track_with_ssrc(
+                                    &mut tracks_in_media_section,
+                                    fec_repair_flow as SSRC,
+                                );
+                                for t in &mut tracks_in_media_section {
+                                    if t.ssrcs.contains(&base_ssrc) {
+                                        t.fec_ssrc = fec_repair_flow;
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -162,6 +195,9 @@ pub(crate) fn track_details_from_sdp(
                         if rtx_repair_flows.contains_key(&ssrc) {
                             continue; // This ssrc is a RTX repair flow, ignore
                         }
+                        if fec_repair_flows.contains_key(&ssrc) {
+                            continue; // This ssrc is a FEC repair flow, ignore
+                        }
 
                         if split.len() == 3 && split[1].starts_with("msid:") {
                             stream_id = &split[1]["msid:".len()..];
@@ -185,6 +221,13 @@ pub(crate) fn track_details_from_sdp(
                             }
                         }
 
+                        let mut fec_ssrc = 0;
+                        for (repair, base) in &fec_repair_flows {
+                            if *base == ssrc {
+                                fec_ssrc = *repair;
+                            }
+                        }
+
                         if track_idx < tracks_in_media_section.len() {
                             tracks_in_media_section[track_idx].mid = mid_value.to_owned();
                             tracks_in_media_section[track_idx].kind = codec_type;
@@ -192,6 +235,7 @@ pub(crate) fn track_details_from_sdp(
                             tracks_in_media_section[track_idx].id = track_id.to_owned();
                             tracks_in_media_section[track_idx].ssrcs = vec![ssrc];
                             tracks_in_media_section[track_idx].repair_ssrc = repair_ssrc;
+                            tracks_in_media_section[track_idx].fec_ssrc = fec_ssrc;
                         } else {
                             let track_details = TrackDetails {
                                 mid: mid_value.to_owned(),
@@ -200,6 +244,7 @@ pub(crate) fn track_details_from_sdp(
                                 id: track_id.to_owned(),
                                 ssrcs: vec![ssrc],
                                 repair_ssrc,
+                                fec_ssrc,
                                 ..Default::default()
                             };
                             tracks_in_media_section.push(track_details);
@@ -401,6 +446,8 @@ pub(crate) struct AddTransceiverSdpParams {
     dtls_role: ConnectionRole,
     ice_gathering_state: RTCIceGatheringState,
     offered_direction: Option<RTCRtpTransceiverDirection>,
+    crypto: Vec<Crypto>,
+    cryptex: bool,
 }
 
 pub(crate) async fn add_transceiver_sdp(
@@ -423,6 +470,16 @@ pub(crate) async fn add_transceiver_sdp(
         params.ice_gathering_state,
     );
 
+    // With SDES the keys are in the crypto attributes, the media isn't over DTLS
+    let protos: Vec<String> = if params.crypto.is_empty() {
+        vec!["UDP", "TLS", "RTP", "SAVPF"]
+    } else {
+        vec!["RTP", "SAVPF"]
+    }
+    .into_iter()
+    .map(|p| p.to_owned())
+    .collect();
+
     let transceivers = &media_section.transceivers;
     // Use the first transceiver to generate the section attributes
     let t = &transceivers[0];
@@ -435,6 +492,7 @@ pub(crate) async fn add_transceiver_sdp(
         )
         .with_property_attribute(ATTR_KEY_RTCPMUX.to_owned())
         .with_property_attribute(ATTR_KEY_RTCPRSIZE.to_owned());
+    media.media_name.protos = protos.clone();
 
     let codecs = t.get_codecs().await;
     for codec in &codecs {
@@ -476,12 +534,7 @@ pub(crate) async fn add_transceiver_sdp(
                     value: 0,
                     range: None,
                 },
-                protos: vec![
-                    "UDP".to_owned(),
-                    "TLS".to_owned(),
-                    "RTP".to_owned(),
-                    "SAVPF".to_owned(),
-                ],
+                protos,
                 formats: vec!["0".to_owned()],
             },
             media_title: None,
@@ -517,6 +570,11 @@ pub(crate) async fn add_transceiver_sdp(
         });
     }
 
+    // Cryptex covers the CSRCs and all the header extensions of the section
+    if params.cryptex {
+        media = media.with_property_attribute(ATTR_KEY_CRYPTEX.to_owned());
+    }
+
     if !media_section.rid_map.is_empty() {
         let mut recv_rids: Vec<String> = vec![];
 
@@ -532,15 +590,61 @@ pub(crate) async fn add_transceiver_sdp(
         );
     }
 
+    let flexfec = codecs.iter().any(|c| {
+        c.capability
+            .mime_type
+            .eq_ignore_ascii_case(MIME_TYPE_FLEXFEC03)
+    });
+
+    let rtx = codecs
+        .iter()
+        .any(|c| c.capability.mime_type.eq_ignore_ascii_case(MIME_TYPE_RTX));
+
     for mt in transceivers {
         if let Some(sender) = mt.sender().await {
             if let Some(track) = sender.track().await {
+                if rtx {
+                    media = media.with_value_attribute(
+                        ATTR_KEY_SSRCGROUP.to_owned(),
+                        format!(
+                            "{} {} {}",
+                            SEMANTIC_TOKEN_FLOW_IDENTIFICATION, sender.ssrc, sender.rtx_ssrc
+                        ),
+                    );
+                }
+                if flexfec {
+                    media = media.with_value_attribute(
+                        ATTR_KEY_SSRCGROUP.to_owned(),
+                        format!(
+                            "{} {} {}",
+                            SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION_FRAMEWORK,
+                            sender.ssrc,
+                            sender.fec_ssrc
+                        ),
+                    );
+                }
                 media = media.with_media_source(
                     sender.ssrc,
                     track.stream_id().to_owned(), /* cname */
                     track.stream_id().to_owned(), /* streamLabel */
                     track.id().to_owned(),
                 );
+                if rtx {
+                    media = media.with_media_source(
+                        sender.rtx_ssrc,
+                        track.stream_id().to_owned(), /* cname */
+                        track.stream_id().to_owned(), /* streamLabel */
+                        track.id().to_owned(),
+                    );
+                }
+                if flexfec {
+                    media = media.with_media_source(
+                        sender.fec_ssrc,
+                        track.stream_id().to_owned(), /* cname */
+                        track.stream_id().to_owned(), /* streamLabel */
+                        track.id().to_owned(),
+                    );
+                }
 
                 // Send msid based on the configured track if we haven't already
                 // sent on this sender. If we have sent we must keep the msid line consistent, this
@@ -623,6 +727,10 @@ pub(crate) async fn add_transceiver_sdp(
     };
     media = media.with_property_attribute(direction.to_string());
 
+    for crypto in params.crypto {
+        media = media.with_crypto(crypto);
+    }
+
     for fingerprint in dtls_fingerprints {
         media = media.with_fingerprint(
             fingerprint.algorithm.to_owned(),
@@ -653,6 +761,9 @@ pub(crate) struct PopulateSdpParams {
     pub(crate) is_icelite: bool,
     pub(crate) connection_role: ConnectionRole,
     pub(crate) ice_gathering_state: RTCIceGatheringState,
+    pub(crate) crypto: Vec<Crypto>,
+    /// cryptex adds a=cryptex to the RTP media sections
+    pub(crate) cryptex: bool,
 }
 
 /// populate_sdp serializes a PeerConnections state into an SDP
@@ -705,6 +816,8 @@ pub(crate) async fn populate_sdp(
                 dtls_role: params.connection_role,
                 ice_gathering_state: params.ice_gathering_state,
                 offered_direction: m.offered_direction,
+                crypto: params.crypto.clone(),
+                cryptex: params.cryptex,
             };
             let (d1, should_add_id) = add_transceiver_sdp(
                 d,
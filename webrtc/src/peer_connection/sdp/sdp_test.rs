@@ -476,6 +476,48 @@ fn test_track_details_from_sdp() -> Result<()> {
         );
     }
 
+    //"Video with FlexFEC"
+    {
+        let s = SessionDescription {
+            media_descriptions: vec![MediaDescription {
+                media_name: MediaName {
+                    media: "video".to_owned(),
+                    ..Default::default()
+                },
+                attributes: vec![
+                    Attribute {
+                        key: "mid".to_owned(),
+                        value: Some("0".to_owned()),
+                    },
+                    Attribute {
+                        key: "sendonly".to_owned(),
+                        value: None,
+                    },
+                    Attribute {
+                        key: "ssrc".to_owned(),
+                        value: Some("8000 msid:video_trk_label video_trk_guid".to_owned()),
+                    },
+                    Attribute {
+                        key: "ssrc".to_owned(),
+                        value: Some("9000 msid:video_trk_label video_trk_guid".to_owned()),
+                    },
+                    Attribute {
+                        key: "ssrc-group".to_owned(),
+                        value: Some("FEC-FR 8000 9000".to_owned()),
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let tracks = track_details_from_sdp(&s, true);
+        assert_eq!(1, tracks.len());
+        assert_eq!(vec![8000], tracks[0].ssrcs);
+        assert_eq!(9000, tracks[0].fec_ssrc);
+        assert_eq!(0, tracks[0].repair_ssrc);
+    }
+
     Ok(())
 }
 
@@ -543,6 +585,8 @@ async fn fingerprint_test(
         is_icelite: false,
         connection_role: ConnectionRole::Active,
         ice_gathering_state: RTCIceGatheringState::New,
+        crypto: vec![],
+        cryptex: false,
     };
 
     let s = populate_sdp(
@@ -692,6 +736,8 @@ async fn test_populate_sdp() -> Result<()> {
             is_icelite: se.candidates.ice_lite,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
+            crypto: vec![],
+            cryptex: false,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -774,6 +820,8 @@ async fn test_populate_sdp() -> Result<()> {
             is_icelite: se.candidates.ice_lite,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
+            crypto: vec![],
+            cryptex: false,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -878,6 +926,8 @@ async fn test_populate_sdp_reject() -> Result<()> {
         is_icelite: se.candidates.ice_lite,
         connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
         ice_gathering_state: RTCIceGatheringState::Complete,
+        crypto: vec![],
+        cryptex: false,
     };
     let offer_sdp = populate_sdp(
         d,
@@ -88,6 +88,13 @@ pub struct RTCRtpRtxParameters {
     pub ssrc: SSRC,
 }
 
+/// RTPFecParameters dictionary contains information relating to forward error correction (FEC) settings.
+/// <https://draft.ortc.org/#dom-rtcrtpfecparameters>
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RTCRtpFecParameters {
+    pub ssrc: SSRC,
+}
+
 /// RTPCodingParameters provides information relating to both encoding and decoding.
 /// This is a subset of the RFC since Pion WebRTC doesn't implement encoding/decoding itself
 /// <http://draft.ortc.org/#dom-rtcrtpcodingparameters>
@@ -97,6 +104,7 @@ pub struct RTCRtpCodingParameters {
     pub ssrc: SSRC,
     pub payload_type: PayloadType,
     pub rtx: RTCRtpRtxParameters,
+    pub fec: RTCRtpFecParameters,
 }
 
 /// RTPDecodingParameters provides information relating to both encoding and decoding.
@@ -163,6 +171,11 @@ pub(crate) fn create_stream_info(
         channels: codec.channels,
         sdp_fmtp_line: codec.sdp_fmtp_line,
         rtcp_feedback: feedbacks,
+        ssrc_forward_error_correction: 0,
+        payload_type_forward_error_correction: 0,
+        payload_type_redundancy: 0,
+        ssrc_retransmission: 0,
+        payload_type_retransmission: 0,
     }
 }
 
@@ -72,12 +72,16 @@ impl RTCRtpCodecCapability {
         let mime_type = self.mime_type.to_lowercase();
         if mime_type == MIME_TYPE_H264.to_lowercase() {
             Ok(Box::new(rtp::codecs::h264::H264Payloader::default()))
+        } else if mime_type == MIME_TYPE_H265.to_lowercase() {
+            Ok(Box::new(rtp::codecs::h265::H265Payloader::default()))
         } else if mime_type == MIME_TYPE_VP8.to_lowercase() {
             let mut vp8_payloader = rtp::codecs::vp8::Vp8Payloader::default();
             vp8_payloader.enable_picture_id = true;
             Ok(Box::new(vp8_payloader))
         } else if mime_type == MIME_TYPE_VP9.to_lowercase() {
             Ok(Box::new(rtp::codecs::vp9::Vp9Payloader::default()))
+        } else if mime_type == MIME_TYPE_AV1.to_lowercase() {
+            Ok(Box::new(rtp::codecs::av1::Av1Payloader::default()))
         } else if mime_type == MIME_TYPE_OPUS.to_lowercase() {
             Ok(Box::new(rtp::codecs::opus::OpusPayloader::default()))
         } else if mime_type == MIME_TYPE_G722.to_lowercase()
@@ -170,3 +174,23 @@ pub(crate) fn codec_parameters_fuzzy_search(
 
     (RTCRtpCodecParameters::default(), CodecMatch::None)
 }
+
+/// Find the RTX codec (RFC 4588) associated with the given payload type, that is the
+/// codec with an apt fmtp parameter pointing to it
+pub(crate) fn codec_rtx_search(
+    needle: PayloadType,
+    haystack: &[RTCRtpCodecParameters],
+) -> Option<RTCRtpCodecParameters> {
+    haystack
+        .iter()
+        .find(|c| {
+            if !c.capability.mime_type.eq_ignore_ascii_case(MIME_TYPE_RTX) {
+                return false;
+            }
+            let fmtp = fmtp::parse(&c.capability.mime_type, &c.capability.sdp_fmtp_line);
+            fmtp.parameter("apt")
+                .and_then(|apt| apt.parse::<PayloadType>().ok())
+                == Some(needle)
+        })
+        .cloned()
+}
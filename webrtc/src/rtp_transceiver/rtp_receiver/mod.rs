@@ -1,17 +1,18 @@
 #[cfg(test)]
 mod rtp_receiver_test;
 
-use crate::api::media_engine::MediaEngine;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_FLEXFEC03, MIME_TYPE_RED, MIME_TYPE_RTX};
 use crate::dtls_transport::RTCDtlsTransport;
 use crate::error::{flatten_errs, Error, Result};
 use crate::peer_connection::sdp::TrackDetails;
+use crate::rtp_transceiver::fmtp;
 use crate::rtp_transceiver::rtp_codec::{
-    codec_parameters_fuzzy_search, CodecMatch, RTCRtpCodecCapability, RTCRtpCodecParameters,
-    RTCRtpParameters, RTPCodecType,
+    codec_parameters_fuzzy_search, codec_rtx_search, CodecMatch, RTCRtpCodecCapability,
+    RTCRtpCodecParameters, RTCRtpParameters, RTPCodecType,
 };
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::{
-    create_stream_info, RTCRtpDecodingParameters, RTCRtpReceiveParameters, SSRC,
+    create_stream_info, PayloadType, RTCRtpDecodingParameters, RTCRtpReceiveParameters, SSRC,
 };
 use crate::track::track_remote::TrackRemote;
 use crate::track::{TrackStream, TrackStreams};
@@ -19,10 +20,12 @@ use crate::track::{TrackStream, TrackStreams};
 use interceptor::stream_info::RTPHeaderExtension;
 use interceptor::{Attributes, Interceptor};
 use log::trace;
+use std::collections::HashMap;
 use std::fmt;
 
 use std::sync::Arc;
 use tokio::sync::{watch, Mutex, RwLock};
+use util::{Marshal, Unmarshal};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
@@ -522,17 +525,43 @@ impl RTCRtpReceiver {
         } else {
             RTCRtpCodecCapability::default()
         };
+        let fec_codec = global_params.codecs.iter().find(|c| {
+            c.capability
+                .mime_type
+                .eq_ignore_ascii_case(MIME_TYPE_FLEXFEC03)
+        });
+        let red_codec = global_params
+            .codecs
+            .iter()
+            .find(|c| c.capability.mime_type.eq_ignore_ascii_case(MIME_TYPE_RED));
+        let rtx_codec = global_params
+            .codecs
+            .first()
+            .and_then(|c| codec_rtx_search(c.payload_type, &global_params.codecs));
 
         for encoding in &parameters.encodings {
+            let fec_ssrc = if fec_codec.is_some() {
+                encoding.fec.ssrc
+            } else {
+                0
+            };
+
             let (stream_info, rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) =
                 if encoding.ssrc != 0 {
-                    let stream_info = create_stream_info(
+                    let mut stream_info = create_stream_info(
                         "".to_owned(),
                         encoding.ssrc,
                         0,
                         codec.clone(),
                         &global_params.header_extensions,
                     );
+                    if let (Some(fec_codec), true) = (fec_codec, fec_ssrc != 0) {
+                        stream_info.ssrc_forward_error_correction = fec_ssrc;
+                        stream_info.payload_type_forward_error_correction = fec_codec.payload_type;
+                    }
+                    if let Some(red_codec) = red_codec {
+                        stream_info.payload_type_redundancy = red_codec.payload_type;
+                    }
                     let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) =
                         self.transport
                             .streams_for_ssrc(encoding.ssrc, &stream_info, &interceptor)
@@ -574,6 +603,14 @@ impl RTCRtpReceiver {
                     rtcp_read_stream: None,
                     rtcp_interceptor: None,
                 },
+
+                fec_stream: TrackStream {
+                    stream_info: None,
+                    rtp_read_stream: None,
+                    rtp_interceptor: None,
+                    rtcp_read_stream: None,
+                    rtcp_interceptor: None,
+                },
             };
 
             {
@@ -583,13 +620,23 @@ impl RTCRtpReceiver {
 
             let rtx_ssrc = encoding.rtx.ssrc;
             if rtx_ssrc != 0 {
-                let stream_info = create_stream_info(
-                    "".to_owned(),
-                    rtx_ssrc,
-                    0,
-                    codec.clone(),
-                    &global_params.header_extensions,
-                );
+                let stream_info = if let Some(rtx_codec) = &rtx_codec {
+                    create_stream_info(
+                        "".to_owned(),
+                        rtx_ssrc,
+                        rtx_codec.payload_type,
+                        rtx_codec.capability.clone(),
+                        &global_params.header_extensions,
+                    )
+                } else {
+                    create_stream_info(
+                        "".to_owned(),
+                        rtx_ssrc,
+                        0,
+                        codec.clone(),
+                        &global_params.header_extensions,
+                    )
+                };
                 let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) = self
                     .transport
                     .streams_for_ssrc(rtx_ssrc, &stream_info, &interceptor)
@@ -608,6 +655,32 @@ impl RTCRtpReceiver {
                 )
                 .await?;
             }
+
+            if let (Some(fec_codec), true) = (fec_codec, fec_ssrc != 0) {
+                let stream_info = create_stream_info(
+                    "".to_owned(),
+                    fec_ssrc,
+                    fec_codec.payload_type,
+                    fec_codec.capability.clone(),
+                    &global_params.header_extensions,
+                );
+                let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) = self
+                    .transport
+                    .streams_for_ssrc(fec_ssrc, &stream_info, &interceptor)
+                    .await?;
+
+                self.receive_for_fec(
+                    encoding.ssrc,
+                    TrackStream {
+                        stream_info: Some(stream_info),
+                        rtp_read_stream,
+                        rtp_interceptor,
+                        rtcp_read_stream,
+                        rtcp_interceptor,
+                    },
+                )
+                .await?;
+            }
         }
 
         Ok(())
@@ -661,6 +734,7 @@ impl RTCRtpReceiver {
             }
 
             encoding.rtx.ssrc = incoming.repair_ssrc;
+            encoding.fec.ssrc = incoming.fec_ssrc;
         }
 
         if let Err(err) = self.receive(&RTCRtpReceiveParameters { encodings }).await {
@@ -715,6 +789,18 @@ impl RTCRtpReceiver {
                     }
                 }
 
+                if let Some(fec_rtcp_read_stream) = &t.fec_stream.rtcp_read_stream {
+                    if let Err(err) = fec_rtcp_read_stream.close().await {
+                        errs.push(err);
+                    }
+                }
+
+                if let Some(fec_rtp_read_stream) = &t.fec_stream.rtp_read_stream {
+                    if let Err(err) = fec_rtp_read_stream.close().await {
+                        errs.push(err);
+                    }
+                }
+
                 if let Some(stream_info) = &t.stream.stream_info {
                     self.internal
                         .interceptor
@@ -728,6 +814,13 @@ impl RTCRtpReceiver {
                         .unbind_remote_stream(repair_stream_info)
                         .await;
                 }
+
+                if let Some(fec_stream_info) = &t.fec_stream.stream_info {
+                    self.internal
+                        .interceptor
+                        .unbind_remote_stream(fec_stream_info)
+                        .await;
+                }
             }
         }
 
@@ -766,14 +859,17 @@ impl RTCRtpReceiver {
     }
 
     /// receiveForRtx starts a routine that processes the repair stream
-    /// These packets aren't exposed to the user yet, but we need to process them for
-    /// TWCC
+    /// The RTX packets (RFC 4588) are processed by the interceptors of the repair stream,
+    /// e.g. for TWCC, then unwrapped and queued on the stream of the track they repair,
+    /// so they pass its interceptors and are read in the order they arrived
     pub(crate) async fn receive_for_rtx(
         &self,
         ssrc: SSRC,
         rsid: String,
         repair_stream: TrackStream,
     ) -> Result<()> {
+        let associated_payload_types = rtx_associated_payload_types(&self.get_parameters().await);
+
         let mut tracks = self.internal.tracks.write().await;
         let l = tracks.len();
         for t in &mut *tracks {
@@ -782,14 +878,52 @@ impl RTCRtpReceiver {
 
                 let receive_mtu = self.receive_mtu;
                 let track = t.clone();
+                let internal = Arc::clone(&self.internal);
                 tokio::spawn(async move {
                     let a = Attributes::new();
                     let mut b = vec![0u8; receive_mtu];
                     while let Some(repair_rtp_interceptor) = &track.repair_stream.rtp_interceptor {
                         //TODO: cancel repair_rtp_interceptor.read gracefully
                         //println!("repair_rtp_interceptor read begin with ssrc={}", ssrc);
-                        if repair_rtp_interceptor.read(&mut b, &a).await.is_err() {
-                            break;
+                        let (n, _) = match repair_rtp_interceptor.read(&mut b, &a).await {
+                            Ok(r) => r,
+                            Err(_) => break,
+                        };
+
+                        let mut buf = &b[..n];
+                        let pkt = match rtp::packet::Packet::unmarshal(&mut buf) {
+                            Ok(pkt) => pkt,
+                            Err(err) => {
+                                log::debug!("failed to unmarshal rtx packet: {}", err);
+                                continue;
+                            }
+                        };
+                        let payload_type =
+                            match associated_payload_types.get(&pkt.header.payload_type) {
+                                Some(payload_type) => *payload_type,
+                                None => continue,
+                            };
+                        let pkt = match unwrap_rtx(pkt, track.track.ssrc(), payload_type) {
+                            Some(pkt) => pkt,
+                            None => continue,
+                        };
+                        let data = match pkt.marshal() {
+                            Ok(data) => data,
+                            Err(_) => continue,
+                        };
+
+                        // The stream of a simulcast track is only known once it is received
+                        let rtp_read_stream = {
+                            let tracks = internal.tracks.read().await;
+                            tracks
+                                .iter()
+                                .find(|t| t.track.tid() == track.track.tid())
+                                .and_then(|t| t.stream.rtp_read_stream.clone())
+                        };
+                        if let Some(rtp_read_stream) = rtp_read_stream {
+                            if let Err(err) = rtp_read_stream.write(&data).await {
+                                log::debug!("failed to queue unwrapped rtx packet: {}", err);
+                            }
                         }
                     }
                 });
@@ -801,6 +935,34 @@ impl RTCRtpReceiver {
         Err(Error::ErrRTPReceiverForRIDTrackStreamNotFound)
     }
 
+    /// receive_for_fec starts a routine that processes the FlexFEC stream protecting
+    /// the track with the given ssrc. The repair packets are consumed by the
+    /// FlexFEC decoder interceptor, which recovers lost packets of the track.
+    pub(crate) async fn receive_for_fec(&self, ssrc: SSRC, fec_stream: TrackStream) -> Result<()> {
+        let mut tracks = self.internal.tracks.write().await;
+        for t in &mut *tracks {
+            if t.track.ssrc() == ssrc {
+                t.fec_stream = fec_stream;
+
+                let receive_mtu = self.receive_mtu;
+                let track = t.clone();
+                tokio::spawn(async move {
+                    let a = Attributes::new();
+                    let mut b = vec![0u8; receive_mtu];
+                    while let Some(fec_rtp_interceptor) = &track.fec_stream.rtp_interceptor {
+                        if fec_rtp_interceptor.read(&mut b, &a).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                return Ok(());
+            }
+        }
+
+        Err(Error::ErrRTPReceiverForSSRCTrackStreamNotFound)
+    }
+
     // State
 
     pub(crate) fn current_state(&self) -> State {
@@ -843,3 +1005,43 @@ impl RTCRtpReceiver {
         Ok(())
     }
 }
+
+/// rtx_associated_payload_types maps the payload types of the negotiated RTX codecs
+/// to the payload types of the codecs they retransmit
+fn rtx_associated_payload_types(params: &RTCRtpParameters) -> HashMap<PayloadType, PayloadType> {
+    let mut payload_types = HashMap::new();
+    for codec in &params.codecs {
+        if !codec
+            .capability
+            .mime_type
+            .eq_ignore_ascii_case(MIME_TYPE_RTX)
+        {
+            continue;
+        }
+        let fmtp = fmtp::parse(&codec.capability.mime_type, &codec.capability.sdp_fmtp_line);
+        if let Some(apt) = fmtp.parameter("apt").and_then(|apt| apt.parse().ok()) {
+            payload_types.insert(codec.payload_type, apt);
+        }
+    }
+    payload_types
+}
+
+/// unwrap_rtx restores the original packet from a RFC 4588 retransmission by moving the
+/// original sequence number (OSN) from the payload back into the header. Padding only
+/// packets, as sent for bandwidth probing, carry no OSN and return None.
+fn unwrap_rtx(
+    mut pkt: rtp::packet::Packet,
+    ssrc: SSRC,
+    payload_type: PayloadType,
+) -> Option<rtp::packet::Packet> {
+    if pkt.payload.len() < 2 {
+        return None;
+    }
+
+    pkt.header.sequence_number = u16::from_be_bytes([pkt.payload[0], pkt.payload[1]]);
+    pkt.header.ssrc = ssrc;
+    pkt.header.payload_type = payload_type;
+    pkt.header.padding = false;
+    pkt.payload = pkt.payload.slice(2..);
+    Some(pkt)
+}
@@ -248,3 +248,71 @@ async fn test_rtp_receiver_set_read_deadline() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_unwrap_rtx() {
+    let rtx = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 2,
+            payload_type: 97,
+            sequence_number: 7,
+            timestamp: 3000,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x12, 0x34, 1, 2, 3]),
+    };
+
+    let pkt = unwrap_rtx(rtx, 1, 96).expect("the original packet");
+    assert_eq!(pkt.header.ssrc, 1);
+    assert_eq!(pkt.header.payload_type, 96);
+    assert_eq!(pkt.header.sequence_number, 0x1234);
+    assert_eq!(pkt.header.timestamp, 3000);
+    assert_eq!(pkt.payload, Bytes::from_static(&[1, 2, 3]));
+
+    let probe = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc: 2,
+            payload_type: 97,
+            ..Default::default()
+        },
+        payload: Bytes::new(),
+    };
+    assert!(
+        unwrap_rtx(probe, 1, 96).is_none(),
+        "padding only packets carry no OSN"
+    );
+}
+
+#[test]
+fn test_rtx_associated_payload_types() {
+    let params = RTCRtpParameters {
+        codecs: vec![
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
+                    clock_rate: 90000,
+                    ..Default::default()
+                },
+                payload_type: 96,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_RTX.to_owned(),
+                    clock_rate: 90000,
+                    sdp_fmtp_line: "apt=96".to_owned(),
+                    ..Default::default()
+                },
+                payload_type: 97,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let payload_types = rtx_associated_payload_types(&params);
+    assert_eq!(payload_types.len(), 1);
+    assert_eq!(payload_types.get(&97), Some(&96));
+}
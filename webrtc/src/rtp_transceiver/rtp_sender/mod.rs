@@ -1,10 +1,10 @@
 #[cfg(test)]
 mod rtp_sender_test;
 
-use crate::api::media_engine::MediaEngine;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_FLEXFEC03, MIME_TYPE_RED, MIME_TYPE_RTX};
 use crate::dtls_transport::RTCDtlsTransport;
 use crate::error::{Error, Result};
-use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTPCodecType};
+use crate::rtp_transceiver::rtp_codec::{codec_rtx_search, RTCRtpCodecParameters, RTPCodecType};
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::srtp_writer_future::SrtpWriterFuture;
 use crate::rtp_transceiver::{
@@ -88,6 +88,10 @@ pub struct RTCRtpSender {
 
     pub(crate) payload_type: PayloadType,
     pub(crate) ssrc: SSRC,
+    /// the ssrc FlexFEC repair packets are sent on, if FlexFEC is negotiated
+    pub(crate) fec_ssrc: SSRC,
+    /// the ssrc retransmissions are sent on, if RTX is negotiated
+    pub(crate) rtx_ssrc: SSRC,
     receive_mtu: usize,
 
     /// a transceiver sender since we can just check the
@@ -178,6 +182,8 @@ impl RTCRtpSender {
 
             payload_type: 0,
             ssrc,
+            fec_ssrc: rand::random::<u32>(),
+            rtx_ssrc: rand::random::<u32>(),
             receive_mtu,
 
             negotiated: AtomicBool::new(false),
@@ -268,6 +274,19 @@ impl RTCRtpSender {
                 self.media_engine.get_codecs_by_kind(kind).await
             }
         };
+        if codecs.iter().any(|c| {
+            c.capability
+                .mime_type
+                .eq_ignore_ascii_case(MIME_TYPE_FLEXFEC03)
+        }) {
+            send_parameters.encodings[0].fec.ssrc = self.fec_ssrc;
+        }
+        if codecs
+            .iter()
+            .any(|c| c.capability.mime_type.eq_ignore_ascii_case(MIME_TYPE_RTX))
+        {
+            send_parameters.encodings[0].rtx.ssrc = self.rtx_ssrc;
+        }
         send_parameters.rtp_parameters.codecs = codecs;
 
         send_parameters
@@ -403,14 +422,44 @@ impl RTCRtpSender {
             };
             let payload_type = codec.payload_type;
             let capability = codec.capability.clone();
+            let fec_codec = context
+                .params
+                .codecs
+                .iter()
+                .find(|c| {
+                    c.capability
+                        .mime_type
+                        .eq_ignore_ascii_case(MIME_TYPE_FLEXFEC03)
+                })
+                .cloned();
+            let red_codec = context
+                .params
+                .codecs
+                .iter()
+                .find(|c| c.capability.mime_type.eq_ignore_ascii_case(MIME_TYPE_RED))
+                .cloned();
+            let rtx_codec = codec_rtx_search(payload_type, &context.params.codecs);
             context.params.codecs = vec![codec];
-            let stream_info = create_stream_info(
+            let mut stream_info = create_stream_info(
                 self.id.clone(),
                 parameters.encodings[0].ssrc,
                 payload_type,
                 capability,
                 &parameters.rtp_parameters.header_extensions,
             );
+            if let (Some(fec_codec), true) = (fec_codec, parameters.encodings[0].fec.ssrc != 0) {
+                stream_info.ssrc_forward_error_correction = parameters.encodings[0].fec.ssrc;
+                stream_info.payload_type_forward_error_correction = fec_codec.payload_type;
+            }
+            if let Some(red_codec) = red_codec {
+                if red_codec.payload_type != payload_type {
+                    stream_info.payload_type_redundancy = red_codec.payload_type;
+                }
+            }
+            if let (Some(rtx_codec), true) = (rtx_codec, parameters.encodings[0].rtx.ssrc != 0) {
+                stream_info.ssrc_retransmission = parameters.encodings[0].rtx.ssrc;
+                stream_info.payload_type_retransmission = rtx_codec.payload_type;
+            }
 
             (context, stream_info)
         };
@@ -26,4 +26,5 @@ pub(crate) struct TrackStreams {
     pub(crate) track: Arc<TrackRemote>,
     pub(crate) stream: TrackStream,
     pub(crate) repair_stream: TrackStream,
+    pub(crate) fec_stream: TrackStream,
 }